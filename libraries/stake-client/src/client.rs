@@ -0,0 +1,183 @@
+//! Async client for the stake program, generic over `ProgramClient`
+
+use {
+    crate::{
+        error::{StakeClientError, StakeClientResult},
+        instruction,
+        state::{self, StakeActivation, STAKE_ACCOUNT_LEN},
+    },
+    solana_sdk::{
+        clock::{Clock, Epoch, Slot},
+        epoch_schedule::EpochSchedule,
+        feature::{self, Feature},
+        feature_set::{reduce_stake_warmup_cooldown, stake_raise_minimum_delegation_to_1_sol},
+        instruction::Instruction,
+        pubkey::Pubkey,
+        rent::Rent,
+        stake::state::{Meta, Stake, StakeStateV2},
+        stake_history::StakeHistory,
+        sysvar::{self, Sysvar},
+    },
+    spl_token_client::client::{ProgramClient, SendTransaction, SimulateTransaction},
+    std::{fmt, sync::Arc},
+};
+
+/// Client for reading stake program state and building stake instructions
+pub struct StakeClient<T> {
+    client: Arc<dyn ProgramClient<T>>,
+}
+
+impl<T> fmt::Debug for StakeClient<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StakeClient").finish()
+    }
+}
+
+impl<T> Clone for StakeClient<T> {
+    fn clone(&self) -> Self {
+        Self {
+            client: Arc::clone(&self.client),
+        }
+    }
+}
+
+impl<T> StakeClient<T>
+where
+    T: SendTransaction + SimulateTransaction,
+{
+    /// Create a new stake client
+    pub fn new(client: Arc<dyn ProgramClient<T>>) -> Self {
+        Self { client }
+    }
+
+    async fn get_sysvar<S: Sysvar>(&self, address: Pubkey) -> StakeClientResult<S> {
+        let account = self
+            .client
+            .get_account(address)
+            .await
+            .map_err(StakeClientError::Client)?
+            .ok_or(StakeClientError::AccountNotFound)?;
+
+        Ok(bincode::deserialize::<S>(&account.data)?)
+    }
+
+    /// Get the `Rent` sysvar
+    pub async fn get_rent(&self) -> StakeClientResult<Rent> {
+        self.get_sysvar(sysvar::rent::id()).await
+    }
+
+    /// Get the `Clock` sysvar
+    pub async fn get_clock(&self) -> StakeClientResult<Clock> {
+        self.get_sysvar(sysvar::clock::id()).await
+    }
+
+    /// Get the `EpochSchedule` sysvar
+    pub async fn get_epoch_schedule(&self) -> StakeClientResult<EpochSchedule> {
+        self.get_sysvar(sysvar::epoch_schedule::id()).await
+    }
+
+    /// Get the `StakeHistory` sysvar
+    pub async fn get_stake_history(&self) -> StakeClientResult<StakeHistory> {
+        self.get_sysvar(sysvar::stake_history::id()).await
+    }
+
+    /// Get the lamports required for a stake account to be rent-exempt
+    pub async fn get_stake_rent(&self) -> StakeClientResult<u64> {
+        self.client
+            .get_minimum_balance_for_rent_exemption(STAKE_ACCOUNT_LEN)
+            .await
+            .map_err(StakeClientError::Client)
+    }
+
+    async fn get_feature_activation_slot(
+        &self,
+        feature_id: &Pubkey,
+    ) -> StakeClientResult<Option<Slot>> {
+        let maybe_feature = self
+            .client
+            .get_account(*feature_id)
+            .await
+            .map_err(StakeClientError::Client)?
+            .filter(|account| account.owner == feature::id())
+            .and_then(|account| feature::from_account(&account));
+
+        Ok(maybe_feature.and_then(|Feature { activated_at }| activated_at))
+    }
+
+    /// Get the minimum delegation currently enforced by the stake program
+    pub async fn get_minimum_delegation(&self) -> StakeClientResult<u64> {
+        let raise_minimum_delegation_active = self
+            .get_feature_activation_slot(&stake_raise_minimum_delegation_to_1_sol::id())
+            .await?
+            .is_some();
+
+        Ok(state::minimum_delegation(raise_minimum_delegation_active))
+    }
+
+    /// Get the epoch in which the reduced stake warmup and cooldown rate took
+    /// effect, if it has
+    pub async fn get_new_rate_activation_epoch(&self) -> StakeClientResult<Option<Epoch>> {
+        if let Some(slot) = self
+            .get_feature_activation_slot(&reduce_stake_warmup_cooldown::id())
+            .await?
+        {
+            Ok(Some(self.get_epoch_schedule().await?.get_epoch(slot)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Get the state of a stake account, or `None` if it does not exist
+    pub async fn get_stake_state(
+        &self,
+        stake_address: &Pubkey,
+    ) -> StakeClientResult<Option<StakeStateV2>> {
+        self.client
+            .get_account(*stake_address)
+            .await
+            .map_err(StakeClientError::Client)?
+            .map(|account| state::unpack_stake_account(&account))
+            .transpose()
+    }
+
+    /// Get the `Meta` and `Stake` of a delegated stake account, or `None` if it
+    /// does not exist. Returns an error if the account exists but is not
+    /// delegated
+    pub async fn get_delegated_stake(
+        &self,
+        stake_address: &Pubkey,
+    ) -> StakeClientResult<Option<(Meta, Stake)>> {
+        self.get_stake_state(stake_address)
+            .await?
+            .map(|stake_state| state::delegated_stake(&stake_state))
+            .transpose()
+    }
+
+    /// Compute the activation of a delegation in the current epoch
+    pub async fn get_stake_activation(&self, stake: &Stake) -> StakeClientResult<StakeActivation> {
+        let clock = self.get_clock().await?;
+        let stake_history = self.get_stake_history().await?;
+        let new_rate_activation_epoch = self.get_new_rate_activation_epoch().await?;
+
+        Ok(state::get_stake_activation(
+            &stake.delegation,
+            clock.epoch,
+            &stake_history,
+            new_rate_activation_epoch,
+        ))
+    }
+
+    /// Create a `CreateAccount` instruction for a rent-exempt, uninitialized
+    /// stake account
+    pub async fn create_uninitialized_stake_account_instruction(
+        &self,
+        payer: &Pubkey,
+        stake_address: &Pubkey,
+    ) -> StakeClientResult<Instruction> {
+        Ok(instruction::create_uninitialized_stake_account(
+            payer,
+            stake_address,
+            self.get_stake_rent().await?,
+        ))
+    }
+}
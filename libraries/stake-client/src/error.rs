@@ -0,0 +1,29 @@
+//! Error types
+
+use {spl_token_client::client::ProgramClientError, thiserror::Error};
+
+/// Errors that may be returned by the stake client
+#[derive(Error, Debug)]
+pub enum StakeClientError {
+    /// The underlying program client failed
+    #[error("client error: {0}")]
+    Client(ProgramClientError),
+    /// A required account does not exist
+    #[error("account not found")]
+    AccountNotFound,
+    /// The account is not owned by the expected program
+    #[error("invalid account owner")]
+    AccountInvalidOwner,
+    /// The account data could not be deserialized
+    #[error("invalid account data: {0}")]
+    AccountInvalidData(#[from] bincode::Error),
+    /// The stake account has not been initialized
+    #[error("stake account is uninitialized")]
+    StakeUninitialized,
+    /// The stake account is initialized but not delegated
+    #[error("stake account is undelegated")]
+    StakeUndelegated,
+}
+
+/// Result type returned by the stake client
+pub type StakeClientResult<T> = Result<T, StakeClientError>;
@@ -0,0 +1,63 @@
+//! Instruction builders for creating stake accounts
+
+use {
+    crate::state::STAKE_ACCOUNT_LEN,
+    solana_sdk::{
+        instruction::Instruction,
+        pubkey::Pubkey,
+        stake::{
+            self,
+            state::{Authorized, Lockup},
+        },
+        system_instruction,
+    },
+};
+
+/// Creates a `CreateAccount` instruction for a stake account that is left
+/// uninitialized, eg to be initialized by another program. `lamports` must
+/// cover at least rent-exemption
+pub fn create_uninitialized_stake_account(
+    payer: &Pubkey,
+    stake_address: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    system_instruction::create_account(
+        payer,
+        stake_address,
+        lamports,
+        STAKE_ACCOUNT_LEN as u64,
+        &stake::program::id(),
+    )
+}
+
+/// Creates instructions to create and initialize a stake account with the
+/// given authorities and no lockup
+pub fn create_stake_account(
+    payer: &Pubkey,
+    stake_address: &Pubkey,
+    authorized: &Authorized,
+    lamports: u64,
+) -> Vec<Instruction> {
+    vec![
+        create_uninitialized_stake_account(payer, stake_address, lamports),
+        stake::instruction::initialize(stake_address, authorized, &Lockup::default()),
+    ]
+}
+
+/// Creates instructions to create, initialize, and delegate a stake account
+pub fn create_and_delegate_stake_account(
+    payer: &Pubkey,
+    stake_address: &Pubkey,
+    vote_account_address: &Pubkey,
+    authorized: &Authorized,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let mut instructions = create_stake_account(payer, stake_address, authorized, lamports);
+    instructions.push(stake::instruction::delegate_stake(
+        stake_address,
+        &authorized.staker,
+        vote_account_address,
+    ));
+
+    instructions
+}
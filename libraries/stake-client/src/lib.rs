@@ -0,0 +1,9 @@
+//! Client for fetching, parsing, and creating native stake program accounts
+
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+pub mod client;
+pub mod error;
+pub mod instruction;
+pub mod state;
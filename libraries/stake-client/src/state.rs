@@ -0,0 +1,112 @@
+//! Stake account parsing and activation helpers that do not require a client
+
+use {
+    crate::error::{StakeClientError, StakeClientResult},
+    solana_sdk::{
+        account::Account,
+        clock::Epoch,
+        native_token::LAMPORTS_PER_SOL,
+        stake::{
+            self,
+            state::{Delegation, Meta, Stake, StakeActivationStatus, StakeStateV2},
+        },
+        stake_history::StakeHistory,
+    },
+};
+
+/// Size of a stake account's data
+pub const STAKE_ACCOUNT_LEN: usize = std::mem::size_of::<StakeStateV2>();
+
+/// Byte offset of `Meta::authorized::staker` in stake account data, for use in
+/// RPC account filters
+pub const AUTHORIZED_STAKER_OFFSET: usize = 12;
+
+/// Byte offset of `Meta::authorized::withdrawer` in stake account data, for use
+/// in RPC account filters
+pub const AUTHORIZED_WITHDRAWER_OFFSET: usize = 44;
+
+/// Deserialize raw stake account data
+pub fn deserialize_stake_state(data: &[u8]) -> StakeClientResult<StakeStateV2> {
+    Ok(bincode::deserialize::<StakeStateV2>(data)?)
+}
+
+/// Deserialize a stake account, checking that it is owned by the stake program
+pub fn unpack_stake_account(account: &Account) -> StakeClientResult<StakeStateV2> {
+    if account.owner != stake::program::id() {
+        return Err(StakeClientError::AccountInvalidOwner);
+    }
+
+    deserialize_stake_state(&account.data)
+}
+
+/// Get the `Meta` and `Stake` of a delegated stake account, or an error
+/// describing why the account is not delegated
+pub fn delegated_stake(stake_state: &StakeStateV2) -> StakeClientResult<(Meta, Stake)> {
+    match stake_state {
+        StakeStateV2::Stake(meta, stake, _) => Ok((*meta, *stake)),
+        StakeStateV2::Initialized(_) => Err(StakeClientError::StakeUndelegated),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => {
+            Err(StakeClientError::StakeUninitialized)
+        }
+    }
+}
+
+/// Minimum delegation enforced by the stake program, depending on whether the
+/// `stake_raise_minimum_delegation_to_1_sol` feature is active
+pub fn minimum_delegation(raise_minimum_delegation_active: bool) -> u64 {
+    if raise_minimum_delegation_active {
+        LAMPORTS_PER_SOL
+    } else {
+        1
+    }
+}
+
+/// Coarse activation state of a delegation in a given epoch
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StakeActivationState {
+    /// No stake is effective, activating, or deactivating
+    Inactive,
+    /// Some stake is still warming up
+    Activating,
+    /// All stake is effective
+    Active,
+    /// Some stake is cooling down
+    Deactivating,
+}
+
+/// Activation of a delegation in a given epoch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StakeActivation {
+    /// Coarse activation state
+    pub state: StakeActivationState,
+    /// Effective, activating, and deactivating lamports
+    pub status: StakeActivationStatus,
+}
+
+/// Compute the activation of a delegation in the given epoch.
+/// `new_rate_activation_epoch` is the epoch in which the reduced warmup and
+/// cooldown rate took effect, if it has
+pub fn get_stake_activation(
+    delegation: &Delegation,
+    epoch: Epoch,
+    stake_history: &StakeHistory,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> StakeActivation {
+    let status = delegation.stake_activating_and_deactivating(
+        epoch,
+        stake_history,
+        new_rate_activation_epoch,
+    );
+
+    let state = if status.deactivating > 0 {
+        StakeActivationState::Deactivating
+    } else if status.activating > 0 {
+        StakeActivationState::Activating
+    } else if status.effective > 0 {
+        StakeActivationState::Active
+    } else {
+        StakeActivationState::Inactive
+    };
+
+    StakeActivation { state, status }
+}
@@ -0,0 +1,256 @@
+use {
+    solana_program_test::{
+        tokio::{self, sync::Mutex},
+        ProgramTest, ProgramTestContext,
+    },
+    solana_sdk::{
+        native_token::LAMPORTS_PER_SOL,
+        signature::{Keypair, Signer},
+        stake::state::{Authorized, StakeStateV2},
+        system_instruction,
+        transaction::Transaction,
+    },
+    solana_vote_program::{
+        vote_instruction::{self, CreateVoteAccountConfig},
+        vote_state::{VoteInit, VoteState},
+    },
+    spl_stake_client::{
+        client::StakeClient,
+        error::StakeClientError,
+        instruction,
+        state::{StakeActivationState, STAKE_ACCOUNT_LEN},
+    },
+    spl_token_client::client::{
+        ProgramBanksClient, ProgramBanksClientProcessTransaction, ProgramClient,
+    },
+    std::sync::Arc,
+};
+
+struct TestContext {
+    pub context: Arc<Mutex<ProgramTestContext>>,
+    pub client: Arc<dyn ProgramClient<ProgramBanksClientProcessTransaction>>,
+    pub stake_client: StakeClient<ProgramBanksClientProcessTransaction>,
+    pub payer: Keypair,
+    pub vote_account: Keypair,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        let program_test = ProgramTest::default();
+        let context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let context = Arc::new(Mutex::new(context));
+
+        let client: Arc<dyn ProgramClient<ProgramBanksClientProcessTransaction>> =
+            Arc::new(ProgramBanksClient::new_from_context(
+                Arc::clone(&context),
+                ProgramBanksClientProcessTransaction,
+            ));
+        let stake_client = StakeClient::new(Arc::clone(&client));
+
+        let test_context = Self {
+            context,
+            client,
+            stake_client,
+            payer,
+            vote_account: Keypair::new(),
+        };
+        test_context.create_vote().await;
+
+        test_context
+    }
+
+    async fn process(
+        &self,
+        instructions: &[solana_sdk::instruction::Instruction],
+        signers: &[&Keypair],
+    ) {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.client.get_latest_blockhash().await.unwrap(),
+        );
+
+        self.client.send_transaction(&transaction).await.unwrap();
+    }
+
+    async fn create_vote(&self) {
+        let validator = Keypair::new();
+        let rent = self.stake_client.get_rent().await.unwrap();
+
+        let mut instructions = vec![system_instruction::create_account(
+            &self.payer.pubkey(),
+            &validator.pubkey(),
+            rent.minimum_balance(0),
+            0,
+            &solana_sdk::system_program::id(),
+        )];
+        instructions.append(&mut vote_instruction::create_account_with_config(
+            &self.payer.pubkey(),
+            &self.vote_account.pubkey(),
+            &VoteInit {
+                node_pubkey: validator.pubkey(),
+                authorized_voter: validator.pubkey(),
+                authorized_withdrawer: validator.pubkey(),
+                ..VoteInit::default()
+            },
+            rent.minimum_balance(VoteState::size_of()),
+            CreateVoteAccountConfig {
+                space: VoteState::size_of() as u64,
+                ..Default::default()
+            },
+        ));
+
+        self.process(&instructions, &[&validator, &self.vote_account])
+            .await;
+    }
+
+    async fn advance_epoch(&self) {
+        let mut context = self.context.lock().await;
+        let root_slot = context.banks_client.get_root_slot().await.unwrap();
+        let slots_per_epoch = context.genesis_config().epoch_schedule.slots_per_epoch;
+        context.warp_to_slot(root_slot + slots_per_epoch).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn get_sysvars() {
+    let test_context = TestContext::new().await;
+    let stake_client = &test_context.stake_client;
+
+    let rent = stake_client.get_rent().await.unwrap();
+    assert_eq!(
+        stake_client.get_stake_rent().await.unwrap(),
+        rent.minimum_balance(STAKE_ACCOUNT_LEN)
+    );
+
+    let clock = stake_client.get_clock().await.unwrap();
+    test_context.advance_epoch().await;
+    let next_clock = stake_client.get_clock().await.unwrap();
+    assert!(next_clock.epoch > clock.epoch);
+
+    stake_client.get_stake_history().await.unwrap();
+
+    let minimum_delegation = stake_client.get_minimum_delegation().await.unwrap();
+    assert!(minimum_delegation == 1 || minimum_delegation == LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn get_stake_state() {
+    let test_context = TestContext::new().await;
+    let stake_client = &test_context.stake_client;
+    let stake_rent = stake_client.get_stake_rent().await.unwrap();
+
+    // missing account
+    let stake_account = Keypair::new();
+    assert!(stake_client
+        .get_stake_state(&stake_account.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+
+    // uninitialized account
+    let instruction = stake_client
+        .create_uninitialized_stake_account_instruction(
+            &test_context.payer.pubkey(),
+            &stake_account.pubkey(),
+        )
+        .await
+        .unwrap();
+    test_context
+        .process(&[instruction], &[&stake_account])
+        .await;
+
+    assert_eq!(
+        stake_client
+            .get_stake_state(&stake_account.pubkey())
+            .await
+            .unwrap(),
+        Some(StakeStateV2::Uninitialized)
+    );
+    assert!(matches!(
+        stake_client
+            .get_delegated_stake(&stake_account.pubkey())
+            .await
+            .unwrap_err(),
+        StakeClientError::StakeUninitialized
+    ));
+
+    // initialized account
+    let stake_account = Keypair::new();
+    let authorized = Authorized::auto(&test_context.payer.pubkey());
+    let instructions = instruction::create_stake_account(
+        &test_context.payer.pubkey(),
+        &stake_account.pubkey(),
+        &authorized,
+        stake_rent,
+    );
+    test_context.process(&instructions, &[&stake_account]).await;
+
+    let stake_state = stake_client
+        .get_stake_state(&stake_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stake_state.meta().unwrap().authorized, authorized);
+    assert!(matches!(
+        stake_client
+            .get_delegated_stake(&stake_account.pubkey())
+            .await
+            .unwrap_err(),
+        StakeClientError::StakeUndelegated
+    ));
+
+    // not a stake account
+    assert!(matches!(
+        stake_client
+            .get_stake_state(&test_context.payer.pubkey())
+            .await
+            .unwrap_err(),
+        StakeClientError::AccountInvalidOwner
+    ));
+}
+
+#[tokio::test]
+async fn get_stake_activation() {
+    let test_context = TestContext::new().await;
+    let stake_client = &test_context.stake_client;
+    let stake_rent = stake_client.get_stake_rent().await.unwrap();
+    let minimum_delegation = stake_client.get_minimum_delegation().await.unwrap();
+    let stake_amount = minimum_delegation.max(LAMPORTS_PER_SOL);
+
+    let stake_account = Keypair::new();
+    let instructions = instruction::create_and_delegate_stake_account(
+        &test_context.payer.pubkey(),
+        &stake_account.pubkey(),
+        &test_context.vote_account.pubkey(),
+        &Authorized::auto(&test_context.payer.pubkey()),
+        stake_rent + stake_amount,
+    );
+    test_context.process(&instructions, &[&stake_account]).await;
+
+    let (_, stake) = stake_client
+        .get_delegated_stake(&stake_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stake.delegation.stake, stake_amount);
+    assert_eq!(
+        stake.delegation.voter_pubkey,
+        test_context.vote_account.pubkey()
+    );
+
+    let activation = stake_client.get_stake_activation(&stake).await.unwrap();
+    assert_eq!(activation.state, StakeActivationState::Activating);
+    assert_eq!(activation.status.activating, stake_amount);
+
+    test_context.advance_epoch().await;
+
+    let activation = stake_client.get_stake_activation(&stake).await.unwrap();
+    assert_eq!(activation.state, StakeActivationState::Active);
+    assert_eq!(activation.status.effective, stake_amount);
+}
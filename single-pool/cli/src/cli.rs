@@ -87,6 +87,11 @@ pub enum Command {
     /// tokens to burn or the ALL keyword to burn all.
     Withdraw(WithdrawCli),
 
+    /// Deposit liquid SOL into a pool in exchange for pool tokens. The SOL is
+    /// held in the pool's onramp stake account, delegated by a permissionless
+    /// replenish crank, and merged into the pool stake once active.
+    DepositSol(DepositSolCli),
+
     /// Create and delegate a new stake account to a given validator, using a
     /// default address linked to the intended depository pool
     CreateDefaultStake(CreateStakeCli),
@@ -124,6 +129,16 @@ pub enum ManageCommand {
     /// can only be performed by the validator vote account's withdraw
     /// authority
     UpdateTokenMetadata(UpdateMetadataCli),
+
+    /// Permissionlessly crank the pool onramp account, delegating any SOL
+    /// deposited into it and merging it into the pool stake account once
+    /// it has activated. Anyone may call this once per epoch.
+    ReplenishPool(ReplenishCli),
+
+    /// Permissionlessly create the onramp stake account for a pool. Pools
+    /// created before SOL deposits were supported do not have one, and
+    /// cannot accept SOL deposits until it is created.
+    CreateOnRamp(CreateOnRampCli),
 }
 
 #[derive(Clone, Debug, Args)]
@@ -132,9 +147,14 @@ pub struct InitializeCli {
     #[clap(value_parser = |p: &str| parse_address(p, "vote_account_address"))]
     pub vote_account_address: Pubkey,
 
-    /// Do not create MPL metadata for the pool mint
+    /// Do not create token metadata for the pool mint
     #[clap(long)]
     pub skip_metadata: bool,
+
+    /// Create the pool mint with Token-2022, storing token metadata in the
+    /// mint itself rather than with MPL
+    #[clap(long)]
+    pub token_2022: bool,
 }
 
 #[derive(Clone, Debug, Args)]
@@ -153,6 +173,31 @@ pub struct ReactivateCli {
     pub skip_deactivation_check: bool,
 }
 
+#[derive(Clone, Debug, Args)]
+#[clap(group(pool_source_group()))]
+pub struct ReplenishCli {
+    /// The pool to replenish
+    #[clap(short, long = "pool", value_parser = |p: &str| parse_address(p, "pool_address"))]
+    pub pool_address: Option<Pubkey>,
+
+    /// The vote account corresponding to the pool to replenish
+    #[clap(long = "vote-account", value_parser = |p: &str| parse_address(p, "vote_account_address"))]
+    pub vote_account_address: Option<Pubkey>,
+}
+
+#[derive(Clone, Debug, Args)]
+#[clap(group(pool_source_group()))]
+pub struct CreateOnRampCli {
+    /// The pool to create the onramp account for
+    #[clap(short, long = "pool", value_parser = |p: &str| parse_address(p, "pool_address"))]
+    pub pool_address: Option<Pubkey>,
+
+    /// The vote account corresponding to the pool to create the onramp
+    /// account for
+    #[clap(long = "vote-account", value_parser = |p: &str| parse_address(p, "vote_account_address"))]
+    pub vote_account_address: Option<Pubkey>,
+}
+
 #[derive(Clone, Debug, Args)]
 #[clap(group(ArgGroup::new("stake-source").required(true).args(&["stake-account-address", "default-stake-account"])))]
 #[clap(group(pool_source_group().required(false)))]
@@ -197,6 +242,26 @@ pub struct DepositCli {
     pub lamport_recipient_address: Option<Pubkey>,
 }
 
+#[derive(Clone, Debug, Args)]
+#[clap(group(pool_source_group()))]
+pub struct DepositSolCli {
+    /// Number of lamports to deposit
+    pub lamports: u64,
+
+    /// The pool to deposit into
+    #[clap(short, long = "pool", value_parser = |p: &str| parse_address(p, "pool_address"))]
+    pub pool_address: Option<Pubkey>,
+
+    /// The vote account corresponding to the pool to deposit into
+    #[clap(long = "vote-account", value_parser = |p: &str| parse_address(p, "vote_account_address"))]
+    pub vote_account_address: Option<Pubkey>,
+
+    /// The token account to mint to. Defaults to the client keypair's
+    /// associated token account
+    #[clap(long = "token-account", value_parser = |p: &str| parse_address(p, "token_account_address"))]
+    pub token_account_address: Option<Pubkey>,
+}
+
 #[derive(Clone, Debug, Args)]
 #[clap(group(pool_source_group()))]
 pub struct WithdrawCli {
@@ -6,6 +6,7 @@ use {
     solana_client::nonblocking::rpc_client::RpcClient,
     solana_remote_wallet::remote_wallet::RemoteWalletManager,
     solana_sdk::{commitment_config::CommitmentConfig, signature::Signer},
+    spl_stake_client::client::StakeClient,
     spl_token_client::client::{ProgramClient, ProgramRpcClient, ProgramRpcClientSendTransaction},
     std::{process::exit, rc::Rc, sync::Arc},
 };
@@ -33,6 +34,7 @@ pub fn eprintln_display(config: &Config, message: String) {
 pub struct Config {
     pub rpc_client: Arc<RpcClient>,
     pub program_client: Arc<dyn ProgramClient<ProgramRpcClientSendTransaction>>,
+    pub stake_client: StakeClient<ProgramRpcClientSendTransaction>,
     pub default_signer: Option<Arc<dyn Signer>>,
     pub fee_payer: Option<Arc<dyn Signer>>,
     pub output_format: OutputFormat,
@@ -63,10 +65,12 @@ impl Config {
         ));
 
         // and program client
-        let program_client = Arc::new(ProgramRpcClient::new(
-            rpc_client.clone(),
-            ProgramRpcClientSendTransaction,
-        ));
+        let program_client: Arc<dyn ProgramClient<ProgramRpcClientSendTransaction>> = Arc::new(
+            ProgramRpcClient::new(rpc_client.clone(), ProgramRpcClientSendTransaction),
+        );
+
+        // and stake program client
+        let stake_client = StakeClient::new(program_client.clone());
 
         // resolve default signer
         let default_keypair = cli_config.keypair_path;
@@ -92,6 +96,7 @@ impl Config {
         Self {
             rpc_client,
             program_client,
+            stake_client,
             default_signer,
             fee_payer,
             output_format,
@@ -10,6 +10,7 @@ use {
     },
     solana_sdk::{
         borsh1::try_from_slice_unchecked,
+        native_token::LAMPORTS_PER_SOL,
         pubkey::Pubkey,
         signature::{Keypair, Signature, Signer},
         stake,
@@ -18,7 +19,8 @@ use {
     solana_vote_program::{self as vote_program, vote_state::VoteState},
     spl_single_pool::{
         self, find_default_deposit_account_address, find_pool_address, find_pool_mint_address,
-        find_pool_stake_address, instruction::SinglePoolInstruction, state::SinglePool,
+        find_pool_onramp_address, find_pool_stake_address, instruction::SinglePoolInstruction,
+        state::SinglePool,
     },
     spl_token_client::token::Token,
 };
@@ -32,8 +34,6 @@ use cli::*;
 mod output;
 use output::*;
 
-mod quarantine;
-
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     let cli = Cli::parse();
@@ -72,9 +72,18 @@ impl Command {
                 ManageCommand::UpdateTokenMetadata(command_config) => {
                     command_update_metadata(config, command_config).await
                 }
+                ManageCommand::ReplenishPool(command_config) => {
+                    command_replenish_pool(config, command_config).await
+                }
+                ManageCommand::CreateOnRamp(command_config) => {
+                    command_create_onramp(config, command_config).await
+                }
             },
             Command::Deposit(command_config) => command_deposit(config, command_config).await,
             Command::Withdraw(command_config) => command_withdraw(config, command_config).await,
+            Command::DepositSol(command_config) => {
+                command_deposit_sol(config, command_config).await
+            }
             Command::CreateDefaultStake(command_config) => {
                 command_create_stake(config, command_config).await
             }
@@ -121,12 +130,19 @@ async fn command_initialize(config: &Config, command_config: InitializeCli) -> C
         .into());
     }
 
-    let mut instructions = spl_single_pool::instruction::initialize(
+    let token_program_id = if command_config.token_2022 {
+        spl_token_2022::id()
+    } else {
+        spl_token::id()
+    };
+
+    let mut instructions = spl_single_pool::instruction::initialize_with_token_program(
         &spl_single_pool::id(),
         &vote_account_address,
         &payer.pubkey(),
-        &quarantine::get_rent(config).await?,
-        quarantine::get_minimum_delegation(config).await?,
+        &config.stake_client.get_rent().await?,
+        get_pool_minimum_delegation(config).await?,
+        &token_program_id,
     );
 
     // get rid of the CreateMetadata instruction if desired, eg if mpl breaks compat
@@ -189,7 +205,9 @@ async fn command_reactivate_pool_stake(
     if !command_config.skip_deactivation_check {
         let current_epoch = config.rpc_client.get_epoch_info().await?.epoch;
         let pool_stake_address = find_pool_stake_address(&spl_single_pool::id(), &pool_address);
-        let pool_stake_deactivated = quarantine::get_stake_info(config, &pool_stake_address)
+        let pool_stake_deactivated = config
+            .stake_client
+            .get_delegated_stake(&pool_stake_address)
             .await?
             .unwrap()
             .1
@@ -222,6 +240,99 @@ async fn command_reactivate_pool_stake(
     ))
 }
 
+// replenish pool
+async fn command_replenish_pool(config: &Config, command_config: ReplenishCli) -> CommandResult {
+    let payer = config.fee_payer()?;
+    let pool_address = pool_address_from_args(
+        command_config.pool_address,
+        command_config.vote_account_address,
+    );
+
+    println_display(config, format!("Replenishing pool {}\n", pool_address));
+
+    let vote_account_address =
+        if let Some(pool_data) = config.program_client.get_account(pool_address).await? {
+            try_from_slice_unchecked::<SinglePool>(&pool_data.data)?.vote_account_address
+        } else {
+            return Err(format!("Pool {} has not been initialized", pool_address).into());
+        };
+
+    let instruction =
+        spl_single_pool::instruction::replenish_pool(&spl_single_pool::id(), &vote_account_address);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &vec![payer],
+        config.program_client.get_latest_blockhash().await?,
+    );
+
+    let signature = process_transaction(config, transaction).await?;
+
+    Ok(format_output(
+        config,
+        "ReplenishPool".to_string(),
+        SignatureOutput { signature },
+    ))
+}
+
+// create pool onramp
+async fn command_create_onramp(config: &Config, command_config: CreateOnRampCli) -> CommandResult {
+    let payer = config.fee_payer()?;
+    let pool_address = pool_address_from_args(
+        command_config.pool_address,
+        command_config.vote_account_address,
+    );
+
+    println_display(
+        config,
+        format!("Creating onramp account for pool {}\n", pool_address),
+    );
+
+    if config
+        .program_client
+        .get_account(pool_address)
+        .await?
+        .is_none()
+    {
+        return Err(format!("Pool {} has not been initialized", pool_address).into());
+    }
+
+    let onramp_address = find_pool_onramp_address(&spl_single_pool::id(), &pool_address);
+    if config
+        .program_client
+        .get_account(onramp_address)
+        .await?
+        .is_some_and(|account| account.owner == stake::program::id())
+    {
+        return Err(format!(
+            "Onramp account {} for pool {} already exists",
+            onramp_address, pool_address
+        )
+        .into());
+    }
+
+    let instructions = spl_single_pool::instruction::create_pool_onramp(
+        &spl_single_pool::id(),
+        &pool_address,
+        &payer.pubkey(),
+        &config.stake_client.get_rent().await?,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &vec![payer],
+        config.program_client.get_latest_blockhash().await?,
+    );
+
+    let signature = process_transaction(config, transaction).await?;
+
+    Ok(format_output(
+        config,
+        "CreateOnRamp".to_string(),
+        SignatureOutput { signature },
+    ))
+}
+
 // deposit stake
 async fn command_deposit(config: &Config, command_config: DepositCli) -> CommandResult {
     let payer = config.fee_payer()?;
@@ -256,8 +367,10 @@ async fn command_deposit(config: &Config, command_config: DepositCli) -> Command
         };
 
     // now we validate the stake account and definitively resolve the pool address
-    let (pool_address, user_stake_active) = if let Some((meta, stake)) =
-        quarantine::get_stake_info(config, &stake_account_address).await?
+    let (pool_address, user_stake_active) = if let Some((meta, stake)) = config
+        .stake_client
+        .get_delegated_stake(&stake_account_address)
+        .await?
     {
         let derived_pool_address =
             find_pool_address(&spl_single_pool::id(), &stake.delegation.voter_pubkey);
@@ -316,7 +429,9 @@ async fn command_deposit(config: &Config, command_config: DepositCli) -> Command
     }
 
     let pool_stake_address = find_pool_stake_address(&spl_single_pool::id(), &pool_address);
-    let pool_stake_active = quarantine::get_stake_info(config, &pool_stake_address)
+    let pool_stake_active = config
+        .stake_client
+        .get_delegated_stake(&pool_stake_address)
         .await?
         .unwrap()
         .1
@@ -329,9 +444,10 @@ async fn command_deposit(config: &Config, command_config: DepositCli) -> Command
     }
 
     let pool_mint_address = find_pool_mint_address(&spl_single_pool::id(), &pool_address);
+    let token_program_id = get_token_program_id(config, &pool_mint_address).await?;
     let token = Token::new(
         config.program_client.clone(),
-        &spl_token::id(),
+        &token_program_id,
         &pool_mint_address,
         None,
         payer.clone(),
@@ -354,13 +470,14 @@ async fn command_deposit(config: &Config, command_config: DepositCli) -> Command
         .base
         .amount;
 
-    let instructions = spl_single_pool::instruction::deposit(
+    let instructions = spl_single_pool::instruction::deposit_with_token_program(
         &spl_single_pool::id(),
         &pool_address,
         &stake_account_address,
         &token_account_address,
         &lamport_recipient,
         &stake_authority.pubkey(),
+        &token_program_id,
     );
 
     let mut signers = vec![];
@@ -396,6 +513,115 @@ async fn command_deposit(config: &Config, command_config: DepositCli) -> Command
     ))
 }
 
+// deposit sol
+async fn command_deposit_sol(config: &Config, command_config: DepositSolCli) -> CommandResult {
+    let payer = config.fee_payer()?;
+    let owner = config.default_signer()?;
+    let pool_address = pool_address_from_args(
+        command_config.pool_address,
+        command_config.vote_account_address,
+    );
+
+    println_display(
+        config,
+        format!(
+            "Depositing {} lamports into pool {}\n",
+            command_config.lamports, pool_address
+        ),
+    );
+
+    if config
+        .program_client
+        .get_account(pool_address)
+        .await?
+        .is_none()
+    {
+        return Err(format!("Pool {} has not been initialized", pool_address).into());
+    }
+
+    let onramp_address = find_pool_onramp_address(&spl_single_pool::id(), &pool_address);
+    if !config
+        .program_client
+        .get_account(onramp_address)
+        .await?
+        .is_some_and(|account| account.owner == stake::program::id())
+    {
+        return Err(format!(
+            "Pool {} has no onramp account; create one with `manage create-on-ramp`",
+            pool_address
+        )
+        .into());
+    }
+
+    let pool_mint_address = find_pool_mint_address(&spl_single_pool::id(), &pool_address);
+    let token_program_id = get_token_program_id(config, &pool_mint_address).await?;
+    let token = Token::new(
+        config.program_client.clone(),
+        &token_program_id,
+        &pool_mint_address,
+        None,
+        payer.clone(),
+    );
+
+    // use token account provided, or get/create the associated account for the
+    // client keypair
+    let token_account_address = if let Some(account) = command_config.token_account_address {
+        account
+    } else {
+        token
+            .get_or_create_associated_account_info(&owner.pubkey())
+            .await?;
+        token.get_associated_token_address(&owner.pubkey())
+    };
+
+    let previous_token_amount = token
+        .get_account_info(&token_account_address)
+        .await?
+        .base
+        .amount;
+
+    let instruction = spl_single_pool::instruction::deposit_sol_with_token_program(
+        &spl_single_pool::id(),
+        &pool_address,
+        &owner.pubkey(),
+        &token_account_address,
+        command_config.lamports,
+        &token_program_id,
+    );
+
+    let mut signers = vec![];
+    for signer in [payer.clone(), owner] {
+        if !signers.contains(&signer) {
+            signers.push(signer);
+        }
+    }
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &signers,
+        config.program_client.get_latest_blockhash().await?,
+    );
+
+    let signature = process_transaction(config, transaction).await?;
+    let token_amount = token
+        .get_account_info(&token_account_address)
+        .await?
+        .base
+        .amount
+        - previous_token_amount;
+
+    Ok(format_output(
+        config,
+        "DepositSol".to_string(),
+        DepositOutput {
+            pool_address,
+            token_amount,
+            signature,
+        },
+    ))
+}
+
 // withdraw stake
 async fn command_withdraw(config: &Config, command_config: WithdrawCli) -> CommandResult {
     let payer = config.fee_payer()?;
@@ -426,9 +652,10 @@ async fn command_withdraw(config: &Config, command_config: WithdrawCli) -> Comma
 
     // now all the mint and token info
     let pool_mint_address = find_pool_mint_address(&spl_single_pool::id(), &pool_address);
+    let token_program_id = get_token_program_id(config, &pool_mint_address).await?;
     let token = Token::new(
         config.program_client.clone(),
-        &spl_token::id(),
+        &token_program_id,
         &pool_mint_address,
         None,
         payer.clone(),
@@ -479,16 +706,14 @@ async fn command_withdraw(config: &Config, command_config: WithdrawCli) -> Comma
 
     // create a blank stake account to withdraw into
     let mut instructions = vec![
-        quarantine::create_uninitialized_stake_account_instruction(
-            config,
-            &payer.pubkey(),
-            &stake_account_address,
-        )
-        .await?,
+        config
+            .stake_client
+            .create_uninitialized_stake_account_instruction(&payer.pubkey(), &stake_account_address)
+            .await?,
     ];
 
     // perform the withdrawal
-    instructions.extend(spl_single_pool::instruction::withdraw(
+    instructions.extend(spl_single_pool::instruction::withdraw_with_token_program(
         &spl_single_pool::id(),
         &pool_address,
         &stake_account_address,
@@ -496,6 +721,7 @@ async fn command_withdraw(config: &Config, command_config: WithdrawCli) -> Comma
         &token_account_address,
         &token_authority.pubkey(),
         token_amount,
+        &token_program_id,
     ));
 
     // possibly deactivate the new stake account
@@ -521,8 +747,10 @@ async fn command_withdraw(config: &Config, command_config: WithdrawCli) -> Comma
     );
 
     let signature = process_transaction(config, transaction).await?;
-    let stake_amount = if let Some((_, stake)) =
-        quarantine::get_stake_info(config, &stake_account_address).await?
+    let stake_amount = if let Some((_, stake)) = config
+        .stake_client
+        .get_delegated_stake(&stake_account_address)
+        .await?
     {
         stake.delegation.stake
     } else {
@@ -574,11 +802,21 @@ async fn command_create_metadata(
 
     // and... i guess thats it?
 
-    let instruction = spl_single_pool::instruction::create_token_metadata(
-        &spl_single_pool::id(),
-        &pool_address,
-        &payer.pubkey(),
-    );
+    let pool_mint_address = find_pool_mint_address(&spl_single_pool::id(), &pool_address);
+    let instruction =
+        if get_token_program_id(config, &pool_mint_address).await? == spl_token_2022::id() {
+            spl_single_pool::instruction::create_token_2022_metadata(
+                &spl_single_pool::id(),
+                &pool_address,
+                &payer.pubkey(),
+            )
+        } else {
+            spl_single_pool::instruction::create_token_metadata(
+                &spl_single_pool::id(),
+                &pool_address,
+                &payer.pubkey(),
+            )
+        };
 
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -645,14 +883,28 @@ async fn command_update_metadata(
         unreachable!();
     }
 
-    let instruction = spl_single_pool::instruction::update_token_metadata(
-        &spl_single_pool::id(),
-        &vote_account_address,
-        &authorized_withdrawer.pubkey(),
-        command_config.token_name,
-        command_config.token_symbol,
-        command_config.token_uri.unwrap_or_default(),
-    );
+    let pool_mint_address = find_pool_mint_address(&spl_single_pool::id(), &pool_address);
+    let instruction =
+        if get_token_program_id(config, &pool_mint_address).await? == spl_token_2022::id() {
+            spl_single_pool::instruction::update_token_2022_metadata(
+                &spl_single_pool::id(),
+                &vote_account_address,
+                &authorized_withdrawer.pubkey(),
+                &payer.pubkey(),
+                command_config.token_name,
+                command_config.token_symbol,
+                command_config.token_uri.unwrap_or_default(),
+            )
+        } else {
+            spl_single_pool::instruction::update_token_metadata(
+                &spl_single_pool::id(),
+                &vote_account_address,
+                &authorized_withdrawer.pubkey(),
+                command_config.token_name,
+                command_config.token_symbol,
+                command_config.token_uri.unwrap_or_default(),
+            )
+        };
 
     let mut signers = vec![];
     for signer in [payer.clone(), authorized_withdrawer] {
@@ -725,7 +977,7 @@ async fn command_create_stake(config: &Config, command_config: CreateStakeCli) -
         &spl_single_pool::id(),
         &vote_account_address,
         &stake_authority_address,
-        &quarantine::get_rent(config).await?,
+        &config.stake_client.get_rent().await?,
         command_config.lamports,
     );
 
@@ -818,12 +1070,15 @@ async fn get_pool_display(
     };
 
     let pool_stake_address = find_pool_stake_address(&spl_single_pool::id(), &pool_address);
-    let available_stake =
-        if let Some((_, stake)) = quarantine::get_stake_info(config, &pool_stake_address).await? {
-            stake.delegation.stake - quarantine::get_minimum_delegation(config).await?
-        } else {
-            unreachable!()
-        };
+    let available_stake = if let Some((_, stake)) = config
+        .stake_client
+        .get_delegated_stake(&pool_stake_address)
+        .await?
+    {
+        stake.delegation.stake - get_pool_minimum_delegation(config).await?
+    } else {
+        unreachable!()
+    };
 
     let pool_mint_address = find_pool_mint_address(&spl_single_pool::id(), &pool_address);
     let token_supply = config
@@ -874,3 +1129,19 @@ async fn process_transaction(
         ))
     }
 }
+
+async fn get_token_program_id(config: &Config, mint_address: &Pubkey) -> Result<Pubkey, Error> {
+    if let Some(mint_account) = config.program_client.get_account(*mint_address).await? {
+        Ok(mint_account.owner)
+    } else {
+        Err(format!("Pool mint {} does not exist", mint_address).into())
+    }
+}
+
+// the pool program never delegates less than 1 sol, regardless of the stake program minimum
+async fn get_pool_minimum_delegation(config: &Config) -> Result<u64, Error> {
+    Ok(std::cmp::max(
+        config.stake_client.get_minimum_delegation().await?,
+        LAMPORTS_PER_SOL,
+    ))
+}
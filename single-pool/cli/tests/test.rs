@@ -357,6 +357,38 @@ async fn withdraw() {
     assert!(status.success());
 }
 
+#[tokio::test]
+#[serial]
+async fn deposit_sol() {
+    let env = setup(true).await;
+
+    let status = Command::new(SVSP_CLI)
+        .args([
+            "deposit-sol",
+            "-C",
+            &env.config_file_path,
+            "--vote-account",
+            &env.vote_account.to_string(),
+            &LAMPORTS_PER_SOL.to_string(),
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new(SVSP_CLI)
+        .args([
+            "manage",
+            "replenish-pool",
+            "-C",
+            &env.config_file_path,
+            "--vote-account",
+            &env.vote_account.to_string(),
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
 #[tokio::test]
 #[serial]
 async fn create_metadata() {
@@ -2,6 +2,7 @@ import { PublicKey } from '@solana/web3.js';
 import {
   findPoolAddress as findPoolModern,
   findPoolStakeAddress as findStakeModern,
+  findPoolOnrampAddress as findOnrampModern,
   findPoolMintAddress as findMintModern,
   findPoolStakeAuthorityAddress as findStakeAuthorityModern,
   findPoolMintAuthorityAddress as findMintAuthorityModern,
@@ -17,6 +18,10 @@ export async function findPoolStakeAddress(programId: PublicKey, poolAddress: Pu
   return new PublicKey(await findStakeModern(programId.toBase58(), poolAddress.toBase58()));
 }
 
+export async function findPoolOnrampAddress(programId: PublicKey, poolAddress: PublicKey) {
+  return new PublicKey(await findOnrampModern(programId.toBase58(), poolAddress.toBase58()));
+}
+
 export async function findPoolMintAddress(programId: PublicKey, poolAddress: PublicKey) {
   return new PublicKey(await findMintModern(programId.toBase58(), poolAddress.toBase58()));
 }
@@ -24,6 +24,10 @@ export type PoolStakeAddress<TAddress extends string = string> = Base58EncodedAd
   readonly __poolStakeAddress: unique symbol;
 };
 
+export type PoolOnrampAddress<TAddress extends string = string> = Base58EncodedAddress<TAddress> & {
+  readonly __poolOnrampAddress: unique symbol;
+};
+
 export type PoolMintAddress<TAddress extends string = string> = Base58EncodedAddress<TAddress> & {
   readonly __poolMintAddress: unique symbol;
 };
@@ -57,6 +61,13 @@ export async function findPoolStakeAddress(
   return (await findPda(programId, poolAddress, 'stake')) as PoolStakeAddress;
 }
 
+export async function findPoolOnrampAddress(
+  programId: Base58EncodedAddress,
+  poolAddress: PoolAddress,
+): Promise<PoolOnrampAddress> {
+  return (await findPda(programId, poolAddress, 'onramp')) as PoolOnrampAddress;
+}
+
 export async function findPoolMintAddress(
   programId: Base58EncodedAddress,
   poolAddress: PoolAddress,
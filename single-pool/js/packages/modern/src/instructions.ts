@@ -17,12 +17,14 @@ import {
   PoolMplAuthorityAddress,
   PoolStakeAuthorityAddress,
   PoolStakeAddress,
+  PoolOnrampAddress,
   findMplMetadataAddress,
   findPoolMplAuthorityAddress,
   findPoolAddress,
   VoteAccountAddress,
   PoolAddress,
   findPoolStakeAddress,
+  findPoolOnrampAddress,
   findPoolMintAddress,
   findPoolMintAuthorityAddress,
   findPoolStakeAuthorityAddress,
@@ -91,6 +93,7 @@ type DepositStakeInstruction = IInstruction<typeof SINGLE_POOL_PROGRAM_ID> &
       ReadonlyAccount<typeof SYSVAR_STAKE_HISTORY_ID>,
       ReadonlyAccount<typeof TOKEN_PROGRAM_ID>,
       ReadonlyAccount<typeof STAKE_PROGRAM_ID>,
+      ReadonlyAccount<PoolOnrampAddress>,
     ]
   > &
   IInstructionWithData<Uint8Array>;
@@ -108,6 +111,7 @@ type WithdrawStakeInstruction = IInstruction<typeof SINGLE_POOL_PROGRAM_ID> &
       ReadonlyAccount<typeof SYSVAR_CLOCK_ID>,
       ReadonlyAccount<typeof TOKEN_PROGRAM_ID>,
       ReadonlyAccount<typeof STAKE_PROGRAM_ID>,
+      ReadonlyAccount<PoolOnrampAddress>,
     ]
   > &
   IInstructionWithData<Uint8Array>;
@@ -228,8 +232,9 @@ export async function depositStakeInstruction(
   userLamportAccount: Base58EncodedAddress,
 ): Promise<DepositStakeInstruction> {
   const programAddress = SINGLE_POOL_PROGRAM_ID;
-  const [stake, mint, stakeAuthority, mintAuthority] = await Promise.all([
+  const [stake, onramp, mint, stakeAuthority, mintAuthority] = await Promise.all([
     findPoolStakeAddress(programAddress, pool),
+    findPoolOnrampAddress(programAddress, pool),
     findPoolMintAddress(programAddress, pool),
     findPoolStakeAuthorityAddress(programAddress, pool),
     findPoolMintAuthorityAddress(programAddress, pool),
@@ -252,6 +257,7 @@ export async function depositStakeInstruction(
       { address: SYSVAR_STAKE_HISTORY_ID, role: AccountRole.READONLY },
       { address: TOKEN_PROGRAM_ID, role: AccountRole.READONLY },
       { address: STAKE_PROGRAM_ID, role: AccountRole.READONLY },
+      { address: onramp, role: AccountRole.READONLY },
     ],
     programAddress,
   };
@@ -265,8 +271,9 @@ export async function withdrawStakeInstruction(
   tokenAmount: bigint,
 ): Promise<WithdrawStakeInstruction> {
   const programAddress = SINGLE_POOL_PROGRAM_ID;
-  const [stake, mint, stakeAuthority, mintAuthority] = await Promise.all([
+  const [stake, onramp, mint, stakeAuthority, mintAuthority] = await Promise.all([
     findPoolStakeAddress(programAddress, pool),
+    findPoolOnrampAddress(programAddress, pool),
     findPoolMintAddress(programAddress, pool),
     findPoolStakeAuthorityAddress(programAddress, pool),
     findPoolMintAuthorityAddress(programAddress, pool),
@@ -292,6 +299,7 @@ export async function withdrawStakeInstruction(
       { address: SYSVAR_CLOCK_ID, role: AccountRole.READONLY },
       { address: TOKEN_PROGRAM_ID, role: AccountRole.READONLY },
       { address: STAKE_PROGRAM_ID, role: AccountRole.READONLY },
+      { address: onramp, role: AccountRole.READONLY },
     ],
     programAddress,
   };
@@ -92,6 +92,16 @@ pub enum SinglePoolError {
     /// Attempted to initialize a pool that is already initialized.
     #[error("PoolAlreadyInitialized")]
     PoolAlreadyInitialized,
+
+    // 20
+    /// Provided pool onramp account does not match address derived from the
+    /// pool account.
+    #[error("InvalidPoolOnRamp")]
+    InvalidPoolOnRamp,
+    /// Not enough pool stake to cover the withdrawal while lamports are still
+    /// pending in the pool onramp account.
+    #[error("OnRampStakeNotMerged")]
+    OnRampStakeNotMerged,
 }
 impl From<SinglePoolError> for ProgramError {
     fn from(e: SinglePoolError) -> Self {
@@ -152,6 +162,11 @@ impl PrintProgramError for SinglePoolError {
                 msg!("Error: Attempted to deposit from or withdraw to pool stake account."),
             SinglePoolError::PoolAlreadyInitialized =>
                 msg!("Error: Attempted to initialize a pool that is already initialized."),
+            SinglePoolError::InvalidPoolOnRamp =>
+                msg!("Error: Provided pool onramp account does not match address derived from the pool account."),
+            SinglePoolError::OnRampStakeNotMerged =>
+                msg!("Error: Not enough pool stake to cover the withdrawal while lamports are still pending in the pool onramp account. \
+                     (Lamports in the onramp can only be withdrawn after they are merged into the pool stake account.)"),
         }
     }
 }
@@ -5,19 +5,21 @@
 use {
     crate::{
         find_default_deposit_account_address_and_seed, find_pool_address, find_pool_mint_address,
-        find_pool_mint_authority_address, find_pool_mpl_authority_address, find_pool_stake_address,
-        find_pool_stake_authority_address,
+        find_pool_mint_authority_address, find_pool_mpl_authority_address,
+        find_pool_onramp_address, find_pool_stake_address, find_pool_stake_authority_address,
         inline_mpl_token_metadata::{self, pda::find_metadata_account},
         state::SinglePool,
     },
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
+        borsh1::get_packed_len,
         instruction::{AccountMeta, Instruction},
         program_pack::Pack,
         pubkey::Pubkey,
         rent::Rent,
         stake, system_instruction, system_program, sysvar,
     },
+    spl_token_2022::extension::ExtensionType,
 };
 
 /// Instructions supported by the SinglePool program.
@@ -28,6 +30,8 @@ pub enum SinglePoolInstruction {
     ///   stake pool. The pool stake account must contain the rent-exempt
     ///   minimum plus the minimum delegation. No tokens will be minted: to
     ///   deposit more, use `Deposit` after `InitializeStake`.
+    ///   If the token program is Token-2022, the mint is created with a
+    ///   metadata pointer to itself, so token metadata is stored in the mint.
     ///
     ///   0. `[]` Validator vote account
     ///   1. `[w]` Pool account
@@ -60,7 +64,8 @@ pub enum SinglePoolInstruction {
 
     ///   Deposit stake into the pool. The output is a "pool" token
     ///   representing fractional ownership of the pool stake. Inputs are
-    ///   converted to the current ratio.
+    ///   converted to the current ratio, which accounts for lamports pending
+    ///   in the pool onramp account.
     ///
     ///   0. `[]` Pool account
     ///   1. `[w]` Pool stake account
@@ -74,9 +79,13 @@ pub enum SinglePoolInstruction {
     ///   9. `[]` Stake history sysvar
     ///  10. `[]` Token program
     ///  11. `[]` Stake program
+    ///  12. `[]` (Optional) Pool onramp account, required once the pool has
+    ///      been upgraded by `InitializePoolOnRamp`
     DepositStake,
 
     ///   Redeem tokens issued by this pool for stake at the current ratio.
+    ///   Stake is only ever split from the pool stake account, so lamports
+    ///   pending in the onramp cannot be withdrawn until they are merged.
     ///
     ///   0. `[]` Pool account
     ///   1. `[w]` Pool stake account
@@ -88,6 +97,8 @@ pub enum SinglePoolInstruction {
     ///   7. `[]` Clock sysvar
     ///   8. `[]` Token program
     ///   9. `[]` Stake program
+    ///  10. `[]` (Optional) Pool onramp account, required once the pool has
+    ///      been upgraded by `InitializePoolOnRamp`
     WithdrawStake {
         /// User authority for the new stake account
         user_stake_authority: Pubkey,
@@ -101,25 +112,29 @@ pub enum SinglePoolInstruction {
     ///   Note this instruction is not necessary for the pool to operate, to
     ///   ensure we cannot be broken by upstream.
     ///
+    ///   For Token-2022 pools, the metadata is stored in the pool mint itself.
+    ///
     ///   0. `[]` Pool account
     ///   1. `[]` Pool token mint
     ///   2. `[]` Pool mint authority
     ///   3. `[]` Pool MPL authority
     ///   4. `[s, w]` Payer for creation of token metadata account
-    ///   5. `[w]` Token metadata account
-    ///   6. `[]` Metadata program id
+    ///   5. `[w]` Token metadata account, or the pool mint for Token-2022
+    ///   6. `[]` Metadata program id, or the Token-2022 program id
     ///   7. `[]` System program id
     CreateTokenMetadata,
 
     ///   Update token metadata for the stake-pool token in the metaplex-token
-    ///   program.
+    ///   program, or in the pool mint itself for Token-2022 pools.
     ///
     ///   0. `[]` Validator vote account
     ///   1. `[]` Pool account
     ///   2. `[]` Pool MPL authority
     ///   3. `[s]` Vote account authorized withdrawer
-    ///   4. `[w]` Token metadata account
-    ///   5. `[]` Metadata program id
+    ///   4. `[w]` Token metadata account, or the pool mint for Token-2022
+    ///   5. `[]` Metadata program id, or the Token-2022 program id
+    ///   6. `[s, w]` (Optional) Payer to fund rent if Token-2022 metadata grows
+    ///   7. `[]` (Optional) System program id, required with the payer
     UpdateTokenMetadata {
         /// Token name
         name: String,
@@ -128,6 +143,61 @@ pub enum SinglePoolInstruction {
         /// URI of the uploaded metadata of the spl-token
         uri: String,
     },
+
+    ///   Create the onramp stake account for an existing pool, which holds
+    ///   lamports deposited with `DepositSol` until they are delegated and
+    ///   merged into the pool stake account. The onramp account must contain
+    ///   the rent-exempt minimum. Pools created before the onramp existed are
+    ///   reallocated to track `DepositSol` lamports, so the pool account must
+    ///   also hold the rent-exempt minimum for its new size. Pools created
+    ///   with `initialize` already include this step.
+    ///
+    ///   0. `[w]` Pool account
+    ///   1. `[w]` Pool onramp account
+    ///   2. `[]` Pool stake authority
+    ///   3. `[]` Rent sysvar
+    ///   4. `[]` System program
+    ///   5. `[]` Stake program
+    InitializePoolOnRamp,
+
+    ///   Deposit liquid SOL into the pool. The lamports are held in the pool
+    ///   onramp account, which is delegated and later merged into the pool
+    ///   stake account by `ReplenishPool`. Pool tokens are minted at the
+    ///   current ratio, counting lamports already pending in the onramp.
+    ///   Only lamports recorded by this instruction are valued, so lamports
+    ///   transferred directly into the onramp are ignored until delegated.
+    ///
+    ///   0. `[w]` Pool account
+    ///   1. `[]` Pool stake account
+    ///   2. `[w]` Pool onramp account
+    ///   3. `[w]` Pool token mint
+    ///   4. `[]` Pool mint authority
+    ///   5. `[s, w]` User account to take lamports from
+    ///   6. `[w]` User account to receive pool tokens
+    ///   7. `[]` System program
+    ///   8. `[]` Token program
+    ///   9. `[]` Stake program
+    DepositSol {
+        /// Amount of lamports to deposit
+        lamports: u64,
+    },
+
+    ///   Permissionless crank for the pool onramp account. Delegates any
+    ///   lamports deposited into the onramp and, once the onramp stake is
+    ///   active, merges it into the pool stake account, then recreates the
+    ///   onramp with any lamports that were not yet delegated.
+    ///
+    ///   0. `[]` Validator vote account
+    ///   1. `[w]` Pool account
+    ///   2. `[w]` Pool stake account
+    ///   3. `[w]` Pool onramp account
+    ///   4. `[]` Pool stake authority
+    ///   5. `[]` Rent sysvar
+    ///   6. `[]` Clock sysvar
+    ///   7. `[]` Stake history sysvar
+    ///   8. `[]` Stake config sysvar
+    ///   9. `[]` Stake program
+    ReplenishPool,
 }
 
 /// Creates all necessary instructions to initialize the stake pool.
@@ -137,30 +207,77 @@ pub fn initialize(
     payer: &Pubkey,
     rent: &Rent,
     minimum_delegation: u64,
+) -> Vec<Instruction> {
+    initialize_with_token_program(
+        program_id,
+        vote_account_address,
+        payer,
+        rent,
+        minimum_delegation,
+        &spl_token::id(),
+    )
+}
+
+/// Creates all necessary instructions to initialize the stake pool with a
+/// mint owned by the given token program. Token-2022 pools store their token
+/// metadata in the mint itself.
+pub fn initialize_with_token_program(
+    program_id: &Pubkey,
+    vote_account_address: &Pubkey,
+    payer: &Pubkey,
+    rent: &Rent,
+    minimum_delegation: u64,
+    token_program_id: &Pubkey,
 ) -> Vec<Instruction> {
     let pool_address = find_pool_address(program_id, vote_account_address);
     let pool_rent = rent.minimum_balance(std::mem::size_of::<SinglePool>());
 
     let stake_address = find_pool_stake_address(program_id, &pool_address);
     let stake_space = std::mem::size_of::<stake::state::StakeStateV2>();
-    let stake_rent_plus_minimum = rent
-        .minimum_balance(stake_space)
-        .saturating_add(minimum_delegation);
+    let stake_rent = rent.minimum_balance(stake_space);
+    let stake_rent_plus_minimum = stake_rent.saturating_add(minimum_delegation);
+
+    let onramp_address = find_pool_onramp_address(program_id, &pool_address);
 
     let mint_address = find_pool_mint_address(program_id, &pool_address);
-    let mint_rent = rent.minimum_balance(spl_token::state::Mint::LEN);
+    let is_token_2022 = *token_program_id == spl_token_2022::id();
+    let mint_space = if is_token_2022 {
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::MetadataPointer,
+        ])
+        .unwrap()
+    } else {
+        spl_token::state::Mint::LEN
+    };
+    let mint_rent = rent.minimum_balance(mint_space);
 
     vec![
         system_instruction::transfer(payer, &pool_address, pool_rent),
         system_instruction::transfer(payer, &stake_address, stake_rent_plus_minimum),
         system_instruction::transfer(payer, &mint_address, mint_rent),
-        initialize_pool(program_id, vote_account_address),
-        create_token_metadata(program_id, &pool_address, payer),
+        initialize_pool_with_token_program(program_id, vote_account_address, token_program_id),
+        system_instruction::transfer(payer, &onramp_address, stake_rent),
+        initialize_pool_onramp(program_id, &pool_address),
+        if is_token_2022 {
+            create_token_2022_metadata(program_id, &pool_address, payer)
+        } else {
+            create_token_metadata(program_id, &pool_address, payer)
+        },
     ]
 }
 
 /// Creates an `InitializePool` instruction.
 pub fn initialize_pool(program_id: &Pubkey, vote_account_address: &Pubkey) -> Instruction {
+    initialize_pool_with_token_program(program_id, vote_account_address, &spl_token::id())
+}
+
+/// Creates an `InitializePool` instruction for a mint owned by the given token
+/// program.
+pub fn initialize_pool_with_token_program(
+    program_id: &Pubkey,
+    vote_account_address: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Instruction {
     let pool_address = find_pool_address(program_id, vote_account_address);
     let mint_address = find_pool_mint_address(program_id, &pool_address);
 
@@ -184,7 +301,7 @@ pub fn initialize_pool(program_id: &Pubkey, vote_account_address: &Pubkey) -> In
         #[allow(deprecated)]
         AccountMeta::new_readonly(stake::config::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
         AccountMeta::new_readonly(stake::program::id(), false),
     ];
 
@@ -230,6 +347,28 @@ pub fn deposit(
     user_token_account: &Pubkey,
     user_lamport_account: &Pubkey,
     user_withdraw_authority: &Pubkey,
+) -> Vec<Instruction> {
+    deposit_with_token_program(
+        program_id,
+        pool_address,
+        user_stake_account,
+        user_token_account,
+        user_lamport_account,
+        user_withdraw_authority,
+        &spl_token::id(),
+    )
+}
+
+/// Creates all necessary instructions to deposit stake into a pool whose mint
+/// is owned by the given token program.
+pub fn deposit_with_token_program(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    user_stake_account: &Pubkey,
+    user_token_account: &Pubkey,
+    user_lamport_account: &Pubkey,
+    user_withdraw_authority: &Pubkey,
+    token_program_id: &Pubkey,
 ) -> Vec<Instruction> {
     let pool_stake_authority = find_pool_stake_authority_address(program_id, pool_address);
 
@@ -248,12 +387,13 @@ pub fn deposit(
             stake::state::StakeAuthorize::Withdrawer,
             None,
         ),
-        deposit_stake(
+        deposit_stake_with_token_program(
             program_id,
             pool_address,
             user_stake_account,
             user_token_account,
             user_lamport_account,
+            token_program_id,
         ),
     ]
 }
@@ -265,6 +405,26 @@ pub fn deposit_stake(
     user_stake_account: &Pubkey,
     user_token_account: &Pubkey,
     user_lamport_account: &Pubkey,
+) -> Instruction {
+    deposit_stake_with_token_program(
+        program_id,
+        pool_address,
+        user_stake_account,
+        user_token_account,
+        user_lamport_account,
+        &spl_token::id(),
+    )
+}
+
+/// Creates a `DepositStake` instruction for a pool whose mint is owned by the
+/// given token program.
+pub fn deposit_stake_with_token_program(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    user_stake_account: &Pubkey,
+    user_token_account: &Pubkey,
+    user_lamport_account: &Pubkey,
+    token_program_id: &Pubkey,
 ) -> Instruction {
     let data = borsh::to_vec(&SinglePoolInstruction::DepositStake).unwrap();
 
@@ -285,8 +445,9 @@ pub fn deposit_stake(
         AccountMeta::new(*user_lamport_account, false),
         AccountMeta::new_readonly(sysvar::clock::id(), false),
         AccountMeta::new_readonly(sysvar::stake_history::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
         AccountMeta::new_readonly(stake::program::id(), false),
+        AccountMeta::new_readonly(find_pool_onramp_address(program_id, pool_address), false),
     ];
 
     Instruction {
@@ -309,10 +470,35 @@ pub fn withdraw(
     user_token_account: &Pubkey,
     user_token_authority: &Pubkey,
     token_amount: u64,
+) -> Vec<Instruction> {
+    withdraw_with_token_program(
+        program_id,
+        pool_address,
+        user_stake_account,
+        user_stake_authority,
+        user_token_account,
+        user_token_authority,
+        token_amount,
+        &spl_token::id(),
+    )
+}
+
+/// Creates all necessary instructions to withdraw stake from a pool whose mint
+/// is owned by the given token program. The same requirements as `withdraw`
+/// apply to the stake account.
+pub fn withdraw_with_token_program(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    user_stake_account: &Pubkey,
+    user_stake_authority: &Pubkey,
+    user_token_account: &Pubkey,
+    user_token_authority: &Pubkey,
+    token_amount: u64,
+    token_program_id: &Pubkey,
 ) -> Vec<Instruction> {
     vec![
-        spl_token::instruction::approve(
-            &spl_token::id(),
+        spl_token_2022::instruction::approve(
+            token_program_id,
             user_token_account,
             &find_pool_mint_authority_address(program_id, pool_address),
             user_token_authority,
@@ -320,13 +506,14 @@ pub fn withdraw(
             token_amount,
         )
         .unwrap(),
-        withdraw_stake(
+        withdraw_stake_with_token_program(
             program_id,
             pool_address,
             user_stake_account,
             user_stake_authority,
             user_token_account,
             token_amount,
+            token_program_id,
         ),
     ]
 }
@@ -339,6 +526,28 @@ pub fn withdraw_stake(
     user_stake_authority: &Pubkey,
     user_token_account: &Pubkey,
     token_amount: u64,
+) -> Instruction {
+    withdraw_stake_with_token_program(
+        program_id,
+        pool_address,
+        user_stake_account,
+        user_stake_authority,
+        user_token_account,
+        token_amount,
+        &spl_token::id(),
+    )
+}
+
+/// Creates a `WithdrawStake` instruction for a pool whose mint is owned by the
+/// given token program.
+pub fn withdraw_stake_with_token_program(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    user_stake_account: &Pubkey,
+    user_stake_authority: &Pubkey,
+    user_token_account: &Pubkey,
+    token_amount: u64,
+    token_program_id: &Pubkey,
 ) -> Instruction {
     let data = borsh::to_vec(&SinglePoolInstruction::WithdrawStake {
         user_stake_authority: *user_stake_authority,
@@ -361,7 +570,135 @@ pub fn withdraw_stake(
         AccountMeta::new(*user_stake_account, false),
         AccountMeta::new(*user_token_account, false),
         AccountMeta::new_readonly(sysvar::clock::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(stake::program::id(), false),
+        AccountMeta::new_readonly(find_pool_onramp_address(program_id, pool_address), false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Creates all necessary instructions to create the onramp account for an
+/// existing pool, including the rent for growing a pool account created
+/// before the onramp existed.
+pub fn create_pool_onramp(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    payer: &Pubkey,
+    rent: &Rent,
+) -> Vec<Instruction> {
+    let onramp_address = find_pool_onramp_address(program_id, pool_address);
+    let stake_space = std::mem::size_of::<stake::state::StakeStateV2>();
+    let stake_rent = rent.minimum_balance(stake_space);
+    let pool_rent_increase = rent
+        .minimum_balance(get_packed_len::<SinglePool>())
+        .saturating_sub(rent.minimum_balance(SinglePool::LEGACY_LEN));
+
+    vec![
+        system_instruction::transfer(payer, pool_address, pool_rent_increase),
+        system_instruction::transfer(payer, &onramp_address, stake_rent),
+        initialize_pool_onramp(program_id, pool_address),
+    ]
+}
+
+/// Creates an `InitializePoolOnRamp` instruction.
+pub fn initialize_pool_onramp(program_id: &Pubkey, pool_address: &Pubkey) -> Instruction {
+    let data = borsh::to_vec(&SinglePoolInstruction::InitializePoolOnRamp).unwrap();
+    let accounts = vec![
+        AccountMeta::new(*pool_address, false),
+        AccountMeta::new(find_pool_onramp_address(program_id, pool_address), false),
+        AccountMeta::new_readonly(
+            find_pool_stake_authority_address(program_id, pool_address),
+            false,
+        ),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(stake::program::id(), false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Creates a `DepositSol` instruction.
+pub fn deposit_sol(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    user_wallet: &Pubkey,
+    user_token_account: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    deposit_sol_with_token_program(
+        program_id,
+        pool_address,
+        user_wallet,
+        user_token_account,
+        lamports,
+        &spl_token::id(),
+    )
+}
+
+/// Creates a `DepositSol` instruction for a pool whose mint is owned by the
+/// given token program.
+pub fn deposit_sol_with_token_program(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    user_wallet: &Pubkey,
+    user_token_account: &Pubkey,
+    lamports: u64,
+    token_program_id: &Pubkey,
+) -> Instruction {
+    let data = borsh::to_vec(&SinglePoolInstruction::DepositSol { lamports }).unwrap();
+
+    let accounts = vec![
+        AccountMeta::new(*pool_address, false),
+        AccountMeta::new_readonly(find_pool_stake_address(program_id, pool_address), false),
+        AccountMeta::new(find_pool_onramp_address(program_id, pool_address), false),
+        AccountMeta::new(find_pool_mint_address(program_id, pool_address), false),
+        AccountMeta::new_readonly(
+            find_pool_mint_authority_address(program_id, pool_address),
+            false,
+        ),
+        AccountMeta::new(*user_wallet, true),
+        AccountMeta::new(*user_token_account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(stake::program::id(), false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Creates a `ReplenishPool` instruction.
+pub fn replenish_pool(program_id: &Pubkey, vote_account_address: &Pubkey) -> Instruction {
+    let pool_address = find_pool_address(program_id, vote_account_address);
+
+    let data = borsh::to_vec(&SinglePoolInstruction::ReplenishPool).unwrap();
+    let accounts = vec![
+        AccountMeta::new_readonly(*vote_account_address, false),
+        AccountMeta::new(pool_address, false),
+        AccountMeta::new(find_pool_stake_address(program_id, &pool_address), false),
+        AccountMeta::new(find_pool_onramp_address(program_id, &pool_address), false),
+        AccountMeta::new_readonly(
+            find_pool_stake_authority_address(program_id, &pool_address),
+            false,
+        ),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::stake_history::id(), false),
+        #[allow(deprecated)]
+        AccountMeta::new_readonly(stake::config::id(), false),
         AccountMeta::new_readonly(stake::program::id(), false),
     ];
 
@@ -471,3 +808,74 @@ pub fn update_token_metadata(
         data,
     }
 }
+
+/// Creates a `CreateTokenMetadata` instruction for a Token-2022 pool, which
+/// stores the metadata in the pool mint itself.
+pub fn create_token_2022_metadata(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let pool_mint = find_pool_mint_address(program_id, pool_address);
+    let data = borsh::to_vec(&SinglePoolInstruction::CreateTokenMetadata).unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*pool_address, false),
+        AccountMeta::new(pool_mint, false),
+        AccountMeta::new_readonly(
+            find_pool_mint_authority_address(program_id, pool_address),
+            false,
+        ),
+        AccountMeta::new_readonly(
+            find_pool_mpl_authority_address(program_id, pool_address),
+            false,
+        ),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(pool_mint, false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Creates an `UpdateTokenMetadata` instruction for a Token-2022 pool, which
+/// stores the metadata in the pool mint itself.
+pub fn update_token_2022_metadata(
+    program_id: &Pubkey,
+    vote_account_address: &Pubkey,
+    authorized_withdrawer: &Pubkey,
+    payer: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let pool_address = find_pool_address(program_id, vote_account_address);
+    let pool_mint = find_pool_mint_address(program_id, &pool_address);
+    let data =
+        borsh::to_vec(&SinglePoolInstruction::UpdateTokenMetadata { name, symbol, uri }).unwrap();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*vote_account_address, false),
+        AccountMeta::new_readonly(pool_address, false),
+        AccountMeta::new_readonly(
+            find_pool_mpl_authority_address(program_id, &pool_address),
+            false,
+        ),
+        AccountMeta::new_readonly(*authorized_withdrawer, true),
+        AccountMeta::new(pool_mint, false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
@@ -20,6 +20,7 @@ solana_program::declare_id!("SVSPxpvHdN29nkVg9rPapPNDddN5DipNLRUFhyjFThE");
 
 const POOL_PREFIX: &[u8] = b"pool";
 const POOL_STAKE_PREFIX: &[u8] = b"stake";
+const POOL_ONRAMP_PREFIX: &[u8] = b"onramp";
 const POOL_MINT_PREFIX: &[u8] = b"mint";
 const POOL_MINT_AUTHORITY_PREFIX: &[u8] = b"mint_authority";
 const POOL_STAKE_AUTHORITY_PREFIX: &[u8] = b"stake_authority";
@@ -39,6 +40,10 @@ fn find_pool_stake_address_and_bump(program_id: &Pubkey, pool_address: &Pubkey)
     Pubkey::find_program_address(&[POOL_STAKE_PREFIX, pool_address.as_ref()], program_id)
 }
 
+fn find_pool_onramp_address_and_bump(program_id: &Pubkey, pool_address: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POOL_ONRAMP_PREFIX, pool_address.as_ref()], program_id)
+}
+
 fn find_pool_mint_address_and_bump(program_id: &Pubkey, pool_address: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[POOL_MINT_PREFIX, pool_address.as_ref()], program_id)
 }
@@ -95,6 +100,11 @@ pub fn find_pool_stake_address(program_id: &Pubkey, pool_address: &Pubkey) -> Pu
     find_pool_stake_address_and_bump(program_id, pool_address).0
 }
 
+/// Find the canonical onramp stake account address for a given pool account.
+pub fn find_pool_onramp_address(program_id: &Pubkey, pool_address: &Pubkey) -> Pubkey {
+    find_pool_onramp_address_and_bump(program_id, pool_address).0
+}
+
 /// Find the canonical token mint address for a given pool account.
 pub fn find_pool_mint_address(program_id: &Pubkey, pool_address: &Pubkey) -> Pubkey {
     find_pool_mint_address_and_bump(program_id, pool_address).0
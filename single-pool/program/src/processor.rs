@@ -12,7 +12,7 @@ use {
         instruction::SinglePoolInstruction,
         state::{SinglePool, SinglePoolAccountType},
         MINT_DECIMALS, POOL_MINT_AUTHORITY_PREFIX, POOL_MINT_PREFIX, POOL_MPL_AUTHORITY_PREFIX,
-        POOL_PREFIX, POOL_STAKE_AUTHORITY_PREFIX, POOL_STAKE_PREFIX,
+        POOL_ONRAMP_PREFIX, POOL_PREFIX, POOL_STAKE_AUTHORITY_PREFIX, POOL_STAKE_PREFIX,
         VOTE_STATE_AUTHORIZED_WITHDRAWER_END, VOTE_STATE_AUTHORIZED_WITHDRAWER_START,
         VOTE_STATE_DISCRIMINATOR_END,
     },
@@ -23,7 +23,7 @@ use {
         entrypoint::ProgramResult,
         msg,
         native_token::LAMPORTS_PER_SOL,
-        program::invoke_signed,
+        program::{invoke, invoke_signed},
         program_error::ProgramError,
         program_pack::Pack,
         pubkey::Pubkey,
@@ -37,11 +37,21 @@ use {
         sysvar::{clock::Clock, Sysvar},
         vote::program as vote_program,
     },
-    spl_token::state::Mint,
+    spl_token_2022::{
+        extension::{
+            metadata_pointer::{self, MetadataPointer},
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        },
+        state::Mint,
+    },
+    spl_token_metadata_interface::{
+        instruction::{initialize as initialize_token_metadata, update_field},
+        state::{Field, TokenMetadata},
+    },
 };
 
-/// Calculate pool tokens to mint, given outstanding token supply, pool active
-/// stake, and deposit active stake
+/// Calculate pool tokens to mint, given outstanding token supply, pool value
+/// (active stake plus lamports pending in the onramp), and deposit amount
 fn calculate_deposit_amount(
     pre_token_supply: u64,
     pre_pool_stake: u64,
@@ -59,8 +69,8 @@ fn calculate_deposit_amount(
     }
 }
 
-/// Calculate pool stake to return, given outstanding token supply, pool active
-/// stake, and tokens to redeem
+/// Calculate pool stake to return, given outstanding token supply, pool value
+/// (active stake plus lamports pending in the onramp), and tokens to redeem
 fn calculate_withdraw_amount(
     pre_token_supply: u64,
     pre_pool_stake: u64,
@@ -90,6 +100,53 @@ fn get_stake_amount(stake_account_info: &AccountInfo) -> Result<u64, ProgramErro
     Ok(get_stake_state(stake_account_info)?.1.delegation.stake)
 }
 
+/// Deserialize the value held by the pool onramp account: its delegated stake,
+/// plus undelegated lamports up to the amount recorded by `DepositSol`. Any
+/// other lamports are ignored until `ReplenishPool` delegates them
+fn get_onramp_value(
+    pool: &SinglePool,
+    pool_onramp_info: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    if *pool_onramp_info.owner != stake::program::id() || pool_onramp_info.data_len() == 0 {
+        return Ok(0);
+    }
+
+    let (meta, delegated_stake) =
+        match try_from_slice_unchecked::<StakeStateV2>(&pool_onramp_info.data.borrow())? {
+            StakeStateV2::Initialized(meta) => (meta, 0),
+            StakeStateV2::Stake(meta, stake, _) => (meta, stake.delegation.stake),
+            _ => return Ok(0),
+        };
+
+    let undelegated_lamports = pool_onramp_info
+        .lamports()
+        .saturating_sub(meta.rent_exempt_reserve)
+        .saturating_sub(delegated_stake);
+
+    Ok(delegated_stake.saturating_add(undelegated_lamports.min(pool.onramp_deposited_lamports)))
+}
+
+/// Deserialize the onramp value for instructions where the onramp is a trailing
+/// optional account. It may only be omitted for pools that predate the onramp
+fn get_optional_onramp_value(
+    program_id: &Pubkey,
+    pool_info: &AccountInfo,
+    pool: &SinglePool,
+    pool_onramp_info: Option<&AccountInfo>,
+) -> Result<u64, ProgramError> {
+    match pool_onramp_info {
+        Some(pool_onramp_info) => {
+            check_pool_onramp_address(program_id, pool_info.key, pool_onramp_info.key)?;
+            get_onramp_value(pool, pool_onramp_info)
+        }
+        None if pool_info.data_len() < get_packed_len::<SinglePool>() => Ok(0),
+        None => {
+            msg!("Pool onramp account is required for this pool");
+            Err(ProgramError::NotEnoughAccountKeys)
+        }
+    }
+}
+
 /// Determine if stake is active
 fn is_stake_active_without_history(stake: &Stake, current_epoch: Epoch) -> bool {
     stake.delegation.activation_epoch < current_epoch
@@ -128,6 +185,22 @@ fn check_pool_stake_address(
     )
 }
 
+/// Check pool onramp account address for the pool account
+fn check_pool_onramp_address(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    check_address: &Pubkey,
+) -> Result<u8, ProgramError> {
+    check_pool_pda(
+        program_id,
+        pool_address,
+        check_address,
+        &crate::find_pool_onramp_address_and_bump,
+        "onramp account",
+        SinglePoolError::InvalidPoolOnRamp,
+    )
+}
+
 /// Check pool mint address for the pool account
 fn check_pool_mint_address(
     program_id: &Pubkey,
@@ -245,6 +318,58 @@ fn check_mpl_metadata_account_address(
     }
 }
 
+/// Check if the pool mint is a Token-2022 mint whose metadata pointer refers to
+/// the mint itself, meaning that token metadata is stored directly in the mint
+fn mint_holds_metadata(pool_mint_info: &AccountInfo) -> Result<bool, ProgramError> {
+    if *pool_mint_info.owner != spl_token_2022::id() {
+        return Ok(false);
+    }
+    let pool_mint_data = pool_mint_info.try_borrow_data()?;
+    let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+    let metadata_address = pool_mint
+        .get_extension::<MetadataPointer>()
+        .ok()
+        .and_then(|pointer| Option::<Pubkey>::from(pointer.metadata_address));
+    Ok(metadata_address == Some(*pool_mint_info.key))
+}
+
+/// Transfer enough lamports from the payer to keep the pool mint rent-exempt
+/// once it holds the given token metadata
+fn fund_mint_metadata_rent<'a>(
+    payer_info: &AccountInfo<'a>,
+    pool_mint_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    token_metadata: &TokenMetadata,
+) -> ProgramResult {
+    let new_account_len = {
+        let pool_mint_data = pool_mint_info.try_borrow_data()?;
+        let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+        pool_mint.try_get_new_account_len_for_variable_len_extension(token_metadata)?
+    };
+    let required_lamports = Rent::get()?.minimum_balance(new_account_len);
+    let current_lamports = pool_mint_info.lamports();
+    if required_lamports > current_lamports {
+        if !payer_info.is_signer {
+            msg!("Payer did not sign metadata rent top-up");
+            return Err(SinglePoolError::SignatureMissing.into());
+        }
+        check_system_program(system_program_info.key)?;
+        invoke(
+            &system_instruction::transfer(
+                payer_info.key,
+                pool_mint_info.key,
+                required_lamports.saturating_sub(current_lamports),
+            ),
+            &[
+                payer_info.clone(),
+                pool_mint_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
 /// Check system program address
 fn check_system_program(program_id: &Pubkey) -> Result<(), ProgramError> {
     if *program_id != system_program::id() {
@@ -259,12 +384,27 @@ fn check_system_program(program_id: &Pubkey) -> Result<(), ProgramError> {
     }
 }
 
-/// Check token program address
+/// Check token program address, which may be either SPL Token or Token-2022
 fn check_token_program(address: &Pubkey) -> Result<(), ProgramError> {
-    if *address != spl_token::id() {
+    if *address != spl_token::id() && *address != spl_token_2022::id() {
         msg!(
-            "Incorrect token program, expected {}, received {}",
+            "Incorrect token program, expected {} or {}, received {}",
             spl_token::id(),
+            spl_token_2022::id(),
+            address
+        );
+        Err(ProgramError::IncorrectProgramId)
+    } else {
+        Ok(())
+    }
+}
+
+/// Check Token-2022 program address, which holds metadata in the mint itself
+fn check_token_2022_program(address: &Pubkey) -> Result<(), ProgramError> {
+    if *address != spl_token_2022::id() {
+        msg!(
+            "Incorrect token program, expected {}, received {}",
+            spl_token_2022::id(),
             address
         );
         Err(ProgramError::IncorrectProgramId)
@@ -474,6 +614,34 @@ impl Processor {
         )
     }
 
+    fn stake_initialize<'a>(
+        pool_account_key: &Pubkey,
+        stake_account: AccountInfo<'a>,
+        stake_authority: AccountInfo<'a>,
+        bump_seed: u8,
+        rent: AccountInfo<'a>,
+    ) -> Result<(), ProgramError> {
+        let authority_seeds = &[
+            POOL_STAKE_AUTHORITY_PREFIX,
+            pool_account_key.as_ref(),
+            &[bump_seed],
+        ];
+        let signers = &[&authority_seeds[..]];
+
+        let authorized = stake::state::Authorized::auto(stake_authority.key);
+
+        invoke_signed(
+            &stake::instruction::initialize_checked(stake_account.key, &authorized),
+            &[
+                stake_account,
+                rent,
+                stake_authority.clone(),
+                stake_authority,
+            ],
+            signers,
+        )
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn token_mint_to<'a>(
         pool_account_key: &Pubkey,
@@ -491,7 +659,7 @@ impl Processor {
         ];
         let signers = &[&authority_seeds[..]];
 
-        let ix = spl_token::instruction::mint_to(
+        let ix = spl_token_2022::instruction::mint_to(
             token_program.key,
             mint.key,
             destination.key,
@@ -520,7 +688,7 @@ impl Processor {
         ];
         let signers = &[&authority_seeds[..]];
 
-        let ix = spl_token::instruction::burn(
+        let ix = spl_token_2022::instruction::burn(
             token_program.key,
             burn_account.key,
             mint.key,
@@ -626,8 +794,14 @@ impl Processor {
         pool.vote_account_address = *vote_account_info.key;
         borsh::to_writer(&mut pool_info.data.borrow_mut()[..], &pool)?;
 
-        // create the pool mint. user has already transferred in rent
-        let mint_space = spl_token::state::Mint::LEN;
+        // create the pool mint. user has already transferred in rent. token-2022
+        // mints point to themselves so that metadata can live in the mint
+        let is_token_2022 = *token_program_info.key == spl_token_2022::id();
+        let mint_space = if is_token_2022 {
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::MetadataPointer])?
+        } else {
+            Mint::LEN
+        };
 
         invoke_signed(
             &system_instruction::allocate(pool_mint_info.key, mint_space as u64),
@@ -641,8 +815,20 @@ impl Processor {
             mint_signers,
         )?;
 
+        if is_token_2022 {
+            invoke(
+                &metadata_pointer::instruction::initialize(
+                    token_program_info.key,
+                    pool_mint_info.key,
+                    None,
+                    Some(*pool_mint_info.key),
+                )?,
+                &[pool_mint_info.clone()],
+            )?;
+        }
+
         invoke_signed(
-            &spl_token::instruction::initialize_mint2(
+            &spl_token_2022::instruction::initialize_mint2(
                 token_program_info.key,
                 pool_mint_info.key,
                 pool_mint_authority_info.key,
@@ -787,8 +973,9 @@ impl Processor {
         let stake_history_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
         let stake_program_info = next_account_info(account_info_iter)?;
+        let maybe_pool_onramp_info = next_account_info(account_info_iter).ok();
 
-        SinglePool::from_account_info(pool_info, program_id)?;
+        let pool = SinglePool::from_account_info(pool_info, program_id)?;
 
         check_pool_stake_address(program_id, pool_info.key, pool_stake_info.key)?;
         let stake_authority_bump_seed = check_pool_stake_authority_address(
@@ -818,6 +1005,10 @@ impl Processor {
             .saturating_sub(minimum_delegation);
         msg!("Available stake pre merge {}", pre_pool_stake);
 
+        let pool_onramp_lamports =
+            get_optional_onramp_value(program_id, pool_info, &pool, maybe_pool_onramp_info)?;
+        msg!("Pending onramp lamports {}", pool_onramp_lamports);
+
         // user can deposit active stake into an active pool or inactive stake into an
         // activating pool
         let (user_stake_meta, user_stake_state) = get_stake_state(user_stake_info)?;
@@ -869,12 +1060,16 @@ impl Processor {
 
         let token_supply = {
             let pool_mint_data = pool_mint_info.try_borrow_data()?;
-            let pool_mint = Mint::unpack_from_slice(&pool_mint_data)?;
-            pool_mint.supply
+            let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+            pool_mint.base.supply
         };
 
-        // deposit amount is determined off stake because we return excess rent
-        let new_pool_tokens = calculate_deposit_amount(token_supply, pre_pool_stake, stake_added)
+        // deposit amount is determined off stake because we return excess rent.
+        // lamports pending in the onramp are counted so they share in the ratio
+        let pre_pool_value = pre_pool_stake
+            .checked_add(pool_onramp_lamports)
+            .ok_or(SinglePoolError::ArithmeticOverflow)?;
+        let new_pool_tokens = calculate_deposit_amount(token_supply, pre_pool_value, stake_added)
             .ok_or(SinglePoolError::UnexpectedMathError)?;
 
         if new_pool_tokens == 0 {
@@ -926,8 +1121,9 @@ impl Processor {
         let clock_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
         let stake_program_info = next_account_info(account_info_iter)?;
+        let maybe_pool_onramp_info = next_account_info(account_info_iter).ok();
 
-        SinglePool::from_account_info(pool_info, program_id)?;
+        let pool = SinglePool::from_account_info(pool_info, program_id)?;
 
         check_pool_stake_address(program_id, pool_info.key, pool_stake_info.key)?;
         let stake_authority_bump_seed = check_pool_stake_authority_address(
@@ -953,20 +1149,39 @@ impl Processor {
         let pre_pool_stake = get_stake_amount(pool_stake_info)?.saturating_sub(minimum_delegation);
         msg!("Available stake pre split {}", pre_pool_stake);
 
+        let pool_onramp_lamports =
+            get_optional_onramp_value(program_id, pool_info, &pool, maybe_pool_onramp_info)?;
+        msg!("Pending onramp lamports {}", pool_onramp_lamports);
+
         let token_supply = {
             let pool_mint_data = pool_mint_info.try_borrow_data()?;
-            let pool_mint = Mint::unpack_from_slice(&pool_mint_data)?;
-            pool_mint.supply
+            let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+            pool_mint.base.supply
         };
 
-        // withdraw amount is determined off stake just like deposit amount
-        let withdraw_stake = calculate_withdraw_amount(token_supply, pre_pool_stake, token_amount)
+        // withdraw amount is determined off stake just like deposit amount, but can
+        // only be paid out of the pool stake account
+        let pre_pool_value = pre_pool_stake
+            .checked_add(pool_onramp_lamports)
+            .ok_or(SinglePoolError::ArithmeticOverflow)?;
+        let withdraw_stake = calculate_withdraw_amount(token_supply, pre_pool_value, token_amount)
             .ok_or(SinglePoolError::UnexpectedMathError)?;
 
         if withdraw_stake == 0 {
             return Err(SinglePoolError::WithdrawalTooSmall.into());
         }
 
+        // lamports pending in the onramp are part of the pool value, but can only be
+        // paid out once the crank has merged them into the pool stake account
+        if withdraw_stake > pre_pool_stake && pool_onramp_lamports > 0 {
+            msg!(
+                "Withdrawal of {} exceeds available pool stake {}",
+                withdraw_stake,
+                pre_pool_stake
+            );
+            return Err(SinglePoolError::OnRampStakeNotMerged.into());
+        }
+
         // the second case should never be true, but its best to be sure
         if withdraw_stake > pre_pool_stake || withdraw_stake == pool_stake_info.lamports() {
             return Err(SinglePoolError::WithdrawalTooLarge.into());
@@ -1009,6 +1224,325 @@ impl Processor {
         Ok(())
     }
 
+    fn process_initialize_pool_onramp(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let pool_onramp_info = next_account_info(account_info_iter)?;
+        let pool_stake_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(rent_info)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let stake_program_info = next_account_info(account_info_iter)?;
+
+        let pool = SinglePool::from_account_info(pool_info, program_id)?;
+
+        let onramp_bump_seed =
+            check_pool_onramp_address(program_id, pool_info.key, pool_onramp_info.key)?;
+        let stake_authority_bump_seed = check_pool_stake_authority_address(
+            program_id,
+            pool_info.key,
+            pool_stake_authority_info.key,
+        )?;
+        check_system_program(system_program_info.key)?;
+        check_stake_program(stake_program_info.key)?;
+
+        let onramp_seeds = &[
+            POOL_ONRAMP_PREFIX,
+            pool_info.key.as_ref(),
+            &[onramp_bump_seed],
+        ];
+        let onramp_signers = &[&onramp_seeds[..]];
+
+        // create the onramp stake account. user has already transferred in rent
+        let stake_space = std::mem::size_of::<stake::state::StakeStateV2>();
+        if !rent.is_exempt(pool_onramp_info.lamports(), stake_space) {
+            return Err(SinglePoolError::WrongRentAmount.into());
+        }
+        if pool_onramp_info.data_len() != 0 {
+            return Err(SinglePoolError::PoolAlreadyInitialized.into());
+        }
+
+        // pools created before the onramp existed are grown to record deposits.
+        // user has already transferred in the additional rent
+        let pool_space = get_packed_len::<SinglePool>();
+        if pool_info.data_len() < pool_space {
+            if !rent.is_exempt(pool_info.lamports(), pool_space) {
+                return Err(SinglePoolError::WrongRentAmount.into());
+            }
+
+            pool_info.realloc(pool_space, true)?;
+            borsh::to_writer(&mut pool_info.data.borrow_mut()[..], &pool)?;
+        }
+
+        invoke_signed(
+            &system_instruction::allocate(pool_onramp_info.key, stake_space as u64),
+            &[pool_onramp_info.clone()],
+            onramp_signers,
+        )?;
+
+        invoke_signed(
+            &system_instruction::assign(pool_onramp_info.key, stake_program_info.key),
+            &[pool_onramp_info.clone()],
+            onramp_signers,
+        )?;
+
+        Self::stake_initialize(
+            pool_info.key,
+            pool_onramp_info.clone(),
+            pool_stake_authority_info.clone(),
+            stake_authority_bump_seed,
+            rent_info.clone(),
+        )?;
+
+        Ok(())
+    }
+
+    fn process_deposit_sol(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        lamports: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let pool_stake_info = next_account_info(account_info_iter)?;
+        let pool_onramp_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let pool_mint_authority_info = next_account_info(account_info_iter)?;
+        let user_lamport_account_info = next_account_info(account_info_iter)?;
+        let user_token_account_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let stake_program_info = next_account_info(account_info_iter)?;
+
+        let mut pool = SinglePool::from_account_info(pool_info, program_id)?;
+
+        check_pool_stake_address(program_id, pool_info.key, pool_stake_info.key)?;
+        check_pool_onramp_address(program_id, pool_info.key, pool_onramp_info.key)?;
+        check_pool_mint_address(program_id, pool_info.key, pool_mint_info.key)?;
+        let mint_authority_bump_seed = check_pool_mint_authority_address(
+            program_id,
+            pool_info.key,
+            pool_mint_authority_info.key,
+        )?;
+        check_system_program(system_program_info.key)?;
+        check_token_program(token_program_info.key)?;
+        check_stake_program(stake_program_info.key)?;
+
+        if !user_lamport_account_info.is_signer {
+            return Err(SinglePoolError::SignatureMissing.into());
+        }
+
+        // the onramp must already exist, otherwise the lamports could never be staked
+        check_account_owner(pool_onramp_info, &stake::program::id())?;
+        match try_from_slice_unchecked::<StakeStateV2>(&pool_onramp_info.data.borrow())? {
+            StakeStateV2::Initialized(_) | StakeStateV2::Stake(_, _, _) => (),
+            _ => return Err(SinglePoolError::WrongStakeStake.into()),
+        }
+
+        let minimum_delegation = minimum_delegation()?;
+
+        let pre_pool_stake = get_stake_amount(pool_stake_info)?.saturating_sub(minimum_delegation);
+        msg!("Available stake pre deposit {}", pre_pool_stake);
+
+        let pool_onramp_lamports = get_onramp_value(&pool, pool_onramp_info)?;
+        msg!(
+            "Pending onramp lamports pre deposit {}",
+            pool_onramp_lamports
+        );
+
+        let token_supply = {
+            let pool_mint_data = pool_mint_info.try_borrow_data()?;
+            let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+            pool_mint.base.supply
+        };
+
+        // pending lamports are valued the same as active stake, so that the ratio
+        // does not move when the onramp is merged into the pool stake account
+        let pre_pool_value = pre_pool_stake
+            .checked_add(pool_onramp_lamports)
+            .ok_or(SinglePoolError::ArithmeticOverflow)?;
+        let new_pool_tokens = calculate_deposit_amount(token_supply, pre_pool_value, lamports)
+            .ok_or(SinglePoolError::UnexpectedMathError)?;
+
+        if new_pool_tokens == 0 {
+            return Err(SinglePoolError::DepositTooSmall.into());
+        }
+
+        invoke(
+            &system_instruction::transfer(
+                user_lamport_account_info.key,
+                pool_onramp_info.key,
+                lamports,
+            ),
+            &[
+                user_lamport_account_info.clone(),
+                pool_onramp_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+
+        // only lamports recorded here count towards the pool value until delegated
+        pool.onramp_deposited_lamports = pool
+            .onramp_deposited_lamports
+            .checked_add(lamports)
+            .ok_or(SinglePoolError::ArithmeticOverflow)?;
+        borsh::to_writer(&mut pool_info.data.borrow_mut()[..], &pool)?;
+
+        // mint tokens to the user corresponding to their lamport deposit
+        Self::token_mint_to(
+            pool_info.key,
+            token_program_info.clone(),
+            pool_mint_info.clone(),
+            user_token_account_info.clone(),
+            pool_mint_authority_info.clone(),
+            mint_authority_bump_seed,
+            new_pool_tokens,
+        )?;
+
+        Ok(())
+    }
+
+    fn process_replenish_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let vote_account_info = next_account_info(account_info_iter)?;
+        let pool_info = next_account_info(account_info_iter)?;
+        let pool_stake_info = next_account_info(account_info_iter)?;
+        let pool_onramp_info = next_account_info(account_info_iter)?;
+        let pool_stake_authority_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(clock_info)?;
+        let stake_history_info = next_account_info(account_info_iter)?;
+        let stake_config_info = next_account_info(account_info_iter)?;
+        let stake_program_info = next_account_info(account_info_iter)?;
+
+        check_vote_account(vote_account_info)?;
+        check_pool_address(program_id, vote_account_info.key, pool_info.key)?;
+
+        let mut pool = SinglePool::from_account_info(pool_info, program_id)?;
+
+        check_pool_stake_address(program_id, pool_info.key, pool_stake_info.key)?;
+        check_pool_onramp_address(program_id, pool_info.key, pool_onramp_info.key)?;
+        let stake_authority_bump_seed = check_pool_stake_authority_address(
+            program_id,
+            pool_info.key,
+            pool_stake_authority_info.key,
+        )?;
+        check_stake_program(stake_program_info.key)?;
+        check_account_owner(pool_onramp_info, &stake::program::id())?;
+
+        let (_, pool_stake_state) = get_stake_state(pool_stake_info)?;
+        let onramp_state =
+            try_from_slice_unchecked::<StakeStateV2>(&pool_onramp_info.data.borrow())?;
+
+        let delegate_onramp = match onramp_state {
+            StakeStateV2::Initialized(_) => true,
+            StakeStateV2::Stake(_, onramp_stake, _)
+                if is_stake_active_without_history(&onramp_stake, clock.epoch) =>
+            {
+                if !is_stake_active_without_history(&pool_stake_state, clock.epoch) {
+                    msg!("Pool stake must be active to merge the onramp");
+                    return Err(SinglePoolError::WrongStakeStake.into());
+                }
+
+                Self::stake_merge(
+                    pool_info.key,
+                    pool_onramp_info.clone(),
+                    pool_stake_authority_info.clone(),
+                    stake_authority_bump_seed,
+                    pool_stake_info.clone(),
+                    clock_info.clone(),
+                    stake_history_info.clone(),
+                )?;
+
+                // the merge leaves the onramp rent, plus anything deposited while it was
+                // activating, as excess lamports on the pool stake account. these would be
+                // claimable by the next depositor, so move them into a fresh onramp
+                let (pool_stake_meta, pool_stake_state) = get_stake_state(pool_stake_info)?;
+                let excess_lamports = pool_stake_info
+                    .lamports()
+                    .checked_sub(pool_stake_state.delegation.stake)
+                    .and_then(|amount| amount.checked_sub(pool_stake_meta.rent_exempt_reserve))
+                    .ok_or(SinglePoolError::ArithmeticOverflow)?;
+                msg!(
+                    "Merged onramp, returning {} lamports to it",
+                    excess_lamports
+                );
+
+                Self::stake_withdraw(
+                    pool_info.key,
+                    pool_stake_info.clone(),
+                    pool_stake_authority_info.clone(),
+                    stake_authority_bump_seed,
+                    pool_onramp_info.clone(),
+                    clock_info.clone(),
+                    stake_history_info.clone(),
+                    excess_lamports,
+                )?;
+
+                Self::stake_initialize(
+                    pool_info.key,
+                    pool_onramp_info.clone(),
+                    pool_stake_authority_info.clone(),
+                    stake_authority_bump_seed,
+                    rent_info.clone(),
+                )?;
+
+                true
+            }
+            // restake the onramp if it was deactivated, eg by `DeactivateDelinquent`
+            StakeStateV2::Stake(_, onramp_stake, _) => {
+                onramp_stake.delegation.deactivation_epoch != Epoch::MAX
+            }
+            _ => return Err(SinglePoolError::WrongStakeStake.into()),
+        };
+
+        // anything below the minimum stays put until more is deposited
+        let pool_onramp_lamports = get_onramp_value(&pool, pool_onramp_info)?;
+        if !delegate_onramp || pool_onramp_lamports < stake::tools::get_minimum_delegation()? {
+            msg!(
+                "Onramp holds {} lamports, not delegating",
+                pool_onramp_lamports
+            );
+            return Ok(());
+        }
+
+        let stake_authority_seeds = &[
+            POOL_STAKE_AUTHORITY_PREFIX,
+            pool_info.key.as_ref(),
+            &[stake_authority_bump_seed],
+        ];
+        let stake_authority_signers = &[&stake_authority_seeds[..]];
+
+        msg!("Delegating {} onramp lamports", pool_onramp_lamports);
+        invoke_signed(
+            &stake::instruction::delegate_stake(
+                pool_onramp_info.key,
+                pool_stake_authority_info.key,
+                vote_account_info.key,
+            ),
+            &[
+                pool_onramp_info.clone(),
+                vote_account_info.clone(),
+                clock_info.clone(),
+                stake_history_info.clone(),
+                stake_config_info.clone(),
+                pool_stake_authority_info.clone(),
+            ],
+            stake_authority_signers,
+        )?;
+
+        // delegation stakes every lamport in the onramp, including any that were
+        // transferred in directly, so there is nothing left pending
+        pool.onramp_deposited_lamports = 0;
+        borsh::to_writer(&mut pool_info.data.borrow_mut()[..], &pool)?;
+
+        Ok(())
+    }
+
     fn process_create_pool_token_metadata(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
@@ -1020,7 +1554,7 @@ impl Processor {
         let pool_mpl_authority_info = next_account_info(account_info_iter)?;
         let payer_info = next_account_info(account_info_iter)?;
         let metadata_info = next_account_info(account_info_iter)?;
-        let mpl_token_metadata_program_info = next_account_info(account_info_iter)?;
+        let metadata_program_info = next_account_info(account_info_iter)?;
         let system_program_info = next_account_info(account_info_iter)?;
 
         let pool = SinglePool::from_account_info(pool_info, program_id)?;
@@ -1038,8 +1572,17 @@ impl Processor {
         check_pool_mint_address(program_id, pool_info.key, pool_mint_info.key)?;
         check_system_program(system_program_info.key)?;
         check_account_owner(payer_info, &system_program::id())?;
-        check_mpl_metadata_program(mpl_token_metadata_program_info.key)?;
-        check_mpl_metadata_account_address(metadata_info.key, pool_mint_info.key)?;
+
+        let holds_metadata = mint_holds_metadata(pool_mint_info)?;
+        if holds_metadata {
+            check_token_2022_program(metadata_program_info.key)?;
+            if metadata_info.key != pool_mint_info.key {
+                return Err(SinglePoolError::InvalidMetadataAccount.into());
+            }
+        } else {
+            check_mpl_metadata_program(metadata_program_info.key)?;
+            check_mpl_metadata_account_address(metadata_info.key, pool_mint_info.key)?;
+        }
 
         if !payer_info.is_signer {
             msg!("Payer did not sign metadata creation");
@@ -1050,18 +1593,6 @@ impl Processor {
         let token_name = format!("SPL Single Pool {}", &vote_address_str[0..15]);
         let token_symbol = format!("st{}", &vote_address_str[0..7]);
 
-        let new_metadata_instruction = create_metadata_accounts_v3(
-            *mpl_token_metadata_program_info.key,
-            *metadata_info.key,
-            *pool_mint_info.key,
-            *pool_mint_authority_info.key,
-            *payer_info.key,
-            *pool_mpl_authority_info.key,
-            token_name,
-            token_symbol,
-            "".to_string(),
-        );
-
         let mint_authority_seeds = &[
             POOL_MINT_AUTHORITY_PREFIX,
             pool_info.key.as_ref(),
@@ -1074,18 +1605,69 @@ impl Processor {
         ];
         let signers = &[&mint_authority_seeds[..], &mpl_authority_seeds[..]];
 
-        invoke_signed(
-            &new_metadata_instruction,
-            &[
-                metadata_info.clone(),
-                pool_mint_info.clone(),
-                pool_mint_authority_info.clone(),
-                payer_info.clone(),
-                pool_mpl_authority_info.clone(),
-                system_program_info.clone(),
-            ],
-            signers,
-        )?;
+        if holds_metadata {
+            // token-2022 metadata lives in the mint, updated by the same authority as mpl
+            let token_metadata = TokenMetadata {
+                name: token_name.clone(),
+                symbol: token_symbol.clone(),
+                update_authority: Some(*pool_mpl_authority_info.key).try_into()?,
+                mint: *pool_mint_info.key,
+                ..TokenMetadata::default()
+            };
+            fund_mint_metadata_rent(
+                payer_info,
+                pool_mint_info,
+                system_program_info,
+                &token_metadata,
+            )?;
+
+            let initialize_metadata_instruction = initialize_token_metadata(
+                metadata_program_info.key,
+                pool_mint_info.key,
+                pool_mpl_authority_info.key,
+                pool_mint_info.key,
+                pool_mint_authority_info.key,
+                token_name,
+                token_symbol,
+                "".to_string(),
+            );
+
+            invoke_signed(
+                &initialize_metadata_instruction,
+                &[
+                    pool_mint_info.clone(),
+                    pool_mpl_authority_info.clone(),
+                    pool_mint_authority_info.clone(),
+                    metadata_program_info.clone(),
+                ],
+                signers,
+            )?;
+        } else {
+            let new_metadata_instruction = create_metadata_accounts_v3(
+                *metadata_program_info.key,
+                *metadata_info.key,
+                *pool_mint_info.key,
+                *pool_mint_authority_info.key,
+                *payer_info.key,
+                *pool_mpl_authority_info.key,
+                token_name,
+                token_symbol,
+                "".to_string(),
+            );
+
+            invoke_signed(
+                &new_metadata_instruction,
+                &[
+                    metadata_info.clone(),
+                    pool_mint_info.clone(),
+                    pool_mint_authority_info.clone(),
+                    payer_info.clone(),
+                    pool_mpl_authority_info.clone(),
+                    system_program_info.clone(),
+                ],
+                signers,
+            )?;
+        }
 
         Ok(())
     }
@@ -1103,7 +1685,7 @@ impl Processor {
         let pool_mpl_authority_info = next_account_info(account_info_iter)?;
         let authorized_withdrawer_info = next_account_info(account_info_iter)?;
         let metadata_info = next_account_info(account_info_iter)?;
-        let mpl_token_metadata_program_info = next_account_info(account_info_iter)?;
+        let metadata_program_info = next_account_info(account_info_iter)?;
 
         check_vote_account(vote_account_info)?;
         check_pool_address(program_id, vote_account_info.key, pool_info.key)?;
@@ -1119,8 +1701,19 @@ impl Processor {
             pool_mpl_authority_info.key,
         )?;
         let pool_mint_address = crate::find_pool_mint_address(program_id, pool_info.key);
-        check_mpl_metadata_program(mpl_token_metadata_program_info.key)?;
-        check_mpl_metadata_account_address(metadata_info.key, &pool_mint_address)?;
+
+        // token-2022 pools keep their metadata in the pool mint itself
+        let holds_metadata = *metadata_info.key == pool_mint_address;
+        if holds_metadata {
+            check_token_2022_program(metadata_program_info.key)?;
+            if !mint_holds_metadata(metadata_info)? {
+                msg!("Pool mint does not point to itself for token metadata");
+                return Err(SinglePoolError::InvalidMetadataAccount.into());
+            }
+        } else {
+            check_mpl_metadata_program(metadata_program_info.key)?;
+            check_mpl_metadata_account_address(metadata_info.key, &pool_mint_address)?;
+        }
 
         // we use authorized_withdrawer to authenticate the caller controls the vote
         // account this is safer than using an authorized_voter since those keys
@@ -1142,24 +1735,6 @@ impl Processor {
             return Err(SinglePoolError::SignatureMissing.into());
         }
 
-        let update_metadata_accounts_instruction = update_metadata_accounts_v2(
-            *mpl_token_metadata_program_info.key,
-            *metadata_info.key,
-            *pool_mpl_authority_info.key,
-            None,
-            Some(DataV2 {
-                name,
-                symbol,
-                uri,
-                seller_fee_basis_points: 0,
-                creators: None,
-                collection: None,
-                uses: None,
-            }),
-            None,
-            Some(true),
-        );
-
         let mpl_authority_seeds = &[
             POOL_MPL_AUTHORITY_PREFIX,
             pool_info.key.as_ref(),
@@ -1167,11 +1742,75 @@ impl Processor {
         ];
         let signers = &[&mpl_authority_seeds[..]];
 
-        invoke_signed(
-            &update_metadata_accounts_instruction,
-            &[metadata_info.clone(), pool_mpl_authority_info.clone()],
-            signers,
-        )?;
+        if holds_metadata {
+            let mut token_metadata = {
+                let pool_mint_data = metadata_info.try_borrow_data()?;
+                let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+                pool_mint.get_variable_len_extension::<TokenMetadata>()?
+            };
+            let fields = [
+                (Field::Name, name),
+                (Field::Symbol, symbol),
+                (Field::Uri, uri),
+            ];
+            for (field, value) in fields.iter() {
+                token_metadata.update(field.clone(), value.clone());
+            }
+
+            // the payer is only required if the metadata grows
+            if let (Some(payer_info), Some(system_program_info)) =
+                (account_info_iter.next(), account_info_iter.next())
+            {
+                fund_mint_metadata_rent(
+                    payer_info,
+                    metadata_info,
+                    system_program_info,
+                    &token_metadata,
+                )?;
+            }
+
+            for (field, value) in fields {
+                invoke_signed(
+                    &update_field(
+                        metadata_program_info.key,
+                        metadata_info.key,
+                        pool_mpl_authority_info.key,
+                        field,
+                        value,
+                    ),
+                    &[
+                        metadata_info.clone(),
+                        pool_mpl_authority_info.clone(),
+                        metadata_program_info.clone(),
+                    ],
+                    signers,
+                )?;
+            }
+        } else {
+            let update_metadata_accounts_instruction = update_metadata_accounts_v2(
+                *metadata_program_info.key,
+                *metadata_info.key,
+                *pool_mpl_authority_info.key,
+                None,
+                Some(DataV2 {
+                    name,
+                    symbol,
+                    uri,
+                    seller_fee_basis_points: 0,
+                    creators: None,
+                    collection: None,
+                    uses: None,
+                }),
+                None,
+                Some(true),
+            );
+
+            invoke_signed(
+                &update_metadata_accounts_instruction,
+                &[metadata_info.clone(), pool_mpl_authority_info.clone()],
+                signers,
+            )?;
+        }
 
         Ok(())
     }
@@ -1212,6 +1851,18 @@ impl Processor {
                 msg!("Instruction: UpdateTokenMetadata");
                 Self::process_update_pool_token_metadata(program_id, accounts, name, symbol, uri)
             }
+            SinglePoolInstruction::InitializePoolOnRamp => {
+                msg!("Instruction: InitializePoolOnRamp");
+                Self::process_initialize_pool_onramp(program_id, accounts)
+            }
+            SinglePoolInstruction::DepositSol { lamports } => {
+                msg!("Instruction: DepositSol");
+                Self::process_deposit_sol(program_id, accounts, lamports)
+            }
+            SinglePoolInstruction::ReplenishPool => {
+                msg!("Instruction: ReplenishPool");
+                Self::process_replenish_pool(program_id, accounts)
+            }
         }
     }
 }
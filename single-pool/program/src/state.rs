@@ -4,7 +4,9 @@ use {
     crate::{error::SinglePoolError, find_pool_address},
     borsh::{BorshDeserialize, BorshSchema, BorshSerialize},
     solana_program::{
-        account_info::AccountInfo, borsh1::try_from_slice_unchecked, program_error::ProgramError,
+        account_info::AccountInfo,
+        borsh1::{get_packed_len, try_from_slice_unchecked},
+        program_error::ProgramError,
         pubkey::Pubkey,
     },
 };
@@ -26,8 +28,14 @@ pub struct SinglePool {
     pub account_type: SinglePoolAccountType,
     /// The vote account this pool is mapped to
     pub vote_account_address: Pubkey,
+    /// Lamports deposited into the onramp with `DepositSol` that have not yet
+    /// been delegated by `ReplenishPool`
+    pub onramp_deposited_lamports: u64,
 }
 impl SinglePool {
+    /// Size of a pool account created before onramp deposits were tracked
+    pub const LEGACY_LEN: usize = 33;
+
     /// Create a SinglePool struct from its account info
     pub fn from_account_info(
         account_info: &AccountInfo,
@@ -38,7 +46,19 @@ impl SinglePool {
             return Err(SinglePoolError::InvalidPoolAccount.into());
         }
 
-        let pool = try_from_slice_unchecked::<SinglePool>(&account_info.data.borrow())?;
+        // pools created before the onramp existed lack the trailing deposit
+        // counter, which is read as zero until the account is reallocated
+        let pool = {
+            let data = account_info.data.borrow();
+            let pool_len = get_packed_len::<SinglePool>();
+            if data.len() < pool_len {
+                let mut padded = data.to_vec();
+                padded.resize(pool_len, 0);
+                try_from_slice_unchecked::<SinglePool>(&padded)?
+            } else {
+                try_from_slice_unchecked::<SinglePool>(&data)?
+            }
+        };
 
         // pool is well-typed
         if pool.account_type != SinglePoolAccountType::Pool {
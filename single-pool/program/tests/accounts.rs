@@ -180,6 +180,8 @@ async fn fail_account_checks(test_mode: TestMode) {
             check_error(e, SinglePoolError::InvalidPoolAccount)
         } else if prev_pubkey == accounts.stake_account {
             check_error(e, SinglePoolError::InvalidPoolStakeAccount)
+        } else if prev_pubkey == accounts.onramp_account {
+            check_error(e, SinglePoolError::InvalidPoolOnRamp)
         } else if prev_pubkey == accounts.stake_authority {
             check_error(e, SinglePoolError::InvalidPoolStakeAuthority)
         } else if prev_pubkey == accounts.mint_authority {
@@ -237,6 +239,19 @@ fn make_basic_instruction(
             "".to_string(),
             "".to_string(),
         ),
+        SinglePoolInstruction::InitializePoolOnRamp => {
+            instruction::initialize_pool_onramp(&id(), &accounts.pool)
+        }
+        SinglePoolInstruction::DepositSol { .. } => instruction::deposit_sol(
+            &id(),
+            &accounts.pool,
+            &Pubkey::default(),
+            &Pubkey::default(),
+            0,
+        ),
+        SinglePoolInstruction::ReplenishPool => {
+            instruction::replenish_pool(&id(), &accounts.vote_account.pubkey())
+        }
     }
 }
 
@@ -258,6 +273,7 @@ fn consistent_account_order() {
         accounts.vote_account.pubkey(),
         accounts.pool,
         accounts.stake_account,
+        accounts.onramp_account,
         accounts.mint,
         accounts.stake_authority,
         accounts.mint_authority,
@@ -284,17 +300,23 @@ fn consistent_account_order() {
                 uri: "".to_string(),
             },
         ),
+        make_basic_instruction(&accounts, SinglePoolInstruction::InitializePoolOnRamp),
+        make_basic_instruction(&accounts, SinglePoolInstruction::DepositSol { lamports: 0 }),
+        make_basic_instruction(&accounts, SinglePoolInstruction::ReplenishPool),
     ];
 
     for instruction in instructions {
         let mut indexes = vec![];
 
+        // deposit and withdraw take the onramp as a trailing optional account so
+        // their original layouts keep working, which exempts it from the order
+        let metas = match instruction.accounts.split_last() {
+            Some((last, metas)) if last.pubkey == accounts.onramp_account => metas,
+            _ => &instruction.accounts[..],
+        };
+
         for target in &ordering {
-            if let Some(i) = instruction
-                .accounts
-                .iter()
-                .position(|meta| meta.pubkey == *target)
-            {
+            if let Some(i) = metas.iter().position(|meta| meta.pubkey == *target) {
                 indexes.push(i);
             }
         }
@@ -0,0 +1,580 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program_test::*,
+    solana_sdk::{
+        borsh1::{get_packed_len, try_from_slice_unchecked},
+        clock::Clock,
+        instruction::Instruction,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        signature::Signer,
+        signer::keypair::Keypair,
+        transaction::Transaction,
+    },
+    spl_single_pool::{error::SinglePoolError, id, instruction, state::SinglePool},
+    test_case::test_case,
+};
+
+async fn deposit_sol(
+    context: &mut ProgramTestContext,
+    accounts: &SinglePoolAccounts,
+    user: &Keypair,
+    user_token: &Pubkey,
+    lamports: u64,
+) -> Result<(), BanksClientError> {
+    let instruction =
+        instruction::deposit_sol(&id(), &accounts.pool, &user.pubkey(), user_token, lamports);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, user],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(transaction).await
+}
+
+async fn get_pool(context: &mut ProgramTestContext, accounts: &SinglePoolAccounts) -> SinglePool {
+    let pool_account = get_account(&mut context.banks_client, &accounts.pool).await;
+    try_from_slice_unchecked::<SinglePool>(&pool_account.data).unwrap()
+}
+
+// shrink the pool account and remove the onramp, as for a pool created before
+// onramps existed
+async fn make_legacy_pool(context: &mut ProgramTestContext, accounts: &SinglePoolAccounts) {
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let mut pool_account = get_account(&mut context.banks_client, &accounts.pool).await;
+    pool_account.data.truncate(SinglePool::LEGACY_LEN);
+    pool_account.lamports = rent.minimum_balance(SinglePool::LEGACY_LEN);
+    context.set_account(&accounts.pool, &pool_account.into());
+
+    let mut onramp_account = get_account(&mut context.banks_client, &accounts.onramp_account).await;
+    onramp_account.lamports = 0;
+    onramp_account.data = vec![];
+    onramp_account.owner = solana_sdk::system_program::id();
+    context.set_account(&accounts.onramp_account, &onramp_account.into());
+}
+
+async fn replenish(context: &mut ProgramTestContext, accounts: &SinglePoolAccounts) {
+    let instruction = instruction::replenish_pool(&id(), &accounts.vote_account.pubkey());
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[test_case(true; "activated")]
+#[test_case(false; "activating")]
+#[tokio::test]
+async fn success(activate: bool) {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::default();
+    accounts.initialize(&mut context).await;
+
+    if activate {
+        advance_epoch(&mut context).await;
+    }
+
+    let stake_rent = get_stake_account_rent(&mut context.banks_client).await;
+    let (_, onramp_stake, onramp_lamports) =
+        get_stake_account(&mut context.banks_client, &accounts.onramp_account).await;
+    assert_eq!(onramp_stake, None);
+    assert_eq!(onramp_lamports, stake_rent);
+
+    // first deposit is 1:1
+    deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.alice,
+        &accounts.alice_token,
+        TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    let alice_tokens = get_token_balance(&mut context.banks_client, &accounts.alice_token).await;
+    assert_eq!(alice_tokens, TEST_STAKE_AMOUNT);
+
+    // second deposit is priced against the pending lamports
+    deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.bob,
+        &accounts.bob_token,
+        TEST_STAKE_AMOUNT / 2,
+    )
+    .await
+    .unwrap();
+
+    let bob_tokens = get_token_balance(&mut context.banks_client, &accounts.bob_token).await;
+    assert_eq!(bob_tokens, TEST_STAKE_AMOUNT / 2);
+
+    let pool = get_pool(&mut context, &accounts).await;
+    assert_eq!(
+        pool.onramp_deposited_lamports,
+        TEST_STAKE_AMOUNT + TEST_STAKE_AMOUNT / 2
+    );
+
+    let (_, _, onramp_lamports) =
+        get_stake_account(&mut context.banks_client, &accounts.onramp_account).await;
+    assert_eq!(
+        onramp_lamports,
+        stake_rent + TEST_STAKE_AMOUNT + TEST_STAKE_AMOUNT / 2
+    );
+
+    // pool stake is untouched until the onramp is merged
+    let (_, pool_stake, _) =
+        get_stake_account(&mut context.banks_client, &accounts.stake_account).await;
+    let minimum_delegation = get_pool_minimum_delegation(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+    )
+    .await;
+    assert_eq!(pool_stake.unwrap().delegation.stake, minimum_delegation);
+}
+
+#[tokio::test]
+async fn success_replenish_and_withdraw() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::default();
+    let minimum_delegation = accounts.initialize(&mut context).await;
+    advance_epoch(&mut context).await;
+
+    let stake_rent = get_stake_account_rent(&mut context.banks_client).await;
+
+    deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.alice,
+        &accounts.alice_token,
+        TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    // crank delegates the onramp
+    replenish(&mut context, &accounts).await;
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let (_, onramp_stake, _) =
+        get_stake_account(&mut context.banks_client, &accounts.onramp_account).await;
+    let onramp_stake = onramp_stake.unwrap();
+    assert_eq!(onramp_stake.delegation.stake, TEST_STAKE_AMOUNT);
+    assert_eq!(onramp_stake.delegation.activation_epoch, clock.epoch);
+    assert_eq!(
+        get_pool(&mut context, &accounts)
+            .await
+            .onramp_deposited_lamports,
+        0
+    );
+
+    // deposits while the onramp is activating are held as undelegated lamports
+    deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.bob,
+        &accounts.bob_token,
+        TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    let bob_tokens = get_token_balance(&mut context.banks_client, &accounts.bob_token).await;
+    assert_eq!(bob_tokens, TEST_STAKE_AMOUNT);
+
+    // cranking an activating onramp does nothing
+    replenish(&mut context, &accounts).await;
+    let (_, onramp_stake, onramp_lamports) =
+        get_stake_account(&mut context.banks_client, &accounts.onramp_account).await;
+    assert_eq!(onramp_stake.unwrap().delegation.stake, TEST_STAKE_AMOUNT);
+    assert_eq!(onramp_lamports, stake_rent + TEST_STAKE_AMOUNT * 2);
+
+    advance_epoch(&mut context).await;
+
+    // crank merges the active onramp and delegates a fresh one with the rest
+    replenish(&mut context, &accounts).await;
+
+    let (_, pool_stake, pool_lamports) =
+        get_stake_account(&mut context.banks_client, &accounts.stake_account).await;
+    assert_eq!(
+        pool_stake.unwrap().delegation.stake,
+        minimum_delegation + TEST_STAKE_AMOUNT
+    );
+    assert_eq!(
+        pool_lamports,
+        stake_rent + minimum_delegation + TEST_STAKE_AMOUNT
+    );
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let (onramp_meta, onramp_stake, onramp_lamports) =
+        get_stake_account(&mut context.banks_client, &accounts.onramp_account).await;
+    let onramp_stake = onramp_stake.unwrap();
+    assert_eq!(onramp_meta.authorized.staker, accounts.stake_authority);
+    assert_eq!(onramp_meta.authorized.withdrawer, accounts.stake_authority);
+    assert_eq!(onramp_stake.delegation.stake, TEST_STAKE_AMOUNT);
+    assert_eq!(onramp_stake.delegation.activation_epoch, clock.epoch);
+    assert_eq!(onramp_lamports, stake_rent + TEST_STAKE_AMOUNT);
+
+    // alice redeems her share at the same ratio, paid from the pool stake
+    create_blank_stake_account(
+        &mut context.banks_client,
+        &context.payer,
+        &accounts.alice,
+        &context.last_blockhash,
+        &accounts.alice_stake,
+    )
+    .await;
+
+    let instructions = instruction::withdraw(
+        &id(),
+        &accounts.pool,
+        &accounts.alice_stake.pubkey(),
+        &accounts.alice.pubkey(),
+        &accounts.alice_token,
+        &accounts.alice.pubkey(),
+        TEST_STAKE_AMOUNT,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &accounts.alice],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (_, alice_stake, _) =
+        get_stake_account(&mut context.banks_client, &accounts.alice_stake.pubkey()).await;
+    assert_eq!(alice_stake.unwrap().delegation.stake, TEST_STAKE_AMOUNT);
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, &accounts.alice_token).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn fail_withdraw_pending_onramp() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::default();
+    accounts
+        .initialize_for_withdraw(&mut context, TEST_STAKE_AMOUNT, None, true)
+        .await;
+
+    // a large liquid deposit leaves most of the pool value in the onramp
+    deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.bob,
+        &accounts.bob_token,
+        TEST_STAKE_AMOUNT * 10,
+    )
+    .await
+    .unwrap();
+
+    create_blank_stake_account(
+        &mut context.banks_client,
+        &context.payer,
+        &accounts.bob,
+        &context.last_blockhash,
+        &accounts.bob_stake,
+    )
+    .await;
+
+    // bob's share is worth more than the pool stake account holds
+    let instructions = instruction::withdraw(
+        &id(),
+        &accounts.pool,
+        &accounts.bob_stake.pubkey(),
+        &accounts.bob.pubkey(),
+        &accounts.bob_token,
+        &accounts.bob.pubkey(),
+        TEST_STAKE_AMOUNT * 10,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &accounts.bob],
+        context.last_blockhash,
+    );
+
+    let e = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err();
+    check_error(e, SinglePoolError::OnRampStakeNotMerged);
+
+    // alice's share is still paid in full from the pool stake
+    let instructions = instruction::withdraw(
+        &id(),
+        &accounts.pool,
+        &accounts.alice_stake.pubkey(),
+        &accounts.alice.pubkey(),
+        &accounts.alice_token,
+        &accounts.alice.pubkey(),
+        TEST_STAKE_AMOUNT,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &accounts.alice],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (_, alice_stake, _) =
+        get_stake_account(&mut context.banks_client, &accounts.alice_stake.pubkey()).await;
+    assert_eq!(alice_stake.unwrap().delegation.stake, TEST_STAKE_AMOUNT);
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, &accounts.bob_token).await,
+        TEST_STAKE_AMOUNT * 10
+    );
+}
+
+#[tokio::test]
+async fn success_create_onramp() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::default();
+    accounts.initialize(&mut context).await;
+
+    make_legacy_pool(&mut context, &accounts).await;
+
+    let e = deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.alice,
+        &accounts.alice_token,
+        TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap_err();
+    check_error(e, ProgramError::IncorrectProgramId);
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let instructions =
+        instruction::create_pool_onramp(&id(), &accounts.pool, &context.payer.pubkey(), &rent);
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    refresh_blockhash(&mut context).await;
+    deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.alice,
+        &accounts.alice_token,
+        TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    // the pool account grew to record the deposit
+    let pool_account = get_account(&mut context.banks_client, &accounts.pool).await;
+    assert_eq!(pool_account.data.len(), get_packed_len::<SinglePool>());
+    let pool = get_pool(&mut context, &accounts).await;
+    assert_eq!(pool.vote_account_address, accounts.vote_account.pubkey());
+    assert_eq!(pool.onramp_deposited_lamports, TEST_STAKE_AMOUNT);
+
+    // the onramp cannot be created twice
+    refresh_blockhash(&mut context).await;
+    let instructions =
+        instruction::create_pool_onramp(&id(), &accounts.pool, &context.payer.pubkey(), &rent);
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let e = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err();
+    check_error(e, SinglePoolError::PoolAlreadyInitialized);
+}
+
+#[tokio::test]
+async fn success_ignore_onramp_donation() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::default();
+    accounts.initialize(&mut context).await;
+
+    deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.alice,
+        &accounts.alice_token,
+        TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    // lamports sent straight to the onramp do not move the ratio
+    transfer(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &accounts.onramp_account,
+        TEST_STAKE_AMOUNT * 10,
+    )
+    .await;
+
+    deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.bob,
+        &accounts.bob_token,
+        TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    let bob_tokens = get_token_balance(&mut context.banks_client, &accounts.bob_token).await;
+    assert_eq!(bob_tokens, TEST_STAKE_AMOUNT);
+
+    // until the crank delegates them along with the deposits
+    replenish(&mut context, &accounts).await;
+
+    let (_, onramp_stake, _) =
+        get_stake_account(&mut context.banks_client, &accounts.onramp_account).await;
+    assert_eq!(
+        onramp_stake.unwrap().delegation.stake,
+        TEST_STAKE_AMOUNT * 12
+    );
+    assert_eq!(
+        get_pool(&mut context, &accounts)
+            .await
+            .onramp_deposited_lamports,
+        0
+    );
+}
+
+#[test_case(true; "legacy_pool")]
+#[test_case(false; "onramp_pool")]
+#[tokio::test]
+async fn deposit_stake_without_onramp(legacy_pool: bool) {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::default();
+    accounts
+        .initialize_for_deposit(&mut context, TEST_STAKE_AMOUNT, None)
+        .await;
+
+    if legacy_pool {
+        make_legacy_pool(&mut context, &accounts).await;
+    }
+
+    // the pool is activating, so the whole stake account is added to stake
+    let (_, _, alice_stake_lamports) =
+        get_stake_account(&mut context.banks_client, &accounts.alice_stake.pubkey()).await;
+
+    // clients built before the onramp existed omit the trailing account
+    let mut instructions = instruction::deposit(
+        &id(),
+        &accounts.pool,
+        &accounts.alice_stake.pubkey(),
+        &accounts.alice_token,
+        &accounts.alice.pubkey(),
+        &accounts.alice.pubkey(),
+    );
+    let onramp_account = instructions[2].accounts.pop().unwrap();
+    assert_eq!(onramp_account.pubkey, accounts.onramp_account);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&accounts.alice.pubkey()),
+        &[&accounts.alice],
+        context.last_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+
+    if legacy_pool {
+        result.unwrap();
+        let alice_tokens =
+            get_token_balance(&mut context.banks_client, &accounts.alice_token).await;
+        assert_eq!(alice_tokens, alice_stake_lamports);
+    } else {
+        check_error(result.unwrap_err(), ProgramError::NotEnoughAccountKeys);
+    }
+}
+
+#[tokio::test]
+async fn fail_deposit_too_small() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::default();
+    accounts.initialize(&mut context).await;
+
+    let e = deposit_sol(
+        &mut context,
+        &accounts,
+        &accounts.alice,
+        &accounts.alice_token,
+        0,
+    )
+    .await
+    .unwrap_err();
+    check_error(e, SinglePoolError::DepositTooSmall);
+}
+
+#[tokio::test]
+async fn fail_wrong_onramp() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::default();
+    accounts.initialize(&mut context).await;
+
+    let mut instruction: Instruction = instruction::deposit_sol(
+        &id(),
+        &accounts.pool,
+        &accounts.alice.pubkey(),
+        &accounts.alice_token,
+        TEST_STAKE_AMOUNT,
+    );
+    instruction.accounts[2].pubkey = accounts.stake_account;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &accounts.alice],
+        context.last_blockhash,
+    );
+
+    let e = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err();
+    check_error(e, SinglePoolError::InvalidPoolOnRamp);
+}
@@ -20,7 +20,7 @@ use {
     spl_associated_token_account as atoken,
     spl_single_pool::{
         find_pool_address, find_pool_mint_address, find_pool_mint_authority_address,
-        find_pool_mpl_authority_address, find_pool_stake_address,
+        find_pool_mpl_authority_address, find_pool_onramp_address, find_pool_stake_address,
         find_pool_stake_authority_address, id, inline_mpl_token_metadata, instruction,
         processor::Processor,
     },
@@ -40,6 +40,11 @@ pub fn program_test(enable_minimum_delegation: bool) -> ProgramTest {
 
     program_test.add_program("mpl_token_metadata", inline_mpl_token_metadata::id(), None);
     program_test.add_program("spl_single_pool", id(), processor!(Processor::process));
+    program_test.add_program(
+        "spl_token_2022",
+        spl_token_2022::id(),
+        processor!(spl_token_2022::processor::Processor::process),
+    );
     program_test.prefer_bpf(false);
 
     if !enable_minimum_delegation {
@@ -57,6 +62,7 @@ pub struct SinglePoolAccounts {
     pub vote_account: Keypair,
     pub pool: Pubkey,
     pub stake_account: Pubkey,
+    pub onramp_account: Pubkey,
     pub mint: Pubkey,
     pub stake_authority: Pubkey,
     pub mint_authority: Pubkey,
@@ -70,6 +76,43 @@ pub struct SinglePoolAccounts {
     pub token_program_id: Pubkey,
 }
 impl SinglePoolAccounts {
+    pub fn new_with_token_program(token_program_id: Pubkey) -> Self {
+        let vote_account = Keypair::new();
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+        let pool = find_pool_address(&id(), &vote_account.pubkey());
+        let mint = find_pool_mint_address(&id(), &pool);
+
+        Self {
+            validator: Keypair::new(),
+            voter: Keypair::new(),
+            withdrawer: Keypair::new(),
+            stake_account: find_pool_stake_address(&id(), &pool),
+            onramp_account: find_pool_onramp_address(&id(), &pool),
+            pool,
+            mint,
+            stake_authority: find_pool_stake_authority_address(&id(), &pool),
+            mint_authority: find_pool_mint_authority_address(&id(), &pool),
+            mpl_authority: find_pool_mpl_authority_address(&id(), &pool),
+            vote_account,
+            alice_stake: Keypair::new(),
+            bob_stake: Keypair::new(),
+            alice_token: atoken::get_associated_token_address_with_program_id(
+                &alice.pubkey(),
+                &mint,
+                &token_program_id,
+            ),
+            bob_token: atoken::get_associated_token_address_with_program_id(
+                &bob.pubkey(),
+                &mint,
+                &token_program_id,
+            ),
+            alice,
+            bob,
+            token_program_id,
+        }
+    }
+
     // does everything in initialize_for_deposit plus performs the deposit(s) and
     // creates blank account(s) optionally advances to activation before the
     // deposit
@@ -88,13 +131,14 @@ impl SinglePoolAccounts {
             advance_epoch(context).await;
         }
 
-        let instructions = instruction::deposit(
+        let instructions = instruction::deposit_with_token_program(
             &id(),
             &self.pool,
             &self.alice_stake.pubkey(),
             &self.alice_token,
             &self.alice.pubkey(),
             &self.alice.pubkey(),
+            &self.token_program_id,
         );
         let transaction = Transaction::new_signed_with_payer(
             &instructions,
@@ -119,13 +163,14 @@ impl SinglePoolAccounts {
         .await;
 
         if maybe_bob_amount.is_some() {
-            let instructions = instruction::deposit(
+            let instructions = instruction::deposit_with_token_program(
                 &id(),
                 &self.pool,
                 &self.bob_stake.pubkey(),
                 &self.bob_token,
                 &self.bob.pubkey(),
                 &self.bob.pubkey(),
+                &self.token_program_id,
             );
             let transaction = Transaction::new_signed_with_payer(
                 &instructions,
@@ -239,12 +284,13 @@ impl SinglePoolAccounts {
         )
         .await;
 
-        let instructions = instruction::initialize(
+        let instructions = instruction::initialize_with_token_program(
             &id(),
             &self.vote_account.pubkey(),
             &context.payer.pubkey(),
             &rent,
             minimum_delegation,
+            &self.token_program_id,
         );
         let transaction = Transaction::new_signed_with_payer(
             &instructions,
@@ -283,6 +329,7 @@ impl SinglePoolAccounts {
             &self.alice.pubkey(),
             &context.last_blockhash,
             &self.mint,
+            &self.token_program_id,
         )
         .await;
 
@@ -292,6 +339,7 @@ impl SinglePoolAccounts {
             &self.bob.pubkey(),
             &context.last_blockhash,
             &self.mint,
+            &self.token_program_id,
         )
         .await;
 
@@ -300,31 +348,7 @@ impl SinglePoolAccounts {
 }
 impl Default for SinglePoolAccounts {
     fn default() -> Self {
-        let vote_account = Keypair::new();
-        let alice = Keypair::new();
-        let bob = Keypair::new();
-        let pool = find_pool_address(&id(), &vote_account.pubkey());
-        let mint = find_pool_mint_address(&id(), &pool);
-
-        Self {
-            validator: Keypair::new(),
-            voter: Keypair::new(),
-            withdrawer: Keypair::new(),
-            stake_account: find_pool_stake_address(&id(), &pool),
-            pool,
-            mint,
-            stake_authority: find_pool_stake_authority_address(&id(), &pool),
-            mint_authority: find_pool_mint_authority_address(&id(), &pool),
-            mpl_authority: find_pool_mpl_authority_address(&id(), &pool),
-            vote_account,
-            alice_stake: Keypair::new(),
-            bob_stake: Keypair::new(),
-            alice_token: atoken::get_associated_token_address(&alice.pubkey(), &mint),
-            bob_token: atoken::get_associated_token_address(&bob.pubkey(), &mint),
-            alice,
-            bob,
-            token_program_id: spl_token::id(),
-        }
+        Self::new_with_token_program(spl_token::id())
     }
 }
 
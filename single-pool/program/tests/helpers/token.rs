@@ -6,14 +6,16 @@ use {
     solana_sdk::{
         borsh1::try_from_slice_unchecked,
         hash::Hash,
-        program_pack::Pack,
         pubkey::Pubkey,
         signature::{Keypair, Signer},
         transaction::Transaction,
     },
     spl_associated_token_account as atoken,
     spl_single_pool::inline_mpl_token_metadata::pda::find_metadata_account,
-    spl_token::state::{Account, Mint},
+    spl_token_2022::{
+        extension::StateWithExtensions,
+        state::{Account, Mint},
+    },
 };
 
 pub async fn create_ata(
@@ -22,12 +24,13 @@ pub async fn create_ata(
     owner: &Pubkey,
     recent_blockhash: &Hash,
     pool_mint: &Pubkey,
+    token_program_id: &Pubkey,
 ) {
     let instruction = atoken::instruction::create_associated_token_account(
         &payer.pubkey(),
         owner,
         pool_mint,
-        &spl_token::id(),
+        token_program_id,
     );
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -41,14 +44,14 @@ pub async fn create_ata(
 
 pub async fn get_token_balance(banks_client: &mut BanksClient, token: &Pubkey) -> u64 {
     let token_account = banks_client.get_account(*token).await.unwrap().unwrap();
-    let account_info = Account::unpack_from_slice(&token_account.data).unwrap();
-    account_info.amount
+    let account_info = StateWithExtensions::<Account>::unpack(&token_account.data).unwrap();
+    account_info.base.amount
 }
 
 pub async fn get_token_supply(banks_client: &mut BanksClient, mint: &Pubkey) -> u64 {
     let mint_account = banks_client.get_account(*mint).await.unwrap().unwrap();
-    let account_info = Mint::unpack_from_slice(&mint_account.data).unwrap();
-    account_info.supply
+    let account_info = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+    account_info.base.supply
 }
 
 #[derive(Clone, BorshDeserialize, Debug, PartialEq, Eq)]
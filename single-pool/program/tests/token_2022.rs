@@ -0,0 +1,204 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program_test::*,
+    solana_sdk::{pubkey::Pubkey, signature::Signer, transaction::Transaction},
+    spl_single_pool::{error::SinglePoolError, id, instruction},
+    spl_token_2022::{
+        extension::{
+            metadata_pointer::MetadataPointer, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Mint,
+    },
+    spl_token_metadata_interface::state::TokenMetadata,
+};
+
+const UPDATED_NAME: &str = "updated_name";
+const UPDATED_SYMBOL: &str = "USYM";
+const UPDATED_URI: &str = "https://example.com/updated_uri.json";
+
+async fn get_token_metadata(banks_client: &mut BanksClient, mint: &Pubkey) -> TokenMetadata {
+    let mint_account = get_account(banks_client, mint).await;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+    mint.get_variable_len_extension::<TokenMetadata>().unwrap()
+}
+
+#[tokio::test]
+async fn success_initialize() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::new_with_token_program(spl_token_2022::id());
+    accounts.initialize(&mut context).await;
+
+    // mint is owned by token-2022 and points to itself for metadata
+    let mint_account = get_account(&mut context.banks_client, &accounts.mint).await;
+    assert_eq!(mint_account.owner, spl_token_2022::id());
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+    let metadata_pointer = mint.get_extension::<MetadataPointer>().unwrap();
+    assert_eq!(
+        Option::<Pubkey>::from(metadata_pointer.metadata_address),
+        Some(accounts.mint)
+    );
+    assert_eq!(Option::<Pubkey>::from(metadata_pointer.authority), None);
+
+    // default metadata is stored in the mint, controlled by the mpl authority
+    let metadata = get_token_metadata(&mut context.banks_client, &accounts.mint).await;
+    let vote_address_str = accounts.vote_account.pubkey().to_string();
+    assert_eq!(
+        metadata.name,
+        format!("SPL Single Pool {}", &vote_address_str[0..15])
+    );
+    assert_eq!(metadata.symbol, format!("st{}", &vote_address_str[0..7]));
+    assert_eq!(metadata.mint, accounts.mint);
+    assert_eq!(
+        Option::<Pubkey>::from(metadata.update_authority),
+        Some(accounts.mpl_authority)
+    );
+}
+
+#[tokio::test]
+async fn success_deposit_withdraw() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::new_with_token_program(spl_token_2022::id());
+    accounts
+        .initialize_for_withdraw(&mut context, TEST_STAKE_AMOUNT, None, true)
+        .await;
+
+    let token_amount = get_token_balance(&mut context.banks_client, &accounts.alice_token).await;
+    assert_eq!(token_amount, TEST_STAKE_AMOUNT);
+    assert_eq!(
+        get_token_supply(&mut context.banks_client, &accounts.mint).await,
+        TEST_STAKE_AMOUNT
+    );
+
+    let instructions = instruction::withdraw_with_token_program(
+        &id(),
+        &accounts.pool,
+        &accounts.alice_stake.pubkey(),
+        &accounts.alice.pubkey(),
+        &accounts.alice_token,
+        &accounts.alice.pubkey(),
+        token_amount,
+        &spl_token_2022::id(),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &accounts.alice],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (_, alice_stake, _) =
+        get_stake_account(&mut context.banks_client, &accounts.alice_stake.pubkey()).await;
+    assert_eq!(alice_stake.unwrap().delegation.stake, TEST_STAKE_AMOUNT);
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, &accounts.alice_token).await,
+        0
+    );
+    assert_eq!(
+        get_token_supply(&mut context.banks_client, &accounts.mint).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn success_update_pool_token_metadata() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::new_with_token_program(spl_token_2022::id());
+    accounts.initialize(&mut context).await;
+
+    // the uri grows from empty, so the payer tops up the mint rent
+    let instruction = instruction::update_token_2022_metadata(
+        &id(),
+        &accounts.vote_account.pubkey(),
+        &accounts.withdrawer.pubkey(),
+        &context.payer.pubkey(),
+        UPDATED_NAME.to_string(),
+        UPDATED_SYMBOL.to_string(),
+        UPDATED_URI.to_string(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &accounts.withdrawer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let metadata = get_token_metadata(&mut context.banks_client, &accounts.mint).await;
+    assert_eq!(metadata.name, UPDATED_NAME);
+    assert_eq!(metadata.symbol, UPDATED_SYMBOL);
+    assert_eq!(metadata.uri, UPDATED_URI);
+}
+
+#[tokio::test]
+async fn fail_update_wrong_withdrawer() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::new_with_token_program(spl_token_2022::id());
+    accounts.initialize(&mut context).await;
+
+    let instruction = instruction::update_token_2022_metadata(
+        &id(),
+        &accounts.vote_account.pubkey(),
+        &accounts.voter.pubkey(),
+        &context.payer.pubkey(),
+        UPDATED_NAME.to_string(),
+        UPDATED_SYMBOL.to_string(),
+        UPDATED_URI.to_string(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &accounts.voter],
+        context.last_blockhash,
+    );
+
+    let e = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err();
+    check_error(e, SinglePoolError::InvalidMetadataSigner);
+}
+
+#[tokio::test]
+async fn fail_create_metadata_wrong_account() {
+    let mut context = program_test(false).start_with_context().await;
+    let accounts = SinglePoolAccounts::new_with_token_program(spl_token_2022::id());
+    accounts.initialize(&mut context).await;
+    refresh_blockhash(&mut context).await;
+
+    let mut instruction =
+        instruction::create_token_2022_metadata(&id(), &accounts.pool, &context.payer.pubkey());
+    instruction.accounts[5].pubkey = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let e = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err();
+    check_error(e, SinglePoolError::InvalidMetadataAccount);
+}
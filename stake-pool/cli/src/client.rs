@@ -1,5 +1,4 @@
 use {
-    bincode::deserialize,
     solana_account_decoder::UiAccountEncoding,
     solana_client::{
         client_error::ClientError,
@@ -8,9 +7,13 @@ use {
         rpc_filter::{Memcmp, RpcFilterType},
     },
     solana_program::{borsh1::try_from_slice_unchecked, program_pack::Pack, pubkey::Pubkey, stake},
+    spl_stake_client::state::{deserialize_stake_state, AUTHORIZED_STAKER_OFFSET},
     spl_stake_pool::{
-        find_withdraw_authority_program_address,
-        state::{StakePool, ValidatorList},
+        find_validator_policy_program_address, find_withdraw_authority_program_address,
+        state::{StakePool, ValidatorList, ValidatorPolicy},
+    },
+    spl_token_2022::extension::{
+        metadata_pointer::MetadataPointer, BaseStateWithExtensions, StateWithExtensions,
     },
     std::collections::HashSet,
 };
@@ -37,6 +40,32 @@ pub fn get_validator_list(
     Ok(validator_list)
 }
 
+pub(crate) fn get_validator_policy(
+    rpc_client: &RpcClient,
+    stake_pool_address: &Pubkey,
+) -> Result<Option<ValidatorPolicy>, Error> {
+    let (validator_policy_address, _) =
+        find_validator_policy_program_address(&spl_stake_pool::id(), stake_pool_address);
+    let maybe_account = rpc_client
+        .get_account_with_commitment(&validator_policy_address, rpc_client.commitment())?
+        .value;
+    match maybe_account {
+        Some(account) if account.owner == spl_stake_pool::id() => {
+            let validator_policy = try_from_slice_unchecked::<ValidatorPolicy>(
+                account.data.as_slice(),
+            )
+            .map_err(|err| {
+                format!(
+                    "Invalid validator policy {}: {}",
+                    validator_policy_address, err
+                )
+            })?;
+            Ok(Some(validator_policy))
+        }
+        _ => Ok(None),
+    }
+}
+
 pub fn get_token_account(
     rpc_client: &RpcClient,
     token_account_address: &Pubkey,
@@ -68,12 +97,32 @@ pub fn get_token_mint(
     Ok(token_mint)
 }
 
+/// Checks if the mint is a Token-2022 mint whose metadata pointer refers to
+/// itself, in which case the pool stores token metadata directly in the mint
+pub(crate) fn mint_holds_metadata(
+    rpc_client: &RpcClient,
+    token_mint_address: &Pubkey,
+) -> Result<bool, Error> {
+    let account = rpc_client.get_account(token_mint_address)?;
+    if account.owner != spl_token_2022::id() {
+        return Ok(false);
+    }
+    let token_mint =
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(account.data.as_slice())
+            .map_err(|err| format!("Invalid token mint {}: {}", token_mint_address, err))?;
+    let metadata_address = token_mint
+        .get_extension::<MetadataPointer>()
+        .ok()
+        .and_then(|pointer| Option::<Pubkey>::from(pointer.metadata_address));
+    Ok(metadata_address == Some(*token_mint_address))
+}
+
 pub(crate) fn get_stake_state(
     rpc_client: &RpcClient,
     stake_address: &Pubkey,
 ) -> Result<stake::state::StakeStateV2, Error> {
     let account_data = rpc_client.get_account_data(stake_address)?;
-    let stake_state = deserialize(account_data.as_slice())
+    let stake_state = deserialize_stake_state(account_data.as_slice())
         .map_err(|err| format!("Invalid stake account {}: {}", stake_address, err))?;
     Ok(stake_state)
 }
@@ -129,8 +178,11 @@ pub(crate) fn get_all_stake(
         &stake::program::id(),
         RpcProgramAccountsConfig {
             filters: Some(vec![
-                // Filter by `Meta::authorized::staker`, which begins at byte offset 12
-                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(12, authorized_staker.as_ref())),
+                // Filter by `Meta::authorized::staker`
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    AUTHORIZED_STAKER_OFFSET,
+                    authorized_staker.as_ref(),
+                )),
             ]),
             account_config: RpcAccountInfoConfig {
                 encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
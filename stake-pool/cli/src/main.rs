@@ -1,6 +1,8 @@
 #![allow(clippy::arithmetic_side_effects)]
 mod client;
 mod output;
+mod rebalance;
+mod report;
 
 // use instruction::create_associated_token_account once ATA 1.0.5 is released
 #[allow(deprecated)]
@@ -8,15 +10,22 @@ use spl_associated_token_account::create_associated_token_account;
 use {
     crate::{
         client::*,
-        output::{CliStakePool, CliStakePoolDetails, CliStakePoolStakeAccountInfo, CliStakePools},
+        output::{
+            CliRebalancePlan, CliStakePool, CliStakePoolDetails, CliStakePoolReport,
+            CliStakePoolStakeAccountInfo, CliStakePools,
+        },
+        rebalance::{
+            plan_rebalance, RebalanceAction, RebalanceLimits, TargetWeights, TransientStake,
+            ValidatorStake,
+        },
+        report::{build_report, PoolSnapshot, SnapshotStore},
     },
-    bincode::deserialize,
     clap::{
         crate_description, crate_name, crate_version, value_t, value_t_or_exit, App, AppSettings,
         Arg, ArgGroup, ArgMatches, SubCommand,
     },
     solana_clap_utils::{
-        input_parsers::{keypair_of, pubkey_of},
+        input_parsers::{keypair_of, pubkey_of, pubkeys_of},
         input_validators::{
             is_amount, is_keypair_or_ask_keyword, is_parsable, is_pubkey, is_url,
             is_valid_percentage, is_valid_pubkey, is_valid_signer,
@@ -34,25 +43,41 @@ use {
     },
     solana_remote_wallet::remote_wallet::RemoteWalletManager,
     solana_sdk::{
+        account::from_account,
+        clock::Clock,
         commitment_config::CommitmentConfig,
         hash::Hash,
         message::Message,
         native_token::{self, Sol},
         signature::{Keypair, Signer},
         signers::Signers,
-        system_instruction,
+        system_instruction, sysvar,
         transaction::Transaction,
     },
     spl_associated_token_account::get_associated_token_address,
+    spl_stake_client::{
+        instruction::{create_stake_account, create_uninitialized_stake_account},
+        state::{deserialize_stake_state, unpack_stake_account, STAKE_ACCOUNT_LEN},
+    },
     spl_stake_pool::{
         self, find_stake_program_address, find_transient_stake_program_address,
         find_withdraw_authority_program_address,
         instruction::{FundingType, PreferredValidatorType},
         minimum_delegation,
-        state::{Fee, FeeType, StakePool, ValidatorList, ValidatorStakeInfo},
+        state::{
+            Fee, FeeType, StakePool, StakeStatus, ValidatorFilter, ValidatorList,
+            ValidatorStakeInfo,
+        },
         MINIMUM_RESERVE_LAMPORTS,
     },
-    std::{cmp::Ordering, num::NonZeroU32, process::exit, rc::Rc},
+    std::{
+        cmp::Ordering,
+        fs,
+        num::NonZeroU32,
+        path::{Path, PathBuf},
+        process::exit,
+        rc::Rc,
+    },
 };
 
 pub(crate) struct Config {
@@ -71,7 +96,9 @@ pub(crate) struct Config {
 type Error = Box<dyn std::error::Error>;
 type CommandResult = Result<(), Error>;
 
-const STAKE_STATE_LEN: usize = 200;
+/// Ephemeral stake accounts are created and closed within a single
+/// instruction, so the same seed can always be reused
+const EPHEMERAL_STAKE_SEED: u64 = 0;
 
 macro_rules! unique_signers {
     ($vec:ident) => {
@@ -208,13 +235,7 @@ fn new_stake_account(
 
     instructions.push(
         // Creating new account
-        system_instruction::create_account(
-            fee_payer,
-            &stake_receiver_pubkey,
-            lamports,
-            STAKE_STATE_LEN as u64,
-            &stake::program::id(),
-        ),
+        create_uninitialized_stake_account(fee_payer, &stake_receiver_pubkey, lamports),
     );
 
     stake_receiver_keypair
@@ -250,7 +271,7 @@ fn command_create_pool(
 
     let reserve_stake_balance = config
         .rpc_client
-        .get_minimum_balance_for_rent_exemption(STAKE_STATE_LEN)?
+        .get_minimum_balance_for_rent_exemption(STAKE_ACCOUNT_LEN)?
         + MINIMUM_RESERVE_LAMPORTS;
     let mint_account_balance = config
         .rpc_client
@@ -284,23 +305,17 @@ fn command_create_pool(
         println!("Stake pool withdraw authority {}", withdraw_authority);
     }
 
-    let mut instructions = vec![
-        // Account for the stake pool reserve
-        system_instruction::create_account(
-            &config.fee_payer.pubkey(),
-            &reserve_keypair.pubkey(),
-            reserve_stake_balance,
-            STAKE_STATE_LEN as u64,
-            &stake::program::id(),
-        ),
-        stake::instruction::initialize(
-            &reserve_keypair.pubkey(),
-            &stake::state::Authorized {
-                staker: withdraw_authority,
-                withdrawer: withdraw_authority,
-            },
-            &stake::state::Lockup::default(),
-        ),
+    // Account for the stake pool reserve
+    let mut instructions = create_stake_account(
+        &config.fee_payer.pubkey(),
+        &reserve_keypair.pubkey(),
+        &stake::state::Authorized {
+            staker: withdraw_authority,
+            withdrawer: withdraw_authority,
+        },
+        reserve_stake_balance,
+    );
+    instructions.extend([
         // Account for the stake pool mint
         system_instruction::create_account(
             &config.fee_payer.pubkey(),
@@ -317,7 +332,7 @@ fn command_create_pool(
             None,
             default_decimals,
         )?,
-    ];
+    ]);
 
     let pool_fee_account = add_associated_token_account(
         config,
@@ -429,6 +444,30 @@ fn command_vsa_add(
         return Ok(());
     }
 
+    // the program cannot see activated stake, so that part of the policy is
+    // checked here
+    if let Some(validator_policy) = get_validator_policy(&config.rpc_client, stake_pool_address)? {
+        if validator_policy.minimum_activated_stake > 0 {
+            let vote_accounts = config.rpc_client.get_vote_accounts()?;
+            let activated_stake = vote_accounts
+                .current
+                .iter()
+                .chain(vote_accounts.delinquent.iter())
+                .find(|info| info.vote_pubkey == vote_account.to_string())
+                .map(|info| info.activated_stake)
+                .unwrap_or(0);
+            if activated_stake < validator_policy.minimum_activated_stake {
+                return Err(format!(
+                    "Validator {} has {} activated, the pool's validator policy requires {}",
+                    vote_account,
+                    Sol(activated_stake),
+                    Sol(validator_policy.minimum_activated_stake)
+                )
+                .into());
+            }
+        }
+    }
+
     if !config.no_update {
         command_update(config, stake_pool_address, false, false, false)?;
     }
@@ -527,13 +566,11 @@ fn command_vsa_remove(
     Ok(())
 }
 
-fn command_increase_validator_stake(
+fn command_eject_validator(
     config: &Config,
     stake_pool_address: &Pubkey,
     vote_account: &Pubkey,
-    amount: f64,
 ) -> CommandResult {
-    let lamports = native_token::sol_to_lamports(amount);
     if !config.no_update {
         command_update(config, stake_pool_address, false, false, false)?;
     }
@@ -543,34 +580,108 @@ fn command_increase_validator_stake(
     let validator_stake_info = validator_list
         .find(vote_account)
         .ok_or("Vote account not found in validator list")?;
+
     let validator_seed = NonZeroU32::new(validator_stake_info.validator_seed_suffix.into());
+    println!("Ejecting validator {}", vote_account);
 
-    let mut signers = vec![config.fee_payer.as_ref(), config.staker.as_ref()];
+    let mut signers = vec![config.fee_payer.as_ref()];
+    let instructions = vec![spl_stake_pool::instruction::eject_validator_with_vote(
+        &spl_stake_pool::id(),
+        &stake_pool,
+        stake_pool_address,
+        vote_account,
+        validator_seed,
+        validator_stake_info.transient_seed_suffix.into(),
+    )];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(config, &instructions, &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_set_validator_policy(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    max_commission: u8,
+    minimum_activated_stake: u64,
+    filter: ValidatorFilter,
+    vote_accounts: Vec<Pubkey>,
+) -> CommandResult {
+    let mut signers = vec![config.fee_payer.as_ref(), config.manager.as_ref()];
     unique_signers!(signers);
     let transaction = checked_transaction_with_signers(
         config,
-        &[
-            spl_stake_pool::instruction::increase_validator_stake_with_vote(
-                &spl_stake_pool::id(),
-                &stake_pool,
-                stake_pool_address,
-                vote_account,
-                lamports,
-                validator_seed,
-                validator_stake_info.transient_seed_suffix.into(),
-            ),
-        ],
+        &[spl_stake_pool::instruction::set_validator_policy(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &config.manager.pubkey(),
+            &config.fee_payer.pubkey(),
+            max_commission,
+            minimum_activated_stake,
+            filter,
+            vote_accounts,
+        )],
         &signers,
     )?;
     send_transaction(config, transaction)?;
     Ok(())
 }
 
+fn command_increase_validator_stake(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    vote_account: &Pubkey,
+    amount: f64,
+    additional: bool,
+) -> CommandResult {
+    let lamports = native_token::sol_to_lamports(amount);
+    if !config.no_update {
+        command_update(config, stake_pool_address, false, false, false)?;
+    }
+
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+    let validator_list = get_validator_list(&config.rpc_client, &stake_pool.validator_list)?;
+    let validator_stake_info = validator_list
+        .find(vote_account)
+        .ok_or("Vote account not found in validator list")?;
+    let validator_seed = NonZeroU32::new(validator_stake_info.validator_seed_suffix.into());
+
+    let instruction = if additional {
+        spl_stake_pool::instruction::increase_additional_validator_stake_with_vote(
+            &spl_stake_pool::id(),
+            &stake_pool,
+            stake_pool_address,
+            vote_account,
+            lamports,
+            validator_seed,
+            validator_stake_info.transient_seed_suffix.into(),
+            EPHEMERAL_STAKE_SEED,
+        )
+    } else {
+        spl_stake_pool::instruction::increase_validator_stake_with_vote(
+            &spl_stake_pool::id(),
+            &stake_pool,
+            stake_pool_address,
+            vote_account,
+            lamports,
+            validator_seed,
+            validator_stake_info.transient_seed_suffix.into(),
+        )
+    };
+
+    let mut signers = vec![config.fee_payer.as_ref(), config.staker.as_ref()];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(config, &[instruction], &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
 fn command_decrease_validator_stake(
     config: &Config,
     stake_pool_address: &Pubkey,
     vote_account: &Pubkey,
     amount: f64,
+    additional: bool,
 ) -> CommandResult {
     let lamports = native_token::sol_to_lamports(amount);
     if !config.no_update {
@@ -584,27 +695,178 @@ fn command_decrease_validator_stake(
         .ok_or("Vote account not found in validator list")?;
     let validator_seed = NonZeroU32::new(validator_stake_info.validator_seed_suffix.into());
 
+    let instruction = if additional {
+        spl_stake_pool::instruction::decrease_additional_validator_stake_with_vote(
+            &spl_stake_pool::id(),
+            &stake_pool,
+            stake_pool_address,
+            vote_account,
+            lamports,
+            validator_seed,
+            validator_stake_info.transient_seed_suffix.into(),
+            EPHEMERAL_STAKE_SEED,
+        )
+    } else {
+        spl_stake_pool::instruction::decrease_validator_stake_with_vote(
+            &spl_stake_pool::id(),
+            &stake_pool,
+            stake_pool_address,
+            vote_account,
+            lamports,
+            validator_seed,
+            validator_stake_info.transient_seed_suffix.into(),
+        )
+    };
+
+    let mut signers = vec![config.fee_payer.as_ref(), config.staker.as_ref()];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(config, &[instruction], &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_redelegate(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    source_vote_account: &Pubkey,
+    destination_vote_account: &Pubkey,
+    amount: f64,
+) -> CommandResult {
+    if source_vote_account == destination_vote_account {
+        return Err("Source and destination vote accounts must be different".into());
+    }
+    let lamports = native_token::sol_to_lamports(amount);
+    if !config.no_update {
+        command_update(config, stake_pool_address, false, false, false)?;
+    }
+
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+    let validator_list = get_validator_list(&config.rpc_client, &stake_pool.validator_list)?;
+    let source_stake_info = validator_list
+        .find(source_vote_account)
+        .ok_or("Source vote account not found in validator list")?;
+    let destination_stake_info = validator_list
+        .find(destination_vote_account)
+        .ok_or("Destination vote account not found in validator list")?;
+
     let mut signers = vec![config.fee_payer.as_ref(), config.staker.as_ref()];
     unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(
+        config,
+        &[spl_stake_pool::instruction::redelegate_with_vote(
+            &spl_stake_pool::id(),
+            &stake_pool,
+            stake_pool_address,
+            source_vote_account,
+            NonZeroU32::new(source_stake_info.validator_seed_suffix.into()),
+            source_stake_info.transient_seed_suffix.into(),
+            EPHEMERAL_STAKE_SEED,
+            destination_vote_account,
+            NonZeroU32::new(destination_stake_info.validator_seed_suffix.into()),
+            destination_stake_info.transient_seed_suffix.into(),
+            lamports,
+        )],
+        &signers,
+    )?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_cleanup_removed_validators(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+) -> CommandResult {
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
     let transaction = checked_transaction_with_signers(
         config,
         &[
-            spl_stake_pool::instruction::decrease_validator_stake_with_vote(
+            spl_stake_pool::instruction::cleanup_removed_validator_entries(
                 &spl_stake_pool::id(),
-                &stake_pool,
                 stake_pool_address,
-                vote_account,
-                lamports,
-                validator_seed,
-                validator_stake_info.transient_seed_suffix.into(),
+                &stake_pool.validator_list,
             ),
         ],
-        &signers,
+        &[config.fee_payer.as_ref()],
     )?;
     send_transaction(config, transaction)?;
     Ok(())
 }
 
+fn command_create_token_metadata(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> CommandResult {
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.manager.as_ref()];
+    unique_signers!(signers);
+    let instruction = if mint_holds_metadata(&config.rpc_client, &stake_pool.pool_mint)? {
+        spl_stake_pool::instruction::create_token_2022_metadata(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &stake_pool.manager,
+            &stake_pool.pool_mint,
+            &config.fee_payer.pubkey(),
+            name,
+            symbol,
+            uri,
+        )
+    } else {
+        spl_stake_pool::instruction::create_token_metadata(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &stake_pool.manager,
+            &stake_pool.pool_mint,
+            &config.fee_payer.pubkey(),
+            name,
+            symbol,
+            uri,
+        )
+    };
+    let transaction = checked_transaction_with_signers(config, &[instruction], &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_update_token_metadata(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> CommandResult {
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.manager.as_ref()];
+    unique_signers!(signers);
+    let instruction = if mint_holds_metadata(&config.rpc_client, &stake_pool.pool_mint)? {
+        spl_stake_pool::instruction::update_token_2022_metadata(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &stake_pool.manager,
+            &stake_pool.pool_mint,
+            &config.fee_payer.pubkey(),
+            name,
+            symbol,
+            uri,
+        )
+    } else {
+        spl_stake_pool::instruction::update_token_metadata(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &stake_pool.manager,
+            &stake_pool.pool_mint,
+            name,
+            symbol,
+            uri,
+        )
+    };
+    let transaction = checked_transaction_with_signers(config, &[instruction], &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
 fn command_set_preferred_validator(
     config: &Config,
     stake_pool_address: &Pubkey,
@@ -1076,7 +1338,7 @@ fn command_list(config: &Config, stake_pool_address: &Pubkey) -> CommandResult {
     let reserve_stake = config.rpc_client.get_account(&stake_pool.reserve_stake)?;
     let minimum_reserve_stake_balance = config
         .rpc_client
-        .get_minimum_balance_for_rent_exemption(STAKE_STATE_LEN)?
+        .get_minimum_balance_for_rent_exemption(STAKE_ACCOUNT_LEN)?
         + MINIMUM_RESERVE_LAMPORTS;
     let cli_stake_pool_stake_account_infos = validator_list
         .validators
@@ -1206,14 +1468,296 @@ fn command_update(
     )?;
     send_transaction(config, transaction)?;
 
-    Ok(())
-}
+    Ok(())
+}
+
+fn command_rebalance(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    weights: TargetWeights,
+    minimum_amount: u64,
+    allow_redelegate: bool,
+) -> CommandResult {
+    if !config.no_update {
+        command_update(config, stake_pool_address, false, false, false)?;
+    }
+
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+    let validator_list = get_validator_list(&config.rpc_client, &stake_pool.validator_list)?;
+    let reserve_lamports = config.rpc_client.get_balance(&stake_pool.reserve_stake)?;
+    let stake_rent = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(STAKE_ACCOUNT_LEN)?;
+    let stake_minimum_delegation = config.rpc_client.get_stake_minimum_delegation()?;
+
+    let mut validators = vec![];
+    for validator in validator_list
+        .validators
+        .iter()
+        .filter(|validator| validator.status == StakeStatus::Active.into())
+    {
+        let transient_stake_lamports = u64::from(validator.transient_stake_lamports);
+        let transient = if transient_stake_lamports == 0 {
+            TransientStake::None
+        } else {
+            let (transient_stake_address, _) = find_transient_stake_program_address(
+                &spl_stake_pool::id(),
+                &validator.vote_account_address,
+                stake_pool_address,
+                validator.transient_seed_suffix.into(),
+            );
+            let transient_stake_account =
+                config.rpc_client.get_account(&transient_stake_address)?;
+            match deserialize_stake_state(transient_stake_account.data.as_slice())? {
+                stake::state::StakeStateV2::Stake(_, stake, _)
+                    if stake.delegation.deactivation_epoch != u64::MAX =>
+                {
+                    TransientStake::Deactivating(transient_stake_lamports)
+                }
+                _ => TransientStake::Activating(transient_stake_lamports),
+            }
+        };
+        validators.push(ValidatorStake {
+            vote_account: validator.vote_account_address,
+            active_lamports: validator.active_stake_lamports.into(),
+            transient,
+        });
+    }
+
+    let plan = plan_rebalance(
+        &validators,
+        &weights,
+        &RebalanceLimits {
+            reserve_lamports,
+            stake_rent,
+            minimum_delegation: minimum_delegation(stake_minimum_delegation),
+            minimum_amount,
+            allow_redelegate,
+        },
+    )?;
+    let actions = plan.actions.clone();
+    let cli_plan = CliRebalancePlan::from((*stake_pool_address, plan));
+    println!("{}", config.output_format.formatted_string(&cli_plan));
+
+    if config.dry_run {
+        return Ok(());
+    }
+    for action in actions {
+        // seed suffixes change with each move, so the list is read again
+        let validator_list = get_validator_list(&config.rpc_client, &stake_pool.validator_list)?;
+        let instruction =
+            rebalance_instruction(stake_pool_address, &stake_pool, &validator_list, action)?;
+        let mut signers = vec![config.fee_payer.as_ref(), config.staker.as_ref()];
+        unique_signers!(signers);
+        let transaction = checked_transaction_with_signers(config, &[instruction], &signers)?;
+        send_transaction(config, transaction)?;
+    }
+    Ok(())
+}
+
+fn rebalance_instruction(
+    stake_pool_address: &Pubkey,
+    stake_pool: &StakePool,
+    validator_list: &ValidatorList,
+    action: RebalanceAction,
+) -> Result<Instruction, Error> {
+    let find = |vote_account: &Pubkey| {
+        validator_list
+            .find(vote_account)
+            .ok_or_else(|| format!("Vote account {} not found in validator list", vote_account))
+    };
+    let instruction = match action {
+        RebalanceAction::Increase {
+            vote_account,
+            lamports,
+            additional,
+        } => {
+            let info = find(&vote_account)?;
+            let validator_seed = NonZeroU32::new(info.validator_seed_suffix.into());
+            if additional {
+                spl_stake_pool::instruction::increase_additional_validator_stake_with_vote(
+                    &spl_stake_pool::id(),
+                    stake_pool,
+                    stake_pool_address,
+                    &vote_account,
+                    lamports,
+                    validator_seed,
+                    info.transient_seed_suffix.into(),
+                    EPHEMERAL_STAKE_SEED,
+                )
+            } else {
+                spl_stake_pool::instruction::increase_validator_stake_with_vote(
+                    &spl_stake_pool::id(),
+                    stake_pool,
+                    stake_pool_address,
+                    &vote_account,
+                    lamports,
+                    validator_seed,
+                    info.transient_seed_suffix.into(),
+                )
+            }
+        }
+        RebalanceAction::Decrease {
+            vote_account,
+            lamports,
+            additional,
+        } => {
+            let info = find(&vote_account)?;
+            let validator_seed = NonZeroU32::new(info.validator_seed_suffix.into());
+            if additional {
+                spl_stake_pool::instruction::decrease_additional_validator_stake_with_vote(
+                    &spl_stake_pool::id(),
+                    stake_pool,
+                    stake_pool_address,
+                    &vote_account,
+                    lamports,
+                    validator_seed,
+                    info.transient_seed_suffix.into(),
+                    EPHEMERAL_STAKE_SEED,
+                )
+            } else {
+                spl_stake_pool::instruction::decrease_validator_stake_with_vote(
+                    &spl_stake_pool::id(),
+                    stake_pool,
+                    stake_pool_address,
+                    &vote_account,
+                    lamports,
+                    validator_seed,
+                    info.transient_seed_suffix.into(),
+                )
+            }
+        }
+        RebalanceAction::Redelegate {
+            source_vote_account,
+            destination_vote_account,
+            lamports,
+        } => {
+            let source_info = find(&source_vote_account)?;
+            let destination_info = find(&destination_vote_account)?;
+            spl_stake_pool::instruction::redelegate_with_vote(
+                &spl_stake_pool::id(),
+                stake_pool,
+                stake_pool_address,
+                &source_vote_account,
+                NonZeroU32::new(source_info.validator_seed_suffix.into()),
+                source_info.transient_seed_suffix.into(),
+                EPHEMERAL_STAKE_SEED,
+                &destination_vote_account,
+                NonZeroU32::new(destination_info.validator_seed_suffix.into()),
+                destination_info.transient_seed_suffix.into(),
+                lamports,
+            )
+        }
+    };
+    Ok(instruction)
+}
+
+#[derive(PartialEq, Debug)]
+struct WithdrawAccount {
+    stake_address: Pubkey,
+    vote_address: Option<Pubkey>,
+    pool_amount: u64,
+}
+
+fn command_report(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    store_path: &Path,
+    epochs: (Option<u64>, Option<u64>),
+    record_snapshot: bool,
+    csv_path: Option<&Path>,
+) -> CommandResult {
+    let mut store = SnapshotStore::load(store_path, stake_pool_address)?;
+
+    if record_snapshot {
+        let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+        let clock_account = config.rpc_client.get_account(&sysvar::clock::id())?;
+        let clock = from_account::<Clock, _>(&clock_account).ok_or("Invalid clock sysvar")?;
+
+        // balances are only settled once the pool is updated, so any earlier
+        // snapshot would undercount the epoch's rewards
+        if stake_pool.last_update_epoch != clock.epoch {
+            eprintln!(
+                "warning: Stake pool has not been updated for epoch {}, not recording a snapshot",
+                clock.epoch
+            );
+        } else {
+            let validator_list =
+                get_validator_list(&config.rpc_client, &stake_pool.validator_list)?;
+            let stake_addresses: Vec<Pubkey> = validator_list
+                .validators
+                .iter()
+                .map(|validator| {
+                    find_stake_program_address(
+                        &spl_stake_pool::id(),
+                        &validator.vote_account_address,
+                        stake_pool_address,
+                        NonZeroU32::new(validator.validator_seed_suffix.into()),
+                    )
+                    .0
+                })
+                .collect();
+            let last_epoch_rewards = match clock.epoch.checked_sub(1) {
+                Some(last_epoch) => config
+                    .rpc_client
+                    .get_inflation_reward(&stake_addresses, Some(last_epoch))
+                    .map(|rewards| {
+                        rewards
+                            .into_iter()
+                            .map(|reward| Some(reward.map(|r| r.amount).unwrap_or(0)))
+                            .collect()
+                    })
+                    .unwrap_or_else(|err| {
+                        eprintln!(
+                            "warning: Could not fetch rewards for epoch {}: {}",
+                            last_epoch, err
+                        );
+                        vec![None; stake_addresses.len()]
+                    }),
+                None => vec![None; stake_addresses.len()],
+            };
+
+            store.insert(PoolSnapshot::new(
+                clock.epoch,
+                clock.epoch_start_timestamp,
+                &stake_pool,
+                &validator_list,
+                &last_epoch_rewards,
+            ));
+            store.save(store_path)?;
+            if config.verbose {
+                println!(
+                    "Recorded snapshot for epoch {} in {}",
+                    clock.epoch,
+                    store_path.display()
+                );
+            }
+        }
+    }
+
+    if let Some(csv_path) = csv_path {
+        fs::write(csv_path, store.to_csv())?;
+    }
 
-#[derive(PartialEq, Debug)]
-struct WithdrawAccount {
-    stake_address: Pubkey,
-    vote_address: Option<Pubkey>,
-    pool_amount: u64,
+    let from_epoch = epochs
+        .0
+        .or_else(|| store.snapshots.first().map(|snapshot| snapshot.epoch));
+    let to_epoch = epochs
+        .1
+        .or_else(|| store.snapshots.last().map(|snapshot| snapshot.epoch));
+    match (from_epoch, to_epoch) {
+        (Some(from_epoch), Some(to_epoch)) if from_epoch != to_epoch => {
+            let report = build_report(&store, from_epoch, to_epoch)?;
+            let cli_report = CliStakePoolReport::from((*stake_pool_address, report));
+            println!("{}", config.output_format.formatted_string(&cli_report));
+        }
+        _ => println!(
+            "At least two snapshots are needed for a report, {} recorded in {}",
+            store.snapshots.len(),
+            store_path.display()
+        ),
+    }
+    Ok(())
 }
 
 fn sorted_accounts<F>(
@@ -1253,7 +1797,7 @@ fn prepare_withdraw_accounts(
     let stake_minimum_delegation = rpc_client.get_stake_minimum_delegation()?;
     let stake_pool_minimum_delegation = minimum_delegation(stake_minimum_delegation);
     let min_balance = rpc_client
-        .get_minimum_balance_for_rent_exemption(STAKE_STATE_LEN)?
+        .get_minimum_balance_for_rent_exemption(STAKE_ACCOUNT_LEN)?
         .saturating_add(stake_pool_minimum_delegation);
     let pool_mint = get_token_mint(rpc_client, &stake_pool.pool_mint)?;
     let validator_list: ValidatorList = get_validator_list(rpc_client, &stake_pool.validator_list)?;
@@ -1304,7 +1848,7 @@ fn prepare_withdraw_accounts(
     accounts.push((
         stake_pool.reserve_stake,
         reserve_stake.lamports
-            - rpc_client.get_minimum_balance_for_rent_exemption(STAKE_STATE_LEN)?
+            - rpc_client.get_minimum_balance_for_rent_exemption(STAKE_ACCOUNT_LEN)?
             - MINIMUM_RESERVE_LAMPORTS,
         None,
     ));
@@ -1392,7 +1936,7 @@ fn command_withdraw_stake(
     )?;
     let stake_account_rent_exemption = config
         .rpc_client
-        .get_minimum_balance_for_rent_exemption(STAKE_STATE_LEN)?;
+        .get_minimum_balance_for_rent_exemption(STAKE_ACCOUNT_LEN)?;
 
     // Check withdraw_from balance
     if token_account.amount < pool_amount {
@@ -1408,13 +1952,8 @@ fn command_withdraw_stake(
     let maybe_stake_receiver_state = stake_receiver_param
         .map(|stake_receiver_pubkey| {
             let stake_account = config.rpc_client.get_account(&stake_receiver_pubkey).ok()?;
-            let stake_state: stake::state::StakeStateV2 =
-                deserialize(stake_account.data.as_slice())
-                    .map_err(|err| {
-                        format!("Invalid stake account {}: {}", stake_receiver_pubkey, err)
-                    })
-                    .ok()?;
-            if stake_state.delegation().is_some() && stake_account.owner == stake::program::id() {
+            let stake_state = unpack_stake_account(&stake_account).ok()?;
+            if stake_state.delegation().is_some() {
                 Some(stake_state)
             } else {
                 None
@@ -2164,6 +2703,77 @@ fn main() {
                     .help("Vote account for the validator to remove from the pool"),
             )
         )
+        .subcommand(SubCommand::with_name("eject-validator")
+            .about("Remove a validator whose commission is above the pool's validator policy. Can be signed by anyone.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address"),
+            )
+            .arg(
+                Arg::with_name("vote_account")
+                    .index(2)
+                    .validator(is_pubkey)
+                    .value_name("VOTE_ACCOUNT_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Vote account for the validator to eject from the pool"),
+            )
+        )
+        .subcommand(SubCommand::with_name("set-validator-policy")
+            .about("Restrict the validators the staker can add to the pool. Must be signed by the manager.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address"),
+            )
+            .arg(
+                Arg::with_name("max_commission")
+                    .long("max-commission")
+                    .validator(is_valid_percentage)
+                    .value_name("PERCENTAGE")
+                    .takes_value(true)
+                    .help("Highest validator commission allowed, validators above it can be ejected by anyone [default: 100]"),
+            )
+            .arg(
+                Arg::with_name("min_activated_stake")
+                    .long("min-activated-stake")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .help("Minimum activated stake in SOL on a vote account for it to be added. Only checked by clients such as this CLI, not by the program [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("allow")
+                    .long("allow")
+                    .validator(is_pubkey)
+                    .value_name("VOTE_ACCOUNT_ADDRESS")
+                    .takes_value(true)
+                    .multiple(true)
+                    .help("Only allow these vote accounts to be added"),
+            )
+            .arg(
+                Arg::with_name("deny")
+                    .long("deny")
+                    .validator(is_pubkey)
+                    .value_name("VOTE_ACCOUNT_ADDRESS")
+                    .takes_value(true)
+                    .multiple(true)
+                    .help("Prevent these vote accounts from being added"),
+            )
+            .group(ArgGroup::with_name("filter")
+                .arg("allow")
+                .arg("deny")
+            )
+        )
         .subcommand(SubCommand::with_name("increase-validator-stake")
             .about("Increase stake to a validator, drawing from the stake pool reserve. Must be signed by the pool staker.")
             .arg(
@@ -2192,6 +2802,12 @@ fn main() {
                     .takes_value(true)
                     .help("Amount in SOL to add to the validator stake account. Must be at least the rent-exempt amount for a stake plus 1 SOL for merging."),
             )
+            .arg(
+                Arg::with_name("additional")
+                    .long("additional")
+                    .takes_value(false)
+                    .help("Add to the transient stake already activating this epoch, rather than creating a new transient stake account."),
+            )
         )
         .subcommand(SubCommand::with_name("decrease-validator-stake")
             .about("Decrease stake to a validator, splitting from the active stake. Must be signed by the pool staker.")
@@ -2221,6 +2837,63 @@ fn main() {
                     .takes_value(true)
                     .help("Amount in SOL to remove from the validator stake account. Must be at least the rent-exempt amount for a stake."),
             )
+            .arg(
+                Arg::with_name("additional")
+                    .long("additional")
+                    .takes_value(false)
+                    .help("Add to the transient stake already deactivating this epoch, rather than creating a new transient stake account."),
+            )
+        )
+        .subcommand(SubCommand::with_name("redelegate")
+            .about("Redelegate active stake from one validator to another, without going through the reserve. Must be signed by the pool staker.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address"),
+            )
+            .arg(
+                Arg::with_name("source_vote_account")
+                    .index(2)
+                    .validator(is_pubkey)
+                    .value_name("SOURCE_VOTE_ACCOUNT_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Vote account for the validator to move stake from"),
+            )
+            .arg(
+                Arg::with_name("destination_vote_account")
+                    .index(3)
+                    .validator(is_pubkey)
+                    .value_name("DESTINATION_VOTE_ACCOUNT_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Vote account for the validator to move stake to"),
+            )
+            .arg(
+                Arg::with_name("amount")
+                    .index(4)
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Amount in SOL to redelegate. Must be at least the rent-exempt amount for a stake plus the minimum delegation."),
+            )
+        )
+        .subcommand(SubCommand::with_name("cleanup-removed-validators")
+            .about("Remove entries of fully removed validators from the validator list")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address"),
+            )
         )
         .subcommand(SubCommand::with_name("set-preferred-validator")
             .about("Set the preferred validator for deposits or withdrawals. Must be signed by the pool staker.")
@@ -2444,6 +3117,98 @@ fn main() {
                     .help("If set, only updates validator list balances that have not been updated for this epoch. Otherwise, updates all validator balances on the validator list."),
             )
         )
+        .subcommand(SubCommand::with_name("rebalance")
+            .about("Move stake between the reserve and validators to reach target weights. Must be signed by the pool staker.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address."),
+            )
+            .arg(
+                Arg::with_name("weights")
+                    .long("weights")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .help("JSON file of vote account addresses to the percentage of pool stake to delegate to them. Stake not allocated stays in the reserve. [default: same stake on every validator]"),
+            )
+            .arg(
+                Arg::with_name("scores")
+                    .long("scores")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .help("JSON file of vote account addresses to scores. All pool stake is delegated proportionally to the scores."),
+            )
+            .group(ArgGroup::with_name("target")
+                .arg("weights")
+                .arg("scores")
+            )
+            .arg(
+                Arg::with_name("min_amount")
+                    .long("min-amount")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .help("Smallest amount in SOL worth moving for a validator. [default: minimum delegation]"),
+            )
+            .arg(
+                Arg::with_name("redelegate")
+                    .long("redelegate")
+                    .takes_value(false)
+                    .help("Move stake directly between validators with Redelegate where possible, rather than through the reserve."),
+            )
+        )
+        .subcommand(SubCommand::with_name("report")
+            .about("Record a snapshot of the stake pool for the current epoch, and report its yield between two recorded epochs")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address."),
+            )
+            .arg(
+                Arg::with_name("store")
+                    .long("store")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .help("JSON file to keep snapshots in. [default: stake-pool-<POOL_ADDRESS>.json]"),
+            )
+            .arg(
+                Arg::with_name("from_epoch")
+                    .long("from-epoch")
+                    .validator(is_parsable::<u64>)
+                    .value_name("EPOCH")
+                    .takes_value(true)
+                    .help("Recorded epoch to start the report from. [default: earliest snapshot]"),
+            )
+            .arg(
+                Arg::with_name("to_epoch")
+                    .long("to-epoch")
+                    .validator(is_parsable::<u64>)
+                    .value_name("EPOCH")
+                    .takes_value(true)
+                    .help("Recorded epoch to end the report at. [default: latest snapshot]"),
+            )
+            .arg(
+                Arg::with_name("no_snapshot")
+                    .long("no-snapshot")
+                    .takes_value(false)
+                    .help("Only report from recorded snapshots, without recording one for the current epoch."),
+            )
+            .arg(
+                Arg::with_name("csv")
+                    .long("csv")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .help("Also export all recorded snapshots to a CSV file, one row per validator per epoch."),
+            )
+        )
         .subcommand(SubCommand::with_name("withdraw-stake")
             .about("Withdraw active stake from the stake pool in exchange for pool tokens")
             .arg(
@@ -2699,6 +3464,78 @@ fn main() {
                     .help("Fee percentage, maximum 100"),
             )
         )
+        .subcommand(SubCommand::with_name("create-token-metadata")
+            .about("Create metadata for the pool token. Must be signed by the pool manager.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address"),
+            )
+            .arg(
+                Arg::with_name("name")
+                    .index(2)
+                    .value_name("TOKEN_NAME")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Name of the pool token"),
+            )
+            .arg(
+                Arg::with_name("symbol")
+                    .index(3)
+                    .value_name("TOKEN_SYMBOL")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Symbol of the pool token"),
+            )
+            .arg(
+                Arg::with_name("uri")
+                    .index(4)
+                    .value_name("TOKEN_URI")
+                    .takes_value(true)
+                    .required(true)
+                    .help("URI of the pool token metadata"),
+            )
+        )
+        .subcommand(SubCommand::with_name("update-token-metadata")
+            .about("Update metadata for the pool token. Must be signed by the pool manager.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address"),
+            )
+            .arg(
+                Arg::with_name("name")
+                    .index(2)
+                    .value_name("TOKEN_NAME")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Name of the pool token"),
+            )
+            .arg(
+                Arg::with_name("symbol")
+                    .index(3)
+                    .value_name("TOKEN_SYMBOL")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Symbol of the pool token"),
+            )
+            .arg(
+                Arg::with_name("uri")
+                    .index(4)
+                    .value_name("TOKEN_URI")
+                    .takes_value(true)
+                    .required(true)
+                    .help("URI of the pool token metadata"),
+            )
+        )
         .subcommand(SubCommand::with_name("list-all")
             .about("List information about all stake pools")
         )
@@ -2844,17 +3681,82 @@ fn main() {
             let vote_account = pubkey_of(arg_matches, "vote_account").unwrap();
             command_vsa_remove(&config, &stake_pool_address, &vote_account)
         }
+        ("eject-validator", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let vote_account = pubkey_of(arg_matches, "vote_account").unwrap();
+            command_eject_validator(&config, &stake_pool_address, &vote_account)
+        }
+        ("set-validator-policy", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let max_commission = value_t!(arg_matches, "max_commission", u8).unwrap_or(100);
+            let minimum_activated_stake = value_t!(arg_matches, "min_activated_stake", f64)
+                .map(native_token::sol_to_lamports)
+                .unwrap_or(0);
+            let (filter, vote_accounts) = if arg_matches.is_present("allow") {
+                (
+                    ValidatorFilter::Allow,
+                    pubkeys_of(arg_matches, "allow").unwrap(),
+                )
+            } else if arg_matches.is_present("deny") {
+                (
+                    ValidatorFilter::Deny,
+                    pubkeys_of(arg_matches, "deny").unwrap(),
+                )
+            } else {
+                (ValidatorFilter::None, vec![])
+            };
+            command_set_validator_policy(
+                &config,
+                &stake_pool_address,
+                max_commission,
+                minimum_activated_stake,
+                filter,
+                vote_accounts,
+            )
+        }
         ("increase-validator-stake", Some(arg_matches)) => {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
             let vote_account = pubkey_of(arg_matches, "vote_account").unwrap();
             let amount = value_t_or_exit!(arg_matches, "amount", f64);
-            command_increase_validator_stake(&config, &stake_pool_address, &vote_account, amount)
+            let additional = arg_matches.is_present("additional");
+            command_increase_validator_stake(
+                &config,
+                &stake_pool_address,
+                &vote_account,
+                amount,
+                additional,
+            )
         }
         ("decrease-validator-stake", Some(arg_matches)) => {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
             let vote_account = pubkey_of(arg_matches, "vote_account").unwrap();
             let amount = value_t_or_exit!(arg_matches, "amount", f64);
-            command_decrease_validator_stake(&config, &stake_pool_address, &vote_account, amount)
+            let additional = arg_matches.is_present("additional");
+            command_decrease_validator_stake(
+                &config,
+                &stake_pool_address,
+                &vote_account,
+                amount,
+                additional,
+            )
+        }
+        ("redelegate", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let source_vote_account = pubkey_of(arg_matches, "source_vote_account").unwrap();
+            let destination_vote_account =
+                pubkey_of(arg_matches, "destination_vote_account").unwrap();
+            let amount = value_t_or_exit!(arg_matches, "amount", f64);
+            command_redelegate(
+                &config,
+                &stake_pool_address,
+                &source_vote_account,
+                &destination_vote_account,
+                amount,
+            )
+        }
+        ("cleanup-removed-validators", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            command_cleanup_removed_validators(&config, &stake_pool_address)
         }
         ("set-preferred-validator", Some(arg_matches)) => {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
@@ -2923,6 +3825,58 @@ fn main() {
             let stale_only = arg_matches.is_present("stale_only");
             command_update(&config, &stake_pool_address, force, no_merge, stale_only)
         }
+        ("rebalance", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let read_weights = |name| {
+                let path = arg_matches.value_of(name).unwrap();
+                fs::read_to_string(path)
+                    .map_err(Error::from)
+                    .and_then(|contents| TargetWeights::parse_file(&contents))
+                    .unwrap_or_else(|err| {
+                        eprintln!("error: cannot read {}: {}", path, err);
+                        exit(1);
+                    })
+            };
+            let weights = if arg_matches.is_present("weights") {
+                TargetWeights::Percentages(read_weights("weights"))
+            } else if arg_matches.is_present("scores") {
+                TargetWeights::Scores(read_weights("scores"))
+            } else {
+                TargetWeights::Uniform
+            };
+            let minimum_amount = value_t!(arg_matches, "min_amount", f64)
+                .map(native_token::sol_to_lamports)
+                .unwrap_or(0);
+            let allow_redelegate = arg_matches.is_present("redelegate");
+            command_rebalance(
+                &config,
+                &stake_pool_address,
+                weights,
+                minimum_amount,
+                allow_redelegate,
+            )
+        }
+        ("report", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let store_path = arg_matches
+                .value_of("store")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| {
+                    PathBuf::from(format!("stake-pool-{}.json", stake_pool_address))
+                });
+            let from_epoch = value_t!(arg_matches, "from_epoch", u64).ok();
+            let to_epoch = value_t!(arg_matches, "to_epoch", u64).ok();
+            let record_snapshot = !arg_matches.is_present("no_snapshot");
+            let csv_path = arg_matches.value_of("csv").map(PathBuf::from);
+            command_report(
+                &config,
+                &stake_pool_address,
+                &store_path,
+                (from_epoch, to_epoch),
+                record_snapshot,
+                csv_path.as_deref(),
+            )
+        }
         ("withdraw-stake", Some(arg_matches)) => {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
             let vote_account = pubkey_of(arg_matches, "vote_account");
@@ -3051,6 +4005,20 @@ fn main() {
             };
             command_set_fee(&config, &stake_pool_address, fee_type)
         }
+        ("create-token-metadata", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let name = value_t_or_exit!(arg_matches, "name", String);
+            let symbol = value_t_or_exit!(arg_matches, "symbol", String);
+            let uri = value_t_or_exit!(arg_matches, "uri", String);
+            command_create_token_metadata(&config, &stake_pool_address, name, symbol, uri)
+        }
+        ("update-token-metadata", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let name = value_t_or_exit!(arg_matches, "name", String);
+            let symbol = value_t_or_exit!(arg_matches, "symbol", String);
+            let uri = value_t_or_exit!(arg_matches, "uri", String);
+            command_update_token_metadata(&config, &stake_pool_address, name, symbol, uri)
+        }
         ("list-all", _) => command_list_all_pools(&config),
         ("deposit-all-stake", Some(arg_matches)) => {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
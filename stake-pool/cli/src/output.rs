@@ -1,4 +1,8 @@
 use {
+    crate::{
+        rebalance::{RebalanceAction, RebalancePlan},
+        report::PoolReport,
+    },
     serde::{Deserialize, Serialize},
     solana_cli_output::{QuietDisplay, VerboseDisplay},
     solana_sdk::{native_token::Sol, pubkey::Pubkey, stake::state::Lockup},
@@ -503,3 +507,262 @@ impl From<(Pubkey, StakePool, ValidatorList, Pubkey)> for CliStakePool {
         }
     }
 }
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliRebalancePlan {
+    pub stake_pool: String,
+    pub reserve_lamports: u64,
+    pub remaining_reserve_lamports: u64,
+    pub validators: Vec<CliRebalanceValidator>,
+    pub actions: Vec<CliRebalanceAction>,
+    pub skipped: Vec<String>,
+}
+
+impl Display for CliRebalancePlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Stake Pool: {}", &self.stake_pool)?;
+        writeln!(
+            f,
+            "Reserve Balance: {}\tAfter Rebalance: {}",
+            Sol(self.reserve_lamports),
+            Sol(self.remaining_reserve_lamports),
+        )?;
+        for validator in &self.validators {
+            writeln!(
+                f,
+                "Vote Account: {}\tCurrent Stake: {}\tTarget Stake: {}",
+                validator.vote_account_address,
+                Sol(validator.current_lamports),
+                Sol(validator.target_lamports),
+            )?;
+        }
+        if self.actions.is_empty() {
+            writeln!(f, "No stake moves required")?;
+        }
+        for action in &self.actions {
+            match &action.destination_vote_account_address {
+                Some(destination) => writeln!(
+                    f,
+                    "{}: {} from {} to {}",
+                    action.kind,
+                    Sol(action.lamports),
+                    action.vote_account_address,
+                    destination,
+                )?,
+                None => writeln!(
+                    f,
+                    "{}: {} on {}",
+                    action.kind,
+                    Sol(action.lamports),
+                    action.vote_account_address,
+                )?,
+            }
+        }
+        for skipped in &self.skipped {
+            writeln!(f, "Skipped: {}", skipped)?;
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliRebalancePlan {}
+impl VerboseDisplay for CliRebalancePlan {}
+
+impl From<(Pubkey, RebalancePlan)> for CliRebalancePlan {
+    fn from(p: (Pubkey, RebalancePlan)) -> Self {
+        let (stake_pool, plan) = p;
+        Self {
+            stake_pool: stake_pool.to_string(),
+            reserve_lamports: plan.reserve_lamports,
+            remaining_reserve_lamports: plan.remaining_reserve_lamports,
+            validators: plan
+                .validators
+                .into_iter()
+                .map(|v| CliRebalanceValidator {
+                    vote_account_address: v.vote_account.to_string(),
+                    current_lamports: v.current_lamports,
+                    target_lamports: v.target_lamports,
+                })
+                .collect(),
+            actions: plan
+                .actions
+                .into_iter()
+                .map(CliRebalanceAction::from)
+                .collect(),
+            skipped: plan.skipped,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliRebalanceValidator {
+    pub vote_account_address: String,
+    pub current_lamports: u64,
+    pub target_lamports: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliRebalanceAction {
+    pub kind: String,
+    pub vote_account_address: String,
+    pub destination_vote_account_address: Option<String>,
+    pub lamports: u64,
+}
+
+impl From<RebalanceAction> for CliRebalanceAction {
+    fn from(a: RebalanceAction) -> Self {
+        let (kind, vote_account, destination_vote_account, lamports) = match a {
+            RebalanceAction::Increase {
+                vote_account,
+                lamports,
+                additional,
+            } => (
+                if additional {
+                    "Increase Additional"
+                } else {
+                    "Increase"
+                },
+                vote_account,
+                None,
+                lamports,
+            ),
+            RebalanceAction::Decrease {
+                vote_account,
+                lamports,
+                additional,
+            } => (
+                if additional {
+                    "Decrease Additional"
+                } else {
+                    "Decrease"
+                },
+                vote_account,
+                None,
+                lamports,
+            ),
+            RebalanceAction::Redelegate {
+                source_vote_account,
+                destination_vote_account,
+                lamports,
+            } => (
+                "Redelegate",
+                source_vote_account,
+                Some(destination_vote_account),
+                lamports,
+            ),
+        };
+        Self {
+            kind: kind.to_string(),
+            vote_account_address: vote_account.to_string(),
+            destination_vote_account_address: destination_vote_account.map(|d| d.to_string()),
+            lamports,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliStakePoolReport {
+    pub stake_pool: String,
+    pub from_epoch: u64,
+    pub to_epoch: u64,
+    pub elapsed_seconds: i64,
+    pub from_exchange_rate: f64,
+    pub to_exchange_rate: f64,
+    pub exchange_rate_growth: f64,
+    pub apy: Option<f64>,
+    pub rewards: u64,
+    pub fee_lamports: u64,
+    pub epochs_with_rewards: u64,
+    pub validators: Vec<CliStakePoolReportValidator>,
+}
+
+impl Display for CliStakePoolReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Stake Pool: {}", &self.stake_pool)?;
+        writeln!(
+            f,
+            "Epochs: {} to {} ({:.2} days)",
+            self.from_epoch,
+            self.to_epoch,
+            self.elapsed_seconds as f64 / 86_400.0,
+        )?;
+        writeln!(
+            f,
+            "Exchange Rate: {:.9} to {:.9} SOL per pool token ({:+.4}%)",
+            self.from_exchange_rate,
+            self.to_exchange_rate,
+            self.exchange_rate_growth * 100.0,
+        )?;
+        match self.apy {
+            Some(apy) => writeln!(f, "Realized APY: {:.4}%", apy * 100.0)?,
+            None => writeln!(f, "Realized APY: unknown")?,
+        }
+        writeln!(
+            f,
+            "Rewards: {}\tFee Take: {}\t(rewards known for {} of {} epochs)",
+            Sol(self.rewards),
+            Sol(self.fee_lamports),
+            self.epochs_with_rewards,
+            self.to_epoch - self.from_epoch,
+        )?;
+        for validator in &self.validators {
+            writeln!(
+                f,
+                "Vote Account: {}\tStarting Stake: {}\tRewards: {}\tYield: {:.4}%\tShare of Rewards: {:.2}%",
+                validator.vote_account_address,
+                Sol(validator.starting_stake_lamports),
+                Sol(validator.rewards),
+                validator.period_yield * 100.0,
+                validator.rewards_share * 100.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliStakePoolReport {}
+impl VerboseDisplay for CliStakePoolReport {}
+
+impl From<(Pubkey, PoolReport)> for CliStakePoolReport {
+    fn from(r: (Pubkey, PoolReport)) -> Self {
+        let (stake_pool, report) = r;
+        Self {
+            stake_pool: stake_pool.to_string(),
+            from_epoch: report.from_epoch,
+            to_epoch: report.to_epoch,
+            elapsed_seconds: report.elapsed_seconds,
+            from_exchange_rate: report.from_exchange_rate,
+            to_exchange_rate: report.to_exchange_rate,
+            exchange_rate_growth: report.exchange_rate_growth,
+            apy: report.apy,
+            rewards: report.rewards,
+            fee_lamports: report.fee_lamports,
+            epochs_with_rewards: report.epochs_with_rewards,
+            validators: report
+                .validators
+                .into_iter()
+                .map(|v| CliStakePoolReportValidator {
+                    vote_account_address: v.vote_account.to_string(),
+                    starting_stake_lamports: v.starting_stake_lamports,
+                    rewards: v.rewards,
+                    period_yield: v.period_yield,
+                    rewards_share: v.rewards_share,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliStakePoolReportValidator {
+    pub vote_account_address: String,
+    pub starting_stake_lamports: u64,
+    pub rewards: u64,
+    pub period_yield: f64,
+    pub rewards_share: f64,
+}
@@ -0,0 +1,327 @@
+//! Planning of stake moves to bring a pool's validators to target weights
+
+use {
+    solana_program::pubkey::Pubkey,
+    spl_stake_pool::MINIMUM_RESERVE_LAMPORTS,
+    std::{
+        cmp::{Ordering, Reverse},
+        collections::HashMap,
+        str::FromStr,
+    },
+};
+
+type Error = Box<dyn std::error::Error>;
+
+/// How the pool's stake should be split between its validators
+pub(crate) enum TargetWeights {
+    /// Same stake on every active validator
+    Uniform,
+    /// Percentage of the pool's stake per vote account, the rest stays in the
+    /// reserve
+    Percentages(HashMap<Pubkey, f64>),
+    /// All of the pool's stake split proportionally to a score per vote
+    /// account
+    Scores(HashMap<Pubkey, f64>),
+}
+
+impl TargetWeights {
+    /// Parse a JSON object of vote account addresses to numbers
+    pub fn parse_file(contents: &str) -> Result<HashMap<Pubkey, f64>, Error> {
+        let values: HashMap<String, f64> = serde_json::from_str(contents)?;
+        values
+            .into_iter()
+            .map(|(vote_account, value)| {
+                let vote_account = Pubkey::from_str(&vote_account)
+                    .map_err(|_| format!("Invalid vote account address {}", vote_account))?;
+                if !value.is_finite() || value < 0.0 {
+                    return Err(format!("Invalid value {} for {}", value, vote_account).into());
+                }
+                Ok((vote_account, value))
+            })
+            .collect()
+    }
+
+    /// Share of the pool's stake for each vote account, in `vote_accounts`
+    /// order
+    fn shares(&self, vote_accounts: &[Pubkey]) -> Result<Vec<f64>, Error> {
+        let values = match self {
+            Self::Uniform => {
+                return Ok(vec![1.0 / vote_accounts.len() as f64; vote_accounts.len()])
+            }
+            Self::Percentages(values) | Self::Scores(values) => values,
+        };
+        if let Some(vote_account) = values.keys().find(|key| !vote_accounts.contains(key)) {
+            return Err(format!(
+                "Vote account {} is not an active validator of the pool",
+                vote_account
+            )
+            .into());
+        }
+        let total: f64 = values.values().sum();
+        let divisor = match self {
+            Self::Percentages(_) if total > 100.0 => {
+                return Err(format!("Percentages add up to {}, more than 100", total).into());
+            }
+            Self::Percentages(_) => 100.0,
+            _ if total == 0.0 => return Err("Scores add up to zero".into()),
+            _ => total,
+        };
+        Ok(vote_accounts
+            .iter()
+            .map(|vote_account| values.get(vote_account).copied().unwrap_or(0.0) / divisor)
+            .collect())
+    }
+}
+
+/// Transient stake account of a validator in the current epoch
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TransientStake {
+    None,
+    Activating(u64),
+    Deactivating(u64),
+}
+
+/// Stake of one validator of the pool
+pub(crate) struct ValidatorStake {
+    pub vote_account: Pubkey,
+    pub active_lamports: u64,
+    pub transient: TransientStake,
+}
+
+/// Cluster and pool values the stake moves must respect
+pub(crate) struct RebalanceLimits {
+    pub reserve_lamports: u64,
+    pub stake_rent: u64,
+    pub minimum_delegation: u64,
+    pub minimum_amount: u64,
+    pub allow_redelegate: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RebalanceAction {
+    Increase {
+        vote_account: Pubkey,
+        lamports: u64,
+        additional: bool,
+    },
+    Decrease {
+        vote_account: Pubkey,
+        lamports: u64,
+        additional: bool,
+    },
+    Redelegate {
+        source_vote_account: Pubkey,
+        destination_vote_account: Pubkey,
+        lamports: u64,
+    },
+}
+
+/// Current and target stake of one validator
+pub(crate) struct ValidatorTarget {
+    pub vote_account: Pubkey,
+    pub current_lamports: u64,
+    pub target_lamports: u64,
+}
+
+pub(crate) struct RebalancePlan {
+    pub validators: Vec<ValidatorTarget>,
+    pub actions: Vec<RebalanceAction>,
+    pub skipped: Vec<String>,
+    pub reserve_lamports: u64,
+    pub remaining_reserve_lamports: u64,
+}
+
+struct Move {
+    index: usize,
+    lamports: u64,
+}
+
+/// Compute the stake moves that bring validators closest to their targets this
+/// epoch.
+///
+/// Each validator gets at most one decrease or increase, plus redelegations
+/// when allowed, since a transient stake account can only move in one
+/// direction per epoch. Stake being deactivated counts towards the reserve.
+pub(crate) fn plan_rebalance(
+    validators: &[ValidatorStake],
+    weights: &TargetWeights,
+    limits: &RebalanceLimits,
+) -> Result<RebalancePlan, Error> {
+    if validators.is_empty() {
+        return Err("Stake pool has no active validators".into());
+    }
+    let vote_accounts: Vec<Pubkey> = validators.iter().map(|v| v.vote_account).collect();
+    let shares = weights.shares(&vote_accounts)?;
+
+    let reserve_floor = limits.stake_rent.saturating_add(MINIMUM_RESERVE_LAMPORTS);
+    let validator_floor = limits.stake_rent.saturating_add(limits.minimum_delegation);
+    let current: Vec<u64> = validators
+        .iter()
+        .map(|v| match v.transient {
+            TransientStake::Activating(lamports) => v.active_lamports.saturating_add(lamports),
+            _ => v.active_lamports,
+        })
+        .collect();
+    let deactivating: u64 = validators
+        .iter()
+        .map(|v| match v.transient {
+            TransientStake::Deactivating(lamports) => lamports,
+            _ => 0,
+        })
+        .sum();
+    let total = current
+        .iter()
+        .sum::<u64>()
+        .saturating_add(deactivating)
+        .saturating_add(limits.reserve_lamports.saturating_sub(reserve_floor));
+
+    let targets: Vec<u64> = shares
+        .iter()
+        .map(|share| ((total as f64 * share) as u64).max(validator_floor))
+        .collect();
+
+    let minimum_amount = limits.minimum_amount.max(limits.minimum_delegation);
+    let mut transient: Vec<TransientStake> = validators.iter().map(|v| v.transient).collect();
+    let mut surpluses = vec![];
+    let mut deficits = vec![];
+    for (index, (&current, &target)) in current.iter().zip(&targets).enumerate() {
+        match current.cmp(&target) {
+            Ordering::Greater => {
+                let removable = validators[index]
+                    .active_lamports
+                    .saturating_sub(validator_floor);
+                surpluses.push(Move {
+                    index,
+                    lamports: (current - target).min(removable),
+                });
+            }
+            Ordering::Less => deficits.push(Move {
+                index,
+                lamports: target - current,
+            }),
+            Ordering::Equal => {}
+        }
+    }
+    surpluses.sort_by_key(|m| Reverse(m.lamports));
+    deficits.sort_by_key(|m| Reverse(m.lamports));
+
+    let mut budget = limits.reserve_lamports.saturating_sub(reserve_floor);
+    let mut actions = vec![];
+    let mut skipped = vec![];
+
+    if limits.allow_redelegate {
+        let minimum_redelegation = minimum_amount.max(validator_floor);
+        for surplus in surpluses.iter_mut() {
+            if transient[surplus.index] != TransientStake::None {
+                continue;
+            }
+            let Some(deficit) = deficits.iter_mut().find(|deficit| {
+                deficit.lamports >= minimum_redelegation
+                    && !matches!(transient[deficit.index], TransientStake::Deactivating(_))
+            }) else {
+                break;
+            };
+            let lamports = surplus.lamports.min(deficit.lamports);
+            if lamports < minimum_redelegation || budget < limits.stake_rent {
+                continue;
+            }
+            budget -= limits.stake_rent;
+            actions.push(RebalanceAction::Redelegate {
+                source_vote_account: vote_accounts[surplus.index],
+                destination_vote_account: vote_accounts[deficit.index],
+                lamports,
+            });
+            transient[surplus.index] = TransientStake::Deactivating(limits.stake_rent);
+            transient[deficit.index] = TransientStake::Activating(lamports);
+            surplus.lamports -= lamports;
+            deficit.lamports -= lamports;
+        }
+    }
+
+    for surplus in surpluses {
+        let vote_account = vote_accounts[surplus.index];
+        if surplus.lamports < minimum_amount {
+            continue;
+        }
+        let additional = match transient[surplus.index] {
+            TransientStake::None => false,
+            TransientStake::Deactivating(_) => true,
+            TransientStake::Activating(_) => {
+                skipped.push(format!(
+                    "Not decreasing {}, stake is already activating this epoch",
+                    vote_account
+                ));
+                continue;
+            }
+        };
+        // the program funds the rent of the transient or ephemeral account
+        // from the reserve, and the reserve cannot be emptied
+        if budget <= limits.stake_rent {
+            skipped.push(format!(
+                "Not decreasing {}, reserve cannot fund the transient stake rent",
+                vote_account
+            ));
+            continue;
+        }
+        budget -= limits.stake_rent;
+        actions.push(RebalanceAction::Decrease {
+            vote_account,
+            lamports: surplus.lamports,
+            additional,
+        });
+    }
+
+    for deficit in deficits {
+        let vote_account = vote_accounts[deficit.index];
+        if deficit.lamports < minimum_amount {
+            continue;
+        }
+        let additional = match transient[deficit.index] {
+            TransientStake::None => false,
+            TransientStake::Activating(_) => true,
+            TransientStake::Deactivating(_) => {
+                skipped.push(format!(
+                    "Not increasing {}, stake is already deactivating this epoch",
+                    vote_account
+                ));
+                continue;
+            }
+        };
+        // each increase creates a stake account with `lamports + stake_rent`
+        let lamports = deficit
+            .lamports
+            .min(budget.saturating_sub(limits.stake_rent));
+        if lamports < minimum_amount {
+            skipped.push(format!(
+                "Not increasing {}, reserve has too little stake available",
+                vote_account
+            ));
+            continue;
+        }
+        budget -= lamports + limits.stake_rent;
+        actions.push(RebalanceAction::Increase {
+            vote_account,
+            lamports,
+            additional,
+        });
+    }
+
+    Ok(RebalancePlan {
+        validators: validators
+            .iter()
+            .zip(current)
+            .zip(targets)
+            .map(
+                |((validator, current_lamports), target_lamports)| ValidatorTarget {
+                    vote_account: validator.vote_account,
+                    current_lamports,
+                    target_lamports,
+                },
+            )
+            .collect(),
+        actions,
+        skipped,
+        reserve_lamports: limits.reserve_lamports,
+        remaining_reserve_lamports: budget.saturating_add(reserve_floor),
+    })
+}
@@ -0,0 +1,293 @@
+//! Per-epoch snapshots of a stake pool and yield reporting between them
+
+use {
+    serde::{Deserialize, Serialize},
+    solana_program::{clock::UnixTimestamp, pubkey::Pubkey},
+    spl_stake_pool::state::{Fee, StakePool, ValidatorList},
+    std::{collections::BTreeMap, fmt::Write, fs, path::Path, str::FromStr},
+};
+
+type Error = Box<dyn std::error::Error>;
+
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// Snapshots of one stake pool, persisted as JSON between runs
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotStore {
+    pub stake_pool: String,
+    pub snapshots: Vec<PoolSnapshot>,
+}
+
+/// Pool totals and validator stakes right after the pool's update in `epoch`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PoolSnapshot {
+    pub epoch: u64,
+    pub epoch_start_timestamp: UnixTimestamp,
+    pub total_lamports: u64,
+    pub pool_token_supply: u64,
+    pub epoch_fee_numerator: u64,
+    pub epoch_fee_denominator: u64,
+    pub validators: Vec<ValidatorSnapshot>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ValidatorSnapshot {
+    pub vote_account_address: String,
+    pub active_stake_lamports: u64,
+    pub transient_stake_lamports: u64,
+    /// Inflation rewards paid to the validator stake account for the previous
+    /// epoch, if the cluster still had them available when recorded
+    pub last_epoch_rewards: Option<u64>,
+}
+
+impl SnapshotStore {
+    /// Load the store at `path`, or start an empty one if it does not exist
+    pub fn load(path: &Path, stake_pool_address: &Pubkey) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self {
+                stake_pool: stake_pool_address.to_string(),
+                snapshots: vec![],
+            });
+        }
+        let store: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if store.stake_pool != stake_pool_address.to_string() {
+            return Err(format!(
+                "Snapshot store {} belongs to stake pool {}",
+                path.display(),
+                store.stake_pool
+            )
+            .into());
+        }
+        Ok(store)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Add a snapshot, replacing any earlier one for the same epoch
+    pub fn insert(&mut self, snapshot: PoolSnapshot) {
+        match self
+            .snapshots
+            .binary_search_by_key(&snapshot.epoch, |s| s.epoch)
+        {
+            Ok(index) => self.snapshots[index] = snapshot,
+            Err(index) => self.snapshots.insert(index, snapshot),
+        }
+    }
+
+    fn get(&self, epoch: u64) -> Result<&PoolSnapshot, Error> {
+        self.snapshots
+            .iter()
+            .find(|snapshot| snapshot.epoch == epoch)
+            .ok_or_else(|| format!("No snapshot recorded for epoch {}", epoch).into())
+    }
+
+    /// One row per validator per snapshot, with the pool totals repeated on
+    /// each row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "epoch,epoch_start_timestamp,total_lamports,pool_token_supply,vote_account_address,active_stake_lamports,transient_stake_lamports,last_epoch_rewards\n",
+        );
+        for snapshot in &self.snapshots {
+            for validator in &snapshot.validators {
+                let _ = writeln!(
+                    csv,
+                    "{},{},{},{},{},{},{},{}",
+                    snapshot.epoch,
+                    snapshot.epoch_start_timestamp,
+                    snapshot.total_lamports,
+                    snapshot.pool_token_supply,
+                    validator.vote_account_address,
+                    validator.active_stake_lamports,
+                    validator.transient_stake_lamports,
+                    validator
+                        .last_epoch_rewards
+                        .map(|rewards| rewards.to_string())
+                        .unwrap_or_default(),
+                );
+            }
+        }
+        csv
+    }
+}
+
+impl PoolSnapshot {
+    /// `last_epoch_rewards` is in validator list order
+    pub fn new(
+        epoch: u64,
+        epoch_start_timestamp: UnixTimestamp,
+        stake_pool: &StakePool,
+        validator_list: &ValidatorList,
+        last_epoch_rewards: &[Option<u64>],
+    ) -> Self {
+        Self {
+            epoch,
+            epoch_start_timestamp,
+            total_lamports: stake_pool.total_lamports,
+            pool_token_supply: stake_pool.pool_token_supply,
+            epoch_fee_numerator: stake_pool.epoch_fee.numerator,
+            epoch_fee_denominator: stake_pool.epoch_fee.denominator,
+            validators: validator_list
+                .validators
+                .iter()
+                .zip(last_epoch_rewards)
+                .map(|(validator, rewards)| ValidatorSnapshot {
+                    vote_account_address: validator.vote_account_address.to_string(),
+                    active_stake_lamports: validator.active_stake_lamports.into(),
+                    transient_stake_lamports: validator.transient_stake_lamports.into(),
+                    last_epoch_rewards: *rewards,
+                })
+                .collect(),
+        }
+    }
+
+    /// Lamports per pool token
+    fn exchange_rate(&self) -> f64 {
+        if self.pool_token_supply == 0 {
+            1.0
+        } else {
+            self.total_lamports as f64 / self.pool_token_supply as f64
+        }
+    }
+
+    fn epoch_fee(&self) -> Fee {
+        Fee {
+            numerator: self.epoch_fee_numerator,
+            denominator: self.epoch_fee_denominator,
+        }
+    }
+}
+
+/// Yield of a validator between two snapshots
+pub(crate) struct ValidatorYield {
+    pub vote_account: Pubkey,
+    pub rewards: u64,
+    pub starting_stake_lamports: u64,
+    pub rewards_share: f64,
+    pub period_yield: f64,
+}
+
+/// Yield of the pool between two snapshots
+pub(crate) struct PoolReport {
+    pub from_epoch: u64,
+    pub to_epoch: u64,
+    pub elapsed_seconds: i64,
+    pub from_exchange_rate: f64,
+    pub to_exchange_rate: f64,
+    pub exchange_rate_growth: f64,
+    pub apy: Option<f64>,
+    pub rewards: u64,
+    pub fee_lamports: u64,
+    /// Epochs in the range whose rewards are known for every validator
+    pub epochs_with_rewards: u64,
+    pub validators: Vec<ValidatorYield>,
+}
+
+/// Compare the snapshots recorded for `from_epoch` and `to_epoch`. Rewards
+/// and fees add up the snapshots after `from_epoch` up to and including
+/// `to_epoch`, since each records rewards for the epoch before it.
+pub(crate) fn build_report(
+    store: &SnapshotStore,
+    from_epoch: u64,
+    to_epoch: u64,
+) -> Result<PoolReport, Error> {
+    if from_epoch >= to_epoch {
+        return Err(format!(
+            "Report start epoch {} must be before end epoch {}",
+            from_epoch, to_epoch
+        )
+        .into());
+    }
+    let from = store.get(from_epoch)?;
+    let to = store.get(to_epoch)?;
+
+    let from_exchange_rate = from.exchange_rate();
+    let to_exchange_rate = to.exchange_rate();
+    let exchange_rate_growth = to_exchange_rate / from_exchange_rate - 1.0;
+    let elapsed_seconds = to.epoch_start_timestamp - from.epoch_start_timestamp;
+    let apy = (elapsed_seconds > 0).then(|| {
+        (1.0 + exchange_rate_growth).powf(SECONDS_PER_YEAR / elapsed_seconds as f64) - 1.0
+    });
+
+    let mut rewards = 0u64;
+    let mut fee_lamports = 0u64;
+    let mut epochs_with_rewards = 0u64;
+    let mut validator_rewards: BTreeMap<String, u64> = BTreeMap::new();
+    for snapshot in store
+        .snapshots
+        .iter()
+        .filter(|snapshot| snapshot.epoch > from_epoch && snapshot.epoch <= to_epoch)
+    {
+        let mut epoch_rewards = 0u64;
+        for validator in &snapshot.validators {
+            let validator_epoch_rewards = validator.last_epoch_rewards.unwrap_or(0);
+            epoch_rewards = epoch_rewards.saturating_add(validator_epoch_rewards);
+            *validator_rewards
+                .entry(validator.vote_account_address.clone())
+                .or_default() += validator_epoch_rewards;
+        }
+        if snapshot
+            .validators
+            .iter()
+            .all(|validator| validator.last_epoch_rewards.is_some())
+        {
+            epochs_with_rewards += 1;
+        }
+        rewards = rewards.saturating_add(epoch_rewards);
+        fee_lamports = fee_lamports
+            .saturating_add(snapshot.epoch_fee().apply(epoch_rewards).unwrap_or(0) as u64);
+    }
+
+    let validators = validator_rewards
+        .into_iter()
+        .map(|(vote_account_address, validator_rewards)| {
+            // stake at the start of the range, or when the validator was added
+            let starting_stake_lamports = store
+                .snapshots
+                .iter()
+                .filter(|snapshot| snapshot.epoch >= from_epoch && snapshot.epoch <= to_epoch)
+                .find_map(|snapshot| {
+                    snapshot
+                        .validators
+                        .iter()
+                        .find(|validator| validator.vote_account_address == vote_account_address)
+                })
+                .map(|validator| validator.active_stake_lamports)
+                .unwrap_or(0);
+            Ok(ValidatorYield {
+                vote_account: Pubkey::from_str(&vote_account_address)?,
+                rewards: validator_rewards,
+                starting_stake_lamports,
+                rewards_share: if rewards == 0 {
+                    0.0
+                } else {
+                    validator_rewards as f64 / rewards as f64
+                },
+                period_yield: if starting_stake_lamports == 0 {
+                    0.0
+                } else {
+                    validator_rewards as f64 / starting_stake_lamports as f64
+                },
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(PoolReport {
+        from_epoch,
+        to_epoch,
+        elapsed_seconds,
+        from_exchange_rate,
+        to_exchange_rate,
+        exchange_rate_growth,
+        apy,
+        rewards,
+        fee_lamports,
+        epochs_with_rewards,
+        validators,
+    })
+}
@@ -21,6 +21,7 @@ import {
   calcLamportsWithdrawAmount,
   findStakeProgramAddress,
   findTransientStakeProgramAddress,
+  findValidatorPolicyProgramAddress,
   findWithdrawAuthorityProgramAddress,
   getValidatorListAccount,
   newStakeAccount,
@@ -688,6 +689,11 @@ export async function addValidatorToPool(
     seed,
   );
 
+  const validatorPolicy = await findValidatorPolicyProgramAddress(
+    STAKE_POOL_PROGRAM_ID,
+    stakePoolAddress,
+  );
+
   const instructions: TransactionInstruction[] = [
     StakePoolInstruction.addValidatorToPool({
       stakePool: stakePoolAddress,
@@ -697,6 +703,7 @@ export async function addValidatorToPool(
       validatorList: validatorList,
       validatorStake: validatorStake,
       validatorVote: validatorVote,
+      validatorPolicy: validatorPolicy,
     }),
   ];
 
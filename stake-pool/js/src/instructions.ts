@@ -394,6 +394,7 @@ export type AddValidatorToPoolParams = {
   validatorList: PublicKey;
   validatorStake: PublicKey;
   validatorVote: PublicKey;
+  validatorPolicy: PublicKey;
   seed?: number;
 };
 
@@ -422,6 +423,7 @@ export class StakePoolInstruction {
       validatorList,
       validatorStake,
       validatorVote,
+      validatorPolicy,
       seed,
     } = params;
     const type = STAKE_POOL_INSTRUCTION_LAYOUTS.AddValidatorToPool;
@@ -441,6 +443,7 @@ export class StakePoolInstruction {
       { pubkey: STAKE_CONFIG_ID, isSigner: false, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       { pubkey: StakeProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: validatorPolicy, isSigner: false, isWritable: false },
     ];
 
     return new TransactionInstruction({
@@ -21,6 +21,20 @@ export async function findWithdrawAuthorityProgramAddress(
   return publicKey;
 }
 
+/**
+ * Generates the validator policy program address for the stake pool
+ */
+export async function findValidatorPolicyProgramAddress(
+  programId: PublicKey,
+  stakePoolAddress: PublicKey,
+) {
+  const [publicKey] = await PublicKey.findProgramAddress(
+    [Buffer.from('policy'), stakePoolAddress.toBuffer()],
+    programId,
+  );
+  return publicKey;
+}
+
 /**
  * Generates the stake program address for a validator's vote account
  */
@@ -37,7 +37,11 @@ import {
 } from '../src';
 import { STAKE_POOL_PROGRAM_ID } from '../src/constants';
 
-import { decodeData, findStakeProgramAddress } from '../src/utils';
+import {
+  decodeData,
+  findStakeProgramAddress,
+  findValidatorPolicyProgramAddress,
+} from '../src/utils';
 
 import {
   mockRpc,
@@ -77,11 +81,12 @@ describe('StakePoolProgram', () => {
       validatorList: Keypair.generate().publicKey,
       validatorStake: Keypair.generate().publicKey,
       validatorVote: PublicKey.default,
+      validatorPolicy: Keypair.generate().publicKey,
       seed: 0,
     };
 
     const instruction = StakePoolInstruction.addValidatorToPool(payload);
-    expect(instruction.keys).toHaveLength(13);
+    expect(instruction.keys).toHaveLength(14);
     expect(instruction.keys[0].pubkey).toEqual(payload.stakePool);
     expect(instruction.keys[1].pubkey).toEqual(payload.staker);
     expect(instruction.keys[2].pubkey).toEqual(payload.reserveStake);
@@ -91,6 +96,7 @@ describe('StakePoolProgram', () => {
     expect(instruction.keys[6].pubkey).toEqual(payload.validatorVote);
     expect(instruction.keys[11].pubkey).toEqual(SystemProgram.programId);
     expect(instruction.keys[12].pubkey).toEqual(StakeProgram.programId);
+    expect(instruction.keys[13].pubkey).toEqual(payload.validatorPolicy);
 
     const decodedData = decodeData(
       STAKE_POOL_INSTRUCTION_LAYOUTS.AddValidatorToPool,
@@ -209,6 +215,9 @@ describe('StakePoolProgram', () => {
       expect(res.instructions[0].keys[6].pubkey).toEqual(
         validatorListMock.validators[0].voteAccountAddress,
       );
+      expect(res.instructions[0].keys[13].pubkey).toEqual(
+        await findValidatorPolicyProgramAddress(STAKE_POOL_PROGRAM_ID, stakePoolAddress),
+      );
     });
   });
 
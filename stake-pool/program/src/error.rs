@@ -164,6 +164,15 @@ pub enum StakePoolError {
     /// Missing required sysvar account
     #[error("Missing required sysvar account")]
     MissingRequiredSysvar,
+    /// Validator does not satisfy the pool's validator policy
+    #[error("ValidatorPolicyViolation")]
+    ValidatorPolicyViolation,
+    /// Validator satisfies the pool's validator policy and cannot be ejected
+    #[error("ValidatorCompliesWithPolicy")]
+    ValidatorCompliesWithPolicy,
+    /// Metadata stored in the pool mint is not controlled by the pool
+    #[error("InvalidMintMetadataAuthority")]
+    InvalidMintMetadataAuthority,
 }
 impl From<StakePoolError> for ProgramError {
     fn from(e: StakePoolError) -> Self {
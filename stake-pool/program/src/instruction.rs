@@ -6,9 +6,9 @@ use {
     crate::{
         find_deposit_authority_program_address, find_ephemeral_stake_program_address,
         find_stake_program_address, find_transient_stake_program_address,
-        find_withdraw_authority_program_address,
+        find_validator_policy_program_address, find_withdraw_authority_program_address,
         inline_mpl_token_metadata::{self, pda::find_metadata_account},
-        state::{Fee, FeeType, StakePool, ValidatorList, ValidatorStakeInfo},
+        state::{Fee, FeeType, StakePool, ValidatorFilter, ValidatorList, ValidatorStakeInfo},
         MAX_VALIDATORS_TO_UPDATE,
     },
     borsh::{BorshDeserialize, BorshSchema, BorshSerialize},
@@ -61,7 +61,9 @@ pub enum StakePoolInstruction {
     ///   5. `[]` Reserve stake account must be initialized, have zero balance,
     ///      and staker / withdrawer authority set to pool withdraw authority.
     ///   6. `[]` Pool token mint. Must have zero supply, owned by withdraw
-    ///      authority.
+    ///      authority. A Token-2022 mint may carry a metadata pointer and
+    ///      token metadata, as long as their authorities are unset or the
+    ///      withdraw authority.
     ///   7. `[]` Pool account to deposit the generated fee for manager.
     ///   8. `[]` Token program id
     ///   9. `[]` (Optional) Deposit authority that must sign all deposits.
@@ -104,6 +106,9 @@ pub enum StakePoolInstruction {
     ///  10. '[]' Stake config sysvar
     ///  11. `[]` System program
     ///  12. `[]` Stake program
+    ///  13. `[]` Validator policy account, derived with
+    ///      `find_validator_policy_program_address`. If it is initialized, the
+    ///      validator must satisfy the policy.
     ///
     ///  userdata: optional non-zero u32 seed used for generating the validator
     ///  stake address
@@ -398,14 +403,15 @@ pub enum StakePoolInstruction {
     WithdrawSol(u64),
 
     /// Create token metadata for the stake-pool token in the
-    /// metaplex-token program
+    /// metaplex-token program, or directly in the pool mint if it is a
+    /// Token-2022 mint whose metadata pointer refers to itself
     /// 0. `[]` Stake pool
     /// 1. `[s]` Manager
     /// 2. `[]` Stake pool withdraw authority
     /// 3. `[]` Pool token mint account
     /// 4. `[s, w]` Payer for creation of token metadata account
-    /// 5. `[w]` Token metadata account
-    /// 6. `[]` Metadata program id
+    /// 5. `[w]` Token metadata account, or the pool mint for Token-2022
+    /// 6. `[]` Metadata program id, or the Token-2022 program id
     /// 7. `[]` System program id
     CreateTokenMetadata {
         /// Token name
@@ -416,13 +422,16 @@ pub enum StakePoolInstruction {
         uri: String,
     },
     /// Update token metadata for the stake-pool token in the
-    /// metaplex-token program
+    /// metaplex-token program, or directly in the pool mint if it is a
+    /// Token-2022 mint whose metadata pointer refers to itself
     ///
     /// 0. `[]` Stake pool
     /// 1. `[s]` Manager
     /// 2. `[]` Stake pool withdraw authority
-    /// 3. `[w]` Token metadata account
-    /// 4. `[]` Metadata program id
+    /// 3. `[w]` Token metadata account, or the pool mint for Token-2022
+    /// 4. `[]` Metadata program id, or the Token-2022 program id
+    /// 5. `[s, w]` (Optional) Payer to fund rent if Token-2022 metadata grows
+    /// 6. `[]` (Optional) System program id, required with the payer
     UpdateTokenMetadata {
         /// Token name
         name: String,
@@ -722,6 +731,46 @@ pub enum StakePoolInstruction {
         /// Minimum amount of lamports that must be received
         minimum_lamports_out: u64,
     },
+
+    ///   (Manager only) Create or update the pool's validator policy, which
+    ///   restricts the validators the staker can add and allows anyone to
+    ///   eject validators charging more than the maximum commission.
+    ///
+    ///   `minimum_activated_stake` is stored for clients but is not enforced
+    ///   by `AddValidatorToPool`, since programs cannot read a vote account's
+    ///   activated stake. The `spl-stake-pool` CLI checks it before adding.
+    ///
+    ///   0. `[]` Stake pool
+    ///   1. `[s]` Manager
+    ///   2. `[w]` Validator policy account, derived with
+    ///      `find_validator_policy_program_address`
+    ///   3. `[s, w]` Payer for the policy account's rent
+    ///   4. `[]` System program
+    SetValidatorPolicy {
+        /// Highest commission, in percent, allowed for pool validators
+        max_commission: u8,
+        /// Minimum activated stake on a vote account, checked by clients only
+        minimum_activated_stake: u64,
+        /// How `vote_accounts` is applied
+        filter: ValidatorFilter,
+        /// Allowed or denied vote accounts
+        vote_accounts: Vec<Pubkey>,
+    },
+
+    ///   Removes a validator whose commission exceeds the pool's validator
+    ///   policy, deactivating its stake as `RemoveValidatorFromPool` does.
+    ///   Anyone can call it.
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[]` Validator policy account
+    ///   2. `[]` Stake pool withdraw authority
+    ///   3. `[w]` Validator stake list storage account
+    ///   4. `[w]` Stake account to remove from the pool
+    ///   5. `[w]` Transient stake account, to deactivate if necessary
+    ///   6. `[]` Validator vote account
+    ///   7. `[]` Sysvar clock
+    ///   8. `[]` Stake program id,
+    EjectValidator,
 }
 
 /// Creates an 'initialize' instruction.
@@ -800,6 +849,10 @@ pub fn add_validator_to_pool(
         AccountMeta::new_readonly(stake::config::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
         AccountMeta::new_readonly(stake::program::id(), false),
+        AccountMeta::new_readonly(
+            find_validator_policy_program_address(program_id, stake_pool).0,
+            false,
+        ),
     ];
     let data = borsh::to_vec(&StakePoolInstruction::AddValidatorToPool(
         seed.map(|s| s.get()).unwrap_or(0),
@@ -840,6 +893,36 @@ pub fn remove_validator_from_pool(
     }
 }
 
+/// Creates `EjectValidator` instruction (remove a validator violating the
+/// pool's validator policy)
+pub fn eject_validator(
+    program_id: &Pubkey,
+    stake_pool: &Pubkey,
+    stake_pool_withdraw: &Pubkey,
+    validator_list: &Pubkey,
+    stake_account: &Pubkey,
+    transient_stake_account: &Pubkey,
+    vote_account: &Pubkey,
+) -> Instruction {
+    let (validator_policy, _) = find_validator_policy_program_address(program_id, stake_pool);
+    let accounts = vec![
+        AccountMeta::new(*stake_pool, false),
+        AccountMeta::new_readonly(validator_policy, false),
+        AccountMeta::new_readonly(*stake_pool_withdraw, false),
+        AccountMeta::new(*validator_list, false),
+        AccountMeta::new(*stake_account, false),
+        AccountMeta::new(*transient_stake_account, false),
+        AccountMeta::new_readonly(*vote_account, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(stake::program::id(), false),
+    ];
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: borsh::to_vec(&StakePoolInstruction::EjectValidator).unwrap(),
+    }
+}
+
 /// Creates `DecreaseValidatorStake` instruction (rebalance from validator
 /// account to transient account)
 #[deprecated(
@@ -1186,6 +1269,41 @@ pub fn remove_validator_from_pool_with_vote(
     )
 }
 
+/// Create an `EjectValidator` instruction given an existing stake pool and
+/// vote account
+pub fn eject_validator_with_vote(
+    program_id: &Pubkey,
+    stake_pool: &StakePool,
+    stake_pool_address: &Pubkey,
+    vote_account_address: &Pubkey,
+    validator_stake_seed: Option<NonZeroU32>,
+    transient_stake_seed: u64,
+) -> Instruction {
+    let pool_withdraw_authority =
+        find_withdraw_authority_program_address(program_id, stake_pool_address).0;
+    let (stake_account_address, _) = find_stake_program_address(
+        program_id,
+        vote_account_address,
+        stake_pool_address,
+        validator_stake_seed,
+    );
+    let (transient_stake_account, _) = find_transient_stake_program_address(
+        program_id,
+        vote_account_address,
+        stake_pool_address,
+        transient_stake_seed,
+    );
+    eject_validator(
+        program_id,
+        stake_pool_address,
+        &pool_withdraw_authority,
+        &stake_pool.validator_list,
+        &stake_account_address,
+        &transient_stake_account,
+        vote_account_address,
+    )
+}
+
 /// Create an `IncreaseValidatorStake` instruction given an existing stake pool
 /// and vote account
 pub fn increase_validator_stake_with_vote(
@@ -1356,6 +1474,70 @@ pub fn decrease_additional_validator_stake_with_vote(
     )
 }
 
+/// Create a `Redelegate` instruction given an existing stake pool and the
+/// source and destination vote accounts
+pub fn redelegate_with_vote(
+    program_id: &Pubkey,
+    stake_pool: &StakePool,
+    stake_pool_address: &Pubkey,
+    source_vote_account_address: &Pubkey,
+    source_validator_stake_seed: Option<NonZeroU32>,
+    source_transient_stake_seed: u64,
+    ephemeral_stake_seed: u64,
+    destination_vote_account_address: &Pubkey,
+    destination_validator_stake_seed: Option<NonZeroU32>,
+    destination_transient_stake_seed: u64,
+    lamports: u64,
+) -> Instruction {
+    let pool_withdraw_authority =
+        find_withdraw_authority_program_address(program_id, stake_pool_address).0;
+    let (source_validator_stake_address, _) = find_stake_program_address(
+        program_id,
+        source_vote_account_address,
+        stake_pool_address,
+        source_validator_stake_seed,
+    );
+    let (source_transient_stake_address, _) = find_transient_stake_program_address(
+        program_id,
+        source_vote_account_address,
+        stake_pool_address,
+        source_transient_stake_seed,
+    );
+    let (ephemeral_stake_address, _) =
+        find_ephemeral_stake_program_address(program_id, stake_pool_address, ephemeral_stake_seed);
+    let (destination_transient_stake_address, _) = find_transient_stake_program_address(
+        program_id,
+        destination_vote_account_address,
+        stake_pool_address,
+        destination_transient_stake_seed,
+    );
+    let (destination_validator_stake_address, _) = find_stake_program_address(
+        program_id,
+        destination_vote_account_address,
+        stake_pool_address,
+        destination_validator_stake_seed,
+    );
+
+    redelegate(
+        program_id,
+        stake_pool_address,
+        &stake_pool.staker,
+        &pool_withdraw_authority,
+        &stake_pool.validator_list,
+        &stake_pool.reserve_stake,
+        &source_validator_stake_address,
+        &source_transient_stake_address,
+        &ephemeral_stake_address,
+        &destination_transient_stake_address,
+        &destination_validator_stake_address,
+        destination_vote_account_address,
+        lamports,
+        source_transient_stake_seed,
+        ephemeral_stake_seed,
+        destination_transient_stake_seed,
+    )
+}
+
 /// Creates `UpdateValidatorListBalance` instruction (update validator stake
 /// account balances)
 #[deprecated(
@@ -2571,3 +2753,102 @@ pub fn create_token_metadata(
             .unwrap(),
     }
 }
+
+/// Creates an instruction to update the token metadata stored in a Token-2022
+/// pool mint
+pub fn update_token_2022_metadata(
+    program_id: &Pubkey,
+    stake_pool: &Pubkey,
+    manager: &Pubkey,
+    pool_mint: &Pubkey,
+    payer: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let (stake_pool_withdraw_authority, _) =
+        find_withdraw_authority_program_address(program_id, stake_pool);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*stake_pool, false),
+        AccountMeta::new_readonly(*manager, true),
+        AccountMeta::new_readonly(stake_pool_withdraw_authority, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: borsh::to_vec(&StakePoolInstruction::UpdateTokenMetadata { name, symbol, uri })
+            .unwrap(),
+    }
+}
+
+/// Creates an instruction to create token metadata directly in a Token-2022
+/// pool mint. The mint's metadata pointer must refer to the mint itself.
+pub fn create_token_2022_metadata(
+    program_id: &Pubkey,
+    stake_pool: &Pubkey,
+    manager: &Pubkey,
+    pool_mint: &Pubkey,
+    payer: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let (stake_pool_withdraw_authority, _) =
+        find_withdraw_authority_program_address(program_id, stake_pool);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*stake_pool, false),
+        AccountMeta::new_readonly(*manager, true),
+        AccountMeta::new_readonly(stake_pool_withdraw_authority, false),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*pool_mint, false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: borsh::to_vec(&StakePoolInstruction::CreateTokenMetadata { name, symbol, uri })
+            .unwrap(),
+    }
+}
+
+/// Creates a `SetValidatorPolicy` instruction
+pub fn set_validator_policy(
+    program_id: &Pubkey,
+    stake_pool: &Pubkey,
+    manager: &Pubkey,
+    payer: &Pubkey,
+    max_commission: u8,
+    minimum_activated_stake: u64,
+    filter: ValidatorFilter,
+    vote_accounts: Vec<Pubkey>,
+) -> Instruction {
+    let (validator_policy, _) = find_validator_policy_program_address(program_id, stake_pool);
+    let accounts = vec![
+        AccountMeta::new_readonly(*stake_pool, false),
+        AccountMeta::new_readonly(*manager, true),
+        AccountMeta::new(validator_policy, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: borsh::to_vec(&StakePoolInstruction::SetValidatorPolicy {
+            max_commission,
+            minimum_activated_stake,
+            filter,
+            vote_accounts,
+        })
+        .unwrap(),
+    }
+}
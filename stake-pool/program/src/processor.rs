@@ -11,20 +11,20 @@ use {
             state::DataV2,
         },
         instruction::{FundingType, PreferredValidatorType, StakePoolInstruction},
-        minimum_delegation, minimum_reserve_lamports, minimum_stake_lamports,
+        minimum_delegation, minimum_reserve_lamports, minimum_stake_lamports, quote,
         state::{
             is_extension_supported_for_mint, AccountType, Fee, FeeType, FutureEpoch, StakePool,
-            StakeStatus, StakeWithdrawSource, ValidatorList, ValidatorListHeader,
-            ValidatorStakeInfo,
+            StakeStatus, StakeWithdrawSource, ValidatorFilter, ValidatorList, ValidatorListHeader,
+            ValidatorPolicy, ValidatorStakeInfo,
         },
         AUTHORITY_DEPOSIT, AUTHORITY_WITHDRAW, EPHEMERAL_STAKE_SEED_PREFIX,
-        TRANSIENT_STAKE_SEED_PREFIX,
+        TRANSIENT_STAKE_SEED_PREFIX, VALIDATOR_POLICY_SEED_PREFIX,
     },
     borsh::BorshDeserialize,
     num_traits::FromPrimitive,
     solana_program::{
         account_info::{next_account_info, AccountInfo},
-        borsh1::try_from_slice_unchecked,
+        borsh1::{get_instance_packed_len, try_from_slice_unchecked},
         clock::{Clock, Epoch},
         decode_error::DecodeError,
         entrypoint::ProgramResult,
@@ -35,13 +35,20 @@ use {
         rent::Rent,
         stake, system_instruction, system_program,
         sysvar::Sysvar,
+        vote,
     },
     spl_token_2022::{
         check_spl_token_program_account,
-        extension::{BaseStateWithExtensions, StateWithExtensions},
+        extension::{
+            metadata_pointer::MetadataPointer, BaseStateWithExtensions, StateWithExtensions,
+        },
         native_mint,
         state::Mint,
     },
+    spl_token_metadata_interface::{
+        instruction::{initialize as initialize_token_metadata, update_field},
+        state::{Field, TokenMetadata},
+    },
     std::num::NonZeroU32,
 };
 
@@ -133,6 +140,58 @@ fn check_mpl_metadata_account_address(
     }
 }
 
+/// Check if the pool mint is a Token-2022 mint whose metadata pointer refers to
+/// the mint itself, meaning that token metadata is stored directly in the mint
+fn mint_holds_metadata(pool_mint_info: &AccountInfo) -> Result<bool, ProgramError> {
+    if *pool_mint_info.owner != spl_token_2022::id() {
+        return Ok(false);
+    }
+    let pool_mint_data = pool_mint_info.try_borrow_data()?;
+    let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+    let metadata_address = pool_mint
+        .get_extension::<MetadataPointer>()
+        .ok()
+        .and_then(|pointer| Option::<Pubkey>::from(pointer.metadata_address));
+    Ok(metadata_address == Some(*pool_mint_info.key))
+}
+
+/// Transfer enough lamports from the payer to keep the pool mint rent-exempt
+/// once it holds the given token metadata
+fn fund_mint_metadata_rent<'a>(
+    payer_info: &AccountInfo<'a>,
+    pool_mint_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    token_metadata: &TokenMetadata,
+) -> ProgramResult {
+    let new_account_len = {
+        let pool_mint_data = pool_mint_info.try_borrow_data()?;
+        let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+        pool_mint.try_get_new_account_len_for_variable_len_extension(token_metadata)?
+    };
+    let required_lamports = Rent::get()?.minimum_balance(new_account_len);
+    let current_lamports = pool_mint_info.lamports();
+    if required_lamports > current_lamports {
+        if !payer_info.is_signer {
+            msg!("Payer did not sign metadata rent top-up");
+            return Err(StakePoolError::SignatureMissing.into());
+        }
+        check_system_program(system_program_info.key)?;
+        invoke(
+            &system_instruction::transfer(
+                payer_info.key,
+                pool_mint_info.key,
+                required_lamports.saturating_sub(current_lamports),
+            ),
+            &[
+                payer_info.clone(),
+                pool_mint_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
 /// Check system program address
 fn check_system_program(program_id: &Pubkey) -> Result<(), ProgramError> {
     if *program_id != system_program::id() {
@@ -161,6 +220,20 @@ fn check_stake_program(program_id: &Pubkey) -> Result<(), ProgramError> {
     }
 }
 
+/// Check Token-2022 program address, which holds metadata in the mint itself
+fn check_token_2022_program(program_id: &Pubkey) -> Result<(), ProgramError> {
+    if *program_id != spl_token_2022::id() {
+        msg!(
+            "Expected token-2022 program {}, received {}",
+            spl_token_2022::id(),
+            program_id
+        );
+        Err(ProgramError::IncorrectProgramId)
+    } else {
+        Ok(())
+    }
+}
+
 /// Check mpl metadata program
 fn check_mpl_metadata_program(program_id: &Pubkey) -> Result<(), ProgramError> {
     if *program_id != inline_mpl_token_metadata::id() {
@@ -192,6 +265,72 @@ fn check_account_owner(
     }
 }
 
+/// Check address validity for the validator policy of a stake pool
+fn check_validator_policy_address(
+    program_id: &Pubkey,
+    stake_pool_address: &Pubkey,
+    validator_policy_address: &Pubkey,
+) -> Result<u8, ProgramError> {
+    let (address, bump_seed) =
+        crate::find_validator_policy_program_address(program_id, stake_pool_address);
+    if address != *validator_policy_address {
+        msg!(
+            "Incorrect validator policy address, expected {}, received {}",
+            address,
+            validator_policy_address
+        );
+        Err(StakePoolError::InvalidProgramAddress.into())
+    } else {
+        Ok(bump_seed)
+    }
+}
+
+/// Deserialize the validator policy of a stake pool, or `None` if the pool
+/// does not have one
+fn get_validator_policy(
+    program_id: &Pubkey,
+    stake_pool_address: &Pubkey,
+    validator_policy_info: &AccountInfo,
+) -> Result<Option<ValidatorPolicy>, ProgramError> {
+    check_validator_policy_address(program_id, stake_pool_address, validator_policy_info.key)?;
+    if validator_policy_info.owner != program_id {
+        return Ok(None);
+    }
+    let validator_policy =
+        try_from_slice_unchecked::<ValidatorPolicy>(&validator_policy_info.data.borrow())?;
+    if !validator_policy.is_valid() {
+        return Err(StakePoolError::InvalidState.into());
+    }
+    Ok(Some(validator_policy))
+}
+
+/// Read the commission of a vote account.
+///
+/// `VoteState` cannot be deserialized on-chain, so the commission is read at
+/// its offset after the version tag, node pubkey and authorized withdrawer,
+/// which is the same in the current and 1.14.11 layouts.
+fn get_vote_account_commission(vote_account_info: &AccountInfo) -> Result<u8, ProgramError> {
+    const COMMISSION_OFFSET: usize = 4 + 32 + 32;
+    check_account_owner(vote_account_info, &vote::program::id())?;
+    let data = vote_account_info.data.borrow();
+    let version = data
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()));
+    match version {
+        Some(1) | Some(2) => data
+            .get(COMMISSION_OFFSET)
+            .copied()
+            .ok_or(ProgramError::InvalidAccountData),
+        _ => {
+            msg!(
+                "Unsupported vote account version for {}",
+                vote_account_info.key
+            );
+            Err(ProgramError::InvalidAccountData)
+        }
+    }
+}
+
 /// Checks if a stake account can be managed by the pool
 fn stake_is_usable_by_pool(
     meta: &stake::state::Meta,
@@ -859,6 +998,22 @@ impl Processor {
             {
                 return Err(StakePoolError::UnsupportedMintExtension.into());
             }
+
+            // metadata stored in the mint must only be changeable by the pool
+            if let Ok(metadata_pointer) = pool_mint.get_extension::<MetadataPointer>() {
+                if Option::<Pubkey>::from(metadata_pointer.authority)
+                    .is_some_and(|authority| authority != withdraw_authority_key)
+                {
+                    return Err(StakePoolError::InvalidMintMetadataAuthority.into());
+                }
+            }
+            if let Ok(token_metadata) = pool_mint.get_variable_len_extension::<TokenMetadata>() {
+                if Option::<Pubkey>::from(token_metadata.update_authority)
+                    .is_some_and(|authority| authority != withdraw_authority_key)
+                {
+                    return Err(StakePoolError::InvalidMintMetadataAuthority.into());
+                }
+            }
         }
         stake_pool.check_manager_fee_info(manager_fee_info)?;
 
@@ -975,6 +1130,7 @@ impl Processor {
         let stake_config_info = next_account_info(account_info_iter)?;
         let system_program_info = next_account_info(account_info_iter)?;
         let stake_program_info = next_account_info(account_info_iter)?;
+        let validator_policy_info = next_account_info(account_info_iter)?;
 
         check_system_program(system_program_info.key)?;
         check_stake_program(stake_program_info.key)?;
@@ -995,6 +1151,15 @@ impl Processor {
         stake_pool.check_reserve_stake(reserve_stake_info)?;
         stake_pool.check_validator_list(validator_list_info)?;
 
+        if let Some(validator_policy) =
+            get_validator_policy(program_id, stake_pool_info.key, validator_policy_info)?
+        {
+            // `minimum_activated_stake` is left to clients, since activated
+            // stake is not readable from here
+            let commission = get_vote_account_commission(validator_vote_info)?;
+            validator_policy.check_validator(validator_vote_info.key, commission)?;
+        }
+
         if stake_pool.last_update_epoch < clock.epoch {
             return Err(StakePoolError::StakeListAndPoolOutOfDate.into());
         }
@@ -1121,7 +1286,7 @@ impl Processor {
         check_stake_program(stake_program_info.key)?;
         check_account_owner(stake_pool_info, program_id)?;
 
-        let mut stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
+        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
         if !stake_pool.is_valid() {
             return Err(StakePoolError::InvalidState.into());
         }
@@ -1142,6 +1307,96 @@ impl Processor {
             return Err(StakePoolError::StakeListAndPoolOutOfDate.into());
         }
 
+        Self::deactivate_validator(
+            program_id,
+            stake_pool_info,
+            stake_pool,
+            withdraw_authority_info,
+            validator_list_info,
+            stake_account_info,
+            transient_stake_account_info,
+            clock_info,
+            None,
+        )
+    }
+
+    /// Processes `EjectValidator` instruction.
+    #[inline(never)] // needed due to stack size violation
+    fn process_eject_validator(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let stake_pool_info = next_account_info(account_info_iter)?;
+        let validator_policy_info = next_account_info(account_info_iter)?;
+        let withdraw_authority_info = next_account_info(account_info_iter)?;
+        let validator_list_info = next_account_info(account_info_iter)?;
+        let stake_account_info = next_account_info(account_info_iter)?;
+        let transient_stake_account_info = next_account_info(account_info_iter)?;
+        let vote_account_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(clock_info)?;
+        let stake_program_info = next_account_info(account_info_iter)?;
+
+        check_stake_program(stake_program_info.key)?;
+        check_account_owner(stake_pool_info, program_id)?;
+
+        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
+        if !stake_pool.is_valid() {
+            return Err(StakePoolError::InvalidState.into());
+        }
+
+        stake_pool.check_authority_withdraw(
+            withdraw_authority_info.key,
+            program_id,
+            stake_pool_info.key,
+        )?;
+
+        if stake_pool.last_update_epoch < clock.epoch {
+            return Err(StakePoolError::StakeListAndPoolOutOfDate.into());
+        }
+
+        let validator_policy =
+            get_validator_policy(program_id, stake_pool_info.key, validator_policy_info)?
+                .ok_or_else(|| {
+                    msg!("Stake pool does not have a validator policy");
+                    StakePoolError::InvalidState
+                })?;
+        let commission = get_vote_account_commission(vote_account_info)?;
+        if commission <= validator_policy.max_commission {
+            msg!(
+                "Vote account {} has commission {}, policy allows up to {}",
+                vote_account_info.key,
+                commission,
+                validator_policy.max_commission
+            );
+            return Err(StakePoolError::ValidatorCompliesWithPolicy.into());
+        }
+
+        Self::deactivate_validator(
+            program_id,
+            stake_pool_info,
+            stake_pool,
+            withdraw_authority_info,
+            validator_list_info,
+            stake_account_info,
+            transient_stake_account_info,
+            clock_info,
+            Some(vote_account_info.key),
+        )
+    }
+
+    /// Deactivates a validator's stake and marks it for removal, shared by
+    /// `RemoveValidatorFromPool` and `EjectValidator`
+    #[allow(clippy::too_many_arguments)]
+    fn deactivate_validator<'a>(
+        program_id: &Pubkey,
+        stake_pool_info: &AccountInfo<'a>,
+        mut stake_pool: StakePool,
+        withdraw_authority_info: &AccountInfo<'a>,
+        validator_list_info: &AccountInfo<'a>,
+        stake_account_info: &AccountInfo<'a>,
+        transient_stake_account_info: &AccountInfo<'a>,
+        clock_info: &AccountInfo<'a>,
+        expected_vote_account_address: Option<&Pubkey>,
+    ) -> ProgramResult {
         stake_pool.check_validator_list(validator_list_info)?;
 
         check_account_owner(validator_list_info, program_id)?;
@@ -1154,6 +1409,16 @@ impl Processor {
 
         let (_, stake) = get_stake_state(stake_account_info)?;
         let vote_account_address = stake.delegation.voter_pubkey;
+        if let Some(expected_vote_account_address) = expected_vote_account_address {
+            if vote_account_address != *expected_vote_account_address {
+                msg!(
+                    "Stake account is delegated to {}, expected {}",
+                    vote_account_address,
+                    expected_vote_account_address
+                );
+                return Err(StakePoolError::InvalidStakeAccountAddress.into());
+            }
+        }
         let maybe_validator_stake_info = validator_list.find_mut::<ValidatorStakeInfo, _>(|x| {
             ValidatorStakeInfo::memcmp_pubkey(x, &vote_account_address)
         });
@@ -2836,53 +3101,19 @@ impl Processor {
             .stake
             .checked_sub(validator_stake.delegation.stake)
             .ok_or(StakePoolError::CalculationFailure)?;
-        let sol_deposit_lamports = total_deposit_lamports
-            .checked_sub(stake_deposit_lamports)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        let new_pool_tokens = stake_pool
-            .calc_pool_tokens_for_deposit(total_deposit_lamports)
-            .ok_or(StakePoolError::CalculationFailure)?;
-        let new_pool_tokens_from_stake = stake_pool
-            .calc_pool_tokens_for_deposit(stake_deposit_lamports)
-            .ok_or(StakePoolError::CalculationFailure)?;
-        let new_pool_tokens_from_sol = new_pool_tokens
-            .checked_sub(new_pool_tokens_from_stake)
-            .ok_or(StakePoolError::CalculationFailure)?;
 
-        let stake_deposit_fee = stake_pool
-            .calc_pool_tokens_stake_deposit_fee(new_pool_tokens_from_stake)
-            .ok_or(StakePoolError::CalculationFailure)?;
-        let sol_deposit_fee = stake_pool
-            .calc_pool_tokens_sol_deposit_fee(new_pool_tokens_from_sol)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        let total_fee = stake_deposit_fee
-            .checked_add(sol_deposit_fee)
-            .ok_or(StakePoolError::CalculationFailure)?;
-        let pool_tokens_user = new_pool_tokens
-            .checked_sub(total_fee)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        let pool_tokens_referral_fee = stake_pool
-            .calc_pool_tokens_stake_referral_fee(total_fee)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        let pool_tokens_manager_deposit_fee = total_fee
-            .checked_sub(pool_tokens_referral_fee)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        if pool_tokens_user
-            .saturating_add(pool_tokens_manager_deposit_fee)
-            .saturating_add(pool_tokens_referral_fee)
-            != new_pool_tokens
-        {
-            return Err(StakePoolError::CalculationFailure.into());
-        }
-
-        if pool_tokens_user == 0 {
-            return Err(StakePoolError::DepositTooSmall.into());
-        }
+        let quote::DepositStakeQuote {
+            sol_deposit_lamports,
+            pool_tokens: new_pool_tokens,
+            pool_tokens_user,
+            pool_tokens_manager_fee: pool_tokens_manager_deposit_fee,
+            pool_tokens_referral_fee,
+            ..
+        } = quote::quote_deposit_stake(
+            &stake_pool,
+            total_deposit_lamports,
+            stake_deposit_lamports,
+        )?;
 
         if let Some(minimum_pool_tokens_out) = minimum_pool_tokens_out {
             if pool_tokens_user < minimum_pool_tokens_out {
@@ -3013,35 +3244,13 @@ impl Processor {
             return Err(StakePoolError::StakeListAndPoolOutOfDate.into());
         }
 
-        let new_pool_tokens = stake_pool
-            .calc_pool_tokens_for_deposit(deposit_lamports)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        let pool_tokens_sol_deposit_fee = stake_pool
-            .calc_pool_tokens_sol_deposit_fee(new_pool_tokens)
-            .ok_or(StakePoolError::CalculationFailure)?;
-        let pool_tokens_user = new_pool_tokens
-            .checked_sub(pool_tokens_sol_deposit_fee)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        let pool_tokens_referral_fee = stake_pool
-            .calc_pool_tokens_sol_referral_fee(pool_tokens_sol_deposit_fee)
-            .ok_or(StakePoolError::CalculationFailure)?;
-        let pool_tokens_manager_deposit_fee = pool_tokens_sol_deposit_fee
-            .checked_sub(pool_tokens_referral_fee)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        if pool_tokens_user
-            .saturating_add(pool_tokens_manager_deposit_fee)
-            .saturating_add(pool_tokens_referral_fee)
-            != new_pool_tokens
-        {
-            return Err(StakePoolError::CalculationFailure.into());
-        }
-
-        if pool_tokens_user == 0 {
-            return Err(StakePoolError::DepositTooSmall.into());
-        }
+        let quote::DepositSolQuote {
+            pool_tokens: new_pool_tokens,
+            pool_tokens_user,
+            pool_tokens_manager_fee: pool_tokens_manager_deposit_fee,
+            pool_tokens_referral_fee,
+            ..
+        } = quote::quote_deposit_sol(&stake_pool, deposit_lamports)?;
 
         if let Some(minimum_pool_tokens_out) = minimum_pool_tokens_out {
             if pool_tokens_user < minimum_pool_tokens_out {
@@ -3166,26 +3375,14 @@ impl Processor {
         // To prevent a faulty manager fee account from preventing withdrawals
         // if the token program does not own the account, or if the account is not
         // initialized
-        let pool_tokens_fee = if stake_pool.manager_fee_account == *burn_from_pool_info.key
-            || stake_pool.check_manager_fee_info(manager_fee_info).is_err()
-        {
-            0
-        } else {
-            stake_pool
-                .calc_pool_tokens_stake_withdrawal_fee(pool_tokens)
-                .ok_or(StakePoolError::CalculationFailure)?
-        };
-        let pool_tokens_burnt = pool_tokens
-            .checked_sub(pool_tokens_fee)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        let mut withdraw_lamports = stake_pool
-            .calc_lamports_withdraw_amount(pool_tokens_burnt)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        if withdraw_lamports == 0 {
-            return Err(StakePoolError::WithdrawalTooSmall.into());
-        }
+        let skip_fee = stake_pool.manager_fee_account == *burn_from_pool_info.key
+            || stake_pool.check_manager_fee_info(manager_fee_info).is_err();
+        let quote::WithdrawQuote {
+            pool_tokens_fee,
+            pool_tokens_burnt,
+            lamports: mut withdraw_lamports,
+            ..
+        } = quote::calc_stake_withdrawal(&stake_pool, pool_tokens, skip_fee)?;
 
         if let Some(minimum_lamports_out) = minimum_lamports_out {
             if withdraw_lamports < minimum_lamports_out {
@@ -3476,26 +3673,14 @@ impl Processor {
         // To prevent a faulty manager fee account from preventing withdrawals
         // if the token program does not own the account, or if the account is not
         // initialized
-        let pool_tokens_fee = if stake_pool.manager_fee_account == *burn_from_pool_info.key
-            || stake_pool.check_manager_fee_info(manager_fee_info).is_err()
-        {
-            0
-        } else {
-            stake_pool
-                .calc_pool_tokens_sol_withdrawal_fee(pool_tokens)
-                .ok_or(StakePoolError::CalculationFailure)?
-        };
-        let pool_tokens_burnt = pool_tokens
-            .checked_sub(pool_tokens_fee)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        let withdraw_lamports = stake_pool
-            .calc_lamports_withdraw_amount(pool_tokens_burnt)
-            .ok_or(StakePoolError::CalculationFailure)?;
-
-        if withdraw_lamports == 0 {
-            return Err(StakePoolError::WithdrawalTooSmall.into());
-        }
+        let skip_fee = stake_pool.manager_fee_account == *burn_from_pool_info.key
+            || stake_pool.check_manager_fee_info(manager_fee_info).is_err();
+        let quote::WithdrawQuote {
+            pool_tokens_fee,
+            pool_tokens_burnt,
+            lamports: withdraw_lamports,
+            ..
+        } = quote::calc_sol_withdrawal(&stake_pool, pool_tokens, skip_fee)?;
 
         if let Some(minimum_lamports_out) = minimum_lamports_out {
             if withdraw_lamports < minimum_lamports_out {
@@ -3568,6 +3753,93 @@ impl Processor {
         Ok(())
     }
 
+    /// Processes `SetValidatorPolicy` instruction.
+    #[inline(never)]
+    fn process_set_validator_policy(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_commission: u8,
+        minimum_activated_stake: u64,
+        filter: ValidatorFilter,
+        vote_accounts: Vec<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let stake_pool_info = next_account_info(account_info_iter)?;
+        let manager_info = next_account_info(account_info_iter)?;
+        let validator_policy_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if !payer_info.is_signer {
+            msg!("Payer did not sign validator policy update");
+            return Err(StakePoolError::SignatureMissing.into());
+        }
+
+        check_system_program(system_program_info.key)?;
+        check_account_owner(stake_pool_info, program_id)?;
+
+        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
+        if !stake_pool.is_valid() {
+            return Err(StakePoolError::InvalidState.into());
+        }
+        stake_pool.check_manager(manager_info)?;
+
+        if max_commission > 100 {
+            msg!("Maximum commission {} is over 100%", max_commission);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let bump_seed = check_validator_policy_address(
+            program_id,
+            stake_pool_info.key,
+            validator_policy_info.key,
+        )?;
+        let validator_policy = ValidatorPolicy {
+            account_type: AccountType::ValidatorPolicy,
+            stake_pool: *stake_pool_info.key,
+            max_commission,
+            minimum_activated_stake,
+            filter,
+            vote_accounts,
+        };
+        let space = get_instance_packed_len(&validator_policy)?;
+        let required_lamports = Rent::get()?.minimum_balance(space);
+        let current_lamports = validator_policy_info.lamports();
+        if current_lamports < required_lamports {
+            Self::sol_transfer(
+                payer_info.clone(),
+                validator_policy_info.clone(),
+                required_lamports - current_lamports,
+            )?;
+        }
+
+        if validator_policy_info.owner == program_id {
+            validator_policy_info.realloc(space, false)?;
+        } else {
+            let validator_policy_signer_seeds: &[&[_]] = &[
+                VALIDATOR_POLICY_SEED_PREFIX,
+                stake_pool_info.key.as_ref(),
+                &[bump_seed],
+            ];
+            invoke_signed(
+                &system_instruction::allocate(validator_policy_info.key, space as u64),
+                &[validator_policy_info.clone()],
+                &[validator_policy_signer_seeds],
+            )?;
+            invoke_signed(
+                &system_instruction::assign(validator_policy_info.key, program_id),
+                &[validator_policy_info.clone()],
+                &[validator_policy_signer_seeds],
+            )?;
+        }
+
+        borsh::to_writer(
+            &mut validator_policy_info.data.borrow_mut()[..],
+            &validator_policy,
+        )?;
+        Ok(())
+    }
+
     #[inline(never)]
     fn process_create_pool_token_metadata(
         program_id: &Pubkey,
@@ -3583,7 +3855,7 @@ impl Processor {
         let pool_mint_info = next_account_info(account_info_iter)?;
         let payer_info = next_account_info(account_info_iter)?;
         let metadata_info = next_account_info(account_info_iter)?;
-        let mpl_token_metadata_program_info = next_account_info(account_info_iter)?;
+        let metadata_program_info = next_account_info(account_info_iter)?;
         let system_program_info = next_account_info(account_info_iter)?;
 
         if !payer_info.is_signer {
@@ -3594,7 +3866,6 @@ impl Processor {
         check_system_program(system_program_info.key)?;
         check_account_owner(payer_info, &system_program::id())?;
         check_account_owner(stake_pool_info, program_id)?;
-        check_mpl_metadata_program(mpl_token_metadata_program_info.key)?;
 
         let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
         if !stake_pool.is_valid() {
@@ -3608,23 +3879,10 @@ impl Processor {
             stake_pool_info.key,
         )?;
         stake_pool.check_mint(pool_mint_info)?;
-        check_mpl_metadata_account_address(metadata_info.key, &stake_pool.pool_mint)?;
 
         // Token mint authority for stake-pool token is stake-pool withdraw authority
         let token_mint_authority = withdraw_authority_info;
 
-        let new_metadata_instruction = create_metadata_accounts_v3(
-            *mpl_token_metadata_program_info.key,
-            *metadata_info.key,
-            *pool_mint_info.key,
-            *token_mint_authority.key,
-            *payer_info.key,
-            *token_mint_authority.key,
-            name,
-            symbol,
-            uri,
-        );
-
         let (_, stake_withdraw_bump_seed) =
             crate::find_withdraw_authority_program_address(program_id, stake_pool_info.key);
 
@@ -3634,18 +3892,77 @@ impl Processor {
             &[stake_withdraw_bump_seed],
         ];
 
-        invoke_signed(
-            &new_metadata_instruction,
-            &[
-                metadata_info.clone(),
-                pool_mint_info.clone(),
-                withdraw_authority_info.clone(),
-                payer_info.clone(),
-                withdraw_authority_info.clone(),
-                system_program_info.clone(),
-            ],
-            &[token_mint_authority_signer_seeds],
-        )?;
+        if mint_holds_metadata(pool_mint_info)? {
+            // Token-2022 mint pointing to itself, so the metadata lives in the mint
+            if metadata_info.key != pool_mint_info.key {
+                return Err(StakePoolError::InvalidMetadataAccount.into());
+            }
+            check_token_2022_program(metadata_program_info.key)?;
+
+            let token_metadata = TokenMetadata {
+                name: name.clone(),
+                symbol: symbol.clone(),
+                uri: uri.clone(),
+                update_authority: Some(*token_mint_authority.key).try_into()?,
+                mint: *pool_mint_info.key,
+                ..TokenMetadata::default()
+            };
+            fund_mint_metadata_rent(
+                payer_info,
+                pool_mint_info,
+                system_program_info,
+                &token_metadata,
+            )?;
+
+            let initialize_metadata_instruction = initialize_token_metadata(
+                metadata_program_info.key,
+                pool_mint_info.key,
+                token_mint_authority.key,
+                pool_mint_info.key,
+                token_mint_authority.key,
+                name,
+                symbol,
+                uri,
+            );
+
+            invoke_signed(
+                &initialize_metadata_instruction,
+                &[
+                    pool_mint_info.clone(),
+                    token_mint_authority.clone(),
+                    metadata_program_info.clone(),
+                ],
+                &[token_mint_authority_signer_seeds],
+            )?;
+        } else {
+            check_mpl_metadata_program(metadata_program_info.key)?;
+            check_mpl_metadata_account_address(metadata_info.key, &stake_pool.pool_mint)?;
+
+            let new_metadata_instruction = create_metadata_accounts_v3(
+                *metadata_program_info.key,
+                *metadata_info.key,
+                *pool_mint_info.key,
+                *token_mint_authority.key,
+                *payer_info.key,
+                *token_mint_authority.key,
+                name,
+                symbol,
+                uri,
+            );
+
+            invoke_signed(
+                &new_metadata_instruction,
+                &[
+                    metadata_info.clone(),
+                    pool_mint_info.clone(),
+                    withdraw_authority_info.clone(),
+                    payer_info.clone(),
+                    withdraw_authority_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[token_mint_authority_signer_seeds],
+            )?;
+        }
 
         Ok(())
     }
@@ -3664,12 +3981,10 @@ impl Processor {
         let manager_info = next_account_info(account_info_iter)?;
         let withdraw_authority_info = next_account_info(account_info_iter)?;
         let metadata_info = next_account_info(account_info_iter)?;
-        let mpl_token_metadata_program_info = next_account_info(account_info_iter)?;
+        let metadata_program_info = next_account_info(account_info_iter)?;
 
         check_account_owner(stake_pool_info, program_id)?;
 
-        check_mpl_metadata_program(mpl_token_metadata_program_info.key)?;
-
         let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
         if !stake_pool.is_valid() {
             return Err(StakePoolError::InvalidState.into());
@@ -3681,29 +3996,10 @@ impl Processor {
             program_id,
             stake_pool_info.key,
         )?;
-        check_mpl_metadata_account_address(metadata_info.key, &stake_pool.pool_mint)?;
 
         // Token mint authority for stake-pool token is withdraw authority only
         let token_mint_authority = withdraw_authority_info;
 
-        let update_metadata_accounts_instruction = update_metadata_accounts_v2(
-            *mpl_token_metadata_program_info.key,
-            *metadata_info.key,
-            *token_mint_authority.key,
-            None,
-            Some(DataV2 {
-                name,
-                symbol,
-                uri,
-                seller_fee_basis_points: 0,
-                creators: None,
-                collection: None,
-                uses: None,
-            }),
-            None,
-            Some(true),
-        );
-
         let (_, stake_withdraw_bump_seed) =
             crate::find_withdraw_authority_program_address(program_id, stake_pool_info.key);
 
@@ -3713,11 +4009,85 @@ impl Processor {
             &[stake_withdraw_bump_seed],
         ];
 
-        invoke_signed(
-            &update_metadata_accounts_instruction,
-            &[metadata_info.clone(), withdraw_authority_info.clone()],
-            &[token_mint_authority_signer_seeds],
-        )?;
+        if *metadata_info.key == stake_pool.pool_mint {
+            // Token-2022 metadata stored in the pool mint itself
+            check_token_2022_program(metadata_program_info.key)?;
+            if !mint_holds_metadata(metadata_info)? {
+                msg!("Pool mint does not point to itself for token metadata");
+                return Err(StakePoolError::InvalidMetadataAccount.into());
+            }
+
+            let mut token_metadata = {
+                let pool_mint_data = metadata_info.try_borrow_data()?;
+                let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+                pool_mint.get_variable_len_extension::<TokenMetadata>()?
+            };
+            let fields = [
+                (Field::Name, name),
+                (Field::Symbol, symbol),
+                (Field::Uri, uri),
+            ];
+            for (field, value) in fields.iter() {
+                token_metadata.update(field.clone(), value.clone());
+            }
+
+            // the payer is only required if the metadata grows
+            if let (Some(payer_info), Some(system_program_info)) =
+                (account_info_iter.next(), account_info_iter.next())
+            {
+                fund_mint_metadata_rent(
+                    payer_info,
+                    metadata_info,
+                    system_program_info,
+                    &token_metadata,
+                )?;
+            }
+
+            for (field, value) in fields {
+                invoke_signed(
+                    &update_field(
+                        metadata_program_info.key,
+                        metadata_info.key,
+                        token_mint_authority.key,
+                        field,
+                        value,
+                    ),
+                    &[
+                        metadata_info.clone(),
+                        token_mint_authority.clone(),
+                        metadata_program_info.clone(),
+                    ],
+                    &[token_mint_authority_signer_seeds],
+                )?;
+            }
+        } else {
+            check_mpl_metadata_program(metadata_program_info.key)?;
+            check_mpl_metadata_account_address(metadata_info.key, &stake_pool.pool_mint)?;
+
+            let update_metadata_accounts_instruction = update_metadata_accounts_v2(
+                *metadata_program_info.key,
+                *metadata_info.key,
+                *token_mint_authority.key,
+                None,
+                Some(DataV2 {
+                    name,
+                    symbol,
+                    uri,
+                    seller_fee_basis_points: 0,
+                    creators: None,
+                    collection: None,
+                    uses: None,
+                }),
+                None,
+                Some(true),
+            );
+
+            invoke_signed(
+                &update_metadata_accounts_instruction,
+                &[metadata_info.clone(), withdraw_authority_info.clone()],
+                &[token_mint_authority_signer_seeds],
+            )?;
+        }
 
         Ok(())
     }
@@ -4070,6 +4440,26 @@ impl Processor {
                     Some(minimum_lamports_out),
                 )
             }
+            StakePoolInstruction::SetValidatorPolicy {
+                max_commission,
+                minimum_activated_stake,
+                filter,
+                vote_accounts,
+            } => {
+                msg!("Instruction: SetValidatorPolicy");
+                Self::process_set_validator_policy(
+                    program_id,
+                    accounts,
+                    max_commission,
+                    minimum_activated_stake,
+                    filter,
+                    vote_accounts,
+                )
+            }
+            StakePoolInstruction::EjectValidator => {
+                msg!("Instruction: EjectValidator");
+                Self::process_eject_validator(program_id, accounts)
+            }
         }
     }
 }
@@ -4125,6 +4515,9 @@ impl PrintProgramError for StakePoolError {
             StakePoolError::IncorrectMintDecimals => msg!("Error: Provided mint does not have 9 decimals to match SOL"),
             StakePoolError::ReserveDepleted => msg!("Error: Pool reserve does not have enough lamports to fund rent-exempt reserve in split destination. Deposit more SOL in reserve, or pre-fund split destination with the rent-exempt reserve for a stake account."),
             StakePoolError::MissingRequiredSysvar => msg!("Missing required sysvar account"),
+            StakePoolError::ValidatorPolicyViolation => msg!("Error: Validator does not satisfy the pool's validator policy"),
+            StakePoolError::ValidatorCompliesWithPolicy => msg!("Error: Validator satisfies the pool's validator policy and cannot be ejected"),
+            StakePoolError::InvalidMintMetadataAuthority => msg!("Error: Metadata stored in the pool mint must be controlled by the stake pool withdraw authority"),
         }
     }
 }
@@ -0,0 +1,724 @@
+//! Quotes for deposits and withdrawals, computed from the pool's state exactly
+//! as the processor does
+
+use {
+    crate::{
+        error::StakePoolError,
+        find_stake_program_address, find_transient_stake_program_address, minimum_delegation,
+        state::{StakePool, StakeStatus, ValidatorStakeInfo},
+        MINIMUM_RESERVE_LAMPORTS,
+    },
+    solana_program::pubkey::Pubkey,
+    std::num::NonZeroU32,
+};
+
+/// Pool tokens minted by a `DepositSol`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DepositSolQuote {
+    /// Pool tokens worth the deposited lamports, including fees
+    pub pool_tokens: u64,
+    /// Pool tokens minted to the depositor
+    pub pool_tokens_user: u64,
+    /// SOL deposit fee, before the referral split
+    pub pool_tokens_fee: u64,
+    /// Share of the fee minted to the manager fee account
+    pub pool_tokens_manager_fee: u64,
+    /// Share of the fee minted to the referrer
+    pub pool_tokens_referral_fee: u64,
+}
+
+/// Lamports accepted and pool tokens minted by a `DepositStake`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DepositStakeQuote {
+    /// Lamports merged into the validator stake account
+    pub total_deposit_lamports: u64,
+    /// Lamports added to the validator's delegation, charged the stake
+    /// deposit fee
+    pub stake_deposit_lamports: u64,
+    /// Remaining lamports, moved to the reserve and charged the SOL deposit fee
+    pub sol_deposit_lamports: u64,
+    /// Pool tokens worth `total_deposit_lamports`, including fees
+    pub pool_tokens: u64,
+    /// Pool tokens minted to the depositor
+    pub pool_tokens_user: u64,
+    /// Stake deposit fee on the tokens from `stake_deposit_lamports`
+    pub pool_tokens_stake_deposit_fee: u64,
+    /// SOL deposit fee on the tokens from `sol_deposit_lamports`
+    pub pool_tokens_sol_deposit_fee: u64,
+    /// Share of both fees minted to the manager fee account
+    pub pool_tokens_manager_fee: u64,
+    /// Share of both fees minted to the referrer
+    pub pool_tokens_referral_fee: u64,
+}
+
+/// Pool tokens taken and lamports returned by a single `WithdrawSol` or
+/// `WithdrawStake`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WithdrawQuote {
+    /// Pool tokens taken from the user
+    pub pool_tokens: u64,
+    /// Withdrawal fee, transferred to the manager fee account
+    pub pool_tokens_fee: u64,
+    /// Pool tokens burnt, after the fee
+    pub pool_tokens_burnt: u64,
+    /// Lamports worth the burnt pool tokens
+    pub lamports: u64,
+}
+
+/// Stake account a `WithdrawStake` splits from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WithdrawStakeSource {
+    /// Part of a validator's active stake account
+    Active,
+    /// Part of a validator's transient stake account
+    Transient,
+    /// Part of the reserve stake account
+    Reserve,
+}
+
+/// One `WithdrawStake` instruction of a stake withdrawal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StakeSplit {
+    /// Kind of account split from
+    pub source: WithdrawStakeSource,
+    /// Stake account split from
+    pub stake_account: Pubkey,
+    /// Validator of the stake account, `None` for the reserve
+    pub vote_account_address: Option<Pubkey>,
+    /// Pool tokens, fee and lamports of this instruction
+    pub amounts: WithdrawQuote,
+}
+
+/// Pool tokens taken and stake received by a stake withdrawal, split across
+/// as many `WithdrawStake` instructions as needed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WithdrawStakeQuote {
+    /// Sum of the amounts of all splits
+    pub amounts: WithdrawQuote,
+    /// Instructions to send, in order
+    pub splits: Vec<StakeSplit>,
+}
+
+/// Balances of the pool's stake accounts, as found in its validator list and
+/// reserve, needed to quote withdrawals
+#[derive(Clone, Copy, Debug)]
+pub struct PoolStakeAccounts<'a> {
+    /// Address of the stake pool
+    pub stake_pool_address: Pubkey,
+    /// Entries of the pool's validator list
+    pub validators: &'a [ValidatorStakeInfo],
+    /// Lamports in the reserve stake account
+    pub reserve_lamports: u64,
+    /// Rent-exempt reserve of a stake account
+    pub stake_rent_exempt_reserve: u64,
+    /// Minimum delegation enforced by the stake program
+    pub stake_program_minimum_delegation: u64,
+}
+
+/// Quote a `DepositSol` of `lamports`
+pub fn quote_deposit_sol(
+    stake_pool: &StakePool,
+    lamports: u64,
+) -> Result<DepositSolQuote, StakePoolError> {
+    let pool_tokens = stake_pool
+        .calc_pool_tokens_for_deposit(lamports)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_fee = stake_pool
+        .calc_pool_tokens_sol_deposit_fee(pool_tokens)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_user = pool_tokens
+        .checked_sub(pool_tokens_fee)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_referral_fee = stake_pool
+        .calc_pool_tokens_sol_referral_fee(pool_tokens_fee)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_manager_fee = pool_tokens_fee
+        .checked_sub(pool_tokens_referral_fee)
+        .ok_or(StakePoolError::CalculationFailure)?;
+
+    if pool_tokens_user
+        .saturating_add(pool_tokens_manager_fee)
+        .saturating_add(pool_tokens_referral_fee)
+        != pool_tokens
+    {
+        return Err(StakePoolError::CalculationFailure);
+    }
+    if pool_tokens_user == 0 {
+        return Err(StakePoolError::DepositTooSmall);
+    }
+
+    Ok(DepositSolQuote {
+        pool_tokens,
+        pool_tokens_user,
+        pool_tokens_fee,
+        pool_tokens_manager_fee,
+        pool_tokens_referral_fee,
+    })
+}
+
+/// Quote a `DepositStake` merging `total_deposit_lamports` into a validator
+/// stake account, of which `stake_deposit_lamports` are added to its
+/// delegation.
+///
+/// For an active deposited stake account, these are its lamports and its
+/// delegated stake. If both accounts are still activating, the deposited
+/// account's rent-exempt reserve is delegated too.
+pub fn quote_deposit_stake(
+    stake_pool: &StakePool,
+    total_deposit_lamports: u64,
+    stake_deposit_lamports: u64,
+) -> Result<DepositStakeQuote, StakePoolError> {
+    let sol_deposit_lamports = total_deposit_lamports
+        .checked_sub(stake_deposit_lamports)
+        .ok_or(StakePoolError::CalculationFailure)?;
+
+    let pool_tokens = stake_pool
+        .calc_pool_tokens_for_deposit(total_deposit_lamports)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_from_stake = stake_pool
+        .calc_pool_tokens_for_deposit(stake_deposit_lamports)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_from_sol = pool_tokens
+        .checked_sub(pool_tokens_from_stake)
+        .ok_or(StakePoolError::CalculationFailure)?;
+
+    let pool_tokens_stake_deposit_fee = stake_pool
+        .calc_pool_tokens_stake_deposit_fee(pool_tokens_from_stake)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_sol_deposit_fee = stake_pool
+        .calc_pool_tokens_sol_deposit_fee(pool_tokens_from_sol)
+        .ok_or(StakePoolError::CalculationFailure)?;
+
+    let total_fee = pool_tokens_stake_deposit_fee
+        .checked_add(pool_tokens_sol_deposit_fee)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_user = pool_tokens
+        .checked_sub(total_fee)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_referral_fee = stake_pool
+        .calc_pool_tokens_stake_referral_fee(total_fee)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let pool_tokens_manager_fee = total_fee
+        .checked_sub(pool_tokens_referral_fee)
+        .ok_or(StakePoolError::CalculationFailure)?;
+
+    if pool_tokens_user
+        .saturating_add(pool_tokens_manager_fee)
+        .saturating_add(pool_tokens_referral_fee)
+        != pool_tokens
+    {
+        return Err(StakePoolError::CalculationFailure);
+    }
+    if pool_tokens_user == 0 {
+        return Err(StakePoolError::DepositTooSmall);
+    }
+
+    Ok(DepositStakeQuote {
+        total_deposit_lamports,
+        stake_deposit_lamports,
+        sol_deposit_lamports,
+        pool_tokens,
+        pool_tokens_user,
+        pool_tokens_stake_deposit_fee,
+        pool_tokens_sol_deposit_fee,
+        pool_tokens_manager_fee,
+        pool_tokens_referral_fee,
+    })
+}
+
+/// Amounts of a single `WithdrawSol` of `pool_tokens`, without checking the
+/// reserve balance. No fee is charged if `skip_fee` is set, as when burning
+/// from the manager fee account.
+pub fn calc_sol_withdrawal(
+    stake_pool: &StakePool,
+    pool_tokens: u64,
+    skip_fee: bool,
+) -> Result<WithdrawQuote, StakePoolError> {
+    let pool_tokens_fee = if skip_fee {
+        0
+    } else {
+        stake_pool
+            .calc_pool_tokens_sol_withdrawal_fee(pool_tokens)
+            .ok_or(StakePoolError::CalculationFailure)?
+    };
+    non_zero_withdrawal(withdrawal(stake_pool, pool_tokens, pool_tokens_fee)?)
+}
+
+/// Amounts of a single `WithdrawStake` of `pool_tokens`, before it is
+/// truncated to a whole validator stake account. No fee is charged if
+/// `skip_fee` is set, as when burning from the manager fee account.
+pub fn calc_stake_withdrawal(
+    stake_pool: &StakePool,
+    pool_tokens: u64,
+    skip_fee: bool,
+) -> Result<WithdrawQuote, StakePoolError> {
+    non_zero_withdrawal(stake_withdrawal(stake_pool, pool_tokens, skip_fee)?)
+}
+
+/// Quote a `WithdrawSol` of `pool_tokens` from the reserve
+pub fn quote_withdraw_sol(
+    stake_pool: &StakePool,
+    accounts: &PoolStakeAccounts,
+    pool_tokens: u64,
+    skip_fee: bool,
+) -> Result<WithdrawQuote, StakePoolError> {
+    let quote = calc_sol_withdrawal(stake_pool, pool_tokens, skip_fee)?;
+    let new_reserve_lamports = accounts.reserve_lamports.saturating_sub(quote.lamports);
+    if new_reserve_lamports < accounts.minimum_reserve_lamports() {
+        return Err(StakePoolError::SolWithdrawalTooLarge);
+    }
+    Ok(quote)
+}
+
+/// Quote a stake withdrawal of `pool_tokens`, picking the stake accounts to
+/// split from in the order `WithdrawStake` enforces: the preferred withdraw
+/// validator, then the largest active stake accounts, then the largest
+/// transient stake accounts, and the reserve once no validator has stake
+/// left to withdraw. Each split withdraws as much as its account allows,
+/// and is priced against the pool as left by the previous splits.
+///
+/// Whole validator stake accounts are never withdrawn, so the withdrawal
+/// fails if the pool does not have enough withdrawable stake.
+pub fn quote_withdraw_stake(
+    stake_pool: &StakePool,
+    accounts: &PoolStakeAccounts,
+    pool_tokens: u64,
+    skip_fee: bool,
+) -> Result<WithdrawStakeQuote, StakePoolError> {
+    let mut stake_pool = stake_pool.clone();
+    let mut validators = accounts.validators.to_vec();
+    let mut reserve_lamports = accounts.reserve_lamports;
+    let required_lamports = accounts
+        .stake_rent_exempt_reserve
+        .saturating_add(minimum_delegation(
+            accounts.stake_program_minimum_delegation,
+        ));
+
+    let mut quote = WithdrawStakeQuote::default();
+    let mut remaining_pool_tokens = pool_tokens;
+    while remaining_pool_tokens > 0 {
+        let lamports_per_pool_token = stake_pool
+            .get_lamports_per_pool_token()
+            .ok_or(StakePoolError::CalculationFailure)?;
+        let minimum_lamports_with_tolerance =
+            required_lamports.saturating_add(lamports_per_pool_token);
+        let has_active_stake = validators.iter().any(|validator| {
+            u64::from(validator.active_stake_lamports) > minimum_lamports_with_tolerance
+        });
+        let has_transient_stake = validators.iter().any(|validator| {
+            u64::from(validator.transient_stake_lamports) > minimum_lamports_with_tolerance
+        });
+
+        let (source, index, available_lamports) = if has_active_stake || has_transient_stake {
+            let index = select_validator(
+                &stake_pool,
+                &validators,
+                has_active_stake,
+                minimum_lamports_with_tolerance,
+            )?;
+            let validator = &validators[index];
+            if has_active_stake {
+                (
+                    WithdrawStakeSource::Active,
+                    Some(index),
+                    u64::from(validator.active_stake_lamports).saturating_sub(required_lamports),
+                )
+            } else {
+                (
+                    WithdrawStakeSource::Transient,
+                    Some(index),
+                    u64::from(validator.transient_stake_lamports).saturating_sub(required_lamports),
+                )
+            }
+        } else {
+            (
+                WithdrawStakeSource::Reserve,
+                None,
+                reserve_lamports.saturating_sub(accounts.minimum_reserve_lamports()),
+            )
+        };
+
+        let mut split_quote = stake_withdrawal(&stake_pool, remaining_pool_tokens, skip_fee)?;
+        if split_quote.lamports > available_lamports {
+            // largest amount of pool tokens whose withdrawal still fits
+            let (mut low, mut high) = (0, remaining_pool_tokens);
+            while high.saturating_sub(low) > 1 {
+                let mid = low.saturating_add(high.saturating_sub(low) / 2);
+                if stake_withdrawal(&stake_pool, mid, skip_fee)?.lamports <= available_lamports {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            split_quote = stake_withdrawal(&stake_pool, low, skip_fee)?;
+            if split_quote.lamports == 0 {
+                return Err(StakePoolError::StakeLamportsNotEqualToMinimum);
+            }
+        }
+        let split_quote = non_zero_withdrawal(split_quote)?;
+
+        let (stake_account, vote_account_address) = match index {
+            Some(index) => {
+                let validator = &mut validators[index];
+                let stake_account = if source == WithdrawStakeSource::Active {
+                    validator.active_stake_lamports = u64::from(validator.active_stake_lamports)
+                        .saturating_sub(split_quote.lamports)
+                        .into();
+                    find_stake_program_address(
+                        &crate::id(),
+                        &validator.vote_account_address,
+                        &accounts.stake_pool_address,
+                        NonZeroU32::new(validator.validator_seed_suffix.into()),
+                    )
+                    .0
+                } else {
+                    validator.transient_stake_lamports =
+                        u64::from(validator.transient_stake_lamports)
+                            .saturating_sub(split_quote.lamports)
+                            .into();
+                    find_transient_stake_program_address(
+                        &crate::id(),
+                        &validator.vote_account_address,
+                        &accounts.stake_pool_address,
+                        validator.transient_seed_suffix.into(),
+                    )
+                    .0
+                };
+                (stake_account, Some(validator.vote_account_address))
+            }
+            None => {
+                reserve_lamports = reserve_lamports.saturating_sub(split_quote.lamports);
+                (stake_pool.reserve_stake, None)
+            }
+        };
+
+        stake_pool.pool_token_supply = stake_pool
+            .pool_token_supply
+            .checked_sub(split_quote.pool_tokens_burnt)
+            .ok_or(StakePoolError::CalculationFailure)?;
+        stake_pool.total_lamports = stake_pool
+            .total_lamports
+            .checked_sub(split_quote.lamports)
+            .ok_or(StakePoolError::CalculationFailure)?;
+        remaining_pool_tokens = remaining_pool_tokens.saturating_sub(split_quote.pool_tokens);
+
+        quote.amounts.pool_tokens = quote
+            .amounts
+            .pool_tokens
+            .saturating_add(split_quote.pool_tokens);
+        quote.amounts.pool_tokens_fee = quote
+            .amounts
+            .pool_tokens_fee
+            .saturating_add(split_quote.pool_tokens_fee);
+        quote.amounts.pool_tokens_burnt = quote
+            .amounts
+            .pool_tokens_burnt
+            .saturating_add(split_quote.pool_tokens_burnt);
+        quote.amounts.lamports = quote.amounts.lamports.saturating_add(split_quote.lamports);
+        quote.splits.push(StakeSplit {
+            source,
+            stake_account,
+            vote_account_address,
+            amounts: split_quote,
+        });
+    }
+
+    Ok(quote)
+}
+
+impl PoolStakeAccounts<'_> {
+    fn minimum_reserve_lamports(&self) -> u64 {
+        self.stake_rent_exempt_reserve
+            .saturating_add(MINIMUM_RESERVE_LAMPORTS)
+    }
+}
+
+/// Index of the validator to withdraw from, which must be the preferred
+/// withdraw validator while it has stake available
+fn select_validator(
+    stake_pool: &StakePool,
+    validators: &[ValidatorStakeInfo],
+    has_active_stake: bool,
+    minimum_lamports_with_tolerance: u64,
+) -> Result<usize, StakePoolError> {
+    let withdrawable_lamports = |validator: &ValidatorStakeInfo| {
+        if has_active_stake {
+            u64::from(validator.active_stake_lamports)
+        } else {
+            u64::from(validator.transient_stake_lamports)
+        }
+    };
+
+    let preferred_index = match stake_pool.preferred_withdraw_validator_vote_address {
+        Some(preferred_withdraw_validator) => {
+            let index = validators
+                .iter()
+                .position(|validator| {
+                    validator.vote_account_address == preferred_withdraw_validator
+                })
+                .ok_or(StakePoolError::ValidatorNotFound)?;
+            let available_lamports = u64::from(validators[index].active_stake_lamports)
+                .saturating_sub(minimum_lamports_with_tolerance);
+            (available_lamports > 0).then_some(index)
+        }
+        None => None,
+    };
+
+    let index = match preferred_index {
+        Some(index) => index,
+        None => validators
+            .iter()
+            .enumerate()
+            .filter(|(_, validator)| validator.status == StakeStatus::Active.into())
+            .max_by(|(left_index, left), (right_index, right)| {
+                // earliest of the largest accounts
+                withdrawable_lamports(left)
+                    .cmp(&withdrawable_lamports(right))
+                    .then(right_index.cmp(left_index))
+            })
+            .map(|(index, _)| index)
+            .ok_or(StakePoolError::ValidatorNotFound)?,
+    };
+
+    if validators[index].status != StakeStatus::Active.into() {
+        return Err(StakePoolError::ValidatorNotFound);
+    }
+    Ok(index)
+}
+
+fn stake_withdrawal(
+    stake_pool: &StakePool,
+    pool_tokens: u64,
+    skip_fee: bool,
+) -> Result<WithdrawQuote, StakePoolError> {
+    let pool_tokens_fee = if skip_fee {
+        0
+    } else {
+        stake_pool
+            .calc_pool_tokens_stake_withdrawal_fee(pool_tokens)
+            .ok_or(StakePoolError::CalculationFailure)?
+    };
+    withdrawal(stake_pool, pool_tokens, pool_tokens_fee)
+}
+
+fn withdrawal(
+    stake_pool: &StakePool,
+    pool_tokens: u64,
+    pool_tokens_fee: u64,
+) -> Result<WithdrawQuote, StakePoolError> {
+    let pool_tokens_burnt = pool_tokens
+        .checked_sub(pool_tokens_fee)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    let lamports = stake_pool
+        .calc_lamports_withdraw_amount(pool_tokens_burnt)
+        .ok_or(StakePoolError::CalculationFailure)?;
+    Ok(WithdrawQuote {
+        pool_tokens,
+        pool_tokens_fee,
+        pool_tokens_burnt,
+        lamports,
+    })
+}
+
+fn non_zero_withdrawal(quote: WithdrawQuote) -> Result<WithdrawQuote, StakePoolError> {
+    if quote.lamports == 0 {
+        Err(StakePoolError::WithdrawalTooSmall)
+    } else {
+        Ok(quote)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::arithmetic_side_effects)]
+    use {
+        super::*, crate::state::Fee, proptest::prelude::*,
+        solana_program::native_token::LAMPORTS_PER_SOL,
+    };
+
+    const STAKE_RENT: u64 = 2_282_880;
+
+    fn test_stake_pool() -> StakePool {
+        StakePool {
+            total_lamports: 1_000 * LAMPORTS_PER_SOL,
+            pool_token_supply: 900 * LAMPORTS_PER_SOL,
+            reserve_stake: Pubkey::new_unique(),
+            stake_deposit_fee: Fee {
+                numerator: 1,
+                denominator: 100,
+            },
+            sol_deposit_fee: Fee {
+                numerator: 3,
+                denominator: 100,
+            },
+            stake_withdrawal_fee: Fee {
+                numerator: 1,
+                denominator: 1_000,
+            },
+            sol_withdrawal_fee: Fee {
+                numerator: 2,
+                denominator: 1_000,
+            },
+            stake_referral_fee: 25,
+            sol_referral_fee: 50,
+            ..StakePool::default()
+        }
+    }
+
+    fn test_validator(
+        vote_account_address: Pubkey,
+        active_stake_lamports: u64,
+        transient_stake_lamports: u64,
+    ) -> ValidatorStakeInfo {
+        ValidatorStakeInfo {
+            status: StakeStatus::Active.into(),
+            vote_account_address,
+            active_stake_lamports: active_stake_lamports.into(),
+            transient_stake_lamports: transient_stake_lamports.into(),
+            last_update_epoch: 0.into(),
+            transient_seed_suffix: 0.into(),
+            unused: 0.into(),
+            validator_seed_suffix: 0.into(),
+        }
+    }
+
+    fn test_accounts(
+        validators: &[ValidatorStakeInfo],
+        reserve_lamports: u64,
+    ) -> PoolStakeAccounts {
+        PoolStakeAccounts {
+            stake_pool_address: Pubkey::new_unique(),
+            validators,
+            reserve_lamports,
+            stake_rent_exempt_reserve: STAKE_RENT,
+            stake_program_minimum_delegation: 1,
+        }
+    }
+
+    #[test]
+    fn deposit_stake_fees() {
+        let stake_pool = test_stake_pool();
+        let quote =
+            quote_deposit_stake(&stake_pool, 10 * LAMPORTS_PER_SOL, 9 * LAMPORTS_PER_SOL).unwrap();
+        assert_eq!(quote.sol_deposit_lamports, LAMPORTS_PER_SOL);
+        assert_eq!(quote.pool_tokens, 9 * LAMPORTS_PER_SOL);
+        assert_eq!(quote.pool_tokens_stake_deposit_fee, 81_000_000);
+        assert_eq!(quote.pool_tokens_sol_deposit_fee, 27_000_000);
+        assert_eq!(quote.pool_tokens_referral_fee, 27_000_000);
+        assert_eq!(quote.pool_tokens_manager_fee, 81_000_000);
+        assert_eq!(quote.pool_tokens_user, 9 * LAMPORTS_PER_SOL - 108_000_000);
+
+        assert_eq!(
+            quote_deposit_stake(&stake_pool, 1, 2),
+            Err(StakePoolError::CalculationFailure)
+        );
+        assert_eq!(
+            quote_deposit_stake(&stake_pool, 1, 1),
+            Err(StakePoolError::DepositTooSmall)
+        );
+    }
+
+    #[test]
+    fn withdraw_sol_keeps_reserve_minimum() {
+        let stake_pool = test_stake_pool();
+        let validators = [];
+        let accounts = test_accounts(&validators, STAKE_RENT + LAMPORTS_PER_SOL);
+        let quote = quote_withdraw_sol(&stake_pool, &accounts, 900_000_000, false).unwrap();
+        assert_eq!(quote.pool_tokens_fee, 1_800_000);
+        assert_eq!(quote.lamports, 998_000_000);
+        assert_eq!(
+            quote_withdraw_sol(&stake_pool, &accounts, 900_000_001, true),
+            Err(StakePoolError::SolWithdrawalTooLarge)
+        );
+    }
+
+    #[test]
+    fn withdraw_stake_prefers_validator() {
+        let mut stake_pool = test_stake_pool();
+        let small = Pubkey::new_unique();
+        let large = Pubkey::new_unique();
+        let validators = [
+            test_validator(small, 10 * LAMPORTS_PER_SOL, 0),
+            test_validator(large, 500 * LAMPORTS_PER_SOL, 0),
+        ];
+        let accounts = test_accounts(&validators, 490 * LAMPORTS_PER_SOL);
+
+        let quote = quote_withdraw_stake(&stake_pool, &accounts, LAMPORTS_PER_SOL, false).unwrap();
+        assert_eq!(quote.splits.len(), 1);
+        assert_eq!(quote.splits[0].vote_account_address, Some(large));
+
+        stake_pool.preferred_withdraw_validator_vote_address = Some(small);
+        let quote =
+            quote_withdraw_stake(&stake_pool, &accounts, 20 * LAMPORTS_PER_SOL, false).unwrap();
+        assert_eq!(quote.splits.len(), 2);
+        assert_eq!(quote.splits[0].vote_account_address, Some(small));
+        assert_eq!(quote.splits[0].source, WithdrawStakeSource::Active);
+        assert_eq!(
+            quote.splits[0].amounts.lamports,
+            10 * LAMPORTS_PER_SOL - STAKE_RENT - minimum_delegation(1)
+        );
+        assert_eq!(quote.splits[1].vote_account_address, Some(large));
+
+        stake_pool.preferred_withdraw_validator_vote_address = Some(Pubkey::new_unique());
+        assert_eq!(
+            quote_withdraw_stake(&stake_pool, &accounts, LAMPORTS_PER_SOL, false),
+            Err(StakePoolError::ValidatorNotFound)
+        );
+    }
+
+    #[test]
+    fn withdraw_stake_transient_then_reserve() {
+        let stake_pool = test_stake_pool();
+        let vote_account_address = Pubkey::new_unique();
+        let validators = [test_validator(
+            vote_account_address,
+            STAKE_RENT + minimum_delegation(1),
+            5 * LAMPORTS_PER_SOL,
+        )];
+        let accounts = test_accounts(&validators, 995 * LAMPORTS_PER_SOL);
+
+        let quote =
+            quote_withdraw_stake(&stake_pool, &accounts, 10 * LAMPORTS_PER_SOL, false).unwrap();
+        let sources = quote
+            .splits
+            .iter()
+            .map(|split| split.source)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            vec![WithdrawStakeSource::Transient, WithdrawStakeSource::Reserve]
+        );
+        assert_eq!(quote.splits[1].stake_account, stake_pool.reserve_stake);
+
+        assert_eq!(
+            quote_withdraw_stake(&stake_pool, &accounts, 950 * LAMPORTS_PER_SOL, false),
+            Err(StakePoolError::StakeLamportsNotEqualToMinimum)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn withdraw_stake_splits_add_up(
+            pool_tokens in 1..400 * LAMPORTS_PER_SOL,
+            active_stake_lamports in 0..500 * LAMPORTS_PER_SOL,
+            transient_stake_lamports in 0..100 * LAMPORTS_PER_SOL,
+        ) {
+            let stake_pool = test_stake_pool();
+            let validators = [
+                test_validator(Pubkey::new_unique(), STAKE_RENT + active_stake_lamports, 0),
+                test_validator(Pubkey::new_unique(), 0, STAKE_RENT + transient_stake_lamports),
+            ];
+            let accounts = test_accounts(&validators, 400 * LAMPORTS_PER_SOL);
+            let quote = quote_withdraw_stake(&stake_pool, &accounts, pool_tokens, false).unwrap();
+            prop_assert_eq!(quote.amounts.pool_tokens, pool_tokens);
+            prop_assert_eq!(
+                quote.splits.iter().map(|split| split.amounts.lamports).sum::<u64>(),
+                quote.amounts.lamports
+            );
+            prop_assert_eq!(
+                quote.amounts.pool_tokens_burnt + quote.amounts.pool_tokens_fee,
+                pool_tokens
+            );
+        }
+    }
+}
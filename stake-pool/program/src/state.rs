@@ -37,6 +37,8 @@ pub enum AccountType {
     StakePool,
     /// Validator stake list
     ValidatorList,
+    /// Validator admission policy
+    ValidatorPolicy,
 }
 
 /// Initialized program details.
@@ -863,6 +865,86 @@ impl ValidatorListHeader {
     }
 }
 
+/// How the vote accounts listed in a `ValidatorPolicy` are used
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub enum ValidatorFilter {
+    /// The list is ignored
+    #[default]
+    None,
+    /// Only listed vote accounts can be added
+    Allow,
+    /// Listed vote accounts cannot be added
+    Deny,
+}
+
+/// Restrictions on the validators a pool's staker can add, stored at the
+/// address given by `find_validator_policy_program_address`
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct ValidatorPolicy {
+    /// Account type, must be ValidatorPolicy currently
+    pub account_type: AccountType,
+
+    /// Stake pool the policy applies to
+    pub stake_pool: Pubkey,
+
+    /// Highest commission, in percent, a validator can charge to be added,
+    /// and above which anyone can eject it from the pool
+    pub max_commission: u8,
+
+    /// Minimum activated stake on the vote account for it to be added.
+    ///
+    /// The runtime does not expose a vote account's activated stake to
+    /// programs, so `AddValidatorToPool` does not check it. The CLI enforces
+    /// it when adding a validator, and other clients should do the same.
+    pub minimum_activated_stake: u64,
+
+    /// How `vote_accounts` is applied
+    pub filter: ValidatorFilter,
+
+    /// Allowed or denied vote accounts, depending on `filter`
+    pub vote_accounts: Vec<Pubkey>,
+}
+
+impl ValidatorPolicy {
+    /// Check if ValidatorPolicy is initialized
+    pub fn is_valid(&self) -> bool {
+        self.account_type == AccountType::ValidatorPolicy
+    }
+
+    /// Check that a validator can be added under this policy
+    pub fn check_validator(
+        &self,
+        vote_account_address: &Pubkey,
+        commission: u8,
+    ) -> Result<(), StakePoolError> {
+        if commission > self.max_commission {
+            msg!(
+                "Vote account {} has commission {}, policy allows at most {}",
+                vote_account_address,
+                commission,
+                self.max_commission
+            );
+            return Err(StakePoolError::ValidatorPolicyViolation);
+        }
+        let listed = self.vote_accounts.contains(vote_account_address);
+        let allowed = match self.filter {
+            ValidatorFilter::None => true,
+            ValidatorFilter::Allow => listed,
+            ValidatorFilter::Deny => !listed,
+        };
+        if !allowed {
+            msg!(
+                "Vote account {} is not allowed by the pool's validator policy",
+                vote_account_address
+            );
+            return Err(StakePoolError::ValidatorPolicyViolation);
+        }
+        Ok(())
+    }
+}
+
 /// Wrapper type that "counts down" epochs, which is Borsh-compatible with the
 /// native `Option`
 #[repr(C)]
@@ -136,6 +136,12 @@ pub async fn create_mint(
             ),
             ExtensionType::NonTransferable =>
                 spl_token_2022::instruction::initialize_non_transferable_mint(program_id, &mint_pubkey),
+            ExtensionType::MetadataPointer => spl_token_2022::extension::metadata_pointer::instruction::initialize(
+                program_id,
+                &mint_pubkey,
+                Some(*manager),
+                Some(mint_pubkey),
+            ),
             _ => unimplemented!(),
         };
         instructions.push(instruction.unwrap());
@@ -930,6 +936,24 @@ impl StakePoolAccounts {
         payer: &Keypair,
         recent_blockhash: &Hash,
         reserve_lamports: u64,
+    ) -> Result<(), TransportError> {
+        self.initialize_stake_pool_with_mint_extensions(
+            banks_client,
+            payer,
+            recent_blockhash,
+            reserve_lamports,
+            &[],
+        )
+        .await
+    }
+
+    pub async fn initialize_stake_pool_with_mint_extensions(
+        &self,
+        banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+        reserve_lamports: u64,
+        mint_extensions: &[ExtensionType],
     ) -> Result<(), TransportError> {
         create_mint(
             banks_client,
@@ -939,7 +963,7 @@ impl StakePoolAccounts {
             &self.pool_mint,
             &self.withdraw_authority,
             self.pool_decimals,
-            &[],
+            mint_extensions,
         )
         .await?;
         create_token_account(
@@ -950,7 +974,7 @@ impl StakePoolAccounts {
             &self.pool_fee_account,
             &self.pool_mint.pubkey(),
             &self.manager,
-            &[],
+            &ExtensionType::get_required_init_account_extensions(mint_extensions),
         )
         .await?;
         create_independent_stake_account(
@@ -1626,6 +1650,66 @@ impl StakePoolAccounts {
             .err()
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub async fn set_validator_policy(
+        &self,
+        banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+        max_commission: u8,
+        filter: state::ValidatorFilter,
+        vote_accounts: Vec<Pubkey>,
+    ) -> Option<TransportError> {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::set_validator_policy(
+                &id(),
+                &self.stake_pool.pubkey(),
+                &self.manager.pubkey(),
+                &payer.pubkey(),
+                max_commission,
+                0,
+                filter,
+                vote_accounts,
+            )],
+            Some(&payer.pubkey()),
+            &[payer, &self.manager],
+            *recent_blockhash,
+        );
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|e| e.into())
+            .err()
+    }
+
+    pub async fn eject_validator(
+        &self,
+        banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+        validator_stake: &ValidatorStakeAccount,
+    ) -> Option<TransportError> {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::eject_validator(
+                &id(),
+                &self.stake_pool.pubkey(),
+                &self.withdraw_authority,
+                &self.validator_list.pubkey(),
+                &validator_stake.stake_account,
+                &validator_stake.transient_stake_account,
+                &validator_stake.vote.pubkey(),
+            )],
+            Some(&payer.pubkey()),
+            &[payer],
+            *recent_blockhash,
+        );
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|e| e.into())
+            .err()
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub async fn decrease_validator_stake_deprecated(
         &self,
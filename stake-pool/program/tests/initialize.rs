@@ -632,6 +632,111 @@ async fn fail_with_unsupported_mint_extension() {
     );
 }
 
+#[tokio::test]
+async fn fail_with_foreign_metadata_pointer_authority() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let stake_pool_accounts = StakePoolAccounts::new_with_token_program(spl_token_2022::id());
+    let pool_mint = stake_pool_accounts.pool_mint.pubkey();
+
+    let mint_extensions = vec![ExtensionType::MetadataPointer];
+    let space =
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&mint_extensions)
+            .unwrap();
+    let rent = banks_client.get_rent().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &pool_mint,
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::extension::metadata_pointer::instruction::initialize(
+                &spl_token_2022::id(),
+                &pool_mint,
+                Some(Pubkey::new_unique()),
+                Some(pool_mint),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint(
+                &spl_token_2022::id(),
+                &pool_mint,
+                &stake_pool_accounts.withdraw_authority,
+                None,
+                stake_pool_accounts.pool_decimals,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &stake_pool_accounts.pool_mint],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &stake_pool_accounts.token_program_id,
+        &stake_pool_accounts.pool_fee_account,
+        &pool_mint,
+        &stake_pool_accounts.manager,
+        &[],
+    )
+    .await
+    .unwrap();
+
+    create_independent_stake_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &stake_pool_accounts.reserve_stake,
+        &stake::state::Authorized {
+            staker: stake_pool_accounts.withdraw_authority,
+            withdrawer: stake_pool_accounts.withdraw_authority,
+        },
+        &stake::state::Lockup::default(),
+        MINIMUM_RESERVE_LAMPORTS,
+    )
+    .await;
+
+    let error = create_stake_pool(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &stake_pool_accounts.stake_pool,
+        &stake_pool_accounts.validator_list,
+        &stake_pool_accounts.reserve_stake.pubkey(),
+        &stake_pool_accounts.token_program_id,
+        &pool_mint,
+        &stake_pool_accounts.pool_fee_account.pubkey(),
+        &stake_pool_accounts.manager,
+        &stake_pool_accounts.staker.pubkey(),
+        &stake_pool_accounts.withdraw_authority,
+        &None,
+        &stake_pool_accounts.epoch_fee,
+        &stake_pool_accounts.withdrawal_fee,
+        &stake_pool_accounts.deposit_fee,
+        stake_pool_accounts.referral_fee,
+        &stake_pool_accounts.sol_deposit_fee,
+        stake_pool_accounts.sol_referral_fee,
+        stake_pool_accounts.max_validators,
+    )
+    .await
+    .err()
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(error::StakePoolError::InvalidMintMetadataAuthority as u32),
+        )
+    );
+}
+
 #[tokio::test]
 async fn fail_with_unsupported_account_extension() {
     let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
@@ -0,0 +1,254 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, stake},
+    solana_program_test::*,
+    solana_sdk::signature::{Keypair, Signer},
+    spl_stake_pool::quote::{self, PoolStakeAccounts, WithdrawStakeSource},
+};
+
+#[tokio::test]
+async fn success_deposit_sol_matches_quote() {
+    let mut context = program_test().start_with_context().await;
+    let stake_pool_accounts = StakePoolAccounts::default();
+    stake_pool_accounts
+        .initialize_stake_pool(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
+
+    let user = Keypair::new();
+    let pool_token_account = Keypair::new();
+    create_token_account(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts.token_program_id,
+        &pool_token_account,
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &user,
+        &[],
+    )
+    .await
+    .unwrap();
+
+    for lamports in [TEST_STAKE_AMOUNT, 3 * LAMPORTS_PER_SOL + 7] {
+        let stake_pool = stake_pool_accounts
+            .get_stake_pool(&mut context.banks_client)
+            .await;
+        let deposit_quote = quote::quote_deposit_sol(&stake_pool, lamports).unwrap();
+        assert!(deposit_quote.pool_tokens_fee > 0);
+
+        let pre_user_tokens =
+            get_token_balance(&mut context.banks_client, &pool_token_account.pubkey()).await;
+        let pre_fee_tokens = get_token_balance(
+            &mut context.banks_client,
+            &stake_pool_accounts.pool_fee_account.pubkey(),
+        )
+        .await;
+
+        let error = stake_pool_accounts
+            .deposit_sol(
+                &mut context.banks_client,
+                &context.payer,
+                &context.last_blockhash,
+                &pool_token_account.pubkey(),
+                lamports,
+                None,
+            )
+            .await;
+        assert!(error.is_none(), "{:?}", error);
+
+        let user_tokens =
+            get_token_balance(&mut context.banks_client, &pool_token_account.pubkey()).await;
+        let fee_tokens = get_token_balance(
+            &mut context.banks_client,
+            &stake_pool_accounts.pool_fee_account.pubkey(),
+        )
+        .await;
+        assert_eq!(
+            user_tokens - pre_user_tokens,
+            deposit_quote.pool_tokens_user
+        );
+        // the helper uses the manager fee account as referrer
+        assert_eq!(fee_tokens - pre_fee_tokens, deposit_quote.pool_tokens_fee);
+    }
+}
+
+#[tokio::test]
+async fn success_withdraw_stake_follows_quote() {
+    let (mut context, stake_pool_accounts, validator_stake_account, deposit_info, _, _, _) =
+        setup_for_withdraw(spl_token::id(), LAMPORTS_PER_SOL).await;
+
+    let last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+
+    // a larger validator, which must be withdrawn from first
+    let other_validator_stake_account = simple_add_validator_to_pool(
+        &mut context.banks_client,
+        &context.payer,
+        &last_blockhash,
+        &stake_pool_accounts,
+        None,
+    )
+    .await;
+    simple_deposit_stake(
+        &mut context.banks_client,
+        &context.payer,
+        &last_blockhash,
+        &stake_pool_accounts,
+        &other_validator_stake_account,
+        TEST_STAKE_AMOUNT * 5,
+    )
+    .await
+    .unwrap();
+
+    // enough SOL that the withdrawal also needs the reserve
+    let error = stake_pool_accounts
+        .deposit_sol(
+            &mut context.banks_client,
+            &context.payer,
+            &last_blockhash,
+            &deposit_info.pool_account.pubkey(),
+            10 * LAMPORTS_PER_SOL,
+            None,
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let pool_tokens = get_token_balance(
+        &mut context.banks_client,
+        &deposit_info.pool_account.pubkey(),
+    )
+    .await;
+    let user_transfer_authority = Keypair::new();
+    delegate_tokens(
+        &mut context.banks_client,
+        &context.payer,
+        &last_blockhash,
+        &stake_pool_accounts.token_program_id,
+        &deposit_info.pool_account.pubkey(),
+        &deposit_info.authority,
+        &user_transfer_authority.pubkey(),
+        pool_tokens,
+    )
+    .await;
+
+    let stake_pool = stake_pool_accounts
+        .get_stake_pool(&mut context.banks_client)
+        .await;
+    let validator_list = stake_pool_accounts
+        .get_validator_list(&mut context.banks_client)
+        .await;
+    let reserve_stake = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.reserve_stake.pubkey(),
+    )
+    .await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let stake_rent = rent.minimum_balance(std::mem::size_of::<stake::state::StakeStateV2>());
+    let stake_minimum_delegation =
+        stake_get_minimum_delegation(&mut context.banks_client, &context.payer, &last_blockhash)
+            .await;
+
+    let withdraw_quote = quote::quote_withdraw_stake(
+        &stake_pool,
+        &PoolStakeAccounts {
+            stake_pool_address: stake_pool_accounts.stake_pool.pubkey(),
+            validators: &validator_list.validators,
+            reserve_lamports: reserve_stake.lamports,
+            stake_rent_exempt_reserve: stake_rent,
+            stake_program_minimum_delegation: stake_minimum_delegation,
+        },
+        pool_tokens,
+        false,
+    )
+    .unwrap();
+    let sources = withdraw_quote
+        .splits
+        .iter()
+        .map(|split| (split.source, split.stake_account))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sources,
+        vec![
+            (
+                WithdrawStakeSource::Active,
+                other_validator_stake_account.stake_account
+            ),
+            (
+                WithdrawStakeSource::Active,
+                validator_stake_account.stake_account
+            ),
+            (
+                WithdrawStakeSource::Reserve,
+                stake_pool_accounts.reserve_stake.pubkey()
+            ),
+        ]
+    );
+    assert_eq!(withdraw_quote.amounts.pool_tokens, pool_tokens);
+
+    let pre_fee_tokens = get_token_balance(
+        &mut context.banks_client,
+        &stake_pool_accounts.pool_fee_account.pubkey(),
+    )
+    .await;
+    for split in &withdraw_quote.splits {
+        let user_stake_recipient = Keypair::new();
+        create_blank_stake_account(
+            &mut context.banks_client,
+            &context.payer,
+            &last_blockhash,
+            &user_stake_recipient,
+        )
+        .await;
+        let error = stake_pool_accounts
+            .withdraw_stake(
+                &mut context.banks_client,
+                &context.payer,
+                &last_blockhash,
+                &user_stake_recipient.pubkey(),
+                &user_transfer_authority,
+                &deposit_info.pool_account.pubkey(),
+                &split.stake_account,
+                &Pubkey::new_unique(),
+                split.amounts.pool_tokens,
+            )
+            .await;
+        assert!(error.is_none(), "{:?}", error);
+
+        let user_stake_recipient_account =
+            get_account(&mut context.banks_client, &user_stake_recipient.pubkey()).await;
+        assert_eq!(
+            user_stake_recipient_account.lamports,
+            stake_rent + split.amounts.lamports
+        );
+    }
+
+    let user_tokens = get_token_balance(
+        &mut context.banks_client,
+        &deposit_info.pool_account.pubkey(),
+    )
+    .await;
+    assert_eq!(user_tokens, 0);
+    let fee_tokens = get_token_balance(
+        &mut context.banks_client,
+        &stake_pool_accounts.pool_fee_account.pubkey(),
+    )
+    .await;
+    assert_eq!(
+        fee_tokens - pre_fee_tokens,
+        withdraw_quote.amounts.pool_tokens_fee
+    );
+}
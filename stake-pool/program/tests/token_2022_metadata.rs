@@ -0,0 +1,225 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program::{instruction::InstructionError, pubkey::Pubkey},
+    solana_program_test::*,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_stake_pool::{
+        error::StakePoolError::{InvalidMetadataAccount, WrongManager},
+        instruction, MINIMUM_RESERVE_LAMPORTS,
+    },
+    spl_token_2022::{
+        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        state::Mint,
+    },
+    spl_token_metadata_interface::state::TokenMetadata,
+};
+
+async fn setup() -> (ProgramTestContext, StakePoolAccounts) {
+    let mut context = program_test().start_with_context().await;
+    let stake_pool_accounts = StakePoolAccounts::new_with_token_program(spl_token_2022::id());
+    stake_pool_accounts
+        .initialize_stake_pool_with_mint_extensions(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            MINIMUM_RESERVE_LAMPORTS,
+            &[ExtensionType::MetadataPointer],
+        )
+        .await
+        .unwrap();
+
+    (context, stake_pool_accounts)
+}
+
+async fn create_metadata(
+    context: &mut ProgramTestContext,
+    stake_pool_accounts: &StakePoolAccounts,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) {
+    let ix = instruction::create_token_2022_metadata(
+        &spl_stake_pool::id(),
+        &stake_pool_accounts.stake_pool.pubkey(),
+        &stake_pool_accounts.manager.pubkey(),
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &context.payer.pubkey(),
+        name.to_string(),
+        symbol.to_string(),
+        uri.to_string(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn get_token_metadata(banks_client: &mut BanksClient, pool_mint: &Pubkey) -> TokenMetadata {
+    let account = get_account(banks_client, pool_mint).await;
+    let mint = StateWithExtensions::<Mint>::unpack(account.data.as_slice()).unwrap();
+    mint.get_variable_len_extension::<TokenMetadata>().unwrap()
+}
+
+#[tokio::test]
+async fn success_create() {
+    let (mut context, stake_pool_accounts) = setup().await;
+
+    let name = "test_name";
+    let symbol = "SYM";
+    let uri = "test_uri";
+    create_metadata(&mut context, &stake_pool_accounts, name, symbol, uri).await;
+
+    let metadata = get_token_metadata(
+        &mut context.banks_client,
+        &stake_pool_accounts.pool_mint.pubkey(),
+    )
+    .await;
+
+    assert_eq!(metadata.name, name);
+    assert_eq!(metadata.symbol, symbol);
+    assert_eq!(metadata.uri, uri);
+    assert_eq!(metadata.mint, stake_pool_accounts.pool_mint.pubkey());
+    assert_eq!(
+        Option::<Pubkey>::from(metadata.update_authority),
+        Some(stake_pool_accounts.withdraw_authority)
+    );
+}
+
+#[tokio::test]
+async fn success_update() {
+    let (mut context, stake_pool_accounts) = setup().await;
+    create_metadata(&mut context, &stake_pool_accounts, "name", "SYM", "uri").await;
+
+    // longer values force the mint to grow, funded by the payer
+    let updated_name = "updated_test_name";
+    let updated_symbol = "USYM";
+    let updated_uri = "https://example.com/updated_test_uri.json";
+
+    let ix = instruction::update_token_2022_metadata(
+        &spl_stake_pool::id(),
+        &stake_pool_accounts.stake_pool.pubkey(),
+        &stake_pool_accounts.manager.pubkey(),
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &context.payer.pubkey(),
+        updated_name.to_string(),
+        updated_symbol.to_string(),
+        updated_uri.to_string(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let metadata = get_token_metadata(
+        &mut context.banks_client,
+        &stake_pool_accounts.pool_mint.pubkey(),
+    )
+    .await;
+
+    assert_eq!(metadata.name, updated_name);
+    assert_eq!(metadata.symbol, updated_symbol);
+    assert_eq!(metadata.uri, updated_uri);
+}
+
+#[tokio::test]
+async fn fail_create_wrong_metadata_account() {
+    let (mut context, stake_pool_accounts) = setup().await;
+
+    let mut ix = instruction::create_token_2022_metadata(
+        &spl_stake_pool::id(),
+        &stake_pool_accounts.stake_pool.pubkey(),
+        &stake_pool_accounts.manager.pubkey(),
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &context.payer.pubkey(),
+        "name".to_string(),
+        "SYM".to_string(),
+        "uri".to_string(),
+    );
+    ix.accounts[5].pubkey = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(InvalidMetadataAccount as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_update_wrong_manager() {
+    let (mut context, stake_pool_accounts) = setup().await;
+    create_metadata(&mut context, &stake_pool_accounts, "name", "SYM", "uri").await;
+
+    let random_keypair = Keypair::new();
+    let ix = instruction::update_token_2022_metadata(
+        &spl_stake_pool::id(),
+        &stake_pool_accounts.stake_pool.pubkey(),
+        &random_keypair.pubkey(),
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &context.payer.pubkey(),
+        "new_name".to_string(),
+        "NEW".to_string(),
+        "new_uri".to_string(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &random_keypair],
+        context.last_blockhash,
+    );
+
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::Custom(WrongManager as u32))
+    );
+}
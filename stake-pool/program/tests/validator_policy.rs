@@ -0,0 +1,412 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program::{borsh1::try_from_slice_unchecked, pubkey::Pubkey},
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+        transport::TransportError,
+    },
+    solana_vote_program::vote_state::VoteStateVersions,
+    spl_stake_pool::{
+        error::StakePoolError,
+        find_validator_policy_program_address, id, instruction,
+        state::{AccountType, StakeStatus, ValidatorFilter, ValidatorList, ValidatorPolicy},
+        MINIMUM_RESERVE_LAMPORTS,
+    },
+};
+
+async fn setup() -> (ProgramTestContext, StakePoolAccounts, ValidatorStakeAccount) {
+    let mut context = program_test().start_with_context().await;
+    let stake_pool_accounts = StakePoolAccounts::default();
+    stake_pool_accounts
+        .initialize_stake_pool(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            10_000_000_000 + MINIMUM_RESERVE_LAMPORTS,
+        )
+        .await
+        .unwrap();
+
+    let validator_stake =
+        ValidatorStakeAccount::new(&stake_pool_accounts.stake_pool.pubkey(), None, 0);
+    create_vote(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &validator_stake.validator,
+        &validator_stake.vote,
+    )
+    .await;
+
+    (context, stake_pool_accounts, validator_stake)
+}
+
+async fn set_vote_commission(context: &mut ProgramTestContext, vote: &Pubkey, commission: u8) {
+    let mut account = get_account(&mut context.banks_client, vote).await;
+    let mut vote_state = bincode::deserialize::<VoteStateVersions>(&account.data)
+        .unwrap()
+        .convert_to_current();
+    vote_state.commission = commission;
+    bincode::serialize_into(
+        &mut account.data[..],
+        &VoteStateVersions::new_current(vote_state),
+    )
+    .unwrap();
+    context.set_account(vote, &account.into());
+}
+
+async fn add_validator(
+    context: &mut ProgramTestContext,
+    stake_pool_accounts: &StakePoolAccounts,
+    validator_stake: &ValidatorStakeAccount,
+) -> Option<TransportError> {
+    stake_pool_accounts
+        .add_validator_to_pool(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &validator_stake.stake_account,
+            &validator_stake.vote.pubkey(),
+            validator_stake.validator_stake_seed,
+        )
+        .await
+}
+
+fn assert_custom_error(error: Option<TransportError>, expected: StakePoolError) {
+    match error.unwrap() {
+        TransportError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(error_index),
+        )) => assert_eq!(error_index, expected as u32),
+        error => panic!("Unexpected error {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn success_set_and_update() {
+    let (mut context, stake_pool_accounts, _) = setup().await;
+    let validator_policy_address =
+        find_validator_policy_program_address(&id(), &stake_pool_accounts.stake_pool.pubkey()).0;
+
+    let error = stake_pool_accounts
+        .set_validator_policy(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            10,
+            ValidatorFilter::None,
+            vec![],
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let account = get_account(&mut context.banks_client, &validator_policy_address).await;
+    assert_eq!(account.owner, id());
+    let validator_policy =
+        try_from_slice_unchecked::<ValidatorPolicy>(account.data.as_slice()).unwrap();
+    assert_eq!(
+        validator_policy,
+        ValidatorPolicy {
+            account_type: AccountType::ValidatorPolicy,
+            stake_pool: stake_pool_accounts.stake_pool.pubkey(),
+            max_commission: 10,
+            minimum_activated_stake: 0,
+            filter: ValidatorFilter::None,
+            vote_accounts: vec![],
+        }
+    );
+
+    // growing the list reallocates the account
+    let vote_accounts = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let error = stake_pool_accounts
+        .set_validator_policy(
+            &mut context.banks_client,
+            &context.payer,
+            &last_blockhash,
+            5,
+            ValidatorFilter::Deny,
+            vote_accounts.clone(),
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let account = get_account(&mut context.banks_client, &validator_policy_address).await;
+    let validator_policy =
+        try_from_slice_unchecked::<ValidatorPolicy>(account.data.as_slice()).unwrap();
+    assert_eq!(validator_policy.max_commission, 5);
+    assert_eq!(validator_policy.filter, ValidatorFilter::Deny);
+    assert_eq!(validator_policy.vote_accounts, vote_accounts);
+}
+
+#[tokio::test]
+async fn fail_set_wrong_manager() {
+    let (mut context, stake_pool_accounts, _) = setup().await;
+
+    let wrong_manager = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_validator_policy(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &wrong_manager.pubkey(),
+            &context.payer.pubkey(),
+            10,
+            0,
+            ValidatorFilter::None,
+            vec![],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_manager],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .map(|e| e.into());
+    assert_custom_error(error, StakePoolError::WrongManager);
+}
+
+#[tokio::test]
+async fn fail_set_commission_over_100() {
+    let (mut context, stake_pool_accounts, _) = setup().await;
+
+    let error = stake_pool_accounts
+        .set_validator_policy(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            101,
+            ValidatorFilter::None,
+            vec![],
+        )
+        .await;
+    match error.unwrap() {
+        TransportError::TransactionError(TransactionError::InstructionError(_, error)) => {
+            assert_eq!(error, InstructionError::InvalidArgument);
+        }
+        error => panic!("Unexpected error {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn fail_add_commission_too_high() {
+    let (mut context, stake_pool_accounts, validator_stake) = setup().await;
+    set_vote_commission(&mut context, &validator_stake.vote.pubkey(), 10).await;
+
+    let error = stake_pool_accounts
+        .set_validator_policy(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            5,
+            ValidatorFilter::None,
+            vec![],
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let error = add_validator(&mut context, &stake_pool_accounts, &validator_stake).await;
+    assert_custom_error(error, StakePoolError::ValidatorPolicyViolation);
+}
+
+#[tokio::test]
+async fn fail_add_not_in_allowlist() {
+    let (mut context, stake_pool_accounts, validator_stake) = setup().await;
+
+    let error = stake_pool_accounts
+        .set_validator_policy(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            100,
+            ValidatorFilter::Allow,
+            vec![Pubkey::new_unique()],
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let error = add_validator(&mut context, &stake_pool_accounts, &validator_stake).await;
+    assert_custom_error(error, StakePoolError::ValidatorPolicyViolation);
+}
+
+#[tokio::test]
+async fn fail_add_in_denylist() {
+    let (mut context, stake_pool_accounts, validator_stake) = setup().await;
+
+    let error = stake_pool_accounts
+        .set_validator_policy(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            100,
+            ValidatorFilter::Deny,
+            vec![validator_stake.vote.pubkey()],
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let error = add_validator(&mut context, &stake_pool_accounts, &validator_stake).await;
+    assert_custom_error(error, StakePoolError::ValidatorPolicyViolation);
+}
+
+#[tokio::test]
+async fn success_add_in_allowlist() {
+    let (mut context, stake_pool_accounts, validator_stake) = setup().await;
+    set_vote_commission(&mut context, &validator_stake.vote.pubkey(), 5).await;
+
+    let error = stake_pool_accounts
+        .set_validator_policy(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            5,
+            ValidatorFilter::Allow,
+            vec![validator_stake.vote.pubkey()],
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let error = add_validator(&mut context, &stake_pool_accounts, &validator_stake).await;
+    assert!(error.is_none(), "{:?}", error);
+}
+
+#[tokio::test]
+async fn success_add_minimum_activated_stake_left_to_clients() {
+    let (mut context, stake_pool_accounts, validator_stake) = setup().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_validator_policy(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.manager.pubkey(),
+            &context.payer.pubkey(),
+            100,
+            u64::MAX,
+            ValidatorFilter::None,
+            vec![],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let validator_policy_address =
+        find_validator_policy_program_address(&id(), &stake_pool_accounts.stake_pool.pubkey()).0;
+    let account = get_account(&mut context.banks_client, &validator_policy_address).await;
+    let validator_policy =
+        try_from_slice_unchecked::<ValidatorPolicy>(account.data.as_slice()).unwrap();
+    assert_eq!(validator_policy.minimum_activated_stake, u64::MAX);
+
+    // the program cannot see activated stake, so the add goes through
+    let error = add_validator(&mut context, &stake_pool_accounts, &validator_stake).await;
+    assert!(error.is_none(), "{:?}", error);
+}
+
+#[tokio::test]
+async fn success_eject() {
+    let (mut context, stake_pool_accounts, validator_stake) = setup().await;
+    let error = add_validator(&mut context, &stake_pool_accounts, &validator_stake).await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let error = stake_pool_accounts
+        .set_validator_policy(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            5,
+            ValidatorFilter::None,
+            vec![],
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+    set_vote_commission(&mut context, &validator_stake.vote.pubkey(), 6).await;
+
+    let error = stake_pool_accounts
+        .eject_validator(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &validator_stake,
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let validator_list = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.validator_list.pubkey(),
+    )
+    .await;
+    let validator_list =
+        try_from_slice_unchecked::<ValidatorList>(validator_list.data.as_slice()).unwrap();
+    assert_eq!(
+        validator_list.validators[0].status,
+        StakeStatus::DeactivatingValidator.into()
+    );
+}
+
+#[tokio::test]
+async fn fail_eject_within_policy() {
+    let (mut context, stake_pool_accounts, validator_stake) = setup().await;
+    let error = add_validator(&mut context, &stake_pool_accounts, &validator_stake).await;
+    assert!(error.is_none(), "{:?}", error);
+
+    let error = stake_pool_accounts
+        .set_validator_policy(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            5,
+            ValidatorFilter::None,
+            vec![],
+        )
+        .await;
+    assert!(error.is_none(), "{:?}", error);
+    set_vote_commission(&mut context, &validator_stake.vote.pubkey(), 5).await;
+
+    let error = stake_pool_accounts
+        .eject_validator(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &validator_stake,
+        )
+        .await;
+    assert_custom_error(error, StakePoolError::ValidatorCompliesWithPolicy);
+}
+
+#[tokio::test]
+async fn fail_eject_without_policy() {
+    let (mut context, stake_pool_accounts, validator_stake) = setup().await;
+    let error = add_validator(&mut context, &stake_pool_accounts, &validator_stake).await;
+    assert!(error.is_none(), "{:?}", error);
+    set_vote_commission(&mut context, &validator_stake.vote.pubkey(), 100).await;
+
+    let error = stake_pool_accounts
+        .eject_validator(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &validator_stake,
+        )
+        .await;
+    assert_custom_error(error, StakePoolError::InvalidState);
+}
@@ -20,8 +20,8 @@ use {
         transport::TransportError,
     },
     spl_stake_pool::{
-        error::StakePoolError, find_stake_program_address, id, instruction, state,
-        MINIMUM_RESERVE_LAMPORTS,
+        error::StakePoolError, find_stake_program_address, find_validator_policy_program_address,
+        id, instruction, state, MINIMUM_RESERVE_LAMPORTS,
     },
 };
 
@@ -287,6 +287,11 @@ async fn fail_without_signature() {
         AccountMeta::new_readonly(stake::config::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
         AccountMeta::new_readonly(stake::program::id(), false),
+        AccountMeta::new_readonly(
+            find_validator_policy_program_address(&id(), &stake_pool_accounts.stake_pool.pubkey())
+                .0,
+            false,
+        ),
     ];
     let instruction = Instruction {
         program_id: id(),
@@ -342,6 +347,11 @@ async fn fail_with_wrong_stake_program_id() {
         AccountMeta::new_readonly(stake::config::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
         AccountMeta::new_readonly(wrong_stake_program, false),
+        AccountMeta::new_readonly(
+            find_validator_policy_program_address(&id(), &stake_pool_accounts.stake_pool.pubkey())
+                .0,
+            false,
+        ),
     ];
     let instruction = Instruction {
         program_id: id(),
@@ -395,6 +405,11 @@ async fn fail_with_wrong_system_program_id() {
         AccountMeta::new_readonly(stake::config::id(), false),
         AccountMeta::new_readonly(wrong_system_program, false),
         AccountMeta::new_readonly(stake::program::id(), false),
+        AccountMeta::new_readonly(
+            find_validator_policy_program_address(&id(), &stake_pool_accounts.stake_pool.pubkey())
+                .0,
+            false,
+        ),
     ];
     let instruction = Instruction {
         program_id: id(),
@@ -95,6 +95,17 @@ def find_ephemeral_stake_program_address(
     )
 
 
+def find_validator_policy_program_address(
+    program_id: PublicKey,
+    stake_pool_address: PublicKey,
+) -> Tuple[PublicKey, int]:
+    """Generates the validator policy program address for the stake pool"""
+    return PublicKey.find_program_address(
+        [VALIDATOR_POLICY_SEED_PREFIX, bytes(stake_pool_address)],
+        program_id,
+    )
+
+
 def find_metadata_account(
     mint_key: PublicKey
 ) -> Tuple[PublicKey, int]:
@@ -119,3 +130,5 @@ METADATA_SEED_PREFIX = b"metadata"
 """Seed used to avoid certain collision attacks."""
 EPHEMERAL_STAKE_SEED_PREFIX = b'ephemeral'
 """Seed for ephemeral stake account"""
+VALIDATOR_POLICY_SEED_PREFIX = b"policy"
+"""Seed used to derive the validator policy account."""
@@ -12,7 +12,7 @@ from spl.token.constants import TOKEN_PROGRAM_ID
 
 from stake.constants import STAKE_PROGRAM_ID, SYSVAR_STAKE_CONFIG_ID
 from stake_pool.constants import find_stake_program_address, find_transient_stake_program_address
-from stake_pool.constants import find_withdraw_authority_program_address
+from stake_pool.constants import find_validator_policy_program_address, find_withdraw_authority_program_address
 from stake_pool.constants import STAKE_POOL_PROGRAM_ID
 from stake_pool.state import Fee, FEE_LAYOUT
 
@@ -784,6 +784,11 @@ def add_validator_to_pool(params: AddValidatorToPoolParams) -> TransactionInstru
             AccountMeta(pubkey=params.stake_config_sysvar, is_signer=False, is_writable=False),
             AccountMeta(pubkey=params.system_program_id, is_signer=False, is_writable=False),
             AccountMeta(pubkey=params.stake_program_id, is_signer=False, is_writable=False),
+            AccountMeta(
+                pubkey=find_validator_policy_program_address(params.program_id, params.stake_pool)[0],
+                is_signer=False,
+                is_writable=False,
+            ),
         ],
         program_id=params.program_id,
         data=INSTRUCTIONS_LAYOUT.build(
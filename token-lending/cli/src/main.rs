@@ -1,21 +1,33 @@
 #![allow(clippy::arithmetic_side_effects)]
+mod output;
+
 use {
+    crate::output::{CliLendingMarket, CliObligation, CliReserve, RESERVE_OPERATIONS},
     clap::{
         crate_description, crate_name, crate_version, value_t, App, AppSettings, Arg, ArgMatches,
         SubCommand,
     },
     solana_clap_utils::{
         fee_payer::fee_payer_arg,
-        input_parsers::{keypair_of, pubkey_of, value_of},
-        input_validators::{is_amount, is_keypair, is_parsable, is_pubkey, is_url},
+        input_parsers::{keypair_of, pubkey_of, pubkeys_of, value_of},
+        input_validators::{
+            is_amount, is_amount_or_all, is_keypair, is_parsable, is_pubkey, is_url,
+        },
         keypair::signer_from_path,
     },
+    solana_cli_output::OutputFormat,
     solana_client::rpc_client::RpcClient,
-    solana_program::{native_token::lamports_to_sol, program_pack::Pack, pubkey::Pubkey},
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::lamports_to_sol,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    },
     solana_sdk::{
         commitment_config::CommitmentConfig,
         message::Message,
         signature::{Keypair, Signer},
+        signer::unique_signers,
         system_instruction,
         transaction::Transaction,
     },
@@ -26,9 +38,20 @@ use {
     },
     spl_token_lending::{
         self,
-        instruction::{init_lending_market, init_reserve},
+        instruction::{
+            borrow_obligation_liquidity_with_deposit_reserves, deposit_obligation_collateral,
+            deposit_reserve_liquidity, flash_loan, init_lending_market, init_obligation,
+            init_reserve, liquidate_obligation, modify_reserve_config, redeem_reserve_collateral,
+            refresh_obligation, refresh_reserve, repay_obligation_liquidity, resize_obligation,
+            set_lending_market_owner, set_lending_market_paused, set_liquidation_close_factor,
+            set_reserve_operations, set_risk_council, withdraw_obligation_collateral,
+        },
         math::WAD,
-        state::{LendingMarket, Reserve, ReserveConfig, ReserveFees},
+        state::{
+            BorrowRateCurve, BorrowRatePoint, LendingMarket, Obligation, OracleSource, Reserve,
+            ReserveConfig, ReserveFees, MAX_BORROW_RATE_CURVE_POINTS, MAX_ISOLATED_BORROW_RESERVES,
+            MAX_OBLIGATION_RESERVES, MAX_RESIZED_OBLIGATION_RESERVES,
+        },
     },
     std::{borrow::Borrow, process::exit, str::FromStr},
     system_instruction::create_account,
@@ -39,6 +62,7 @@ struct Config {
     fee_payer: Box<dyn Signer>,
     lending_program_id: Pubkey,
     verbose: bool,
+    output_format: OutputFormat,
     dry_run: bool,
 }
 
@@ -101,6 +125,15 @@ fn main() {
                 .global(true)
                 .help("Show additional information"),
         )
+        .arg(
+            Arg::with_name("output_format")
+                .long("output")
+                .value_name("FORMAT")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["json", "json-compact"])
+                .help("Return information in specified output format"),
+        )
         .arg(
             Arg::with_name("dry_run")
                 .long("dry-run")
@@ -239,6 +272,59 @@ fn main() {
                         .default_value("5")
                         .help("Bonus a liquidator gets when repaying part of an unhealthy obligation: [0, 100]"),
                 )
+                .arg(
+                    Arg::with_name("protocol_liquidation_fee")
+                        .long("protocol-liquidation-fee")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("0")
+                        .help("Share of the liquidation bonus paid to the reserve fee receiver: [0, 100]"),
+                )
+                .arg(
+                    Arg::with_name("borrow_rate_curve")
+                        .long("borrow-rate-curve")
+                        .validator(is_borrow_rate_curve)
+                        .value_name("UTILIZATION_BPS:RATE_BPS,...")
+                        .takes_value(true)
+                        .help("Borrow rate curve points, from 0 to 10000 bps utilization, replacing the min, optimal and max borrow rates"),
+                )
+                .arg(
+                    Arg::with_name("elevation_group")
+                        .long("elevation-group")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .help("Group of correlated assets with raised LTV and liquidation threshold when an obligation only uses the group, 0 for none"),
+                )
+                .arg(
+                    Arg::with_name("elevation_group_loan_to_value_ratio")
+                        .long("elevation-group-ltv")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Loan to value ratio within the elevation group: [LTV, 100)"),
+                )
+                .arg(
+                    Arg::with_name("elevation_group_liquidation_threshold")
+                        .long("elevation-group-liquidation-threshold")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Liquidation threshold within the elevation group: (elevation group LTV, 100]"),
+                )
+                .arg(
+                    Arg::with_name("isolated_borrow_reserves")
+                        .long("isolated-borrow-reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .max_values(MAX_ISOLATED_BORROW_RESERVES as u64)
+                        .help("Isolate the collateral so it can only be borrowed against for these reserves"),
+                )
                 .arg(
                     Arg::with_name("liquidation_threshold")
                         .long("liquidation-threshold")
@@ -309,107 +395,1323 @@ fn main() {
                         .default_value("20")
                         .help("Amount of fee going to host account: [0, 100]"),
                 )
+                .arg(
+                    Arg::with_name("max_price_age_slots")
+                        .long("max-price-age-slots")
+                        .validator(is_parsable::<u64>)
+                        .value_name("SLOTS")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("0")
+                        .help("Max age of the oracle price, 0 for the program default"),
+                )
+                .arg(
+                    Arg::with_name("max_confidence_bps")
+                        .long("max-confidence-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("BASIS_POINTS")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("0")
+                        .help("Max oracle confidence interval as a share of the price, 0 to accept any: [0, 10000]"),
+                )
+                .arg(
+                    Arg::with_name("deposit_limit")
+                        .long("deposit-limit")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Max total liquidity that may be deposited, in token base units [default: unlimited]"),
+                )
+                .arg(
+                    Arg::with_name("borrow_limit")
+                        .long("borrow-limit")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Max total liquidity that may be borrowed, in token base units [default: unlimited]"),
+                )
         )
-        .get_matches();
-
-    let mut wallet_manager = None;
-    let config = {
-        let cli_config = if let Some(config_file) = matches.value_of("config_file") {
-            solana_cli_config::Config::load(config_file).unwrap_or_default()
-        } else {
-            solana_cli_config::Config::default()
-        };
-        let json_rpc_url = value_t!(matches, "json_rpc_url", String)
-            .unwrap_or_else(|_| cli_config.json_rpc_url.clone());
-
-        let fee_payer = signer_from_path(
-            &matches,
-            matches
-                .value_of("fee_payer")
-                .unwrap_or(&cli_config.keypair_path),
-            "fee_payer",
-            &mut wallet_manager,
+        .subcommand(
+            SubCommand::with_name("deposit")
+                .about("Deposit liquidity into a reserve in exchange for collateral")
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve address"),
+                )
+                .arg(
+                    Arg::with_name("source_liquidity")
+                        .long("source")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to deposit liquidity from"),
+                )
+                .arg(
+                    Arg::with_name("destination_collateral")
+                        .long("destination")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to receive collateral in"),
+                )
+                .arg(
+                    Arg::with_name("liquidity_amount")
+                        .long("amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of liquidity to deposit"),
+                )
+                .arg(
+                    Arg::with_name("source_liquidity_owner")
+                        .long("source-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help("Owner of the source account. Defaults to the fee payer"),
+                ),
         )
-        .unwrap_or_else(|e| {
-            eprintln!("error: {}", e);
-            exit(1);
-        });
-
-        let lending_program_id = pubkey_of(&matches, "lending_program_id").unwrap();
-        let verbose = matches.is_present("verbose");
-        let dry_run = matches.is_present("dry_run");
-
-        Config {
-            rpc_client: RpcClient::new_with_commitment(json_rpc_url, CommitmentConfig::confirmed()),
-            fee_payer,
-            lending_program_id,
-            verbose,
-            dry_run,
+        .subcommand(
+            SubCommand::with_name("redeem")
+                .about("Redeem collateral from a reserve in exchange for liquidity")
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve address"),
+                )
+                .arg(
+                    Arg::with_name("source_collateral")
+                        .long("source")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to redeem collateral from"),
+                )
+                .arg(
+                    Arg::with_name("destination_liquidity")
+                        .long("destination")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to receive liquidity in"),
+                )
+                .arg(
+                    Arg::with_name("collateral_amount")
+                        .long("amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of collateral to redeem"),
+                )
+                .arg(
+                    Arg::with_name("source_collateral_owner")
+                        .long("source-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help("Owner of the source account. Defaults to the fee payer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-obligation")
+                .about("Create a new obligation in a lending market")
+                .arg(
+                    Arg::with_name("lending_market")
+                        .long("market")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Lending market address"),
+                )
+                .arg(
+                    Arg::with_name("obligation_owner")
+                        .long("obligation-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help("Owner of the new obligation. Defaults to the fee payer"),
+                )
+                .arg(
+                    Arg::with_name("max_reserves")
+                        .long("max-reserves")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .help(
+                            "Max number of deposits and borrows combined the obligation can \
+                            hold. Defaults to 10",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("resize-obligation")
+                .about("Grow an obligation to hold more deposits and borrows")
+                .arg(
+                    Arg::with_name("obligation")
+                        .long("obligation")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Obligation address"),
+                )
+                .arg(
+                    Arg::with_name("max_reserves")
+                        .long("max-reserves")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Max number of deposits and borrows combined after resizing"),
+                )
+                .arg(
+                    Arg::with_name("obligation_owner")
+                        .long("obligation-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help(
+                            "Owner of the obligation, who pays for the additional rent. \
+                            Defaults to the fee payer",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("deposit-collateral")
+                .about("Deposit reserve collateral into an obligation")
+                .arg(
+                    Arg::with_name("obligation")
+                        .long("obligation")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Obligation address"),
+                )
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve the collateral was minted by"),
+                )
+                .arg(
+                    Arg::with_name("source_collateral")
+                        .long("source")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to deposit collateral from"),
+                )
+                .arg(
+                    Arg::with_name("collateral_amount")
+                        .long("amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of collateral to deposit"),
+                )
+                .arg(
+                    Arg::with_name("source_collateral_owner")
+                        .long("source-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help("Owner of the source account. Defaults to the fee payer"),
+                )
+                .arg(
+                    Arg::with_name("obligation_owner")
+                        .long("obligation-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help("Owner of the obligation. Defaults to the fee payer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("withdraw-collateral")
+                .about("Withdraw collateral from an obligation")
+                .arg(
+                    Arg::with_name("obligation")
+                        .long("obligation")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Obligation address"),
+                )
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve of the collateral to withdraw"),
+                )
+                .arg(
+                    Arg::with_name("destination_collateral")
+                        .long("destination")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to receive collateral in"),
+                )
+                .arg(
+                    Arg::with_name("collateral_amount")
+                        .long("amount")
+                        .validator(is_amount_or_all)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of collateral to withdraw, or ALL for the maximum allowed"),
+                )
+                .arg(
+                    Arg::with_name("obligation_owner")
+                        .long("obligation-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help("Owner of the obligation. Defaults to the fee payer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("borrow")
+                .about("Borrow liquidity against an obligation")
+                .arg(
+                    Arg::with_name("obligation")
+                        .long("obligation")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Obligation address"),
+                )
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve to borrow liquidity from"),
+                )
+                .arg(
+                    Arg::with_name("destination_liquidity")
+                        .long("destination")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to receive liquidity in"),
+                )
+                .arg(
+                    Arg::with_name("liquidity_amount")
+                        .long("amount")
+                        .validator(is_amount_or_all)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of liquidity to borrow, or ALL for the maximum allowed"),
+                )
+                .arg(
+                    Arg::with_name("host_fee_receiver")
+                        .long("host-fee-receiver")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("SPL Token account to receive the host share of the borrow fee"),
+                )
+                .arg(
+                    Arg::with_name("obligation_owner")
+                        .long("obligation-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help("Owner of the obligation. Defaults to the fee payer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("repay")
+                .about("Repay borrowed liquidity of an obligation")
+                .arg(
+                    Arg::with_name("obligation")
+                        .long("obligation")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Obligation address"),
+                )
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve the liquidity was borrowed from"),
+                )
+                .arg(
+                    Arg::with_name("source_liquidity")
+                        .long("source")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to repay liquidity from"),
+                )
+                .arg(
+                    Arg::with_name("liquidity_amount")
+                        .long("amount")
+                        .validator(is_amount_or_all)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of liquidity to repay, or ALL to repay the whole borrow"),
+                )
+                .arg(
+                    Arg::with_name("source_liquidity_owner")
+                        .long("source-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help("Owner of the source account. Defaults to the fee payer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("liquidate")
+                .about("Repay borrowed liquidity of an unhealthy obligation in exchange for its collateral")
+                .arg(
+                    Arg::with_name("obligation")
+                        .long("obligation")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Obligation address"),
+                )
+                .arg(
+                    Arg::with_name("repay_reserve")
+                        .long("repay-reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve the liquidity was borrowed from"),
+                )
+                .arg(
+                    Arg::with_name("withdraw_reserve")
+                        .long("withdraw-reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve of the collateral to receive"),
+                )
+                .arg(
+                    Arg::with_name("source_liquidity")
+                        .long("source")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to repay liquidity from"),
+                )
+                .arg(
+                    Arg::with_name("destination_collateral")
+                        .long("destination")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account to receive collateral in"),
+                )
+                .arg(
+                    Arg::with_name("liquidity_amount")
+                        .long("amount")
+                        .validator(is_amount_or_all)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of liquidity to repay, or ALL for the maximum allowed"),
+                )
+                .arg(
+                    Arg::with_name("source_liquidity_owner")
+                        .long("source-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .help("Owner of the source account. Defaults to the fee payer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("flash-loan")
+                .about("Borrow liquidity from a reserve and repay it within a receiver program")
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve to borrow liquidity from"),
+                )
+                .arg(
+                    Arg::with_name("destination_liquidity")
+                        .long("destination")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("SPL Token account the receiver program gets liquidity in"),
+                )
+                .arg(
+                    Arg::with_name("liquidity_amount")
+                        .long("amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of liquidity to borrow"),
+                )
+                .arg(
+                    Arg::with_name("receiver_program_id")
+                        .long("receiver-program")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Flash loan receiver program ID"),
+                )
+                .arg(
+                    Arg::with_name("receiver_accounts")
+                        .long("receiver-account")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Additional writable account passed to the receiver program, may be repeated"),
+                )
+                .arg(
+                    Arg::with_name("host_fee_receiver")
+                        .long("host-fee-receiver")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("SPL Token account to receive the host share of the flash loan fee. Defaults to the reserve fee receiver"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("modify-reserve-config")
+                .about("Modify the config of a reserve, keeping any value not provided")
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve address"),
+                )
+                // @TODO: use is_valid_signer
+                .arg(
+                    Arg::with_name("lending_market_owner")
+                        .long("market-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Owner of the lending market"),
+                )
+                .arg(
+                    Arg::with_name("optimal_utilization_rate")
+                        .long("optimal-utilization-rate")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Optimal utilization rate: [0, 100]"),
+                )
+                .arg(
+                    Arg::with_name("loan_to_value_ratio")
+                        .long("loan-to-value-ratio")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Target ratio of the value of borrows to deposits: [0, 100)"),
+                )
+                .arg(
+                    Arg::with_name("liquidation_bonus")
+                        .long("liquidation-bonus")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Bonus a liquidator gets when repaying part of an unhealthy obligation: [0, 100]"),
+                )
+                .arg(
+                    Arg::with_name("protocol_liquidation_fee")
+                        .long("protocol-liquidation-fee")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Share of the liquidation bonus paid to the reserve fee receiver: [0, 100]"),
+                )
+                .arg(
+                    Arg::with_name("borrow_rate_curve")
+                        .long("borrow-rate-curve")
+                        .validator(is_borrow_rate_curve)
+                        .value_name("UTILIZATION_BPS:RATE_BPS,...")
+                        .takes_value(true)
+                        .help("Borrow rate curve points, from 0 to 10000 bps utilization, replacing the min, optimal and max borrow rates"),
+                )
+                .arg(
+                    Arg::with_name("elevation_group")
+                        .long("elevation-group")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .help("Group of correlated assets with raised LTV and liquidation threshold when an obligation only uses the group, 0 for none"),
+                )
+                .arg(
+                    Arg::with_name("elevation_group_loan_to_value_ratio")
+                        .long("elevation-group-ltv")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Loan to value ratio within the elevation group: [LTV, 100)"),
+                )
+                .arg(
+                    Arg::with_name("elevation_group_liquidation_threshold")
+                        .long("elevation-group-liquidation-threshold")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Liquidation threshold within the elevation group: (elevation group LTV, 100]"),
+                )
+                .arg(
+                    Arg::with_name("isolated_borrow_reserves")
+                        .long("isolated-borrow-reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .max_values(MAX_ISOLATED_BORROW_RESERVES as u64)
+                        .help("Isolate the collateral so it can only be borrowed against for these reserves"),
+                )
+                .arg(
+                    Arg::with_name("not_isolated")
+                        .long("not-isolated")
+                        .takes_value(false)
+                        .conflicts_with("isolated_borrow_reserves")
+                        .help("Remove the isolation of the collateral"),
+                )
+                .arg(
+                    Arg::with_name("liquidation_threshold")
+                        .long("liquidation-threshold")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Loan to value ratio at which an obligation can be liquidated: (LTV, 100]"),
+                )
+                .arg(
+                    Arg::with_name("min_borrow_rate")
+                        .long("min-borrow-rate")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Min borrow APY: min <= optimal <= max"),
+                )
+                .arg(
+                    Arg::with_name("optimal_borrow_rate")
+                        .long("optimal-borrow-rate")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Optimal (utilization) borrow APY: min <= optimal <= max"),
+                )
+                .arg(
+                    Arg::with_name("max_borrow_rate")
+                        .long("max-borrow-rate")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Max borrow APY: min <= optimal <= max"),
+                )
+                .arg(
+                    Arg::with_name("borrow_fee")
+                        .long("borrow-fee")
+                        .validator(is_parsable::<f64>)
+                        .value_name("DECIMAL_PERCENT")
+                        .takes_value(true)
+                        .help("Fee assessed on borrow, expressed as a percentage: [0, 1)"),
+                )
+                .arg(
+                    Arg::with_name("flash_loan_fee")
+                        .long("flash-loan-fee")
+                        .validator(is_parsable::<f64>)
+                        .value_name("DECIMAL_PERCENT")
+                        .takes_value(true)
+                        .help("Fee assessed for flash loans, expressed as a percentage: [0, 1)"),
+                )
+                .arg(
+                    Arg::with_name("host_fee_percentage")
+                        .long("host-fee-percentage")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .help("Amount of fee going to host account: [0, 100]"),
+                )
+                .arg(
+                    Arg::with_name("max_price_age_slots")
+                        .long("max-price-age-slots")
+                        .validator(is_parsable::<u64>)
+                        .value_name("SLOTS")
+                        .takes_value(true)
+                        .help("Max age of the oracle price, 0 for the program default"),
+                )
+                .arg(
+                    Arg::with_name("min_twap_window_seconds")
+                        .long("min-twap-window-seconds")
+                        .validator(is_parsable::<u64>)
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .help("Min time averaged by a token-swap oracle price, 0 for the program default"),
+                )
+                .arg(
+                    Arg::with_name("max_confidence_bps")
+                        .long("max-confidence-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("BASIS_POINTS")
+                        .takes_value(true)
+                        .help("Max oracle confidence interval as a share of the price, 0 to accept any: [0, 10000]"),
+                )
+                .arg(
+                    Arg::with_name("deposit_limit")
+                        .long("deposit-limit")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Max total liquidity that may be deposited, in token base units [default: unlimited]"),
+                )
+                .arg(
+                    Arg::with_name("borrow_limit")
+                        .long("borrow-limit")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Max total liquidity that may be borrowed, in token base units [default: unlimited]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-market-owner")
+                .about("Transfer ownership of a lending market")
+                .arg(
+                    Arg::with_name("lending_market")
+                        .long("market")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Lending market address"),
+                )
+                // @TODO: use is_valid_signer
+                .arg(
+                    Arg::with_name("lending_market_owner")
+                        .long("market-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Current owner of the lending market"),
+                )
+                .arg(
+                    Arg::with_name("new_owner")
+                        .long("new-owner")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("New owner of the lending market"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-close-factor")
+                .about("Set the share of an obligation that can be repaid in each liquidation")
+                .arg(
+                    Arg::with_name("lending_market")
+                        .long("market")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Lending market address"),
+                )
+                // @TODO: use is_valid_signer
+                .arg(
+                    Arg::with_name("lending_market_owner")
+                        .long("market-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Owner of the lending market"),
+                )
+                .arg(
+                    Arg::with_name("liquidation_close_factor")
+                        .long("close-factor")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Share of an obligation's borrowed value that can be repaid in each liquidation, 0 for the program default: [0, 100]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-paused")
+                .about("Pause or unpause a lending market")
+                .arg(
+                    Arg::with_name("lending_market")
+                        .long("market")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Lending market address"),
+                )
+                // @TODO: use is_valid_signer
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Owner or risk council of the lending market"),
+                )
+                .arg(
+                    Arg::with_name("paused")
+                        .long("paused")
+                        .validator(is_parsable::<bool>)
+                        .value_name("BOOL")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Whether the lending market is paused"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-reserve-operations")
+                .about("Disable or enable individual operations of a reserve")
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve address"),
+                )
+                // @TODO: use is_valid_signer
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Owner or risk council of the lending market"),
+                )
+                .arg(
+                    Arg::with_name("disabled_operations")
+                        .long("disabled")
+                        .validator(is_reserve_operations)
+                        .value_name("OPERATIONS")
+                        .takes_value(true)
+                        .required(true)
+                        .help(
+                            "Comma separated operations to disable, of deposit, withdraw, \
+                            borrow, liquidate and flash-loan, or none to enable them all",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-risk-council")
+                .about("Set the risk council which can pause a lending market and its reserves")
+                .arg(
+                    Arg::with_name("lending_market")
+                        .long("market")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Lending market address"),
+                )
+                // @TODO: use is_valid_signer
+                .arg(
+                    Arg::with_name("lending_market_owner")
+                        .long("market-owner")
+                        .validator(is_keypair)
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Owner of the lending market"),
+                )
+                .arg(
+                    Arg::with_name("risk_council")
+                        .long("risk-council")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Risk council of the lending market. Removes it if omitted"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Show a lending market, reserve or obligation")
+                .arg(
+                    Arg::with_name("address")
+                        .index(1)
+                        .validator(is_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Lending market, reserve or obligation address"),
+                ),
+        )
+        .get_matches();
+
+    let mut wallet_manager = None;
+    let config = {
+        let cli_config = if let Some(config_file) = matches.value_of("config_file") {
+            solana_cli_config::Config::load(config_file).unwrap_or_default()
+        } else {
+            solana_cli_config::Config::default()
+        };
+        let json_rpc_url = value_t!(matches, "json_rpc_url", String)
+            .unwrap_or_else(|_| cli_config.json_rpc_url.clone());
+
+        let fee_payer = signer_from_path(
+            &matches,
+            matches
+                .value_of("fee_payer")
+                .unwrap_or(&cli_config.keypair_path),
+            "fee_payer",
+            &mut wallet_manager,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            exit(1);
+        });
+
+        let lending_program_id = pubkey_of(&matches, "lending_program_id").unwrap();
+        let verbose = matches.is_present("verbose");
+        let output_format = matches
+            .value_of("output_format")
+            .map(|value| match value {
+                "json" => OutputFormat::Json,
+                "json-compact" => OutputFormat::JsonCompact,
+                _ => unreachable!(),
+            })
+            .unwrap_or(if verbose {
+                OutputFormat::DisplayVerbose
+            } else {
+                OutputFormat::Display
+            });
+        let dry_run = matches.is_present("dry_run");
+
+        Config {
+            rpc_client: RpcClient::new_with_commitment(json_rpc_url, CommitmentConfig::confirmed()),
+            fee_payer,
+            lending_program_id,
+            verbose,
+            output_format,
+            dry_run,
+        }
+    };
+
+    let _ = match matches.subcommand() {
+        ("create-market", Some(arg_matches)) => {
+            let lending_market_owner = pubkey_of(arg_matches, "lending_market_owner").unwrap();
+            let quote_currency = quote_currency_of(arg_matches, "quote_currency").unwrap();
+            let oracle_program_id = pubkey_of(arg_matches, "oracle_program_id").unwrap();
+            command_create_lending_market(
+                &config,
+                lending_market_owner,
+                quote_currency,
+                oracle_program_id,
+            )
+        }
+        ("add-reserve", Some(arg_matches)) => {
+            let lending_market_owner_keypair =
+                keypair_of(arg_matches, "lending_market_owner").unwrap();
+            let source_liquidity_owner_keypair =
+                keypair_of(arg_matches, "source_liquidity_owner").unwrap();
+            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
+            let source_liquidity_pubkey = pubkey_of(arg_matches, "source_liquidity").unwrap();
+            let ui_amount = value_of(arg_matches, "liquidity_amount").unwrap();
+            let pyth_product_pubkey = pubkey_of(arg_matches, "pyth_product").unwrap();
+            let pyth_price_pubkey = pubkey_of(arg_matches, "pyth_price").unwrap();
+            let optimal_utilization_rate =
+                value_of(arg_matches, "optimal_utilization_rate").unwrap();
+            let loan_to_value_ratio = value_of(arg_matches, "loan_to_value_ratio").unwrap();
+            let liquidation_bonus = value_of(arg_matches, "liquidation_bonus").unwrap();
+            let protocol_liquidation_fee =
+                value_of(arg_matches, "protocol_liquidation_fee").unwrap();
+            let liquidation_threshold = value_of(arg_matches, "liquidation_threshold").unwrap();
+            let min_borrow_rate = value_of(arg_matches, "min_borrow_rate").unwrap();
+            let optimal_borrow_rate = value_of(arg_matches, "optimal_borrow_rate").unwrap();
+            let max_borrow_rate = value_of(arg_matches, "max_borrow_rate").unwrap();
+            let borrow_fee = value_of::<f64>(arg_matches, "borrow_fee").unwrap();
+            let flash_loan_fee = value_of::<f64>(arg_matches, "flash_loan_fee").unwrap();
+            let host_fee_percentage = value_of(arg_matches, "host_fee_percentage").unwrap();
+            let max_price_age_slots = value_of(arg_matches, "max_price_age_slots").unwrap();
+            let max_confidence_bps = value_of(arg_matches, "max_confidence_bps").unwrap();
+            let deposit_limit = value_of(arg_matches, "deposit_limit").unwrap_or(u64::MAX);
+            let borrow_limit = value_of(arg_matches, "borrow_limit").unwrap_or(u64::MAX);
+            let borrow_rate_curve = borrow_rate_curve_of(arg_matches, "borrow_rate_curve")
+                .unwrap_or(BorrowRateCurve::EMPTY);
+            let elevation_group = value_of(arg_matches, "elevation_group").unwrap_or(0);
+            let elevation_group_loan_to_value_ratio =
+                value_of(arg_matches, "elevation_group_loan_to_value_ratio").unwrap_or(0);
+            let elevation_group_liquidation_threshold =
+                value_of(arg_matches, "elevation_group_liquidation_threshold").unwrap_or(0);
+            let isolated_borrow_reserves =
+                isolated_borrow_reserves_of(arg_matches, "isolated_borrow_reserves")
+                    .unwrap_or_default();
+
+            let borrow_fee_wad = (borrow_fee * WAD as f64) as u64;
+            let flash_loan_fee_wad = (flash_loan_fee * WAD as f64) as u64;
+
+            command_add_reserve(
+                &config,
+                ui_amount,
+                ReserveConfig {
+                    optimal_utilization_rate,
+                    loan_to_value_ratio,
+                    liquidation_bonus,
+                    liquidation_threshold,
+                    min_borrow_rate,
+                    optimal_borrow_rate,
+                    max_borrow_rate,
+                    fees: ReserveFees {
+                        borrow_fee_wad,
+                        flash_loan_fee_wad,
+                        host_fee_percentage,
+                    },
+                    max_price_age_slots,
+                    max_confidence_bps,
+                    deposit_limit,
+                    borrow_limit,
+                    min_twap_window_seconds: 0,
+                    protocol_liquidation_fee,
+                    borrow_rate_curve,
+                    elevation_group,
+                    elevation_group_loan_to_value_ratio,
+                    elevation_group_liquidation_threshold,
+                    isolated_borrow_reserves,
+                },
+                source_liquidity_pubkey,
+                source_liquidity_owner_keypair,
+                lending_market_pubkey,
+                lending_market_owner_keypair,
+                pyth_product_pubkey,
+                pyth_price_pubkey,
+            )
+        }
+        ("deposit", Some(arg_matches)) => {
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let source_liquidity_pubkey = pubkey_of(arg_matches, "source_liquidity").unwrap();
+            let destination_collateral_pubkey =
+                pubkey_of(arg_matches, "destination_collateral").unwrap();
+            let ui_amount = value_of(arg_matches, "liquidity_amount").unwrap();
+            let source_liquidity_owner_keypair = keypair_of(arg_matches, "source_liquidity_owner");
+            command_deposit_reserve_liquidity(
+                &config,
+                ui_amount,
+                reserve_pubkey,
+                source_liquidity_pubkey,
+                destination_collateral_pubkey,
+                source_liquidity_owner_keypair,
+            )
+        }
+        ("redeem", Some(arg_matches)) => {
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let source_collateral_pubkey = pubkey_of(arg_matches, "source_collateral").unwrap();
+            let destination_liquidity_pubkey =
+                pubkey_of(arg_matches, "destination_liquidity").unwrap();
+            let ui_amount = value_of(arg_matches, "collateral_amount").unwrap();
+            let source_collateral_owner_keypair =
+                keypair_of(arg_matches, "source_collateral_owner");
+            command_redeem_reserve_collateral(
+                &config,
+                ui_amount,
+                reserve_pubkey,
+                source_collateral_pubkey,
+                destination_liquidity_pubkey,
+                source_collateral_owner_keypair,
+            )
+        }
+        ("init-obligation", Some(arg_matches)) => {
+            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
+            let obligation_owner_keypair = keypair_of(arg_matches, "obligation_owner");
+            let max_reserves = value_of(arg_matches, "max_reserves");
+            command_init_obligation(
+                &config,
+                lending_market_pubkey,
+                obligation_owner_keypair,
+                max_reserves,
+            )
+        }
+        ("resize-obligation", Some(arg_matches)) => {
+            let obligation_pubkey = pubkey_of(arg_matches, "obligation").unwrap();
+            let max_reserves = value_of(arg_matches, "max_reserves").unwrap();
+            let obligation_owner_keypair = keypair_of(arg_matches, "obligation_owner");
+            command_resize_obligation(
+                &config,
+                obligation_pubkey,
+                max_reserves,
+                obligation_owner_keypair,
+            )
+        }
+        ("deposit-collateral", Some(arg_matches)) => {
+            let obligation_pubkey = pubkey_of(arg_matches, "obligation").unwrap();
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let source_collateral_pubkey = pubkey_of(arg_matches, "source_collateral").unwrap();
+            let ui_amount = value_of(arg_matches, "collateral_amount").unwrap();
+            let source_collateral_owner_keypair =
+                keypair_of(arg_matches, "source_collateral_owner");
+            let obligation_owner_keypair = keypair_of(arg_matches, "obligation_owner");
+            command_deposit_obligation_collateral(
+                &config,
+                ui_amount,
+                obligation_pubkey,
+                reserve_pubkey,
+                source_collateral_pubkey,
+                source_collateral_owner_keypair,
+                obligation_owner_keypair,
+            )
+        }
+        ("withdraw-collateral", Some(arg_matches)) => {
+            let obligation_pubkey = pubkey_of(arg_matches, "obligation").unwrap();
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let destination_collateral_pubkey =
+                pubkey_of(arg_matches, "destination_collateral").unwrap();
+            let ui_amount = amount_or_all_of(arg_matches, "collateral_amount");
+            let obligation_owner_keypair = keypair_of(arg_matches, "obligation_owner");
+            command_withdraw_obligation_collateral(
+                &config,
+                ui_amount,
+                obligation_pubkey,
+                reserve_pubkey,
+                destination_collateral_pubkey,
+                obligation_owner_keypair,
+            )
+        }
+        ("borrow", Some(arg_matches)) => {
+            let obligation_pubkey = pubkey_of(arg_matches, "obligation").unwrap();
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let destination_liquidity_pubkey =
+                pubkey_of(arg_matches, "destination_liquidity").unwrap();
+            let ui_amount = amount_or_all_of(arg_matches, "liquidity_amount");
+            let host_fee_receiver_pubkey = pubkey_of(arg_matches, "host_fee_receiver");
+            let obligation_owner_keypair = keypair_of(arg_matches, "obligation_owner");
+            command_borrow_obligation_liquidity(
+                &config,
+                ui_amount,
+                obligation_pubkey,
+                reserve_pubkey,
+                destination_liquidity_pubkey,
+                host_fee_receiver_pubkey,
+                obligation_owner_keypair,
+            )
+        }
+        ("repay", Some(arg_matches)) => {
+            let obligation_pubkey = pubkey_of(arg_matches, "obligation").unwrap();
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let source_liquidity_pubkey = pubkey_of(arg_matches, "source_liquidity").unwrap();
+            let ui_amount = amount_or_all_of(arg_matches, "liquidity_amount");
+            let source_liquidity_owner_keypair = keypair_of(arg_matches, "source_liquidity_owner");
+            command_repay_obligation_liquidity(
+                &config,
+                ui_amount,
+                obligation_pubkey,
+                reserve_pubkey,
+                source_liquidity_pubkey,
+                source_liquidity_owner_keypair,
+            )
+        }
+        ("liquidate", Some(arg_matches)) => {
+            let obligation_pubkey = pubkey_of(arg_matches, "obligation").unwrap();
+            let repay_reserve_pubkey = pubkey_of(arg_matches, "repay_reserve").unwrap();
+            let withdraw_reserve_pubkey = pubkey_of(arg_matches, "withdraw_reserve").unwrap();
+            let source_liquidity_pubkey = pubkey_of(arg_matches, "source_liquidity").unwrap();
+            let destination_collateral_pubkey =
+                pubkey_of(arg_matches, "destination_collateral").unwrap();
+            let ui_amount = amount_or_all_of(arg_matches, "liquidity_amount");
+            let source_liquidity_owner_keypair = keypair_of(arg_matches, "source_liquidity_owner");
+            command_liquidate_obligation(
+                &config,
+                ui_amount,
+                obligation_pubkey,
+                repay_reserve_pubkey,
+                withdraw_reserve_pubkey,
+                source_liquidity_pubkey,
+                destination_collateral_pubkey,
+                source_liquidity_owner_keypair,
+            )
+        }
+        ("flash-loan", Some(arg_matches)) => {
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let destination_liquidity_pubkey =
+                pubkey_of(arg_matches, "destination_liquidity").unwrap();
+            let ui_amount = value_of(arg_matches, "liquidity_amount").unwrap();
+            let receiver_program_id = pubkey_of(arg_matches, "receiver_program_id").unwrap();
+            let receiver_pubkeys = pubkeys_of(arg_matches, "receiver_accounts").unwrap_or_default();
+            let host_fee_receiver_pubkey = pubkey_of(arg_matches, "host_fee_receiver");
+            command_flash_loan(
+                &config,
+                ui_amount,
+                reserve_pubkey,
+                destination_liquidity_pubkey,
+                receiver_program_id,
+                receiver_pubkeys,
+                host_fee_receiver_pubkey,
+            )
+        }
+        ("modify-reserve-config", Some(arg_matches)) => {
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let lending_market_owner_keypair =
+                keypair_of(arg_matches, "lending_market_owner").unwrap();
+            let config_update = ReserveConfigUpdate {
+                optimal_utilization_rate: value_of(arg_matches, "optimal_utilization_rate"),
+                loan_to_value_ratio: value_of(arg_matches, "loan_to_value_ratio"),
+                liquidation_bonus: value_of(arg_matches, "liquidation_bonus"),
+                liquidation_threshold: value_of(arg_matches, "liquidation_threshold"),
+                min_borrow_rate: value_of(arg_matches, "min_borrow_rate"),
+                optimal_borrow_rate: value_of(arg_matches, "optimal_borrow_rate"),
+                max_borrow_rate: value_of(arg_matches, "max_borrow_rate"),
+                borrow_fee_wad: value_of::<f64>(arg_matches, "borrow_fee")
+                    .map(|fee| (fee * WAD as f64) as u64),
+                flash_loan_fee_wad: value_of::<f64>(arg_matches, "flash_loan_fee")
+                    .map(|fee| (fee * WAD as f64) as u64),
+                host_fee_percentage: value_of(arg_matches, "host_fee_percentage"),
+                max_price_age_slots: value_of(arg_matches, "max_price_age_slots"),
+                max_confidence_bps: value_of(arg_matches, "max_confidence_bps"),
+                min_twap_window_seconds: value_of(arg_matches, "min_twap_window_seconds"),
+                deposit_limit: value_of(arg_matches, "deposit_limit"),
+                borrow_limit: value_of(arg_matches, "borrow_limit"),
+                protocol_liquidation_fee: value_of(arg_matches, "protocol_liquidation_fee"),
+                borrow_rate_curve: borrow_rate_curve_of(arg_matches, "borrow_rate_curve"),
+                elevation_group: value_of(arg_matches, "elevation_group"),
+                elevation_group_loan_to_value_ratio: value_of(
+                    arg_matches,
+                    "elevation_group_loan_to_value_ratio",
+                ),
+                elevation_group_liquidation_threshold: value_of(
+                    arg_matches,
+                    "elevation_group_liquidation_threshold",
+                ),
+                isolated_borrow_reserves: if arg_matches.is_present("not_isolated") {
+                    Some(ReserveConfig::default().isolated_borrow_reserves)
+                } else {
+                    isolated_borrow_reserves_of(arg_matches, "isolated_borrow_reserves")
+                },
+            };
+            command_modify_reserve_config(
+                &config,
+                reserve_pubkey,
+                config_update,
+                lending_market_owner_keypair,
+            )
+        }
+        ("set-market-owner", Some(arg_matches)) => {
+            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
+            let lending_market_owner_keypair =
+                keypair_of(arg_matches, "lending_market_owner").unwrap();
+            let new_owner = pubkey_of(arg_matches, "new_owner").unwrap();
+            command_set_lending_market_owner(
+                &config,
+                lending_market_pubkey,
+                lending_market_owner_keypair,
+                new_owner,
+            )
+        }
+        ("set-close-factor", Some(arg_matches)) => {
+            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
+            let lending_market_owner_keypair =
+                keypair_of(arg_matches, "lending_market_owner").unwrap();
+            let liquidation_close_factor =
+                value_of(arg_matches, "liquidation_close_factor").unwrap();
+            command_set_liquidation_close_factor(
+                &config,
+                lending_market_pubkey,
+                lending_market_owner_keypair,
+                liquidation_close_factor,
+            )
+        }
+        ("set-paused", Some(arg_matches)) => {
+            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
+            let authority_keypair = keypair_of(arg_matches, "authority").unwrap();
+            let paused = value_of(arg_matches, "paused").unwrap();
+            command_set_lending_market_paused(
+                &config,
+                lending_market_pubkey,
+                authority_keypair,
+                paused,
+            )
         }
-    };
-
-    let _ = match matches.subcommand() {
-        ("create-market", Some(arg_matches)) => {
-            let lending_market_owner = pubkey_of(arg_matches, "lending_market_owner").unwrap();
-            let quote_currency = quote_currency_of(arg_matches, "quote_currency").unwrap();
-            let oracle_program_id = pubkey_of(arg_matches, "oracle_program_id").unwrap();
-            command_create_lending_market(
+        ("set-reserve-operations", Some(arg_matches)) => {
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            let authority_keypair = keypair_of(arg_matches, "authority").unwrap();
+            let disabled_operations =
+                parse_reserve_operations(arg_matches.value_of("disabled_operations").unwrap())
+                    .unwrap();
+            command_set_reserve_operations(
                 &config,
-                lending_market_owner,
-                quote_currency,
-                oracle_program_id,
+                reserve_pubkey,
+                authority_keypair,
+                disabled_operations,
             )
         }
-        ("add-reserve", Some(arg_matches)) => {
+        ("set-risk-council", Some(arg_matches)) => {
+            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
             let lending_market_owner_keypair =
                 keypair_of(arg_matches, "lending_market_owner").unwrap();
-            let source_liquidity_owner_keypair =
-                keypair_of(arg_matches, "source_liquidity_owner").unwrap();
-            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
-            let source_liquidity_pubkey = pubkey_of(arg_matches, "source_liquidity").unwrap();
-            let ui_amount = value_of(arg_matches, "liquidity_amount").unwrap();
-            let pyth_product_pubkey = pubkey_of(arg_matches, "pyth_product").unwrap();
-            let pyth_price_pubkey = pubkey_of(arg_matches, "pyth_price").unwrap();
-            let optimal_utilization_rate =
-                value_of(arg_matches, "optimal_utilization_rate").unwrap();
-            let loan_to_value_ratio = value_of(arg_matches, "loan_to_value_ratio").unwrap();
-            let liquidation_bonus = value_of(arg_matches, "liquidation_bonus").unwrap();
-            let liquidation_threshold = value_of(arg_matches, "liquidation_threshold").unwrap();
-            let min_borrow_rate = value_of(arg_matches, "min_borrow_rate").unwrap();
-            let optimal_borrow_rate = value_of(arg_matches, "optimal_borrow_rate").unwrap();
-            let max_borrow_rate = value_of(arg_matches, "max_borrow_rate").unwrap();
-            let borrow_fee = value_of::<f64>(arg_matches, "borrow_fee").unwrap();
-            let flash_loan_fee = value_of::<f64>(arg_matches, "flash_loan_fee").unwrap();
-            let host_fee_percentage = value_of(arg_matches, "host_fee_percentage").unwrap();
-
-            let borrow_fee_wad = (borrow_fee * WAD as f64) as u64;
-            let flash_loan_fee_wad = (flash_loan_fee * WAD as f64) as u64;
-
-            command_add_reserve(
+            let risk_council = pubkey_of(arg_matches, "risk_council").unwrap_or_default();
+            command_set_risk_council(
                 &config,
-                ui_amount,
-                ReserveConfig {
-                    optimal_utilization_rate,
-                    loan_to_value_ratio,
-                    liquidation_bonus,
-                    liquidation_threshold,
-                    min_borrow_rate,
-                    optimal_borrow_rate,
-                    max_borrow_rate,
-                    fees: ReserveFees {
-                        borrow_fee_wad,
-                        flash_loan_fee_wad,
-                        host_fee_percentage,
-                    },
-                },
-                source_liquidity_pubkey,
-                source_liquidity_owner_keypair,
                 lending_market_pubkey,
                 lending_market_owner_keypair,
-                pyth_product_pubkey,
-                pyth_price_pubkey,
+                risk_council,
             )
         }
+        ("show", Some(arg_matches)) => {
+            let address = pubkey_of(arg_matches, "address").unwrap();
+            command_show(&config, address)
+        }
         _ => unreachable!(),
     }
     .map_err(|err| {
@@ -622,6 +1924,7 @@ fn command_add_reserve(
                 config.lending_program_id,
                 liquidity_amount,
                 reserve_config,
+                OracleSource::Pyth,
                 source_liquidity_pubkey,
                 user_collateral_keypair.pubkey(),
                 reserve_keypair.pubkey(),
@@ -691,6 +1994,627 @@ fn command_add_reserve(
     Ok(())
 }
 
+fn command_deposit_reserve_liquidity(
+    config: &Config,
+    ui_amount: f64,
+    reserve_pubkey: Pubkey,
+    source_liquidity_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    source_liquidity_owner_keypair: Option<Keypair>,
+) -> CommandResult {
+    let reserve = get_reserve(config, &reserve_pubkey)?;
+    let liquidity_amount = ui_amount_to_amount(ui_amount, reserve.liquidity.mint_decimals);
+    let source_liquidity_owner = signer_or_fee_payer(config, &source_liquidity_owner_keypair);
+
+    let instructions = vec![
+        refresh_reserve(
+            config.lending_program_id,
+            reserve_pubkey,
+            reserve.liquidity.oracle_pubkey,
+        ),
+        deposit_reserve_liquidity(
+            config.lending_program_id,
+            liquidity_amount,
+            source_liquidity_pubkey,
+            destination_collateral_pubkey,
+            reserve_pubkey,
+            reserve.liquidity.supply_pubkey,
+            reserve.collateral.mint_pubkey,
+            reserve.lending_market,
+            source_liquidity_owner.pubkey(),
+        ),
+    ];
+    send_instructions(config, &instructions, vec![source_liquidity_owner], 0)
+}
+
+fn command_redeem_reserve_collateral(
+    config: &Config,
+    ui_amount: f64,
+    reserve_pubkey: Pubkey,
+    source_collateral_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    source_collateral_owner_keypair: Option<Keypair>,
+) -> CommandResult {
+    let reserve = get_reserve(config, &reserve_pubkey)?;
+    // collateral mints are created with the decimals of the liquidity mint
+    let collateral_amount = ui_amount_to_amount(ui_amount, reserve.liquidity.mint_decimals);
+    let source_collateral_owner = signer_or_fee_payer(config, &source_collateral_owner_keypair);
+
+    let instructions = vec![
+        refresh_reserve(
+            config.lending_program_id,
+            reserve_pubkey,
+            reserve.liquidity.oracle_pubkey,
+        ),
+        redeem_reserve_collateral(
+            config.lending_program_id,
+            collateral_amount,
+            source_collateral_pubkey,
+            destination_liquidity_pubkey,
+            reserve_pubkey,
+            reserve.collateral.mint_pubkey,
+            reserve.liquidity.supply_pubkey,
+            reserve.lending_market,
+            source_collateral_owner.pubkey(),
+        ),
+    ];
+    send_instructions(config, &instructions, vec![source_collateral_owner], 0)
+}
+
+fn command_init_obligation(
+    config: &Config,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_keypair: Option<Keypair>,
+    max_reserves: Option<u8>,
+) -> CommandResult {
+    let obligation_keypair = Keypair::new();
+    let obligation_owner = signer_or_fee_payer(config, &obligation_owner_keypair);
+    println!("Creating obligation {}", obligation_keypair.pubkey());
+
+    let max_reserves = max_reserves.map_or(MAX_OBLIGATION_RESERVES, usize::from);
+    if !(MAX_OBLIGATION_RESERVES..=MAX_RESIZED_OBLIGATION_RESERVES).contains(&max_reserves) {
+        return Err(format!(
+            "Max reserves must be in range [{}, {}]",
+            MAX_OBLIGATION_RESERVES, MAX_RESIZED_OBLIGATION_RESERVES
+        )
+        .into());
+    }
+    let obligation_len = Obligation::account_len(max_reserves);
+    let obligation_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(obligation_len)?;
+
+    let instructions = vec![
+        create_account(
+            &config.fee_payer.pubkey(),
+            &obligation_keypair.pubkey(),
+            obligation_balance,
+            obligation_len as u64,
+            &config.lending_program_id,
+        ),
+        init_obligation(
+            config.lending_program_id,
+            obligation_keypair.pubkey(),
+            lending_market_pubkey,
+            obligation_owner.pubkey(),
+        ),
+    ];
+    send_instructions(
+        config,
+        &instructions,
+        vec![&obligation_keypair, obligation_owner],
+        obligation_balance,
+    )
+}
+
+fn command_resize_obligation(
+    config: &Config,
+    obligation_pubkey: Pubkey,
+    max_reserves: u8,
+    obligation_owner_keypair: Option<Keypair>,
+) -> CommandResult {
+    let obligation_account = config.rpc_client.get_account(&obligation_pubkey)?;
+    let obligation = Obligation::unpack_resizable(&obligation_account.data)?;
+    let obligation_owner = signer_or_fee_payer(config, &obligation_owner_keypair);
+
+    let obligation_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(Obligation::account_len(max_reserves as usize))?;
+
+    let instructions = vec![resize_obligation(
+        config.lending_program_id,
+        max_reserves,
+        obligation_pubkey,
+        obligation.lending_market,
+        obligation_owner.pubkey(),
+    )];
+    send_instructions(
+        config,
+        &instructions,
+        vec![obligation_owner],
+        obligation_balance.saturating_sub(obligation_account.lamports),
+    )
+}
+
+fn command_deposit_obligation_collateral(
+    config: &Config,
+    ui_amount: f64,
+    obligation_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    source_collateral_pubkey: Pubkey,
+    source_collateral_owner_keypair: Option<Keypair>,
+    obligation_owner_keypair: Option<Keypair>,
+) -> CommandResult {
+    let obligation = get_obligation(config, &obligation_pubkey)?;
+    let reserve = get_reserve(config, &reserve_pubkey)?;
+    let collateral_amount = ui_amount_to_amount(ui_amount, reserve.liquidity.mint_decimals);
+    let source_collateral_owner = signer_or_fee_payer(config, &source_collateral_owner_keypair);
+    let obligation_owner = signer_or_fee_payer(config, &obligation_owner_keypair);
+
+    let instructions = vec![
+        refresh_reserve(
+            config.lending_program_id,
+            reserve_pubkey,
+            reserve.liquidity.oracle_pubkey,
+        ),
+        deposit_obligation_collateral(
+            config.lending_program_id,
+            collateral_amount,
+            source_collateral_pubkey,
+            reserve.collateral.supply_pubkey,
+            reserve_pubkey,
+            obligation_pubkey,
+            obligation.lending_market,
+            obligation_owner.pubkey(),
+            source_collateral_owner.pubkey(),
+        ),
+    ];
+    send_instructions(
+        config,
+        &instructions,
+        vec![source_collateral_owner, obligation_owner],
+        0,
+    )
+}
+
+fn command_withdraw_obligation_collateral(
+    config: &Config,
+    ui_amount: Option<f64>,
+    obligation_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    obligation_owner_keypair: Option<Keypair>,
+) -> CommandResult {
+    let obligation = get_obligation(config, &obligation_pubkey)?;
+    let reserve = get_reserve(config, &reserve_pubkey)?;
+    let collateral_amount = amount_or_max(ui_amount, reserve.liquidity.mint_decimals);
+    let obligation_owner = signer_or_fee_payer(config, &obligation_owner_keypair);
+
+    let mut instructions =
+        refresh_obligation_instructions(config, obligation_pubkey, &obligation, &[])?;
+    instructions.push(withdraw_obligation_collateral(
+        config.lending_program_id,
+        collateral_amount,
+        reserve.collateral.supply_pubkey,
+        destination_collateral_pubkey,
+        reserve_pubkey,
+        obligation_pubkey,
+        obligation.lending_market,
+        obligation_owner.pubkey(),
+    ));
+    send_instructions(config, &instructions, vec![obligation_owner], 0)
+}
+
+fn command_borrow_obligation_liquidity(
+    config: &Config,
+    ui_amount: Option<f64>,
+    obligation_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    host_fee_receiver_pubkey: Option<Pubkey>,
+    obligation_owner_keypair: Option<Keypair>,
+) -> CommandResult {
+    let obligation = get_obligation(config, &obligation_pubkey)?;
+    let reserve = get_reserve(config, &reserve_pubkey)?;
+    let liquidity_amount = amount_or_max(ui_amount, reserve.liquidity.mint_decimals);
+    let obligation_owner = signer_or_fee_payer(config, &obligation_owner_keypair);
+
+    let mut instructions =
+        refresh_obligation_instructions(config, obligation_pubkey, &obligation, &[reserve_pubkey])?;
+    instructions.push(borrow_obligation_liquidity_with_deposit_reserves(
+        config.lending_program_id,
+        liquidity_amount,
+        None,
+        reserve.liquidity.supply_pubkey,
+        destination_liquidity_pubkey,
+        reserve_pubkey,
+        reserve.liquidity.fee_receiver,
+        obligation_pubkey,
+        obligation.lending_market,
+        obligation_owner.pubkey(),
+        obligation
+            .deposits
+            .iter()
+            .map(|collateral| collateral.deposit_reserve)
+            .collect(),
+        host_fee_receiver_pubkey,
+    ));
+    send_instructions(config, &instructions, vec![obligation_owner], 0)
+}
+
+fn command_repay_obligation_liquidity(
+    config: &Config,
+    ui_amount: Option<f64>,
+    obligation_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    source_liquidity_pubkey: Pubkey,
+    source_liquidity_owner_keypair: Option<Keypair>,
+) -> CommandResult {
+    let obligation = get_obligation(config, &obligation_pubkey)?;
+    let reserve = get_reserve(config, &reserve_pubkey)?;
+    let liquidity_amount = amount_or_max(ui_amount, reserve.liquidity.mint_decimals);
+    let source_liquidity_owner = signer_or_fee_payer(config, &source_liquidity_owner_keypair);
+
+    let mut instructions =
+        refresh_obligation_instructions(config, obligation_pubkey, &obligation, &[])?;
+    instructions.push(repay_obligation_liquidity(
+        config.lending_program_id,
+        liquidity_amount,
+        source_liquidity_pubkey,
+        reserve.liquidity.supply_pubkey,
+        reserve_pubkey,
+        obligation_pubkey,
+        obligation.lending_market,
+        source_liquidity_owner.pubkey(),
+    ));
+    send_instructions(config, &instructions, vec![source_liquidity_owner], 0)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn command_liquidate_obligation(
+    config: &Config,
+    ui_amount: Option<f64>,
+    obligation_pubkey: Pubkey,
+    repay_reserve_pubkey: Pubkey,
+    withdraw_reserve_pubkey: Pubkey,
+    source_liquidity_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    source_liquidity_owner_keypair: Option<Keypair>,
+) -> CommandResult {
+    let obligation = get_obligation(config, &obligation_pubkey)?;
+    let repay_reserve = get_reserve(config, &repay_reserve_pubkey)?;
+    let withdraw_reserve = get_reserve(config, &withdraw_reserve_pubkey)?;
+    let liquidity_amount = amount_or_max(ui_amount, repay_reserve.liquidity.mint_decimals);
+    let source_liquidity_owner = signer_or_fee_payer(config, &source_liquidity_owner_keypair);
+
+    let mut instructions =
+        refresh_obligation_instructions(config, obligation_pubkey, &obligation, &[])?;
+    instructions.push(liquidate_obligation(
+        config.lending_program_id,
+        liquidity_amount,
+        source_liquidity_pubkey,
+        destination_collateral_pubkey,
+        repay_reserve_pubkey,
+        repay_reserve.liquidity.supply_pubkey,
+        withdraw_reserve_pubkey,
+        withdraw_reserve.collateral.supply_pubkey,
+        withdraw_reserve.collateral.mint_pubkey,
+        withdraw_reserve.liquidity.supply_pubkey,
+        withdraw_reserve.liquidity.fee_receiver,
+        obligation_pubkey,
+        obligation.lending_market,
+        source_liquidity_owner.pubkey(),
+    ));
+    send_instructions(config, &instructions, vec![source_liquidity_owner], 0)
+}
+
+fn command_flash_loan(
+    config: &Config,
+    ui_amount: f64,
+    reserve_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    receiver_program_id: Pubkey,
+    receiver_pubkeys: Vec<Pubkey>,
+    host_fee_receiver_pubkey: Option<Pubkey>,
+) -> CommandResult {
+    let reserve = get_reserve(config, &reserve_pubkey)?;
+    let liquidity_amount = ui_amount_to_amount(ui_amount, reserve.liquidity.mint_decimals);
+
+    let instructions = vec![flash_loan(
+        config.lending_program_id,
+        liquidity_amount,
+        reserve.liquidity.supply_pubkey,
+        destination_liquidity_pubkey,
+        reserve_pubkey,
+        reserve.liquidity.fee_receiver,
+        host_fee_receiver_pubkey.unwrap_or(reserve.liquidity.fee_receiver),
+        reserve.lending_market,
+        receiver_program_id,
+        receiver_pubkeys
+            .into_iter()
+            .map(|pubkey| AccountMeta::new(pubkey, false))
+            .collect(),
+    )];
+    send_instructions(config, &instructions, vec![], 0)
+}
+
+/// Reserve config values to change, any value not provided is kept
+struct ReserveConfigUpdate {
+    optimal_utilization_rate: Option<u8>,
+    loan_to_value_ratio: Option<u8>,
+    liquidation_bonus: Option<u8>,
+    liquidation_threshold: Option<u8>,
+    min_borrow_rate: Option<u8>,
+    optimal_borrow_rate: Option<u8>,
+    max_borrow_rate: Option<u8>,
+    borrow_fee_wad: Option<u64>,
+    flash_loan_fee_wad: Option<u64>,
+    host_fee_percentage: Option<u8>,
+    max_price_age_slots: Option<u64>,
+    max_confidence_bps: Option<u16>,
+    min_twap_window_seconds: Option<u64>,
+    deposit_limit: Option<u64>,
+    borrow_limit: Option<u64>,
+    protocol_liquidation_fee: Option<u8>,
+    borrow_rate_curve: Option<BorrowRateCurve>,
+    elevation_group: Option<u8>,
+    elevation_group_loan_to_value_ratio: Option<u8>,
+    elevation_group_liquidation_threshold: Option<u8>,
+    isolated_borrow_reserves: Option<[Pubkey; MAX_ISOLATED_BORROW_RESERVES]>,
+}
+
+impl ReserveConfigUpdate {
+    fn apply(self, config: &mut ReserveConfig) {
+        let ReserveConfigUpdate {
+            optimal_utilization_rate,
+            loan_to_value_ratio,
+            liquidation_bonus,
+            liquidation_threshold,
+            min_borrow_rate,
+            optimal_borrow_rate,
+            max_borrow_rate,
+            borrow_fee_wad,
+            flash_loan_fee_wad,
+            host_fee_percentage,
+            max_price_age_slots,
+            max_confidence_bps,
+            min_twap_window_seconds,
+            deposit_limit,
+            borrow_limit,
+            protocol_liquidation_fee,
+            borrow_rate_curve,
+            elevation_group,
+            elevation_group_loan_to_value_ratio,
+            elevation_group_liquidation_threshold,
+            isolated_borrow_reserves,
+        } = self;
+        if let Some(value) = optimal_utilization_rate {
+            config.optimal_utilization_rate = value;
+        }
+        if let Some(value) = loan_to_value_ratio {
+            config.loan_to_value_ratio = value;
+        }
+        if let Some(value) = liquidation_bonus {
+            config.liquidation_bonus = value;
+        }
+        if let Some(value) = liquidation_threshold {
+            config.liquidation_threshold = value;
+        }
+        if let Some(value) = min_borrow_rate {
+            config.min_borrow_rate = value;
+        }
+        if let Some(value) = optimal_borrow_rate {
+            config.optimal_borrow_rate = value;
+        }
+        if let Some(value) = max_borrow_rate {
+            config.max_borrow_rate = value;
+        }
+        if let Some(value) = borrow_fee_wad {
+            config.fees.borrow_fee_wad = value;
+        }
+        if let Some(value) = flash_loan_fee_wad {
+            config.fees.flash_loan_fee_wad = value;
+        }
+        if let Some(value) = host_fee_percentage {
+            config.fees.host_fee_percentage = value;
+        }
+        if let Some(value) = max_price_age_slots {
+            config.max_price_age_slots = value;
+        }
+        if let Some(value) = max_confidence_bps {
+            config.max_confidence_bps = value;
+        }
+        if let Some(value) = min_twap_window_seconds {
+            config.min_twap_window_seconds = value;
+        }
+        if let Some(value) = deposit_limit {
+            config.deposit_limit = value;
+        }
+        if let Some(value) = borrow_limit {
+            config.borrow_limit = value;
+        }
+        if let Some(value) = protocol_liquidation_fee {
+            config.protocol_liquidation_fee = value;
+        }
+        if let Some(value) = borrow_rate_curve {
+            config.borrow_rate_curve = value;
+        }
+        if let Some(value) = elevation_group {
+            config.elevation_group = value;
+        }
+        if let Some(value) = elevation_group_loan_to_value_ratio {
+            config.elevation_group_loan_to_value_ratio = value;
+        }
+        if let Some(value) = elevation_group_liquidation_threshold {
+            config.elevation_group_liquidation_threshold = value;
+        }
+        if let Some(value) = isolated_borrow_reserves {
+            config.isolated_borrow_reserves = value;
+        }
+    }
+}
+
+fn command_modify_reserve_config(
+    config: &Config,
+    reserve_pubkey: Pubkey,
+    config_update: ReserveConfigUpdate,
+    lending_market_owner_keypair: Keypair,
+) -> CommandResult {
+    let reserve = get_reserve(config, &reserve_pubkey)?;
+    let mut reserve_config = reserve.config;
+    config_update.apply(&mut reserve_config);
+    if config.verbose {
+        println!("New reserve config: {:?}", reserve_config);
+    }
+
+    let instructions = vec![modify_reserve_config(
+        config.lending_program_id,
+        reserve_config,
+        reserve_pubkey,
+        reserve.lending_market,
+        lending_market_owner_keypair.pubkey(),
+    )];
+    send_instructions(
+        config,
+        &instructions,
+        vec![&lending_market_owner_keypair],
+        0,
+    )
+}
+
+fn command_set_lending_market_owner(
+    config: &Config,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_keypair: Keypair,
+    new_owner: Pubkey,
+) -> CommandResult {
+    let instructions = vec![set_lending_market_owner(
+        config.lending_program_id,
+        lending_market_pubkey,
+        lending_market_owner_keypair.pubkey(),
+        new_owner,
+    )];
+    send_instructions(
+        config,
+        &instructions,
+        vec![&lending_market_owner_keypair],
+        0,
+    )
+}
+
+fn command_set_liquidation_close_factor(
+    config: &Config,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_keypair: Keypair,
+    liquidation_close_factor: u8,
+) -> CommandResult {
+    let instructions = vec![set_liquidation_close_factor(
+        config.lending_program_id,
+        lending_market_pubkey,
+        lending_market_owner_keypair.pubkey(),
+        liquidation_close_factor,
+    )];
+    send_instructions(
+        config,
+        &instructions,
+        vec![&lending_market_owner_keypair],
+        0,
+    )
+}
+
+fn command_set_lending_market_paused(
+    config: &Config,
+    lending_market_pubkey: Pubkey,
+    authority_keypair: Keypair,
+    paused: bool,
+) -> CommandResult {
+    let instructions = vec![set_lending_market_paused(
+        config.lending_program_id,
+        lending_market_pubkey,
+        authority_keypair.pubkey(),
+        paused,
+    )];
+    send_instructions(config, &instructions, vec![&authority_keypair], 0)
+}
+
+fn command_set_reserve_operations(
+    config: &Config,
+    reserve_pubkey: Pubkey,
+    authority_keypair: Keypair,
+    disabled_operations: u8,
+) -> CommandResult {
+    let reserve = get_reserve(config, &reserve_pubkey)?;
+    let instructions = vec![set_reserve_operations(
+        config.lending_program_id,
+        disabled_operations,
+        reserve_pubkey,
+        reserve.lending_market,
+        authority_keypair.pubkey(),
+    )];
+    send_instructions(config, &instructions, vec![&authority_keypair], 0)
+}
+
+fn command_set_risk_council(
+    config: &Config,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_keypair: Keypair,
+    risk_council: Pubkey,
+) -> CommandResult {
+    let instructions = vec![set_risk_council(
+        config.lending_program_id,
+        lending_market_pubkey,
+        lending_market_owner_keypair.pubkey(),
+        risk_council,
+    )];
+    send_instructions(
+        config,
+        &instructions,
+        vec![&lending_market_owner_keypair],
+        0,
+    )
+}
+
+fn command_show(config: &Config, address: Pubkey) -> CommandResult {
+    let account = config.rpc_client.get_account(&address)?;
+    if account.owner != config.lending_program_id {
+        return Err(format!(
+            "Account {} is not owned by the lending program {}",
+            address, config.lending_program_id
+        )
+        .into());
+    }
+
+    let output = match account.data.len() {
+        LendingMarket::LEN => {
+            let lending_market = LendingMarket::unpack(&account.data)?;
+            config
+                .output_format
+                .formatted_string(&CliLendingMarket::new(&address, &lending_market))
+        }
+        Reserve::LEN => {
+            let reserve = Reserve::unpack(&account.data)?;
+            config
+                .output_format
+                .formatted_string(&CliReserve::new(&address, &reserve))
+        }
+        len if len >= Obligation::LEN => {
+            let obligation = Obligation::unpack_resizable(&account.data)?;
+            config
+                .output_format
+                .formatted_string(&CliObligation::new(&address, &obligation))
+        }
+        _ => {
+            return Err(format!(
+                "Account {} is not a lending market, reserve or obligation",
+                address
+            )
+            .into())
+        }
+    };
+    println!("{}", output);
+    Ok(())
+}
+
 // HELPERS
 
 fn check_fee_payer_balance(config: &Config, required_balance: u64) -> Result<(), Error> {
@@ -739,3 +2663,177 @@ fn quote_currency_of(matches: &ArgMatches<'_>, name: &str) -> Option<[u8; 32]> {
         None
     }
 }
+
+fn parse_borrow_rate_curve(string: &str) -> Result<BorrowRateCurve, String> {
+    let points = string
+        .split(',')
+        .map(|point| {
+            let (utilization_rate_bps, borrow_rate_bps) = point
+                .split_once(':')
+                .ok_or_else(|| format!("Borrow rate curve point {} is not UTIL:RATE", point))?;
+            Ok(BorrowRatePoint {
+                utilization_rate_bps: utilization_rate_bps
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("Invalid utilization rate {}: {}", point, e))?,
+                borrow_rate_bps: borrow_rate_bps
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("Invalid borrow rate {}: {}", point, e))?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    BorrowRateCurve::new(&points).map_err(|_| {
+        format!(
+            "Borrow rate curve cannot have more than {} points",
+            MAX_BORROW_RATE_CURVE_POINTS
+        )
+    })
+}
+
+fn is_borrow_rate_curve(string: String) -> Result<(), String> {
+    parse_borrow_rate_curve(&string).map(|_| ())
+}
+
+fn borrow_rate_curve_of(matches: &ArgMatches<'_>, name: &str) -> Option<BorrowRateCurve> {
+    matches
+        .value_of(name)
+        .map(|value| parse_borrow_rate_curve(value).unwrap())
+}
+
+fn isolated_borrow_reserves_of(
+    matches: &ArgMatches<'_>,
+    name: &str,
+) -> Option<[Pubkey; MAX_ISOLATED_BORROW_RESERVES]> {
+    pubkeys_of(matches, name).map(|pubkeys| {
+        let mut isolated_borrow_reserves = [Pubkey::default(); MAX_ISOLATED_BORROW_RESERVES];
+        for (reserve, pubkey) in isolated_borrow_reserves.iter_mut().zip(pubkeys) {
+            *reserve = pubkey;
+        }
+        isolated_borrow_reserves
+    })
+}
+
+fn parse_reserve_operations(string: &str) -> Result<u8, String> {
+    if string.trim() == "none" {
+        return Ok(0);
+    }
+    string.split(',').try_fold(0, |disabled_operations, name| {
+        RESERVE_OPERATIONS
+            .iter()
+            .find(|(operation_name, _)| *operation_name == name.trim())
+            .map(|(_, operation)| disabled_operations | *operation as u8)
+            .ok_or_else(|| format!("Unknown reserve operation {}", name))
+    })
+}
+
+fn is_reserve_operations(string: String) -> Result<(), String> {
+    parse_reserve_operations(&string).map(|_| ())
+}
+
+fn send_instructions(
+    config: &Config,
+    instructions: &[Instruction],
+    signers: Vec<&dyn Signer>,
+    required_balance: u64,
+) -> CommandResult {
+    let recent_blockhash = config.rpc_client.get_latest_blockhash()?;
+    let message = Message::new_with_blockhash(
+        instructions,
+        Some(&config.fee_payer.pubkey()),
+        &recent_blockhash,
+    );
+    check_fee_payer_balance(
+        config,
+        required_balance + config.rpc_client.get_fee_for_message(&message)?,
+    )?;
+
+    let mut signers = signers;
+    signers.insert(0, config.fee_payer.as_ref());
+    let transaction = Transaction::new(&unique_signers(signers), message, recent_blockhash);
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn get_reserve(config: &Config, reserve_pubkey: &Pubkey) -> Result<Reserve, Error> {
+    let reserve_account = config.rpc_client.get_account(reserve_pubkey)?;
+    Ok(Reserve::unpack(reserve_account.data.borrow())?)
+}
+
+fn get_obligation(config: &Config, obligation_pubkey: &Pubkey) -> Result<Obligation, Error> {
+    let obligation_account = config.rpc_client.get_account(obligation_pubkey)?;
+    Ok(Obligation::unpack_resizable(
+        obligation_account.data.borrow(),
+    )?)
+}
+
+/// Refresh every reserve of an obligation, along with any other reserve the
+/// instruction following the refreshes needs fresh, and then the obligation
+fn refresh_obligation_instructions(
+    config: &Config,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    other_reserve_pubkeys: &[Pubkey],
+) -> Result<Vec<Instruction>, Error> {
+    let obligation_reserve_pubkeys: Vec<Pubkey> = obligation
+        .deposits
+        .iter()
+        .map(|collateral| collateral.deposit_reserve)
+        .chain(
+            obligation
+                .borrows
+                .iter()
+                .map(|liquidity| liquidity.borrow_reserve),
+        )
+        .collect();
+
+    // a reserve can be both deposited to and borrowed from
+    let mut reserve_pubkeys: Vec<Pubkey> = vec![];
+    for reserve_pubkey in obligation_reserve_pubkeys
+        .iter()
+        .chain(other_reserve_pubkeys.iter())
+    {
+        if !reserve_pubkeys.contains(reserve_pubkey) {
+            reserve_pubkeys.push(*reserve_pubkey);
+        }
+    }
+
+    let mut instructions = vec![];
+    for reserve_pubkey in reserve_pubkeys {
+        let reserve = get_reserve(config, &reserve_pubkey)?;
+        instructions.push(refresh_reserve(
+            config.lending_program_id,
+            reserve_pubkey,
+            reserve.liquidity.oracle_pubkey,
+        ));
+    }
+    instructions.push(refresh_obligation(
+        config.lending_program_id,
+        obligation_pubkey,
+        obligation_reserve_pubkeys,
+    ));
+    Ok(instructions)
+}
+
+fn signer_or_fee_payer<'a>(config: &'a Config, keypair: &'a Option<Keypair>) -> &'a dyn Signer {
+    match keypair {
+        Some(keypair) => keypair,
+        None => config.fee_payer.as_ref(),
+    }
+}
+
+/// Amount provided in an `is_amount_or_all` argument, `None` for ALL
+fn amount_or_all_of(matches: &ArgMatches<'_>, name: &str) -> Option<f64> {
+    match matches.value_of(name) {
+        Some("ALL") | None => None,
+        Some(value) => value.parse().ok(),
+    }
+}
+
+/// Convert an optional UI amount to a token amount, `u64::MAX` lets the
+/// program pick the maximum amount allowed
+fn amount_or_max(ui_amount: Option<f64>, decimals: u8) -> u64 {
+    ui_amount.map_or(u64::MAX, |ui_amount| {
+        ui_amount_to_amount(ui_amount, decimals)
+    })
+}
@@ -0,0 +1,493 @@
+use {
+    serde::{Deserialize, Serialize},
+    solana_cli_output::{QuietDisplay, VerboseDisplay},
+    solana_program::pubkey::Pubkey,
+    spl_token_lending::{
+        math::{Decimal, TryDiv, WAD},
+        state::{LendingMarket, Obligation, OracleSource, Reserve, ReserveOperation},
+    },
+    std::fmt::{Display, Formatter, Result, Write},
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliLendingMarket {
+    pub address: String,
+    pub owner: String,
+    pub quote_currency: String,
+    pub token_program_id: String,
+    pub oracle_program_id: String,
+    pub liquidation_close_factor: u8,
+    pub paused: bool,
+    pub risk_council: Option<String>,
+}
+
+impl CliLendingMarket {
+    pub(crate) fn new(address: &Pubkey, lending_market: &LendingMarket) -> Self {
+        Self {
+            address: address.to_string(),
+            owner: lending_market.owner.to_string(),
+            quote_currency: quote_currency_to_string(&lending_market.quote_currency),
+            token_program_id: lending_market.token_program_id.to_string(),
+            oracle_program_id: lending_market.oracle_program_id.to_string(),
+            liquidation_close_factor: lending_market.liquidation_close_factor_percent(),
+            paused: lending_market.paused,
+            risk_council: Some(lending_market.risk_council)
+                .filter(|risk_council| *risk_council != Pubkey::default())
+                .map(|risk_council| risk_council.to_string()),
+        }
+    }
+}
+
+impl Display for CliLendingMarket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Lending Market: {}", self.address)?;
+        writeln!(f, "Owner: {}", self.owner)?;
+        writeln!(f, "Quote Currency: {}", self.quote_currency)?;
+        writeln!(f, "Token Program: {}", self.token_program_id)?;
+        writeln!(f, "Oracle Program: {}", self.oracle_program_id)?;
+        writeln!(
+            f,
+            "Liquidation Close Factor: {}%",
+            self.liquidation_close_factor
+        )?;
+        if let Some(risk_council) = &self.risk_council {
+            writeln!(f, "Risk Council: {}", risk_council)?;
+        }
+        if self.paused {
+            writeln!(f, "Paused: yes")?;
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliLendingMarket {}
+impl VerboseDisplay for CliLendingMarket {}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliReserve {
+    pub address: String,
+    pub lending_market: String,
+    pub last_update_slot: u64,
+    pub stale: bool,
+    pub liquidity_mint: String,
+    pub liquidity_mint_decimals: u8,
+    pub liquidity_supply: String,
+    pub liquidity_fee_receiver: String,
+    pub oracle: String,
+    pub oracle_source: String,
+    pub available_amount: u64,
+    pub borrowed_amount: String,
+    pub market_price: String,
+    pub market_price_slot: u64,
+    pub utilization_rate: String,
+    pub current_borrow_rate: String,
+    pub collateral_mint: String,
+    pub collateral_mint_total_supply: u64,
+    pub collateral_supply: String,
+    pub disabled_operations: Vec<String>,
+    pub config: CliReserveConfig,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliReserveConfig {
+    pub optimal_utilization_rate: u8,
+    pub loan_to_value_ratio: u8,
+    pub liquidation_bonus: u8,
+    pub liquidation_threshold: u8,
+    pub min_borrow_rate: u8,
+    pub optimal_borrow_rate: u8,
+    pub max_borrow_rate: u8,
+    pub borrow_fee: String,
+    pub flash_loan_fee: String,
+    pub host_fee_percentage: u8,
+    pub max_price_age_slots: u64,
+    pub max_confidence_bps: u16,
+    pub min_twap_window_seconds: u64,
+    pub deposit_limit: u64,
+    pub borrow_limit: u64,
+    pub protocol_liquidation_fee: u8,
+    pub borrow_rate_curve: Vec<CliBorrowRatePoint>,
+    pub elevation_group: u8,
+    pub elevation_group_loan_to_value_ratio: u8,
+    pub elevation_group_liquidation_threshold: u8,
+    pub isolated_borrow_reserves: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliBorrowRatePoint {
+    pub utilization_rate_bps: u16,
+    pub borrow_rate_bps: u16,
+}
+
+impl CliReserve {
+    pub(crate) fn new(address: &Pubkey, reserve: &Reserve) -> Self {
+        let config = &reserve.config;
+        Self {
+            address: address.to_string(),
+            lending_market: reserve.lending_market.to_string(),
+            last_update_slot: reserve.last_update.slot,
+            stale: reserve.last_update.stale,
+            liquidity_mint: reserve.liquidity.mint_pubkey.to_string(),
+            liquidity_mint_decimals: reserve.liquidity.mint_decimals,
+            liquidity_supply: reserve.liquidity.supply_pubkey.to_string(),
+            liquidity_fee_receiver: reserve.liquidity.fee_receiver.to_string(),
+            oracle: reserve.liquidity.oracle_pubkey.to_string(),
+            oracle_source: oracle_source_to_string(reserve.liquidity.oracle_source),
+            available_amount: reserve.liquidity.available_amount,
+            borrowed_amount: reserve.liquidity.borrowed_amount_wads.to_string(),
+            market_price: reserve.liquidity.market_price.to_string(),
+            market_price_slot: reserve.liquidity.market_price_slot,
+            utilization_rate: reserve
+                .liquidity
+                .utilization_rate()
+                .map(|rate| rate.to_string())
+                .unwrap_or_default(),
+            current_borrow_rate: reserve
+                .current_borrow_rate()
+                .map(|rate| rate.to_string())
+                .unwrap_or_default(),
+            collateral_mint: reserve.collateral.mint_pubkey.to_string(),
+            collateral_mint_total_supply: reserve.collateral.mint_total_supply,
+            collateral_supply: reserve.collateral.supply_pubkey.to_string(),
+            disabled_operations: RESERVE_OPERATIONS
+                .iter()
+                .filter(|(_, operation)| reserve.is_operation_disabled(*operation))
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            config: CliReserveConfig {
+                optimal_utilization_rate: config.optimal_utilization_rate,
+                loan_to_value_ratio: config.loan_to_value_ratio,
+                liquidation_bonus: config.liquidation_bonus,
+                liquidation_threshold: config.liquidation_threshold,
+                min_borrow_rate: config.min_borrow_rate,
+                optimal_borrow_rate: config.optimal_borrow_rate,
+                max_borrow_rate: config.max_borrow_rate,
+                borrow_fee: wad_to_string(config.fees.borrow_fee_wad),
+                flash_loan_fee: wad_to_string(config.fees.flash_loan_fee_wad),
+                host_fee_percentage: config.fees.host_fee_percentage,
+                max_price_age_slots: config.max_price_age_slots,
+                max_confidence_bps: config.max_confidence_bps,
+                min_twap_window_seconds: config.min_twap_window_seconds,
+                deposit_limit: config.deposit_limit,
+                borrow_limit: config.borrow_limit,
+                protocol_liquidation_fee: config.protocol_liquidation_fee,
+                borrow_rate_curve: config
+                    .effective_borrow_rate_curve()
+                    .points()
+                    .iter()
+                    .map(|point| CliBorrowRatePoint {
+                        utilization_rate_bps: point.utilization_rate_bps,
+                        borrow_rate_bps: point.borrow_rate_bps,
+                    })
+                    .collect(),
+                elevation_group: config.elevation_group,
+                elevation_group_loan_to_value_ratio: config.elevation_group_loan_to_value_ratio,
+                elevation_group_liquidation_threshold: config.elevation_group_liquidation_threshold,
+                isolated_borrow_reserves: config
+                    .isolated_borrow_reserves
+                    .iter()
+                    .filter(|reserve| **reserve != Pubkey::default())
+                    .map(|reserve| reserve.to_string())
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl Display for CliReserve {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Reserve: {}", self.address)?;
+        writeln!(f, "Lending Market: {}", self.lending_market)?;
+        writeln!(
+            f,
+            "Last Update Slot: {}{}",
+            self.last_update_slot,
+            if self.stale { " (stale)" } else { "" }
+        )?;
+        writeln!(
+            f,
+            "Liquidity Mint: {} ({} decimals)",
+            self.liquidity_mint, self.liquidity_mint_decimals
+        )?;
+        writeln!(f, "Liquidity Supply: {}", self.liquidity_supply)?;
+        writeln!(f, "Liquidity Fee Receiver: {}", self.liquidity_fee_receiver)?;
+        writeln!(f, "Oracle: {} ({})", self.oracle, self.oracle_source)?;
+        writeln!(
+            f,
+            "Market Price: {} (slot {})",
+            self.market_price, self.market_price_slot
+        )?;
+        writeln!(f, "Available Liquidity: {}", self.available_amount)?;
+        writeln!(f, "Borrowed Liquidity: {}", self.borrowed_amount)?;
+        writeln!(f, "Utilization Rate: {}", self.utilization_rate)?;
+        writeln!(f, "Current Borrow Rate: {}", self.current_borrow_rate)?;
+        writeln!(f, "Collateral Mint: {}", self.collateral_mint)?;
+        writeln!(
+            f,
+            "Collateral Mint Supply: {}",
+            self.collateral_mint_total_supply
+        )?;
+        writeln!(f, "Collateral Supply: {}", self.collateral_supply)?;
+        if !self.disabled_operations.is_empty() {
+            writeln!(
+                f,
+                "Disabled Operations: {}",
+                self.disabled_operations.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliReserve {}
+impl VerboseDisplay for CliReserve {
+    fn write_str(&self, w: &mut dyn Write) -> Result {
+        write!(w, "{}", self)?;
+        let config = &self.config;
+        writeln!(w, "Config:")?;
+        writeln!(
+            w,
+            "  Optimal Utilization Rate: {}%",
+            config.optimal_utilization_rate
+        )?;
+        writeln!(w, "  Loan To Value Ratio: {}%", config.loan_to_value_ratio)?;
+        writeln!(w, "  Liquidation Bonus: {}%", config.liquidation_bonus)?;
+        writeln!(
+            w,
+            "  Protocol Liquidation Fee: {}% of bonus",
+            config.protocol_liquidation_fee
+        )?;
+        writeln!(
+            w,
+            "  Liquidation Threshold: {}%",
+            config.liquidation_threshold
+        )?;
+        writeln!(
+            w,
+            "  Borrow Rates: {}% min, {}% optimal, {}% max",
+            config.min_borrow_rate, config.optimal_borrow_rate, config.max_borrow_rate
+        )?;
+        writeln!(
+            w,
+            "  Borrow Rate Curve (utilization bps:rate bps): {}",
+            config
+                .borrow_rate_curve
+                .iter()
+                .map(|point| format!("{}:{}", point.utilization_rate_bps, point.borrow_rate_bps))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        writeln!(w, "  Borrow Fee: {}", config.borrow_fee)?;
+        writeln!(w, "  Flash Loan Fee: {}", config.flash_loan_fee)?;
+        writeln!(w, "  Host Fee Percentage: {}%", config.host_fee_percentage)?;
+        writeln!(w, "  Max Price Age: {} slots", config.max_price_age_slots)?;
+        writeln!(w, "  Max Confidence: {} bps", config.max_confidence_bps)?;
+        writeln!(
+            w,
+            "  Min TWAP Window: {} seconds",
+            config.min_twap_window_seconds
+        )?;
+        writeln!(
+            w,
+            "  Deposit Limit: {}",
+            limit_to_string(config.deposit_limit)
+        )?;
+        writeln!(
+            w,
+            "  Borrow Limit: {}",
+            limit_to_string(config.borrow_limit)
+        )?;
+        if config.elevation_group != 0 {
+            writeln!(
+                w,
+                "  Elevation Group: {} ({}% LTV, {}% liquidation threshold)",
+                config.elevation_group,
+                config.elevation_group_loan_to_value_ratio,
+                config.elevation_group_liquidation_threshold
+            )?;
+        }
+        if !config.isolated_borrow_reserves.is_empty() {
+            writeln!(
+                w,
+                "  Isolated, Borrowable Against For: {}",
+                config.isolated_borrow_reserves.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliObligation {
+    pub address: String,
+    pub lending_market: String,
+    pub owner: String,
+    pub last_update_slot: u64,
+    pub stale: bool,
+    pub deposits: Vec<CliObligationCollateral>,
+    pub borrows: Vec<CliObligationLiquidity>,
+    pub deposited_value: String,
+    pub borrowed_value: String,
+    pub allowed_borrow_value: String,
+    pub unhealthy_borrow_value: String,
+    pub loan_to_value: String,
+    /// Unhealthy borrow value over borrowed value, liquidatable below 1
+    pub health_factor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliObligationCollateral {
+    pub deposit_reserve: String,
+    pub deposited_amount: u64,
+    pub market_value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliObligationLiquidity {
+    pub borrow_reserve: String,
+    pub borrowed_amount: String,
+    pub cumulative_borrow_rate: String,
+    pub market_value: String,
+}
+
+impl CliObligation {
+    pub(crate) fn new(address: &Pubkey, obligation: &Obligation) -> Self {
+        let health_factor = if obligation.borrowed_value == Decimal::zero() {
+            None
+        } else {
+            obligation
+                .unhealthy_borrow_value
+                .try_div(obligation.borrowed_value)
+                .ok()
+                .map(|health_factor| health_factor.to_string())
+        };
+        Self {
+            address: address.to_string(),
+            lending_market: obligation.lending_market.to_string(),
+            owner: obligation.owner.to_string(),
+            last_update_slot: obligation.last_update.slot,
+            stale: obligation.last_update.stale,
+            deposits: obligation
+                .deposits
+                .iter()
+                .map(|collateral| CliObligationCollateral {
+                    deposit_reserve: collateral.deposit_reserve.to_string(),
+                    deposited_amount: collateral.deposited_amount,
+                    market_value: collateral.market_value.to_string(),
+                })
+                .collect(),
+            borrows: obligation
+                .borrows
+                .iter()
+                .map(|liquidity| CliObligationLiquidity {
+                    borrow_reserve: liquidity.borrow_reserve.to_string(),
+                    borrowed_amount: liquidity.borrowed_amount_wads.to_string(),
+                    cumulative_borrow_rate: liquidity.cumulative_borrow_rate_wads.to_string(),
+                    market_value: liquidity.market_value.to_string(),
+                })
+                .collect(),
+            deposited_value: obligation.deposited_value.to_string(),
+            borrowed_value: obligation.borrowed_value.to_string(),
+            allowed_borrow_value: obligation.allowed_borrow_value.to_string(),
+            unhealthy_borrow_value: obligation.unhealthy_borrow_value.to_string(),
+            loan_to_value: obligation
+                .loan_to_value()
+                .map(|ratio| ratio.to_string())
+                .unwrap_or_default(),
+            health_factor,
+        }
+    }
+}
+
+impl Display for CliObligation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Obligation: {}", self.address)?;
+        writeln!(f, "Lending Market: {}", self.lending_market)?;
+        writeln!(f, "Owner: {}", self.owner)?;
+        writeln!(
+            f,
+            "Last Update Slot: {}{}",
+            self.last_update_slot,
+            if self.stale { " (stale)" } else { "" }
+        )?;
+        writeln!(f, "Deposited Value: {}", self.deposited_value)?;
+        writeln!(f, "Borrowed Value: {}", self.borrowed_value)?;
+        writeln!(f, "Allowed Borrow Value: {}", self.allowed_borrow_value)?;
+        writeln!(f, "Unhealthy Borrow Value: {}", self.unhealthy_borrow_value)?;
+        writeln!(f, "Loan To Value: {}", self.loan_to_value)?;
+        writeln!(
+            f,
+            "Health Factor: {}",
+            self.health_factor.as_deref().unwrap_or("None (no borrows)")
+        )?;
+        writeln!(f, "Deposits:")?;
+        for collateral in &self.deposits {
+            writeln!(
+                f,
+                "  Reserve: {}\tAmount: {}\tValue: {}",
+                collateral.deposit_reserve, collateral.deposited_amount, collateral.market_value
+            )?;
+        }
+        writeln!(f, "Borrows:")?;
+        for liquidity in &self.borrows {
+            writeln!(
+                f,
+                "  Reserve: {}\tAmount: {}\tValue: {}",
+                liquidity.borrow_reserve, liquidity.borrowed_amount, liquidity.market_value
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliObligation {}
+impl VerboseDisplay for CliObligation {}
+
+fn quote_currency_to_string(quote_currency: &[u8; 32]) -> String {
+    let trimmed = quote_currency
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map(|end| &quote_currency[..=end])
+        .unwrap_or_default();
+    match std::str::from_utf8(trimmed) {
+        Ok(currency) if currency.chars().all(|c| c.is_ascii_graphic()) => currency.to_string(),
+        _ => Pubkey::new_from_array(*quote_currency).to_string(),
+    }
+}
+
+/// Reserve operations by the name the CLI takes and shows them with
+pub(crate) const RESERVE_OPERATIONS: [(&str, ReserveOperation); 5] = [
+    ("deposit", ReserveOperation::Deposit),
+    ("withdraw", ReserveOperation::Withdraw),
+    ("borrow", ReserveOperation::Borrow),
+    ("liquidate", ReserveOperation::Liquidate),
+    ("flash-loan", ReserveOperation::FlashLoan),
+];
+
+fn oracle_source_to_string(oracle_source: OracleSource) -> String {
+    match oracle_source {
+        OracleSource::Pyth => "Pyth",
+        OracleSource::TokenSwapTwap => "Token-swap TWAP",
+        OracleSource::FixedPriceAuthority => "Fixed price",
+    }
+    .to_string()
+}
+
+fn wad_to_string(wad: u64) -> String {
+    format!("{}", wad as f64 / WAD as f64)
+}
+
+fn limit_to_string(limit: u64) -> String {
+    if limit == u64::MAX {
+        "unlimited".to_string()
+    } else {
+        limit.to_string()
+    }
+}
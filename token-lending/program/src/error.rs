@@ -168,6 +168,32 @@ pub enum LendingError {
     /// Lending instruction exceeds desired slippage limit
     #[error("Amount smaller than desired slippage limit")]
     ExceededSlippage,
+    /// Fixed price authority is invalid
+    #[error("Fixed price authority is invalid")]
+    InvalidFixedPriceAuthority,
+    /// Oracle price is older than the reserve allows
+    #[error("Oracle price is stale")]
+    StaleOraclePrice,
+    /// Oracle price confidence interval is wider than the reserve allows
+    #[error("Oracle price confidence interval is too wide")]
+    OraclePriceConfidenceTooWide,
+    /// Deposit would exceed the reserve deposit limit
+    #[error("Deposit would exceed the reserve deposit limit")]
+    DepositLimitExceeded,
+
+    // 50
+    /// Borrow would exceed the reserve borrow limit
+    #[error("Borrow would exceed the reserve borrow limit")]
+    BorrowLimitExceeded,
+    /// Isolated collateral cannot be borrowed against for this reserve
+    #[error("Isolated collateral cannot be borrowed against for this reserve")]
+    IsolatedCollateralBorrowNotAllowed,
+    /// Lending market is paused
+    #[error("Lending market is paused")]
+    LendingMarketPaused,
+    /// Reserve operation is disabled
+    #[error("Reserve operation is disabled")]
+    ReserveOperationDisabled,
 }
 
 impl From<LendingError> for ProgramError {
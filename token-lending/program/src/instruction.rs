@@ -3,18 +3,24 @@
 use {
     crate::{
         error::LendingError,
-        state::{ReserveConfig, ReserveFees},
+        state::{
+            BorrowRateCurve, BorrowRatePoint, OracleSource, ReserveConfig, ReserveFees,
+            MAX_BORROW_RATE_CURVE_POINTS, MAX_ISOLATED_BORROW_RESERVES,
+        },
     },
     solana_program::{
         instruction::{AccountMeta, Instruction},
         msg,
         program_error::ProgramError,
         pubkey::{Pubkey, PUBKEY_BYTES},
-        sysvar,
+        system_program, sysvar,
     },
     std::{convert::TryInto, mem::size_of},
 };
 
+/// Size of the reserve config packed by clients predating oracle sources
+const LEGACY_RESERVE_CONFIG_LEN: usize = 24; // 7 + 8 + 8 + 1
+
 /// Instructions supported by the lending program.
 #[derive(Clone, Debug, PartialEq)]
 pub enum LendingInstruction {
@@ -64,9 +70,14 @@ pub enum LendingInstruction {
     ///   5. `[writable]` Reserve liquidity fee receiver - uninitialized.
     ///   6. `[writable]` Reserve collateral SPL Token mint - uninitialized.
     ///   7. `[writable]` Reserve collateral token supply - uninitialized.
-    ///   8. `[]` Pyth product account.
-    ///   9. `[]` Pyth price account. This will be used as the reserve liquidity
-    ///      oracle account.
+    ///   8. `[]` Oracle product account, depending on the oracle source:
+    ///      - Pyth: Pyth product account.
+    ///      - Token-swap TWAP: quote currency mint on the other side of the
+    ///        pool.
+    ///      - Fixed price authority: fixed price account.
+    ///   9. `[]` Oracle price account, depending on the oracle source: Pyth
+    ///      price account, token-swap pool or fixed price account. This will be
+    ///      used as the reserve liquidity oracle account.
     ///   10 `[]` Lending market account.
     ///   11 `[]` Derived lending market authority.
     ///   12 `[signer]` Lending market owner.
@@ -79,6 +90,8 @@ pub enum LendingInstruction {
         liquidity_amount: u64,
         /// Reserve configuration values
         config: ReserveConfig,
+        /// Kind of oracle account pricing the reserve liquidity
+        oracle_source: OracleSource,
     },
 
     // 3
@@ -87,7 +100,7 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Reserve account.
-    ///   1. `[]` Reserve liquidity oracle account. Must be the Pyth price
+    ///   1. `[]` Reserve liquidity oracle account. Must be the oracle price
     ///      account specified at InitReserve.
     ///   2. `[]` Clock sysvar.
     RefreshReserve,
@@ -140,7 +153,8 @@ pub enum LendingInstruction {
     ///
     /// Accounts expected by this instruction:
     ///
-    ///   0. `[writable]` Obligation account - uninitialized.
+    ///   0. `[writable]` Obligation account - uninitialized, of the default
+    /// size or larger to hold more deposits and borrows.
     ///   1. `[]` Lending market account.
     ///   2. `[signer]` Obligation owner.
     ///   3. `[]` Clock sysvar.
@@ -212,6 +226,11 @@ pub enum LendingInstruction {
     /// Borrow liquidity from a reserve by depositing collateral tokens.
     /// Requires a refreshed obligation and reserve.
     ///
+    /// A first borrow from the reserve is rejected if the obligation has
+    /// isolated collateral, or collateral in an elevation group the borrow
+    /// reserve is not part of, use BorrowObligationLiquidityWithDepositReserves
+    /// instead.
+    ///
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source borrow reserve liquidity supply SPL Token
@@ -270,7 +289,7 @@ pub enum LendingInstruction {
     ///      withdraw reserve collateral mint.
     ///   2. `[writable]` Repay reserve account - refreshed.
     ///   3. `[writable]` Repay reserve liquidity supply SPL Token account.
-    ///   4. `[]` Withdraw reserve account - refreshed.
+    ///   4. `[writable]` Withdraw reserve account - refreshed.
     ///   5. `[writable]` Withdraw reserve collateral supply SPL Token account.
     ///   6. `[writable]` Obligation account - refreshed.
     ///   7. `[]` Lending market account.
@@ -278,6 +297,13 @@ pub enum LendingInstruction {
     ///   9. `[signer]` User transfer authority ($authority).
     ///   10 `[]` Clock sysvar.
     ///   11 `[]` Token program id.
+    ///
+    ///   Optional, required if the withdraw reserve has a protocol liquidation
+    ///   fee:
+    ///   12 `[writable]` Withdraw reserve collateral SPL Token mint.
+    ///   13 `[writable]` Withdraw reserve liquidity supply SPL Token account.
+    ///   14 `[writable]` Withdraw reserve liquidity fee receiver account. Receives
+    ///      the protocol share of the liquidation bonus, redeemed as liquidity.
     LiquidateObligation {
         /// Amount of liquidity to repay - u64::MAX for up to 100% of borrowed
         /// amount
@@ -343,6 +369,129 @@ pub enum LendingInstruction {
         /// Reserve configuration updated values
         new_config: ReserveConfig,
     },
+
+    // 15
+    /// Sets the price of a fixed price account, which can be used as a reserve
+    /// liquidity oracle. Initializes the account with the signer as its
+    /// authority if needed. Only accepted by builds with the
+    /// `fixed-price-oracle` feature.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Fixed price account - owned by the lending program.
+    ///   1. `[signer]` Fixed price authority.
+    ///   2. `[]` Clock sysvar.
+    ///   3. `[]` Rent sysvar.
+    SetFixedPrice {
+        /// Currency the price is quoted in, cannot change once initialized
+        quote_currency: [u8; 32],
+        /// Price, to be multiplied by 10^expo
+        price: u64,
+        /// Price exponent
+        expo: i32,
+        /// Confidence interval around the price, in the same units
+        confidence: u64,
+    },
+
+    // 16
+    /// Sets the liquidation close factor of a lending market.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetLiquidationCloseFactor {
+        /// Percentage of an obligation's borrowed value that can be repaid
+        /// during each liquidation call, 0 for the default
+        liquidation_close_factor: u8,
+    },
+
+    // 17
+    /// Grows an obligation account to hold more deposits and borrows
+    /// combined. The obligation owner pays for any additional rent.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Obligation account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[writable, signer]` Obligation owner.
+    ///   3. `[]` System program id.
+    ResizeObligation {
+        /// Max number of deposits and borrows combined the obligation can
+        /// hold after resizing
+        max_reserves: u8,
+    },
+
+    // 18
+    /// Pauses or unpauses a lending market. A paused lending market only
+    /// allows refreshes and repayments.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner or risk council.
+    SetLendingMarketPaused {
+        /// Whether the lending market is paused
+        paused: bool,
+    },
+
+    // 19
+    /// Disables or enables individual operations of a reserve.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Reserve account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer]` Lending market owner or risk council.
+    SetReserveOperations {
+        /// Bitmask of disabled reserve operations, see
+        /// [ReserveOperation](../state/enum.ReserveOperation.html)
+        disabled_operations: u8,
+    },
+
+    // 20
+    /// Sets the risk council of a lending market, which can pause it and
+    /// disable reserve operations alongside the owner.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetRiskCouncil {
+        /// The new risk council, default to remove it
+        risk_council: Pubkey,
+    },
+
+    // 21
+    /// Borrow liquidity from a reserve by depositing collateral tokens, with
+    /// the obligation collateral deposit reserves to check isolated collateral
+    /// and elevation groups against a first borrow from the reserve. Requires
+    /// a refreshed obligation and reserve.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Source borrow reserve liquidity supply SPL Token
+    ///      account.
+    ///   1. `[writable]` Destination liquidity token account. Minted by borrow
+    ///      reserve liquidity mint.
+    ///   2. `[writable]` Borrow reserve account - refreshed.
+    ///   3. `[writable]` Borrow reserve liquidity fee receiver account. Must be
+    ///      the fee account specified at InitReserve.
+    ///   4. `[writable]` Obligation account - refreshed.
+    ///   5. `[]` Lending market account.
+    ///   6. `[]` Derived lending market authority.
+    ///   7. `[signer]` Obligation owner.
+    ///   8. `[]` Clock sysvar.
+    ///   9. `[]` Token program id.
+    ///   .. `[]` Collateral deposit reserve accounts - all, in order.
+    ///   .. `[optional, writable]` Host fee receiver account.
+    BorrowObligationLiquidityWithDepositReserves {
+        /// Amount of liquidity to borrow - u64::MAX for 100% of borrowing power
+        liquidity_amount: u64,
+        /// Minimum amount of liquidity to receive, if borrowing 100% of
+        /// borrowing power
+        slippage_limit: u64,
+    },
 }
 
 impl LendingInstruction {
@@ -367,10 +516,22 @@ impl LendingInstruction {
             }
             2 => {
                 let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
-                let config = Self::unpack_reserve_config(rest)?;
+                // clients predating oracle sources only pack the first config
+                // fields, the new reserve starts with defaults for the others
+                let (config, oracle_source) = if rest.len() == LEGACY_RESERVE_CONFIG_LEN {
+                    let (config, _rest) = Self::unpack_legacy_reserve_config(rest)?;
+                    (config, OracleSource::Pyth)
+                } else {
+                    let (config, rest) = Self::unpack_reserve_config(rest)?;
+                    let (oracle_source, _rest) = Self::unpack_u8(rest)?;
+                    let oracle_source = OracleSource::try_from(oracle_source)
+                        .map_err(|_| LendingError::InstructionUnpackError)?;
+                    (config, oracle_source)
+                };
                 Self::InitReserve {
                     liquidity_amount,
                     config,
+                    oracle_source,
                 }
             }
             3 => Self::RefreshReserve,
@@ -413,9 +574,61 @@ impl LendingInstruction {
                 Self::FlashLoan { amount }
             }
             14 => {
-                let new_config = Self::unpack_reserve_config(rest)?;
+                let (new_config, _rest) = Self::unpack_reserve_config(rest)?;
                 Self::ModifyReserveConfig { new_config }
             }
+            15 => {
+                let (quote_currency, rest) = Self::unpack_bytes32(rest)?;
+                let (price, rest) = Self::unpack_u64(rest)?;
+                let (expo, rest) = Self::unpack_i32(rest)?;
+                let (confidence, _rest) = Self::unpack_u64(rest)?;
+                Self::SetFixedPrice {
+                    quote_currency: *quote_currency,
+                    price,
+                    expo,
+                    confidence,
+                }
+            }
+            16 => {
+                let (liquidation_close_factor, _rest) = Self::unpack_u8(rest)?;
+                Self::SetLiquidationCloseFactor {
+                    liquidation_close_factor,
+                }
+            }
+            17 => {
+                let (max_reserves, _rest) = Self::unpack_u8(rest)?;
+                Self::ResizeObligation { max_reserves }
+            }
+            18 => {
+                let (paused, _rest) = Self::unpack_u8(rest)?;
+                let paused = match paused {
+                    0 => false,
+                    1 => true,
+                    _ => {
+                        msg!("Paused flag cannot be unpacked");
+                        return Err(LendingError::InstructionUnpackError.into());
+                    }
+                };
+                Self::SetLendingMarketPaused { paused }
+            }
+            19 => {
+                let (disabled_operations, _rest) = Self::unpack_u8(rest)?;
+                Self::SetReserveOperations {
+                    disabled_operations,
+                }
+            }
+            20 => {
+                let (risk_council, _rest) = Self::unpack_pubkey(rest)?;
+                Self::SetRiskCouncil { risk_council }
+            }
+            21 => {
+                let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
+                let (slippage_limit, _rest) = Self::unpack_u64(rest)?;
+                Self::BorrowObligationLiquidityWithDepositReserves {
+                    liquidity_amount,
+                    slippage_limit,
+                }
+            }
             _ => {
                 msg!("Instruction cannot be unpacked");
                 return Err(LendingError::InstructionUnpackError.into());
@@ -437,6 +650,34 @@ impl LendingInstruction {
         Ok((value, rest))
     }
 
+    fn unpack_i32(input: &[u8]) -> Result<(i32, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            msg!("i32 cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(4);
+        let value = bytes
+            .get(..4)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i32::from_le_bytes)
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok((value, rest))
+    }
+
+    fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+        if input.len() < 2 {
+            msg!("u16 cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(2);
+        let value = bytes
+            .get(..2)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u16::from_le_bytes)
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok((value, rest))
+    }
+
     fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
         if input.is_empty() {
             msg!("u8 cannot be unpacked");
@@ -475,7 +716,7 @@ impl LendingInstruction {
         Ok((pk, rest))
     }
 
-    fn unpack_reserve_config(input: &[u8]) -> Result<ReserveConfig, ProgramError> {
+    fn unpack_legacy_reserve_config(input: &[u8]) -> Result<(ReserveConfig, &[u8]), ProgramError> {
         let (optimal_utilization_rate, rest) = Self::unpack_u8(input)?;
         let (loan_to_value_ratio, rest) = Self::unpack_u8(rest)?;
         let (liquidation_bonus, rest) = Self::unpack_u8(rest)?;
@@ -485,9 +726,8 @@ impl LendingInstruction {
         let (max_borrow_rate, rest) = Self::unpack_u8(rest)?;
         let (borrow_fee_wad, rest) = Self::unpack_u64(rest)?;
         let (flash_loan_fee_wad, rest) = Self::unpack_u64(rest)?;
-        let (host_fee_percentage, _rest) = Self::unpack_u8(rest)?;
-
-        Ok(ReserveConfig {
+        let (host_fee_percentage, rest) = Self::unpack_u8(rest)?;
+        let config = ReserveConfig {
             optimal_utilization_rate,
             loan_to_value_ratio,
             liquidation_bonus,
@@ -500,7 +740,72 @@ impl LendingInstruction {
                 flash_loan_fee_wad,
                 host_fee_percentage,
             },
-        })
+            ..ReserveConfig::default()
+        };
+        Ok((config, rest))
+    }
+
+    fn unpack_reserve_config(input: &[u8]) -> Result<(ReserveConfig, &[u8]), ProgramError> {
+        let (config, rest) = Self::unpack_legacy_reserve_config(input)?;
+        let (max_price_age_slots, rest) = Self::unpack_u64(rest)?;
+        let (max_confidence_bps, rest) = Self::unpack_u16(rest)?;
+        let (min_twap_window_seconds, rest) = Self::unpack_u64(rest)?;
+        let (deposit_limit, rest) = Self::unpack_u64(rest)?;
+        let (borrow_limit, rest) = Self::unpack_u64(rest)?;
+        let (protocol_liquidation_fee, rest) = Self::unpack_u8(rest)?;
+        let (borrow_rate_curve, rest) = Self::unpack_borrow_rate_curve(rest)?;
+        let (elevation_group, rest) = Self::unpack_u8(rest)?;
+        let (elevation_group_loan_to_value_ratio, rest) = Self::unpack_u8(rest)?;
+        let (elevation_group_liquidation_threshold, rest) = Self::unpack_u8(rest)?;
+        let mut isolated_borrow_reserves = [Pubkey::default(); MAX_ISOLATED_BORROW_RESERVES];
+        let (num_reserves, mut rest) = Self::unpack_u8(rest)?;
+        if num_reserves as usize > MAX_ISOLATED_BORROW_RESERVES {
+            msg!("Isolated collateral has too many borrow reserves");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        for reserve in isolated_borrow_reserves
+            .iter_mut()
+            .take(num_reserves as usize)
+        {
+            let (pubkey, next) = Self::unpack_pubkey(rest)?;
+            *reserve = pubkey;
+            rest = next;
+        }
+
+        let config = ReserveConfig {
+            max_price_age_slots,
+            max_confidence_bps,
+            min_twap_window_seconds,
+            deposit_limit,
+            borrow_limit,
+            protocol_liquidation_fee,
+            borrow_rate_curve,
+            elevation_group,
+            elevation_group_loan_to_value_ratio,
+            elevation_group_liquidation_threshold,
+            isolated_borrow_reserves,
+            ..config
+        };
+        Ok((config, rest))
+    }
+
+    fn unpack_borrow_rate_curve(input: &[u8]) -> Result<(BorrowRateCurve, &[u8]), ProgramError> {
+        let (num_points, mut rest) = Self::unpack_u8(input)?;
+        if num_points as usize > MAX_BORROW_RATE_CURVE_POINTS {
+            msg!("Borrow rate curve has too many points");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let mut points = Vec::with_capacity(num_points as usize);
+        for _ in 0..num_points {
+            let (utilization_rate_bps, next) = Self::unpack_u16(rest)?;
+            let (borrow_rate_bps, next) = Self::unpack_u16(next)?;
+            points.push(BorrowRatePoint {
+                utilization_rate_bps,
+                borrow_rate_bps,
+            });
+            rest = next;
+        }
+        Ok((BorrowRateCurve::new(&points)?, rest))
     }
 
     /// Packs a [LendingInstruction](enum.LendingInstruction.html) into a byte
@@ -523,10 +828,12 @@ impl LendingInstruction {
             Self::InitReserve {
                 liquidity_amount,
                 config,
+                oracle_source,
             } => {
                 buf.push(2);
                 buf.extend_from_slice(&liquidity_amount.to_le_bytes());
                 Self::extend_buffer_from_reserve_config(&mut buf, &config);
+                buf.extend_from_slice(&(oracle_source as u8).to_le_bytes());
             }
             Self::RefreshReserve => {
                 buf.push(3);
@@ -577,6 +884,50 @@ impl LendingInstruction {
                 buf.push(14);
                 Self::extend_buffer_from_reserve_config(&mut buf, &new_config);
             }
+            Self::SetFixedPrice {
+                quote_currency,
+                price,
+                expo,
+                confidence,
+            } => {
+                buf.push(15);
+                buf.extend_from_slice(quote_currency.as_ref());
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.extend_from_slice(&expo.to_le_bytes());
+                buf.extend_from_slice(&confidence.to_le_bytes());
+            }
+            Self::SetLiquidationCloseFactor {
+                liquidation_close_factor,
+            } => {
+                buf.push(16);
+                buf.extend_from_slice(&liquidation_close_factor.to_le_bytes());
+            }
+            Self::ResizeObligation { max_reserves } => {
+                buf.push(17);
+                buf.extend_from_slice(&max_reserves.to_le_bytes());
+            }
+            Self::SetLendingMarketPaused { paused } => {
+                buf.push(18);
+                buf.push(paused as u8);
+            }
+            Self::SetReserveOperations {
+                disabled_operations,
+            } => {
+                buf.push(19);
+                buf.extend_from_slice(&disabled_operations.to_le_bytes());
+            }
+            Self::SetRiskCouncil { risk_council } => {
+                buf.push(20);
+                buf.extend_from_slice(risk_council.as_ref());
+            }
+            Self::BorrowObligationLiquidityWithDepositReserves {
+                liquidity_amount,
+                slippage_limit,
+            } => {
+                buf.push(21);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+                buf.extend_from_slice(&slippage_limit.to_le_bytes());
+            }
         }
         buf
     }
@@ -593,6 +944,30 @@ impl LendingInstruction {
         buf.extend_from_slice(&config.fees.borrow_fee_wad.to_le_bytes());
         buf.extend_from_slice(&config.fees.flash_loan_fee_wad.to_le_bytes());
         buf.extend_from_slice(&config.fees.host_fee_percentage.to_le_bytes());
+        buf.extend_from_slice(&config.max_price_age_slots.to_le_bytes());
+        buf.extend_from_slice(&config.max_confidence_bps.to_le_bytes());
+        buf.extend_from_slice(&config.min_twap_window_seconds.to_le_bytes());
+        buf.extend_from_slice(&config.deposit_limit.to_le_bytes());
+        buf.extend_from_slice(&config.borrow_limit.to_le_bytes());
+        buf.extend_from_slice(&config.protocol_liquidation_fee.to_le_bytes());
+        let points = config.borrow_rate_curve.points();
+        buf.push(points.len() as u8);
+        for point in points {
+            buf.extend_from_slice(&point.utilization_rate_bps.to_le_bytes());
+            buf.extend_from_slice(&point.borrow_rate_bps.to_le_bytes());
+        }
+        buf.extend_from_slice(&config.elevation_group.to_le_bytes());
+        buf.extend_from_slice(&config.elevation_group_loan_to_value_ratio.to_le_bytes());
+        buf.extend_from_slice(&config.elevation_group_liquidation_threshold.to_le_bytes());
+        let isolated_borrow_reserves = config
+            .isolated_borrow_reserves
+            .iter()
+            .filter(|reserve| **reserve != Pubkey::default())
+            .collect::<Vec<_>>();
+        buf.push(isolated_borrow_reserves.len() as u8);
+        for reserve in isolated_borrow_reserves {
+            buf.extend_from_slice(reserve.as_ref());
+        }
     }
 }
 
@@ -637,12 +1012,67 @@ pub fn set_lending_market_owner(
     }
 }
 
+/// Creates a 'SetLiquidationCloseFactor' instruction.
+pub fn set_liquidation_close_factor(
+    program_id: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner: Pubkey,
+    liquidation_close_factor: u8,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner, true),
+        ],
+        data: LendingInstruction::SetLiquidationCloseFactor {
+            liquidation_close_factor,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a 'SetLendingMarketPaused' instruction.
+pub fn set_lending_market_paused(
+    program_id: Pubkey,
+    lending_market_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    paused: bool,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, true),
+        ],
+        data: LendingInstruction::SetLendingMarketPaused { paused }.pack(),
+    }
+}
+
+/// Creates a 'SetRiskCouncil' instruction.
+pub fn set_risk_council(
+    program_id: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner: Pubkey,
+    risk_council: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner, true),
+        ],
+        data: LendingInstruction::SetRiskCouncil { risk_council }.pack(),
+    }
+}
+
 /// Creates an 'InitReserve' instruction.
 #[allow(clippy::too_many_arguments)]
 pub fn init_reserve(
     program_id: Pubkey,
     liquidity_amount: u64,
     config: ReserveConfig,
+    oracle_source: OracleSource,
     source_liquidity_pubkey: Pubkey,
     destination_collateral_pubkey: Pubkey,
     reserve_pubkey: Pubkey,
@@ -651,8 +1081,8 @@ pub fn init_reserve(
     reserve_liquidity_fee_receiver_pubkey: Pubkey,
     reserve_collateral_mint_pubkey: Pubkey,
     reserve_collateral_supply_pubkey: Pubkey,
-    pyth_product_pubkey: Pubkey,
-    pyth_price_pubkey: Pubkey,
+    oracle_product_pubkey: Pubkey,
+    oracle_price_pubkey: Pubkey,
     lending_market_pubkey: Pubkey,
     lending_market_owner_pubkey: Pubkey,
     user_transfer_authority_pubkey: Pubkey,
@@ -670,8 +1100,8 @@ pub fn init_reserve(
         AccountMeta::new(reserve_liquidity_fee_receiver_pubkey, false),
         AccountMeta::new(reserve_collateral_mint_pubkey, false),
         AccountMeta::new(reserve_collateral_supply_pubkey, false),
-        AccountMeta::new_readonly(pyth_product_pubkey, false),
-        AccountMeta::new_readonly(pyth_price_pubkey, false),
+        AccountMeta::new_readonly(oracle_product_pubkey, false),
+        AccountMeta::new_readonly(oracle_price_pubkey, false),
         AccountMeta::new_readonly(lending_market_pubkey, false),
         AccountMeta::new_readonly(lending_market_authority_pubkey, false),
         AccountMeta::new_readonly(lending_market_owner_pubkey, true),
@@ -686,6 +1116,7 @@ pub fn init_reserve(
         data: LendingInstruction::InitReserve {
             liquidity_amount,
             config,
+            oracle_source,
         }
         .pack(),
     }
@@ -801,6 +1232,26 @@ pub fn init_obligation(
     }
 }
 
+/// Creates a 'ResizeObligation' instruction.
+pub fn resize_obligation(
+    program_id: Pubkey,
+    max_reserves: u8,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::ResizeObligation { max_reserves }.pack(),
+    }
+}
+
 /// Creates a 'RefreshObligation' instruction.
 #[allow(clippy::too_many_arguments)]
 pub fn refresh_obligation(
@@ -933,6 +1384,58 @@ pub fn borrow_obligation_liquidity(
     }
 }
 
+/// Creates a 'BorrowObligationLiquidityWithDepositReserves' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity_with_deposit_reserves(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    slippage_limit: Option<u64>,
+    source_liquidity_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    deposit_reserve_pubkeys: Vec<Pubkey>,
+    host_fee_receiver_pubkey: Option<Pubkey>,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    let mut accounts = vec![
+        AccountMeta::new(source_liquidity_pubkey, false),
+        AccountMeta::new(destination_liquidity_pubkey, false),
+        AccountMeta::new(borrow_reserve_pubkey, false),
+        AccountMeta::new(borrow_reserve_liquidity_fee_receiver_pubkey, false),
+        AccountMeta::new(obligation_pubkey, false),
+        AccountMeta::new_readonly(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+        AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(
+        deposit_reserve_pubkeys
+            .into_iter()
+            .map(|pubkey| AccountMeta::new_readonly(pubkey, false)),
+    );
+    if let Some(host_fee_receiver_pubkey) = host_fee_receiver_pubkey {
+        accounts.push(AccountMeta::new(host_fee_receiver_pubkey, false));
+    }
+    let slippage_limit = slippage_limit.unwrap_or(0);
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::BorrowObligationLiquidityWithDepositReserves {
+            liquidity_amount,
+            slippage_limit,
+        }
+        .pack(),
+    }
+}
+
 /// Creates a `RepayObligationLiquidity` instruction
 #[allow(clippy::too_many_arguments)]
 pub fn repay_obligation_liquidity(
@@ -972,6 +1475,9 @@ pub fn liquidate_obligation(
     repay_reserve_liquidity_supply_pubkey: Pubkey,
     withdraw_reserve_pubkey: Pubkey,
     withdraw_reserve_collateral_supply_pubkey: Pubkey,
+    withdraw_reserve_collateral_mint_pubkey: Pubkey,
+    withdraw_reserve_liquidity_supply_pubkey: Pubkey,
+    withdraw_reserve_liquidity_fee_receiver_pubkey: Pubkey,
     obligation_pubkey: Pubkey,
     lending_market_pubkey: Pubkey,
     user_transfer_authority_pubkey: Pubkey,
@@ -987,7 +1493,7 @@ pub fn liquidate_obligation(
             AccountMeta::new(destination_collateral_pubkey, false),
             AccountMeta::new(repay_reserve_pubkey, false),
             AccountMeta::new(repay_reserve_liquidity_supply_pubkey, false),
-            AccountMeta::new_readonly(withdraw_reserve_pubkey, false),
+            AccountMeta::new(withdraw_reserve_pubkey, false),
             AccountMeta::new(withdraw_reserve_collateral_supply_pubkey, false),
             AccountMeta::new(obligation_pubkey, false),
             AccountMeta::new_readonly(lending_market_pubkey, false),
@@ -995,6 +1501,9 @@ pub fn liquidate_obligation(
             AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
             AccountMeta::new_readonly(sysvar::clock::id(), false),
             AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(withdraw_reserve_collateral_mint_pubkey, false),
+            AccountMeta::new(withdraw_reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(withdraw_reserve_liquidity_fee_receiver_pubkey, false),
         ],
         data: LendingInstruction::LiquidateObligation { liquidity_amount }.pack(),
     }
@@ -1058,9 +1567,59 @@ pub fn modify_reserve_config(
     }
 }
 
+/// Creates a 'SetReserveOperations' instruction.
+pub fn set_reserve_operations(
+    program_id: Pubkey,
+    disabled_operations: u8,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, true),
+        ],
+        data: LendingInstruction::SetReserveOperations {
+            disabled_operations,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a 'SetFixedPrice' instruction.
+pub fn set_fixed_price(
+    program_id: Pubkey,
+    fixed_price_pubkey: Pubkey,
+    fixed_price_authority_pubkey: Pubkey,
+    quote_currency: [u8; 32],
+    price: u64,
+    expo: i32,
+    confidence: u64,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(fixed_price_pubkey, false),
+            AccountMeta::new_readonly(fixed_price_authority_pubkey, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: LendingInstruction::SetFixedPrice {
+            quote_currency,
+            price,
+            expo,
+            confidence,
+        }
+        .pack(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {super::*, crate::state::ReserveOperation};
 
     #[test]
     fn test_init_lending_market() {
@@ -1127,6 +1686,17 @@ mod tests {
                 flash_loan_fee_wad: 3,
                 host_fee_percentage: 1,
             },
+            max_price_age_slots: 10,
+            max_confidence_bps: 200,
+            min_twap_window_seconds: 600,
+            deposit_limit: 1_000_000,
+            borrow_limit: 500_000,
+            protocol_liquidation_fee: 10,
+            borrow_rate_curve: BorrowRateCurve::from_optimal(50, 2, 4, 10),
+            elevation_group: 1,
+            elevation_group_loan_to_value_ratio: 80,
+            elevation_group_liquidation_threshold: 90,
+            isolated_borrow_reserves: [Pubkey::new_unique(), Pubkey::default(), Pubkey::default()],
         };
         let source_liquidity_pubkey = Pubkey::new_unique();
         let destination_collateral_pubkey = Pubkey::new_unique();
@@ -1136,8 +1706,8 @@ mod tests {
         let reserve_liquidity_fee_receiver_pubkey = Pubkey::new_unique();
         let reserve_collateral_mint_pubkey = Pubkey::new_unique();
         let reserve_collateral_supply_pubkey = Pubkey::new_unique();
-        let pyth_product_pubkey = Pubkey::new_unique();
-        let pyth_price_pubkey = Pubkey::new_unique();
+        let oracle_product_pubkey = Pubkey::new_unique();
+        let oracle_price_pubkey = Pubkey::new_unique();
         let lending_market_pubkey = Pubkey::new_unique();
         let lending_market_owner_pubkey = Pubkey::new_unique();
         let user_transfer_authority_pubkey = Pubkey::new_unique();
@@ -1145,6 +1715,7 @@ mod tests {
             program_id,
             liquidity_amount,
             config,
+            OracleSource::TokenSwapTwap,
             source_liquidity_pubkey,
             destination_collateral_pubkey,
             reserve_pubkey,
@@ -1153,21 +1724,89 @@ mod tests {
             reserve_liquidity_fee_receiver_pubkey,
             reserve_collateral_mint_pubkey,
             reserve_collateral_supply_pubkey,
-            pyth_product_pubkey,
-            pyth_price_pubkey,
+            oracle_product_pubkey,
+            oracle_price_pubkey,
             lending_market_pubkey,
             lending_market_owner_pubkey,
             user_transfer_authority_pubkey,
         );
         assert_eq!(instruction.program_id, program_id);
         assert_eq!(instruction.accounts.len(), 17);
+        let expected = LendingInstruction::InitReserve {
+            liquidity_amount,
+            config,
+            oracle_source: OracleSource::TokenSwapTwap,
+        };
+        assert_eq!(instruction.data, expected.pack());
         assert_eq!(
-            instruction.data,
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_init_reserve_without_oracle_fields() {
+        let config = ReserveConfig {
+            optimal_utilization_rate: 50,
+            liquidation_threshold: 5,
+            max_price_age_slots: 10,
+            max_confidence_bps: 200,
+            min_twap_window_seconds: 600,
+            deposit_limit: 1_000_000,
+            borrow_limit: 500_000,
+            protocol_liquidation_fee: 10,
+            borrow_rate_curve: BorrowRateCurve::from_optimal(50, 2, 4, 10),
+            elevation_group: 1,
+            elevation_group_loan_to_value_ratio: 80,
+            elevation_group_liquidation_threshold: 90,
+            isolated_borrow_reserves: [Pubkey::new_unique(), Pubkey::default(), Pubkey::default()],
+            ..ReserveConfig::default()
+        };
+        let data = LendingInstruction::InitReserve {
+            liquidity_amount: 42,
+            config,
+            oracle_source: OracleSource::FixedPriceAuthority,
+        }
+        .pack();
+
+        // data packed before oracle sources, price guards, the token-swap oracle
+        // window, limits, the protocol liquidation fee, borrow rate curves,
+        // elevation groups and isolated collateral were added
+        let legacy_data = &data[..data.len() - 85];
+        assert_eq!(
+            LendingInstruction::unpack(legacy_data).unwrap(),
             LendingInstruction::InitReserve {
-                liquidity_amount,
-                config,
+                liquidity_amount: 42,
+                config: ReserveConfig {
+                    max_price_age_slots: 0,
+                    max_confidence_bps: 0,
+                    min_twap_window_seconds: 0,
+                    deposit_limit: u64::MAX,
+                    borrow_limit: u64::MAX,
+                    protocol_liquidation_fee: 0,
+                    borrow_rate_curve: BorrowRateCurve::EMPTY,
+                    elevation_group: 0,
+                    elevation_group_loan_to_value_ratio: 0,
+                    elevation_group_liquidation_threshold: 0,
+                    isolated_borrow_reserves: ReserveConfig::default().isolated_borrow_reserves,
+                    ..config
+                },
+                oracle_source: OracleSource::Pyth,
             }
-            .pack()
+        );
+
+        let mut invalid_data = data.clone();
+        *invalid_data.last_mut().unwrap() = 3;
+        assert_eq!(
+            LendingInstruction::unpack(&invalid_data),
+            Err(LendingError::InstructionUnpackError.into())
+        );
+
+        // isolated borrow reserve missing its last bytes
+        let truncated_data = [&data[..data.len() - 3], &data[data.len() - 1..]].concat();
+        assert_eq!(
+            LendingInstruction::unpack(&truncated_data),
+            Err(LendingError::InstructionUnpackError.into())
         );
     }
 
@@ -1260,6 +1899,29 @@ mod tests {
         assert_eq!(instruction.data, LendingInstruction::InitObligation.pack());
     }
 
+    #[test]
+    fn test_resize_obligation() {
+        let program_id = Pubkey::new_unique();
+        let obligation_pubkey = Pubkey::new_unique();
+        let lending_market_pubkey = Pubkey::new_unique();
+        let obligation_owner_pubkey = Pubkey::new_unique();
+        let instruction = resize_obligation(
+            program_id,
+            20,
+            obligation_pubkey,
+            lending_market_pubkey,
+            obligation_owner_pubkey,
+        );
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.accounts.len(), 4);
+        let expected = LendingInstruction::ResizeObligation { max_reserves: 20 };
+        assert_eq!(instruction.data, expected.pack());
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            expected
+        );
+    }
+
     #[test]
     fn test_refresh_obligation() {
         let program_id = Pubkey::new_unique();
@@ -1369,6 +2031,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_borrow_obligation_liquidity_with_deposit_reserves() {
+        let program_id = Pubkey::new_unique();
+        let liquidity_amount = u64::MAX;
+        let slippage_limit = 1_000;
+        let borrow_reserve_liquidity_fee_receiver_pubkey = Pubkey::new_unique();
+        let deposit_reserve_pubkeys = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let host_fee_receiver_pubkey = Pubkey::new_unique();
+        let instruction = borrow_obligation_liquidity_with_deposit_reserves(
+            program_id,
+            liquidity_amount,
+            Some(slippage_limit),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            borrow_reserve_liquidity_fee_receiver_pubkey,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            deposit_reserve_pubkeys.clone(),
+            Some(host_fee_receiver_pubkey),
+        );
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.accounts.len(), 13);
+        assert_eq!(instruction.accounts[10].pubkey, deposit_reserve_pubkeys[0]);
+        assert_eq!(instruction.accounts[11].pubkey, deposit_reserve_pubkeys[1]);
+        assert_eq!(instruction.accounts[12].pubkey, host_fee_receiver_pubkey);
+        let expected = LendingInstruction::BorrowObligationLiquidityWithDepositReserves {
+            liquidity_amount,
+            slippage_limit,
+        };
+        assert_eq!(instruction.data, expected.pack());
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            expected
+        );
+    }
+
     #[test]
     fn test_repay_obligation_liquidity() {
         let program_id = Pubkey::new_unique();
@@ -1407,6 +2107,9 @@ mod tests {
         let repay_reserve_liquidity_supply_pubkey = Pubkey::new_unique();
         let withdraw_reserve_pubkey = Pubkey::new_unique();
         let withdraw_reserve_collateral_supply_pubkey = Pubkey::new_unique();
+        let withdraw_reserve_collateral_mint_pubkey = Pubkey::new_unique();
+        let withdraw_reserve_liquidity_supply_pubkey = Pubkey::new_unique();
+        let withdraw_reserve_liquidity_fee_receiver_pubkey = Pubkey::new_unique();
         let obligation_pubkey = Pubkey::new_unique();
         let lending_market_pubkey = Pubkey::new_unique();
         let user_transfer_authority_pubkey = Pubkey::new_unique();
@@ -1419,12 +2122,15 @@ mod tests {
             repay_reserve_liquidity_supply_pubkey,
             withdraw_reserve_pubkey,
             withdraw_reserve_collateral_supply_pubkey,
+            withdraw_reserve_collateral_mint_pubkey,
+            withdraw_reserve_liquidity_supply_pubkey,
+            withdraw_reserve_liquidity_fee_receiver_pubkey,
             obligation_pubkey,
             lending_market_pubkey,
             user_transfer_authority_pubkey,
         );
         assert_eq!(instruction.program_id, program_id);
-        assert_eq!(instruction.accounts.len(), 12);
+        assert_eq!(instruction.accounts.len(), 15);
         assert_eq!(
             instruction.data,
             LendingInstruction::LiquidateObligation { liquidity_amount }.pack()
@@ -1484,6 +2190,17 @@ mod tests {
                 flash_loan_fee_wad: 3,
                 host_fee_percentage: 1,
             },
+            max_price_age_slots: 10,
+            max_confidence_bps: 200,
+            min_twap_window_seconds: 600,
+            deposit_limit: 1_000_000,
+            borrow_limit: 500_000,
+            protocol_liquidation_fee: 10,
+            borrow_rate_curve: BorrowRateCurve::from_optimal(50, 2, 4, 10),
+            elevation_group: 1,
+            elevation_group_loan_to_value_ratio: 80,
+            elevation_group_liquidation_threshold: 90,
+            isolated_borrow_reserves: [Pubkey::new_unique(), Pubkey::default(), Pubkey::default()],
         };
         let reserve_pubkey = Pubkey::new_unique();
         let lending_market_pubkey = Pubkey::new_unique();
@@ -1501,5 +2218,142 @@ mod tests {
             instruction.data,
             LendingInstruction::ModifyReserveConfig { new_config: config }.pack()
         );
+
+        // a config missing its later fields must not reset them to defaults
+        for len in [1 + LEGACY_RESERVE_CONFIG_LEN, instruction.data.len() - 1] {
+            assert_eq!(
+                LendingInstruction::unpack(&instruction.data[..len]),
+                Err(LendingError::InstructionUnpackError.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_fixed_price() {
+        let program_id = Pubkey::new_unique();
+        let fixed_price_pubkey = Pubkey::new_unique();
+        let fixed_price_authority_pubkey = Pubkey::new_unique();
+        let quote_currency = [1u8; 32];
+        let instruction = set_fixed_price(
+            program_id,
+            fixed_price_pubkey,
+            fixed_price_authority_pubkey,
+            quote_currency,
+            u64::MAX,
+            -8,
+            42,
+        );
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.accounts.len(), 4);
+        let expected = LendingInstruction::SetFixedPrice {
+            quote_currency,
+            price: u64::MAX,
+            expo: -8,
+            confidence: 42,
+        };
+        assert_eq!(instruction.data, expected.pack());
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_set_liquidation_close_factor() {
+        let program_id = Pubkey::new_unique();
+        let lending_market_pubkey = Pubkey::new_unique();
+        let lending_market_owner = Pubkey::new_unique();
+        let instruction = set_liquidation_close_factor(
+            program_id,
+            lending_market_pubkey,
+            lending_market_owner,
+            20,
+        );
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.accounts.len(), 2);
+        let expected = LendingInstruction::SetLiquidationCloseFactor {
+            liquidation_close_factor: 20,
+        };
+        assert_eq!(instruction.data, expected.pack());
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_set_lending_market_paused() {
+        let program_id = Pubkey::new_unique();
+        let lending_market_pubkey = Pubkey::new_unique();
+        let authority_pubkey = Pubkey::new_unique();
+        for paused in [true, false] {
+            let instruction = set_lending_market_paused(
+                program_id,
+                lending_market_pubkey,
+                authority_pubkey,
+                paused,
+            );
+            assert_eq!(instruction.program_id, program_id);
+            assert_eq!(instruction.accounts.len(), 2);
+            let expected = LendingInstruction::SetLendingMarketPaused { paused };
+            assert_eq!(instruction.data, expected.pack());
+            assert_eq!(
+                LendingInstruction::unpack(&instruction.data).unwrap(),
+                expected
+            );
+        }
+        assert_eq!(
+            LendingInstruction::unpack(&[18, 2]),
+            Err(LendingError::InstructionUnpackError.into())
+        );
+    }
+
+    #[test]
+    fn test_set_reserve_operations() {
+        let program_id = Pubkey::new_unique();
+        let reserve_pubkey = Pubkey::new_unique();
+        let lending_market_pubkey = Pubkey::new_unique();
+        let authority_pubkey = Pubkey::new_unique();
+        let disabled_operations =
+            ReserveOperation::Borrow as u8 | ReserveOperation::FlashLoan as u8;
+        let instruction = set_reserve_operations(
+            program_id,
+            disabled_operations,
+            reserve_pubkey,
+            lending_market_pubkey,
+            authority_pubkey,
+        );
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.accounts.len(), 3);
+        let expected = LendingInstruction::SetReserveOperations {
+            disabled_operations,
+        };
+        assert_eq!(instruction.data, expected.pack());
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_set_risk_council() {
+        let program_id = Pubkey::new_unique();
+        let lending_market_pubkey = Pubkey::new_unique();
+        let lending_market_owner = Pubkey::new_unique();
+        let risk_council = Pubkey::new_unique();
+        let instruction = set_risk_council(
+            program_id,
+            lending_market_pubkey,
+            lending_market_owner,
+            risk_council,
+        );
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.accounts.len(), 2);
+        let expected = LendingInstruction::SetRiskCouncil { risk_council };
+        assert_eq!(instruction.data, expected.pack());
+        assert_eq!(
+            LendingInstruction::unpack(&instruction.data).unwrap(),
+            expected
+        );
     }
 }
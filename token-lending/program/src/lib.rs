@@ -9,6 +9,8 @@ pub mod instruction;
 pub mod math;
 pub mod processor;
 pub mod pyth;
+#[cfg(not(target_os = "solana"))]
+pub mod simulation;
 pub mod state;
 
 // Export current sdk types for downstream users building with a different sdk
@@ -7,15 +7,17 @@ use {
         math::{Decimal, Rate, TryAdd, TryDiv, TryMul},
         pyth,
         state::{
-            CalculateBorrowResult, CalculateLiquidationResult, CalculateRepayResult,
-            InitLendingMarketParams, InitObligationParams, InitReserveParams, LendingMarket,
-            NewReserveCollateralParams, NewReserveLiquidityParams, Obligation, Reserve,
-            ReserveCollateral, ReserveConfig, ReserveLiquidity,
+            CalculateBorrowResult, CalculateLiquidationResult, CalculateRepayResult, FixedPrice,
+            InitFixedPriceParams, InitLendingMarketParams, InitObligationParams, InitReserveParams,
+            LendingMarket, NewReserveCollateralParams, NewReserveLiquidityParams, Obligation,
+            OracleSource, Reserve, ReserveCollateral, ReserveConfig, ReserveLiquidity,
+            ReserveOperation, TokenSwapOracle, MAX_RESIZED_OBLIGATION_RESERVES,
         },
     },
     num_traits::FromPrimitive,
     solana_program::{
         account_info::{next_account_info, AccountInfo},
+        clock::Slot,
         decode_error::DecodeError,
         entrypoint::ProgramResult,
         instruction::Instruction,
@@ -24,12 +26,14 @@ use {
         program_error::{PrintProgramError, ProgramError},
         program_pack::{IsInitialized, Pack},
         pubkey::Pubkey,
+        system_instruction,
         sysvar::{clock::Clock, rent::Rent, Sysvar},
     },
     spl_token::{
         solana_program::instruction::AccountMeta,
         state::{Account, Mint},
     },
+    spl_token_swap::{curve::base::CurveType, oracle::PRICE_FRACTIONAL_BITS, state::SwapVersion},
     std::convert::TryInto,
 };
 
@@ -55,9 +59,16 @@ pub fn process_instruction(
         LendingInstruction::InitReserve {
             liquidity_amount,
             config,
+            oracle_source,
         } => {
             msg!("Instruction: Init Reserve");
-            process_init_reserve(program_id, liquidity_amount, config, accounts)
+            process_init_reserve(
+                program_id,
+                liquidity_amount,
+                config,
+                oracle_source,
+                accounts,
+            )
         }
         LendingInstruction::RefreshReserve => {
             msg!("Instruction: Refresh Reserve");
@@ -96,6 +107,7 @@ pub fn process_instruction(
                 program_id,
                 liquidity_amount,
                 slippage_limit,
+                false,
                 accounts,
             )
         }
@@ -115,6 +127,59 @@ pub fn process_instruction(
             msg!("Instruction: Modify Reserve Config");
             process_modify_reserve_config(program_id, new_config, accounts)
         }
+        LendingInstruction::SetFixedPrice {
+            quote_currency,
+            price,
+            expo,
+            confidence,
+        } => {
+            msg!("Instruction: Set Fixed Price");
+            process_set_fixed_price(
+                program_id,
+                quote_currency,
+                price,
+                expo,
+                confidence,
+                accounts,
+            )
+        }
+        LendingInstruction::SetLiquidationCloseFactor {
+            liquidation_close_factor,
+        } => {
+            msg!("Instruction: Set Liquidation Close Factor");
+            process_set_liquidation_close_factor(program_id, liquidation_close_factor, accounts)
+        }
+        LendingInstruction::ResizeObligation { max_reserves } => {
+            msg!("Instruction: Resize Obligation");
+            process_resize_obligation(program_id, max_reserves, accounts)
+        }
+        LendingInstruction::SetLendingMarketPaused { paused } => {
+            msg!("Instruction: Set Lending Market Paused");
+            process_set_lending_market_paused(program_id, paused, accounts)
+        }
+        LendingInstruction::SetReserveOperations {
+            disabled_operations,
+        } => {
+            msg!("Instruction: Set Reserve Operations");
+            process_set_reserve_operations(program_id, disabled_operations, accounts)
+        }
+        LendingInstruction::SetRiskCouncil { risk_council } => {
+            msg!("Instruction: Set Risk Council");
+            process_set_risk_council(program_id, risk_council, accounts)
+        }
+        LendingInstruction::BorrowObligationLiquidityWithDepositReserves {
+            liquidity_amount,
+            slippage_limit,
+        } => {
+            msg!("Instruction: Borrow Obligation Liquidity With Deposit Reserves");
+            process_borrow_obligation_liquidity(
+                program_id,
+                liquidity_amount,
+                slippage_limit,
+                true,
+                accounts,
+            )
+        }
     }
 }
 
@@ -179,10 +244,148 @@ fn process_set_lending_market_owner(
     Ok(())
 }
 
+fn process_set_liquidation_close_factor(
+    program_id: &Pubkey,
+    liquidation_close_factor: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidation_close_factor > 100 {
+        msg!("Liquidation close factor must be in range [0, 100]");
+        return Err(LendingError::InvalidConfig.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if lending_market_info.owner != program_id {
+        msg!("Lending market provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &lending_market.owner != lending_market_owner_info.key {
+        msg!("Lending market owner does not match the lending market owner provided");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+    if !lending_market_owner_info.is_signer {
+        msg!("Lending market owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    lending_market.liquidation_close_factor = liquidation_close_factor;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_set_risk_council(
+    program_id: &Pubkey,
+    risk_council: Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if lending_market_info.owner != program_id {
+        msg!("Lending market provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &lending_market.owner != lending_market_owner_info.key {
+        msg!("Lending market owner does not match the lending market owner provided");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+    if !lending_market_owner_info.is_signer {
+        msg!("Lending market owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    lending_market.risk_council = risk_council;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_set_lending_market_paused(
+    program_id: &Pubkey,
+    paused: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if lending_market_info.owner != program_id {
+        msg!("Lending market provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if !lending_market.is_pause_authority(authority_info.key) {
+        msg!("Authority provided is not the lending market owner or risk council");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+    if !authority_info.is_signer {
+        msg!("Authority provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    lending_market.paused = paused;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_set_reserve_operations(
+    program_id: &Pubkey,
+    disabled_operations: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if disabled_operations & !ReserveOperation::ALL != 0 {
+        msg!("Disabled reserve operations contain an unknown operation");
+        return Err(LendingError::InvalidConfig.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &reserve.lending_market != lending_market_info.key {
+        msg!("Reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if lending_market_info.owner != program_id {
+        msg!("Lending market provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if !lending_market.is_pause_authority(authority_info.key) {
+        msg!("Authority provided is not the lending market owner or risk council");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+    if !authority_info.is_signer {
+        msg!("Authority provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    reserve.disabled_operations = disabled_operations;
+    Reserve::pack(reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
 fn process_init_reserve(
     program_id: &Pubkey,
     liquidity_amount: u64,
     config: ReserveConfig,
+    oracle_source: OracleSource,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     if liquidity_amount == 0 {
@@ -201,8 +404,8 @@ fn process_init_reserve(
     let reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
     let reserve_collateral_mint_info = next_account_info(account_info_iter)?;
     let reserve_collateral_supply_info = next_account_info(account_info_iter)?;
-    let pyth_product_info = next_account_info(account_info_iter)?;
-    let pyth_price_info = next_account_info(account_info_iter)?;
+    let oracle_product_info = next_account_info(account_info_iter)?;
+    let oracle_price_info = next_account_info(account_info_iter)?;
     let lending_market_info = next_account_info(account_info_iter)?;
     let lending_market_authority_info = next_account_info(account_info_iter)?;
     let lending_market_owner_info = next_account_info(account_info_iter)?;
@@ -242,49 +445,6 @@ fn process_init_reserve(
         return Err(LendingError::InvalidSigner.into());
     }
 
-    if &lending_market.oracle_program_id != pyth_product_info.owner {
-        msg!("Pyth product account provided is not owned by the lending market oracle program");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-    if &lending_market.oracle_program_id != pyth_price_info.owner {
-        msg!("Pyth price account provided is not owned by the lending market oracle program");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let pyth_product_data = pyth_product_info.try_borrow_data()?;
-    let pyth_product = pyth::load::<pyth::Product>(&pyth_product_data)
-        .map_err(|_| ProgramError::InvalidAccountData)?;
-    if pyth_product.magic != pyth::MAGIC {
-        msg!("Pyth product account provided is not a valid Pyth account");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-    if pyth_product.ver != pyth::VERSION_2 {
-        msg!("Pyth product account provided has a different version than expected");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-    if pyth_product.atype != pyth::AccountType::Product as u32 {
-        msg!("Pyth product account provided is not a valid Pyth product account");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let pyth_price_pubkey_bytes: &[u8; 32] = pyth_price_info
-        .key
-        .as_ref()
-        .try_into()
-        .map_err(|_| LendingError::InvalidAccountInput)?;
-    if &pyth_product.px_acc.val != pyth_price_pubkey_bytes {
-        msg!("Pyth product price account does not match the Pyth price provided");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let quote_currency = get_pyth_product_quote_currency(pyth_product)?;
-    if lending_market.quote_currency != quote_currency {
-        msg!("Lending market quote currency does not match the oracle quote currency");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let market_price = get_pyth_price(pyth_price_info, clock)?;
-
     let authority_signer_seeds = &[
         lending_market_info.key.as_ref(),
         &[lending_market.bump_seed],
@@ -304,6 +464,32 @@ fn process_init_reserve(
         return Err(LendingError::InvalidTokenOwner.into());
     }
 
+    let (market_price, market_price_slot, token_swap_oracle) = match oracle_source {
+        OracleSource::Pyth => {
+            check_pyth_oracle(&lending_market, oracle_product_info, oracle_price_info)?;
+            let (market_price, market_price_slot) =
+                get_pyth_price(oracle_price_info, clock, &config)?;
+            (market_price, market_price_slot, TokenSwapOracle::default())
+        }
+        OracleSource::TokenSwapTwap => {
+            // the first price is only known after a refresh in a later second
+            let token_swap_oracle = init_token_swap_oracle(
+                &lending_market,
+                reserve_liquidity_mint_info.key,
+                oracle_product_info,
+                oracle_price_info,
+            )?;
+            (Decimal::zero(), 0, token_swap_oracle)
+        }
+        OracleSource::FixedPriceAuthority => {
+            check_fixed_price_oracle_enabled()?;
+            check_fixed_price_oracle(program_id, &lending_market, oracle_price_info)?;
+            let (market_price, market_price_slot) =
+                get_fixed_price(oracle_price_info, clock, &config)?;
+            (market_price, market_price_slot, TokenSwapOracle::default())
+        }
+    };
+
     reserve.init(InitReserveParams {
         current_slot: clock.slot,
         lending_market: *lending_market_info.key,
@@ -312,8 +498,11 @@ fn process_init_reserve(
             mint_decimals: reserve_liquidity_mint.decimals,
             supply_pubkey: *reserve_liquidity_supply_info.key,
             fee_receiver: *reserve_liquidity_fee_receiver_info.key,
-            oracle_pubkey: *pyth_price_info.key,
+            oracle_pubkey: *oracle_price_info.key,
+            oracle_source,
+            token_swap_oracle,
             market_price,
+            market_price_slot,
         }),
         collateral: ReserveCollateral::new(NewReserveCollateralParams {
             mint_pubkey: *reserve_collateral_mint_info.key,
@@ -402,7 +591,23 @@ fn process_refresh_reserve(program_id: &Pubkey, accounts: &[AccountInfo]) -> Pro
         return Err(LendingError::InvalidAccountInput.into());
     }
 
-    reserve.liquidity.market_price = get_pyth_price(reserve_liquidity_oracle_info, clock)?;
+    match reserve.liquidity.oracle_source {
+        OracleSource::Pyth => {
+            let (market_price, market_price_slot) =
+                get_pyth_price(reserve_liquidity_oracle_info, clock, &reserve.config)?;
+            reserve.liquidity.market_price = market_price;
+            reserve.liquidity.market_price_slot = market_price_slot;
+        }
+        OracleSource::TokenSwapTwap => {
+            refresh_token_swap_oracle(&mut reserve, reserve_liquidity_oracle_info, clock)?;
+        }
+        OracleSource::FixedPriceAuthority => {
+            let (market_price, market_price_slot) =
+                get_fixed_price(reserve_liquidity_oracle_info, clock, &reserve.config)?;
+            reserve.liquidity.market_price = market_price;
+            reserve.liquidity.market_price_slot = market_price_slot;
+        }
+    }
 
     reserve.accrue_interest(clock.slot)?;
     reserve.last_update.update_slot(clock.slot);
@@ -442,6 +647,10 @@ fn process_deposit_reserve_liquidity(
         msg!("Lending market token program does not match the token program provided");
         return Err(LendingError::InvalidTokenProgram.into());
     }
+    if lending_market.paused {
+        msg!("Lending market is paused");
+        return Err(LendingError::LendingMarketPaused.into());
+    }
 
     let mut reserve = Reserve::unpack(&reserve_info.data.borrow())?;
     if reserve_info.owner != program_id {
@@ -452,6 +661,10 @@ fn process_deposit_reserve_liquidity(
         msg!("Reserve lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
+    if reserve.is_operation_disabled(ReserveOperation::Deposit) {
+        msg!("Reserve has deposits disabled");
+        return Err(LendingError::ReserveOperationDisabled.into());
+    }
     if &reserve.liquidity.supply_pubkey != reserve_liquidity_supply_info.key {
         msg!("Reserve liquidity supply does not match the reserve liquidity supply provided");
         return Err(LendingError::InvalidAccountInput.into());
@@ -488,6 +701,15 @@ fn process_deposit_reserve_liquidity(
         return Err(LendingError::InvalidMarketAuthority.into());
     }
 
+    let deposited_amount = reserve
+        .liquidity
+        .total_supply()?
+        .try_add(Decimal::from(liquidity_amount))?;
+    if deposited_amount > Decimal::from(reserve.config.deposit_limit) {
+        msg!("Deposit would exceed the reserve deposit limit");
+        return Err(LendingError::DepositLimitExceeded.into());
+    }
+
     let collateral_amount = reserve.deposit_liquidity(liquidity_amount)?;
     reserve.last_update.mark_stale();
     Reserve::pack(reserve, &mut reserve_info.data.borrow_mut())?;
@@ -544,6 +766,10 @@ fn process_redeem_reserve_collateral(
         msg!("Lending market token program does not match the token program provided");
         return Err(LendingError::InvalidTokenProgram.into());
     }
+    if lending_market.paused {
+        msg!("Lending market is paused");
+        return Err(LendingError::LendingMarketPaused.into());
+    }
 
     let mut reserve = Reserve::unpack(&reserve_info.data.borrow())?;
     if reserve_info.owner != program_id {
@@ -554,6 +780,10 @@ fn process_redeem_reserve_collateral(
         msg!("Reserve lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
+    if reserve.is_operation_disabled(ReserveOperation::Withdraw) {
+        msg!("Reserve has withdrawals disabled");
+        return Err(LendingError::ReserveOperationDisabled.into());
+    }
     if &reserve.collateral.mint_pubkey != reserve_collateral_mint_info.key {
         msg!("Reserve collateral mint does not match the reserve collateral mint provided");
         return Err(LendingError::InvalidAccountInput.into());
@@ -624,7 +854,14 @@ fn process_init_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) -> Pro
     let token_program_id = next_account_info(account_info_iter)?;
 
     assert_rent_exempt(rent, obligation_info)?;
-    let mut obligation = assert_uninitialized::<Obligation>(obligation_info)?;
+    if obligation_info.data_len() < Obligation::LEN {
+        msg!("Obligation account is too small");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    let mut obligation = Obligation::unpack_from_slice(&obligation_info.data.borrow())?;
+    if obligation.is_initialized() {
+        return Err(LendingError::AlreadyInitialized.into());
+    }
     if obligation_info.owner != program_id {
         msg!("Obligation provided is not owned by the lending program");
         return Err(LendingError::InvalidAccountOwner.into());
@@ -652,7 +889,73 @@ fn process_init_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) -> Pro
         deposits: vec![],
         borrows: vec![],
     });
-    Obligation::pack(obligation, &mut obligation_info.data.borrow_mut())?;
+    Obligation::pack_resizable(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_resize_obligation(
+    program_id: &Pubkey,
+    max_reserves: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let obligation = Obligation::unpack_resizable(&obligation_info.data.borrow())?;
+    if obligation_info.owner != program_id {
+        msg!("Obligation provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &obligation.lending_market != lending_market_info.key {
+        msg!("Obligation lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &obligation.owner != obligation_owner_info.key {
+        msg!("Obligation owner does not match the obligation owner provided");
+        return Err(LendingError::InvalidObligationOwner.into());
+    }
+    if !obligation_owner_info.is_signer {
+        msg!("Obligation owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    let max_reserves = max_reserves as usize;
+    if max_reserves > MAX_RESIZED_OBLIGATION_RESERVES {
+        msg!(
+            "Obligation cannot be resized to more than {} deposits and borrows combined",
+            MAX_RESIZED_OBLIGATION_RESERVES
+        );
+        return Err(LendingError::InvalidAmount.into());
+    }
+    let new_len = Obligation::account_len(max_reserves);
+    if new_len <= obligation_info.data_len() {
+        msg!("Obligation can only be resized to hold more deposits and borrows");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let rent = Rent::get()?;
+    let lamports_required = rent
+        .minimum_balance(new_len)
+        .saturating_sub(obligation_info.lamports());
+    if lamports_required > 0 {
+        invoke(
+            &system_instruction::transfer(
+                obligation_owner_info.key,
+                obligation_info.key,
+                lamports_required,
+            ),
+            &[
+                obligation_owner_info.clone(),
+                obligation_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+    obligation_info.realloc(new_len, true)?;
 
     Ok(())
 }
@@ -662,7 +965,7 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
     let obligation_info = next_account_info(account_info_iter)?;
     let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
 
-    let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+    let mut obligation = Obligation::unpack_resizable(&obligation_info.data.borrow())?;
     if obligation_info.owner != program_id {
         msg!("Obligation provided is not owned by the lending program");
         return Err(LendingError::InvalidAccountOwner.into());
@@ -670,8 +973,9 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
 
     let mut deposited_value = Decimal::zero();
     let mut borrowed_value = Decimal::zero();
-    let mut allowed_borrow_value = Decimal::zero();
-    let mut unhealthy_borrow_value = Decimal::zero();
+    let mut deposit_reserve_configs = Vec::with_capacity(obligation.deposits.len());
+    let mut elevation_groups =
+        Vec::with_capacity(obligation.deposits.len() + obligation.borrows.len());
 
     for (index, collateral) in obligation.deposits.iter_mut().enumerate() {
         let deposit_reserve_info = next_account_info(account_info_iter)?;
@@ -698,28 +1002,23 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
             );
             return Err(LendingError::ReserveStale.into());
         }
+        if deposit_reserve
+            .config
+            .is_price_stale(deposit_reserve.liquidity.market_price_slot, clock.slot)?
+        {
+            msg!(
+                "Deposit reserve provided for collateral {} has a stale oracle price",
+                index
+            );
+            return Err(LendingError::StaleOraclePrice.into());
+        }
 
-        // @TODO: add lookup table https://git.io/JOCYq
-        let decimals = 10u64
-            .checked_pow(deposit_reserve.liquidity.mint_decimals as u32)
-            .ok_or(LendingError::MathOverflow)?;
-
-        let market_value = deposit_reserve
-            .collateral_exchange_rate()?
-            .decimal_collateral_to_liquidity(collateral.deposited_amount.into())?
-            .try_mul(deposit_reserve.liquidity.market_price)?
-            .try_div(decimals)?;
+        let market_value = deposit_reserve.collateral_market_value(collateral.deposited_amount)?;
         collateral.market_value = market_value;
 
-        let loan_to_value_rate = Rate::from_percent(deposit_reserve.config.loan_to_value_ratio);
-        let liquidation_threshold_rate =
-            Rate::from_percent(deposit_reserve.config.liquidation_threshold);
-
         deposited_value = deposited_value.try_add(market_value)?;
-        allowed_borrow_value =
-            allowed_borrow_value.try_add(market_value.try_mul(loan_to_value_rate)?)?;
-        unhealthy_borrow_value =
-            unhealthy_borrow_value.try_add(market_value.try_mul(liquidation_threshold_rate)?)?;
+        elevation_groups.push(deposit_reserve.config.elevation_group);
+        deposit_reserve_configs.push(deposit_reserve.config);
     }
 
     for (index, liquidity) in obligation.borrows.iter_mut().enumerate() {
@@ -747,21 +1046,24 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
             );
             return Err(LendingError::ReserveStale.into());
         }
+        if borrow_reserve
+            .config
+            .is_price_stale(borrow_reserve.liquidity.market_price_slot, clock.slot)?
+        {
+            msg!(
+                "Borrow reserve provided for liquidity {} has a stale oracle price",
+                index
+            );
+            return Err(LendingError::StaleOraclePrice.into());
+        }
 
         liquidity.accrue_interest(borrow_reserve.liquidity.cumulative_borrow_rate_wads)?;
 
-        // @TODO: add lookup table https://git.io/JOCYq
-        let decimals = 10u64
-            .checked_pow(borrow_reserve.liquidity.mint_decimals as u32)
-            .ok_or(LendingError::MathOverflow)?;
-
-        let market_value = liquidity
-            .borrowed_amount_wads
-            .try_mul(borrow_reserve.liquidity.market_price)?
-            .try_div(decimals)?;
+        let market_value = borrow_reserve.market_value(liquidity.borrowed_amount_wads)?;
         liquidity.market_value = market_value;
 
         borrowed_value = borrowed_value.try_add(market_value)?;
+        elevation_groups.push(borrow_reserve.config.elevation_group);
     }
 
     if account_info_iter.peek().is_some() {
@@ -769,13 +1071,25 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
         return Err(LendingError::InvalidAccountInput.into());
     }
 
+    let elevation_group = Obligation::common_elevation_group(elevation_groups);
+    let (allowed_borrow_value, unhealthy_borrow_value) =
+        obligation.calculate_borrow_values(&deposit_reserve_configs, elevation_group)?;
+
     obligation.deposited_value = deposited_value;
     obligation.borrowed_value = borrowed_value;
     obligation.allowed_borrow_value = allowed_borrow_value;
     obligation.unhealthy_borrow_value = unhealthy_borrow_value;
+    obligation.deposits_elevation_group = Obligation::common_elevation_group(
+        deposit_reserve_configs
+            .iter()
+            .map(|config| config.elevation_group),
+    );
+    obligation.has_isolated_collateral = deposit_reserve_configs
+        .iter()
+        .any(|config| config.is_isolated());
 
     obligation.last_update.update_slot(clock.slot);
-    Obligation::pack(obligation, &mut obligation_info.data.borrow_mut())?;
+    Obligation::pack_resizable(obligation, &mut obligation_info.data.borrow_mut())?;
 
     Ok(())
 }
@@ -811,6 +1125,10 @@ fn process_deposit_obligation_collateral(
         msg!("Lending market token program does not match the token program provided");
         return Err(LendingError::InvalidTokenProgram.into());
     }
+    if lending_market.paused {
+        msg!("Lending market is paused");
+        return Err(LendingError::LendingMarketPaused.into());
+    }
 
     let deposit_reserve = Reserve::unpack(&deposit_reserve_info.data.borrow())?;
     if deposit_reserve_info.owner != program_id {
@@ -821,6 +1139,10 @@ fn process_deposit_obligation_collateral(
         msg!("Deposit reserve lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
+    if deposit_reserve.is_operation_disabled(ReserveOperation::Deposit) {
+        msg!("Deposit reserve has deposits disabled");
+        return Err(LendingError::ReserveOperationDisabled.into());
+    }
     if &deposit_reserve.collateral.supply_pubkey == source_collateral_info.key {
         msg!("Deposit reserve collateral supply cannot be used as the source collateral provided");
         return Err(LendingError::InvalidAccountInput.into());
@@ -835,12 +1157,12 @@ fn process_deposit_obligation_collateral(
         msg!("Deposit reserve is stale and must be refreshed in the current slot");
         return Err(LendingError::ReserveStale.into());
     }
-    if deposit_reserve.config.loan_to_value_ratio == 0 {
+    if deposit_reserve.config.max_loan_to_value_ratio() == 0 {
         msg!("Deposit reserve has collateral disabled for borrowing");
         return Err(LendingError::ReserveCollateralDisabled.into());
     }
 
-    let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+    let mut obligation = Obligation::unpack_resizable(&obligation_info.data.borrow())?;
     if obligation_info.owner != program_id {
         msg!("Obligation provided is not owned by the lending program");
         return Err(LendingError::InvalidAccountOwner.into());
@@ -859,10 +1181,13 @@ fn process_deposit_obligation_collateral(
     }
 
     obligation
-        .find_or_add_collateral_to_deposits(*deposit_reserve_info.key)?
+        .find_or_add_collateral_to_deposits(
+            *deposit_reserve_info.key,
+            Obligation::max_reserves(obligation_info.data_len()),
+        )?
         .deposit(collateral_amount)?;
     obligation.last_update.mark_stale();
-    Obligation::pack(obligation, &mut obligation_info.data.borrow_mut())?;
+    Obligation::pack_resizable(obligation, &mut obligation_info.data.borrow_mut())?;
 
     spl_token_transfer(TokenTransferParams {
         source: source_collateral_info.clone(),
@@ -907,6 +1232,10 @@ fn process_withdraw_obligation_collateral(
         msg!("Lending market token program does not match the token program provided");
         return Err(LendingError::InvalidTokenProgram.into());
     }
+    if lending_market.paused {
+        msg!("Lending market is paused");
+        return Err(LendingError::LendingMarketPaused.into());
+    }
 
     let withdraw_reserve = Reserve::unpack(&withdraw_reserve_info.data.borrow())?;
     if withdraw_reserve_info.owner != program_id {
@@ -917,6 +1246,10 @@ fn process_withdraw_obligation_collateral(
         msg!("Withdraw reserve lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
+    if withdraw_reserve.is_operation_disabled(ReserveOperation::Withdraw) {
+        msg!("Withdraw reserve has withdrawals disabled");
+        return Err(LendingError::ReserveOperationDisabled.into());
+    }
     if &withdraw_reserve.collateral.supply_pubkey != source_collateral_info.key {
         msg!("Withdraw reserve collateral supply must be used as the source collateral provided");
         return Err(LendingError::InvalidAccountInput.into());
@@ -930,7 +1263,7 @@ fn process_withdraw_obligation_collateral(
         return Err(LendingError::ReserveStale.into());
     }
 
-    let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+    let mut obligation = Obligation::unpack_resizable(&obligation_info.data.borrow())?;
     if obligation_info.owner != program_id {
         msg!("Obligation provided is not owned by the lending program");
         return Err(LendingError::InvalidAccountOwner.into());
@@ -982,8 +1315,10 @@ fn process_withdraw_obligation_collateral(
         msg!("Obligation deposited value is zero");
         return Err(LendingError::ObligationDepositsZero.into());
     } else {
+        // the obligation may be using the elevated loan to value ratio of the
+        // collateral, so the higher ratio bounds the value that can be withdrawn
         let max_withdraw_value = obligation.max_withdraw_value(Rate::from_percent(
-            withdraw_reserve.config.loan_to_value_ratio,
+            withdraw_reserve.config.max_loan_to_value_ratio(),
         ))?;
         if max_withdraw_value == Decimal::zero() {
             msg!("Maximum withdraw value is zero");
@@ -1017,7 +1352,7 @@ fn process_withdraw_obligation_collateral(
 
     obligation.withdraw(withdraw_amount, collateral_index)?;
     obligation.last_update.mark_stale();
-    Obligation::pack(obligation, &mut obligation_info.data.borrow_mut())?;
+    Obligation::pack_resizable(obligation, &mut obligation_info.data.borrow_mut())?;
 
     spl_token_transfer(TokenTransferParams {
         source: source_collateral_info.clone(),
@@ -1036,6 +1371,7 @@ fn process_borrow_obligation_liquidity(
     program_id: &Pubkey,
     liquidity_amount: u64,
     slippage_limit: u64,
+    with_deposit_reserves: bool,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     if liquidity_amount == 0 {
@@ -1064,6 +1400,10 @@ fn process_borrow_obligation_liquidity(
         msg!("Lending market token program does not match the token program provided");
         return Err(LendingError::InvalidTokenProgram.into());
     }
+    if lending_market.paused {
+        msg!("Lending market is paused");
+        return Err(LendingError::LendingMarketPaused.into());
+    }
 
     let mut borrow_reserve = Reserve::unpack(&borrow_reserve_info.data.borrow())?;
     if borrow_reserve_info.owner != program_id {
@@ -1074,6 +1414,10 @@ fn process_borrow_obligation_liquidity(
         msg!("Borrow reserve lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
+    if borrow_reserve.is_operation_disabled(ReserveOperation::Borrow) {
+        msg!("Borrow reserve has borrows disabled");
+        return Err(LendingError::ReserveOperationDisabled.into());
+    }
     if &borrow_reserve.liquidity.supply_pubkey != source_liquidity_info.key {
         msg!("Borrow reserve liquidity supply must be used as the source liquidity provided");
         return Err(LendingError::InvalidAccountInput.into());
@@ -1093,7 +1437,7 @@ fn process_borrow_obligation_liquidity(
         return Err(LendingError::ReserveStale.into());
     }
 
-    let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+    let mut obligation = Obligation::unpack_resizable(&obligation_info.data.borrow())?;
     if obligation_info.owner != program_id {
         msg!("Obligation provided is not owned by the lending program");
         return Err(LendingError::InvalidAccountOwner.into());
@@ -1123,6 +1467,93 @@ fn process_borrow_obligation_liquidity(
         return Err(LendingError::ObligationDepositsZero.into());
     }
 
+    let deposit_reserve_configs = if with_deposit_reserves {
+        let mut deposit_reserve_configs = Vec::with_capacity(obligation.deposits.len());
+        for (index, collateral) in obligation.deposits.iter().enumerate() {
+            let deposit_reserve_info = next_account_info(account_info_iter)?;
+            if deposit_reserve_info.owner != program_id {
+                msg!(
+                    "Deposit reserve provided for collateral {} is not owned by the lending program",
+                    index
+                );
+                return Err(LendingError::InvalidAccountOwner.into());
+            }
+            if collateral.deposit_reserve != *deposit_reserve_info.key {
+                msg!(
+                    "Deposit reserve of collateral {} does not match the deposit reserve provided",
+                    index
+                );
+                return Err(LendingError::InvalidAccountInput.into());
+            }
+            let deposit_reserve = Reserve::unpack(&deposit_reserve_info.data.borrow())?;
+            deposit_reserve_configs.push(deposit_reserve.config);
+        }
+        Some(deposit_reserve_configs)
+    } else {
+        None
+    };
+
+    // a new borrow changes the borrow values the obligation was refreshed with
+    // if it leaves the elevation group shared by the deposits, or if isolated
+    // collateral does not allow it and stops counting toward them
+    let is_new_borrow = !obligation
+        .borrows
+        .iter()
+        .any(|liquidity| liquidity.borrow_reserve == *borrow_reserve_info.key);
+    if is_new_borrow {
+        match deposit_reserve_configs {
+            Some(deposit_reserve_configs) => {
+                let deposits_elevation_group = Obligation::common_elevation_group(
+                    deposit_reserve_configs
+                        .iter()
+                        .map(|config| config.elevation_group),
+                );
+                let leaves_elevation_group = deposits_elevation_group != 0
+                    && borrow_reserve.config.elevation_group != deposits_elevation_group;
+                let excludes_isolated_collateral = deposit_reserve_configs
+                    .iter()
+                    .any(|config| !config.allows_borrow(borrow_reserve_info.key));
+                if leaves_elevation_group || excludes_isolated_collateral {
+                    // the elevation groups of other borrows are not known here, so
+                    // the elevated ratios are only kept for the obligation's first
+                    // borrow
+                    let elevation_group =
+                        if obligation.borrows.is_empty() && !leaves_elevation_group {
+                            deposits_elevation_group
+                        } else {
+                            0
+                        };
+                    obligation.find_or_add_liquidity_to_borrows(
+                        *borrow_reserve_info.key,
+                        Obligation::max_reserves(obligation_info.data_len()),
+                    )?;
+                    let (allowed_borrow_value, unhealthy_borrow_value) = obligation
+                        .calculate_borrow_values(&deposit_reserve_configs, elevation_group)?;
+                    if allowed_borrow_value <= obligation.borrowed_value {
+                        if excludes_isolated_collateral {
+                            msg!("Remaining borrow value without the isolated collateral that does not allow the borrow reserve is zero");
+                            return Err(LendingError::IsolatedCollateralBorrowNotAllowed.into());
+                        }
+                        msg!("Remaining borrow value outside of the elevation group is zero");
+                        return Err(LendingError::BorrowTooLarge.into());
+                    }
+                    obligation.allowed_borrow_value = allowed_borrow_value;
+                    obligation.unhealthy_borrow_value = unhealthy_borrow_value;
+                }
+            }
+            None => {
+                if obligation.has_isolated_collateral
+                    || (obligation.deposits_elevation_group != 0
+                        && borrow_reserve.config.elevation_group
+                            != obligation.deposits_elevation_group)
+                {
+                    msg!("Borrowing from a new reserve against isolated or elevation group collateral requires the obligation deposit reserves");
+                    return Err(LendingError::InvalidAccountInput.into());
+                }
+            }
+        }
+    }
+
     let authority_signer_seeds = &[
         lending_market_info.key.as_ref(),
         &[lending_market.bump_seed],
@@ -1159,15 +1590,27 @@ fn process_borrow_obligation_liquidity(
         return Err(LendingError::ExceededSlippage.into());
     }
 
+    let borrowed_amount = borrow_reserve
+        .liquidity
+        .borrowed_amount_wads
+        .try_add(borrow_amount)?;
+    if borrowed_amount > Decimal::from(borrow_reserve.config.borrow_limit) {
+        msg!("Borrow would exceed the reserve borrow limit");
+        return Err(LendingError::BorrowLimitExceeded.into());
+    }
+
     borrow_reserve.liquidity.borrow(borrow_amount)?;
     borrow_reserve.last_update.mark_stale();
     Reserve::pack(borrow_reserve, &mut borrow_reserve_info.data.borrow_mut())?;
 
     obligation
-        .find_or_add_liquidity_to_borrows(*borrow_reserve_info.key)?
+        .find_or_add_liquidity_to_borrows(
+            *borrow_reserve_info.key,
+            Obligation::max_reserves(obligation_info.data_len()),
+        )?
         .borrow(borrow_amount)?;
     obligation.last_update.mark_stale();
-    Obligation::pack(obligation, &mut obligation_info.data.borrow_mut())?;
+    Obligation::pack_resizable(obligation, &mut obligation_info.data.borrow_mut())?;
 
     let mut owner_fee = borrow_fee;
     if let Ok(host_fee_receiver_info) = next_account_info(account_info_iter) {
@@ -1262,7 +1705,7 @@ fn process_repay_obligation_liquidity(
         return Err(LendingError::ReserveStale.into());
     }
 
-    let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+    let mut obligation = Obligation::unpack_resizable(&obligation_info.data.borrow())?;
     if obligation_info.owner != program_id {
         msg!("Obligation provided is not owned by the lending program");
         return Err(LendingError::InvalidAccountOwner.into());
@@ -1299,7 +1742,7 @@ fn process_repay_obligation_liquidity(
 
     obligation.repay(settle_amount, liquidity_index)?;
     obligation.last_update.mark_stale();
-    Obligation::pack(obligation, &mut obligation_info.data.borrow_mut())?;
+    Obligation::pack_resizable(obligation, &mut obligation_info.data.borrow_mut())?;
 
     spl_token_transfer(TokenTransferParams {
         source: source_liquidity_info.clone(),
@@ -1337,6 +1780,9 @@ fn process_liquidate_obligation(
     let user_transfer_authority_info = next_account_info(account_info_iter)?;
     let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
     let token_program_id = next_account_info(account_info_iter)?;
+    let withdraw_reserve_collateral_mint_info = next_account_info(account_info_iter).ok();
+    let withdraw_reserve_liquidity_supply_info = next_account_info(account_info_iter).ok();
+    let withdraw_reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter).ok();
 
     let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
     if lending_market_info.owner != program_id {
@@ -1347,6 +1793,10 @@ fn process_liquidate_obligation(
         msg!("Lending market token program does not match the token program provided");
         return Err(LendingError::InvalidTokenProgram.into());
     }
+    if lending_market.paused {
+        msg!("Lending market is paused");
+        return Err(LendingError::LendingMarketPaused.into());
+    }
 
     let mut repay_reserve = Reserve::unpack(&repay_reserve_info.data.borrow())?;
     if repay_reserve_info.owner != program_id {
@@ -1357,6 +1807,10 @@ fn process_liquidate_obligation(
         msg!("Repay reserve lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
+    if repay_reserve.is_operation_disabled(ReserveOperation::Liquidate) {
+        msg!("Repay reserve has liquidations disabled");
+        return Err(LendingError::ReserveOperationDisabled.into());
+    }
     if &repay_reserve.liquidity.supply_pubkey != repay_reserve_liquidity_supply_info.key {
         msg!("Repay reserve liquidity supply does not match the repay reserve liquidity supply provided");
         return Err(LendingError::InvalidAccountInput.into());
@@ -1385,6 +1839,10 @@ fn process_liquidate_obligation(
         msg!("Withdraw reserve lending market does not match the lending market provided");
         return Err(LendingError::InvalidAccountInput.into());
     }
+    if withdraw_reserve.is_operation_disabled(ReserveOperation::Liquidate) {
+        msg!("Withdraw reserve has liquidations disabled");
+        return Err(LendingError::ReserveOperationDisabled.into());
+    }
     if &withdraw_reserve.collateral.supply_pubkey != withdraw_reserve_collateral_supply_info.key {
         msg!("Withdraw reserve collateral supply does not match the withdraw reserve collateral supply provided");
         return Err(LendingError::InvalidAccountInput.into());
@@ -1402,7 +1860,52 @@ fn process_liquidate_obligation(
         return Err(LendingError::ReserveStale.into());
     }
 
-    let mut obligation = Obligation::unpack(&obligation_info.data.borrow())?;
+    // the accounts used to redeem the protocol liquidation fee are only
+    // required when the withdraw reserve charges one
+    let protocol_fee_infos = if withdraw_reserve.config.protocol_liquidation_fee > 0 {
+        let (
+            withdraw_reserve_collateral_mint_info,
+            withdraw_reserve_liquidity_supply_info,
+            withdraw_reserve_liquidity_fee_receiver_info,
+        ) = match (
+            withdraw_reserve_collateral_mint_info,
+            withdraw_reserve_liquidity_supply_info,
+            withdraw_reserve_liquidity_fee_receiver_info,
+        ) {
+            (Some(collateral_mint_info), Some(liquidity_supply_info), Some(fee_receiver_info)) => (
+                collateral_mint_info,
+                liquidity_supply_info,
+                fee_receiver_info,
+            ),
+            _ => {
+                msg!("Withdraw reserve collateral mint, liquidity supply and liquidity fee receiver must be provided when the withdraw reserve has a protocol liquidation fee");
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+        };
+        if &withdraw_reserve.collateral.mint_pubkey != withdraw_reserve_collateral_mint_info.key {
+            msg!("Withdraw reserve collateral mint does not match the withdraw reserve collateral mint provided");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+        if &withdraw_reserve.liquidity.supply_pubkey != withdraw_reserve_liquidity_supply_info.key {
+            msg!("Withdraw reserve liquidity supply does not match the withdraw reserve liquidity supply provided");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+        if &withdraw_reserve.liquidity.fee_receiver
+            != withdraw_reserve_liquidity_fee_receiver_info.key
+        {
+            msg!("Withdraw reserve liquidity fee receiver does not match the withdraw reserve liquidity fee receiver provided");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+        Some((
+            withdraw_reserve_collateral_mint_info,
+            withdraw_reserve_liquidity_supply_info,
+            withdraw_reserve_liquidity_fee_receiver_info,
+        ))
+    } else {
+        None
+    };
+
+    let mut obligation = Obligation::unpack_resizable(&obligation_info.data.borrow())?;
     if obligation_info.owner != program_id {
         msg!("Obligation provided is not owned by the lending program");
         return Err(LendingError::InvalidAccountOwner.into());
@@ -1459,13 +1962,25 @@ fn process_liquidate_obligation(
         settle_amount,
         repay_amount,
         withdraw_amount,
+        protocol_fee_amount,
     } = withdraw_reserve.calculate_liquidation(
         liquidity_amount,
+        lending_market.liquidation_close_factor_percent(),
         &obligation,
         liquidity,
         collateral,
     )?;
 
+    if protocol_fee_amount > 0
+        && withdraw_reserve
+            .collateral_exchange_rate()?
+            .collateral_to_liquidity(protocol_fee_amount)?
+            > withdraw_reserve.liquidity.available_amount
+    {
+        msg!("Withdraw reserve liquidity is insufficient to pay the protocol liquidation fee");
+        return Err(LendingError::InsufficientLiquidity.into());
+    }
+
     if repay_amount == 0 {
         msg!("Liquidation is too small to transfer liquidity");
         return Err(LendingError::LiquidationTooSmall.into());
@@ -1482,7 +1997,7 @@ fn process_liquidate_obligation(
     obligation.repay(settle_amount, liquidity_index)?;
     obligation.withdraw(withdraw_amount, collateral_index)?;
     obligation.last_update.mark_stale();
-    Obligation::pack(obligation, &mut obligation_info.data.borrow_mut())?;
+    Obligation::pack_resizable(obligation, &mut obligation_info.data.borrow_mut())?;
 
     spl_token_transfer(TokenTransferParams {
         source: source_liquidity_info.clone(),
@@ -1496,7 +2011,73 @@ fn process_liquidate_obligation(
     spl_token_transfer(TokenTransferParams {
         source: withdraw_reserve_collateral_supply_info.clone(),
         destination: destination_collateral_info.clone(),
-        amount: withdraw_amount,
+        amount: withdraw_amount
+            .checked_sub(protocol_fee_amount)
+            .ok_or(LendingError::MathOverflow)?,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+
+    if let Some((
+        withdraw_reserve_collateral_mint_info,
+        withdraw_reserve_liquidity_supply_info,
+        withdraw_reserve_liquidity_fee_receiver_info,
+    )) = protocol_fee_infos.filter(|_| protocol_fee_amount > 0)
+    {
+        redeem_protocol_liquidation_fee(
+            protocol_fee_amount,
+            withdraw_reserve_info,
+            withdraw_reserve_collateral_supply_info,
+            withdraw_reserve_collateral_mint_info,
+            withdraw_reserve_liquidity_supply_info,
+            withdraw_reserve_liquidity_fee_receiver_info,
+            lending_market_authority_info,
+            authority_signer_seeds,
+            token_program_id,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Redeem the protocol share of liquidated collateral and send the liquidity
+/// to the withdraw reserve fee receiver
+#[inline(never)] // avoid stack frame limit
+#[allow(clippy::too_many_arguments)]
+fn redeem_protocol_liquidation_fee<'a>(
+    protocol_fee_amount: u64,
+    withdraw_reserve_info: &AccountInfo<'a>,
+    withdraw_reserve_collateral_supply_info: &AccountInfo<'a>,
+    withdraw_reserve_collateral_mint_info: &AccountInfo<'a>,
+    withdraw_reserve_liquidity_supply_info: &AccountInfo<'a>,
+    withdraw_reserve_liquidity_fee_receiver_info: &AccountInfo<'a>,
+    lending_market_authority_info: &AccountInfo<'a>,
+    authority_signer_seeds: &[&[u8]],
+    token_program_id: &AccountInfo<'a>,
+) -> ProgramResult {
+    // unpacked again since the repay reserve may be the same account
+    let mut withdraw_reserve = Reserve::unpack(&withdraw_reserve_info.data.borrow())?;
+    let fee_liquidity_amount = withdraw_reserve.redeem_collateral(protocol_fee_amount)?;
+    withdraw_reserve.last_update.mark_stale();
+    Reserve::pack(
+        withdraw_reserve,
+        &mut withdraw_reserve_info.data.borrow_mut(),
+    )?;
+
+    spl_token_burn(TokenBurnParams {
+        mint: withdraw_reserve_collateral_mint_info.clone(),
+        source: withdraw_reserve_collateral_supply_info.clone(),
+        amount: protocol_fee_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: withdraw_reserve_liquidity_supply_info.clone(),
+        destination: withdraw_reserve_liquidity_fee_receiver_info.clone(),
+        amount: fee_liquidity_amount,
         authority: lending_market_authority_info.clone(),
         authority_signer_seeds,
         token_program: token_program_id.clone(),
@@ -1540,6 +2121,10 @@ fn process_flash_loan(
         msg!("Lending market token program does not match the token program provided");
         return Err(LendingError::InvalidTokenProgram.into());
     }
+    if lending_market.paused {
+        msg!("Lending market is paused");
+        return Err(LendingError::LendingMarketPaused.into());
+    }
 
     let authority_signer_seeds = &[
         lending_market_info.key.as_ref(),
@@ -1563,6 +2148,10 @@ fn process_flash_loan(
         msg!("Invalid reserve lending market account");
         return Err(LendingError::InvalidAccountInput.into());
     }
+    if reserve.is_operation_disabled(ReserveOperation::FlashLoan) {
+        msg!("Reserve has flash loans disabled");
+        return Err(LendingError::ReserveOperationDisabled.into());
+    }
     if &reserve.liquidity.supply_pubkey != source_liquidity_info.key {
         msg!("Reserve liquidity supply must be used as the source liquidity provided");
         return Err(LendingError::InvalidAccountInput.into());
@@ -1733,6 +2322,55 @@ fn process_modify_reserve_config(
     Ok(())
 }
 
+fn process_set_fixed_price(
+    program_id: &Pubkey,
+    quote_currency: [u8; 32],
+    price: u64,
+    expo: i32,
+    confidence: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    check_fixed_price_oracle_enabled()?;
+
+    let account_info_iter = &mut accounts.iter();
+    let fixed_price_info = next_account_info(account_info_iter)?;
+    let fixed_price_authority_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if fixed_price_info.owner != program_id {
+        msg!("Fixed price account provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if !fixed_price_authority_info.is_signer {
+        msg!("Fixed price authority provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    let mut fixed_price = FixedPrice::unpack_unchecked(&fixed_price_info.data.borrow())?;
+    if fixed_price.is_initialized() {
+        if &fixed_price.authority != fixed_price_authority_info.key {
+            msg!("Fixed price authority does not match the fixed price authority provided");
+            return Err(LendingError::InvalidFixedPriceAuthority.into());
+        }
+        if fixed_price.quote_currency != quote_currency {
+            msg!("Fixed price quote currency cannot be changed");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+    } else {
+        assert_rent_exempt(rent, fixed_price_info)?;
+        fixed_price.init(InitFixedPriceParams {
+            authority: *fixed_price_authority_info.key,
+            quote_currency,
+        });
+    }
+
+    fixed_price.set_price(price, expo, confidence, clock.slot);
+    FixedPrice::pack(fixed_price, &mut fixed_price_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
 fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> ProgramResult {
     if !rent.is_exempt(account_info.lamports(), account_info.data_len()) {
         msg!(&rent.minimum_balance(account_info.data_len()).to_string());
@@ -1800,9 +2438,60 @@ fn get_pyth_product_quote_currency(pyth_product: &pyth::Product) -> Result<[u8;
     Err(LendingError::InvalidOracleConfig.into())
 }
 
-fn get_pyth_price(pyth_price_info: &AccountInfo, clock: &Clock) -> Result<Decimal, ProgramError> {
-    const STALE_AFTER_SLOTS_ELAPSED: u64 = 5;
+fn check_pyth_oracle(
+    lending_market: &LendingMarket,
+    pyth_product_info: &AccountInfo,
+    pyth_price_info: &AccountInfo,
+) -> ProgramResult {
+    if &lending_market.oracle_program_id != pyth_product_info.owner {
+        msg!("Pyth product account provided is not owned by the lending market oracle program");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+    if &lending_market.oracle_program_id != pyth_price_info.owner {
+        msg!("Pyth price account provided is not owned by the lending market oracle program");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    let pyth_product_data = pyth_product_info.try_borrow_data()?;
+    let pyth_product = pyth::load::<pyth::Product>(&pyth_product_data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if pyth_product.magic != pyth::MAGIC {
+        msg!("Pyth product account provided is not a valid Pyth account");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+    if pyth_product.ver != pyth::VERSION_2 {
+        msg!("Pyth product account provided has a different version than expected");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+    if pyth_product.atype != pyth::AccountType::Product as u32 {
+        msg!("Pyth product account provided is not a valid Pyth product account");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    let pyth_price_pubkey_bytes: &[u8; 32] = pyth_price_info
+        .key
+        .as_ref()
+        .try_into()
+        .map_err(|_| LendingError::InvalidAccountInput)?;
+    if &pyth_product.px_acc.val != pyth_price_pubkey_bytes {
+        msg!("Pyth product price account does not match the Pyth price provided");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
 
+    let quote_currency = get_pyth_product_quote_currency(pyth_product)?;
+    if lending_market.quote_currency != quote_currency {
+        msg!("Lending market quote currency does not match the oracle quote currency");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    Ok(())
+}
+
+fn get_pyth_price(
+    pyth_price_info: &AccountInfo,
+    clock: &Clock,
+    config: &ReserveConfig,
+) -> Result<(Decimal, Slot), ProgramError> {
     let pyth_price_data = pyth_price_info.try_borrow_data()?;
     let pyth_price = pyth::load::<pyth::Price>(&pyth_price_data)
         .map_err(|_| ProgramError::InvalidAccountData)?;
@@ -1817,32 +2506,106 @@ fn get_pyth_price(pyth_price_info: &AccountInfo, clock: &Clock) -> Result<Decima
         return Err(LendingError::InvalidOracleConfig.into());
     }
 
-    let slots_elapsed = clock
-        .slot
-        .checked_sub(pyth_price.valid_slot)
-        .ok_or(LendingError::MathOverflow)?;
-    if slots_elapsed >= STALE_AFTER_SLOTS_ELAPSED {
-        msg!("Oracle price is stale");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
     let price: u64 = pyth_price.agg.price.try_into().map_err(|_| {
         msg!("Oracle price cannot be negative");
         LendingError::InvalidOracleConfig
     })?;
 
-    let market_price = if pyth_price.expo >= 0 {
-        let exponent = pyth_price
-            .expo
-            .try_into()
-            .map_err(|_| LendingError::MathOverflow)?;
+    let market_price = validate_oracle_price(
+        price,
+        pyth_price.expo,
+        pyth_price.agg.conf,
+        pyth_price.valid_slot,
+        clock,
+        config,
+    )?;
+
+    Ok((market_price, pyth_price.valid_slot))
+}
+
+/// Fixed prices are trusted from a single key, so they are only available in
+/// builds of the program with the `fixed-price-oracle` feature
+fn check_fixed_price_oracle_enabled() -> ProgramResult {
+    if cfg!(feature = "fixed-price-oracle") {
+        Ok(())
+    } else {
+        msg!("Fixed price oracles are not enabled in this build of the lending program");
+        Err(LendingError::InvalidOracleConfig.into())
+    }
+}
+
+fn check_fixed_price_oracle(
+    program_id: &Pubkey,
+    lending_market: &LendingMarket,
+    fixed_price_info: &AccountInfo,
+) -> ProgramResult {
+    if fixed_price_info.owner != program_id {
+        msg!("Fixed price account provided is not owned by the lending program");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+    let fixed_price = FixedPrice::unpack(&fixed_price_info.data.borrow())?;
+    if lending_market.quote_currency != fixed_price.quote_currency {
+        msg!("Lending market quote currency does not match the oracle quote currency");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    Ok(())
+}
+
+fn get_fixed_price(
+    fixed_price_info: &AccountInfo,
+    clock: &Clock,
+    config: &ReserveConfig,
+) -> Result<(Decimal, Slot), ProgramError> {
+    let fixed_price = FixedPrice::unpack(&fixed_price_info.data.borrow())?;
+    let market_price = validate_oracle_price(
+        fixed_price.price,
+        fixed_price.expo,
+        fixed_price.confidence,
+        fixed_price.last_update_slot,
+        clock,
+        config,
+    )?;
+
+    Ok((market_price, fixed_price.last_update_slot))
+}
+
+/// Checks the age and confidence interval of an oracle price against the
+/// reserve config, and converts it to a decimal
+fn validate_oracle_price(
+    price: u64,
+    expo: i32,
+    confidence: u64,
+    publish_slot: Slot,
+    clock: &Clock,
+    config: &ReserveConfig,
+) -> Result<Decimal, ProgramError> {
+    if config.is_price_stale(publish_slot, clock.slot)? {
+        msg!("Oracle price is stale");
+        return Err(LendingError::StaleOraclePrice.into());
+    }
+
+    if config.max_confidence_bps > 0 {
+        let max_confidence = (price as u128)
+            .checked_mul(config.max_confidence_bps as u128)
+            .ok_or(LendingError::MathOverflow)?;
+        let confidence = (confidence as u128)
+            .checked_mul(10_000)
+            .ok_or(LendingError::MathOverflow)?;
+        if confidence > max_confidence {
+            msg!("Oracle price confidence interval is too wide");
+            return Err(LendingError::OraclePriceConfidenceTooWide.into());
+        }
+    }
+
+    let market_price = if expo >= 0 {
+        let exponent = expo.try_into().map_err(|_| LendingError::MathOverflow)?;
         let zeros = 10u64
             .checked_pow(exponent)
             .ok_or(LendingError::MathOverflow)?;
         Decimal::from(price).try_mul(zeros)?
     } else {
-        let exponent = pyth_price
-            .expo
+        let exponent = expo
             .checked_abs()
             .ok_or(LendingError::MathOverflow)?
             .try_into()
@@ -1856,6 +2619,122 @@ fn get_pyth_price(pyth_price_info: &AccountInfo, clock: &Clock) -> Result<Decima
     Ok(market_price)
 }
 
+/// Checks that the token-swap pool trades the reserve liquidity against the
+/// lending market quote currency, and records its last cumulative price
+fn init_token_swap_oracle(
+    lending_market: &LendingMarket,
+    liquidity_mint_pubkey: &Pubkey,
+    quote_mint_info: &AccountInfo,
+    swap_info: &AccountInfo,
+) -> Result<TokenSwapOracle, ProgramError> {
+    if swap_info.owner != &spl_token_swap::id() {
+        msg!("Token-swap pool provided is not owned by the token-swap program");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+    let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+    let price_oracle = token_swap.price_oracle().ok_or_else(|| {
+        msg!("Token-swap pool provided does not have a price oracle");
+        LendingError::InvalidOracleConfig
+    })?;
+    // the pool only accumulates the spot price of the constant product curve
+    if token_swap.swap_curve().curve_type != CurveType::ConstantProduct {
+        msg!("Token-swap pool provided does not use the constant product curve");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    let (quote_mint_pubkey, price_cumulative) =
+        if token_swap.token_a_mint() == liquidity_mint_pubkey {
+            (token_swap.token_b_mint(), price_oracle.price_a_cumulative)
+        } else if token_swap.token_b_mint() == liquidity_mint_pubkey {
+            (token_swap.token_a_mint(), price_oracle.price_b_cumulative)
+        } else {
+            msg!("Token-swap pool provided does not trade the reserve liquidity mint");
+            return Err(LendingError::InvalidOracleConfig.into());
+        };
+    if quote_mint_pubkey != quote_mint_info.key {
+        msg!("Token-swap pool quote mint does not match the quote mint provided");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+    if lending_market.quote_currency != quote_mint_pubkey.to_bytes() {
+        msg!("Lending market quote currency does not match the oracle quote currency");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    // the pool may hold token-2022 mints, only read the base mint layout
+    let quote_mint = quote_mint_info
+        .data
+        .borrow()
+        .get(..Mint::LEN)
+        .ok_or(LendingError::InvalidTokenMint)
+        .and_then(unpack_mint)?;
+
+    Ok(TokenSwapOracle {
+        quote_mint_decimals: quote_mint.decimals,
+        price_cumulative,
+        timestamp: price_oracle.last_update_timestamp,
+    })
+}
+
+/// Sets the market price to the time-weighted average price of the token-swap
+/// pool between the last observation and the last pool update, once it spans
+/// the configured window
+fn refresh_token_swap_oracle(
+    reserve: &mut Reserve,
+    swap_info: &AccountInfo,
+    clock: &Clock,
+) -> ProgramResult {
+    let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+    let price_oracle = token_swap.price_oracle().ok_or_else(|| {
+        msg!("Token-swap pool provided does not have a price oracle");
+        LendingError::InvalidOracleConfig
+    })?;
+
+    // only the prices accumulated by the pool itself are used, the balances
+    // of its token accounts can be moved by donations
+    let price_cumulative = if token_swap.token_a_mint() == &reserve.liquidity.mint_pubkey {
+        price_oracle.price_a_cumulative
+    } else {
+        price_oracle.price_b_cumulative
+    };
+
+    let window = reserve.config.twap_window_seconds();
+    let liquidity = &mut reserve.liquidity;
+    let elapsed = price_oracle
+        .last_update_timestamp
+        .saturating_sub(liquidity.token_swap_oracle.timestamp);
+    // a shorter average is cheap to move with a trade right before the refresh
+    if u64::try_from(elapsed).unwrap_or(0) >= window {
+        // cumulative prices wrap around, only their difference is meaningful
+        let price = price_cumulative.wrapping_sub(liquidity.token_swap_oracle.price_cumulative)
+            / elapsed as u128;
+        let decimals = 10u64
+            .checked_pow(liquidity.mint_decimals as u32)
+            .ok_or(LendingError::MathOverflow)?;
+        let quote_decimals = 10u64
+            .checked_pow(liquidity.token_swap_oracle.quote_mint_decimals as u32)
+            .ok_or(LendingError::MathOverflow)?;
+        liquidity.market_price = Decimal::from(price)
+            .try_div(Decimal::from(1u128 << PRICE_FRACTIONAL_BITS))?
+            .try_mul(decimals)?
+            .try_div(quote_decimals)?;
+        liquidity.market_price_slot = price_oracle.last_update_slot;
+        liquidity.token_swap_oracle.price_cumulative = price_cumulative;
+        liquidity.token_swap_oracle.timestamp = price_oracle.last_update_timestamp;
+    } else if liquidity.market_price == Decimal::zero() {
+        msg!("Token-swap oracle has not averaged a price over its window yet");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+    if reserve
+        .config
+        .is_price_stale(reserve.liquidity.market_price_slot, clock.slot)?
+    {
+        msg!("Token-swap oracle price is stale");
+        return Err(LendingError::StaleOraclePrice.into());
+    }
+
+    Ok(())
+}
+
 /// Issue a spl_token `InitializeAccount` instruction.
 #[inline(always)]
 fn spl_token_init_account(params: TokenInitializeAccountParams<'_>) -> ProgramResult {
@@ -0,0 +1,456 @@
+//! Off-chain simulation of obligation health and liquidations
+//!
+//! Projects fetched accounts to a target slot with the same math the program
+//! uses, so that liquidators and dashboards do not need to re-implement it.
+
+use {
+    crate::{
+        error::LendingError,
+        math::{Decimal, TryAdd, TrySub},
+        state::{
+            CalculateLiquidationResult, LendingMarket, Obligation, ObligationCollateral,
+            ObligationLiquidity, Reserve, ReserveOperation,
+        },
+    },
+    solana_program::{clock::Slot, msg, program_error::ProgramError, pubkey::Pubkey},
+    std::collections::HashMap,
+};
+
+/// Obligation projected to a target slot
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulatedObligation {
+    /// Obligation refreshed against the projected reserves
+    pub obligation: Obligation,
+    /// Ratio of unhealthy borrow value to borrowed value, None if nothing is
+    /// borrowed
+    pub health_factor: Option<Decimal>,
+    /// Most profitable liquidation, None if the obligation cannot be
+    /// liquidated
+    pub best_liquidation: Option<SimulatedLiquidation>,
+}
+
+/// Liquidation of an obligation for the full close factor
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulatedLiquidation {
+    /// Reserve the borrowed liquidity is repaid to
+    pub repay_reserve: Pubkey,
+    /// Reserve the deposited collateral is withdrawn from
+    pub withdraw_reserve: Pubkey,
+    /// Amount of liquidity the liquidator repays
+    pub repay_amount: u64,
+    /// Amount of liquidity settled from the obligation
+    pub settle_amount: Decimal,
+    /// Amount of collateral withdrawn from the obligation
+    pub withdraw_amount: u64,
+    /// Amount of the withdrawn collateral owed to the protocol
+    pub protocol_fee_amount: u64,
+    /// Market value of the repaid liquidity
+    pub repay_value: Decimal,
+    /// Market value of the collateral received by the liquidator
+    pub receive_value: Decimal,
+    /// Received value in excess of the repaid value
+    pub profit_value: Decimal,
+}
+
+/// Project an obligation and the reserves it uses to `slot` and find its most
+/// profitable liquidation.
+///
+/// `reserves` must contain every deposit and borrow reserve of the obligation,
+/// keyed by reserve address. `market_prices` are keyed by liquidity mint, in
+/// the same quote currency as the reserves, and reserves without a price keep
+/// their last refreshed one.
+pub fn simulate_obligation(
+    lending_market: &LendingMarket,
+    reserves: &HashMap<Pubkey, Reserve>,
+    obligation: &Obligation,
+    market_prices: &HashMap<Pubkey, Decimal>,
+    slot: Slot,
+) -> Result<SimulatedObligation, ProgramError> {
+    let mut projected_reserves = HashMap::new();
+    let reserve_pubkeys = obligation
+        .deposits
+        .iter()
+        .map(|collateral| collateral.deposit_reserve)
+        .chain(
+            obligation
+                .borrows
+                .iter()
+                .map(|liquidity| liquidity.borrow_reserve),
+        );
+    for reserve_pubkey in reserve_pubkeys {
+        let reserve = get_reserve(reserves, &reserve_pubkey)?;
+        let market_price = market_prices.get(&reserve.liquidity.mint_pubkey).copied();
+        projected_reserves.insert(
+            reserve_pubkey,
+            project_reserve(reserve, market_price, slot)?,
+        );
+    }
+
+    let obligation = refresh_obligation(obligation, &projected_reserves, slot)?;
+    let health_factor = obligation.health_factor()?;
+    let best_liquidation = simulate_liquidations(lending_market, &projected_reserves, &obligation)?
+        .into_iter()
+        .max_by(|a, b| a.profit_value.cmp(&b.profit_value));
+
+    Ok(SimulatedObligation {
+        obligation,
+        health_factor,
+        best_liquidation,
+    })
+}
+
+/// Project a reserve to `slot`, as if refreshed with `market_price`
+pub fn project_reserve(
+    reserve: &Reserve,
+    market_price: Option<Decimal>,
+    slot: Slot,
+) -> Result<Reserve, ProgramError> {
+    let mut reserve = reserve.clone();
+    if let Some(market_price) = market_price {
+        reserve.liquidity.market_price = market_price;
+        reserve.liquidity.market_price_slot = slot;
+    }
+    reserve.accrue_interest(slot)?;
+    reserve.last_update.update_slot(slot);
+    Ok(reserve)
+}
+
+/// Refresh an obligation against reserves already projected to `slot`
+pub fn refresh_obligation(
+    obligation: &Obligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+    slot: Slot,
+) -> Result<Obligation, ProgramError> {
+    let mut obligation = obligation.clone();
+    let mut deposited_value = Decimal::zero();
+    let mut borrowed_value = Decimal::zero();
+    let mut deposit_reserve_configs = Vec::with_capacity(obligation.deposits.len());
+    let mut elevation_groups =
+        Vec::with_capacity(obligation.deposits.len() + obligation.borrows.len());
+
+    for collateral in obligation.deposits.iter_mut() {
+        let deposit_reserve = get_reserve(reserves, &collateral.deposit_reserve)?;
+        let market_value = deposit_reserve.collateral_market_value(collateral.deposited_amount)?;
+        collateral.market_value = market_value;
+
+        deposited_value = deposited_value.try_add(market_value)?;
+        elevation_groups.push(deposit_reserve.config.elevation_group);
+        deposit_reserve_configs.push(deposit_reserve.config);
+    }
+
+    for liquidity in obligation.borrows.iter_mut() {
+        let borrow_reserve = get_reserve(reserves, &liquidity.borrow_reserve)?;
+        liquidity.accrue_interest(borrow_reserve.liquidity.cumulative_borrow_rate_wads)?;
+
+        let market_value = borrow_reserve.market_value(liquidity.borrowed_amount_wads)?;
+        liquidity.market_value = market_value;
+
+        borrowed_value = borrowed_value.try_add(market_value)?;
+        elevation_groups.push(borrow_reserve.config.elevation_group);
+    }
+
+    let elevation_group = Obligation::common_elevation_group(elevation_groups);
+    let (allowed_borrow_value, unhealthy_borrow_value) =
+        obligation.calculate_borrow_values(&deposit_reserve_configs, elevation_group)?;
+
+    obligation.deposited_value = deposited_value;
+    obligation.borrowed_value = borrowed_value;
+    obligation.allowed_borrow_value = allowed_borrow_value;
+    obligation.unhealthy_borrow_value = unhealthy_borrow_value;
+    obligation.last_update.update_slot(slot);
+
+    Ok(obligation)
+}
+
+/// Simulate every liquidation the program would accept for a refreshed
+/// obligation, one per pair of borrow and deposit
+pub fn simulate_liquidations(
+    lending_market: &LendingMarket,
+    reserves: &HashMap<Pubkey, Reserve>,
+    obligation: &Obligation,
+) -> Result<Vec<SimulatedLiquidation>, ProgramError> {
+    let mut liquidations = vec![];
+    if lending_market.paused
+        || obligation.deposited_value == Decimal::zero()
+        || obligation.borrowed_value == Decimal::zero()
+        || obligation.borrowed_value < obligation.unhealthy_borrow_value
+    {
+        return Ok(liquidations);
+    }
+
+    for liquidity in &obligation.borrows {
+        let repay_reserve = get_reserve(reserves, &liquidity.borrow_reserve)?;
+        for collateral in &obligation.deposits {
+            let withdraw_reserve = get_reserve(reserves, &collateral.deposit_reserve)?;
+            if let Some(liquidation) = simulate_liquidation(
+                lending_market,
+                obligation,
+                repay_reserve,
+                liquidity,
+                withdraw_reserve,
+                collateral,
+            )? {
+                liquidations.push(liquidation);
+            }
+        }
+    }
+    Ok(liquidations)
+}
+
+fn simulate_liquidation(
+    lending_market: &LendingMarket,
+    obligation: &Obligation,
+    repay_reserve: &Reserve,
+    liquidity: &ObligationLiquidity,
+    withdraw_reserve: &Reserve,
+    collateral: &ObligationCollateral,
+) -> Result<Option<SimulatedLiquidation>, ProgramError> {
+    if repay_reserve.is_operation_disabled(ReserveOperation::Liquidate)
+        || withdraw_reserve.is_operation_disabled(ReserveOperation::Liquidate)
+        || liquidity.market_value == Decimal::zero()
+        || collateral.market_value == Decimal::zero()
+    {
+        return Ok(None);
+    }
+
+    let CalculateLiquidationResult {
+        settle_amount,
+        repay_amount,
+        withdraw_amount,
+        mut protocol_fee_amount,
+    } = withdraw_reserve.calculate_liquidation(
+        u64::MAX,
+        lending_market.liquidation_close_factor_percent(),
+        obligation,
+        liquidity,
+        collateral,
+    )?;
+
+    // mirrors the program, which waives the fee rather than fail
+    if protocol_fee_amount > 0
+        && withdraw_reserve
+            .collateral_exchange_rate()?
+            .collateral_to_liquidity(protocol_fee_amount)?
+            > withdraw_reserve.liquidity.available_amount
+    {
+        protocol_fee_amount = 0;
+    }
+
+    if repay_amount == 0 || withdraw_amount == 0 {
+        return Ok(None);
+    }
+
+    let receive_amount = withdraw_amount
+        .checked_sub(protocol_fee_amount)
+        .ok_or(LendingError::MathOverflow)?;
+    let repay_value = repay_reserve.market_value(repay_amount.into())?;
+    let receive_value = withdraw_reserve.collateral_market_value(receive_amount)?;
+    let profit_value = if receive_value > repay_value {
+        receive_value.try_sub(repay_value)?
+    } else {
+        Decimal::zero()
+    };
+
+    Ok(Some(SimulatedLiquidation {
+        repay_reserve: liquidity.borrow_reserve,
+        withdraw_reserve: collateral.deposit_reserve,
+        repay_amount,
+        settle_amount,
+        withdraw_amount,
+        protocol_fee_amount,
+        repay_value,
+        receive_value,
+        profit_value,
+    }))
+}
+
+fn get_reserve<'a>(
+    reserves: &'a HashMap<Pubkey, Reserve>,
+    reserve_pubkey: &Pubkey,
+) -> Result<&'a Reserve, ProgramError> {
+    reserves.get(reserve_pubkey).ok_or_else(|| {
+        msg!("Reserve {} was not provided", reserve_pubkey);
+        LendingError::InvalidAccountInput.into()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            math::TryDiv,
+            state::{
+                LastUpdate, ReserveCollateral, ReserveConfig, ReserveLiquidity, PROGRAM_VERSION,
+            },
+        },
+    };
+
+    const USDC_DECIMALS: u8 = 6;
+    const SOL_DECIMALS: u8 = 9;
+
+    fn test_reserve(mint_decimals: u8, market_price: u64, liquidation_bonus: u8) -> Reserve {
+        Reserve {
+            version: PROGRAM_VERSION,
+            last_update: LastUpdate::new(0),
+            liquidity: ReserveLiquidity {
+                mint_pubkey: Pubkey::new_unique(),
+                mint_decimals,
+                available_amount: 1_000_000 * 10u64.pow(mint_decimals as u32),
+                cumulative_borrow_rate_wads: Decimal::one(),
+                market_price: Decimal::from(market_price),
+                ..ReserveLiquidity::default()
+            },
+            collateral: ReserveCollateral::default(),
+            config: ReserveConfig {
+                optimal_utilization_rate: 80,
+                loan_to_value_ratio: 50,
+                liquidation_bonus,
+                liquidation_threshold: 60,
+                min_borrow_rate: 0,
+                optimal_borrow_rate: 4,
+                max_borrow_rate: 30,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        }
+    }
+
+    // Obligation with 100 SOL deposited and 5,000 USDC borrowed
+    fn test_accounts() -> (HashMap<Pubkey, Reserve>, Obligation, Pubkey, Pubkey) {
+        let sol_reserve_pubkey = Pubkey::new_unique();
+        let usdc_reserve_pubkey = Pubkey::new_unique();
+        let sol_reserve = test_reserve(SOL_DECIMALS, 100, 5);
+        let mut usdc_reserve = test_reserve(USDC_DECIMALS, 1, 5);
+        usdc_reserve.liquidity.borrowed_amount_wads = Decimal::from(5_000_000_000u64);
+
+        let mut obligation = Obligation {
+            version: PROGRAM_VERSION,
+            ..Obligation::default()
+        };
+        obligation
+            .find_or_add_collateral_to_deposits(sol_reserve_pubkey, 2)
+            .unwrap()
+            .deposit(100_000_000_000)
+            .unwrap();
+        obligation
+            .find_or_add_liquidity_to_borrows(usdc_reserve_pubkey, 2)
+            .unwrap()
+            .borrow(Decimal::from(5_000_000_000u64))
+            .unwrap();
+
+        let reserves = HashMap::from([
+            (sol_reserve_pubkey, sol_reserve),
+            (usdc_reserve_pubkey, usdc_reserve),
+        ]);
+        (
+            reserves,
+            obligation,
+            sol_reserve_pubkey,
+            usdc_reserve_pubkey,
+        )
+    }
+
+    #[test]
+    fn simulate_healthy_obligation() {
+        let (reserves, obligation, _, _) = test_accounts();
+        let simulation = simulate_obligation(
+            &LendingMarket::default(),
+            &reserves,
+            &obligation,
+            &HashMap::new(),
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(
+            simulation.obligation.deposited_value,
+            Decimal::from(10_000u64)
+        );
+        assert_eq!(
+            simulation.obligation.borrowed_value,
+            Decimal::from(5_000u64)
+        );
+        assert_eq!(
+            simulation.health_factor,
+            Some(Decimal::from(6u64).try_div(5u64).unwrap())
+        );
+        assert_eq!(simulation.best_liquidation, None);
+    }
+
+    #[test]
+    fn simulate_unhealthy_obligation() {
+        let (reserves, obligation, sol_reserve_pubkey, usdc_reserve_pubkey) = test_accounts();
+        let sol_mint = reserves[&sol_reserve_pubkey].liquidity.mint_pubkey;
+        let market_prices = HashMap::from([(sol_mint, Decimal::from(80u64))]);
+        let lending_market = LendingMarket::default();
+
+        let simulation =
+            simulate_obligation(&lending_market, &reserves, &obligation, &market_prices, 0)
+                .unwrap();
+        assert!(simulation.health_factor.unwrap() < Decimal::one());
+
+        let liquidation = simulation.best_liquidation.unwrap();
+        assert_eq!(liquidation.repay_reserve, usdc_reserve_pubkey);
+        assert_eq!(liquidation.withdraw_reserve, sol_reserve_pubkey);
+        // half of the 5,000 USDC borrowed is repaid for 5% more in SOL
+        assert_eq!(liquidation.repay_amount, 2_500_000_000);
+        assert_eq!(liquidation.withdraw_amount, 32_812_500_000);
+        assert_eq!(liquidation.repay_value, Decimal::from(2_500u64));
+        assert_eq!(liquidation.receive_value, Decimal::from(2_625u64));
+        assert_eq!(liquidation.profit_value, Decimal::from(125u64));
+
+        let paused_lending_market = LendingMarket {
+            paused: true,
+            ..LendingMarket::default()
+        };
+        let simulation = simulate_obligation(
+            &paused_lending_market,
+            &reserves,
+            &obligation,
+            &market_prices,
+            0,
+        )
+        .unwrap();
+        assert_eq!(simulation.best_liquidation, None);
+    }
+
+    #[test]
+    fn simulate_accrued_interest() {
+        let (reserves, obligation, _, usdc_reserve_pubkey) = test_accounts();
+        let slot = 1_000_000;
+        let simulation = simulate_obligation(
+            &LendingMarket::default(),
+            &reserves,
+            &obligation,
+            &HashMap::new(),
+            slot,
+        )
+        .unwrap();
+
+        let usdc_reserve = project_reserve(&reserves[&usdc_reserve_pubkey], None, slot).unwrap();
+        assert!(usdc_reserve.liquidity.cumulative_borrow_rate_wads > Decimal::one());
+        assert_eq!(
+            simulation.obligation.borrows[0].cumulative_borrow_rate_wads,
+            usdc_reserve.liquidity.cumulative_borrow_rate_wads
+        );
+        assert!(simulation.obligation.borrowed_value > Decimal::from(5_000u64));
+        assert_eq!(simulation.obligation.last_update.slot, slot);
+    }
+
+    #[test]
+    fn simulate_missing_reserve() {
+        let (mut reserves, obligation, sol_reserve_pubkey, _) = test_accounts();
+        reserves.remove(&sol_reserve_pubkey);
+        assert_eq!(
+            simulate_obligation(
+                &LendingMarket::default(),
+                &reserves,
+                &obligation,
+                &HashMap::new(),
+                0,
+            ),
+            Err(LendingError::InvalidAccountInput.into())
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use {
+    super::*,
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    solana_program::{
+        clock::Slot,
+        msg,
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+        pubkey::{Pubkey, PUBKEY_BYTES},
+    },
+};
+
+/// Price set by an authority, used as a reserve liquidity oracle in local
+/// testing
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FixedPrice {
+    /// Version of the struct
+    pub version: u8,
+    /// Authority which can update the price
+    pub authority: Pubkey,
+    /// Currency the price is quoted in, must match the lending market quote
+    /// currency
+    pub quote_currency: [u8; 32],
+    /// Price, to be multiplied by 10^expo
+    pub price: u64,
+    /// Price exponent
+    pub expo: i32,
+    /// Confidence interval around the price, in the same units
+    pub confidence: u64,
+    /// Slot when the price was last set
+    pub last_update_slot: Slot,
+}
+
+impl FixedPrice {
+    /// Create a new fixed price
+    pub fn new(params: InitFixedPriceParams) -> Self {
+        let mut fixed_price = Self::default();
+        Self::init(&mut fixed_price, params);
+        fixed_price
+    }
+
+    /// Initialize a fixed price
+    pub fn init(&mut self, params: InitFixedPriceParams) {
+        self.version = PROGRAM_VERSION;
+        self.authority = params.authority;
+        self.quote_currency = params.quote_currency;
+    }
+
+    /// Set the price at the given slot
+    pub fn set_price(&mut self, price: u64, expo: i32, confidence: u64, slot: Slot) {
+        self.price = price;
+        self.expo = expo;
+        self.confidence = confidence;
+        self.last_update_slot = slot;
+    }
+}
+
+/// Initialize a fixed price
+pub struct InitFixedPriceParams {
+    /// Authority which can update the price
+    pub authority: Pubkey,
+    /// Currency the price is quoted in
+    pub quote_currency: [u8; 32],
+}
+
+impl Sealed for FixedPrice {}
+impl IsInitialized for FixedPrice {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const FIXED_PRICE_LEN: usize = 157; // 1 + 32 + 32 + 8 + 4 + 8 + 8 + 64
+impl Pack for FixedPrice {
+    const LEN: usize = FIXED_PRICE_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, FIXED_PRICE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            authority,
+            quote_currency,
+            price,
+            expo,
+            confidence,
+            last_update_slot,
+            _padding,
+        ) = mut_array_refs![output, 1, PUBKEY_BYTES, 32, 8, 4, 8, 8, 64];
+
+        *version = self.version.to_le_bytes();
+        authority.copy_from_slice(self.authority.as_ref());
+        quote_currency.copy_from_slice(self.quote_currency.as_ref());
+        *price = self.price.to_le_bytes();
+        *expo = self.expo.to_le_bytes();
+        *confidence = self.confidence.to_le_bytes();
+        *last_update_slot = self.last_update_slot.to_le_bytes();
+    }
+
+    /// Unpacks a byte buffer into a [FixedPrice](struct.FixedPrice.html)
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, FIXED_PRICE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            authority,
+            quote_currency,
+            price,
+            expo,
+            confidence,
+            last_update_slot,
+            _padding,
+        ) = array_refs![input, 1, PUBKEY_BYTES, 32, 8, 4, 8, 8, 64];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Fixed price version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            version,
+            authority: Pubkey::new_from_array(*authority),
+            quote_currency: *quote_currency,
+            price: u64::from_le_bytes(*price),
+            expo: i32::from_le_bytes(*expo),
+            confidence: u64::from_le_bytes(*confidence),
+            last_update_slot: u64::from_le_bytes(*last_update_slot),
+        })
+    }
+}
@@ -27,6 +27,16 @@ pub struct LendingMarket {
     pub token_program_id: Pubkey,
     /// Oracle (Pyth) program id
     pub oracle_program_id: Pubkey,
+    /// Percentage of an obligation's borrowed value that can be repaid during
+    /// each liquidation call
+    /// 0 to use the default of 50%
+    pub liquidation_close_factor: u8,
+    /// Whether the lending market is paused, halting every operation except
+    /// refreshes and repayments
+    pub paused: bool,
+    /// Authority which can pause the lending market and disable reserve
+    /// operations alongside the owner, default if unset
+    pub risk_council: Pubkey,
 }
 
 impl LendingMarket {
@@ -46,6 +56,24 @@ impl LendingMarket {
         self.token_program_id = params.token_program_id;
         self.oracle_program_id = params.oracle_program_id;
     }
+
+    /// Percentage of an obligation's borrowed value that can be repaid during
+    /// each liquidation call, falling back to the default for lending markets
+    /// configured without one
+    pub fn liquidation_close_factor_percent(&self) -> u8 {
+        if self.liquidation_close_factor == 0 {
+            LIQUIDATION_CLOSE_FACTOR
+        } else {
+            self.liquidation_close_factor
+        }
+    }
+
+    /// Check whether an authority can pause the lending market and disable
+    /// reserve operations
+    pub fn is_pause_authority(&self, authority: &Pubkey) -> bool {
+        self.owner == *authority
+            || (self.risk_council != Pubkey::default() && self.risk_council == *authority)
+    }
 }
 
 /// Initialize a lending market
@@ -72,7 +100,7 @@ impl IsInitialized for LendingMarket {
     }
 }
 
-const LENDING_MARKET_LEN: usize = 258; // 1 + 1 + 32 + 32 + 32 + 32 + 128
+const LENDING_MARKET_LEN: usize = 258; // 1 + 1 + 32 + 32 + 32 + 32 + 1 + 1 + 32 + 94
 impl Pack for LendingMarket {
     const LEN: usize = LENDING_MARKET_LEN;
 
@@ -86,6 +114,9 @@ impl Pack for LendingMarket {
             quote_currency,
             token_program_id,
             oracle_program_id,
+            liquidation_close_factor,
+            paused,
+            risk_council,
             _padding,
         ) = mut_array_refs![
             output,
@@ -95,7 +126,10 @@ impl Pack for LendingMarket {
             32,
             PUBKEY_BYTES,
             PUBKEY_BYTES,
-            128
+            1,
+            1,
+            PUBKEY_BYTES,
+            94
         ];
 
         *version = self.version.to_le_bytes();
@@ -104,6 +138,9 @@ impl Pack for LendingMarket {
         quote_currency.copy_from_slice(self.quote_currency.as_ref());
         token_program_id.copy_from_slice(self.token_program_id.as_ref());
         oracle_program_id.copy_from_slice(self.oracle_program_id.as_ref());
+        *liquidation_close_factor = self.liquidation_close_factor.to_le_bytes();
+        pack_bool(self.paused, paused);
+        risk_council.copy_from_slice(self.risk_council.as_ref());
     }
 
     /// Unpacks a byte buffer into a
@@ -118,6 +155,9 @@ impl Pack for LendingMarket {
             quote_currency,
             token_program_id,
             oracle_program_id,
+            liquidation_close_factor,
+            paused,
+            risk_council,
             _padding,
         ) = array_refs![
             input,
@@ -127,7 +167,10 @@ impl Pack for LendingMarket {
             32,
             PUBKEY_BYTES,
             PUBKEY_BYTES,
-            128
+            1,
+            1,
+            PUBKEY_BYTES,
+            94
         ];
 
         let version = u8::from_le_bytes(*version);
@@ -143,6 +186,9 @@ impl Pack for LendingMarket {
             quote_currency: *quote_currency,
             token_program_id: Pubkey::new_from_array(*token_program_id),
             oracle_program_id: Pubkey::new_from_array(*oracle_program_id),
+            liquidation_close_factor: u8::from_le_bytes(*liquidation_close_factor),
+            paused: unpack_bool(paused)?,
+            risk_council: Pubkey::new_from_array(*risk_council),
         })
     }
 }
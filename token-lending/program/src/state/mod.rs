@@ -1,5 +1,6 @@
 //! State types
 
+mod fixed_price;
 mod last_update;
 mod lending_market;
 mod obligation;
@@ -13,7 +14,7 @@ use {
         program_error::ProgramError,
     },
 };
-pub use {last_update::*, lending_market::*, obligation::*, reserve::*};
+pub use {fixed_price::*, last_update::*, lending_market::*, obligation::*, reserve::*};
 
 /// Collateral tokens are initially valued at a ratio of 5:1
 /// (collateral:liquidity)
@@ -22,7 +23,7 @@ pub const INITIAL_COLLATERAL_RATIO: u64 = 1;
 const INITIAL_COLLATERAL_RATE: u64 = INITIAL_COLLATERAL_RATIO * WAD;
 
 /// Current version of the program and all new accounts created
-pub const PROGRAM_VERSION: u8 = 1;
+pub const PROGRAM_VERSION: u8 = 3;
 
 /// Accounts are created with data zeroed out, so uninitialized state instances
 /// will have the version set to 0.
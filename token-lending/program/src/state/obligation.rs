@@ -20,9 +20,13 @@ use {
 };
 
 /// Max number of collateral and liquidity reserve accounts combined for an
-/// obligation
+/// obligation account of the default size
 pub const MAX_OBLIGATION_RESERVES: usize = 10;
 
+/// Max number of collateral and liquidity reserve accounts combined for a
+/// resized obligation account
+pub const MAX_RESIZED_OBLIGATION_RESERVES: usize = 32;
+
 /// Lending market obligation state
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Obligation {
@@ -47,6 +51,11 @@ pub struct Obligation {
     pub allowed_borrow_value: Decimal,
     /// The dangerous borrow value at the weighted average liquidation threshold
     pub unhealthy_borrow_value: Decimal,
+    /// Elevation group shared by all deposit reserves at the last refresh, 0
+    /// if they are not all in the same group
+    pub deposits_elevation_group: u8,
+    /// Whether any deposit reserve was isolated collateral at the last refresh
+    pub has_isolated_collateral: bool,
 }
 
 impl Obligation {
@@ -72,6 +81,18 @@ impl Obligation {
         self.borrowed_value.try_div(self.deposited_value)
     }
 
+    /// Calculate the ratio of unhealthy borrow value to borrowed value, the
+    /// obligation can be liquidated once it is at or below one. None if the
+    /// obligation has no borrowed value.
+    pub fn health_factor(&self) -> Result<Option<Decimal>, ProgramError> {
+        if self.borrowed_value == Decimal::zero() {
+            return Ok(None);
+        }
+        self.unhealthy_borrow_value
+            .try_div(self.borrowed_value)
+            .map(Some)
+    }
+
     /// Repay liquidity and remove it from borrows if zeroed out
     pub fn repay(&mut self, settle_amount: Decimal, liquidity_index: usize) -> ProgramResult {
         let liquidity = &mut self.borrows[liquidity_index];
@@ -115,14 +136,67 @@ impl Obligation {
         self.allowed_borrow_value.try_sub(self.borrowed_value)
     }
 
-    /// Calculate the maximum liquidation amount for a given liquidity
+    /// Find the elevation group shared by all deposit and borrow reserves,
+    /// 0 if they are not all in the same group
+    pub fn common_elevation_group<I: IntoIterator<Item = u8>>(elevation_groups: I) -> u8 {
+        let mut elevation_groups = elevation_groups.into_iter();
+        let first = elevation_groups.next().unwrap_or(0);
+        if elevation_groups.all(|elevation_group| elevation_group == first) {
+            first
+        } else {
+            0
+        }
+    }
+
+    /// Calculate the allowed and unhealthy borrow values of the deposits from
+    /// their market values and the configs of their reserves, in deposit
+    /// order. Isolated collateral only counts if it allows every borrow.
+    pub fn calculate_borrow_values(
+        &self,
+        deposit_reserve_configs: &[ReserveConfig],
+        elevation_group: u8,
+    ) -> Result<(Decimal, Decimal), ProgramError> {
+        if deposit_reserve_configs.len() != self.deposits.len() {
+            msg!("Deposit reserve configs do not match the obligation deposits");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+
+        let mut allowed_borrow_value = Decimal::zero();
+        let mut unhealthy_borrow_value = Decimal::zero();
+        for (collateral, config) in self.deposits.iter().zip(deposit_reserve_configs) {
+            if !self
+                .borrows
+                .iter()
+                .all(|liquidity| config.allows_borrow(&liquidity.borrow_reserve))
+            {
+                continue;
+            }
+
+            allowed_borrow_value = allowed_borrow_value.try_add(
+                collateral
+                    .market_value
+                    .try_mul(config.loan_to_value_rate(elevation_group))?,
+            )?;
+            unhealthy_borrow_value = unhealthy_borrow_value.try_add(
+                collateral
+                    .market_value
+                    .try_mul(config.liquidation_threshold_rate(elevation_group))?,
+            )?;
+        }
+        Ok((allowed_borrow_value, unhealthy_borrow_value))
+    }
+
+    /// Calculate the maximum liquidation amount for a given liquidity, where
+    /// `liquidation_close_factor` is the percentage of the borrowed value that
+    /// can be repaid
     pub fn max_liquidation_amount(
         &self,
         liquidity: &ObligationLiquidity,
+        liquidation_close_factor: u8,
     ) -> Result<Decimal, ProgramError> {
         let max_liquidation_value = self
             .borrowed_value
-            .try_mul(Rate::from_percent(LIQUIDATION_CLOSE_FACTOR))?
+            .try_mul(Rate::from_percent(liquidation_close_factor))?
             .min(liquidity.market_value);
         let max_liquidation_pct = max_liquidation_value.try_div(liquidity.market_value)?;
         liquidity.borrowed_amount_wads.try_mul(max_liquidation_pct)
@@ -143,18 +217,61 @@ impl Obligation {
         Ok((&self.deposits[collateral_index], collateral_index))
     }
 
+    /// Size of an obligation account with room for `max_reserves` collateral
+    /// and liquidity reserves combined
+    pub fn account_len(max_reserves: usize) -> usize {
+        OBLIGATION_HEADER_LEN
+            + OBLIGATION_COLLATERAL_LEN
+            + OBLIGATION_LIQUIDITY_LEN * max_reserves.saturating_sub(1)
+    }
+
+    /// Max number of collateral and liquidity reserves combined for an
+    /// obligation account of `account_len` bytes
+    pub fn max_reserves(account_len: usize) -> usize {
+        let positions_len =
+            account_len.saturating_sub(OBLIGATION_HEADER_LEN + OBLIGATION_COLLATERAL_LEN);
+        (positions_len / OBLIGATION_LIQUIDITY_LEN + 1).min(MAX_RESIZED_OBLIGATION_RESERVES)
+    }
+
+    /// Unpack an obligation account of the default size or resized
+    pub fn unpack_resizable(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < OBLIGATION_LEN {
+            msg!("Obligation account is too small");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let obligation = Self::unpack_from_slice(input)?;
+        if !obligation.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(obligation)
+    }
+
+    /// Pack an obligation into an account of the default size or resized,
+    /// failing if its deposits and borrows do not fit
+    pub fn pack_resizable(src: Self, dst: &mut [u8]) -> ProgramResult {
+        if dst.len() < OBLIGATION_LEN
+            || src.deposits.len() + src.borrows.len() > Self::max_reserves(dst.len())
+        {
+            msg!("Obligation deposits and borrows do not fit in the obligation account");
+            return Err(LendingError::ObligationReserveLimit.into());
+        }
+        src.pack_into_slice(dst);
+        Ok(())
+    }
+
     /// Find or add collateral by deposit reserve
     pub fn find_or_add_collateral_to_deposits(
         &mut self,
         deposit_reserve: Pubkey,
+        max_reserves: usize,
     ) -> Result<&mut ObligationCollateral, ProgramError> {
         if let Some(collateral_index) = self._find_collateral_index_in_deposits(deposit_reserve) {
             return Ok(&mut self.deposits[collateral_index]);
         }
-        if self.deposits.len() + self.borrows.len() >= MAX_OBLIGATION_RESERVES {
+        if self.deposits.len() + self.borrows.len() >= max_reserves {
             msg!(
                 "Obligation cannot have more than {} deposits and borrows combined",
-                MAX_OBLIGATION_RESERVES
+                max_reserves
             );
             return Err(LendingError::ObligationReserveLimit.into());
         }
@@ -188,14 +305,15 @@ impl Obligation {
     pub fn find_or_add_liquidity_to_borrows(
         &mut self,
         borrow_reserve: Pubkey,
+        max_reserves: usize,
     ) -> Result<&mut ObligationLiquidity, ProgramError> {
         if let Some(liquidity_index) = self._find_liquidity_index_in_borrows(borrow_reserve) {
             return Ok(&mut self.borrows[liquidity_index]);
         }
-        if self.deposits.len() + self.borrows.len() >= MAX_OBLIGATION_RESERVES {
+        if self.deposits.len() + self.borrows.len() >= max_reserves {
             msg!(
                 "Obligation cannot have more than {} deposits and borrows combined",
-                MAX_OBLIGATION_RESERVES
+                max_reserves
             );
             return Err(LendingError::ObligationReserveLimit.into());
         }
@@ -335,13 +453,24 @@ impl ObligationLiquidity {
 
 const OBLIGATION_COLLATERAL_LEN: usize = 56; // 32 + 8 + 16
 const OBLIGATION_LIQUIDITY_LEN: usize = 80; // 32 + 16 + 16 + 16
-const OBLIGATION_LEN: usize = 916; // 1 + 8 + 1 + 32 + 32 + 16 + 16 + 16 + 16 + 1 + 1 + (56 * 1) + (80 * 9)
+const OBLIGATION_HEADER_LEN: usize = 140; // 1 + 8 + 1 + 32 + 32 + 16 + 16 + 16 + 16 + 1 + 1
+const OBLIGATION_DEPOSIT_MODES_LEN: usize = 2; // 1 + 1
+const OBLIGATION_LEN: usize = 916; // 140 + (56 * 1) + (80 * 9)
                                    // @TODO: break this up by obligation / collateral / liquidity https://git.io/JOCca
+
+// Resized obligation accounts keep the same layout, with room for more
+// deposits and borrows after the header.
+//
+// The deposits elevation group and isolated collateral flag follow the
+// deposits and borrows when there is room for them, which is always the case
+// while another reserve can be added. They are rewritten by every refresh, so
+// leftover bytes from older versions only matter until the next one.
 impl Pack for Obligation {
     const LEN: usize = OBLIGATION_LEN;
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let output = array_mut_ref![dst, 0, OBLIGATION_LEN];
+        let (output, data_flat) = dst.split_at_mut(OBLIGATION_HEADER_LEN);
+        let output = array_mut_ref![output, 0, OBLIGATION_HEADER_LEN];
         #[allow(clippy::ptr_offset_with_cast)]
         let (
             version,
@@ -355,7 +484,6 @@ impl Pack for Obligation {
             unhealthy_borrow_value,
             deposits_len,
             borrows_len,
-            data_flat,
         ) = mut_array_refs![
             output,
             1,
@@ -368,8 +496,7 @@ impl Pack for Obligation {
             16,
             16,
             1,
-            1,
-            OBLIGATION_COLLATERAL_LEN + (OBLIGATION_LIQUIDITY_LEN * (MAX_OBLIGATION_RESERVES - 1))
+            1
         ];
 
         // obligation
@@ -414,12 +541,28 @@ impl Pack for Obligation {
             pack_decimal(liquidity.market_value, market_value);
             offset += OBLIGATION_LIQUIDITY_LEN;
         }
+
+        // deposit modes
+        if let Some(deposit_modes_flat) =
+            data_flat.get_mut(offset..offset + OBLIGATION_DEPOSIT_MODES_LEN)
+        {
+            let deposit_modes_flat =
+                array_mut_ref![deposit_modes_flat, 0, OBLIGATION_DEPOSIT_MODES_LEN];
+            let (deposits_elevation_group, has_isolated_collateral) =
+                mut_array_refs![deposit_modes_flat, 1, 1];
+            *deposits_elevation_group = self.deposits_elevation_group.to_le_bytes();
+            pack_bool(self.has_isolated_collateral, has_isolated_collateral);
+        }
     }
 
     /// Unpacks a byte buffer into an
     /// [ObligationInfo](struct.ObligationInfo.html).
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let input = array_ref![src, 0, OBLIGATION_LEN];
+        if src.len() < OBLIGATION_HEADER_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (input, data_flat) = src.split_at(OBLIGATION_HEADER_LEN);
+        let input = array_ref![input, 0, OBLIGATION_HEADER_LEN];
         #[allow(clippy::ptr_offset_with_cast)]
         let (
             version,
@@ -433,7 +576,6 @@ impl Pack for Obligation {
             unhealthy_borrow_value,
             deposits_len,
             borrows_len,
-            data_flat,
         ) = array_refs![
             input,
             1,
@@ -446,8 +588,7 @@ impl Pack for Obligation {
             16,
             16,
             1,
-            1,
-            OBLIGATION_COLLATERAL_LEN + (OBLIGATION_LIQUIDITY_LEN * (MAX_OBLIGATION_RESERVES - 1))
+            1
         ];
 
         let version = u8::from_le_bytes(*version);
@@ -458,6 +599,13 @@ impl Pack for Obligation {
 
         let deposits_len = u8::from_le_bytes(*deposits_len);
         let borrows_len = u8::from_le_bytes(*borrows_len);
+        if deposits_len as usize * OBLIGATION_COLLATERAL_LEN
+            + borrows_len as usize * OBLIGATION_LIQUIDITY_LEN
+            > data_flat.len()
+        {
+            msg!("Obligation deposits and borrows do not fit in the obligation account");
+            return Err(ProgramError::InvalidAccountData);
+        }
         let mut deposits = Vec::with_capacity(deposits_len as usize + 1);
         let mut borrows = Vec::with_capacity(borrows_len as usize + 1);
 
@@ -488,6 +636,23 @@ impl Pack for Obligation {
             offset += OBLIGATION_LIQUIDITY_LEN;
         }
 
+        // without room for them, assume isolated collateral so a borrow from a
+        // new reserve cannot skip its checks
+        let (deposits_elevation_group, has_isolated_collateral) =
+            match data_flat.get(offset..offset + OBLIGATION_DEPOSIT_MODES_LEN) {
+                Some(deposit_modes_flat) => {
+                    let deposit_modes_flat =
+                        array_ref![deposit_modes_flat, 0, OBLIGATION_DEPOSIT_MODES_LEN];
+                    let (deposits_elevation_group, has_isolated_collateral) =
+                        array_refs![deposit_modes_flat, 1, 1];
+                    (
+                        u8::from_le_bytes(*deposits_elevation_group),
+                        has_isolated_collateral[0] != 0,
+                    )
+                }
+                None => (0, true),
+            };
+
         Ok(Self {
             version,
             last_update: LastUpdate {
@@ -502,6 +667,8 @@ impl Pack for Obligation {
             borrowed_value: unpack_decimal(borrowed_value),
             allowed_borrow_value: unpack_decimal(allowed_borrow_value),
             unhealthy_borrow_value: unpack_decimal(unhealthy_borrow_value),
+            deposits_elevation_group,
+            has_isolated_collateral,
         })
     }
 }
@@ -635,4 +802,142 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn calculate_borrow_values() {
+        let sol_reserve = Pubkey::new_unique();
+        let lst_reserve = Pubkey::new_unique();
+        let usdc_reserve = Pubkey::new_unique();
+        let sol_config = ReserveConfig {
+            loan_to_value_ratio: 50,
+            liquidation_threshold: 60,
+            elevation_group: 1,
+            elevation_group_loan_to_value_ratio: 80,
+            elevation_group_liquidation_threshold: 90,
+            ..ReserveConfig::default()
+        };
+        let lst_config = ReserveConfig {
+            isolated_borrow_reserves: [sol_reserve, Pubkey::default(), Pubkey::default()],
+            ..sol_config
+        };
+        let mut obligation = Obligation {
+            deposits: vec![
+                ObligationCollateral {
+                    deposit_reserve: sol_reserve,
+                    deposited_amount: 1,
+                    market_value: Decimal::from(100u64),
+                },
+                ObligationCollateral {
+                    deposit_reserve: lst_reserve,
+                    deposited_amount: 1,
+                    market_value: Decimal::from(100u64),
+                },
+            ],
+            borrows: vec![ObligationLiquidity::new(sol_reserve)],
+            ..Obligation::default()
+        };
+        let deposit_reserve_configs = [sol_config, lst_config];
+
+        let elevation_group = Obligation::common_elevation_group([1, 1, 1]);
+        assert_eq!(elevation_group, 1);
+        assert_eq!(
+            obligation.calculate_borrow_values(&deposit_reserve_configs, elevation_group),
+            Ok((Decimal::from(160u64), Decimal::from(180u64)))
+        );
+        assert_eq!(
+            obligation.calculate_borrow_values(&deposit_reserve_configs, 0),
+            Ok((Decimal::from(100u64), Decimal::from(120u64)))
+        );
+
+        // isolated collateral does not back borrows outside of its list
+        obligation
+            .borrows
+            .push(ObligationLiquidity::new(usdc_reserve));
+        assert_eq!(Obligation::common_elevation_group([1, 1, 1, 0]), 0);
+        assert_eq!(
+            obligation.calculate_borrow_values(&deposit_reserve_configs, 0),
+            Ok((Decimal::from(50u64), Decimal::from(60u64)))
+        );
+    }
+
+    #[test]
+    fn resizable_obligation() {
+        assert_eq!(
+            Obligation::account_len(MAX_OBLIGATION_RESERVES),
+            OBLIGATION_LEN
+        );
+        assert_eq!(
+            Obligation::max_reserves(OBLIGATION_LEN),
+            MAX_OBLIGATION_RESERVES
+        );
+        assert_eq!(
+            Obligation::max_reserves(Obligation::account_len(20) + OBLIGATION_LIQUIDITY_LEN - 1),
+            20
+        );
+        assert_eq!(
+            Obligation::max_reserves(usize::MAX),
+            MAX_RESIZED_OBLIGATION_RESERVES
+        );
+
+        let mut obligation = Obligation {
+            version: PROGRAM_VERSION,
+            deposits_elevation_group: 1,
+            has_isolated_collateral: true,
+            ..Obligation::default()
+        };
+        for _ in 0..12 {
+            obligation
+                .find_or_add_collateral_to_deposits(Pubkey::new_unique(), 20)
+                .unwrap()
+                .deposit(1)
+                .unwrap();
+        }
+        for _ in 0..8 {
+            obligation
+                .find_or_add_liquidity_to_borrows(Pubkey::new_unique(), 20)
+                .unwrap()
+                .borrow(Decimal::one())
+                .unwrap();
+        }
+        assert_eq!(
+            obligation.find_or_add_collateral_to_deposits(Pubkey::new_unique(), 20),
+            Err(LendingError::ObligationReserveLimit.into())
+        );
+
+        let mut default_account = vec![0; OBLIGATION_LEN];
+        assert_eq!(
+            Obligation::pack_resizable(obligation.clone(), &mut default_account),
+            Err(LendingError::ObligationReserveLimit.into())
+        );
+
+        let mut resized_account = vec![0; Obligation::account_len(20)];
+        Obligation::pack_resizable(obligation.clone(), &mut resized_account).unwrap();
+        assert_eq!(
+            Obligation::unpack_resizable(&resized_account),
+            Ok(obligation)
+        );
+        assert_eq!(
+            Obligation::unpack_resizable(&resized_account[..OBLIGATION_LEN]),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // the deposit modes are left out of a full account
+        let mut full_obligation = Obligation {
+            version: PROGRAM_VERSION,
+            deposits: vec![ObligationCollateral::new(Pubkey::new_unique())],
+            borrows: vec![
+                ObligationLiquidity::new(Pubkey::new_unique());
+                MAX_OBLIGATION_RESERVES - 1
+            ],
+            deposits_elevation_group: 1,
+            ..Obligation::default()
+        };
+        Obligation::pack_resizable(full_obligation.clone(), &mut default_account).unwrap();
+        full_obligation.deposits_elevation_group = 0;
+        full_obligation.has_isolated_collateral = true;
+        assert_eq!(
+            Obligation::unpack_resizable(&default_account),
+            Ok(full_obligation)
+        );
+    }
 }
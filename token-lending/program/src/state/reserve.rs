@@ -5,8 +5,10 @@ use {
         math::{Decimal, Rate, TryAdd, TryDiv, TryMul, TrySub},
     },
     arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    num_derive::FromPrimitive,
+    num_traits::FromPrimitive,
     solana_program::{
-        clock::Slot,
+        clock::{Slot, UnixTimestamp},
         entrypoint::ProgramResult,
         msg,
         program_error::ProgramError,
@@ -19,12 +21,33 @@ use {
     },
 };
 
-/// Percentage of an obligation that can be repaid during each liquidation call
+/// Percentage of an obligation that can be repaid during each liquidation call,
+/// for lending markets configured without one
 pub const LIQUIDATION_CLOSE_FACTOR: u8 = 50;
 
 /// Obligation borrow amount that is small enough to close out
 pub const LIQUIDATION_CLOSE_AMOUNT: u64 = 2;
 
+/// Maximum age of an oracle price, in slots, for reserves configured without
+/// one
+pub const DEFAULT_MAX_PRICE_AGE_SLOTS: u64 = 4;
+
+/// Minimum time averaged by a token-swap oracle price, in seconds, for reserves
+/// configured without one
+pub const DEFAULT_MIN_TWAP_WINDOW_SECONDS: u64 = 30 * 60;
+
+/// Maximum number of points on a reserve borrow rate curve
+pub const MAX_BORROW_RATE_CURVE_POINTS: usize = 10;
+
+/// Maximum number of reserves isolated collateral can be borrowed against
+pub const MAX_ISOLATED_BORROW_RESERVES: usize = 3;
+
+/// Reserve version before deposit and borrow limits were added
+const RESERVE_VERSION_WITHOUT_LIMITS: u8 = 1;
+
+/// Reserve version before borrow rate curves were added
+const RESERVE_VERSION_WITHOUT_RATE_CURVE: u8 = 2;
+
 /// Lending market reserve state
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Reserve {
@@ -40,6 +63,9 @@ pub struct Reserve {
     pub collateral: ReserveCollateral,
     /// Reserve configuration values
     pub config: ReserveConfig,
+    /// Bitmask of [ReserveOperation](enum.ReserveOperation.html)s disabled
+    /// by the lending market owner or risk council
+    pub disabled_operations: u8,
 }
 
 impl Reserve {
@@ -60,6 +86,11 @@ impl Reserve {
         self.config = params.config;
     }
 
+    /// Check whether an operation is disabled for the reserve
+    pub fn is_operation_disabled(&self, operation: ReserveOperation) -> bool {
+        self.disabled_operations & operation as u8 != 0
+    }
+
     /// Record deposited liquidity and return amount of collateral tokens to
     /// mint
     pub fn deposit_liquidity(&mut self, liquidity_amount: u64) -> Result<u64, ProgramError> {
@@ -88,37 +119,9 @@ impl Reserve {
     /// Calculate the current borrow rate
     pub fn current_borrow_rate(&self) -> Result<Rate, ProgramError> {
         let utilization_rate = self.liquidity.utilization_rate()?;
-        let optimal_utilization_rate = Rate::from_percent(self.config.optimal_utilization_rate);
-        let low_utilization = utilization_rate < optimal_utilization_rate;
-        if low_utilization || self.config.optimal_utilization_rate == 100 {
-            let normalized_rate = utilization_rate.try_div(optimal_utilization_rate)?;
-            let min_rate = Rate::from_percent(self.config.min_borrow_rate);
-            let rate_range = Rate::from_percent(
-                self.config
-                    .optimal_borrow_rate
-                    .checked_sub(self.config.min_borrow_rate)
-                    .ok_or(LendingError::MathOverflow)?,
-            );
-
-            Ok(normalized_rate.try_mul(rate_range)?.try_add(min_rate)?)
-        } else {
-            let normalized_rate = utilization_rate
-                .try_sub(optimal_utilization_rate)?
-                .try_div(Rate::from_percent(
-                    100u8
-                        .checked_sub(self.config.optimal_utilization_rate)
-                        .ok_or(LendingError::MathOverflow)?,
-                ))?;
-            let min_rate = Rate::from_percent(self.config.optimal_borrow_rate);
-            let rate_range = Rate::from_percent(
-                self.config
-                    .max_borrow_rate
-                    .checked_sub(self.config.optimal_borrow_rate)
-                    .ok_or(LendingError::MathOverflow)?,
-            );
-
-            Ok(normalized_rate.try_mul(rate_range)?.try_add(min_rate)?)
-        }
+        self.config
+            .effective_borrow_rate_curve()
+            .borrow_rate(utilization_rate)
     }
 
     /// Collateral exchange rate
@@ -127,6 +130,26 @@ impl Reserve {
         self.collateral.exchange_rate(total_liquidity)
     }
 
+    /// Calculate the market value of an amount of liquidity
+    pub fn market_value(&self, liquidity_amount: Decimal) -> Result<Decimal, ProgramError> {
+        // @TODO: add lookup table https://git.io/JOCYq
+        let decimals = 10u64
+            .checked_pow(self.liquidity.mint_decimals as u32)
+            .ok_or(LendingError::MathOverflow)?;
+        liquidity_amount
+            .try_mul(self.liquidity.market_price)?
+            .try_div(decimals)
+    }
+
+    /// Calculate the market value of the liquidity an amount of collateral
+    /// can be redeemed for
+    pub fn collateral_market_value(&self, collateral_amount: u64) -> Result<Decimal, ProgramError> {
+        let liquidity_amount = self
+            .collateral_exchange_rate()?
+            .decimal_collateral_to_liquidity(collateral_amount.into())?;
+        self.market_value(liquidity_amount)
+    }
+
     /// Update borrow rate and accrue interest
     pub fn accrue_interest(&mut self, current_slot: Slot) -> ProgramResult {
         let slots_elapsed = self.last_update.slots_elapsed(current_slot)?;
@@ -217,6 +240,7 @@ impl Reserve {
     pub fn calculate_liquidation(
         &self,
         amount_to_liquidate: u64,
+        liquidation_close_factor: u8,
         obligation: &Obligation,
         liquidity: &ObligationLiquidity,
         collateral: &ObligationCollateral,
@@ -261,7 +285,7 @@ impl Reserve {
             // calculate settle_amount and withdraw_amount, repay_amount is settle_amount
             // rounded
             let liquidation_amount = obligation
-                .max_liquidation_amount(liquidity)?
+                .max_liquidation_amount(liquidity, liquidation_close_factor)?
                 .min(max_amount);
             let liquidation_pct = liquidation_amount.try_div(liquidity.borrowed_amount_wads)?;
             let liquidation_value = liquidity
@@ -292,12 +316,29 @@ impl Reserve {
             }
         }
 
+        let protocol_fee_amount = self.calculate_protocol_liquidation_fee(withdraw_amount)?;
+
         Ok(CalculateLiquidationResult {
             settle_amount,
             repay_amount,
             withdraw_amount,
+            protocol_fee_amount,
         })
     }
+
+    /// Calculate the share of the liquidation bonus in `withdraw_amount` of
+    /// collateral that goes to the protocol
+    pub fn calculate_protocol_liquidation_fee(
+        &self,
+        withdraw_amount: u64,
+    ) -> Result<u64, ProgramError> {
+        let bonus_rate = Rate::from_percent(self.config.liquidation_bonus).try_add(Rate::one())?;
+        let withdraw_amount = Decimal::from(withdraw_amount);
+        let bonus_amount = withdraw_amount.try_sub(withdraw_amount.try_div(bonus_rate)?)?;
+        bonus_amount
+            .try_mul(Rate::from_percent(self.config.protocol_liquidation_fee))?
+            .try_floor_u64()
+    }
 }
 
 /// Initialize a reserve
@@ -346,6 +387,9 @@ pub struct CalculateLiquidationResult {
     pub repay_amount: u64,
     /// Amount of collateral to withdraw in exchange for repay amount
     pub withdraw_amount: u64,
+    /// Amount of the withdrawn collateral owed to the protocol, taken from
+    /// the liquidation bonus
+    pub protocol_fee_amount: u64,
 }
 
 /// Reserve liquidity
@@ -361,6 +405,10 @@ pub struct ReserveLiquidity {
     pub fee_receiver: Pubkey,
     /// Reserve liquidity oracle account
     pub oracle_pubkey: Pubkey,
+    /// Kind of account the reserve liquidity oracle is
+    pub oracle_source: OracleSource,
+    /// Last observation of the oracle, when priced from a token-swap pool
+    pub token_swap_oracle: TokenSwapOracle,
     /// Reserve liquidity available
     pub available_amount: u64,
     /// Reserve liquidity borrowed
@@ -369,6 +417,8 @@ pub struct ReserveLiquidity {
     pub cumulative_borrow_rate_wads: Decimal,
     /// Reserve liquidity market price in quote currency
     pub market_price: Decimal,
+    /// Slot the reserve liquidity market price was published in
+    pub market_price_slot: Slot,
 }
 
 impl ReserveLiquidity {
@@ -380,10 +430,13 @@ impl ReserveLiquidity {
             supply_pubkey: params.supply_pubkey,
             fee_receiver: params.fee_receiver,
             oracle_pubkey: params.oracle_pubkey,
+            oracle_source: params.oracle_source,
+            token_swap_oracle: params.token_swap_oracle,
             available_amount: 0,
             borrowed_amount_wads: Decimal::zero(),
             cumulative_borrow_rate_wads: Decimal::one(),
             market_price: params.market_price,
+            market_price_slot: params.market_price_slot,
         }
     }
 
@@ -484,8 +537,74 @@ pub struct NewReserveLiquidityParams {
     pub fee_receiver: Pubkey,
     /// Reserve liquidity oracle account
     pub oracle_pubkey: Pubkey,
+    /// Kind of account the reserve liquidity oracle is
+    pub oracle_source: OracleSource,
+    /// Initial observation of the oracle, when priced from a token-swap pool
+    pub token_swap_oracle: TokenSwapOracle,
     /// Reserve liquidity market price in quote currency
     pub market_price: Decimal,
+    /// Slot the reserve liquidity market price was published in
+    pub market_price_slot: Slot,
+}
+
+/// Kind of account used to price the reserve liquidity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromPrimitive)]
+pub enum OracleSource {
+    /// Pyth v2 price account
+    #[default]
+    Pyth,
+    /// Constant product token-swap pool between the liquidity mint and a
+    /// quote currency mint, priced with the time-weighted average price over
+    /// at least the reserve's TWAP window
+    TokenSwapTwap,
+    /// [FixedPrice](struct.FixedPrice.html) account owned by the lending
+    /// program, whose authority sets the price
+    /// Only accepted by builds with the `fixed-price-oracle` feature
+    FixedPriceAuthority,
+}
+
+impl TryFrom<u8> for OracleSource {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::from_u8(value).ok_or_else(|| {
+            msg!("Oracle source is invalid");
+            LendingError::InvalidOracleConfig.into()
+        })
+    }
+}
+
+/// Reserve operations that can be disabled individually
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReserveOperation {
+    /// Deposit liquidity into the reserve, or its collateral into obligations
+    Deposit = 1,
+    /// Redeem reserve collateral, or withdraw it from obligations
+    Withdraw = 1 << 1,
+    /// Borrow liquidity from the reserve
+    Borrow = 1 << 2,
+    /// Liquidate obligations by repaying to or withdrawing from the reserve
+    Liquidate = 1 << 3,
+    /// Flash loan liquidity from the reserve
+    FlashLoan = 1 << 4,
+}
+
+impl ReserveOperation {
+    /// Bitmask of every reserve operation
+    pub const ALL: u8 = 0b11111;
+}
+
+/// Last observation of the cumulative price of a token-swap pool, from which
+/// the time-weighted average price is computed on refresh
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenSwapOracle {
+    /// Decimals of the quote currency mint on the other side of the pool
+    pub quote_mint_decimals: u8,
+    /// Cumulative price of the liquidity in the quote currency, as stored by
+    /// the pool
+    pub price_cumulative: u128,
+    /// Unix timestamp of the pool update which stored the cumulative price
+    pub timestamp: UnixTimestamp,
 }
 
 /// Reserve collateral
@@ -592,7 +711,7 @@ impl From<CollateralExchangeRate> for Rate {
 }
 
 /// Reserve configuration values
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ReserveConfig {
     /// Optimal utilization rate, as a percentage
     pub optimal_utilization_rate: u8,
@@ -613,6 +732,68 @@ pub struct ReserveConfig {
     pub max_borrow_rate: u8,
     /// Program owner fees assessed, separate from gains due to interest accrual
     pub fees: ReserveFees,
+    /// Maximum age of the oracle price, in slots, which must cover the
+    /// averaging window of a token-swap oracle
+    /// 0 to use the default of 4 slots
+    pub max_price_age_slots: u64,
+    /// Maximum width of the oracle confidence interval, in basis points of the
+    /// price
+    /// 0 to accept any confidence interval
+    pub max_confidence_bps: u16,
+    /// Minimum time averaged by a token-swap oracle price, in seconds
+    /// 0 to use the default of 30 minutes
+    pub min_twap_window_seconds: u64,
+    /// Maximum total liquidity supplied to the reserve, including borrows
+    /// u64::MAX for no limit
+    pub deposit_limit: u64,
+    /// Maximum total liquidity borrowed from the reserve
+    /// u64::MAX for no limit
+    pub borrow_limit: u64,
+    /// Share of the liquidation bonus paid to the reserve fee receiver rather
+    /// than the liquidator, as a percentage
+    pub protocol_liquidation_fee: u8,
+    /// Borrow APY by utilization rate, in basis points
+    /// Empty to use the curve through the min, optimal and max borrow rates
+    pub borrow_rate_curve: BorrowRateCurve,
+    /// Group of correlated assets, e.g. SOL and its liquid staking tokens
+    /// 0 if the reserve is not in a group
+    pub elevation_group: u8,
+    /// Loan to value ratio used when all deposits and borrows of an
+    /// obligation are in the elevation group, as a percentage
+    pub elevation_group_loan_to_value_ratio: u8,
+    /// Liquidation threshold used when all deposits and borrows of an
+    /// obligation are in the elevation group, as a percentage
+    pub elevation_group_liquidation_threshold: u8,
+    /// Reserves that can be borrowed against this reserve's collateral
+    /// All default pubkeys if the collateral is not isolated
+    pub isolated_borrow_reserves: [Pubkey; MAX_ISOLATED_BORROW_RESERVES],
+}
+
+impl Default for ReserveConfig {
+    fn default() -> Self {
+        Self {
+            optimal_utilization_rate: 0,
+            loan_to_value_ratio: 0,
+            liquidation_bonus: 0,
+            liquidation_threshold: 0,
+            min_borrow_rate: 0,
+            optimal_borrow_rate: 0,
+            max_borrow_rate: 0,
+            fees: ReserveFees::default(),
+            max_price_age_slots: 0,
+            max_confidence_bps: 0,
+            min_twap_window_seconds: 0,
+            deposit_limit: u64::MAX,
+            borrow_limit: u64::MAX,
+            protocol_liquidation_fee: 0,
+            borrow_rate_curve: BorrowRateCurve::EMPTY,
+            elevation_group: 0,
+            elevation_group_loan_to_value_ratio: 0,
+            elevation_group_liquidation_threshold: 0,
+            isolated_borrow_reserves: [Pubkey::new_from_array([0; 32]);
+                MAX_ISOLATED_BORROW_RESERVES],
+        }
+    }
 }
 
 impl ReserveConfig {
@@ -657,9 +838,270 @@ impl ReserveConfig {
             msg!("Host fee percentage must be in range [0, 100]");
             return Err(LendingError::InvalidConfig.into());
         }
+        if self.max_confidence_bps > 10_000 {
+            msg!("Max confidence must be in range [0, 10_000] basis points");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if self.protocol_liquidation_fee > 100 {
+            msg!("Protocol liquidation fee must be in range [0, 100]");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if !self.borrow_rate_curve.is_empty() {
+            self.borrow_rate_curve.validate()?;
+        }
+        if self.elevation_group != 0 {
+            if self.elevation_group_loan_to_value_ratio < self.loan_to_value_ratio
+                || self.elevation_group_loan_to_value_ratio >= 100
+            {
+                msg!("Elevation group loan to value ratio must be in range [LTV, 100)");
+                return Err(LendingError::InvalidConfig.into());
+            }
+            if self.elevation_group_liquidation_threshold
+                <= self.elevation_group_loan_to_value_ratio
+                || self.elevation_group_liquidation_threshold < self.liquidation_threshold
+                || self.elevation_group_liquidation_threshold > 100
+            {
+                msg!("Elevation group liquidation threshold must be in range (elevation group LTV, 100] and at least the liquidation threshold");
+                return Err(LendingError::InvalidConfig.into());
+            }
+        }
 
         Ok(())
     }
+
+    /// Maximum age of the oracle price, in slots, falling back to the default
+    /// for reserves configured without one
+    pub fn price_age_limit_slots(&self) -> u64 {
+        if self.max_price_age_slots == 0 {
+            DEFAULT_MAX_PRICE_AGE_SLOTS
+        } else {
+            self.max_price_age_slots
+        }
+    }
+
+    /// Minimum time averaged by a token-swap oracle price, in seconds, falling
+    /// back to the default for reserves configured without one
+    pub fn twap_window_seconds(&self) -> u64 {
+        if self.min_twap_window_seconds == 0 {
+            DEFAULT_MIN_TWAP_WINDOW_SECONDS
+        } else {
+            self.min_twap_window_seconds
+        }
+    }
+
+    /// Check whether an oracle price published in `price_slot` is too old to
+    /// be used in `slot`
+    pub fn is_price_stale(&self, price_slot: Slot, slot: Slot) -> Result<bool, ProgramError> {
+        let slots_elapsed = slot
+            .checked_sub(price_slot)
+            .ok_or(LendingError::MathOverflow)?;
+        Ok(slots_elapsed > self.price_age_limit_slots())
+    }
+
+    /// Loan to value ratio of the collateral, as a rate, for an obligation
+    /// whose deposits and borrows are all in `elevation_group`
+    pub fn loan_to_value_rate(&self, elevation_group: u8) -> Rate {
+        if self.elevation_group != 0 && self.elevation_group == elevation_group {
+            Rate::from_percent(self.elevation_group_loan_to_value_ratio)
+        } else {
+            Rate::from_percent(self.loan_to_value_ratio)
+        }
+    }
+
+    /// Liquidation threshold of the collateral, as a rate, for an obligation
+    /// whose deposits and borrows are all in `elevation_group`
+    pub fn liquidation_threshold_rate(&self, elevation_group: u8) -> Rate {
+        if self.elevation_group != 0 && self.elevation_group == elevation_group {
+            Rate::from_percent(self.elevation_group_liquidation_threshold)
+        } else {
+            Rate::from_percent(self.liquidation_threshold)
+        }
+    }
+
+    /// Highest loan to value ratio of the collateral in or out of its
+    /// elevation group, as a percentage
+    pub fn max_loan_to_value_ratio(&self) -> u8 {
+        if self.elevation_group != 0 {
+            self.loan_to_value_ratio
+                .max(self.elevation_group_loan_to_value_ratio)
+        } else {
+            self.loan_to_value_ratio
+        }
+    }
+
+    /// Check whether the collateral can only be borrowed against for a list of
+    /// reserves
+    pub fn is_isolated(&self) -> bool {
+        self.isolated_borrow_reserves
+            .iter()
+            .any(|reserve| *reserve != Pubkey::default())
+    }
+
+    /// Check whether the collateral can be borrowed against for a reserve
+    pub fn allows_borrow(&self, borrow_reserve: &Pubkey) -> bool {
+        !self.is_isolated() || self.isolated_borrow_reserves.contains(borrow_reserve)
+    }
+
+    /// Borrow rate curve of the reserve, falling back to the curve through the
+    /// min, optimal and max borrow rates for reserves configured without one
+    pub fn effective_borrow_rate_curve(&self) -> BorrowRateCurve {
+        if self.borrow_rate_curve.is_empty() {
+            BorrowRateCurve::from_optimal(
+                self.optimal_utilization_rate,
+                self.min_borrow_rate,
+                self.optimal_borrow_rate,
+                self.max_borrow_rate,
+            )
+        } else {
+            self.borrow_rate_curve
+        }
+    }
+}
+
+/// Point on a borrow rate curve
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BorrowRatePoint {
+    /// Utilization rate, in basis points
+    pub utilization_rate_bps: u16,
+    /// Borrow APY at this utilization rate, in basis points
+    pub borrow_rate_bps: u16,
+}
+
+/// Piecewise-linear borrow rate curve
+///
+/// Points are ordered by utilization rate, starting at 0% and ending at 100%.
+/// Two points may share a utilization rate to make the borrow rate jump.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BorrowRateCurve {
+    num_points: u8,
+    points: [BorrowRatePoint; MAX_BORROW_RATE_CURVE_POINTS],
+}
+
+impl BorrowRateCurve {
+    /// Curve without any points
+    pub const EMPTY: Self = Self {
+        num_points: 0,
+        points: [BorrowRatePoint {
+            utilization_rate_bps: 0,
+            borrow_rate_bps: 0,
+        }; MAX_BORROW_RATE_CURVE_POINTS],
+    };
+
+    /// Create a borrow rate curve from its points
+    pub fn new(points: &[BorrowRatePoint]) -> Result<Self, ProgramError> {
+        if points.len() > MAX_BORROW_RATE_CURVE_POINTS {
+            msg!(
+                "Borrow rate curve cannot have more than {} points",
+                MAX_BORROW_RATE_CURVE_POINTS
+            );
+            return Err(LendingError::InvalidConfig.into());
+        }
+        let mut curve = Self::EMPTY;
+        curve.num_points = points.len() as u8;
+        curve.points[..points.len()].copy_from_slice(points);
+        Ok(curve)
+    }
+
+    /// Create the two segment curve from the min borrow rate at 0%
+    /// utilization, through the optimal borrow rate at the optimal utilization
+    /// rate, to the max borrow rate at 100% utilization, all as percentages
+    pub fn from_optimal(
+        optimal_utilization_rate: u8,
+        min_borrow_rate: u8,
+        optimal_borrow_rate: u8,
+        max_borrow_rate: u8,
+    ) -> Self {
+        let point = |utilization_rate: u8, borrow_rate: u8| BorrowRatePoint {
+            utilization_rate_bps: utilization_rate as u16 * 100,
+            borrow_rate_bps: borrow_rate as u16 * 100,
+        };
+        let mut curve = Self::EMPTY;
+        curve.num_points = 3;
+        curve.points[0] = point(0, min_borrow_rate);
+        curve.points[1] = point(optimal_utilization_rate, optimal_borrow_rate);
+        curve.points[2] = point(100, max_borrow_rate);
+        curve
+    }
+
+    /// Points of the curve
+    pub fn points(&self) -> &[BorrowRatePoint] {
+        &self.points[..self.num_points as usize]
+    }
+
+    /// Check whether the curve has no points
+    pub fn is_empty(&self) -> bool {
+        self.num_points == 0
+    }
+
+    /// Validate the curve points
+    pub fn validate(&self) -> ProgramResult {
+        let points = self.points();
+        if points.len() < 2 {
+            msg!("Borrow rate curve must have at least 2 points");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if points[0].utilization_rate_bps != 0 {
+            msg!("Borrow rate curve must start at 0% utilization");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if points[points.len() - 1].utilization_rate_bps != 10_000 {
+            msg!("Borrow rate curve must end at 100% utilization");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        for window in points.windows(2) {
+            if window[1].utilization_rate_bps < window[0].utilization_rate_bps {
+                msg!("Borrow rate curve utilization rates must be increasing");
+                return Err(LendingError::InvalidConfig.into());
+            }
+            if window[1].borrow_rate_bps < window[0].borrow_rate_bps {
+                msg!("Borrow rate curve borrow rates must be increasing");
+                return Err(LendingError::InvalidConfig.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Calculate the borrow rate at a utilization rate, interpolating between
+    /// the points around it
+    pub fn borrow_rate(&self, utilization_rate: Rate) -> Result<Rate, ProgramError> {
+        let points = self.points();
+        for window in points.windows(2) {
+            let start_utilization_rate = Rate::from_bps(window[0].utilization_rate_bps);
+            let end_utilization_rate = Rate::from_bps(window[1].utilization_rate_bps);
+            if utilization_rate > end_utilization_rate
+                || start_utilization_rate == end_utilization_rate
+            {
+                continue;
+            }
+
+            let normalized_rate = utilization_rate
+                .try_sub(start_utilization_rate)?
+                .try_div(end_utilization_rate.try_sub(start_utilization_rate)?)?;
+            let min_rate = Rate::from_bps(window[0].borrow_rate_bps);
+            let rate_range = Rate::from_bps(
+                window[1]
+                    .borrow_rate_bps
+                    .checked_sub(window[0].borrow_rate_bps)
+                    .ok_or(LendingError::MathOverflow)?,
+            );
+
+            return normalized_rate.try_mul(rate_range)?.try_add(min_rate);
+        }
+
+        points
+            .last()
+            .map(|point| Rate::from_bps(point.borrow_rate_bps))
+            .ok_or_else(|| {
+                msg!("Borrow rate curve has no points");
+                LendingError::InvalidConfig.into()
+            })
+    }
+}
+
+impl Default for BorrowRateCurve {
+    fn default() -> Self {
+        Self::EMPTY
+    }
 }
 
 /// Additional fee information on a reserve
@@ -771,7 +1213,7 @@ impl IsInitialized for Reserve {
 }
 
 const RESERVE_LEN: usize = 571; // 1 + 8 + 1 + 32 + 32 + 1 + 32 + 32 + 32 + 8 + 16 + 16 + 16 + 32 + 8 + 32 + 1 +
-                                // 1 + 1 + 1 + 1 + 1 + 1 + 8 + 8 + 1 + 248
+                                // 1 + 1 + 1 + 1 + 1 + 1 + 8 + 8 + 1 + 1 + 1 + 16 + 8 + 8 + 2 + 8 + 8 + 8 + 8 + 1 + 41 + 1 + 1 + 1 + 96 + 1 + 38
 impl Pack for Reserve {
     const LEN: usize = RESERVE_LEN;
 
@@ -806,6 +1248,23 @@ impl Pack for Reserve {
             config_fees_borrow_fee_wad,
             config_fees_flash_loan_fee_wad,
             config_fees_host_fee_percentage,
+            liquidity_oracle_source,
+            liquidity_token_swap_oracle_quote_mint_decimals,
+            liquidity_token_swap_oracle_price_cumulative,
+            liquidity_token_swap_oracle_timestamp,
+            config_max_price_age_slots,
+            config_max_confidence_bps,
+            config_min_twap_window_seconds,
+            liquidity_market_price_slot,
+            config_deposit_limit,
+            config_borrow_limit,
+            config_protocol_liquidation_fee,
+            config_borrow_rate_curve,
+            config_elevation_group,
+            config_elevation_group_loan_to_value_ratio,
+            config_elevation_group_liquidation_threshold,
+            config_isolated_borrow_reserves,
+            disabled_operations,
             _padding,
         ) = mut_array_refs![
             output,
@@ -835,7 +1294,24 @@ impl Pack for Reserve {
             8,
             8,
             1,
-            248
+            1,
+            1,
+            16,
+            8,
+            8,
+            2,
+            8,
+            8,
+            8,
+            8,
+            1,
+            BORROW_RATE_CURVE_LEN,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES * MAX_ISOLATED_BORROW_RESERVES,
+            1,
+            38
         ];
 
         // reserve
@@ -843,6 +1319,7 @@ impl Pack for Reserve {
         *last_update_slot = self.last_update.slot.to_le_bytes();
         pack_bool(self.last_update.stale, last_update_stale);
         lending_market.copy_from_slice(self.lending_market.as_ref());
+        *disabled_operations = self.disabled_operations.to_le_bytes();
 
         // liquidity
         liquidity_mint_pubkey.copy_from_slice(self.liquidity.mint_pubkey.as_ref());
@@ -860,6 +1337,7 @@ impl Pack for Reserve {
             liquidity_cumulative_borrow_rate_wads,
         );
         pack_decimal(self.liquidity.market_price, liquidity_market_price);
+        *liquidity_market_price_slot = self.liquidity.market_price_slot.to_le_bytes();
 
         // collateral
         collateral_mint_pubkey.copy_from_slice(self.collateral.mint_pubkey.as_ref());
@@ -877,6 +1355,45 @@ impl Pack for Reserve {
         *config_fees_borrow_fee_wad = self.config.fees.borrow_fee_wad.to_le_bytes();
         *config_fees_flash_loan_fee_wad = self.config.fees.flash_loan_fee_wad.to_le_bytes();
         *config_fees_host_fee_percentage = self.config.fees.host_fee_percentage.to_le_bytes();
+        *config_max_price_age_slots = self.config.max_price_age_slots.to_le_bytes();
+        *config_max_confidence_bps = self.config.max_confidence_bps.to_le_bytes();
+        *config_min_twap_window_seconds = self.config.min_twap_window_seconds.to_le_bytes();
+        *config_deposit_limit = self.config.deposit_limit.to_le_bytes();
+        *config_borrow_limit = self.config.borrow_limit.to_le_bytes();
+        *config_protocol_liquidation_fee = self.config.protocol_liquidation_fee.to_le_bytes();
+        pack_borrow_rate_curve(&self.config.borrow_rate_curve, config_borrow_rate_curve);
+        *config_elevation_group = self.config.elevation_group.to_le_bytes();
+        *config_elevation_group_loan_to_value_ratio = self
+            .config
+            .elevation_group_loan_to_value_ratio
+            .to_le_bytes();
+        *config_elevation_group_liquidation_threshold = self
+            .config
+            .elevation_group_liquidation_threshold
+            .to_le_bytes();
+        for (reserve, dst) in self
+            .config
+            .isolated_borrow_reserves
+            .iter()
+            .zip(config_isolated_borrow_reserves.chunks_exact_mut(PUBKEY_BYTES))
+        {
+            dst.copy_from_slice(reserve.as_ref());
+        }
+
+        // oracle
+        *liquidity_oracle_source = (self.liquidity.oracle_source as u8).to_le_bytes();
+        *liquidity_token_swap_oracle_quote_mint_decimals = self
+            .liquidity
+            .token_swap_oracle
+            .quote_mint_decimals
+            .to_le_bytes();
+        *liquidity_token_swap_oracle_price_cumulative = self
+            .liquidity
+            .token_swap_oracle
+            .price_cumulative
+            .to_le_bytes();
+        *liquidity_token_swap_oracle_timestamp =
+            self.liquidity.token_swap_oracle.timestamp.to_le_bytes();
     }
 
     /// Unpacks a byte buffer into a [ReserveInfo](struct.ReserveInfo.html).
@@ -910,6 +1427,23 @@ impl Pack for Reserve {
             config_fees_borrow_fee_wad,
             config_fees_flash_loan_fee_wad,
             config_fees_host_fee_percentage,
+            liquidity_oracle_source,
+            liquidity_token_swap_oracle_quote_mint_decimals,
+            liquidity_token_swap_oracle_price_cumulative,
+            liquidity_token_swap_oracle_timestamp,
+            config_max_price_age_slots,
+            config_max_confidence_bps,
+            config_min_twap_window_seconds,
+            liquidity_market_price_slot,
+            config_deposit_limit,
+            config_borrow_limit,
+            config_protocol_liquidation_fee,
+            config_borrow_rate_curve,
+            config_elevation_group,
+            config_elevation_group_loan_to_value_ratio,
+            config_elevation_group_liquidation_threshold,
+            config_isolated_borrow_reserves,
+            disabled_operations,
             _padding,
         ) = array_refs![
             input,
@@ -939,7 +1473,24 @@ impl Pack for Reserve {
             8,
             8,
             1,
-            248
+            1,
+            1,
+            16,
+            8,
+            8,
+            2,
+            8,
+            8,
+            8,
+            8,
+            1,
+            BORROW_RATE_CURVE_LEN,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES * MAX_ISOLATED_BORROW_RESERVES,
+            1,
+            38
         ];
 
         let version = u8::from_le_bytes(*version);
@@ -948,6 +1499,33 @@ impl Pack for Reserve {
             return Err(ProgramError::InvalidAccountData);
         }
 
+        // reserves created before limits were added are migrated without limits
+        let (deposit_limit, borrow_limit) = if version == RESERVE_VERSION_WITHOUT_LIMITS {
+            (u64::MAX, u64::MAX)
+        } else {
+            (
+                u64::from_le_bytes(*config_deposit_limit),
+                u64::from_le_bytes(*config_borrow_limit),
+            )
+        };
+
+        // reserves created before rate curves were added keep their optimal
+        // utilization curve
+        let (version, borrow_rate_curve) =
+            if version <= RESERVE_VERSION_WITHOUT_RATE_CURVE && version != UNINITIALIZED_VERSION {
+                (PROGRAM_VERSION, BorrowRateCurve::EMPTY)
+            } else {
+                (version, unpack_borrow_rate_curve(config_borrow_rate_curve)?)
+            };
+
+        let mut isolated_borrow_reserves = [Pubkey::default(); MAX_ISOLATED_BORROW_RESERVES];
+        for (reserve, src) in isolated_borrow_reserves
+            .iter_mut()
+            .zip(config_isolated_borrow_reserves.chunks_exact(PUBKEY_BYTES))
+        {
+            *reserve = Pubkey::try_from(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        }
+
         Ok(Self {
             version,
             last_update: LastUpdate {
@@ -961,10 +1539,21 @@ impl Pack for Reserve {
                 supply_pubkey: Pubkey::new_from_array(*liquidity_supply_pubkey),
                 fee_receiver: Pubkey::new_from_array(*liquidity_fee_receiver),
                 oracle_pubkey: Pubkey::new_from_array(*liquidity_oracle_pubkey),
+                oracle_source: OracleSource::try_from(u8::from_le_bytes(*liquidity_oracle_source))?,
+                token_swap_oracle: TokenSwapOracle {
+                    quote_mint_decimals: u8::from_le_bytes(
+                        *liquidity_token_swap_oracle_quote_mint_decimals,
+                    ),
+                    price_cumulative: u128::from_le_bytes(
+                        *liquidity_token_swap_oracle_price_cumulative,
+                    ),
+                    timestamp: i64::from_le_bytes(*liquidity_token_swap_oracle_timestamp),
+                },
                 available_amount: u64::from_le_bytes(*liquidity_available_amount),
                 borrowed_amount_wads: unpack_decimal(liquidity_borrowed_amount_wads),
                 cumulative_borrow_rate_wads: unpack_decimal(liquidity_cumulative_borrow_rate_wads),
                 market_price: unpack_decimal(liquidity_market_price),
+                market_price_slot: u64::from_le_bytes(*liquidity_market_price_slot),
             },
             collateral: ReserveCollateral {
                 mint_pubkey: Pubkey::new_from_array(*collateral_mint_pubkey),
@@ -984,11 +1573,58 @@ impl Pack for Reserve {
                     flash_loan_fee_wad: u64::from_le_bytes(*config_fees_flash_loan_fee_wad),
                     host_fee_percentage: u8::from_le_bytes(*config_fees_host_fee_percentage),
                 },
+                max_price_age_slots: u64::from_le_bytes(*config_max_price_age_slots),
+                max_confidence_bps: u16::from_le_bytes(*config_max_confidence_bps),
+                min_twap_window_seconds: u64::from_le_bytes(*config_min_twap_window_seconds),
+                deposit_limit,
+                borrow_limit,
+                protocol_liquidation_fee: u8::from_le_bytes(*config_protocol_liquidation_fee),
+                borrow_rate_curve,
+                elevation_group: u8::from_le_bytes(*config_elevation_group),
+                elevation_group_loan_to_value_ratio: u8::from_le_bytes(
+                    *config_elevation_group_loan_to_value_ratio,
+                ),
+                elevation_group_liquidation_threshold: u8::from_le_bytes(
+                    *config_elevation_group_liquidation_threshold,
+                ),
+                isolated_borrow_reserves,
             },
+            disabled_operations: u8::from_le_bytes(*disabled_operations),
         })
     }
 }
 
+const BORROW_RATE_CURVE_LEN: usize = 1 + MAX_BORROW_RATE_CURVE_POINTS * 4;
+
+fn pack_borrow_rate_curve(curve: &BorrowRateCurve, dst: &mut [u8; BORROW_RATE_CURVE_LEN]) {
+    #[allow(clippy::ptr_offset_with_cast)]
+    let (num_points, points) = mut_array_refs![dst, 1, MAX_BORROW_RATE_CURVE_POINTS * 4];
+    *num_points = curve.num_points.to_le_bytes();
+    for (point, dst) in curve.points.iter().zip(points.chunks_exact_mut(4)) {
+        dst[..2].copy_from_slice(&point.utilization_rate_bps.to_le_bytes());
+        dst[2..].copy_from_slice(&point.borrow_rate_bps.to_le_bytes());
+    }
+}
+
+fn unpack_borrow_rate_curve(
+    src: &[u8; BORROW_RATE_CURVE_LEN],
+) -> Result<BorrowRateCurve, ProgramError> {
+    #[allow(clippy::ptr_offset_with_cast)]
+    let (num_points, points) = array_refs![src, 1, MAX_BORROW_RATE_CURVE_POINTS * 4];
+    let num_points = u8::from_le_bytes(*num_points);
+    if num_points as usize > MAX_BORROW_RATE_CURVE_POINTS {
+        msg!("Borrow rate curve has too many points");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut curve = BorrowRateCurve::EMPTY;
+    curve.num_points = num_points;
+    for (point, src) in curve.points.iter_mut().zip(points.chunks_exact(4)) {
+        point.utilization_rate_bps = u16::from_le_bytes([src[0], src[1]]);
+        point.borrow_rate_bps = u16::from_le_bytes([src[2], src[3]]);
+    }
+    Ok(curve)
+}
+
 #[cfg(test)]
 mod test {
     use {
@@ -1367,4 +2003,121 @@ mod test {
         assert_eq!(total_fee, 10); // 1% of 1000
         assert_eq!(host_fee, 0); // 0 host fee
     }
+
+    #[test]
+    fn unpack_reserve_without_limits() {
+        let reserve = Reserve {
+            version: RESERVE_VERSION_WITHOUT_LIMITS,
+            config: ReserveConfig {
+                deposit_limit: 100,
+                borrow_limit: 50,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let mut data = [0u8; Reserve::LEN];
+        Reserve::pack(reserve, &mut data).unwrap();
+
+        let reserve = Reserve::unpack_from_slice(&data).unwrap();
+        assert_eq!(reserve.version, PROGRAM_VERSION);
+        assert_eq!(reserve.config.deposit_limit, u64::MAX);
+        assert_eq!(reserve.config.borrow_limit, u64::MAX);
+    }
+
+    fn point(utilization_rate_bps: u16, borrow_rate_bps: u16) -> BorrowRatePoint {
+        BorrowRatePoint {
+            utilization_rate_bps,
+            borrow_rate_bps,
+        }
+    }
+
+    #[test]
+    fn borrow_rate_curve() {
+        let curve = BorrowRateCurve::new(&[
+            point(0, 0),
+            point(8_000, 400),
+            point(9_000, 1_000),
+            point(9_000, 2_000),
+            point(10_000, 5_000),
+        ])
+        .unwrap();
+        curve.validate().unwrap();
+
+        let rate_at = |bps| curve.borrow_rate(Rate::from_bps(bps)).unwrap();
+        assert_eq!(rate_at(0), Rate::zero());
+        assert_eq!(rate_at(4_000), Rate::from_bps(200));
+        assert_eq!(rate_at(8_500), Rate::from_bps(700));
+        assert_eq!(rate_at(9_000), Rate::from_bps(1_000));
+        assert_eq!(rate_at(9_500), Rate::from_bps(3_500));
+        assert_eq!(rate_at(10_000), Rate::from_bps(5_000));
+
+        // the default curve through the optimal utilization rate
+        let config = ReserveConfig {
+            optimal_utilization_rate: 80,
+            min_borrow_rate: 1,
+            optimal_borrow_rate: 5,
+            max_borrow_rate: 20,
+            ..ReserveConfig::default()
+        };
+        assert_eq!(
+            config.effective_borrow_rate_curve().points(),
+            &[point(0, 100), point(8_000, 500), point(10_000, 2_000)]
+        );
+    }
+
+    #[test]
+    fn validate_borrow_rate_curve() {
+        let invalid_curves: [&[BorrowRatePoint]; 5] = [
+            &[point(0, 100)],
+            &[point(100, 100), point(10_000, 200)],
+            &[point(0, 100), point(9_000, 200)],
+            &[
+                point(0, 100),
+                point(5_000, 300),
+                point(4_000, 400),
+                point(10_000, 500),
+            ],
+            &[point(0, 100), point(5_000, 300), point(10_000, 200)],
+        ];
+        for points in invalid_curves {
+            assert_eq!(
+                BorrowRateCurve::new(points).unwrap().validate(),
+                Err(LendingError::InvalidConfig.into())
+            );
+        }
+
+        assert_eq!(
+            BorrowRateCurve::new(&[point(0, 0); MAX_BORROW_RATE_CURVE_POINTS + 1]),
+            Err(LendingError::InvalidConfig.into())
+        );
+    }
+
+    #[test]
+    fn pack_borrow_rate_curve() {
+        let reserve = Reserve {
+            version: PROGRAM_VERSION,
+            config: ReserveConfig {
+                borrow_rate_curve: BorrowRateCurve::new(&[
+                    point(0, 0),
+                    point(9_000, 1_000),
+                    point(10_000, 30_000),
+                ])
+                .unwrap(),
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let mut data = [0u8; Reserve::LEN];
+        Reserve::pack(reserve.clone(), &mut data).unwrap();
+        assert_eq!(Reserve::unpack_from_slice(&data).unwrap(), reserve);
+
+        let reserve = Reserve {
+            version: RESERVE_VERSION_WITHOUT_RATE_CURVE,
+            ..reserve
+        };
+        Reserve::pack(reserve, &mut data).unwrap();
+        let reserve = Reserve::unpack_from_slice(&data).unwrap();
+        assert_eq!(reserve.version, PROGRAM_VERSION);
+        assert!(reserve.config.borrow_rate_curve.is_empty());
+    }
 }
@@ -7,13 +7,16 @@ use {
     helpers::*,
     solana_program_test::*,
     solana_sdk::{
+        instruction::InstructionError,
         signature::{Keypair, Signer},
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError},
     },
     spl_token::instruction::approve,
     spl_token_lending::{
-        instruction::deposit_obligation_collateral, processor::process_instruction,
-        state::INITIAL_COLLATERAL_RATIO,
+        error::LendingError,
+        instruction::deposit_obligation_collateral,
+        processor::process_instruction,
+        state::{INITIAL_COLLATERAL_RATIO, MAX_OBLIGATION_RESERVES},
     },
 };
 
@@ -115,3 +118,130 @@ async fn test_success() {
         initial_user_collateral_balance - SOL_DEPOSIT_AMOUNT_LAMPORTS
     );
 }
+
+#[tokio::test]
+async fn test_resized_obligation() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    const SOL_DEPOSIT_AMOUNT_LAMPORTS: u64 = 10 * LAMPORTS_TO_SOL * INITIAL_COLLATERAL_RATIO;
+    const SOL_RESERVE_COLLATERAL_LAMPORTS: u64 = 2 * SOL_DEPOSIT_AMOUNT_LAMPORTS;
+
+    let user_accounts_owner = Keypair::new();
+    let user_transfer_authority = Keypair::new();
+
+    let lending_market = add_lending_market(&mut test);
+
+    let sol_oracle = add_sol_oracle(&mut test);
+    let sol_test_reserves: Vec<TestReserve> = (0..=MAX_OBLIGATION_RESERVES)
+        .map(|_| {
+            add_reserve(
+                &mut test,
+                &lending_market,
+                &sol_oracle,
+                &user_accounts_owner,
+                AddReserveArgs {
+                    user_liquidity_amount: SOL_RESERVE_COLLATERAL_LAMPORTS,
+                    liquidity_amount: SOL_RESERVE_COLLATERAL_LAMPORTS,
+                    liquidity_mint_decimals: 9,
+                    liquidity_mint_pubkey: spl_token::native_mint::id(),
+                    config: TEST_RESERVE_CONFIG,
+                    mark_fresh: true,
+                    ..AddReserveArgs::default()
+                },
+            )
+        })
+        .collect();
+    let (new_test_reserve, test_reserves) = sol_test_reserves.split_last().unwrap();
+    let deposits: Vec<(&TestReserve, u64)> = test_reserves
+        .iter()
+        .map(|test_reserve| (test_reserve, SOL_DEPOSIT_AMOUNT_LAMPORTS))
+        .collect();
+
+    let full_test_obligation = add_obligation(
+        &mut test,
+        &lending_market,
+        &user_accounts_owner,
+        AddObligationArgs {
+            deposits: &deposits,
+            ..AddObligationArgs::default()
+        },
+    );
+    let resized_test_obligation = add_obligation(
+        &mut test,
+        &lending_market,
+        &user_accounts_owner,
+        AddObligationArgs {
+            deposits: &deposits,
+            max_reserves: MAX_OBLIGATION_RESERVES + 1,
+            ..AddObligationArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let deposit_instructions = |test_obligation: &TestObligation| {
+        [
+            approve(
+                &spl_token::id(),
+                &new_test_reserve.user_collateral_pubkey,
+                &user_transfer_authority.pubkey(),
+                &user_accounts_owner.pubkey(),
+                &[],
+                SOL_DEPOSIT_AMOUNT_LAMPORTS,
+            )
+            .unwrap(),
+            deposit_obligation_collateral(
+                spl_token_lending::id(),
+                SOL_DEPOSIT_AMOUNT_LAMPORTS,
+                new_test_reserve.user_collateral_pubkey,
+                new_test_reserve.collateral_supply_pubkey,
+                new_test_reserve.pubkey,
+                test_obligation.pubkey,
+                lending_market.pubkey,
+                test_obligation.owner,
+                user_transfer_authority.pubkey(),
+            ),
+        ]
+    };
+
+    let mut transaction = Transaction::new_with_payer(
+        &deposit_instructions(&full_test_obligation),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &vec![&payer, &user_accounts_owner, &user_transfer_authority],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::ObligationReserveLimit as u32)
+        )
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &deposit_instructions(&resized_test_obligation),
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &vec![&payer, &user_accounts_owner, &user_transfer_authority],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let obligation = resized_test_obligation.get_state(&mut banks_client).await;
+    assert_eq!(obligation.deposits.len(), MAX_OBLIGATION_RESERVES + 1);
+    assert_eq!(
+        obligation.deposits[MAX_OBLIGATION_RESERVES].deposit_reserve,
+        new_test_reserve.pubkey
+    );
+}
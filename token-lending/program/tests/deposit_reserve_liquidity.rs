@@ -4,8 +4,18 @@
 mod helpers;
 
 use {
-    helpers::*, solana_program_test::*, solana_sdk::signature::Keypair,
-    spl_token_lending::processor::process_instruction,
+    helpers::*,
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_token::instruction::approve,
+    spl_token_lending::{
+        error::LendingError, instruction::deposit_reserve_liquidity,
+        processor::process_instruction, state::ReserveConfig,
+    },
 };
 
 #[tokio::test]
@@ -52,3 +62,87 @@ async fn test_success() {
         )
         .await;
 }
+
+#[tokio::test]
+async fn test_deposit_limit_exceeded() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    const USDC_RESERVE_LIQUIDITY_FRACTIONAL: u64 = 10_000 * FRACTIONAL_TO_USDC;
+    const USDC_DEPOSIT_AMOUNT_FRACTIONAL: u64 = 100 * FRACTIONAL_TO_USDC;
+
+    let user_accounts_owner = Keypair::new();
+    let user_transfer_authority = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let usdc_mint = add_usdc_mint(&mut test);
+    let usdc_oracle = add_usdc_oracle(&mut test);
+    let usdc_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &usdc_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            user_liquidity_amount: USDC_DEPOSIT_AMOUNT_FRACTIONAL,
+            liquidity_amount: USDC_RESERVE_LIQUIDITY_FRACTIONAL,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            config: ReserveConfig {
+                deposit_limit: USDC_RESERVE_LIQUIDITY_FRACTIONAL + USDC_DEPOSIT_AMOUNT_FRACTIONAL
+                    - 1,
+                ..TEST_RESERVE_CONFIG
+            },
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            approve(
+                &spl_token::id(),
+                &usdc_test_reserve.user_liquidity_pubkey,
+                &user_transfer_authority.pubkey(),
+                &user_accounts_owner.pubkey(),
+                &[],
+                USDC_DEPOSIT_AMOUNT_FRACTIONAL,
+            )
+            .unwrap(),
+            deposit_reserve_liquidity(
+                spl_token_lending::id(),
+                USDC_DEPOSIT_AMOUNT_FRACTIONAL,
+                usdc_test_reserve.user_liquidity_pubkey,
+                usdc_test_reserve.user_collateral_pubkey,
+                usdc_test_reserve.pubkey,
+                usdc_test_reserve.liquidity_supply_pubkey,
+                usdc_test_reserve.collateral_mint_pubkey,
+                lending_market.pubkey,
+                user_transfer_authority.pubkey(),
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+
+    transaction.sign(
+        &[&payer, &user_accounts_owner, &user_transfer_authority],
+        recent_blockhash,
+    );
+
+    // check that transaction fails
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::DepositLimitExceeded as u32)
+        )
+    );
+}
@@ -21,13 +21,15 @@ use {
             borrow_obligation_liquidity, deposit_reserve_liquidity, init_lending_market,
             init_obligation, init_reserve, liquidate_obligation, refresh_reserve,
         },
-        math::{Decimal, Rate, TryAdd, TryMul},
+        math::{Decimal, Rate, TryAdd, TryDiv, TryMul},
         pyth,
         state::{
-            InitLendingMarketParams, InitObligationParams, InitReserveParams, LendingMarket,
-            NewReserveCollateralParams, NewReserveLiquidityParams, Obligation,
-            ObligationCollateral, ObligationLiquidity, Reserve, ReserveCollateral, ReserveConfig,
-            ReserveFees, ReserveLiquidity, INITIAL_COLLATERAL_RATIO, PROGRAM_VERSION,
+            BorrowRateCurve, FixedPrice, InitFixedPriceParams, InitLendingMarketParams,
+            InitObligationParams, InitReserveParams, LendingMarket, NewReserveCollateralParams,
+            NewReserveLiquidityParams, Obligation, ObligationCollateral, ObligationLiquidity,
+            OracleSource, Reserve, ReserveCollateral, ReserveConfig, ReserveFees, ReserveLiquidity,
+            TokenSwapOracle, INITIAL_COLLATERAL_RATIO, MAX_ISOLATED_BORROW_RESERVES,
+            PROGRAM_VERSION,
         },
     },
     std::{convert::TryInto, str::FromStr},
@@ -54,6 +56,17 @@ pub const TEST_RESERVE_CONFIG: ReserveConfig = ReserveConfig {
         flash_loan_fee_wad: 3_000_000_000_000_000,
         host_fee_percentage: 20,
     },
+    max_price_age_slots: 0,
+    max_confidence_bps: 0,
+    min_twap_window_seconds: 0,
+    deposit_limit: u64::MAX,
+    borrow_limit: u64::MAX,
+    protocol_liquidation_fee: 0,
+    borrow_rate_curve: BorrowRateCurve::EMPTY,
+    elevation_group: 0,
+    elevation_group_loan_to_value_ratio: 0,
+    elevation_group_liquidation_threshold: 0,
+    isolated_borrow_reserves: [Pubkey::new_from_array([0; 32]); MAX_ISOLATED_BORROW_RESERVES],
 };
 
 pub const SOL_PYTH_PRODUCT: &str = "3Mnn2fX6rQyUsyELYms1sBJyChWofzSNRoqYzvgMVz5E";
@@ -89,6 +102,13 @@ impl AddPacked for ProgramTest {
 }
 
 pub fn add_lending_market(test: &mut ProgramTest) -> TestLendingMarket {
+    add_lending_market_with_quote_currency(test, QUOTE_CURRENCY)
+}
+
+pub fn add_lending_market_with_quote_currency(
+    test: &mut ProgramTest,
+    quote_currency: [u8; 32],
+) -> TestLendingMarket {
     let lending_market_pubkey = Pubkey::new_unique();
     let (lending_market_authority, bump_seed) =
         Pubkey::find_program_address(&[lending_market_pubkey.as_ref()], &spl_token_lending::id());
@@ -105,7 +125,7 @@ pub fn add_lending_market(test: &mut ProgramTest) -> TestLendingMarket {
         &LendingMarket::new(InitLendingMarketParams {
             bump_seed,
             owner: lending_market_owner.pubkey(),
-            quote_currency: QUOTE_CURRENCY,
+            quote_currency,
             token_program_id: spl_token::id(),
             oracle_program_id,
         }),
@@ -116,7 +136,7 @@ pub fn add_lending_market(test: &mut ProgramTest) -> TestLendingMarket {
         pubkey: lending_market_pubkey,
         owner: lending_market_owner,
         authority: lending_market_authority,
-        quote_currency: QUOTE_CURRENCY,
+        quote_currency,
         oracle_program_id,
     }
 }
@@ -127,6 +147,9 @@ pub struct AddObligationArgs<'a> {
     pub borrows: &'a [(&'a TestReserve, u64)],
     pub mark_fresh: bool,
     pub slots_elapsed: u64,
+    /// Max number of deposits and borrows combined, 0 for the default
+    /// obligation account size
+    pub max_reserves: usize,
 }
 
 pub fn add_obligation(
@@ -140,6 +163,7 @@ pub fn add_obligation(
         borrows,
         mark_fresh,
         slots_elapsed,
+        max_reserves,
     } = args;
 
     let obligation_keypair = Keypair::new();
@@ -196,12 +220,22 @@ pub fn add_obligation(
         obligation.last_update.update_slot(current_slot);
     }
 
-    test.add_packable_account(
-        obligation_pubkey,
-        u32::MAX as u64,
-        &obligation,
-        &spl_token_lending::id(),
-    );
+    if max_reserves == 0 {
+        test.add_packable_account(
+            obligation_pubkey,
+            u32::MAX as u64,
+            &obligation,
+            &spl_token_lending::id(),
+        );
+    } else {
+        let mut account = Account::new(
+            u32::MAX as u64,
+            Obligation::account_len(max_reserves),
+            &spl_token_lending::id(),
+        );
+        Obligation::pack_resizable(obligation, &mut account.data).unwrap();
+        test.add_account(obligation_pubkey, account);
+    }
 
     TestObligation {
         pubkey: obligation_pubkey,
@@ -344,7 +378,10 @@ pub fn add_reserve(
             supply_pubkey: liquidity_supply_pubkey,
             fee_receiver: liquidity_fee_receiver_pubkey,
             oracle_pubkey: oracle.price_pubkey,
+            oracle_source: oracle.source,
+            token_swap_oracle: TokenSwapOracle::default(),
             market_price: oracle.price,
+            market_price_slot: current_slot,
         }),
         collateral: ReserveCollateral::new(NewReserveCollateralParams {
             mint_pubkey: collateral_mint_pubkey,
@@ -619,6 +656,9 @@ impl TestLendingMarket {
                     repay_reserve.liquidity_supply_pubkey,
                     withdraw_reserve.pubkey,
                     withdraw_reserve.collateral_supply_pubkey,
+                    withdraw_reserve.collateral_mint_pubkey,
+                    withdraw_reserve.liquidity_supply_pubkey,
+                    withdraw_reserve.liquidity_fee_receiver_pubkey,
                     obligation.pubkey,
                     self.pubkey,
                     user_transfer_authority.pubkey(),
@@ -803,6 +843,7 @@ impl TestReserve {
                     spl_token_lending::id(),
                     liquidity_amount,
                     config,
+                    oracle.source,
                     user_liquidity_pubkey,
                     user_collateral_token_keypair.pubkey(),
                     reserve_pubkey,
@@ -912,6 +953,13 @@ pub struct TestObligation {
 }
 
 impl TestObligation {
+    pub fn deposit_reserves(&self) -> Vec<Pubkey> {
+        self.deposits
+            .iter()
+            .map(|collateral| collateral.deposit_reserve)
+            .collect()
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub async fn init(
         banks_client: &mut BanksClient,
@@ -968,7 +1016,7 @@ impl TestObligation {
             .await
             .unwrap()
             .unwrap();
-        Obligation::unpack(&obligation_account.data[..]).unwrap()
+        Obligation::unpack_resizable(&obligation_account.data[..]).unwrap()
     }
 
     pub async fn validate_state(&self, banks_client: &mut BanksClient) {
@@ -993,7 +1041,7 @@ impl TestObligationCollateral {
             .await
             .unwrap()
             .unwrap();
-        Obligation::unpack(&obligation_account.data[..]).unwrap()
+        Obligation::unpack_resizable(&obligation_account.data[..]).unwrap()
     }
 
     pub async fn validate_state(&self, banks_client: &mut BanksClient) {
@@ -1021,7 +1069,7 @@ impl TestObligationLiquidity {
             .await
             .unwrap()
             .unwrap();
-        Obligation::unpack(&obligation_account.data[..]).unwrap()
+        Obligation::unpack_resizable(&obligation_account.data[..]).unwrap()
     }
 
     pub async fn validate_state(&self, banks_client: &mut BanksClient) {
@@ -1064,6 +1112,7 @@ pub fn add_usdc_mint(test: &mut ProgramTest) -> TestMint {
 }
 
 pub struct TestOracle {
+    pub source: OracleSource,
     pub product_pubkey: Pubkey,
     pub price_pubkey: Pubkey,
     pub price: Decimal,
@@ -1139,12 +1188,46 @@ pub fn add_oracle(
     );
 
     TestOracle {
+        source: OracleSource::Pyth,
         product_pubkey,
         price_pubkey,
         price,
     }
 }
 
+pub fn add_fixed_price_oracle(
+    test: &mut ProgramTest,
+    authority: Pubkey,
+    price: u64,
+    expo: i32,
+    confidence: u64,
+) -> TestOracle {
+    let price_pubkey = Pubkey::new_unique();
+    let mut fixed_price = FixedPrice::new(InitFixedPriceParams {
+        authority,
+        quote_currency: QUOTE_CURRENCY,
+    });
+    fixed_price.set_price(price, expo, confidence, 0);
+    test.add_packable_account(
+        price_pubkey,
+        u32::MAX as u64,
+        &fixed_price,
+        &spl_token_lending::id(),
+    );
+
+    let decimals = 10u64.pow(expo.unsigned_abs());
+    TestOracle {
+        source: OracleSource::FixedPriceAuthority,
+        product_pubkey: price_pubkey,
+        price_pubkey,
+        price: if expo < 0 {
+            Decimal::from(price).try_div(decimals).unwrap()
+        } else {
+            Decimal::from(price).try_mul(decimals).unwrap()
+        },
+    }
+}
+
 pub async fn create_and_mint_to_token_account(
     banks_client: &mut BanksClient,
     mint_pubkey: Pubkey,
@@ -114,6 +114,7 @@ async fn test_already_initialized() {
             spl_token_lending::id(),
             42,
             usdc_test_reserve.config,
+            usdc_oracle.source,
             usdc_test_reserve.user_liquidity_pubkey,
             usdc_test_reserve.user_collateral_pubkey,
             usdc_test_reserve.pubkey,
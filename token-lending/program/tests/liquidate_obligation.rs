@@ -7,12 +7,14 @@ use {
     helpers::*,
     solana_program_test::*,
     solana_sdk::{
+        instruction::InstructionError,
         signature::{Keypair, Signer},
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError},
     },
     spl_token::instruction::approve,
     spl_token_lending::{
-        instruction::{liquidate_obligation, refresh_obligation},
+        error::LendingError,
+        instruction::{liquidate_obligation, refresh_obligation, set_liquidation_close_factor},
         processor::process_instruction,
         state::INITIAL_COLLATERAL_RATIO,
     },
@@ -107,6 +109,26 @@ async fn test_success() {
     let initial_collateral_supply_balance =
         get_token_balance(&mut banks_client, sol_test_reserve.collateral_supply_pubkey).await;
 
+    // the protocol liquidation fee accounts are not needed when the withdraw
+    // reserve has no protocol liquidation fee
+    let mut liquidate_instruction = liquidate_obligation(
+        spl_token_lending::id(),
+        USDC_LIQUIDATION_AMOUNT_FRACTIONAL,
+        usdc_test_reserve.user_liquidity_pubkey,
+        sol_test_reserve.user_collateral_pubkey,
+        usdc_test_reserve.pubkey,
+        usdc_test_reserve.liquidity_supply_pubkey,
+        sol_test_reserve.pubkey,
+        sol_test_reserve.collateral_supply_pubkey,
+        sol_test_reserve.collateral_mint_pubkey,
+        sol_test_reserve.liquidity_supply_pubkey,
+        sol_test_reserve.liquidity_fee_receiver_pubkey,
+        test_obligation.pubkey,
+        lending_market.pubkey,
+        user_transfer_authority.pubkey(),
+    );
+    liquidate_instruction.accounts.truncate(12);
+
     let mut transaction = Transaction::new_with_payer(
         &[
             approve(
@@ -123,19 +145,7 @@ async fn test_success() {
                 test_obligation.pubkey,
                 vec![sol_test_reserve.pubkey, usdc_test_reserve.pubkey],
             ),
-            liquidate_obligation(
-                spl_token_lending::id(),
-                USDC_LIQUIDATION_AMOUNT_FRACTIONAL,
-                usdc_test_reserve.user_liquidity_pubkey,
-                sol_test_reserve.user_collateral_pubkey,
-                usdc_test_reserve.pubkey,
-                usdc_test_reserve.liquidity_supply_pubkey,
-                sol_test_reserve.pubkey,
-                sol_test_reserve.collateral_supply_pubkey,
-                test_obligation.pubkey,
-                lending_market.pubkey,
-                user_transfer_authority.pubkey(),
-            ),
+            liquidate_instruction,
         ],
         Some(&payer.pubkey()),
     );
@@ -184,3 +194,487 @@ async fn test_success() {
         (USDC_BORROW_AMOUNT_FRACTIONAL - USDC_LIQUIDATION_AMOUNT_FRACTIONAL).into()
     )
 }
+
+#[tokio::test]
+async fn test_protocol_liquidation_fee() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    // 100 SOL collateral
+    const SOL_DEPOSIT_AMOUNT_LAMPORTS: u64 = 100 * LAMPORTS_TO_SOL * INITIAL_COLLATERAL_RATIO;
+    // 100 SOL * 80% LTV -> 80 SOL * 20 USDC -> 1600 USDC borrow
+    const USDC_BORROW_AMOUNT_FRACTIONAL: u64 = 1_600 * FRACTIONAL_TO_USDC;
+    // 1600 USDC * 50% -> 800 USDC liquidation
+    const USDC_LIQUIDATION_AMOUNT_FRACTIONAL: u64 = USDC_BORROW_AMOUNT_FRACTIONAL / 2;
+    // 800 USDC / 20 USDC per SOL -> 40 SOL + 10% bonus -> 44 SOL
+    const SOL_LIQUIDATION_AMOUNT_LAMPORTS: u64 = 44 * LAMPORTS_TO_SOL * INITIAL_COLLATERAL_RATIO;
+    // 4 SOL bonus * 50% protocol fee -> 2 SOL
+    const SOL_PROTOCOL_FEE_LAMPORTS: u64 = 2 * LAMPORTS_TO_SOL;
+
+    const SOL_RESERVE_LIQUIDITY_LAMPORTS: u64 = 2 * SOL_DEPOSIT_AMOUNT_LAMPORTS;
+    const USDC_RESERVE_LIQUIDITY_FRACTIONAL: u64 = 2 * USDC_BORROW_AMOUNT_FRACTIONAL;
+
+    let user_accounts_owner = Keypair::new();
+    let user_transfer_authority = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let mut reserve_config = TEST_RESERVE_CONFIG;
+    reserve_config.loan_to_value_ratio = 50;
+    reserve_config.liquidation_threshold = 80;
+    reserve_config.liquidation_bonus = 10;
+    reserve_config.protocol_liquidation_fee = 50;
+
+    let sol_oracle = add_sol_oracle(&mut test);
+    let sol_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &sol_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            collateral_amount: SOL_RESERVE_LIQUIDITY_LAMPORTS * INITIAL_COLLATERAL_RATIO,
+            liquidity_amount: SOL_RESERVE_LIQUIDITY_LAMPORTS,
+            liquidity_mint_pubkey: spl_token::native_mint::id(),
+            liquidity_mint_decimals: 9,
+            config: reserve_config,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let usdc_mint = add_usdc_mint(&mut test);
+    let usdc_oracle = add_usdc_oracle(&mut test);
+    let usdc_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &usdc_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            borrow_amount: USDC_BORROW_AMOUNT_FRACTIONAL,
+            user_liquidity_amount: USDC_BORROW_AMOUNT_FRACTIONAL,
+            liquidity_amount: USDC_RESERVE_LIQUIDITY_FRACTIONAL,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            config: reserve_config,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let test_obligation = add_obligation(
+        &mut test,
+        &lending_market,
+        &user_accounts_owner,
+        AddObligationArgs {
+            deposits: &[(&sol_test_reserve, SOL_DEPOSIT_AMOUNT_LAMPORTS)],
+            borrows: &[(&usdc_test_reserve, USDC_BORROW_AMOUNT_FRACTIONAL)],
+            ..AddObligationArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let initial_user_collateral_balance =
+        get_token_balance(&mut banks_client, sol_test_reserve.user_collateral_pubkey).await;
+    let initial_collateral_supply_balance =
+        get_token_balance(&mut banks_client, sol_test_reserve.collateral_supply_pubkey).await;
+    let initial_fee_receiver_balance = get_token_balance(
+        &mut banks_client,
+        sol_test_reserve.liquidity_fee_receiver_pubkey,
+    )
+    .await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            approve(
+                &spl_token::id(),
+                &usdc_test_reserve.user_liquidity_pubkey,
+                &user_transfer_authority.pubkey(),
+                &user_accounts_owner.pubkey(),
+                &[],
+                USDC_LIQUIDATION_AMOUNT_FRACTIONAL,
+            )
+            .unwrap(),
+            refresh_obligation(
+                spl_token_lending::id(),
+                test_obligation.pubkey,
+                vec![sol_test_reserve.pubkey, usdc_test_reserve.pubkey],
+            ),
+            liquidate_obligation(
+                spl_token_lending::id(),
+                USDC_LIQUIDATION_AMOUNT_FRACTIONAL,
+                usdc_test_reserve.user_liquidity_pubkey,
+                sol_test_reserve.user_collateral_pubkey,
+                usdc_test_reserve.pubkey,
+                usdc_test_reserve.liquidity_supply_pubkey,
+                sol_test_reserve.pubkey,
+                sol_test_reserve.collateral_supply_pubkey,
+                sol_test_reserve.collateral_mint_pubkey,
+                sol_test_reserve.liquidity_supply_pubkey,
+                sol_test_reserve.liquidity_fee_receiver_pubkey,
+                test_obligation.pubkey,
+                lending_market.pubkey,
+                user_transfer_authority.pubkey(),
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+
+    transaction.sign(
+        &[&payer, &user_accounts_owner, &user_transfer_authority],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let user_collateral_balance =
+        get_token_balance(&mut banks_client, sol_test_reserve.user_collateral_pubkey).await;
+    assert_eq!(
+        user_collateral_balance,
+        initial_user_collateral_balance + SOL_LIQUIDATION_AMOUNT_LAMPORTS
+            - SOL_PROTOCOL_FEE_LAMPORTS * INITIAL_COLLATERAL_RATIO
+    );
+
+    let collateral_supply_balance =
+        get_token_balance(&mut banks_client, sol_test_reserve.collateral_supply_pubkey).await;
+    assert_eq!(
+        collateral_supply_balance,
+        initial_collateral_supply_balance - SOL_LIQUIDATION_AMOUNT_LAMPORTS
+    );
+
+    let fee_receiver_balance = get_token_balance(
+        &mut banks_client,
+        sol_test_reserve.liquidity_fee_receiver_pubkey,
+    )
+    .await;
+    assert_eq!(
+        fee_receiver_balance,
+        initial_fee_receiver_balance + SOL_PROTOCOL_FEE_LAMPORTS
+    );
+
+    let sol_reserve = sol_test_reserve.get_state(&mut banks_client).await;
+    assert_eq!(
+        sol_reserve.liquidity.available_amount,
+        SOL_RESERVE_LIQUIDITY_LAMPORTS - SOL_PROTOCOL_FEE_LAMPORTS
+    );
+
+    let obligation = test_obligation.get_state(&mut banks_client).await;
+    assert_eq!(
+        obligation.deposits[0].deposited_amount,
+        SOL_DEPOSIT_AMOUNT_LAMPORTS - SOL_LIQUIDATION_AMOUNT_LAMPORTS
+    );
+}
+
+#[tokio::test]
+async fn test_protocol_liquidation_fee_insufficient_liquidity() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    // 100 SOL collateral
+    const SOL_DEPOSIT_AMOUNT_LAMPORTS: u64 = 100 * LAMPORTS_TO_SOL * INITIAL_COLLATERAL_RATIO;
+    // 100 SOL * 80% LTV -> 80 SOL * 20 USDC -> 1600 USDC borrow
+    const USDC_BORROW_AMOUNT_FRACTIONAL: u64 = 1_600 * FRACTIONAL_TO_USDC;
+    // 1600 USDC * 50% -> 800 USDC liquidation -> 2 SOL protocol fee
+    const USDC_LIQUIDATION_AMOUNT_FRACTIONAL: u64 = USDC_BORROW_AMOUNT_FRACTIONAL / 2;
+
+    // only 1 SOL of the withdraw reserve liquidity is left to pay the fee
+    const SOL_RESERVE_LIQUIDITY_LAMPORTS: u64 = 2 * SOL_DEPOSIT_AMOUNT_LAMPORTS;
+    const SOL_BORROW_AMOUNT_LAMPORTS: u64 = SOL_RESERVE_LIQUIDITY_LAMPORTS - LAMPORTS_TO_SOL;
+    const USDC_RESERVE_LIQUIDITY_FRACTIONAL: u64 = 2 * USDC_BORROW_AMOUNT_FRACTIONAL;
+
+    let user_accounts_owner = Keypair::new();
+    let user_transfer_authority = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let mut reserve_config = TEST_RESERVE_CONFIG;
+    reserve_config.loan_to_value_ratio = 50;
+    reserve_config.liquidation_threshold = 80;
+    reserve_config.liquidation_bonus = 10;
+    reserve_config.protocol_liquidation_fee = 50;
+
+    let sol_oracle = add_sol_oracle(&mut test);
+    let sol_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &sol_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            collateral_amount: SOL_RESERVE_LIQUIDITY_LAMPORTS * INITIAL_COLLATERAL_RATIO,
+            liquidity_amount: SOL_RESERVE_LIQUIDITY_LAMPORTS,
+            borrow_amount: SOL_BORROW_AMOUNT_LAMPORTS,
+            liquidity_mint_pubkey: spl_token::native_mint::id(),
+            liquidity_mint_decimals: 9,
+            config: reserve_config,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let usdc_mint = add_usdc_mint(&mut test);
+    let usdc_oracle = add_usdc_oracle(&mut test);
+    let usdc_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &usdc_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            borrow_amount: USDC_BORROW_AMOUNT_FRACTIONAL,
+            user_liquidity_amount: USDC_BORROW_AMOUNT_FRACTIONAL,
+            liquidity_amount: USDC_RESERVE_LIQUIDITY_FRACTIONAL,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            config: reserve_config,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let test_obligation = add_obligation(
+        &mut test,
+        &lending_market,
+        &user_accounts_owner,
+        AddObligationArgs {
+            deposits: &[(&sol_test_reserve, SOL_DEPOSIT_AMOUNT_LAMPORTS)],
+            borrows: &[(&usdc_test_reserve, USDC_BORROW_AMOUNT_FRACTIONAL)],
+            ..AddObligationArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let liquidate_instruction = liquidate_obligation(
+        spl_token_lending::id(),
+        USDC_LIQUIDATION_AMOUNT_FRACTIONAL,
+        usdc_test_reserve.user_liquidity_pubkey,
+        sol_test_reserve.user_collateral_pubkey,
+        usdc_test_reserve.pubkey,
+        usdc_test_reserve.liquidity_supply_pubkey,
+        sol_test_reserve.pubkey,
+        sol_test_reserve.collateral_supply_pubkey,
+        sol_test_reserve.collateral_mint_pubkey,
+        sol_test_reserve.liquidity_supply_pubkey,
+        sol_test_reserve.liquidity_fee_receiver_pubkey,
+        test_obligation.pubkey,
+        lending_market.pubkey,
+        user_transfer_authority.pubkey(),
+    );
+    let approve_instruction = approve(
+        &spl_token::id(),
+        &usdc_test_reserve.user_liquidity_pubkey,
+        &user_transfer_authority.pubkey(),
+        &user_accounts_owner.pubkey(),
+        &[],
+        USDC_LIQUIDATION_AMOUNT_FRACTIONAL,
+    )
+    .unwrap();
+    let refresh_instruction = refresh_obligation(
+        spl_token_lending::id(),
+        test_obligation.pubkey,
+        vec![sol_test_reserve.pubkey, usdc_test_reserve.pubkey],
+    );
+
+    // the protocol liquidation fee accounts are required when the withdraw
+    // reserve has a protocol liquidation fee
+    let mut truncated_liquidate_instruction = liquidate_instruction.clone();
+    truncated_liquidate_instruction.accounts.truncate(12);
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            approve_instruction.clone(),
+            refresh_instruction.clone(),
+            truncated_liquidate_instruction,
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &[&payer, &user_accounts_owner, &user_transfer_authority],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(2, InstructionError::NotEnoughAccountKeys)
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[approve_instruction, refresh_instruction, liquidate_instruction],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &[&payer, &user_accounts_owner, &user_transfer_authority],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(LendingError::InsufficientLiquidity as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_liquidation_close_factor() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    // 100 SOL collateral
+    const SOL_DEPOSIT_AMOUNT_LAMPORTS: u64 = 100 * LAMPORTS_TO_SOL * INITIAL_COLLATERAL_RATIO;
+    // 100 SOL * 80% LTV -> 80 SOL * 20 USDC -> 1600 USDC borrow
+    const USDC_BORROW_AMOUNT_FRACTIONAL: u64 = 1_600 * FRACTIONAL_TO_USDC;
+    // 1600 USDC * 25% -> 400 USDC liquidation
+    const USDC_LIQUIDATION_AMOUNT_FRACTIONAL: u64 = USDC_BORROW_AMOUNT_FRACTIONAL / 4;
+    // 400 USDC / 20 USDC per SOL -> 20 SOL + 10% bonus -> 22 SOL
+    const SOL_LIQUIDATION_AMOUNT_LAMPORTS: u64 = 22 * LAMPORTS_TO_SOL * INITIAL_COLLATERAL_RATIO;
+
+    const SOL_RESERVE_COLLATERAL_LAMPORTS: u64 = 2 * SOL_DEPOSIT_AMOUNT_LAMPORTS;
+    const USDC_RESERVE_LIQUIDITY_FRACTIONAL: u64 = 2 * USDC_BORROW_AMOUNT_FRACTIONAL;
+
+    let user_accounts_owner = Keypair::new();
+    let user_transfer_authority = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let mut reserve_config = TEST_RESERVE_CONFIG;
+    reserve_config.loan_to_value_ratio = 50;
+    reserve_config.liquidation_threshold = 80;
+    reserve_config.liquidation_bonus = 10;
+
+    let sol_oracle = add_sol_oracle(&mut test);
+    let sol_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &sol_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            collateral_amount: SOL_RESERVE_COLLATERAL_LAMPORTS,
+            liquidity_mint_pubkey: spl_token::native_mint::id(),
+            liquidity_mint_decimals: 9,
+            config: reserve_config,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let usdc_mint = add_usdc_mint(&mut test);
+    let usdc_oracle = add_usdc_oracle(&mut test);
+    let usdc_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &usdc_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            borrow_amount: USDC_BORROW_AMOUNT_FRACTIONAL,
+            user_liquidity_amount: USDC_BORROW_AMOUNT_FRACTIONAL,
+            liquidity_amount: USDC_RESERVE_LIQUIDITY_FRACTIONAL,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            config: reserve_config,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let test_obligation = add_obligation(
+        &mut test,
+        &lending_market,
+        &user_accounts_owner,
+        AddObligationArgs {
+            deposits: &[(&sol_test_reserve, SOL_DEPOSIT_AMOUNT_LAMPORTS)],
+            borrows: &[(&usdc_test_reserve, USDC_BORROW_AMOUNT_FRACTIONAL)],
+            ..AddObligationArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let initial_user_liquidity_balance =
+        get_token_balance(&mut banks_client, usdc_test_reserve.user_liquidity_pubkey).await;
+    let initial_user_collateral_balance =
+        get_token_balance(&mut banks_client, sol_test_reserve.user_collateral_pubkey).await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            set_liquidation_close_factor(
+                spl_token_lending::id(),
+                lending_market.pubkey,
+                lending_market.owner.pubkey(),
+                25,
+            ),
+            approve(
+                &spl_token::id(),
+                &usdc_test_reserve.user_liquidity_pubkey,
+                &user_transfer_authority.pubkey(),
+                &user_accounts_owner.pubkey(),
+                &[],
+                USDC_BORROW_AMOUNT_FRACTIONAL,
+            )
+            .unwrap(),
+            refresh_obligation(
+                spl_token_lending::id(),
+                test_obligation.pubkey,
+                vec![sol_test_reserve.pubkey, usdc_test_reserve.pubkey],
+            ),
+            liquidate_obligation(
+                spl_token_lending::id(),
+                u64::MAX,
+                usdc_test_reserve.user_liquidity_pubkey,
+                sol_test_reserve.user_collateral_pubkey,
+                usdc_test_reserve.pubkey,
+                usdc_test_reserve.liquidity_supply_pubkey,
+                sol_test_reserve.pubkey,
+                sol_test_reserve.collateral_supply_pubkey,
+                sol_test_reserve.collateral_mint_pubkey,
+                sol_test_reserve.liquidity_supply_pubkey,
+                sol_test_reserve.liquidity_fee_receiver_pubkey,
+                test_obligation.pubkey,
+                lending_market.pubkey,
+                user_transfer_authority.pubkey(),
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+
+    transaction.sign(
+        &[
+            &payer,
+            &lending_market.owner,
+            &user_accounts_owner,
+            &user_transfer_authority,
+        ],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let user_liquidity_balance =
+        get_token_balance(&mut banks_client, usdc_test_reserve.user_liquidity_pubkey).await;
+    assert_eq!(
+        user_liquidity_balance,
+        initial_user_liquidity_balance - USDC_LIQUIDATION_AMOUNT_FRACTIONAL
+    );
+
+    let user_collateral_balance =
+        get_token_balance(&mut banks_client, sol_test_reserve.user_collateral_pubkey).await;
+    assert_eq!(
+        user_collateral_balance,
+        initial_user_collateral_balance + SOL_LIQUIDATION_AMOUNT_LAMPORTS
+    );
+
+    let obligation = test_obligation.get_state(&mut banks_client).await;
+    assert_eq!(
+        obligation.borrows[0].borrowed_amount_wads,
+        (USDC_BORROW_AMOUNT_FRACTIONAL - USDC_LIQUIDATION_AMOUNT_FRACTIONAL).into()
+    )
+}
@@ -17,8 +17,8 @@ use {
         instruction::modify_reserve_config,
         processor::process_instruction,
         state::{
-            InitLendingMarketParams, LendingMarket, ReserveConfig, ReserveFees,
-            INITIAL_COLLATERAL_RATIO,
+            BorrowRateCurve, BorrowRatePoint, InitLendingMarketParams, LendingMarket,
+            ReserveConfig, ReserveFees, INITIAL_COLLATERAL_RATIO,
         },
     },
 };
@@ -74,6 +74,35 @@ async fn modify_reserve_config_success() {
             flash_loan_fee_wad: 3_000_000_000_000_000,
             host_fee_percentage: 20,
         },
+        max_price_age_slots: 10,
+        max_confidence_bps: 100,
+        min_twap_window_seconds: 600,
+        deposit_limit: 1_000_000,
+        borrow_limit: 500_000,
+        protocol_liquidation_fee: 10,
+        borrow_rate_curve: BorrowRateCurve::new(&[
+            BorrowRatePoint {
+                utilization_rate_bps: 0,
+                borrow_rate_bps: 0,
+            },
+            BorrowRatePoint {
+                utilization_rate_bps: 7_000,
+                borrow_rate_bps: 400,
+            },
+            BorrowRatePoint {
+                utilization_rate_bps: 9_000,
+                borrow_rate_bps: 1_000,
+            },
+            BorrowRatePoint {
+                utilization_rate_bps: 10_000,
+                borrow_rate_bps: 3_000,
+            },
+        ])
+        .unwrap(),
+        elevation_group: 1,
+        elevation_group_loan_to_value_ratio: 80,
+        elevation_group_liquidation_threshold: 90,
+        isolated_borrow_reserves: [Pubkey::new_unique(), Pubkey::default(), Pubkey::default()],
     };
 
     let mut transaction = Transaction::new_with_payer(
@@ -155,6 +184,7 @@ async fn wrong_signer_of_lending_market_cannot_change_reserve_config() {
             flash_loan_fee_wad: 3_000_000_000_000_000,
             host_fee_percentage: 20,
         },
+        ..TEST_RESERVE_CONFIG
     };
 
     let mut instruction = modify_reserve_config(
@@ -269,6 +299,7 @@ async fn owner_of_different_lending_market_cannot_change_reserve_config() {
             flash_loan_fee_wad: 3_000_000_000_000_000,
             host_fee_percentage: 20,
         },
+        ..TEST_RESERVE_CONFIG
     };
 
     let mut transaction = Transaction::new_with_payer(
@@ -359,6 +390,7 @@ async fn correct_owner_providing_wrong_lending_market_fails() {
             flash_loan_fee_wad: 3_000_000_000_000_000,
             host_fee_percentage: 20,
         },
+        ..TEST_RESERVE_CONFIG
     };
 
     let mut transaction = Transaction::new_with_payer(
@@ -251,7 +251,7 @@ async fn test_success() {
             .await
             .unwrap()
             .unwrap();
-        Obligation::unpack(&obligation_account.data[..]).unwrap()
+        Obligation::unpack_resizable(&obligation_account.data[..]).unwrap()
     };
 
     let collateral_supply_balance =
@@ -7,14 +7,16 @@ use {
     helpers::*,
     solana_program_test::*,
     solana_sdk::{
+        instruction::InstructionError,
         signature::{Keypair, Signer},
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError},
     },
     spl_token_lending::{
-        instruction::{refresh_obligation, refresh_reserve},
+        error::LendingError,
+        instruction::{modify_reserve_config, refresh_obligation, refresh_reserve},
         math::{Decimal, Rate, TryAdd, TryDiv, TryMul},
         processor::process_instruction,
-        state::{INITIAL_COLLATERAL_RATIO, SLOTS_PER_YEAR},
+        state::{INITIAL_COLLATERAL_RATIO, MAX_OBLIGATION_RESERVES, SLOTS_PER_YEAR},
     },
 };
 
@@ -164,3 +166,213 @@ async fn test_success() {
     assert_eq!(sol_reserve.liquidity.market_price, collateral_price,);
     assert_eq!(usdc_reserve.liquidity.market_price, liquidity_price,);
 }
+
+#[tokio::test]
+async fn test_stale_oracle_price() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    const SOL_DEPOSIT_AMOUNT_LAMPORTS: u64 = 100 * LAMPORTS_TO_SOL * INITIAL_COLLATERAL_RATIO;
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let mut reserve_config = TEST_RESERVE_CONFIG;
+    reserve_config.max_price_age_slots = 5;
+
+    // price set at slot 0
+    let sol_oracle = add_fixed_price_oracle(&mut test, Keypair::new().pubkey(), 20, 0, 0);
+    let sol_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &sol_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            collateral_amount: 2 * SOL_DEPOSIT_AMOUNT_LAMPORTS,
+            liquidity_mint_decimals: 9,
+            liquidity_mint_pubkey: spl_token::native_mint::id(),
+            config: reserve_config,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let test_obligation = add_obligation(
+        &mut test,
+        &lending_market,
+        &user_accounts_owner,
+        AddObligationArgs {
+            deposits: &[(&sol_test_reserve, SOL_DEPOSIT_AMOUNT_LAMPORTS)],
+            ..AddObligationArgs::default()
+        },
+    );
+
+    let mut test_context = test.start_with_context().await;
+    test_context.warp_to_slot(3).unwrap(); // clock.slot = 3
+
+    let ProgramTestContext {
+        mut banks_client,
+        payer,
+        last_blockhash: recent_blockhash,
+        ..
+    } = test_context;
+
+    // tightening the price age limit after the refresh leaves the reserve
+    // fresh, but its price too old for the obligation
+    let mut new_config = reserve_config;
+    new_config.max_price_age_slots = 1;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            refresh_reserve(
+                spl_token_lending::id(),
+                sol_test_reserve.pubkey,
+                sol_oracle.price_pubkey,
+            ),
+            modify_reserve_config(
+                spl_token_lending::id(),
+                new_config,
+                sol_test_reserve.pubkey,
+                lending_market.pubkey,
+                lending_market.owner.pubkey(),
+            ),
+            refresh_obligation(
+                spl_token_lending::id(),
+                test_obligation.pubkey,
+                vec![sol_test_reserve.pubkey],
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &lending_market.owner], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(LendingError::StaleOraclePrice as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_resized_obligation() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    // refreshing more than the default number of positions needs a compute
+    // budget above the per-instruction default
+    test.set_compute_max_units(400_000);
+
+    const SOL_DEPOSIT_AMOUNT: u64 = 10;
+    const USDC_BORROW_AMOUNT: u64 = 10;
+    const SOL_DEPOSIT_AMOUNT_LAMPORTS: u64 =
+        SOL_DEPOSIT_AMOUNT * LAMPORTS_TO_SOL * INITIAL_COLLATERAL_RATIO;
+    const USDC_BORROW_AMOUNT_FRACTIONAL: u64 = USDC_BORROW_AMOUNT * FRACTIONAL_TO_USDC;
+    const DEPOSIT_COUNT: usize = 12;
+    const BORROW_COUNT: usize = 8;
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let sol_oracle = add_sol_oracle(&mut test);
+    let sol_test_reserves: Vec<TestReserve> = (0..DEPOSIT_COUNT)
+        .map(|_| {
+            add_reserve(
+                &mut test,
+                &lending_market,
+                &sol_oracle,
+                &user_accounts_owner,
+                AddReserveArgs {
+                    collateral_amount: SOL_DEPOSIT_AMOUNT_LAMPORTS,
+                    liquidity_mint_decimals: 9,
+                    liquidity_mint_pubkey: spl_token::native_mint::id(),
+                    config: TEST_RESERVE_CONFIG,
+                    mark_fresh: true,
+                    ..AddReserveArgs::default()
+                },
+            )
+        })
+        .collect();
+
+    let usdc_mint = add_usdc_mint(&mut test);
+    let usdc_oracle = add_usdc_oracle(&mut test);
+    let usdc_test_reserves: Vec<TestReserve> = (0..BORROW_COUNT)
+        .map(|_| {
+            add_reserve(
+                &mut test,
+                &lending_market,
+                &usdc_oracle,
+                &user_accounts_owner,
+                AddReserveArgs {
+                    borrow_amount: USDC_BORROW_AMOUNT_FRACTIONAL,
+                    liquidity_amount: 2 * USDC_BORROW_AMOUNT_FRACTIONAL,
+                    liquidity_mint_decimals: usdc_mint.decimals,
+                    liquidity_mint_pubkey: usdc_mint.pubkey,
+                    config: TEST_RESERVE_CONFIG,
+                    mark_fresh: true,
+                    ..AddReserveArgs::default()
+                },
+            )
+        })
+        .collect();
+
+    let deposits: Vec<(&TestReserve, u64)> = sol_test_reserves
+        .iter()
+        .map(|test_reserve| (test_reserve, SOL_DEPOSIT_AMOUNT_LAMPORTS))
+        .collect();
+    let borrows: Vec<(&TestReserve, u64)> = usdc_test_reserves
+        .iter()
+        .map(|test_reserve| (test_reserve, USDC_BORROW_AMOUNT_FRACTIONAL))
+        .collect();
+    assert!(deposits.len() + borrows.len() > MAX_OBLIGATION_RESERVES);
+
+    let test_obligation = add_obligation(
+        &mut test,
+        &lending_market,
+        &user_accounts_owner,
+        AddObligationArgs {
+            deposits: &deposits,
+            borrows: &borrows,
+            max_reserves: DEPOSIT_COUNT + BORROW_COUNT,
+            ..AddObligationArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[refresh_obligation(
+            spl_token_lending::id(),
+            test_obligation.pubkey,
+            sol_test_reserves
+                .iter()
+                .chain(usdc_test_reserves.iter())
+                .map(|test_reserve| test_reserve.pubkey)
+                .collect(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let obligation = test_obligation.get_state(&mut banks_client).await;
+    assert_eq!(obligation.deposits.len(), DEPOSIT_COUNT);
+    assert_eq!(obligation.borrows.len(), BORROW_COUNT);
+    assert_eq!(
+        obligation.deposited_value,
+        Decimal::from(SOL_DEPOSIT_AMOUNT * 20 * DEPOSIT_COUNT as u64)
+    );
+    assert_eq!(
+        obligation.borrowed_value,
+        Decimal::from(USDC_BORROW_AMOUNT * BORROW_COUNT as u64)
+    );
+}
@@ -5,17 +5,31 @@ mod helpers;
 
 use {
     helpers::*,
+    solana_program::{clock::Clock, program_option::COption, pubkey::Pubkey},
     solana_program_test::*,
     solana_sdk::{
+        account::Account,
+        instruction::InstructionError,
         signature::{Keypair, Signer},
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError},
     },
+    spl_token::state::Mint,
     spl_token_lending::{
-        instruction::refresh_reserve,
+        error::LendingError,
+        instruction::{refresh_reserve, set_fixed_price},
         math::{Decimal, Rate, TryAdd, TryDiv, TryMul},
         processor::process_instruction,
-        state::SLOTS_PER_YEAR,
+        state::{OracleSource, ReserveConfig, SLOTS_PER_YEAR},
     },
+    spl_token_swap::{
+        curve::{
+            base::{CurveType, SwapCurve},
+            constant_price::ConstantPriceCurve,
+        },
+        oracle::{PriceOracle, PRICE_FRACTIONAL_BITS},
+        state::{SwapV2, SwapVersion},
+    },
+    std::sync::Arc,
 };
 
 #[tokio::test]
@@ -140,3 +154,538 @@ async fn test_success() {
         usdc_test_reserve.market_price
     );
 }
+
+#[tokio::test]
+async fn test_fixed_price_oracle() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let mut reserve_config = TEST_RESERVE_CONFIG;
+    reserve_config.max_price_age_slots = 5;
+    reserve_config.max_confidence_bps = 100;
+
+    let authority = Keypair::new();
+    // $25.00 +/- $0.10
+    let oracle = add_fixed_price_oracle(&mut test, authority.pubkey(), 2_500, -2, 10);
+    let usdc_mint = add_usdc_mint(&mut test);
+    let test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            liquidity_amount: 100 * FRACTIONAL_TO_USDC,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            config: reserve_config,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let mut test_context = test.start_with_context().await;
+    test_context.warp_to_slot(5).unwrap(); // clock.slot = 5
+
+    let ProgramTestContext {
+        mut banks_client,
+        payer,
+        last_blockhash: recent_blockhash,
+        ..
+    } = test_context;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[refresh_reserve(
+            spl_token_lending::id(),
+            test_reserve.pubkey,
+            oracle.price_pubkey,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let reserve = test_reserve.get_state(&mut banks_client).await;
+    assert_eq!(
+        reserve.liquidity.oracle_source,
+        OracleSource::FixedPriceAuthority
+    );
+    assert_eq!(reserve.liquidity.market_price, Decimal::from(25u64));
+
+    // $30 +/- $0.31 is outside of the 1% confidence limit
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            set_fixed_price(
+                spl_token_lending::id(),
+                oracle.price_pubkey,
+                authority.pubkey(),
+                QUOTE_CURRENCY,
+                3_000,
+                -2,
+                31,
+            ),
+            refresh_reserve(
+                spl_token_lending::id(),
+                test_reserve.pubkey,
+                oracle.price_pubkey,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &authority], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::OraclePriceConfidenceTooWide as u32)
+        )
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            set_fixed_price(
+                spl_token_lending::id(),
+                oracle.price_pubkey,
+                authority.pubkey(),
+                QUOTE_CURRENCY,
+                3_000,
+                -2,
+                30,
+            ),
+            refresh_reserve(
+                spl_token_lending::id(),
+                test_reserve.pubkey,
+                oracle.price_pubkey,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &authority], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let reserve = test_reserve.get_state(&mut banks_client).await;
+    assert_eq!(reserve.liquidity.market_price, Decimal::from(30u64));
+}
+
+#[tokio::test]
+async fn test_stale_fixed_price_oracle() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let mut reserve_config = TEST_RESERVE_CONFIG;
+    reserve_config.max_price_age_slots = 5;
+
+    // price set at slot 0
+    let oracle = add_fixed_price_oracle(&mut test, Pubkey::new_unique(), 25, 0, 0);
+    let usdc_mint = add_usdc_mint(&mut test);
+    let test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            liquidity_amount: 100 * FRACTIONAL_TO_USDC,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            config: reserve_config,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let mut test_context = test.start_with_context().await;
+    test_context.warp_to_slot(6).unwrap(); // clock.slot = 6
+
+    let ProgramTestContext {
+        mut banks_client,
+        payer,
+        last_blockhash: recent_blockhash,
+        ..
+    } = test_context;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[refresh_reserve(
+            spl_token_lending::id(),
+            test_reserve.pubkey,
+            oracle.price_pubkey,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::StaleOraclePrice as u32)
+        )
+    );
+}
+
+fn swap_state(
+    token_a_mint: Pubkey,
+    token_b_mint: Pubkey,
+    swap_curve: SwapCurve,
+    price_oracle: PriceOracle,
+) -> Account {
+    let mut data = vec![0; SwapVersion::LATEST_LEN];
+    SwapVersion::pack(
+        SwapVersion::SwapV2(SwapV2 {
+            is_initialized: true,
+            token_program_id: spl_token::id(),
+            token_a_mint,
+            token_b_mint,
+            swap_curve,
+            price_oracle,
+            ..SwapV2::default()
+        }),
+        &mut data,
+    )
+    .unwrap();
+    Account {
+        lamports: u32::MAX as u64,
+        data,
+        owner: spl_token_swap::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_token_swap_oracle() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    const START_TIMESTAMP: i64 = 1_700_000_000;
+    const ONE: u128 = 1 << PRICE_FRACTIONAL_BITS;
+
+    let user_accounts_owner = Keypair::new();
+
+    // quote currency with the same decimals as SOL, so that prices are exact
+    let quote_mint_pubkey = Pubkey::new_unique();
+    test.add_packable_account(
+        quote_mint_pubkey,
+        u32::MAX as u64,
+        &Mint {
+            is_initialized: true,
+            decimals: 9,
+            mint_authority: COption::Some(Pubkey::new_unique()),
+            ..Mint::default()
+        },
+        &spl_token::id(),
+    );
+    let lending_market =
+        add_lending_market_with_quote_currency(&mut test, quote_mint_pubkey.to_bytes());
+
+    // pool starts at 20 quote tokens per SOL, with accumulators about to wrap
+    let swap_pubkey = Pubkey::new_unique();
+    let swap_account = |price_cumulative: u128, timestamp_offset: i64, slot: u64| {
+        swap_state(
+            spl_token::native_mint::id(),
+            quote_mint_pubkey,
+            SwapCurve::default(),
+            PriceOracle {
+                price_a_cumulative: price_cumulative,
+                price_b_cumulative: 0,
+                last_update_timestamp: START_TIMESTAMP + timestamp_offset,
+                last_update_slot: slot,
+            },
+        )
+    };
+    test.add_account(swap_pubkey, swap_account(u128::MAX - 5, 0, 0));
+
+    let mut test_context = test.start_with_context().await;
+    let clock = test_context
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap();
+    let slot = clock.slot + 1_000;
+    let clock = Clock { slot, ..clock };
+    test_context.set_sysvar(&Clock {
+        unix_timestamp: START_TIMESTAMP,
+        ..clock
+    });
+    let payer = test_context.payer.insecure_clone();
+
+    const RESERVE_AMOUNT: u64 = 42;
+    let sol_user_liquidity_account = create_and_mint_to_token_account(
+        &mut test_context.banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &payer,
+        user_accounts_owner.pubkey(),
+        RESERVE_AMOUNT,
+    )
+    .await;
+
+    let oracle = TestOracle {
+        source: OracleSource::TokenSwapTwap,
+        product_pubkey: quote_mint_pubkey,
+        price_pubkey: swap_pubkey,
+        price: Decimal::zero(),
+    };
+    let sol_reserve = TestReserve::init(
+        "sol".to_owned(),
+        &mut test_context.banks_client,
+        &lending_market,
+        &oracle,
+        RESERVE_AMOUNT,
+        // the 100 second window spans 250 slots
+        ReserveConfig {
+            max_price_age_slots: 250,
+            min_twap_window_seconds: 100,
+            ..TEST_RESERVE_CONFIG
+        },
+        spl_token::native_mint::id(),
+        sol_user_liquidity_account,
+        &payer,
+        &user_accounts_owner,
+    )
+    .await
+    .unwrap();
+
+    let reserve = sol_reserve.get_state(&mut test_context.banks_client).await;
+    assert_eq!(reserve.liquidity.oracle_source, OracleSource::TokenSwapTwap);
+    assert_eq!(reserve.liquidity.token_swap_oracle.quote_mint_decimals, 9);
+    assert_eq!(
+        reserve.liquidity.token_swap_oracle.price_cumulative,
+        u128::MAX - 5
+    );
+    assert_eq!(
+        reserve.liquidity.token_swap_oracle.timestamp,
+        START_TIMESTAMP
+    );
+
+    let refresh = refresh_reserve(spl_token_lending::id(), sol_reserve.pubkey, swap_pubkey);
+
+    // the pool was not updated since it was observed
+    test_context.set_sysvar(&Clock {
+        unix_timestamp: START_TIMESTAMP + 100,
+        ..clock
+    });
+    let recent_blockhash = test_context.get_new_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(&[refresh.clone()], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        test_context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidOracleConfig as u32)
+        )
+    );
+
+    // a trade after 100 seconds records 20 quote tokens per SOL
+    test_context.set_account(
+        &swap_pubkey,
+        &swap_account(
+            (u128::MAX - 5).wrapping_add(20 * ONE * 100),
+            100,
+            slot - 100,
+        )
+        .into(),
+    );
+    let recent_blockhash = test_context.get_new_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(&[refresh.clone()], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(test_context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .is_ok());
+
+    let reserve = sol_reserve.get_state(&mut test_context.banks_client).await;
+    assert_eq!(reserve.liquidity.market_price, Decimal::from(20u64));
+    assert_eq!(reserve.liquidity.market_price_slot, slot - 100);
+    assert_eq!(
+        reserve.liquidity.token_swap_oracle.price_cumulative,
+        (u128::MAX - 5).wrapping_add(20 * ONE * 100)
+    );
+    assert_eq!(
+        reserve.liquidity.token_swap_oracle.timestamp,
+        START_TIMESTAMP + 100
+    );
+
+    // a trade after 150 seconds doubles the price
+    test_context.set_account(
+        &swap_pubkey,
+        &swap_account((u128::MAX - 5).wrapping_add(20 * ONE * 150), 150, slot - 50).into(),
+    );
+
+    // 50 seconds since the last observation is shorter than the window, the
+    // price, its slot and the observation are kept
+    test_context.set_sysvar(&Clock {
+        unix_timestamp: START_TIMESTAMP + 170,
+        ..clock
+    });
+    let recent_blockhash = test_context.get_new_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(&[refresh.clone()], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(test_context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .is_ok());
+
+    let reserve = sol_reserve.get_state(&mut test_context.banks_client).await;
+    assert_eq!(reserve.liquidity.market_price, Decimal::from(20u64));
+    assert_eq!(reserve.liquidity.market_price_slot, slot - 100);
+    assert_eq!(
+        reserve.liquidity.token_swap_oracle.timestamp,
+        START_TIMESTAMP + 100
+    );
+
+    // the doubled price is held for 50 more seconds until the next trade
+    test_context.set_account(
+        &swap_pubkey,
+        &swap_account(
+            (u128::MAX - 5).wrapping_add(20 * ONE * 150 + 40 * ONE * 50),
+            200,
+            slot,
+        )
+        .into(),
+    );
+    test_context.set_sysvar(&Clock {
+        unix_timestamp: START_TIMESTAMP + 200,
+        ..clock
+    });
+    let recent_blockhash = test_context.get_new_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(&[refresh.clone()], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(test_context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .is_ok());
+
+    let reserve = sol_reserve.get_state(&mut test_context.banks_client).await;
+    assert_eq!(reserve.liquidity.market_price, Decimal::from(30u64));
+    assert_eq!(reserve.liquidity.market_price_slot, slot);
+
+    // without trades the pool has no newer observation, the price goes stale
+    test_context.set_sysvar(&Clock {
+        slot: slot + 251,
+        unix_timestamp: START_TIMESTAMP + 300,
+        ..clock
+    });
+    let recent_blockhash = test_context.get_new_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(&[refresh], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        test_context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::StaleOraclePrice as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_token_swap_oracle_curve_type() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let user_accounts_owner = Keypair::new();
+
+    let quote_mint_pubkey = Pubkey::new_unique();
+    test.add_packable_account(
+        quote_mint_pubkey,
+        u32::MAX as u64,
+        &Mint {
+            is_initialized: true,
+            decimals: 9,
+            mint_authority: COption::Some(Pubkey::new_unique()),
+            ..Mint::default()
+        },
+        &spl_token::id(),
+    );
+    let lending_market =
+        add_lending_market_with_quote_currency(&mut test, quote_mint_pubkey.to_bytes());
+
+    // the constant price curve does not move its price with trades
+    let swap_pubkey = Pubkey::new_unique();
+    test.add_account(
+        swap_pubkey,
+        swap_state(
+            spl_token::native_mint::id(),
+            quote_mint_pubkey,
+            SwapCurve {
+                curve_type: CurveType::ConstantPrice,
+                calculator: Arc::new(ConstantPriceCurve { token_b_price: 20 }),
+            },
+            PriceOracle::default(),
+        ),
+    );
+
+    let (mut banks_client, payer, _recent_blockhash) = test.start().await;
+
+    const RESERVE_AMOUNT: u64 = 42;
+    let sol_user_liquidity_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &payer,
+        user_accounts_owner.pubkey(),
+        RESERVE_AMOUNT,
+    )
+    .await;
+
+    let oracle = TestOracle {
+        source: OracleSource::TokenSwapTwap,
+        product_pubkey: quote_mint_pubkey,
+        price_pubkey: swap_pubkey,
+        price: Decimal::zero(),
+    };
+    assert_eq!(
+        TestReserve::init(
+            "sol".to_owned(),
+            &mut banks_client,
+            &lending_market,
+            &oracle,
+            RESERVE_AMOUNT,
+            TEST_RESERVE_CONFIG,
+            spl_token::native_mint::id(),
+            sol_user_liquidity_account,
+            &payer,
+            &user_accounts_owner,
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            8,
+            InstructionError::Custom(LendingError::InvalidOracleConfig as u32)
+        )
+    );
+}
@@ -0,0 +1,174 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        system_instruction::transfer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_token_lending::{
+        error::LendingError,
+        instruction::resize_obligation,
+        processor::process_instruction,
+        state::{Obligation, MAX_OBLIGATION_RESERVES, MAX_RESIZED_OBLIGATION_RESERVES},
+    },
+};
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    // limit to track compute unit increase
+    test.set_compute_max_units(10_000);
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+    let test_obligation = TestObligation::init(
+        &mut banks_client,
+        &lending_market,
+        &user_accounts_owner,
+        &payer,
+    )
+    .await
+    .unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            transfer(
+                &payer.pubkey(),
+                &user_accounts_owner.pubkey(),
+                LAMPORTS_TO_SOL,
+            ),
+            resize_obligation(
+                spl_token_lending::id(),
+                MAX_RESIZED_OBLIGATION_RESERVES as u8,
+                test_obligation.pubkey,
+                lending_market.pubkey,
+                user_accounts_owner.pubkey(),
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &user_accounts_owner], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let obligation_account = banks_client
+        .get_account(test_obligation.pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let rent = banks_client.get_rent().await.unwrap();
+    assert_eq!(
+        obligation_account.data.len(),
+        Obligation::account_len(MAX_RESIZED_OBLIGATION_RESERVES)
+    );
+    assert!(rent.is_exempt(obligation_account.lamports, obligation_account.data.len()));
+    assert_eq!(
+        Obligation::max_reserves(obligation_account.data.len()),
+        MAX_RESIZED_OBLIGATION_RESERVES
+    );
+
+    test_obligation.validate_state(&mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_invalid_max_reserves() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let test_obligation = add_obligation(
+        &mut test,
+        &lending_market,
+        &user_accounts_owner,
+        AddObligationArgs::default(),
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    for max_reserves in [MAX_OBLIGATION_RESERVES, MAX_RESIZED_OBLIGATION_RESERVES + 1] {
+        let mut transaction = Transaction::new_with_payer(
+            &[resize_obligation(
+                spl_token_lending::id(),
+                max_reserves as u8,
+                test_obligation.pubkey,
+                lending_market.pubkey,
+                user_accounts_owner.pubkey(),
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &user_accounts_owner], recent_blockhash);
+        assert_eq!(
+            banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(LendingError::InvalidAmount as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_invalid_owner() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let user_accounts_owner = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let test_obligation = add_obligation(
+        &mut test,
+        &lending_market,
+        &user_accounts_owner,
+        AddObligationArgs::default(),
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[resize_obligation(
+            spl_token_lending::id(),
+            MAX_RESIZED_OBLIGATION_RESERVES as u8,
+            test_obligation.pubkey,
+            lending_market.pubkey,
+            payer.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidObligationOwner as u32)
+        )
+    );
+}
@@ -0,0 +1,176 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program::program_pack::Pack,
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        system_instruction::create_account,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_token_lending::{
+        error::LendingError, instruction::set_fixed_price, processor::process_instruction,
+        state::FixedPrice,
+    },
+};
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    // limit to track compute unit increase
+    test.set_compute_max_units(10_000);
+
+    let mut test_context = test.start_with_context().await;
+    test_context.warp_to_slot(3).unwrap(); // clock.slot = 3
+
+    let ProgramTestContext {
+        mut banks_client,
+        payer,
+        last_blockhash: recent_blockhash,
+        ..
+    } = test_context;
+
+    let fixed_price_keypair = Keypair::new();
+    let authority = Keypair::new();
+    let rent = banks_client.get_rent().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_account(
+                &payer.pubkey(),
+                &fixed_price_keypair.pubkey(),
+                rent.minimum_balance(FixedPrice::LEN),
+                FixedPrice::LEN as u64,
+                &spl_token_lending::id(),
+            ),
+            set_fixed_price(
+                spl_token_lending::id(),
+                fixed_price_keypair.pubkey(),
+                authority.pubkey(),
+                QUOTE_CURRENCY,
+                2_000,
+                -2,
+                10,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &[&payer, &fixed_price_keypair, &authority],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let fixed_price_account = banks_client
+        .get_account(fixed_price_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let fixed_price = FixedPrice::unpack(&fixed_price_account.data).unwrap();
+    assert_eq!(fixed_price.authority, authority.pubkey());
+    assert_eq!(fixed_price.quote_currency, QUOTE_CURRENCY);
+    assert_eq!(fixed_price.price, 2_000);
+    assert_eq!(fixed_price.expo, -2);
+    assert_eq!(fixed_price.confidence, 10);
+    assert_eq!(fixed_price.last_update_slot, 3);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[set_fixed_price(
+            spl_token_lending::id(),
+            fixed_price_keypair.pubkey(),
+            authority.pubkey(),
+            QUOTE_CURRENCY,
+            25,
+            0,
+            0,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &authority], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let fixed_price_account = banks_client
+        .get_account(fixed_price_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let fixed_price = FixedPrice::unpack(&fixed_price_account.data).unwrap();
+    assert_eq!(fixed_price.authority, authority.pubkey());
+    assert_eq!(fixed_price.price, 25);
+    assert_eq!(fixed_price.expo, 0);
+    assert_eq!(fixed_price.confidence, 0);
+}
+
+#[tokio::test]
+async fn test_invalid_authority() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let authority = Keypair::new();
+    let oracle = add_fixed_price_oracle(&mut test, authority.pubkey(), 20, 0, 0);
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let invalid_authority = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[set_fixed_price(
+            spl_token_lending::id(),
+            oracle.price_pubkey,
+            invalid_authority.pubkey(),
+            QUOTE_CURRENCY,
+            1,
+            0,
+            0,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &invalid_authority], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidFixedPriceAuthority as u32)
+        )
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[set_fixed_price(
+            spl_token_lending::id(),
+            oracle.price_pubkey,
+            authority.pubkey(),
+            [1u8; 32],
+            1,
+            0,
+            0,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &authority], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidOracleConfig as u32)
+        )
+    );
+}
@@ -0,0 +1,211 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_token::instruction::approve,
+    spl_token_lending::{
+        error::LendingError,
+        instruction::{deposit_reserve_liquidity, set_lending_market_paused, set_risk_council},
+        processor::process_instruction,
+    },
+};
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    // limit to track compute unit increase
+    test.set_compute_max_units(50_000);
+
+    const USDC_DEPOSIT_AMOUNT_FRACTIONAL: u64 = 100 * FRACTIONAL_TO_USDC;
+
+    let user_accounts_owner = Keypair::new();
+    let user_transfer_authority = Keypair::new();
+    let lending_market = add_lending_market(&mut test);
+
+    let usdc_mint = add_usdc_mint(&mut test);
+    let usdc_oracle = add_usdc_oracle(&mut test);
+    let usdc_test_reserve = add_reserve(
+        &mut test,
+        &lending_market,
+        &usdc_oracle,
+        &user_accounts_owner,
+        AddReserveArgs {
+            user_liquidity_amount: 2 * USDC_DEPOSIT_AMOUNT_FRACTIONAL,
+            liquidity_amount: 10_000 * FRACTIONAL_TO_USDC,
+            liquidity_mint_decimals: usdc_mint.decimals,
+            liquidity_mint_pubkey: usdc_mint.pubkey,
+            config: TEST_RESERVE_CONFIG,
+            mark_fresh: true,
+            ..AddReserveArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[set_lending_market_paused(
+            spl_token_lending::id(),
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            true,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &lending_market.owner], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let lending_market_info = lending_market.get_state(&mut banks_client).await;
+    assert!(lending_market_info.paused);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            approve(
+                &spl_token::id(),
+                &usdc_test_reserve.user_liquidity_pubkey,
+                &user_transfer_authority.pubkey(),
+                &user_accounts_owner.pubkey(),
+                &[],
+                USDC_DEPOSIT_AMOUNT_FRACTIONAL,
+            )
+            .unwrap(),
+            deposit_reserve_liquidity(
+                spl_token_lending::id(),
+                USDC_DEPOSIT_AMOUNT_FRACTIONAL,
+                usdc_test_reserve.user_liquidity_pubkey,
+                usdc_test_reserve.user_collateral_pubkey,
+                usdc_test_reserve.pubkey,
+                usdc_test_reserve.liquidity_supply_pubkey,
+                usdc_test_reserve.collateral_mint_pubkey,
+                lending_market.pubkey,
+                user_transfer_authority.pubkey(),
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &[&payer, &user_accounts_owner, &user_transfer_authority],
+        recent_blockhash,
+    );
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::LendingMarketPaused as u32)
+        )
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[set_lending_market_paused(
+            spl_token_lending::id(),
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            false,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &lending_market.owner], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    lending_market
+        .deposit(
+            &mut banks_client,
+            &user_accounts_owner,
+            &payer,
+            &usdc_test_reserve,
+            USDC_DEPOSIT_AMOUNT_FRACTIONAL,
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn test_risk_council() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let lending_market = add_lending_market(&mut test);
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let risk_council = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            set_risk_council(
+                spl_token_lending::id(),
+                lending_market.pubkey,
+                lending_market.owner.pubkey(),
+                risk_council.pubkey(),
+            ),
+            set_lending_market_paused(
+                spl_token_lending::id(),
+                lending_market.pubkey,
+                risk_council.pubkey(),
+                true,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &[&payer, &lending_market.owner, &risk_council],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let lending_market_info = lending_market.get_state(&mut banks_client).await;
+    assert!(lending_market_info.paused);
+    assert_eq!(lending_market_info.risk_council, risk_council.pubkey());
+}
+
+#[tokio::test]
+async fn test_invalid_authority() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let lending_market = add_lending_market(&mut test);
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let invalid_authority = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[set_lending_market_paused(
+            spl_token_lending::id(),
+            lending_market.pubkey,
+            invalid_authority.pubkey(),
+            true,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &invalid_authority], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+}
@@ -0,0 +1,134 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_token_lending::{
+        error::LendingError, instruction::set_liquidation_close_factor,
+        processor::process_instruction, state::LIQUIDATION_CLOSE_FACTOR,
+    },
+};
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    // limit to track compute unit increase
+    test.set_compute_max_units(4_000);
+
+    let lending_market = add_lending_market(&mut test);
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let lending_market_info = lending_market.get_state(&mut banks_client).await;
+    assert_eq!(
+        lending_market_info.liquidation_close_factor_percent(),
+        LIQUIDATION_CLOSE_FACTOR
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[set_liquidation_close_factor(
+            spl_token_lending::id(),
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            20,
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    transaction.sign(&[&payer, &lending_market.owner], recent_blockhash);
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|e| e.unwrap())
+        .unwrap();
+
+    let lending_market_info = lending_market.get_state(&mut banks_client).await;
+    assert_eq!(lending_market_info.liquidation_close_factor, 20);
+    assert_eq!(lending_market_info.liquidation_close_factor_percent(), 20);
+}
+
+#[tokio::test]
+async fn test_invalid_close_factor() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let lending_market = add_lending_market(&mut test);
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[set_liquidation_close_factor(
+            spl_token_lending::id(),
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            101,
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    transaction.sign(&[&payer, &lending_market.owner], recent_blockhash);
+
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidConfig as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_owner() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let lending_market = add_lending_market(&mut test);
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let invalid_owner = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[set_liquidation_close_factor(
+            spl_token_lending::id(),
+            lending_market.pubkey,
+            invalid_owner.pubkey(),
+            20,
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    transaction.sign(&[&payer, &invalid_owner], recent_blockhash);
+
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+}
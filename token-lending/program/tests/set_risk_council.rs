@@ -0,0 +1,89 @@
+#![allow(clippy::arithmetic_side_effects)]
+#![cfg(feature = "test-sbf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_token_lending::{
+        error::LendingError, instruction::set_risk_council, processor::process_instruction,
+    },
+};
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    // limit to track compute unit increase
+    test.set_compute_max_units(4_000);
+
+    let lending_market = add_lending_market(&mut test);
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let lending_market_info = lending_market.get_state(&mut banks_client).await;
+    assert_eq!(lending_market_info.risk_council, Pubkey::default());
+
+    let risk_council = Pubkey::new_unique();
+    let mut transaction = Transaction::new_with_payer(
+        &[set_risk_council(
+            spl_token_lending::id(),
+            lending_market.pubkey,
+            lending_market.owner.pubkey(),
+            risk_council,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &lending_market.owner], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_ok());
+
+    let lending_market_info = lending_market.get_state(&mut banks_client).await;
+    assert_eq!(lending_market_info.risk_council, risk_council);
+    assert!(lending_market_info.is_pause_authority(&risk_council));
+    assert!(lending_market_info.is_pause_authority(&lending_market.owner.pubkey()));
+}
+
+#[tokio::test]
+async fn test_invalid_owner() {
+    let mut test = ProgramTest::new(
+        "spl_token_lending",
+        spl_token_lending::id(),
+        processor!(process_instruction),
+    );
+
+    let lending_market = add_lending_market(&mut test);
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let invalid_owner = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[set_risk_council(
+            spl_token_lending::id(),
+            lending_market.pubkey,
+            invalid_owner.pubkey(),
+            invalid_owner.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &invalid_owner], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+}
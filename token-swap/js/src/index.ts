@@ -120,6 +120,7 @@ export const CurveType = Object.freeze({
   ConstantProduct: 0, // Constant product curve, Uniswap-style
   ConstantPrice: 1, // Constant price curve, always X amount of A token for 1 B token, where X is defined at init
   Offset: 2, // Offset curve, like Uniswap, but with an additional offset on the token B side
+  Stable: 3, // StableSwap curve, like Curve, close to a constant sum around the balanced point
 });
 
 /**
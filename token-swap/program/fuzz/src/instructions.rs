@@ -12,6 +12,7 @@ use {
             constant_product::ConstantProductCurve,
             fees::Fees,
             offset::OffsetCurve,
+            stable::StableCurve,
         },
         error::SwapError,
         instruction::{
@@ -471,6 +472,10 @@ fn get_swap_curve(curve_type: CurveType) -> SwapCurve {
             CurveType::Offset => Arc::new(OffsetCurve {
                 token_b_offset: 100_000_000_000,
             }),
+            CurveType::Stable => Arc::new(StableCurve {
+                amp: 100,
+                ..StableCurve::default()
+            }),
         },
     }
 }
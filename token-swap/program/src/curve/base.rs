@@ -9,9 +9,11 @@ use {
         constant_product::ConstantProductCurve,
         fees::Fees,
         offset::OffsetCurve,
+        stable::StableCurve,
     },
     arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
     solana_program::{
+        clock::UnixTimestamp,
         program_error::ProgramError,
         program_pack::{Pack, Sealed},
     },
@@ -34,6 +36,9 @@ pub enum CurveType {
     ConstantPrice,
     /// Offset curve, like Uniswap, but the token B side has a faked offset
     Offset,
+    /// StableSwap curve, like Curve, close to a constant sum around the
+    /// balanced point with an amplification coefficient
+    Stable,
 }
 
 /// Encodes all results of swapping from a source token to a destination token
@@ -166,6 +171,18 @@ impl SwapCurve {
             RoundDirection::Ceiling,
         )
     }
+
+    /// Get the curve to use for calculations at the given unix timestamp, with
+    /// any parameters that change over time fixed to their current value
+    pub fn at_timestamp(&self, unix_timestamp: UnixTimestamp) -> SwapCurve {
+        SwapCurve {
+            curve_type: self.curve_type,
+            calculator: self
+                .calculator
+                .at_timestamp(unix_timestamp)
+                .unwrap_or_else(|| self.calculator.clone()),
+        }
+    }
 }
 
 /// Default implementation for SwapCurve cannot be derived because of
@@ -229,6 +246,7 @@ impl Pack for SwapCurve {
                     Arc::new(ConstantPriceCurve::unpack_from_slice(calculator)?)
                 }
                 CurveType::Offset => Arc::new(OffsetCurve::unpack_from_slice(calculator)?),
+                CurveType::Stable => Arc::new(StableCurve::unpack_from_slice(calculator)?),
             },
         })
     }
@@ -258,6 +276,7 @@ impl TryFrom<u8> for CurveType {
             0 => Ok(CurveType::ConstantProduct),
             1 => Ok(CurveType::ConstantPrice),
             2 => Ok(CurveType::Offset),
+            3 => Ok(CurveType::Stable),
             _ => Err(ProgramError::InvalidAccountData),
         }
     }
@@ -287,6 +306,63 @@ mod test {
         assert_eq!(swap_curve, unpacked);
     }
 
+    #[test]
+    fn pack_stable_swap_curve() {
+        let amp = 100;
+        let target_amp = 200;
+        let start_ramp_ts = 1_000_000;
+        let stop_ramp_ts = 2_000_000;
+        let curve_type = CurveType::Stable;
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator: Arc::new(StableCurve {
+                amp,
+                target_amp,
+                start_ramp_ts,
+                stop_ramp_ts,
+            }),
+        };
+
+        let mut packed = [0u8; SwapCurve::LEN];
+        Pack::pack_into_slice(&swap_curve, &mut packed[..]);
+        let unpacked = SwapCurve::unpack_from_slice(&packed).unwrap();
+        assert_eq!(swap_curve, unpacked);
+
+        let mut packed = vec![curve_type as u8];
+        packed.extend_from_slice(&amp.to_le_bytes());
+        packed.extend_from_slice(&target_amp.to_le_bytes());
+        packed.extend_from_slice(&start_ramp_ts.to_le_bytes());
+        packed.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+        let unpacked = SwapCurve::unpack_from_slice(&packed).unwrap();
+        assert_eq!(swap_curve, unpacked);
+    }
+
+    #[test]
+    fn stable_swap_curve_at_timestamp() {
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::Stable,
+            calculator: Arc::new(StableCurve {
+                amp: 100,
+                target_amp: 200,
+                start_ramp_ts: 1_000,
+                stop_ramp_ts: 2_000,
+            }),
+        };
+        let expected = SwapCurve {
+            curve_type: CurveType::Stable,
+            calculator: Arc::new(StableCurve {
+                amp: 150,
+                target_amp: 150,
+                start_ramp_ts: 1_500,
+                stop_ramp_ts: 1_500,
+            }),
+        };
+        assert_eq!(swap_curve.at_timestamp(1_500), expected);
+
+        let swap_curve = SwapCurve::default();
+        assert_eq!(swap_curve.at_timestamp(1_500), swap_curve);
+    }
+
     #[test]
     fn constant_product_trade_fee() {
         // calculation on https://github.com/solana-labs/solana-program-library/issues/341
@@ -2,7 +2,12 @@
 
 #[cfg(feature = "fuzz")]
 use arbitrary::Arbitrary;
-use {crate::error::SwapError, spl_math::precise_number::PreciseNumber, std::fmt::Debug};
+use {
+    crate::error::SwapError,
+    solana_program::clock::UnixTimestamp,
+    spl_math::precise_number::PreciseNumber,
+    std::{fmt::Debug, sync::Arc},
+};
 
 /// Initial amount of pool tokens for swap contract, hard-coded to something
 /// "sensible" given a maximum of u128.
@@ -189,6 +194,33 @@ pub trait CurveCalculator: Debug + DynPack {
         swap_token_a_amount: u128,
         swap_token_b_amount: u128,
     ) -> Option<PreciseNumber>;
+
+    /// Get a copy of the curve with any parameters that change over time,
+    /// such as a ramping amplification coefficient, fixed to their value at
+    /// the given unix timestamp.
+    ///
+    /// Curves with constant parameters return `None`, and can be used as they
+    /// are.
+    fn at_timestamp(
+        &self,
+        _unix_timestamp: UnixTimestamp,
+    ) -> Option<Arc<dyn CurveCalculator + Sync + Send>> {
+        None
+    }
+
+    /// Get a copy of the curve which linearly ramps its amplification
+    /// coefficient from the current value to `target_amp`, reached at
+    /// `stop_ramp_ts`.
+    ///
+    /// Only curves with an amplification coefficient can be ramped.
+    fn ramp_amplification(
+        &self,
+        _target_amp: u64,
+        _current_ts: UnixTimestamp,
+        _stop_ramp_ts: UnixTimestamp,
+    ) -> Result<Arc<dyn CurveCalculator + Sync + Send>, SwapError> {
+        Err(SwapError::UnsupportedCurveOperation)
+    }
 }
 
 /// Test helpers for curves
@@ -0,0 +1,928 @@
+//! The StableSwap invariant calculator.
+
+use {
+    crate::{
+        curve::{
+            calculator::{
+                map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
+                TradeDirection, TradingTokenResult,
+            },
+            constant_product::pool_tokens_to_trading_tokens,
+        },
+        error::SwapError,
+    },
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    solana_program::{
+        clock::UnixTimestamp,
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+    },
+    spl_math::precise_number::PreciseNumber,
+    std::sync::Arc,
+};
+
+/// Minimum amplification coefficient allowed for a stable curve
+pub const MIN_AMP: u64 = 1;
+
+/// Maximum amplification coefficient allowed for a stable curve
+pub const MAX_AMP: u64 = 1_000_000;
+
+/// Minimum duration of an amplification ramp, in seconds
+pub const MIN_RAMP_DURATION: UnixTimestamp = 86_400;
+
+/// Maximum factor by which a single ramp can increase or decrease the
+/// amplification coefficient
+pub const MAX_AMP_CHANGE: u64 = 10;
+
+/// Number of tokens in the pool, written as `n` in the invariant
+const N_COINS: u128 = 2;
+
+/// `n ^ n`, which shows up throughout the invariant
+const N_COINS_POW_N: u128 = 4;
+
+/// Maximum number of Newton iterations when approximating the invariant or
+/// one of the pool balances.  Very imbalanced pools converge slowly at first,
+/// so this matches the bound used by the Curve contracts.
+const ITERATIONS: u8 = 255;
+
+/// StableSwap curve, from the Curve whitepaper
+/// <https://curve.fi/files/stableswap-paper.pdf>
+///
+/// The invariant behaves like a constant sum when the pool is balanced, and
+/// like a constant product as it becomes imbalanced.  The amplification
+/// coefficient controls how quickly it moves from one to the other.
+///
+/// The amplification coefficient can be ramped linearly from `amp` to
+/// `target_amp` between `start_ramp_ts` and `stop_ramp_ts`.  If `stop_ramp_ts`
+/// is not after `start_ramp_ts`, the curve is not ramping and always uses
+/// `amp`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StableCurve {
+    /// Amplification coefficient (A), or its value at the start of the ramp
+    pub amp: u64,
+    /// Amplification coefficient at the end of the ramp
+    pub target_amp: u64,
+    /// Unix timestamp at which the ramp starts
+    pub start_ramp_ts: UnixTimestamp,
+    /// Unix timestamp at which the ramp stops
+    pub stop_ramp_ts: UnixTimestamp,
+}
+
+impl StableCurve {
+    /// Get the amplification coefficient at the given unix timestamp,
+    /// linearly interpolated if the curve is ramping
+    pub fn amp_at(&self, unix_timestamp: UnixTimestamp) -> Option<u64> {
+        if self.stop_ramp_ts <= self.start_ramp_ts || unix_timestamp <= self.start_ramp_ts {
+            return Some(self.amp);
+        }
+        if unix_timestamp >= self.stop_ramp_ts {
+            return Some(self.target_amp);
+        }
+        let elapsed = u128::try_from(unix_timestamp.checked_sub(self.start_ramp_ts)?).ok()?;
+        let duration = u128::try_from(self.stop_ramp_ts.checked_sub(self.start_ramp_ts)?).ok()?;
+        let amp = u128::from(self.amp);
+        let target_amp = u128::from(self.target_amp);
+        let current_amp = if target_amp >= amp {
+            amp.checked_add(
+                target_amp
+                    .checked_sub(amp)?
+                    .checked_mul(elapsed)?
+                    .checked_div(duration)?,
+            )?
+        } else {
+            amp.checked_sub(
+                amp.checked_sub(target_amp)?
+                    .checked_mul(elapsed)?
+                    .checked_div(duration)?,
+            )?
+        };
+        u64::try_from(current_amp).ok()
+    }
+
+    /// Check if the amplification coefficient is still moving at the given
+    /// unix timestamp
+    pub fn is_ramping(&self, unix_timestamp: UnixTimestamp) -> bool {
+        self.start_ramp_ts < self.stop_ramp_ts && unix_timestamp < self.stop_ramp_ts
+    }
+}
+
+/// Compute the StableSwap invariant `D` for the given amounts, which solves:
+///
+/// A * n^n * sum(x_i) + D = A * D * n^n + D^(n+1) / (n^n * prod(x_i))
+///
+/// Newton's method starts from `sum(x_i)` and decreases monotonically towards
+/// `D`, so the iterations stop as soon as they no longer make progress.  Returns
+/// `None` if the calculation does not converge.
+pub fn compute_d(
+    amp: u64,
+    swap_token_a_amount: u128,
+    swap_token_b_amount: u128,
+) -> Option<PreciseNumber> {
+    let sum = swap_token_a_amount.checked_add(swap_token_b_amount)?;
+    if sum == 0 {
+        return PreciseNumber::new(0);
+    }
+    let ann = PreciseNumber::new(u128::from(amp).checked_mul(N_COINS_POW_N)?)?;
+    let ann_minus_one = ann.checked_sub(&PreciseNumber::new(1)?)?;
+    let n_coins = PreciseNumber::new(N_COINS)?;
+    let n_coins_plus_one = PreciseNumber::new(N_COINS.checked_add(1)?)?;
+    let token_a_times_n = PreciseNumber::new(swap_token_a_amount.checked_mul(N_COINS)?)?;
+    let token_b_times_n = PreciseNumber::new(swap_token_b_amount.checked_mul(N_COINS)?)?;
+    let sum = PreciseNumber::new(sum)?;
+    let ann_sum = ann.checked_mul(&sum)?;
+
+    let mut d = sum;
+    for _ in 0..ITERATIONS {
+        // D_P = D^(n+1) / (n^n * prod(x_i))
+        let d_product = d
+            .checked_mul(&d)?
+            .checked_div(&token_a_times_n)?
+            .checked_mul(&d)?
+            .checked_div(&token_b_times_n)?;
+        // D = (Ann * S + n * D_P) * D / ((Ann - 1) * D + (n + 1) * D_P)
+        let numerator = ann_sum
+            .checked_add(&d_product.checked_mul(&n_coins)?)?
+            .checked_mul(&d)?;
+        let denominator = ann_minus_one
+            .checked_mul(&d)?
+            .checked_add(&d_product.checked_mul(&n_coins_plus_one)?)?;
+        let next_d = numerator.checked_div(&denominator)?;
+        if next_d.greater_than_or_equal(&d) {
+            return Some(d);
+        }
+        d = next_d;
+    }
+    None
+}
+
+/// Compute the new amount of destination token `y` which keeps the invariant
+/// `D` once the amount of source token has moved to `x`, which solves:
+///
+/// y^2 + (x + D / (A * n^n) - D) * y = D^(n+1) / (n^(2n) * x * A)
+///
+/// After its first step, Newton's method decreases monotonically towards `y`,
+/// so the iterations stop as soon as they no longer make progress.  Returns
+/// `None` if the calculation does not converge.
+pub fn compute_new_destination_amount(
+    amp: u64,
+    new_swap_source_amount: u128,
+    d: &PreciseNumber,
+) -> Option<PreciseNumber> {
+    let ann = PreciseNumber::new(u128::from(amp).checked_mul(N_COINS_POW_N)?)?;
+    let n_coins = PreciseNumber::new(N_COINS)?;
+    let new_swap_source_amount = PreciseNumber::new(new_swap_source_amount)?;
+
+    // c = D^(n+1) / (n^n * x * A * n^n)
+    let c = d
+        .checked_mul(d)?
+        .checked_div(&new_swap_source_amount.checked_mul(&n_coins)?)?
+        .checked_mul(d)?
+        .checked_div(&ann.checked_mul(&n_coins)?)?;
+    // b = x + D / (A * n^n)
+    let b = new_swap_source_amount.checked_add(&d.checked_div(&ann)?)?;
+
+    let mut y = d.clone();
+    for i in 0..ITERATIONS {
+        // y = (y^2 + c) / (2 * y + b - D)
+        let numerator = y.checked_mul(&y)?.checked_add(&c)?;
+        let denominator = y.checked_add(&y)?.checked_add(&b)?.checked_sub(d)?;
+        let next_y = numerator.checked_div(&denominator)?;
+        if i > 0 && next_y.greater_than_or_equal(&y) {
+            return Some(y);
+        }
+        y = next_y;
+    }
+    None
+}
+
+/// Get the amount of pool tokens corresponding to the change in the invariant
+/// from `d` to `new_d`, proportional to the current pool supply
+fn pool_tokens_from_invariant_change(
+    d: &PreciseNumber,
+    new_d: &PreciseNumber,
+    pool_supply: u128,
+    round_direction: RoundDirection,
+) -> Option<u128> {
+    let (difference, _) = new_d.unsigned_sub(d);
+    let pool_tokens = PreciseNumber::new(pool_supply)?
+        .checked_mul(&difference)?
+        .checked_div(d)?;
+    match round_direction {
+        RoundDirection::Floor => pool_tokens.floor()?.to_imprecise(),
+        RoundDirection::Ceiling => pool_tokens.ceiling()?.to_imprecise(),
+    }
+}
+
+impl CurveCalculator for StableCurve {
+    /// Stable swap keeps the invariant `D` constant.  The new destination
+    /// amount is rounded up to the benefit of the pool, and since the
+    /// invariant is symmetric, the source amount is then reduced to the
+    /// minimum required for that destination amount, just like the constant
+    /// product curve.
+    fn swap_without_fees(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        _trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let d = compute_d(self.amp, swap_source_amount, swap_destination_amount)?;
+        let new_swap_source_amount = swap_source_amount.checked_add(source_amount)?;
+        let new_swap_destination_amount =
+            compute_new_destination_amount(self.amp, new_swap_source_amount, &d)?
+                .ceiling()?
+                .to_imprecise()?;
+        let destination_amount_swapped =
+            map_zero_to_none(swap_destination_amount.checked_sub(new_swap_destination_amount)?)?;
+
+        let new_swap_source_amount = std::cmp::min(
+            new_swap_source_amount,
+            compute_new_destination_amount(self.amp, new_swap_destination_amount, &d)?
+                .ceiling()?
+                .to_imprecise()?,
+        );
+        let source_amount_swapped =
+            map_zero_to_none(new_swap_source_amount.checked_sub(swap_source_amount)?)?;
+
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped,
+        })
+    }
+
+    /// Since the invariant scales linearly with the pool, the stable curve
+    /// uses the same ratio calculation as the constant product curve
+    fn pool_tokens_to_trading_tokens(
+        &self,
+        pool_tokens: u128,
+        pool_token_supply: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        round_direction: RoundDirection,
+    ) -> Option<TradingTokenResult> {
+        pool_tokens_to_trading_tokens(
+            pool_tokens,
+            pool_token_supply,
+            swap_token_a_amount,
+            swap_token_b_amount,
+            round_direction,
+        )
+    }
+
+    /// Get the amount of pool tokens for the deposited amount of token A or B,
+    /// proportional to the increase in the invariant
+    fn deposit_single_token_type(
+        &self,
+        source_amount: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        pool_supply: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        if source_amount == 0 {
+            return Some(0);
+        }
+        let d = compute_d(self.amp, swap_token_a_amount, swap_token_b_amount)?;
+        let (new_swap_token_a_amount, new_swap_token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (
+                swap_token_a_amount.checked_add(source_amount)?,
+                swap_token_b_amount,
+            ),
+            TradeDirection::BtoA => (
+                swap_token_a_amount,
+                swap_token_b_amount.checked_add(source_amount)?,
+            ),
+        };
+        let new_d = compute_d(self.amp, new_swap_token_a_amount, new_swap_token_b_amount)?;
+        pool_tokens_from_invariant_change(&d, &new_d, pool_supply, RoundDirection::Floor)
+    }
+
+    /// Get the amount of pool tokens for the withdrawn amount of token A or B,
+    /// proportional to the decrease in the invariant
+    fn withdraw_single_token_type_exact_out(
+        &self,
+        source_amount: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        pool_supply: u128,
+        trade_direction: TradeDirection,
+        round_direction: RoundDirection,
+    ) -> Option<u128> {
+        if source_amount == 0 {
+            return Some(0);
+        }
+        let d = compute_d(self.amp, swap_token_a_amount, swap_token_b_amount)?;
+        let (new_swap_token_a_amount, new_swap_token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (
+                swap_token_a_amount.checked_sub(source_amount)?,
+                swap_token_b_amount,
+            ),
+            TradeDirection::BtoA => (
+                swap_token_a_amount,
+                swap_token_b_amount.checked_sub(source_amount)?,
+            ),
+        };
+        let new_d = compute_d(self.amp, new_swap_token_a_amount, new_swap_token_b_amount)?;
+        pool_tokens_from_invariant_change(&d, &new_d, pool_supply, round_direction)
+    }
+
+    /// The invariant `D` already has the dimension of `tokens ^ 1`, and
+    /// represents the total value of the pool when it is balanced
+    fn normalized_value(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<PreciseNumber> {
+        compute_d(self.amp, swap_token_a_amount, swap_token_b_amount)
+    }
+
+    fn validate(&self) -> Result<(), SwapError> {
+        if self.amp < MIN_AMP || self.amp > MAX_AMP {
+            return Err(SwapError::InvalidCurve);
+        }
+        if self.start_ramp_ts < self.stop_ramp_ts
+            && (self.target_amp < MIN_AMP || self.target_amp > MAX_AMP)
+        {
+            return Err(SwapError::InvalidCurve);
+        }
+        Ok(())
+    }
+
+    /// The amplification coefficient is fixed to its interpolated value, so
+    /// that the returned curve is no longer ramping
+    fn at_timestamp(
+        &self,
+        unix_timestamp: UnixTimestamp,
+    ) -> Option<Arc<dyn CurveCalculator + Sync + Send>> {
+        let amp = self.amp_at(unix_timestamp)?;
+        Some(Arc::new(Self {
+            amp,
+            target_amp: amp,
+            start_ramp_ts: unix_timestamp,
+            stop_ramp_ts: unix_timestamp,
+        }))
+    }
+
+    /// Ramps must last at least `MIN_RAMP_DURATION`, cannot start while
+    /// another ramp is in progress, and cannot change the amplification
+    /// coefficient by more than a factor of `MAX_AMP_CHANGE`
+    fn ramp_amplification(
+        &self,
+        target_amp: u64,
+        current_ts: UnixTimestamp,
+        stop_ramp_ts: UnixTimestamp,
+    ) -> Result<Arc<dyn CurveCalculator + Sync + Send>, SwapError> {
+        if self.is_ramping(current_ts) {
+            return Err(SwapError::InvalidRamp);
+        }
+        let min_stop_ramp_ts = current_ts
+            .checked_add(MIN_RAMP_DURATION)
+            .ok_or(SwapError::InvalidRamp)?;
+        if stop_ramp_ts < min_stop_ramp_ts {
+            return Err(SwapError::InvalidRamp);
+        }
+        if !(MIN_AMP..=MAX_AMP).contains(&target_amp) {
+            return Err(SwapError::InvalidCurve);
+        }
+        let amp = self.amp_at(current_ts).ok_or(SwapError::InvalidCurve)?;
+        if target_amp > amp.saturating_mul(MAX_AMP_CHANGE)
+            || target_amp.saturating_mul(MAX_AMP_CHANGE) < amp
+        {
+            return Err(SwapError::InvalidRamp);
+        }
+        Ok(Arc::new(Self {
+            amp,
+            target_amp,
+            start_ramp_ts: current_ts,
+            stop_ramp_ts,
+        }))
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for StableCurve {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Sealed for StableCurve {}
+impl Pack for StableCurve {
+    const LEN: usize = 32;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        (self as &dyn DynPack).pack_into_slice(output);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<StableCurve, ProgramError> {
+        let input = array_ref![input, 0, 32];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (amp, target_amp, start_ramp_ts, stop_ramp_ts) = array_refs![input, 8, 8, 8, 8];
+        Ok(Self {
+            amp: u64::from_le_bytes(*amp),
+            target_amp: u64::from_le_bytes(*target_amp),
+            start_ramp_ts: UnixTimestamp::from_le_bytes(*start_ramp_ts),
+            stop_ramp_ts: UnixTimestamp::from_le_bytes(*stop_ramp_ts),
+        })
+    }
+}
+
+impl DynPack for StableCurve {
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 32];
+        let (amp, target_amp, start_ramp_ts, stop_ramp_ts) = mut_array_refs![output, 8, 8, 8, 8];
+        *amp = self.amp.to_le_bytes();
+        *target_amp = self.target_amp.to_le_bytes();
+        *start_ramp_ts = self.start_ramp_ts.to_le_bytes();
+        *stop_ramp_ts = self.stop_ramp_ts.to_le_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::curve::calculator::{
+            test::{
+                check_curve_value_from_swap, check_deposit_token_conversion,
+                check_pool_value_from_deposit, check_pool_value_from_withdraw,
+                check_withdraw_token_conversion, total_and_intermediate,
+                CONVERSION_BASIS_POINTS_GUARANTEE,
+            },
+            INITIAL_SWAP_POOL_AMOUNT,
+        },
+        proptest::prelude::*,
+        roots::find_roots_cubic,
+    };
+
+    /// Maximum amount for tests, keeping the invariant of two full sides
+    /// within the range of the fixed-point calculations
+    const MAX_TEST_AMOUNT: u64 = u64::MAX >> 8;
+
+    #[test]
+    fn initial_pool_amount() {
+        let calculator = StableCurve {
+            amp: 100,
+            ..StableCurve::default()
+        };
+        assert_eq!(calculator.new_pool_supply(), INITIAL_SWAP_POOL_AMOUNT);
+    }
+
+    #[test]
+    fn pack_curve() {
+        let amp = 1_000;
+        let target_amp = 2_000;
+        let start_ramp_ts = 1_700_000_000;
+        let stop_ramp_ts = 1_700_086_400;
+        let curve = StableCurve {
+            amp,
+            target_amp,
+            start_ramp_ts,
+            stop_ramp_ts,
+        };
+
+        let mut packed = [0u8; StableCurve::LEN];
+        Pack::pack_into_slice(&curve, &mut packed[..]);
+        let unpacked = StableCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&amp.to_le_bytes());
+        packed.extend_from_slice(&target_amp.to_le_bytes());
+        packed.extend_from_slice(&start_ramp_ts.to_le_bytes());
+        packed.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+        let unpacked = StableCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+    }
+
+    #[test]
+    fn amp_at_timestamp() {
+        let curve = StableCurve {
+            amp: 100,
+            ..StableCurve::default()
+        };
+        assert_eq!(curve.amp_at(0), Some(100));
+        assert_eq!(curve.amp_at(1_700_000_000), Some(100));
+        assert!(!curve.is_ramping(0));
+
+        let start_ramp_ts = 1_000;
+        let stop_ramp_ts = start_ramp_ts + MIN_RAMP_DURATION;
+        let ramp_up = StableCurve {
+            amp: 100,
+            target_amp: 1_000,
+            start_ramp_ts,
+            stop_ramp_ts,
+        };
+        assert_eq!(ramp_up.amp_at(0), Some(100));
+        assert_eq!(ramp_up.amp_at(start_ramp_ts), Some(100));
+        assert_eq!(
+            ramp_up.amp_at(start_ramp_ts + MIN_RAMP_DURATION / 2),
+            Some(550)
+        );
+        assert_eq!(ramp_up.amp_at(stop_ramp_ts), Some(1_000));
+        assert_eq!(ramp_up.amp_at(stop_ramp_ts + 1), Some(1_000));
+        assert!(ramp_up.is_ramping(start_ramp_ts));
+        assert!(!ramp_up.is_ramping(stop_ramp_ts));
+
+        let ramp_down = StableCurve {
+            amp: 1_000,
+            target_amp: 100,
+            start_ramp_ts,
+            stop_ramp_ts,
+        };
+        assert_eq!(
+            ramp_down.amp_at(start_ramp_ts + MIN_RAMP_DURATION / 2),
+            Some(550)
+        );
+        assert_eq!(ramp_down.amp_at(stop_ramp_ts), Some(100));
+    }
+
+    #[test]
+    fn ramp_amplification() {
+        let curve = StableCurve {
+            amp: 100,
+            ..StableCurve::default()
+        };
+        let current_ts = 1_000;
+        let stop_ramp_ts = current_ts + MIN_RAMP_DURATION;
+
+        let ramped = curve
+            .ramp_amplification(1_000, current_ts, stop_ramp_ts)
+            .unwrap();
+        let mut packed = [0u8; StableCurve::LEN];
+        ramped.pack_into_slice(&mut packed);
+        let ramped = StableCurve::unpack(&packed).unwrap();
+        assert_eq!(
+            ramped,
+            StableCurve {
+                amp: 100,
+                target_amp: 1_000,
+                start_ramp_ts: current_ts,
+                stop_ramp_ts,
+            }
+        );
+
+        // too short
+        assert_eq!(
+            curve
+                .ramp_amplification(1_000, current_ts, stop_ramp_ts - 1)
+                .unwrap_err(),
+            SwapError::InvalidRamp
+        );
+        // too large a change in either direction
+        assert_eq!(
+            curve
+                .ramp_amplification(1_001, current_ts, stop_ramp_ts)
+                .unwrap_err(),
+            SwapError::InvalidRamp
+        );
+        assert_eq!(
+            curve
+                .ramp_amplification(9, current_ts, stop_ramp_ts)
+                .unwrap_err(),
+            SwapError::InvalidRamp
+        );
+        // out of bounds
+        assert_eq!(
+            curve
+                .ramp_amplification(0, current_ts, stop_ramp_ts)
+                .unwrap_err(),
+            SwapError::InvalidCurve
+        );
+        // already ramping
+        assert_eq!(
+            ramped
+                .ramp_amplification(500, current_ts + 1, stop_ramp_ts + 1)
+                .unwrap_err(),
+            SwapError::InvalidRamp
+        );
+
+        // stopping the ramp halfway fixes the current value
+        let stopped = ramped
+            .at_timestamp(current_ts + MIN_RAMP_DURATION / 2)
+            .unwrap();
+        stopped.pack_into_slice(&mut packed);
+        let stopped = StableCurve::unpack(&packed).unwrap();
+        assert_eq!(stopped.amp, 550);
+        assert!(!stopped.is_ramping(current_ts + MIN_RAMP_DURATION / 2));
+        assert_eq!(stopped.amp_at(stop_ramp_ts), Some(550));
+    }
+
+    #[test]
+    fn validate_amp() {
+        assert_eq!(
+            StableCurve {
+                amp: 0,
+                ..StableCurve::default()
+            }
+            .validate(),
+            Err(SwapError::InvalidCurve)
+        );
+        assert_eq!(
+            StableCurve {
+                amp: MAX_AMP + 1,
+                ..StableCurve::default()
+            }
+            .validate(),
+            Err(SwapError::InvalidCurve)
+        );
+        assert_eq!(
+            StableCurve {
+                amp: MIN_AMP,
+                ..StableCurve::default()
+            }
+            .validate(),
+            Ok(())
+        );
+        assert_eq!(
+            StableCurve {
+                amp: MAX_AMP,
+                ..StableCurve::default()
+            }
+            .validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn balanced_invariant() {
+        // a balanced pool behaves like a constant sum, so D is the sum
+        let d = compute_d(100, 1_000_000, 1_000_000).unwrap();
+        assert_eq!(d.to_imprecise().unwrap(), 2_000_000);
+        let d = compute_d(100, 0, 0).unwrap();
+        assert_eq!(d.to_imprecise().unwrap(), 0);
+    }
+
+    fn check_invariant_against_cubic(
+        amp: u64,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) {
+        // D^3 / (n^n * x * y) + (A * n^n - 1) * D - A * n^n * (x + y) = 0
+        let ann = amp as f64 * N_COINS_POW_N as f64;
+        let x = swap_token_a_amount as f64;
+        let y = swap_token_b_amount as f64;
+        let roots = find_roots_cubic(
+            1.0 / (N_COINS_POW_N as f64 * x * y),
+            0.0,
+            ann - 1.0,
+            -ann * (x + y),
+        );
+        let expected = roots
+            .as_ref()
+            .iter()
+            .copied()
+            .find(|root| *root > 0.0)
+            .unwrap();
+        let d = compute_d(amp, swap_token_a_amount, swap_token_b_amount)
+            .unwrap()
+            .to_imprecise()
+            .unwrap() as f64;
+        let difference = (d - expected).abs();
+        assert!(
+            difference <= f64::max(1.0, expected * 1e-9),
+            "invariant {} should be close to {}",
+            d,
+            expected
+        );
+    }
+
+    #[test]
+    fn imbalanced_invariant() {
+        check_invariant_against_cubic(1, 1_000_000, 1_000);
+        check_invariant_against_cubic(100, 1_000_000, 1_000);
+        check_invariant_against_cubic(100, 50_000_000_000, 1_000_000_000_000);
+        check_invariant_against_cubic(MAX_AMP, 1_000_000, 10);
+    }
+
+    #[test]
+    fn swap_close_to_constant_sum() {
+        let swap_source_amount: u128 = 1_000_000_000;
+        let swap_destination_amount: u128 = 1_000_000_000;
+        let source_amount: u128 = 1_000_000;
+
+        // a high amplification gets very close to 1:1
+        let curve = StableCurve {
+            amp: 1_000,
+            ..StableCurve::default()
+        };
+        let result = curve
+            .swap_without_fees(
+                source_amount,
+                swap_source_amount,
+                swap_destination_amount,
+                TradeDirection::AtoB,
+            )
+            .unwrap();
+        assert_eq!(result.source_amount_swapped, source_amount);
+        assert!(result.destination_amount_swapped < source_amount);
+        assert!(result.destination_amount_swapped > source_amount * 9_999 / 10_000);
+
+        // a lower amplification gives more slippage
+        let curve = StableCurve {
+            amp: 1,
+            ..StableCurve::default()
+        };
+        let low_amp_result = curve
+            .swap_without_fees(
+                source_amount,
+                swap_source_amount,
+                swap_destination_amount,
+                TradeDirection::AtoB,
+            )
+            .unwrap();
+        assert!(low_amp_result.destination_amount_swapped < result.destination_amount_swapped);
+    }
+
+    #[test]
+    fn swap_too_small() {
+        let curve = StableCurve {
+            amp: 100,
+            ..StableCurve::default()
+        };
+        assert!(curve
+            .swap_without_fees(1, 70_000_000_000, 4_000, TradeDirection::AtoB)
+            .is_none());
+    }
+
+    proptest! {
+           #[test]
+           fn deposit_token_conversion(
+               // the separate deposits only match a single-side deposit up to
+               // terms of the second order in the deposit size, so the deposit
+               // is kept under 1% of the smaller side of the pool
+               deposit_basis_points in 1..100u128,
+               swap_source_amount in 1..MAX_TEST_AMOUNT,
+               swap_destination_amount in 1..MAX_TEST_AMOUNT,
+               pool_supply in INITIAL_SWAP_POOL_AMOUNT..u64::MAX as u128,
+               amp in MIN_AMP..=MAX_AMP,
+           ) {
+               let curve = StableCurve {
+    amp,
+    ..StableCurve::default()
+    };
+               let swap_source_amount = swap_source_amount as u128;
+               let swap_destination_amount = swap_destination_amount as u128;
+               let source_token_amount = std::cmp::min(swap_source_amount, swap_destination_amount)
+                   * deposit_basis_points
+                   / 10_000;
+               // in the pool token conversion calcs, we simulate trading half of
+               // source_token_amount, so this needs to be at least 2
+               prop_assume!(source_token_amount >= 2);
+               // Make sure that the half swap gives at least one token
+               prop_assume!(curve.swap_without_fees(
+                   source_token_amount / 2,
+                   swap_source_amount,
+                   swap_destination_amount,
+                   TradeDirection::AtoB
+               ).is_some());
+               check_deposit_token_conversion(
+                   &curve,
+                   source_token_amount,
+                   swap_source_amount,
+                   swap_destination_amount,
+                   TradeDirection::AtoB,
+                   pool_supply,
+                   CONVERSION_BASIS_POINTS_GUARANTEE,
+               );
+           }
+       }
+
+    proptest! {
+           #[test]
+           fn withdraw_token_conversion(
+               (pool_token_supply, pool_token_amount) in total_and_intermediate(u64::MAX),
+               swap_token_a_amount in 1..MAX_TEST_AMOUNT,
+               swap_token_b_amount in 1..MAX_TEST_AMOUNT,
+               amp in MIN_AMP..=MAX_AMP,
+           ) {
+               let curve = StableCurve {
+    amp,
+    ..StableCurve::default()
+    };
+               let pool_token_amount = pool_token_amount as u128;
+               let pool_token_supply = pool_token_supply as u128;
+               let swap_token_a_amount = swap_token_a_amount as u128;
+               let swap_token_b_amount = swap_token_b_amount as u128;
+               // Make sure we will get at least one trading token out for each
+               // side, and that the withdrawn tokens can be swapped
+               let withdraw_result = curve.pool_tokens_to_trading_tokens(
+                   pool_token_amount,
+                   pool_token_supply,
+                   swap_token_a_amount,
+                   swap_token_b_amount,
+                   RoundDirection::Floor,
+               ).unwrap();
+               prop_assume!(withdraw_result.token_a_amount >= 1);
+               prop_assume!(withdraw_result.token_b_amount >= 1);
+               prop_assume!(withdraw_result.token_a_amount < swap_token_a_amount);
+               prop_assume!(curve.swap_without_fees(
+                   withdraw_result.token_a_amount,
+                   swap_token_a_amount - withdraw_result.token_a_amount,
+                   swap_token_b_amount - withdraw_result.token_b_amount,
+                   TradeDirection::AtoB
+               ).is_some());
+               check_withdraw_token_conversion(
+                   &curve,
+                   pool_token_amount,
+                   pool_token_supply,
+                   swap_token_a_amount,
+                   swap_token_b_amount,
+                   TradeDirection::AtoB,
+                   CONVERSION_BASIS_POINTS_GUARANTEE
+               );
+           }
+       }
+
+    proptest! {
+           #[test]
+           fn curve_value_does_not_decrease_from_swap(
+               source_token_amount in 1..MAX_TEST_AMOUNT,
+               swap_source_amount in 1..MAX_TEST_AMOUNT,
+               swap_destination_amount in 1..MAX_TEST_AMOUNT,
+               amp in MIN_AMP..=MAX_AMP,
+           ) {
+               let curve = StableCurve {
+    amp,
+    ..StableCurve::default()
+    };
+               let source_token_amount = source_token_amount as u128;
+               let swap_source_amount = swap_source_amount as u128;
+               let swap_destination_amount = swap_destination_amount as u128;
+               prop_assume!(curve.swap_without_fees(
+                   source_token_amount,
+                   swap_source_amount,
+                   swap_destination_amount,
+                   TradeDirection::AtoB
+               ).is_some());
+               check_curve_value_from_swap(
+                   &curve,
+                   source_token_amount,
+                   swap_source_amount,
+                   swap_destination_amount,
+                   TradeDirection::AtoB
+               );
+           }
+       }
+
+    proptest! {
+           #[test]
+           fn curve_value_does_not_decrease_from_deposit(
+               pool_token_amount in 1..u64::MAX,
+               pool_token_supply in 1..u64::MAX,
+               swap_token_a_amount in 1..MAX_TEST_AMOUNT,
+               swap_token_b_amount in 1..MAX_TEST_AMOUNT,
+               amp in MIN_AMP..=MAX_AMP,
+           ) {
+               let pool_token_amount = pool_token_amount as u128;
+               let pool_token_supply = pool_token_supply as u128;
+               let swap_token_a_amount = swap_token_a_amount as u128;
+               let swap_token_b_amount = swap_token_b_amount as u128;
+               // Make sure we will get at least one trading token out for each
+               // side, otherwise the calculation fails
+               prop_assume!(pool_token_amount * swap_token_a_amount / pool_token_supply >= 1);
+               prop_assume!(pool_token_amount * swap_token_b_amount / pool_token_supply >= 1);
+               let curve = StableCurve {
+    amp,
+    ..StableCurve::default()
+    };
+               check_pool_value_from_deposit(
+                   &curve,
+                   pool_token_amount,
+                   pool_token_supply,
+                   swap_token_a_amount,
+                   swap_token_b_amount,
+               );
+           }
+       }
+
+    proptest! {
+           #[test]
+           fn curve_value_does_not_decrease_from_withdraw(
+               (pool_token_supply, pool_token_amount) in total_and_intermediate(u64::MAX),
+               swap_token_a_amount in 1..MAX_TEST_AMOUNT,
+               swap_token_b_amount in 1..MAX_TEST_AMOUNT,
+               amp in MIN_AMP..=MAX_AMP,
+           ) {
+               let pool_token_amount = pool_token_amount as u128;
+               let pool_token_supply = pool_token_supply as u128;
+               let swap_token_a_amount = swap_token_a_amount as u128;
+               let swap_token_b_amount = swap_token_b_amount as u128;
+               // Make sure we will get at least one trading token out for each
+               // side, and that the pool keeps some of each, otherwise the
+               // calculation fails
+               prop_assume!(pool_token_amount * swap_token_a_amount / pool_token_supply >= 1);
+               prop_assume!(pool_token_amount * swap_token_b_amount / pool_token_supply >= 1);
+               prop_assume!(pool_token_amount < pool_token_supply);
+               let curve = StableCurve {
+    amp,
+    ..StableCurve::default()
+    };
+               check_pool_value_from_withdraw(
+                   &curve,
+                   pool_token_amount,
+                   pool_token_supply,
+                   swap_token_a_amount,
+                   swap_token_b_amount,
+               );
+           }
+       }
+}
@@ -117,6 +117,10 @@ pub enum SwapError {
     /// The pool fee account is invalid.
     #[error("The pool fee account is invalid")]
     InvalidFeeAccount,
+    /// The amplification ramp is too short, too steep, or overlaps another
+    /// ramp.
+    #[error("The amplification ramp is invalid")]
+    InvalidRamp,
 }
 impl From<SwapError> for ProgramError {
     fn from(e: SwapError) -> Self {
@@ -200,6 +204,9 @@ impl PrintProgramError for SwapError {
             SwapError::InvalidFeeAccount => {
                 msg!("Error: The pool fee account is invalid")
             }
+            SwapError::InvalidRamp => {
+                msg!("Error: The amplification ramp is invalid")
+            }
         }
     }
 }
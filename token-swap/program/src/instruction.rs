@@ -10,6 +10,7 @@ use {
         error::SwapError,
     },
     solana_program::{
+        clock::UnixTimestamp,
         instruction::{AccountMeta, Instruction},
         program_error::ProgramError,
         program_pack::Pack,
@@ -37,8 +38,8 @@ pub struct Swap {
     /// SOURCE amount to transfer, output to DESTINATION is based on the
     /// exchange rate
     pub amount_in: u64,
-    /// Minimum amount of DESTINATION token to output, prevents excessive
-    /// slippage
+    /// Minimum amount of DESTINATION token to receive after any transfer
+    /// fee, prevents excessive slippage
     pub minimum_amount_out: u64,
 }
 
@@ -50,9 +51,11 @@ pub struct DepositAllTokenTypes {
     /// Pool token amount to transfer. token_a and token_b amount are set by
     /// the current exchange rate and size of the pool
     pub pool_token_amount: u64,
-    /// Maximum token A amount to deposit, prevents excessive slippage
+    /// Maximum token A amount to deposit, including any transfer fee,
+    /// prevents excessive slippage
     pub maximum_token_a_amount: u64,
-    /// Maximum token B amount to deposit, prevents excessive slippage
+    /// Maximum token B amount to deposit, including any transfer fee,
+    /// prevents excessive slippage
     pub maximum_token_b_amount: u64,
 }
 
@@ -64,9 +67,11 @@ pub struct WithdrawAllTokenTypes {
     /// Amount of pool tokens to burn. User receives an output of token a
     /// and b based on the percentage of the pool tokens that are returned.
     pub pool_token_amount: u64,
-    /// Minimum amount of token A to receive, prevents excessive slippage
+    /// Minimum amount of token A to receive after any transfer fee, prevents
+    /// excessive slippage
     pub minimum_token_a_amount: u64,
-    /// Minimum amount of token B to receive, prevents excessive slippage
+    /// Minimum amount of token B to receive after any transfer fee, prevents
+    /// excessive slippage
     pub minimum_token_b_amount: u64,
 }
 
@@ -75,7 +80,8 @@ pub struct WithdrawAllTokenTypes {
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct DepositSingleTokenTypeExactAmountIn {
-    /// Token amount to deposit
+    /// Token amount to deposit. Any transfer fee is deducted before the
+    /// amount is added to the pool
     pub source_token_amount: u64,
     /// Pool token amount to receive in exchange. The amount is set by
     /// the current exchange rate and size of the pool
@@ -87,13 +93,39 @@ pub struct DepositSingleTokenTypeExactAmountIn {
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct WithdrawSingleTokenTypeExactAmountOut {
-    /// Amount of token A or B to receive
+    /// Amount of token A or B to receive after any transfer fee
     pub destination_token_amount: u64,
     /// Maximum amount of pool tokens to burn. User receives an output of token
     /// A or B based on the percentage of the pool tokens that are returned.
     pub maximum_pool_token_amount: u64,
 }
 
+/// RouteSwap instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteSwap {
+    /// SOURCE amount to transfer into the first pool of the route
+    pub amount_in: u64,
+    /// Minimum amount of DESTINATION token to receive from the last pool
+    /// after any transfer fee, prevents excessive slippage
+    pub minimum_amount_out: u64,
+    /// Number of extra accounts required by transfer hooks following the
+    /// accounts of each pool of the route, empty if there are none
+    pub transfer_hook_account_counts: Vec<u8>,
+}
+
+/// RampAmplification instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RampAmplification {
+    /// Amplification coefficient to reach at the end of the ramp
+    pub target_amp: u64,
+    /// Unix timestamp at which the ramp stops, and the target is reached
+    pub stop_ramp_ts: UnixTimestamp,
+}
+
 /// Instructions supported by the token swap program.
 #[repr(C)]
 #[derive(Debug, PartialEq)]
@@ -116,7 +148,7 @@ pub enum SwapInstruction {
 
     ///   Swap the tokens in the pool.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by
@@ -135,14 +167,17 @@ pub enum SwapInstruction {
     ///   12. `[]` Token (A|B) DESTINATION program id
     ///   13. `[]` Pool Token program id
     ///   14. `[optional, writable]` Host fee account to receive additional
-    ///       trading fees
+    ///       trading fees, or the token-swap program id for no host.  Required
+    ///       if transfer hook accounts are given.
+    ///   15. ..15+N `[]` Extra accounts required by the transfer hooks of the
+    ///       token mints
     Swap(Swap),
 
     ///   Deposit both types of tokens into the pool.  The output is a "pool"
     ///   token representing ownership in the pool. Inputs are converted to
     ///   the current ratio.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_a user transfer authority can transfer amount,
@@ -157,13 +192,15 @@ pub enum SwapInstruction {
     ///   11. `[]` Token A program id
     ///   12. `[]` Token B program id
     ///   13. `[]` Pool Token program id
+    ///   14. ..14+N `[]` Extra accounts required by the transfer hooks of the
+    ///       token mints
     DepositAllTokenTypes(DepositAllTokenTypes),
 
     ///   Withdraw both types of tokens from the pool at the current ratio,
     ///   given pool tokens. The pool tokens are burned in exchange for an
     ///   equivalent amount of token A and B.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` Pool mint account, swap authority is the owner
@@ -179,13 +216,15 @@ pub enum SwapInstruction {
     ///   12. `[]` Pool Token program id
     ///   13. `[]` Token A program id
     ///   14. `[]` Token B program id
+    ///   15. ..15+N `[]` Extra accounts required by the transfer hooks of the
+    ///       token mints
     WithdrawAllTokenTypes(WithdrawAllTokenTypes),
 
     ///   Deposit one type of tokens into the pool. The output is a "pool"
     ///   token representing ownership into the pool. Input token is
     ///   converted as if a swap and deposit all token types were performed.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by
@@ -198,12 +237,14 @@ pub enum SwapInstruction {
     ///   8. `[]` Token (A|B) SOURCE mint
     ///   9. `[]` Token (A|B) SOURCE program id
     ///   10. `[]` Pool Token program id
+    ///   11. ..11+N `[]` Extra accounts required by the transfer hook of the
+    ///       SOURCE mint
     DepositSingleTokenTypeExactAmountIn(DepositSingleTokenTypeExactAmountIn),
 
     ///   Withdraw one token type from the pool at the current ratio given the
     ///   exact amount out expected.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` Pool mint account, swap authority is the owner
@@ -216,9 +257,63 @@ pub enum SwapInstruction {
     ///   9. `[]` Token (A|B) DESTINATION mint
     ///   10. `[]` Pool Token program id
     ///   11. `[]` Token (A|B) DESTINATION program id
+    ///   12. ..12+N `[]` Extra accounts required by the transfer hook of the
+    ///       DESTINATION mint
     WithdrawSingleTokenTypeExactAmountOut(WithdrawSingleTokenTypeExactAmountOut),
+
+    ///   Linearly ramp the amplification coefficient of the curve from its
+    ///   current value to a target value, reached at the given time.  Only
+    ///   supported by curves with an amplification coefficient.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Swap admin, the owner key of the swap constraints.
+    ///      Fails if the program runs without constraints
+    ///   2. `[]` Pool fee account
+    RampAmplification(RampAmplification),
+
+    ///   Stop any amplification ramp in progress, keeping the amplification
+    ///   coefficient at its current value.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Swap admin, the owner key of the swap constraints.
+    ///      Fails if the program runs without constraints
+    ///   2. `[]` Pool fee account
+    StopRamp,
+
+    ///   Swap through a route of pools, where the output of each pool is the
+    ///   input of the next one.  Only the final output is checked for
+    ///   slippage.  A pool only passes on the amount the next pool's curve
+    ///   consumes, and keeps the rest of its output.
+    ///
+    ///   0. `[signer]` user transfer authority
+    ///   1. `[writable]` SOURCE Account, amount is transferable by user
+    ///      transfer authority
+    ///   2. `[writable]` DESTINATION Account assigned to USER as the owner
+    ///
+    ///   Followed by `ROUTE_HOP_ACCOUNTS` accounts for each pool of the route,
+    ///   in order:
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` swap authority
+    ///   2. `[writable]` token_(A|B) Base Account to swap INTO
+    ///   3. `[writable]` token_(A|B) Base Account to swap FROM
+    ///   4. `[writable]` Pool token mint, to generate trading fees
+    ///   5. `[writable]` Fee account, to receive trading fees
+    ///   6. `[]` Token (A|B) SOURCE mint
+    ///   7. `[]` Token (A|B) DESTINATION mint
+    ///   8. `[]` Token (A|B) SOURCE program id
+    ///   9. `[]` Token (A|B) DESTINATION program id
+    ///   10. `[]` Pool Token program id
+    ///   11. ..11+N `[]` Extra accounts required by the transfer hook of the
+    ///       SOURCE mint, and of the DESTINATION mint for the last pool.  N
+    ///       is given by the pool's entry in `transfer_hook_account_counts`
+    RouteSwap(RouteSwap),
 }
 
+/// Number of accounts required for each pool of a
+/// [RouteSwap](enum.SwapInstruction.html)
+pub const ROUTE_HOP_ACCOUNTS: usize = 11;
+
 impl SwapInstruction {
     /// Unpacks a byte buffer into a
     /// [SwapInstruction](enum.SwapInstruction.html).
@@ -279,10 +374,33 @@ impl SwapInstruction {
                     maximum_pool_token_amount,
                 })
             }
+            6 => {
+                let (target_amp, rest) = Self::unpack_u64(rest)?;
+                let (stop_ramp_ts, _rest) = Self::unpack_i64(rest)?;
+                Self::RampAmplification(RampAmplification {
+                    target_amp,
+                    stop_ramp_ts,
+                })
+            }
+            7 => Self::StopRamp,
+            8 => {
+                let (amount_in, rest) = Self::unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = Self::unpack_u64(rest)?;
+                Self::RouteSwap(RouteSwap {
+                    amount_in,
+                    minimum_amount_out,
+                    transfer_hook_account_counts: rest.to_vec(),
+                })
+            }
             _ => return Err(SwapError::InvalidInstruction.into()),
         })
     }
 
+    fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+        let (value, rest) = Self::unpack_u64(input)?;
+        Ok((value as i64, rest))
+    }
+
     fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
         if input.len() >= 8 {
             let (amount, rest) = input.split_at(8);
@@ -356,6 +474,25 @@ impl SwapInstruction {
                 buf.extend_from_slice(&destination_token_amount.to_le_bytes());
                 buf.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
             }
+            Self::RampAmplification(RampAmplification {
+                target_amp,
+                stop_ramp_ts,
+            }) => {
+                buf.push(6);
+                buf.extend_from_slice(&target_amp.to_le_bytes());
+                buf.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+            }
+            Self::StopRamp => buf.push(7),
+            Self::RouteSwap(RouteSwap {
+                amount_in,
+                minimum_amount_out,
+                transfer_hook_account_counts,
+            }) => {
+                buf.push(8);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                buf.extend_from_slice(transfer_hook_account_counts);
+            }
         }
         buf
     }
@@ -418,7 +555,7 @@ pub fn deposit_all_token_types(
     let data = SwapInstruction::DepositAllTokenTypes(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*deposit_token_a_pubkey, false),
@@ -464,7 +601,7 @@ pub fn withdraw_all_token_types(
     let data = SwapInstruction::WithdrawAllTokenTypes(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*pool_mint_pubkey, false),
@@ -507,7 +644,7 @@ pub fn deposit_single_token_type_exact_amount_in(
     let data = SwapInstruction::DepositSingleTokenTypeExactAmountIn(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*source_token_pubkey, false),
@@ -547,7 +684,7 @@ pub fn withdraw_single_token_type_exact_amount_out(
     let data = SwapInstruction::WithdrawSingleTokenTypeExactAmountOut(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*pool_mint_pubkey, false),
@@ -569,6 +706,10 @@ pub fn withdraw_single_token_type_exact_amount_out(
 }
 
 /// Creates a 'swap' instruction.
+///
+/// The host fee account slot is always filled, with the program id when there
+/// is no host, so that accounts required by the transfer hooks of the token
+/// mints can be appended after it.
 pub fn swap(
     program_id: &Pubkey,
     source_token_program_id: &Pubkey,
@@ -591,7 +732,7 @@ pub fn swap(
     let data = SwapInstruction::Swap(instruction).pack();
 
     let mut accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*source_pubkey, false),
@@ -606,8 +747,141 @@ pub fn swap(
         AccountMeta::new_readonly(*destination_token_program_id, false),
         AccountMeta::new_readonly(*pool_token_program_id, false),
     ];
-    if let Some(host_fee_pubkey) = host_fee_pubkey {
-        accounts.push(AccountMeta::new(*host_fee_pubkey, false));
+    accounts.push(match host_fee_pubkey {
+        Some(host_fee_pubkey) => AccountMeta::new(*host_fee_pubkey, false),
+        None => AccountMeta::new_readonly(*program_id, false),
+    });
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'ramp_amplification' instruction.
+pub fn ramp_amplification(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+    instruction: RampAmplification,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::RampAmplification(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*pool_fee_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'stop_ramp' instruction.
+pub fn stop_ramp(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::StopRamp.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*pool_fee_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Accounts of one pool along a route, used to build a 'route_swap'
+/// instruction
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteSwapHop {
+    /// Token-swap account
+    pub swap_pubkey: Pubkey,
+    /// Swap authority
+    pub authority_pubkey: Pubkey,
+    /// Swap token account receiving the hop's input
+    pub swap_source_pubkey: Pubkey,
+    /// Swap token account sending the hop's output
+    pub swap_destination_pubkey: Pubkey,
+    /// Pool token mint
+    pub pool_mint_pubkey: Pubkey,
+    /// Pool fee account
+    pub pool_fee_pubkey: Pubkey,
+    /// Mint of the hop's input
+    pub source_mint_pubkey: Pubkey,
+    /// Mint of the hop's output
+    pub destination_mint_pubkey: Pubkey,
+    /// Token program of the hop's input
+    pub source_token_program_id: Pubkey,
+    /// Token program of the hop's output
+    pub destination_token_program_id: Pubkey,
+    /// Token program of the pool mint
+    pub pool_token_program_id: Pubkey,
+    /// Extra accounts required by the transfer hook of the hop's input mint,
+    /// and of its output mint for the last hop of the route
+    pub transfer_hook_pubkeys: Vec<Pubkey>,
+}
+
+/// Creates a 'route_swap' instruction.  The transfer hook account counts of
+/// the instruction data are taken from the hops.
+pub fn route_swap(
+    program_id: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    hops: &[RouteSwapHop],
+    instruction: RouteSwap,
+) -> Result<Instruction, ProgramError> {
+    let transfer_hook_account_counts = hops
+        .iter()
+        .map(|hop| u8::try_from(hop.transfer_hook_pubkeys.len()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    let data = SwapInstruction::RouteSwap(RouteSwap {
+        transfer_hook_account_counts,
+        ..instruction
+    })
+    .pack();
+
+    let mut accounts = Vec::with_capacity(3 + hops.len() * ROUTE_HOP_ACCOUNTS);
+    accounts.push(AccountMeta::new_readonly(
+        *user_transfer_authority_pubkey,
+        true,
+    ));
+    accounts.push(AccountMeta::new(*source_pubkey, false));
+    accounts.push(AccountMeta::new(*destination_pubkey, false));
+    for hop in hops {
+        accounts.extend_from_slice(&[
+            AccountMeta::new(hop.swap_pubkey, false),
+            AccountMeta::new_readonly(hop.authority_pubkey, false),
+            AccountMeta::new(hop.swap_source_pubkey, false),
+            AccountMeta::new(hop.swap_destination_pubkey, false),
+            AccountMeta::new(hop.pool_mint_pubkey, false),
+            AccountMeta::new(hop.pool_fee_pubkey, false),
+            AccountMeta::new_readonly(hop.source_mint_pubkey, false),
+            AccountMeta::new_readonly(hop.destination_mint_pubkey, false),
+            AccountMeta::new_readonly(hop.source_token_program_id, false),
+            AccountMeta::new_readonly(hop.destination_token_program_id, false),
+            AccountMeta::new_readonly(hop.pool_token_program_id, false),
+        ]);
+        accounts.extend(
+            hop.transfer_hook_pubkeys
+                .iter()
+                .map(|pubkey| AccountMeta::new_readonly(*pubkey, false)),
+        );
     }
 
     Ok(Instruction {
@@ -776,4 +1050,51 @@ mod tests {
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
     }
+
+    #[test]
+    fn pack_ramp_amplification() {
+        let target_amp: u64 = 1_000;
+        let stop_ramp_ts: UnixTimestamp = 1_700_086_400;
+        let check = SwapInstruction::RampAmplification(RampAmplification {
+            target_amp,
+            stop_ramp_ts,
+        });
+        let packed = check.pack();
+        let mut expect = vec![6];
+        expect.extend_from_slice(&target_amp.to_le_bytes());
+        expect.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_route_swap() {
+        let amount_in: u64 = 2;
+        let minimum_amount_out: u64 = 10;
+        let transfer_hook_account_counts = vec![0, 3];
+        let check = SwapInstruction::RouteSwap(RouteSwap {
+            amount_in,
+            minimum_amount_out,
+            transfer_hook_account_counts: transfer_hook_account_counts.clone(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![8];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.extend_from_slice(&transfer_hook_account_counts);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_stop_ramp() {
+        let check = SwapInstruction::StopRamp;
+        let packed = check.pack();
+        let expect = vec![7];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
 }
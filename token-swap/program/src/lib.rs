@@ -7,7 +7,9 @@ pub mod constraints;
 pub mod curve;
 pub mod error;
 pub mod instruction;
+pub mod oracle;
 pub mod processor;
+pub mod route;
 pub mod state;
 
 #[cfg(not(feature = "no-entrypoint"))]
@@ -0,0 +1,241 @@
+//! Time-weighted average price oracle
+
+use {
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    solana_program::{
+        clock::{Slot, UnixTimestamp},
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+    },
+};
+
+/// Number of fractional bits in the fixed-point prices of the oracle
+pub const PRICE_FRACTIONAL_BITS: u32 = 64;
+
+/// Cumulative prices of a pool, like Uniswap v2 oracles.  Every price is an
+/// unsigned 64.64 fixed-point number, multiplied by the number of seconds it
+/// was held.  The accumulators wrap around on overflow, only the difference
+/// between two observations is meaningful.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PriceOracle {
+    /// Sum of the price of token A, in token B, for every second
+    pub price_a_cumulative: u128,
+    /// Sum of the price of token B, in token A, for every second
+    pub price_b_cumulative: u128,
+    /// Unix timestamp of the last update
+    pub last_update_timestamp: UnixTimestamp,
+    /// Slot of the last update
+    pub last_update_slot: Slot,
+}
+
+/// Snapshot of the oracle at a given time
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PriceObservation {
+    /// Cumulative price of token A at the time of the observation
+    pub price_a_cumulative: u128,
+    /// Cumulative price of token B at the time of the observation
+    pub price_b_cumulative: u128,
+    /// Unix timestamp of the observation
+    pub timestamp: UnixTimestamp,
+}
+
+/// Time-weighted average prices between two observations, as unsigned 64.64
+/// fixed-point numbers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeWeightedAveragePrice {
+    /// Average price of token A, in token B
+    pub price_a: u128,
+    /// Average price of token B, in token A
+    pub price_b: u128,
+}
+
+impl PriceOracle {
+    /// Creates an oracle starting at the given time
+    pub fn new(unix_timestamp: UnixTimestamp, slot: Slot) -> Self {
+        Self {
+            last_update_timestamp: unix_timestamp,
+            last_update_slot: slot,
+            ..Self::default()
+        }
+    }
+
+    /// Accumulates the prices given by the pool amounts, held since the last
+    /// update.  Must be called before the amounts change.
+    pub fn update(
+        &mut self,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        unix_timestamp: UnixTimestamp,
+        slot: Slot,
+    ) {
+        let observation = self.observe(token_a_amount, token_b_amount, unix_timestamp);
+        self.price_a_cumulative = observation.price_a_cumulative;
+        self.price_b_cumulative = observation.price_b_cumulative;
+        self.last_update_timestamp = observation.timestamp;
+        self.last_update_slot = self.last_update_slot.max(slot);
+    }
+
+    /// Observes the cumulative prices at the given time, as if the oracle was
+    /// updated with the current pool amounts.  Does not modify the oracle, so
+    /// it may be used off-chain on the pool state.
+    pub fn observe(
+        &self,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        unix_timestamp: UnixTimestamp,
+    ) -> PriceObservation {
+        let mut observation = PriceObservation {
+            price_a_cumulative: self.price_a_cumulative,
+            price_b_cumulative: self.price_b_cumulative,
+            timestamp: self.last_update_timestamp.max(unix_timestamp),
+        };
+        let elapsed = unix_timestamp.saturating_sub(self.last_update_timestamp);
+        if elapsed > 0 && token_a_amount > 0 && token_b_amount > 0 {
+            let elapsed = elapsed as u128;
+            observation.price_a_cumulative = observation
+                .price_a_cumulative
+                .wrapping_add(price(token_b_amount, token_a_amount).wrapping_mul(elapsed));
+            observation.price_b_cumulative = observation
+                .price_b_cumulative
+                .wrapping_add(price(token_a_amount, token_b_amount).wrapping_mul(elapsed));
+        }
+        observation
+    }
+}
+
+/// Calculates the time-weighted average prices between two observations,
+/// returning `None` if no time elapsed between them
+pub fn time_weighted_average_price(
+    older: &PriceObservation,
+    newer: &PriceObservation,
+) -> Option<TimeWeightedAveragePrice> {
+    let elapsed = newer.timestamp.checked_sub(older.timestamp)?;
+    if elapsed <= 0 {
+        return None;
+    }
+    let elapsed = elapsed as u128;
+    Some(TimeWeightedAveragePrice {
+        price_a: newer
+            .price_a_cumulative
+            .wrapping_sub(older.price_a_cumulative)
+            / elapsed,
+        price_b: newer
+            .price_b_cumulative
+            .wrapping_sub(older.price_b_cumulative)
+            / elapsed,
+    })
+}
+
+/// Price of one base token in quote tokens, as an unsigned 64.64 fixed-point
+/// number
+fn price(quote_amount: u64, base_amount: u64) -> u128 {
+    ((quote_amount as u128) << PRICE_FRACTIONAL_BITS) / base_amount as u128
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for PriceOracle {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for PriceOracle {}
+impl Pack for PriceOracle {
+    const LEN: usize = 48;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 48];
+        let (price_a_cumulative, price_b_cumulative, last_update_timestamp, last_update_slot) =
+            mut_array_refs![output, 16, 16, 8, 8];
+        *price_a_cumulative = self.price_a_cumulative.to_le_bytes();
+        *price_b_cumulative = self.price_b_cumulative.to_le_bytes();
+        *last_update_timestamp = self.last_update_timestamp.to_le_bytes();
+        *last_update_slot = self.last_update_slot.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<PriceOracle, ProgramError> {
+        let input = array_ref![input, 0, 48];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (price_a_cumulative, price_b_cumulative, last_update_timestamp, last_update_slot) =
+            array_refs![input, 16, 16, 8, 8];
+        Ok(Self {
+            price_a_cumulative: u128::from_le_bytes(*price_a_cumulative),
+            price_b_cumulative: u128::from_le_bytes(*price_b_cumulative),
+            last_update_timestamp: i64::from_le_bytes(*last_update_timestamp),
+            last_update_slot: u64::from_le_bytes(*last_update_slot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE: u128 = 1 << PRICE_FRACTIONAL_BITS;
+
+    #[test]
+    fn pack_price_oracle() {
+        let price_oracle = PriceOracle {
+            price_a_cumulative: u128::MAX - 1,
+            price_b_cumulative: 42,
+            last_update_timestamp: 1_700_000_000,
+            last_update_slot: 250_000_000,
+        };
+        let mut packed = [0u8; PriceOracle::LEN];
+        Pack::pack_into_slice(&price_oracle, &mut packed[..]);
+        let unpacked = PriceOracle::unpack_from_slice(&packed).unwrap();
+        assert_eq!(price_oracle, unpacked);
+
+        let mut expect = vec![];
+        expect.extend_from_slice(&price_oracle.price_a_cumulative.to_le_bytes());
+        expect.extend_from_slice(&price_oracle.price_b_cumulative.to_le_bytes());
+        expect.extend_from_slice(&price_oracle.last_update_timestamp.to_le_bytes());
+        expect.extend_from_slice(&price_oracle.last_update_slot.to_le_bytes());
+        assert_eq!(packed.to_vec(), expect);
+    }
+
+    #[test]
+    fn accumulate_prices() {
+        let mut price_oracle = PriceOracle::new(100, 1_000);
+        let start = price_oracle.observe(1_000, 2_000, 100);
+
+        // 2 B per A for 10 seconds, then 8 B per A for 30 seconds
+        price_oracle.update(1_000, 2_000, 110, 1_025);
+        assert_eq!(price_oracle.price_a_cumulative, 20 * ONE);
+        assert_eq!(price_oracle.price_b_cumulative, 5 * ONE);
+        assert_eq!(price_oracle.last_update_timestamp, 110);
+        assert_eq!(price_oracle.last_update_slot, 1_025);
+        let end = price_oracle.observe(500, 4_000, 140);
+
+        let twap = time_weighted_average_price(&start, &end).unwrap();
+        assert_eq!(twap.price_a, (20 + 240) * ONE / 40);
+        assert_eq!(twap.price_b, (5 * ONE + 30 * ONE / 8) / 40);
+        assert_eq!(time_weighted_average_price(&end, &end), None);
+        assert_eq!(time_weighted_average_price(&end, &start), None);
+    }
+
+    #[test]
+    fn skip_empty_pool_and_stale_time() {
+        let mut price_oracle = PriceOracle::new(100, 1_000);
+        price_oracle.update(0, 2_000, 110, 1_025);
+        assert_eq!(price_oracle, PriceOracle::new(110, 1_025));
+        price_oracle.update(1_000, 2_000, 105, 1_020);
+        assert_eq!(price_oracle, PriceOracle::new(110, 1_025));
+    }
+
+    #[test]
+    fn accumulators_wrap() {
+        let price_oracle = PriceOracle {
+            price_a_cumulative: u128::MAX,
+            price_b_cumulative: u128::MAX,
+            last_update_timestamp: 0,
+            last_update_slot: 0,
+        };
+        let start = price_oracle.observe(1, 1, 0);
+        let end = price_oracle.observe(1, 1, 10);
+        assert!(end.price_a_cumulative < start.price_a_cumulative);
+        let twap = time_weighted_average_price(&start, &end).unwrap();
+        assert_eq!(twap.price_a, ONE);
+        assert_eq!(twap.price_b, ONE);
+    }
+}
@@ -10,15 +10,17 @@ use {
         },
         error::SwapError,
         instruction::{
-            DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Initialize, Swap,
-            SwapInstruction, WithdrawAllTokenTypes, WithdrawSingleTokenTypeExactAmountOut,
+            DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Initialize,
+            RampAmplification, RouteSwap, Swap, SwapInstruction, WithdrawAllTokenTypes,
+            WithdrawSingleTokenTypeExactAmountOut, ROUTE_HOP_ACCOUNTS,
         },
-        state::{SwapState, SwapV1, SwapVersion},
+        oracle::PriceOracle,
+        state::{SwapState, SwapV1, SwapV2, SwapVersion},
     },
     num_traits::FromPrimitive,
     solana_program::{
         account_info::{next_account_info, AccountInfo},
-        clock::Clock,
+        clock::{Clock, UnixTimestamp},
         decode_error::DecodeError,
         entrypoint::ProgramResult,
         instruction::Instruction,
@@ -36,6 +38,7 @@ use {
             mint_close_authority::MintCloseAuthority, transfer_fee::TransferFeeConfig,
             BaseStateWithExtensions, StateWithExtensions,
         },
+        onchain::invoke_transfer_checked,
         state::{Account, Mint},
     },
     std::{convert::TryInto, error::Error},
@@ -158,7 +161,8 @@ impl Processor {
         )
     }
 
-    /// Issue a spl_token `Transfer` instruction.
+    /// Issue a spl_token `TransferChecked` instruction, forwarding any extra
+    /// accounts required by the mint's transfer hook.
     #[allow(clippy::too_many_arguments)]
     pub fn token_transfer<'a>(
         swap: &Pubkey,
@@ -167,6 +171,7 @@ impl Processor {
         mint: AccountInfo<'a>,
         destination: AccountInfo<'a>,
         authority: AccountInfo<'a>,
+        additional_accounts: &[AccountInfo<'a>],
         bump_seed: u8,
         amount: u64,
         decimals: u8,
@@ -174,21 +179,58 @@ impl Processor {
         let swap_bytes = swap.to_bytes();
         let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
         let signers = &[&authority_signature_seeds[..]];
-        let ix = spl_token_2022::instruction::transfer_checked(
+        invoke_transfer_checked(
             token_program.key,
-            source.key,
-            mint.key,
-            destination.key,
-            authority.key,
-            &[],
+            source,
+            mint,
+            destination,
+            authority,
+            additional_accounts,
             amount,
             decimals,
-        )?;
-        invoke_signed_wrapper::<TokenError>(
-            &ix,
-            &[source, mint, destination, authority, token_program],
             signers,
         )
+        .map_err(|err| {
+            err.print::<TokenError>();
+            err
+        })
+    }
+
+    /// Calculates the fee withheld by the mint on a transfer of `amount`
+    pub fn calculate_transfer_fee(
+        mint_info: &AccountInfo,
+        token_program_id: &Pubkey,
+        amount: u64,
+    ) -> Result<u64, ProgramError> {
+        let mint_data = mint_info.data.borrow();
+        let mint =
+            Self::unpack_mint_with_extensions(&mint_data, mint_info.owner, token_program_id)?;
+        if let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() {
+            Ok(transfer_fee_config
+                .calculate_epoch_fee(Clock::get()?.epoch, amount)
+                .ok_or(SwapError::FeeCalculationFailure)?)
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Calculates the fee to add to a transfer so that `post_fee_amount`
+    /// arrives at the destination after the mint withholds its fee
+    pub fn calculate_inverse_transfer_fee(
+        mint_info: &AccountInfo,
+        token_program_id: &Pubkey,
+        post_fee_amount: u64,
+    ) -> Result<u64, ProgramError> {
+        let mint_data = mint_info.data.borrow();
+        let mint =
+            Self::unpack_mint_with_extensions(&mint_data, mint_info.owner, token_program_id)?;
+        if let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() {
+            Ok(transfer_fee_config
+                .calculate_inverse_epoch_fee(Clock::get()?.epoch, post_fee_amount)
+                .ok_or(SwapError::FeeCalculationFailure)?)
+        } else {
+            Ok(0)
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -361,19 +403,39 @@ impl Processor {
             to_u64(initial_amount)?,
         )?;
 
-        let obj = SwapVersion::SwapV1(SwapV1 {
-            is_initialized: true,
-            bump_seed,
-            token_program_id,
-            token_a: *token_a_info.key,
-            token_b: *token_b_info.key,
-            pool_mint: *pool_mint_info.key,
-            token_a_mint: token_a.mint,
-            token_b_mint: token_b.mint,
-            pool_fee_account: *fee_account_info.key,
-            fees,
-            swap_curve,
-        });
+        // Accounts sized for the first version keep working, without a price
+        // oracle
+        let clock = Clock::get()?;
+        let obj = if swap_info.data_len() >= SwapVersion::LATEST_LEN {
+            SwapVersion::SwapV2(SwapV2 {
+                is_initialized: true,
+                bump_seed,
+                token_program_id,
+                token_a: *token_a_info.key,
+                token_b: *token_b_info.key,
+                pool_mint: *pool_mint_info.key,
+                token_a_mint: token_a.mint,
+                token_b_mint: token_b.mint,
+                pool_fee_account: *fee_account_info.key,
+                fees,
+                swap_curve,
+                price_oracle: PriceOracle::new(clock.unix_timestamp, clock.slot),
+            })
+        } else {
+            SwapVersion::SwapV1(SwapV1 {
+                is_initialized: true,
+                bump_seed,
+                token_program_id,
+                token_a: *token_a_info.key,
+                token_b: *token_b_info.key,
+                pool_mint: *pool_mint_info.key,
+                token_a_mint: token_a.mint,
+                token_b_mint: token_b.mint,
+                pool_fee_account: *fee_account_info.key,
+                fees,
+                swap_curve,
+            })
+        };
         SwapVersion::pack(obj, &mut swap_info.data.borrow_mut())?;
         Ok(())
     }
@@ -400,6 +462,11 @@ impl Processor {
         let source_token_program_info = next_account_info(account_info_iter)?;
         let destination_token_program_info = next_account_info(account_info_iter)?;
         let pool_token_program_info = next_account_info(account_info_iter)?;
+        // Allow error to fall through, the program id stands for no host
+        let host_fee_account_info = next_account_info(account_info_iter)
+            .ok()
+            .filter(|host_fee_account_info| host_fee_account_info.key != program_id);
+        let transfer_hook_accounts = account_info_iter.as_slice();
 
         if swap_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
@@ -445,26 +512,25 @@ impl Processor {
         let dest_account =
             Self::unpack_token_account(swap_destination_info, token_swap.token_program_id())?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+        let (token_a_amount, token_b_amount) =
+            if *swap_source_info.key == *token_swap.token_a_account() {
+                (source_account.amount, dest_account.amount)
+            } else {
+                (dest_account.amount, source_account.amount)
+            };
+        Self::update_price_oracle(
+            swap_info,
+            token_swap.as_ref(),
+            token_a_amount,
+            token_b_amount,
+        )?;
 
         // Take transfer fees into account for actual amount transferred in
-        let actual_amount_in = {
-            let source_mint_data = source_token_mint_info.data.borrow();
-            let source_mint = Self::unpack_mint_with_extensions(
-                &source_mint_data,
-                source_token_mint_info.owner,
-                token_swap.token_program_id(),
-            )?;
-
-            if let Ok(transfer_fee_config) = source_mint.get_extension::<TransferFeeConfig>() {
-                amount_in.saturating_sub(
-                    transfer_fee_config
-                        .calculate_epoch_fee(Clock::get()?.epoch, amount_in)
-                        .ok_or(SwapError::FeeCalculationFailure)?,
-                )
-            } else {
-                amount_in
-            }
-        };
+        let actual_amount_in = amount_in.saturating_sub(Self::calculate_transfer_fee(
+            source_token_mint_info,
+            token_swap.token_program_id(),
+            amount_in,
+        )?);
 
         // Calculate the trade amounts
         let trade_direction = if *swap_source_info.key == *token_swap.token_a_account() {
@@ -472,8 +538,10 @@ impl Processor {
         } else {
             TradeDirection::BtoA
         };
-        let result = token_swap
+        let swap_curve = token_swap
             .swap_curve()
+            .at_timestamp(Clock::get()?.unix_timestamp);
+        let result = swap_curve
             .swap(
                 to_u128(actual_amount_in)?,
                 to_u128(source_account.amount)?,
@@ -484,52 +552,25 @@ impl Processor {
             .ok_or(SwapError::ZeroTradingTokens)?;
 
         // Re-calculate the source amount swapped based on what the curve says
-        let (source_transfer_amount, source_mint_decimals) = {
-            let source_amount_swapped = to_u64(result.source_amount_swapped)?;
-
-            let source_mint_data = source_token_mint_info.data.borrow();
-            let source_mint = Self::unpack_mint_with_extensions(
-                &source_mint_data,
-                source_token_mint_info.owner,
+        let source_amount_swapped = to_u64(result.source_amount_swapped)?;
+        let source_transfer_amount =
+            source_amount_swapped.saturating_add(Self::calculate_inverse_transfer_fee(
+                source_token_mint_info,
                 token_swap.token_program_id(),
-            )?;
-            let amount =
-                if let Ok(transfer_fee_config) = source_mint.get_extension::<TransferFeeConfig>() {
-                    source_amount_swapped.saturating_add(
-                        transfer_fee_config
-                            .calculate_inverse_epoch_fee(Clock::get()?.epoch, source_amount_swapped)
-                            .ok_or(SwapError::FeeCalculationFailure)?,
-                    )
-                } else {
-                    source_amount_swapped
-                };
-            (amount, source_mint.base.decimals)
-        };
-
-        let (destination_transfer_amount, destination_mint_decimals) = {
-            let destination_mint_data = destination_token_mint_info.data.borrow();
-            let destination_mint = Self::unpack_mint_with_extensions(
-                &destination_mint_data,
-                source_token_mint_info.owner,
+                source_amount_swapped,
+            )?);
+
+        // Slippage is checked on the amount received after transfer fees
+        let destination_transfer_amount = to_u64(result.destination_amount_swapped)?;
+        let amount_received =
+            destination_transfer_amount.saturating_sub(Self::calculate_transfer_fee(
+                destination_token_mint_info,
                 token_swap.token_program_id(),
-            )?;
-            let amount_out = to_u64(result.destination_amount_swapped)?;
-            let amount_received = if let Ok(transfer_fee_config) =
-                destination_mint.get_extension::<TransferFeeConfig>()
-            {
-                amount_out.saturating_sub(
-                    transfer_fee_config
-                        .calculate_epoch_fee(Clock::get()?.epoch, amount_out)
-                        .ok_or(SwapError::FeeCalculationFailure)?,
-                )
-            } else {
-                amount_out
-            };
-            if amount_received < minimum_amount_out {
-                return Err(SwapError::ExceededSlippage.into());
-            }
-            (amount_out, destination_mint.base.decimals)
-        };
+                destination_transfer_amount,
+            )?);
+        if amount_received < minimum_amount_out {
+            return Err(SwapError::ExceededSlippage.into());
+        }
 
         let (swap_token_a_amount, swap_token_b_amount) = match trade_direction {
             TradeDirection::AtoB => (
@@ -549,14 +590,14 @@ impl Processor {
             source_token_mint_info.clone(),
             swap_source_info.clone(),
             user_transfer_authority_info.clone(),
+            transfer_hook_accounts,
             token_swap.bump_seed(),
             source_transfer_amount,
-            source_mint_decimals,
+            Self::unpack_mint(source_token_mint_info, token_swap.token_program_id())?.decimals,
         )?;
 
         if result.owner_fee > 0 {
-            let mut pool_token_amount = token_swap
-                .swap_curve()
+            let mut pool_token_amount = swap_curve
                 .calculator
                 .withdraw_single_token_type_exact_out(
                     result.owner_fee,
@@ -567,8 +608,7 @@ impl Processor {
                     RoundDirection::Floor,
                 )
                 .ok_or(SwapError::FeeCalculationFailure)?;
-            // Allow error to fall through
-            if let Ok(host_fee_account_info) = next_account_info(account_info_iter) {
+            if let Some(host_fee_account_info) = host_fee_account_info {
                 let host_fee_account = Self::unpack_token_account(
                     host_fee_account_info,
                     token_swap.token_program_id(),
@@ -618,14 +658,292 @@ impl Processor {
             destination_token_mint_info.clone(),
             destination_info.clone(),
             authority_info.clone(),
+            transfer_hook_accounts,
             token_swap.bump_seed(),
             destination_transfer_amount,
-            destination_mint_decimals,
+            Self::unpack_mint(destination_token_mint_info, token_swap.token_program_id())?.decimals,
         )?;
 
         Ok(())
     }
 
+    /// Checks a mint of one pool of a route against the pool's token account,
+    /// and that the token program given for it is the mint's owner.  Later
+    /// pools of the route are paid with a CPI signed by the previous pool's
+    /// authority, so no other program may be invoked.
+    fn check_route_hop_mint(
+        mint_info: &AccountInfo,
+        swap_token_account: &Account,
+        token_program_info: &AccountInfo,
+    ) -> ProgramResult {
+        if *mint_info.key != swap_token_account.mint {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if token_program_info.key != mint_info.owner
+            || check_spl_token_program_account(token_program_info.key).is_err()
+        {
+            return Err(SwapError::IncorrectTokenProgramId.into());
+        }
+        Ok(())
+    }
+
+    /// Processes a [RouteSwap](enum.Instruction.html).
+    pub fn process_route_swap(
+        program_id: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        transfer_hook_account_counts: &[u8],
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let mut remaining_accounts = account_info_iter.as_slice();
+        let hop_count = if transfer_hook_account_counts.is_empty() {
+            remaining_accounts.len() / ROUTE_HOP_ACCOUNTS
+        } else {
+            transfer_hook_account_counts.len()
+        };
+        if hop_count == 0 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        // The first pool is paid by the user, every later pool by the previous
+        // pool of the route, signing as its swap authority
+        let mut hop_source_info = source_info;
+        let mut hop_source_authority_info = user_transfer_authority_info;
+        let mut hop_source_signer: Option<(Pubkey, u8)> = None;
+        let mut hop_amount_in = amount_in;
+        let mut last_hop = None;
+
+        for hop_index in 0..hop_count {
+            let transfer_hook_account_count = transfer_hook_account_counts
+                .get(hop_index)
+                .map_or(0, |count| usize::from(*count));
+            if remaining_accounts.len() < ROUTE_HOP_ACCOUNTS + transfer_hook_account_count {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+            let (hop, rest) = remaining_accounts.split_at(ROUTE_HOP_ACCOUNTS);
+            let (transfer_hook_accounts, rest) = rest.split_at(transfer_hook_account_count);
+            remaining_accounts = rest;
+
+            let hop_info_iter = &mut hop.iter();
+            let swap_info = next_account_info(hop_info_iter)?;
+            let authority_info = next_account_info(hop_info_iter)?;
+            let swap_source_info = next_account_info(hop_info_iter)?;
+            let swap_destination_info = next_account_info(hop_info_iter)?;
+            let pool_mint_info = next_account_info(hop_info_iter)?;
+            let pool_fee_account_info = next_account_info(hop_info_iter)?;
+            let source_token_mint_info = next_account_info(hop_info_iter)?;
+            let destination_token_mint_info = next_account_info(hop_info_iter)?;
+            let source_token_program_info = next_account_info(hop_info_iter)?;
+            let destination_token_program_info = next_account_info(hop_info_iter)?;
+            let pool_token_program_info = next_account_info(hop_info_iter)?;
+
+            if swap_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+
+            if *authority_info.key
+                != Self::authority_id(program_id, swap_info.key, token_swap.bump_seed())?
+            {
+                return Err(SwapError::InvalidProgramAddress.into());
+            }
+            if !(*swap_source_info.key == *token_swap.token_a_account()
+                || *swap_source_info.key == *token_swap.token_b_account())
+            {
+                return Err(SwapError::IncorrectSwapAccount.into());
+            }
+            if !(*swap_destination_info.key == *token_swap.token_a_account()
+                || *swap_destination_info.key == *token_swap.token_b_account())
+            {
+                return Err(SwapError::IncorrectSwapAccount.into());
+            }
+            if *swap_source_info.key == *swap_destination_info.key {
+                return Err(SwapError::InvalidInput.into());
+            }
+            if swap_source_info.key == hop_source_info.key {
+                return Err(SwapError::InvalidInput.into());
+            }
+            if swap_destination_info.key == destination_info.key {
+                return Err(SwapError::InvalidInput.into());
+            }
+            if *pool_mint_info.key != *token_swap.pool_mint() {
+                return Err(SwapError::IncorrectPoolMint.into());
+            }
+            if *pool_fee_account_info.key != *token_swap.pool_fee_account() {
+                return Err(SwapError::IncorrectFeeAccount.into());
+            }
+            if *pool_token_program_info.key != *token_swap.token_program_id() {
+                return Err(SwapError::IncorrectTokenProgramId.into());
+            }
+
+            let source_account =
+                Self::unpack_token_account(swap_source_info, token_swap.token_program_id())?;
+            let dest_account =
+                Self::unpack_token_account(swap_destination_info, token_swap.token_program_id())?;
+            let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+            Self::check_route_hop_mint(
+                source_token_mint_info,
+                &source_account,
+                source_token_program_info,
+            )?;
+            Self::check_route_hop_mint(
+                destination_token_mint_info,
+                &dest_account,
+                destination_token_program_info,
+            )?;
+            let (token_a_amount, token_b_amount) =
+                if *swap_source_info.key == *token_swap.token_a_account() {
+                    (source_account.amount, dest_account.amount)
+                } else {
+                    (dest_account.amount, source_account.amount)
+                };
+            Self::update_price_oracle(
+                swap_info,
+                token_swap.as_ref(),
+                token_a_amount,
+                token_b_amount,
+            )?;
+
+            let actual_amount_in = hop_amount_in.saturating_sub(Self::calculate_transfer_fee(
+                source_token_mint_info,
+                token_swap.token_program_id(),
+                hop_amount_in,
+            )?);
+
+            let trade_direction = if *swap_source_info.key == *token_swap.token_a_account() {
+                TradeDirection::AtoB
+            } else {
+                TradeDirection::BtoA
+            };
+            let swap_curve = token_swap
+                .swap_curve()
+                .at_timestamp(Clock::get()?.unix_timestamp);
+            let result = swap_curve
+                .swap(
+                    to_u128(actual_amount_in)?,
+                    to_u128(source_account.amount)?,
+                    to_u128(dest_account.amount)?,
+                    trade_direction,
+                    token_swap.fees(),
+                )
+                .ok_or(SwapError::ZeroTradingTokens)?;
+
+            // Only what the curve takes is paid in, so the previous pool of
+            // the route keeps anything this pool's curve does not consume.
+            // Never pay in more than the hop received, whatever the inverse
+            // transfer fee rounds to
+            let source_amount_swapped = to_u64(result.source_amount_swapped)?;
+            let source_transfer_amount = source_amount_swapped
+                .saturating_add(Self::calculate_inverse_transfer_fee(
+                    source_token_mint_info,
+                    token_swap.token_program_id(),
+                    source_amount_swapped,
+                )?)
+                .min(hop_amount_in);
+            let (signer_key, signer_bump_seed) =
+                hop_source_signer.unwrap_or((*swap_info.key, token_swap.bump_seed()));
+            Self::token_transfer(
+                &signer_key,
+                source_token_program_info.clone(),
+                hop_source_info.clone(),
+                source_token_mint_info.clone(),
+                swap_source_info.clone(),
+                hop_source_authority_info.clone(),
+                transfer_hook_accounts,
+                signer_bump_seed,
+                source_transfer_amount,
+                Self::unpack_mint(source_token_mint_info, token_swap.token_program_id())?.decimals,
+            )?;
+
+            if result.owner_fee > 0
+                && token_swap
+                    .check_pool_fee_info(pool_fee_account_info)
+                    .is_ok()
+            {
+                let (swap_token_a_amount, swap_token_b_amount) = match trade_direction {
+                    TradeDirection::AtoB => (
+                        result.new_swap_source_amount,
+                        result.new_swap_destination_amount,
+                    ),
+                    TradeDirection::BtoA => (
+                        result.new_swap_destination_amount,
+                        result.new_swap_source_amount,
+                    ),
+                };
+                let pool_token_amount = swap_curve
+                    .calculator
+                    .withdraw_single_token_type_exact_out(
+                        result.owner_fee,
+                        swap_token_a_amount,
+                        swap_token_b_amount,
+                        to_u128(pool_mint.supply)?,
+                        trade_direction,
+                        RoundDirection::Floor,
+                    )
+                    .ok_or(SwapError::FeeCalculationFailure)?;
+                Self::token_mint_to(
+                    swap_info.key,
+                    pool_token_program_info.clone(),
+                    pool_mint_info.clone(),
+                    pool_fee_account_info.clone(),
+                    authority_info.clone(),
+                    token_swap.bump_seed(),
+                    to_u64(pool_token_amount)?,
+                )?;
+            }
+
+            hop_source_info = swap_destination_info;
+            hop_source_authority_info = authority_info;
+            hop_source_signer = Some((*swap_info.key, token_swap.bump_seed()));
+            hop_amount_in = to_u64(result.destination_amount_swapped)?;
+            last_hop = Some((
+                destination_token_mint_info,
+                destination_token_program_info,
+                *token_swap.token_program_id(),
+                transfer_hook_accounts,
+            ));
+        }
+        if !remaining_accounts.is_empty() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let (
+            destination_token_mint_info,
+            destination_token_program_info,
+            token_program_id,
+            transfer_hook_accounts,
+        ) = last_hop.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let (signer_key, signer_bump_seed) =
+            hop_source_signer.ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        // Slippage is checked on the amount received after transfer fees
+        let amount_received = hop_amount_in.saturating_sub(Self::calculate_transfer_fee(
+            destination_token_mint_info,
+            &token_program_id,
+            hop_amount_in,
+        )?);
+        if amount_received < minimum_amount_out {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+
+        Self::token_transfer(
+            &signer_key,
+            destination_token_program_info.clone(),
+            hop_source_info.clone(),
+            destination_token_mint_info.clone(),
+            destination_info.clone(),
+            hop_source_authority_info.clone(),
+            transfer_hook_accounts,
+            signer_bump_seed,
+            hop_amount_in,
+            Self::unpack_mint(destination_token_mint_info, &token_program_id)?.decimals,
+        )
+    }
+
     /// Processes an [DepositAllTokenTypes](enum.Instruction.html).
     pub fn process_deposit_all_token_types(
         program_id: &Pubkey,
@@ -649,6 +967,7 @@ impl Processor {
         let token_a_program_info = next_account_info(account_info_iter)?;
         let token_b_program_info = next_account_info(account_info_iter)?;
         let pool_token_program_info = next_account_info(account_info_iter)?;
+        let transfer_hook_accounts = account_info_iter.as_slice();
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
         let calculator = &token_swap.swap_curve().calculator;
@@ -672,6 +991,12 @@ impl Processor {
         let token_a = Self::unpack_token_account(token_a_info, token_swap.token_program_id())?;
         let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+        Self::update_price_oracle(
+            swap_info,
+            token_swap.as_ref(),
+            token_a.amount,
+            token_b.amount,
+        )?;
         let current_pool_mint_supply = to_u128(pool_mint.supply)?;
         let (pool_token_amount, pool_mint_supply) = if current_pool_mint_supply > 0 {
             (to_u128(pool_token_amount)?, current_pool_mint_supply)
@@ -688,15 +1013,29 @@ impl Processor {
                 RoundDirection::Ceiling,
             )
             .ok_or(SwapError::ZeroTradingTokens)?;
+        // The pool must receive the curve amounts, so the user also pays any
+        // transfer fees on top
         let token_a_amount = to_u64(results.token_a_amount)?;
-        if token_a_amount > maximum_token_a_amount {
+        let token_a_transfer_amount =
+            token_a_amount.saturating_add(Self::calculate_inverse_transfer_fee(
+                token_a_mint_info,
+                token_swap.token_program_id(),
+                token_a_amount,
+            )?);
+        if token_a_transfer_amount > maximum_token_a_amount {
             return Err(SwapError::ExceededSlippage.into());
         }
         if token_a_amount == 0 {
             return Err(SwapError::ZeroTradingTokens.into());
         }
         let token_b_amount = to_u64(results.token_b_amount)?;
-        if token_b_amount > maximum_token_b_amount {
+        let token_b_transfer_amount =
+            token_b_amount.saturating_add(Self::calculate_inverse_transfer_fee(
+                token_b_mint_info,
+                token_swap.token_program_id(),
+                token_b_amount,
+            )?);
+        if token_b_transfer_amount > maximum_token_b_amount {
             return Err(SwapError::ExceededSlippage.into());
         }
         if token_b_amount == 0 {
@@ -712,8 +1051,9 @@ impl Processor {
             token_a_mint_info.clone(),
             token_a_info.clone(),
             user_transfer_authority_info.clone(),
+            transfer_hook_accounts,
             token_swap.bump_seed(),
-            token_a_amount,
+            token_a_transfer_amount,
             Self::unpack_mint(token_a_mint_info, token_swap.token_program_id())?.decimals,
         )?;
         Self::token_transfer(
@@ -723,8 +1063,9 @@ impl Processor {
             token_b_mint_info.clone(),
             token_b_info.clone(),
             user_transfer_authority_info.clone(),
+            transfer_hook_accounts,
             token_swap.bump_seed(),
-            token_b_amount,
+            token_b_transfer_amount,
             Self::unpack_mint(token_b_mint_info, token_swap.token_program_id())?.decimals,
         )?;
         Self::token_mint_to(
@@ -764,6 +1105,7 @@ impl Processor {
         let pool_token_program_info = next_account_info(account_info_iter)?;
         let token_a_program_info = next_account_info(account_info_iter)?;
         let token_b_program_info = next_account_info(account_info_iter)?;
+        let transfer_hook_accounts = account_info_iter.as_slice();
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
         Self::check_accounts(
@@ -783,6 +1125,12 @@ impl Processor {
         let token_a = Self::unpack_token_account(token_a_info, token_swap.token_program_id())?;
         let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+        Self::update_price_oracle(
+            swap_info,
+            token_swap.as_ref(),
+            token_a.amount,
+            token_b.amount,
+        )?;
 
         let calculator = &token_swap.swap_curve().calculator;
 
@@ -815,7 +1163,12 @@ impl Processor {
             .ok_or(SwapError::ZeroTradingTokens)?;
         let token_a_amount = to_u64(results.token_a_amount)?;
         let token_a_amount = std::cmp::min(token_a.amount, token_a_amount);
-        if token_a_amount < minimum_token_a_amount {
+        let token_a_received = token_a_amount.saturating_sub(Self::calculate_transfer_fee(
+            token_a_mint_info,
+            token_swap.token_program_id(),
+            token_a_amount,
+        )?);
+        if token_a_received < minimum_token_a_amount {
             return Err(SwapError::ExceededSlippage.into());
         }
         if token_a_amount == 0 && token_a.amount != 0 {
@@ -823,7 +1176,12 @@ impl Processor {
         }
         let token_b_amount = to_u64(results.token_b_amount)?;
         let token_b_amount = std::cmp::min(token_b.amount, token_b_amount);
-        if token_b_amount < minimum_token_b_amount {
+        let token_b_received = token_b_amount.saturating_sub(Self::calculate_transfer_fee(
+            token_b_mint_info,
+            token_swap.token_program_id(),
+            token_b_amount,
+        )?);
+        if token_b_received < minimum_token_b_amount {
             return Err(SwapError::ExceededSlippage.into());
         }
         if token_b_amount == 0 && token_b.amount != 0 {
@@ -838,6 +1196,7 @@ impl Processor {
                 pool_mint_info.clone(),
                 pool_fee_account_info.clone(),
                 user_transfer_authority_info.clone(),
+                transfer_hook_accounts,
                 token_swap.bump_seed(),
                 to_u64(withdraw_fee)?,
                 pool_mint.decimals,
@@ -861,6 +1220,7 @@ impl Processor {
                 token_a_mint_info.clone(),
                 dest_token_a_info.clone(),
                 authority_info.clone(),
+                transfer_hook_accounts,
                 token_swap.bump_seed(),
                 token_a_amount,
                 Self::unpack_mint(token_a_mint_info, token_swap.token_program_id())?.decimals,
@@ -874,6 +1234,7 @@ impl Processor {
                 token_b_mint_info.clone(),
                 dest_token_b_info.clone(),
                 authority_info.clone(),
+                transfer_hook_accounts,
                 token_swap.bump_seed(),
                 token_b_amount,
                 Self::unpack_mint(token_b_mint_info, token_swap.token_program_id())?.decimals,
@@ -901,6 +1262,7 @@ impl Processor {
         let source_token_mint_info = next_account_info(account_info_iter)?;
         let source_token_program_info = next_account_info(account_info_iter)?;
         let pool_token_program_info = next_account_info(account_info_iter)?;
+        let transfer_hook_accounts = account_info_iter.as_slice();
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
         let calculator = &token_swap.swap_curve().calculator;
@@ -942,12 +1304,25 @@ impl Processor {
         )?;
 
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+        Self::update_price_oracle(
+            swap_info,
+            token_swap.as_ref(),
+            swap_token_a.amount,
+            swap_token_b.amount,
+        )?;
         let pool_mint_supply = to_u128(pool_mint.supply)?;
+        // The curve only sees what the pool receives after transfer fees
+        let actual_amount_in = source_token_amount.saturating_sub(Self::calculate_transfer_fee(
+            source_token_mint_info,
+            token_swap.token_program_id(),
+            source_token_amount,
+        )?);
         let pool_token_amount = if pool_mint_supply > 0 {
             token_swap
                 .swap_curve()
+                .at_timestamp(Clock::get()?.unix_timestamp)
                 .deposit_single_token_type(
-                    to_u128(source_token_amount)?,
+                    to_u128(actual_amount_in)?,
                     to_u128(swap_token_a.amount)?,
                     to_u128(swap_token_b.amount)?,
                     pool_mint_supply,
@@ -976,6 +1351,7 @@ impl Processor {
                     source_token_mint_info.clone(),
                     swap_token_a_info.clone(),
                     user_transfer_authority_info.clone(),
+                    transfer_hook_accounts,
                     token_swap.bump_seed(),
                     source_token_amount,
                     Self::unpack_mint(source_token_mint_info, token_swap.token_program_id())?
@@ -990,6 +1366,7 @@ impl Processor {
                     source_token_mint_info.clone(),
                     swap_token_b_info.clone(),
                     user_transfer_authority_info.clone(),
+                    transfer_hook_accounts,
                     token_swap.bump_seed(),
                     source_token_amount,
                     Self::unpack_mint(source_token_mint_info, token_swap.token_program_id())?
@@ -1031,6 +1408,7 @@ impl Processor {
         let destination_token_mint_info = next_account_info(account_info_iter)?;
         let pool_token_program_info = next_account_info(account_info_iter)?;
         let destination_token_program_info = next_account_info(account_info_iter)?;
+        let transfer_hook_accounts = account_info_iter.as_slice();
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
         let destination_account =
@@ -1067,14 +1445,29 @@ impl Processor {
         )?;
 
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+        Self::update_price_oracle(
+            swap_info,
+            token_swap.as_ref(),
+            swap_token_a.amount,
+            swap_token_b.amount,
+        )?;
         let pool_mint_supply = to_u128(pool_mint.supply)?;
         let swap_token_a_amount = to_u128(swap_token_a.amount)?;
         let swap_token_b_amount = to_u128(swap_token_b.amount)?;
+        // The pool sends enough to cover transfer fees, so the user receives
+        // exactly the requested amount
+        let destination_transfer_amount =
+            destination_token_amount.saturating_add(Self::calculate_inverse_transfer_fee(
+                destination_token_mint_info,
+                token_swap.token_program_id(),
+                destination_token_amount,
+            )?);
 
         let burn_pool_token_amount = token_swap
             .swap_curve()
+            .at_timestamp(Clock::get()?.unix_timestamp)
             .withdraw_single_token_type_exact_out(
-                to_u128(destination_token_amount)?,
+                to_u128(destination_transfer_amount)?,
                 swap_token_a_amount,
                 swap_token_b_amount,
                 pool_mint_supply,
@@ -1116,6 +1509,7 @@ impl Processor {
                 pool_mint_info.clone(),
                 pool_fee_account_info.clone(),
                 user_transfer_authority_info.clone(),
+                transfer_hook_accounts,
                 token_swap.bump_seed(),
                 to_u64(withdraw_fee)?,
                 pool_mint.decimals,
@@ -1140,8 +1534,9 @@ impl Processor {
                     destination_token_mint_info.clone(),
                     destination_info.clone(),
                     authority_info.clone(),
+                    transfer_hook_accounts,
                     token_swap.bump_seed(),
-                    destination_token_amount,
+                    destination_transfer_amount,
                     Self::unpack_mint(destination_token_mint_info, token_swap.token_program_id())?
                         .decimals,
                 )?;
@@ -1154,8 +1549,9 @@ impl Processor {
                     destination_token_mint_info.clone(),
                     destination_info.clone(),
                     authority_info.clone(),
+                    transfer_hook_accounts,
                     token_swap.bump_seed(),
-                    destination_token_amount,
+                    destination_transfer_amount,
                     Self::unpack_mint(destination_token_mint_info, token_swap.token_program_id())?
                         .decimals,
                 )?;
@@ -1165,53 +1561,234 @@ impl Processor {
         Ok(())
     }
 
-    /// Processes an [Instruction](enum.Instruction.html).
-    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
-        Self::process_with_constraints(program_id, accounts, input, &SWAP_CONSTRAINTS)
+    /// Checks that the swap admin signed the instruction.  The admin is the
+    /// owner key of the swap constraints, so admin instructions are rejected
+    /// if the program runs without constraints.
+    fn check_swap_admin(
+        token_swap: &dyn SwapState,
+        admin_info: &AccountInfo,
+        pool_fee_account_info: &AccountInfo,
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        if *pool_fee_account_info.key != *token_swap.pool_fee_account() {
+            return Err(SwapError::IncorrectFeeAccount.into());
+        }
+        let admin_key = if let Some(swap_constraints) = swap_constraints {
+            swap_constraints
+                .owner_key
+                .parse::<Pubkey>()
+                .map_err(|_| SwapError::InvalidOwner)?
+        } else {
+            msg!("Swap admin instructions require swap constraints");
+            return Err(SwapError::InvalidOwner.into());
+        };
+        if *admin_info.key != admin_key {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if !admin_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(())
     }
 
-    /// Processes an instruction given extra constraint
-    pub fn process_with_constraints(
+    /// Writes the swap back to its account, keeping its version
+    fn pack_swap_state(
+        swap_info: &AccountInfo,
+        token_swap: &dyn SwapState,
+        swap_curve: SwapCurve,
+        price_oracle: Option<PriceOracle>,
+    ) -> ProgramResult {
+        let obj = if let Some(price_oracle) = price_oracle {
+            SwapVersion::SwapV2(SwapV2 {
+                is_initialized: token_swap.is_initialized(),
+                bump_seed: token_swap.bump_seed(),
+                token_program_id: *token_swap.token_program_id(),
+                token_a: *token_swap.token_a_account(),
+                token_b: *token_swap.token_b_account(),
+                pool_mint: *token_swap.pool_mint(),
+                token_a_mint: *token_swap.token_a_mint(),
+                token_b_mint: *token_swap.token_b_mint(),
+                pool_fee_account: *token_swap.pool_fee_account(),
+                fees: token_swap.fees().clone(),
+                swap_curve,
+                price_oracle,
+            })
+        } else {
+            SwapVersion::SwapV1(SwapV1 {
+                is_initialized: token_swap.is_initialized(),
+                bump_seed: token_swap.bump_seed(),
+                token_program_id: *token_swap.token_program_id(),
+                token_a: *token_swap.token_a_account(),
+                token_b: *token_swap.token_b_account(),
+                pool_mint: *token_swap.pool_mint(),
+                token_a_mint: *token_swap.token_a_mint(),
+                token_b_mint: *token_swap.token_b_mint(),
+                pool_fee_account: *token_swap.pool_fee_account(),
+                fees: token_swap.fees().clone(),
+                swap_curve,
+            })
+        };
+        SwapVersion::pack(obj, &mut swap_info.data.borrow_mut())
+    }
+
+    /// Accumulates the pool prices up to now, must be called before the
+    /// instruction changes the pool amounts
+    fn update_price_oracle(
+        swap_info: &AccountInfo,
+        token_swap: &dyn SwapState,
+        token_a_amount: u64,
+        token_b_amount: u64,
+    ) -> ProgramResult {
+        if let Some(price_oracle) = token_swap.price_oracle() {
+            let mut price_oracle = *price_oracle;
+            let clock = Clock::get()?;
+            price_oracle.update(
+                token_a_amount,
+                token_b_amount,
+                clock.unix_timestamp,
+                clock.slot,
+            );
+            Self::pack_swap_state(
+                swap_info,
+                token_swap,
+                SwapCurve {
+                    curve_type: token_swap.swap_curve().curve_type,
+                    calculator: token_swap.swap_curve().calculator.clone(),
+                },
+                Some(price_oracle),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Processes a [RampAmplification](enum.Instruction.html).
+    pub fn process_ramp_amplification(
         program_id: &Pubkey,
+        target_amp: u64,
+        stop_ramp_ts: UnixTimestamp,
         accounts: &[AccountInfo],
-        input: &[u8],
         swap_constraints: &Option<SwapConstraints>,
     ) -> ProgramResult {
-        let instruction = SwapInstruction::unpack(input)?;
-        match instruction {
-            SwapInstruction::Initialize(Initialize { fees, swap_curve }) => {
-                msg!("Instruction: Init");
-                Self::process_initialize(program_id, fees, swap_curve, accounts, swap_constraints)
-            }
-            SwapInstruction::Swap(Swap {
-                amount_in,
-                minimum_amount_out,
-            }) => {
-                msg!("Instruction: Swap");
-                Self::process_swap(program_id, amount_in, minimum_amount_out, accounts)
-            }
-            SwapInstruction::DepositAllTokenTypes(DepositAllTokenTypes {
-                pool_token_amount,
-                maximum_token_a_amount,
-                maximum_token_b_amount,
-            }) => {
-                msg!("Instruction: DepositAllTokenTypes");
-                Self::process_deposit_all_token_types(
-                    program_id,
-                    pool_token_amount,
-                    maximum_token_a_amount,
-                    maximum_token_b_amount,
-                    accounts,
-                )
-            }
-            SwapInstruction::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
-                pool_token_amount,
-                minimum_token_a_amount,
-                minimum_token_b_amount,
-            }) => {
-                msg!("Instruction: WithdrawAllTokenTypes");
-                Self::process_withdraw_all_token_types(
-                    program_id,
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        Self::check_swap_admin(
+            token_swap.as_ref(),
+            admin_info,
+            pool_fee_account_info,
+            swap_constraints,
+        )?;
+
+        let swap_curve = token_swap.swap_curve();
+        let calculator = swap_curve.calculator.ramp_amplification(
+            target_amp,
+            Clock::get()?.unix_timestamp,
+            stop_ramp_ts,
+        )?;
+        let swap_curve = SwapCurve {
+            curve_type: swap_curve.curve_type,
+            calculator,
+        };
+        Self::pack_swap_state(
+            swap_info,
+            token_swap.as_ref(),
+            swap_curve,
+            token_swap.price_oracle().copied(),
+        )
+    }
+
+    /// Processes a [StopRamp](enum.Instruction.html).
+    pub fn process_stop_ramp(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        Self::check_swap_admin(
+            token_swap.as_ref(),
+            admin_info,
+            pool_fee_account_info,
+            swap_constraints,
+        )?;
+
+        let swap_curve = token_swap.swap_curve();
+        let calculator = swap_curve
+            .calculator
+            .at_timestamp(Clock::get()?.unix_timestamp)
+            .ok_or(SwapError::UnsupportedCurveOperation)?;
+        let swap_curve = SwapCurve {
+            curve_type: swap_curve.curve_type,
+            calculator,
+        };
+        Self::pack_swap_state(
+            swap_info,
+            token_swap.as_ref(),
+            swap_curve,
+            token_swap.price_oracle().copied(),
+        )
+    }
+
+    /// Processes an [Instruction](enum.Instruction.html).
+    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+        Self::process_with_constraints(program_id, accounts, input, &SWAP_CONSTRAINTS)
+    }
+
+    /// Processes an instruction given extra constraint
+    pub fn process_with_constraints(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        input: &[u8],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let instruction = SwapInstruction::unpack(input)?;
+        match instruction {
+            SwapInstruction::Initialize(Initialize { fees, swap_curve }) => {
+                msg!("Instruction: Init");
+                Self::process_initialize(program_id, fees, swap_curve, accounts, swap_constraints)
+            }
+            SwapInstruction::Swap(Swap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                msg!("Instruction: Swap");
+                Self::process_swap(program_id, amount_in, minimum_amount_out, accounts)
+            }
+            SwapInstruction::DepositAllTokenTypes(DepositAllTokenTypes {
+                pool_token_amount,
+                maximum_token_a_amount,
+                maximum_token_b_amount,
+            }) => {
+                msg!("Instruction: DepositAllTokenTypes");
+                Self::process_deposit_all_token_types(
+                    program_id,
+                    pool_token_amount,
+                    maximum_token_a_amount,
+                    maximum_token_b_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+            }) => {
+                msg!("Instruction: WithdrawAllTokenTypes");
+                Self::process_withdraw_all_token_types(
+                    program_id,
                     pool_token_amount,
                     minimum_token_a_amount,
                     minimum_token_b_amount,
@@ -1246,6 +1823,37 @@ impl Processor {
                     accounts,
                 )
             }
+            SwapInstruction::RampAmplification(RampAmplification {
+                target_amp,
+                stop_ramp_ts,
+            }) => {
+                msg!("Instruction: RampAmplification");
+                Self::process_ramp_amplification(
+                    program_id,
+                    target_amp,
+                    stop_ramp_ts,
+                    accounts,
+                    swap_constraints,
+                )
+            }
+            SwapInstruction::StopRamp => {
+                msg!("Instruction: StopRamp");
+                Self::process_stop_ramp(program_id, accounts, swap_constraints)
+            }
+            SwapInstruction::RouteSwap(RouteSwap {
+                amount_in,
+                minimum_amount_out,
+                transfer_hook_account_counts,
+            }) => {
+                msg!("Instruction: RouteSwap");
+                Self::process_route_swap(
+                    program_id,
+                    amount_in,
+                    minimum_amount_out,
+                    &transfer_hook_account_counts,
+                    accounts,
+                )
+            }
         }
     }
 }
@@ -1283,23 +1891,33 @@ mod tests {
                 constant_price::ConstantPriceCurve,
                 constant_product::ConstantProductCurve,
                 offset::OffsetCurve,
+                stable::{StableCurve, MIN_RAMP_DURATION},
             },
             instruction::{
                 deposit_all_token_types, deposit_single_token_type_exact_amount_in, initialize,
-                swap, withdraw_all_token_types, withdraw_single_token_type_exact_amount_out,
+                ramp_amplification, route_swap, stop_ramp, swap, withdraw_all_token_types,
+                withdraw_single_token_type_exact_amount_out, RouteSwapHop,
             },
+            oracle::PRICE_FRACTIONAL_BITS,
+            route::{quote_route, RouteHop},
         },
         solana_program::{
-            clock::Clock, entrypoint::SUCCESS, instruction::Instruction, program_pack::Pack,
-            program_stubs, rent::Rent,
+            clock::{Clock, Slot},
+            entrypoint::SUCCESS,
+            instruction::{AccountMeta, Instruction},
+            program_pack::Pack,
+            program_stubs,
+            rent::Rent,
         },
         solana_sdk::account::{
             create_account_for_test, create_is_signer_account_infos, Account as SolanaAccount,
         },
+        spl_tlv_account_resolution::state::ExtraAccountMetaList,
         spl_token_2022::{
             error::TokenError,
             extension::{
                 transfer_fee::{instruction::initialize_transfer_fee_config, TransferFee},
+                transfer_hook::instruction::initialize as initialize_transfer_hook,
                 ExtensionType,
             },
             instruction::{
@@ -1308,6 +1926,10 @@ mod tests {
                 mint_to, revoke, set_authority, AuthorityType,
             },
         },
+        spl_transfer_hook_interface::{
+            error::TransferHookError, get_extra_account_metas_address,
+            instruction::ExecuteInstruction,
+        },
         std::sync::Arc,
         test_case::test_case,
     };
@@ -1315,6 +1937,9 @@ mod tests {
     // Test program id for the swap program.
     const SWAP_PROGRAM_ID: Pubkey = Pubkey::new_from_array([2u8; 32]);
 
+    // Test program id for the transfer hook program.
+    const TRANSFER_HOOK_PROGRAM_ID: Pubkey = Pubkey::new_from_array([3u8; 32]);
+
     struct TestSyscallStubs {}
     impl program_stubs::SyscallStubs for TestSyscallStubs {
         fn sol_invoke_signed(
@@ -1327,13 +1952,8 @@ mod tests {
 
             let mut new_account_infos = vec![];
 
-            // mimic check for token program in accounts
-            if !account_infos
-                .iter()
-                .any(|x| *x.key == spl_token::id() || *x.key == spl_token_2022::id())
-            {
-                return Err(ProgramError::InvalidAccountData);
-            }
+            // the runtime looks for the invoked program among the accounts of
+            // the calling instruction, not the account infos given to invoke
 
             for meta in instruction.accounts.iter() {
                 for account_info in account_infos.iter() {
@@ -1363,6 +1983,12 @@ mod tests {
                     &new_account_infos,
                     &instruction.data,
                 )
+            } else if instruction.program_id == TRANSFER_HOOK_PROGRAM_ID {
+                spl_transfer_hook_example::processor::process(
+                    &instruction.program_id,
+                    &new_account_infos,
+                    &instruction.data,
+                )
             } else {
                 Err(ProgramError::IncorrectProgramId)
             }
@@ -1370,12 +1996,30 @@ mod tests {
 
         fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
             unsafe {
-                *(var_addr as *mut _ as *mut Clock) = Clock::default();
+                *(var_addr as *mut _ as *mut Clock) = Clock {
+                    slot: SLOT.with(|slot| slot.get()),
+                    unix_timestamp: UNIX_TIMESTAMP.with(|ts| ts.get()),
+                    ..Clock::default()
+                };
             }
             SUCCESS
         }
     }
 
+    thread_local! {
+        // Clock time seen by the program in the current test
+        static UNIX_TIMESTAMP: std::cell::Cell<UnixTimestamp> = std::cell::Cell::new(0);
+        static SLOT: std::cell::Cell<Slot> = std::cell::Cell::new(0);
+    }
+
+    fn set_unix_timestamp(unix_timestamp: UnixTimestamp) {
+        UNIX_TIMESTAMP.with(|ts| ts.set(unix_timestamp));
+    }
+
+    fn set_slot(slot: Slot) {
+        SLOT.with(|s| s.set(slot));
+    }
+
     fn test_syscall_stubs() {
         use std::sync::Once;
         static ONCE: Once = Once::new();
@@ -2161,11 +2805,12 @@ mod tests {
     ) -> (Pubkey, SolanaAccount) {
         let account_key = Pubkey::new_unique();
         let space = if *program_id == spl_token_2022::id() {
-            ExtensionType::try_calculate_account_len::<Account>(&[
-                ExtensionType::ImmutableOwner,
-                ExtensionType::TransferFeeAmount,
-            ])
-            .unwrap()
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+            let mut extension_types = ExtensionType::get_required_init_account_extensions(
+                &mint.get_extension_types().unwrap(),
+            );
+            extension_types.push(ExtensionType::ImmutableOwner);
+            ExtensionType::try_calculate_account_len::<Account>(&extension_types).unwrap()
         } else {
             Account::get_packed_len()
         };
@@ -2275,6 +2920,39 @@ mod tests {
         (mint_key, mint_account)
     }
 
+    fn create_transfer_hook_mint(
+        authority_key: &Pubkey,
+        transfer_hook_program_id: &Pubkey,
+    ) -> (Pubkey, SolanaAccount) {
+        let program_id = spl_token_2022::id();
+        let mint_key = Pubkey::new_unique();
+        let space =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook])
+                .unwrap();
+        let minimum_balance = Rent::default().minimum_balance(space);
+        let mut mint_account = SolanaAccount::new(minimum_balance, space, &program_id);
+        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
+
+        do_process_instruction(
+            initialize_transfer_hook(
+                &program_id,
+                &mint_key,
+                Some(*authority_key),
+                Some(*transfer_hook_program_id),
+            )
+            .unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, authority_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar_account],
+        )
+        .unwrap();
+
+        (mint_key, mint_account)
+    }
+
     #[test_case(spl_token::id(); "token")]
     #[test_case(spl_token_2022::id(); "token-2022")]
     fn test_token_program_id_error(token_program_id: Pubkey) {
@@ -8377,4 +9055,1708 @@ mod tests {
             &token_b_program_id,
         );
     }
+
+    #[test]
+    fn test_transfer_fees_on_deposits_and_withdrawals() {
+        let user_key = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let transfer_fees = SwapTransferFees {
+            pool_token: TransferFee::default(),
+            token_a: TransferFee {
+                epoch: 0.into(),
+                transfer_fee_basis_points: 100.into(),
+                maximum_fee: 1_000_000_000.into(),
+            },
+            token_b: TransferFee::default(),
+        };
+        let token_a_fee = transfer_fees.token_a;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            transfer_fees,
+            swap_curve,
+            10_000_000,
+            10_000_000,
+            &spl_token_2022::id(),
+            &spl_token_2022::id(),
+            &spl_token_2022::id(),
+        );
+        accounts.initialize_swap().unwrap();
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &depositor_key, 10_000_000, 10_000_000, 0);
+        let amount = |account: &SolanaAccount| {
+            StateWithExtensions::<Account>::unpack(&account.data)
+                .unwrap()
+                .base
+                .amount
+        };
+
+        // deposit all: the pool receives the curve amount, the depositor also
+        // pays the transfer fee
+        let swap_a_before = amount(&accounts.token_a_account);
+        let user_a_before = amount(&token_a_account);
+        accounts
+            .deposit_all_token_types(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                to_u64(INITIAL_SWAP_POOL_AMOUNT / 10).unwrap(),
+                2_000_000,
+                2_000_000,
+            )
+            .unwrap();
+        let received = amount(&accounts.token_a_account) - swap_a_before;
+        assert_eq!(received, 1_000_000);
+        assert_eq!(
+            user_a_before - amount(&token_a_account),
+            received + token_a_fee.calculate_inverse_fee(received).unwrap()
+        );
+
+        // deposit single: the curve only sees the amount after fees
+        let swap_a_before = amount(&accounts.token_a_account);
+        let source_token_amount = 100_000;
+        accounts
+            .deposit_single_token_type_exact_amount_in(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                source_token_amount,
+                0,
+            )
+            .unwrap();
+        assert_eq!(
+            amount(&accounts.token_a_account) - swap_a_before,
+            source_token_amount - token_a_fee.calculate_fee(source_token_amount).unwrap()
+        );
+
+        // withdraw single: the depositor receives exactly the requested amount
+        let user_a_before = amount(&token_a_account);
+        let destination_token_amount = 50_000;
+        accounts
+            .withdraw_single_token_type_exact_amount_out(
+                &depositor_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                destination_token_amount,
+                u64::MAX,
+            )
+            .unwrap();
+        assert_eq!(
+            amount(&token_a_account) - user_a_before,
+            destination_token_amount
+        );
+
+        // withdraw all: slippage is checked on the amount received after fees
+        let swap_a_before = amount(&accounts.token_a_account);
+        let pool_token_amount = amount(&pool_account) / 2;
+        let pool_mint_supply =
+            StateWithExtensions::<Mint>::unpack(&accounts.pool_mint_account.data)
+                .unwrap()
+                .base
+                .supply;
+        let sent = ConstantProductCurve {}
+            .pool_tokens_to_trading_tokens(
+                pool_token_amount.into(),
+                pool_mint_supply.into(),
+                swap_a_before.into(),
+                amount(&accounts.token_b_account).into(),
+                RoundDirection::Floor,
+            )
+            .unwrap()
+            .token_a_amount;
+        let sent = to_u64(sent).unwrap();
+        let expected_received = sent - token_a_fee.calculate_fee(sent).unwrap();
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.withdraw_all_token_types(
+                &depositor_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                pool_token_amount,
+                expected_received + 1,
+                0,
+            )
+        );
+        let user_a_before = amount(&token_a_account);
+        accounts
+            .withdraw_all_token_types(
+                &depositor_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                pool_token_amount,
+                expected_received,
+                0,
+            )
+            .unwrap();
+        assert_eq!(swap_a_before - amount(&accounts.token_a_account), sent);
+        assert_eq!(amount(&token_a_account) - user_a_before, expected_received);
+    }
+
+    #[test]
+    fn test_swap_with_transfer_hook() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let token_a_amount = 10_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            SwapTransferFees::default(),
+            swap_curve,
+            token_a_amount,
+            10_000_000,
+            &spl_token_2022::id(),
+            &spl_token_2022::id(),
+            &spl_token_2022::id(),
+        );
+
+        // token A has a transfer hook
+        let (token_a_mint_key, mut token_a_mint_account) =
+            create_transfer_hook_mint(&user_key, &TRANSFER_HOOK_PROGRAM_ID);
+        let (token_a_key, token_a_account) = mint_token(
+            &spl_token_2022::id(),
+            &token_a_mint_key,
+            &mut token_a_mint_account,
+            &user_key,
+            &accounts.authority_key,
+            token_a_amount,
+        );
+        accounts.token_a_mint_key = token_a_mint_key;
+        accounts.token_a_mint_account = token_a_mint_account;
+        accounts.token_a_key = token_a_key;
+        accounts.token_a_account = token_a_account;
+        accounts.initialize_swap().unwrap();
+
+        let validation_key =
+            get_extra_account_metas_address(&accounts.token_a_mint_key, &TRANSFER_HOOK_PROGRAM_ID);
+        let mut validation_account = SolanaAccount::new(
+            0,
+            ExtraAccountMetaList::size_of(0).unwrap(),
+            &TRANSFER_HOOK_PROGRAM_ID,
+        );
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut validation_account.data, &[])
+            .unwrap();
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, 1_000_000, 1_000_000, 0);
+        let amount = |account: &SolanaAccount| {
+            StateWithExtensions::<Account>::unpack(&account.data)
+                .unwrap()
+                .base
+                .amount
+        };
+
+        let amount_in = 100_000;
+        let user_transfer_key = Pubkey::new_unique();
+        for (source_key, source_account) in [
+            (&token_a_key, &mut token_a_account),
+            (&token_b_key, &mut token_b_account),
+        ] {
+            do_process_instruction(
+                approve(
+                    &spl_token_2022::id(),
+                    source_key,
+                    &user_transfer_key,
+                    &swapper_key,
+                    &[],
+                    2 * amount_in,
+                )
+                .unwrap(),
+                vec![
+                    source_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                ],
+            )
+            .unwrap();
+        }
+
+        let swap_key = accounts.swap_key;
+        let authority_key = accounts.authority_key;
+        let pool_mint_key = accounts.pool_mint_key;
+        let pool_fee_key = accounts.pool_fee_key;
+        let swap_instruction = |source_key: &Pubkey,
+                                swap_source_key: &Pubkey,
+                                swap_destination_key: &Pubkey,
+                                destination_key: &Pubkey,
+                                source_mint_key: &Pubkey,
+                                destination_mint_key: &Pubkey,
+                                host_fee_key: Option<&Pubkey>| {
+            swap(
+                &SWAP_PROGRAM_ID,
+                &spl_token_2022::id(),
+                &spl_token_2022::id(),
+                &spl_token_2022::id(),
+                &swap_key,
+                &authority_key,
+                &user_transfer_key,
+                source_key,
+                swap_source_key,
+                swap_destination_key,
+                destination_key,
+                &pool_mint_key,
+                &pool_fee_key,
+                source_mint_key,
+                destination_mint_key,
+                host_fee_key,
+                Swap {
+                    amount_in,
+                    minimum_amount_out: 0,
+                },
+            )
+            .unwrap()
+        };
+
+        // A to B without the transfer hook accounts
+        let instruction = swap_instruction(
+            &token_a_key,
+            &accounts.token_a_key,
+            &accounts.token_b_key,
+            &token_b_key,
+            &accounts.token_a_mint_key,
+            &accounts.token_b_mint_key,
+            Some(&pool_key),
+        );
+        assert_eq!(
+            Err(TransferHookError::IncorrectAccount.into()),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut accounts.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    &mut token_a_account,
+                    &mut accounts.token_a_account,
+                    &mut accounts.token_b_account,
+                    &mut token_b_account,
+                    &mut accounts.pool_mint_account,
+                    &mut accounts.pool_fee_account,
+                    &mut accounts.token_a_mint_account,
+                    &mut accounts.token_b_mint_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    &mut pool_account,
+                ],
+            )
+        );
+
+        // A to B, the hook is called on the transfer into the pool
+        let mut instruction = swap_instruction(
+            &token_a_key,
+            &accounts.token_a_key,
+            &accounts.token_b_key,
+            &token_b_key,
+            &accounts.token_a_mint_key,
+            &accounts.token_b_mint_key,
+            Some(&pool_key),
+        );
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(TRANSFER_HOOK_PROGRAM_ID, false));
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(validation_key, false));
+        let user_a_before = amount(&token_a_account);
+        let user_b_before = amount(&token_b_account);
+        do_process_instruction(
+            instruction,
+            vec![
+                &mut accounts.swap_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut token_a_account,
+                &mut accounts.token_a_account,
+                &mut accounts.token_b_account,
+                &mut token_b_account,
+                &mut accounts.pool_mint_account,
+                &mut accounts.pool_fee_account,
+                &mut accounts.token_a_mint_account,
+                &mut accounts.token_b_mint_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut pool_account,
+                &mut SolanaAccount::default(),
+                &mut validation_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(user_a_before - amount(&token_a_account), amount_in);
+        assert!(amount(&token_b_account) > user_b_before);
+
+        // B to A, the hook is called on the transfer out of the pool
+        let mut instruction = swap_instruction(
+            &token_b_key,
+            &accounts.token_b_key,
+            &accounts.token_a_key,
+            &token_a_key,
+            &accounts.token_b_mint_key,
+            &accounts.token_a_mint_key,
+            Some(&pool_key),
+        );
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(TRANSFER_HOOK_PROGRAM_ID, false));
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(validation_key, false));
+        let user_a_before = amount(&token_a_account);
+        do_process_instruction(
+            instruction,
+            vec![
+                &mut accounts.swap_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut token_b_account,
+                &mut accounts.token_b_account,
+                &mut accounts.token_a_account,
+                &mut token_a_account,
+                &mut accounts.pool_mint_account,
+                &mut accounts.pool_fee_account,
+                &mut accounts.token_b_mint_account,
+                &mut accounts.token_a_mint_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut pool_account,
+                &mut SolanaAccount::default(),
+                &mut validation_account,
+            ],
+        )
+        .unwrap();
+        assert!(amount(&token_a_account) > user_a_before);
+
+        // A to B without a host, the program id fills the host fee slot so the
+        // transfer hook accounts still follow it
+        let mut instruction = swap_instruction(
+            &token_a_key,
+            &accounts.token_a_key,
+            &accounts.token_b_key,
+            &token_b_key,
+            &accounts.token_a_mint_key,
+            &accounts.token_b_mint_key,
+            None,
+        );
+        assert_eq!(
+            instruction.accounts[14],
+            AccountMeta::new_readonly(SWAP_PROGRAM_ID, false)
+        );
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(TRANSFER_HOOK_PROGRAM_ID, false));
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(validation_key, false));
+        let user_a_before = amount(&token_a_account);
+        let host_before = amount(&pool_account);
+        do_process_instruction(
+            instruction,
+            vec![
+                &mut accounts.swap_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut token_a_account,
+                &mut accounts.token_a_account,
+                &mut accounts.token_b_account,
+                &mut token_b_account,
+                &mut accounts.pool_mint_account,
+                &mut accounts.pool_fee_account,
+                &mut accounts.token_a_mint_account,
+                &mut accounts.token_b_mint_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut validation_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(user_a_before - amount(&token_a_account), amount_in);
+        assert_eq!(amount(&pool_account), host_before);
+    }
+
+    #[test]
+    fn test_route_swap() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let token_program_id = spl_token::id();
+
+        // route A -> B through the first pool, then B -> C through the second
+        let mut first = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve.clone(),
+            1_000_000,
+            2_000_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        first.initialize_swap().unwrap();
+        let mut second = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve.clone(),
+            3_000_000,
+            500_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        second.token_a_mint_key = first.token_b_mint_key;
+        second.token_a_mint_account = first.token_b_mint_account.clone();
+        (second.token_a_key, second.token_a_account) = mint_token(
+            &token_program_id,
+            &second.token_a_mint_key,
+            &mut second.token_a_mint_account,
+            &user_key,
+            &second.authority_key,
+            3_000_000,
+        );
+        second.initialize_swap().unwrap();
+
+        let (source_key, mut source_account, _, _, _, _) =
+            first.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let (_, _, destination_key, mut destination_account, _, _) =
+            second.setup_token_accounts(&user_key, &swapper_key, 0, 0, 0);
+
+        let amount_in = 10_000;
+        let user_transfer_authority_key = Pubkey::new_unique();
+        do_process_instruction(
+            approve(
+                &token_program_id,
+                &source_key,
+                &user_transfer_authority_key,
+                &swapper_key,
+                &[],
+                amount_in,
+            )
+            .unwrap(),
+            vec![
+                &mut source_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+            ],
+        )
+        .unwrap();
+
+        let amount = |account: &SolanaAccount| {
+            StateWithExtensions::<Account>::unpack(&account.data)
+                .unwrap()
+                .base
+                .amount
+        };
+        let quote = quote_route(
+            amount_in,
+            &[
+                RouteHop {
+                    swap_curve: &swap_curve,
+                    fees: &fees,
+                    swap_source_amount: amount(&first.token_a_account),
+                    swap_destination_amount: amount(&first.token_b_account),
+                    trade_direction: TradeDirection::AtoB,
+                    source_transfer_fee: None,
+                },
+                RouteHop {
+                    swap_curve: &swap_curve,
+                    fees: &fees,
+                    swap_source_amount: amount(&second.token_a_account),
+                    swap_destination_amount: amount(&second.token_b_account),
+                    trade_direction: TradeDirection::AtoB,
+                    source_transfer_fee: None,
+                },
+            ],
+            None,
+            0,
+        )
+        .unwrap();
+
+        let hops = [
+            RouteSwapHop {
+                swap_pubkey: first.swap_key,
+                authority_pubkey: first.authority_key,
+                swap_source_pubkey: first.token_a_key,
+                swap_destination_pubkey: first.token_b_key,
+                pool_mint_pubkey: first.pool_mint_key,
+                pool_fee_pubkey: first.pool_fee_key,
+                source_mint_pubkey: first.token_a_mint_key,
+                destination_mint_pubkey: first.token_b_mint_key,
+                source_token_program_id: token_program_id,
+                destination_token_program_id: token_program_id,
+                pool_token_program_id: token_program_id,
+                transfer_hook_pubkeys: vec![],
+            },
+            RouteSwapHop {
+                swap_pubkey: second.swap_key,
+                authority_pubkey: second.authority_key,
+                swap_source_pubkey: second.token_a_key,
+                swap_destination_pubkey: second.token_b_key,
+                pool_mint_pubkey: second.pool_mint_key,
+                pool_fee_pubkey: second.pool_fee_key,
+                source_mint_pubkey: second.token_a_mint_key,
+                destination_mint_pubkey: second.token_b_mint_key,
+                source_token_program_id: token_program_id,
+                destination_token_program_id: token_program_id,
+                pool_token_program_id: token_program_id,
+                transfer_hook_pubkeys: vec![],
+            },
+        ];
+
+        // the second pool is paid with the first pool's authority, so its
+        // token programs must be the ones owning its mints
+        let mut wrong_source_program = hops.clone();
+        wrong_source_program[1].source_token_program_id = Pubkey::new_unique();
+        let mut wrong_destination_program = hops.clone();
+        wrong_destination_program[1].destination_token_program_id = Pubkey::new_unique();
+
+        // only the final output is checked for slippage
+        for (hops, minimum_amount_out, expected) in [
+            (
+                &wrong_source_program,
+                0,
+                Err(SwapError::IncorrectTokenProgramId.into()),
+            ),
+            (
+                &wrong_destination_program,
+                0,
+                Err(SwapError::IncorrectTokenProgramId.into()),
+            ),
+            (
+                &hops,
+                quote.amount_out + 1,
+                Err(SwapError::ExceededSlippage.into()),
+            ),
+            (&hops, quote.amount_out, Ok(())),
+        ] {
+            assert_eq!(
+                expected,
+                do_process_instruction(
+                    route_swap(
+                        &SWAP_PROGRAM_ID,
+                        &user_transfer_authority_key,
+                        &source_key,
+                        &destination_key,
+                        hops,
+                        RouteSwap {
+                            amount_in,
+                            minimum_amount_out,
+                            transfer_hook_account_counts: vec![],
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut SolanaAccount::default(),
+                        &mut source_account,
+                        &mut destination_account,
+                        &mut first.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut first.token_a_account,
+                        &mut first.token_b_account,
+                        &mut first.pool_mint_account,
+                        &mut first.pool_fee_account,
+                        &mut first.token_a_mint_account,
+                        &mut first.token_b_mint_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                        &mut second.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut second.token_a_account,
+                        &mut second.token_b_account,
+                        &mut second.pool_mint_account,
+                        &mut second.pool_fee_account,
+                        &mut second.token_a_mint_account,
+                        &mut second.token_b_mint_account,
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                        &mut SolanaAccount::default(),
+                    ],
+                )
+            );
+        }
+
+        assert_eq!(amount(&source_account), 100_000 - amount_in);
+        assert_eq!(amount(&destination_account), quote.amount_out);
+        assert_eq!(
+            u128::from(amount(&first.token_a_account)),
+            quote.hops[0].new_swap_source_amount
+        );
+        // the first pool keeps what the second pool's curve did not consume
+        assert_eq!(
+            u128::from(amount(&first.token_b_account)),
+            quote.hops[0].new_swap_destination_amount + quote.hops[0].destination_amount_swapped
+                - quote.hops[1].source_amount_swapped
+        );
+        assert_eq!(
+            u128::from(amount(&second.token_a_account)),
+            quote.hops[1].new_swap_source_amount
+        );
+        assert_eq!(
+            u128::from(amount(&second.token_b_account)),
+            quote.hops[1].new_swap_destination_amount
+        );
+        assert!(amount(&first.pool_fee_account) > 0);
+        assert!(amount(&second.pool_fee_account) > 0);
+    }
+
+    #[test]
+    fn test_route_swap_with_intermediate_transfer_fee() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let token_program_id = spl_token_2022::id();
+
+        // route A -> B through the first pool, then B -> C through the second,
+        // with B withholding a transfer fee when paid into the second pool
+        let mut first = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees {
+                token_b: TransferFee {
+                    epoch: 0.into(),
+                    transfer_fee_basis_points: 333.into(),
+                    maximum_fee: u64::MAX.into(),
+                },
+                ..SwapTransferFees::default()
+            },
+            swap_curve.clone(),
+            1_000_000,
+            2_000_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        first.initialize_swap().unwrap();
+        let mut second = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve.clone(),
+            3_000_000,
+            500_000,
+            &token_program_id,
+            &token_program_id,
+            &token_program_id,
+        );
+        second.token_a_mint_key = first.token_b_mint_key;
+        second.token_a_mint_account = first.token_b_mint_account.clone();
+        (second.token_a_key, second.token_a_account) = mint_token(
+            &token_program_id,
+            &second.token_a_mint_key,
+            &mut second.token_a_mint_account,
+            &user_key,
+            &second.authority_key,
+            3_000_000,
+        );
+        second.initialize_swap().unwrap();
+
+        let (source_key, mut source_account, _, _, _, _) =
+            first.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let (_, _, destination_key, mut destination_account, _, _) =
+            second.setup_token_accounts(&user_key, &swapper_key, 0, 0, 0);
+
+        let amount_in = 10_007;
+        let user_transfer_authority_key = Pubkey::new_unique();
+        do_process_instruction(
+            approve(
+                &token_program_id,
+                &source_key,
+                &user_transfer_authority_key,
+                &swapper_key,
+                &[],
+                amount_in,
+            )
+            .unwrap(),
+            vec![
+                &mut source_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+            ],
+        )
+        .unwrap();
+
+        let amount = |account: &SolanaAccount| {
+            StateWithExtensions::<Account>::unpack(&account.data)
+                .unwrap()
+                .base
+                .amount
+        };
+        let intermediate_mint =
+            StateWithExtensions::<Mint>::unpack(&first.token_b_mint_account.data).unwrap();
+        let intermediate_transfer_fee = *intermediate_mint
+            .get_extension::<TransferFeeConfig>()
+            .unwrap();
+        let quote = quote_route(
+            amount_in,
+            &[
+                RouteHop {
+                    swap_curve: &swap_curve,
+                    fees: &fees,
+                    swap_source_amount: amount(&first.token_a_account),
+                    swap_destination_amount: amount(&first.token_b_account),
+                    trade_direction: TradeDirection::AtoB,
+                    source_transfer_fee: None,
+                },
+                RouteHop {
+                    swap_curve: &swap_curve,
+                    fees: &fees,
+                    swap_source_amount: amount(&second.token_a_account),
+                    swap_destination_amount: amount(&second.token_b_account),
+                    trade_direction: TradeDirection::AtoB,
+                    source_transfer_fee: Some(&intermediate_transfer_fee),
+                },
+            ],
+            None,
+            0,
+        )
+        .unwrap();
+
+        let hops = [
+            RouteSwapHop {
+                swap_pubkey: first.swap_key,
+                authority_pubkey: first.authority_key,
+                swap_source_pubkey: first.token_a_key,
+                swap_destination_pubkey: first.token_b_key,
+                pool_mint_pubkey: first.pool_mint_key,
+                pool_fee_pubkey: first.pool_fee_key,
+                source_mint_pubkey: first.token_a_mint_key,
+                destination_mint_pubkey: first.token_b_mint_key,
+                source_token_program_id: token_program_id,
+                destination_token_program_id: token_program_id,
+                pool_token_program_id: token_program_id,
+                transfer_hook_pubkeys: vec![],
+            },
+            RouteSwapHop {
+                swap_pubkey: second.swap_key,
+                authority_pubkey: second.authority_key,
+                swap_source_pubkey: second.token_a_key,
+                swap_destination_pubkey: second.token_b_key,
+                pool_mint_pubkey: second.pool_mint_key,
+                pool_fee_pubkey: second.pool_fee_key,
+                source_mint_pubkey: second.token_a_mint_key,
+                destination_mint_pubkey: second.token_b_mint_key,
+                source_token_program_id: token_program_id,
+                destination_token_program_id: token_program_id,
+                pool_token_program_id: token_program_id,
+                transfer_hook_pubkeys: vec![],
+            },
+        ];
+        do_process_instruction(
+            route_swap(
+                &SWAP_PROGRAM_ID,
+                &user_transfer_authority_key,
+                &source_key,
+                &destination_key,
+                &hops,
+                RouteSwap {
+                    amount_in,
+                    minimum_amount_out: quote.amount_out,
+                    transfer_hook_account_counts: vec![],
+                },
+            )
+            .unwrap(),
+            vec![
+                &mut SolanaAccount::default(),
+                &mut source_account,
+                &mut destination_account,
+                &mut first.swap_account,
+                &mut SolanaAccount::default(),
+                &mut first.token_a_account,
+                &mut first.token_b_account,
+                &mut first.pool_mint_account,
+                &mut first.pool_fee_account,
+                &mut first.token_a_mint_account,
+                &mut first.token_b_mint_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut second.swap_account,
+                &mut SolanaAccount::default(),
+                &mut second.token_a_account,
+                &mut second.token_b_account,
+                &mut second.pool_mint_account,
+                &mut second.pool_fee_account,
+                &mut second.token_a_mint_account,
+                &mut second.token_b_mint_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(amount(&source_account), 100_000 - amount_in);
+        assert_eq!(amount(&destination_account), quote.amount_out);
+        assert_eq!(
+            u128::from(amount(&second.token_a_account)),
+            quote.hops[1].new_swap_source_amount
+        );
+
+        // the first pool pays the second pool at most what it swapped out, and
+        // the second pool receives what its curve consumed after the fee
+        let paid_in = quote.hops[0].new_swap_destination_amount
+            + quote.hops[0].destination_amount_swapped
+            - u128::from(amount(&first.token_b_account));
+        assert!(paid_in <= quote.hops[0].destination_amount_swapped);
+        let paid_in = to_u64(paid_in).unwrap();
+        assert_eq!(
+            u128::from(
+                paid_in
+                    - intermediate_transfer_fee
+                        .calculate_epoch_fee(0, paid_in)
+                        .unwrap()
+            ),
+            quote.hops[1].source_amount_swapped
+        );
+    }
+
+    #[test]
+    fn test_route_swap_with_transfer_hook() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+
+        // route A -> B through the first pool, then B -> C through the second,
+        // where B has a transfer hook and the token programs differ
+        let mut first = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve.clone(),
+            1_000_000,
+            2_000_000,
+            &spl_token::id(),
+            &spl_token::id(),
+            &spl_token_2022::id(),
+        );
+        (first.token_b_mint_key, first.token_b_mint_account) =
+            create_transfer_hook_mint(&user_key, &TRANSFER_HOOK_PROGRAM_ID);
+        (first.token_b_key, first.token_b_account) = mint_token(
+            &spl_token_2022::id(),
+            &first.token_b_mint_key,
+            &mut first.token_b_mint_account,
+            &user_key,
+            &first.authority_key,
+            2_000_000,
+        );
+        first.initialize_swap().unwrap();
+        let mut second = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve.clone(),
+            3_000_000,
+            500_000,
+            &spl_token_2022::id(),
+            &spl_token_2022::id(),
+            &spl_token::id(),
+        );
+        second.token_a_mint_key = first.token_b_mint_key;
+        second.token_a_mint_account = first.token_b_mint_account.clone();
+        (second.token_a_key, second.token_a_account) = mint_token(
+            &spl_token_2022::id(),
+            &second.token_a_mint_key,
+            &mut second.token_a_mint_account,
+            &user_key,
+            &second.authority_key,
+            3_000_000,
+        );
+        second.initialize_swap().unwrap();
+
+        let validation_key =
+            get_extra_account_metas_address(&first.token_b_mint_key, &TRANSFER_HOOK_PROGRAM_ID);
+        let mut validation_account = SolanaAccount::new(
+            0,
+            ExtraAccountMetaList::size_of(0).unwrap(),
+            &TRANSFER_HOOK_PROGRAM_ID,
+        );
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut validation_account.data, &[])
+            .unwrap();
+
+        let (source_key, mut source_account, _, _, _, _) =
+            first.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let (_, _, destination_key, mut destination_account, _, _) =
+            second.setup_token_accounts(&user_key, &swapper_key, 0, 0, 0);
+
+        let amount_in = 10_000;
+        let user_transfer_authority_key = Pubkey::new_unique();
+        do_process_instruction(
+            approve(
+                &spl_token::id(),
+                &source_key,
+                &user_transfer_authority_key,
+                &swapper_key,
+                &[],
+                amount_in,
+            )
+            .unwrap(),
+            vec![
+                &mut source_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+            ],
+        )
+        .unwrap();
+
+        let amount = |account: &SolanaAccount| {
+            StateWithExtensions::<Account>::unpack(&account.data)
+                .unwrap()
+                .base
+                .amount
+        };
+        let quote = quote_route(
+            amount_in,
+            &[
+                RouteHop {
+                    swap_curve: &swap_curve,
+                    fees: &fees,
+                    swap_source_amount: amount(&first.token_a_account),
+                    swap_destination_amount: amount(&first.token_b_account),
+                    trade_direction: TradeDirection::AtoB,
+                    source_transfer_fee: None,
+                },
+                RouteHop {
+                    swap_curve: &swap_curve,
+                    fees: &fees,
+                    swap_source_amount: amount(&second.token_a_account),
+                    swap_destination_amount: amount(&second.token_b_account),
+                    trade_direction: TradeDirection::AtoB,
+                    source_transfer_fee: None,
+                },
+            ],
+            None,
+            0,
+        )
+        .unwrap();
+
+        let hops = [
+            RouteSwapHop {
+                swap_pubkey: first.swap_key,
+                authority_pubkey: first.authority_key,
+                swap_source_pubkey: first.token_a_key,
+                swap_destination_pubkey: first.token_b_key,
+                pool_mint_pubkey: first.pool_mint_key,
+                pool_fee_pubkey: first.pool_fee_key,
+                source_mint_pubkey: first.token_a_mint_key,
+                destination_mint_pubkey: first.token_b_mint_key,
+                source_token_program_id: spl_token::id(),
+                destination_token_program_id: spl_token_2022::id(),
+                pool_token_program_id: spl_token::id(),
+                transfer_hook_pubkeys: vec![],
+            },
+            RouteSwapHop {
+                swap_pubkey: second.swap_key,
+                authority_pubkey: second.authority_key,
+                swap_source_pubkey: second.token_a_key,
+                swap_destination_pubkey: second.token_b_key,
+                pool_mint_pubkey: second.pool_mint_key,
+                pool_fee_pubkey: second.pool_fee_key,
+                source_mint_pubkey: second.token_a_mint_key,
+                destination_mint_pubkey: second.token_b_mint_key,
+                source_token_program_id: spl_token_2022::id(),
+                destination_token_program_id: spl_token::id(),
+                pool_token_program_id: spl_token_2022::id(),
+                transfer_hook_pubkeys: vec![],
+            },
+        ];
+        let route_swap_instruction = |hops: &[RouteSwapHop]| {
+            route_swap(
+                &SWAP_PROGRAM_ID,
+                &user_transfer_authority_key,
+                &source_key,
+                &destination_key,
+                hops,
+                RouteSwap {
+                    amount_in,
+                    minimum_amount_out: quote.amount_out,
+                    transfer_hook_account_counts: vec![],
+                },
+            )
+            .unwrap()
+        };
+
+        // the transfer from the first pool to the second calls the hook of B
+        assert_eq!(
+            Err(TransferHookError::IncorrectAccount.into()),
+            do_process_instruction(
+                route_swap_instruction(&hops),
+                vec![
+                    &mut SolanaAccount::default(),
+                    &mut source_account,
+                    &mut destination_account,
+                    &mut first.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut first.token_a_account,
+                    &mut first.token_b_account,
+                    &mut first.pool_mint_account,
+                    &mut first.pool_fee_account,
+                    &mut first.token_a_mint_account,
+                    &mut first.token_b_mint_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    &mut second.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut second.token_a_account,
+                    &mut second.token_b_account,
+                    &mut second.pool_mint_account,
+                    &mut second.pool_fee_account,
+                    &mut second.token_a_mint_account,
+                    &mut second.token_b_mint_account,
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                    &mut SolanaAccount::default(),
+                ],
+            )
+        );
+
+        let mut hops = hops;
+        hops[1].transfer_hook_pubkeys = vec![TRANSFER_HOOK_PROGRAM_ID, validation_key];
+        do_process_instruction(
+            route_swap_instruction(&hops),
+            vec![
+                &mut SolanaAccount::default(),
+                &mut source_account,
+                &mut destination_account,
+                &mut first.swap_account,
+                &mut SolanaAccount::default(),
+                &mut first.token_a_account,
+                &mut first.token_b_account,
+                &mut first.pool_mint_account,
+                &mut first.pool_fee_account,
+                &mut first.token_a_mint_account,
+                &mut first.token_b_mint_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut second.swap_account,
+                &mut SolanaAccount::default(),
+                &mut second.token_a_account,
+                &mut second.token_b_account,
+                &mut second.pool_mint_account,
+                &mut second.pool_fee_account,
+                &mut second.token_a_mint_account,
+                &mut second.token_b_mint_account,
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut SolanaAccount::default(),
+                &mut validation_account,
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(amount(&source_account), 100_000 - amount_in);
+        assert_eq!(amount(&destination_account), quote.amount_out);
+        assert_eq!(
+            u128::from(amount(&second.token_a_account)),
+            quote.hops[1].new_swap_source_amount
+        );
+    }
+
+    #[test]
+    fn test_price_oracle() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 4_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            SwapTransferFees::default(),
+            swap_curve,
+            token_a_amount,
+            token_b_amount,
+            &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
+        );
+        set_unix_timestamp(1_000);
+        set_slot(2_500);
+        accounts.initialize_swap().unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(
+            swap_state.price_oracle(),
+            Some(&PriceOracle::new(1_000, 2_500))
+        );
+
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+
+        // the accumulators use the pool amounts held before the swap
+        set_unix_timestamp(1_100);
+        set_slot(2_750);
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100_000,
+                0,
+            )
+            .unwrap();
+        set_unix_timestamp(0);
+        set_slot(0);
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        let price_oracle = *swap_state.price_oracle().unwrap();
+        let one = 1u128 << PRICE_FRACTIONAL_BITS;
+        assert_eq!(
+            price_oracle,
+            PriceOracle {
+                price_a_cumulative: 4 * one * 100,
+                price_b_cumulative: one / 4 * 100,
+                last_update_timestamp: 1_100,
+                last_update_slot: 2_750,
+            }
+        );
+    }
+
+    #[test]
+    fn test_swap_without_price_oracle() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            SwapTransferFees::default(),
+            swap_curve,
+            1_000_000,
+            1_000_000,
+            &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
+        );
+        // accounts sized for the first version are initialized without oracle
+        accounts.swap_account = SolanaAccount::new(0, 1 + SwapV1::LEN, &SWAP_PROGRAM_ID);
+        accounts.initialize_swap().unwrap();
+        assert_eq!(accounts.swap_account.data[0], 1);
+
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100_000,
+                0,
+            )
+            .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_state.price_oracle(), None);
+    }
+
+    #[test]
+    fn test_ramp_amplification() {
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::Stable,
+            calculator: Arc::new(StableCurve {
+                amp: 100,
+                ..StableCurve::default()
+            }),
+        };
+        let user_key_str = &user_key.to_string();
+        let valid_curve_types = &[CurveType::Stable];
+        let constraints = Some(SwapConstraints {
+            owner_key: user_key_str,
+            valid_curve_types,
+            fees: &fees,
+        });
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve,
+            1_000_000,
+            1_000_000,
+            &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
+        );
+        accounts.initialize_swap().unwrap();
+
+        let ramp = RampAmplification {
+            target_amp: 1_000,
+            stop_ramp_ts: MIN_RAMP_DURATION,
+        };
+
+        // no swap constraints
+        {
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                do_process_instruction_with_fee_constraints(
+                    ramp_amplification(
+                        &SWAP_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &user_key,
+                        &accounts.pool_fee_key,
+                        ramp.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut accounts.pool_fee_account,
+                    ],
+                    &None,
+                )
+            );
+        }
+
+        // wrong admin
+        {
+            let wrong_admin_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                do_process_instruction_with_fee_constraints(
+                    ramp_amplification(
+                        &SWAP_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &wrong_admin_key,
+                        &accounts.pool_fee_key,
+                        ramp.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut accounts.pool_fee_account,
+                    ],
+                    &constraints,
+                )
+            );
+        }
+
+        // wrong fee account
+        {
+            let (_, _, _, _, pool_key, mut pool_account) =
+                accounts.setup_token_accounts(&user_key, &user_key, 0, 0, 0);
+            assert_eq!(
+                Err(SwapError::IncorrectFeeAccount.into()),
+                do_process_instruction_with_fee_constraints(
+                    ramp_amplification(
+                        &SWAP_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &user_key,
+                        &pool_key,
+                        ramp.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut pool_account,
+                    ],
+                    &constraints,
+                )
+            );
+        }
+
+        // admin did not sign
+        {
+            let mut instruction = ramp_amplification(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &user_key,
+                &accounts.pool_fee_key,
+                ramp.clone(),
+            )
+            .unwrap();
+            instruction.accounts[1].is_signer = false;
+            assert_eq!(
+                Err(ProgramError::MissingRequiredSignature),
+                do_process_instruction_with_fee_constraints(
+                    instruction,
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut accounts.pool_fee_account,
+                    ],
+                    &constraints,
+                )
+            );
+        }
+
+        // ramp too short
+        {
+            assert_eq!(
+                Err(SwapError::InvalidRamp.into()),
+                do_process_instruction_with_fee_constraints(
+                    ramp_amplification(
+                        &SWAP_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &user_key,
+                        &accounts.pool_fee_key,
+                        RampAmplification {
+                            target_amp: 1_000,
+                            stop_ramp_ts: MIN_RAMP_DURATION - 1,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut accounts.pool_fee_account,
+                    ],
+                    &constraints,
+                )
+            );
+        }
+
+        // correct ramp
+        do_process_instruction_with_fee_constraints(
+            ramp_amplification(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &user_key,
+                &accounts.pool_fee_key,
+                ramp.clone(),
+            )
+            .unwrap(),
+            vec![
+                &mut accounts.swap_account,
+                &mut SolanaAccount::default(),
+                &mut accounts.pool_fee_account,
+            ],
+            &constraints,
+        )
+        .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        let mut expected = vec![0u8; StableCurve::LEN];
+        StableCurve {
+            amp: 100,
+            target_amp: 1_000,
+            start_ramp_ts: 0,
+            stop_ramp_ts: MIN_RAMP_DURATION,
+        }
+        .pack_into_slice(&mut expected);
+        let mut packed = vec![0u8; StableCurve::LEN];
+        swap_state
+            .swap_curve()
+            .calculator
+            .pack_into_slice(&mut packed);
+        assert_eq!(packed, expected);
+
+        // ramp already in progress
+        {
+            assert_eq!(
+                Err(SwapError::InvalidRamp.into()),
+                do_process_instruction_with_fee_constraints(
+                    ramp_amplification(
+                        &SWAP_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &user_key,
+                        &accounts.pool_fee_key,
+                        ramp,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut SolanaAccount::default(),
+                        &mut accounts.pool_fee_account,
+                    ],
+                    &constraints,
+                )
+            );
+        }
+
+        // stop ramp
+        do_process_instruction_with_fee_constraints(
+            stop_ramp(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &user_key,
+                &accounts.pool_fee_key,
+            )
+            .unwrap(),
+            vec![
+                &mut accounts.swap_account,
+                &mut SolanaAccount::default(),
+                &mut accounts.pool_fee_account,
+            ],
+            &constraints,
+        )
+        .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        StableCurve {
+            amp: 100,
+            target_amp: 100,
+            start_ramp_ts: 0,
+            stop_ramp_ts: 0,
+        }
+        .pack_into_slice(&mut expected);
+        swap_state
+            .swap_curve()
+            .calculator
+            .pack_into_slice(&mut packed);
+        assert_eq!(packed, expected);
+    }
+
+    #[test]
+    fn test_ramp_amplification_with_constraints() {
+        let owner_key = Pubkey::new_unique();
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::Stable,
+            calculator: Arc::new(StableCurve {
+                amp: 100,
+                ..StableCurve::default()
+            }),
+        };
+        let owner_key_str = &owner_key.to_string();
+        let valid_curve_types = &[CurveType::Stable];
+        let constraints = Some(SwapConstraints {
+            owner_key: owner_key_str,
+            valid_curve_types,
+            fees: &fees,
+        });
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve,
+            1_000_000,
+            1_000_000,
+            &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
+        );
+        accounts.initialize_swap().unwrap();
+        let ramp = RampAmplification {
+            target_amp: 1_000,
+            stop_ramp_ts: MIN_RAMP_DURATION,
+        };
+
+        // the fee account owner is not the admin under constraints
+        assert_eq!(
+            Err(SwapError::InvalidOwner.into()),
+            do_process_instruction_with_fee_constraints(
+                ramp_amplification(
+                    &SWAP_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &user_key,
+                    &accounts.pool_fee_key,
+                    ramp.clone(),
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut accounts.pool_fee_account,
+                ],
+                &constraints,
+            )
+        );
+
+        do_process_instruction_with_fee_constraints(
+            ramp_amplification(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &owner_key,
+                &accounts.pool_fee_key,
+                ramp,
+            )
+            .unwrap(),
+            vec![
+                &mut accounts.swap_account,
+                &mut SolanaAccount::default(),
+                &mut accounts.pool_fee_account,
+            ],
+            &constraints,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_ramp_amplification_unsupported_curve() {
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            ..Fees::default()
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let user_key_str = &user_key.to_string();
+        let valid_curve_types = &[CurveType::ConstantProduct];
+        let constraints = Some(SwapConstraints {
+            owner_key: user_key_str,
+            valid_curve_types,
+            fees: &fees,
+        });
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapTransferFees::default(),
+            swap_curve,
+            1_000_000,
+            1_000_000,
+            &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
+        );
+        accounts.initialize_swap().unwrap();
+
+        assert_eq!(
+            Err(SwapError::UnsupportedCurveOperation.into()),
+            do_process_instruction_with_fee_constraints(
+                ramp_amplification(
+                    &SWAP_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &user_key,
+                    &accounts.pool_fee_key,
+                    RampAmplification {
+                        target_amp: 1_000,
+                        stop_ramp_ts: MIN_RAMP_DURATION,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut accounts.pool_fee_account,
+                ],
+                &constraints,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::UnsupportedCurveOperation.into()),
+            do_process_instruction_with_fee_constraints(
+                stop_ramp(
+                    &SWAP_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &user_key,
+                    &accounts.pool_fee_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut SolanaAccount::default(),
+                    &mut accounts.pool_fee_account,
+                ],
+                &constraints,
+            )
+        );
+    }
 }
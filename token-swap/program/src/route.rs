@@ -0,0 +1,190 @@
+//! Off-chain quoting for routed swaps
+
+use {
+    crate::curve::{
+        base::{SwapCurve, SwapResult},
+        calculator::TradeDirection,
+        fees::Fees,
+    },
+    solana_program::clock::Epoch,
+    spl_token_2022::extension::transfer_fee::TransferFeeConfig,
+    std::convert::TryInto,
+};
+
+/// State of one pool along a route, oriented in the direction of the trade
+#[derive(Debug)]
+pub struct RouteHop<'a> {
+    /// Curve of the pool, resolved at the time of the swap
+    pub swap_curve: &'a SwapCurve,
+    /// Fees of the pool
+    pub fees: &'a Fees,
+    /// Amount held by the pool in the token it receives
+    pub swap_source_amount: u64,
+    /// Amount held by the pool in the token it sends
+    pub swap_destination_amount: u64,
+    /// Direction of the trade through the pool
+    pub trade_direction: TradeDirection,
+    /// Transfer fee config of the mint received by the pool, if any
+    pub source_transfer_fee: Option<&'a TransferFeeConfig>,
+}
+
+/// Result of quoting a route
+#[derive(Debug, PartialEq)]
+pub struct RouteQuote {
+    /// Result of the swap through each pool of the route
+    pub hops: Vec<SwapResult>,
+    /// Amount received by the user, after any transfer fee
+    pub amount_out: u64,
+}
+
+/// Simulates a [RouteSwap](../instruction/enum.SwapInstruction.html) with the
+/// same curve and transfer fee math as the program.  Every pool is expected
+/// to appear only once in the route.
+pub fn quote_route(
+    amount_in: u64,
+    hops: &[RouteHop],
+    destination_transfer_fee: Option<&TransferFeeConfig>,
+    epoch: Epoch,
+) -> Option<RouteQuote> {
+    if hops.is_empty() {
+        return None;
+    }
+    let mut hop_amount_in = amount_in;
+    let mut results = Vec::with_capacity(hops.len());
+    for hop in hops {
+        let actual_amount_in = hop_amount_in.saturating_sub(transfer_fee(
+            hop.source_transfer_fee,
+            epoch,
+            hop_amount_in,
+        )?);
+        let result = hop.swap_curve.swap(
+            actual_amount_in.into(),
+            hop.swap_source_amount.into(),
+            hop.swap_destination_amount.into(),
+            hop.trade_direction,
+            hop.fees,
+        )?;
+        hop_amount_in = result.destination_amount_swapped.try_into().ok()?;
+        results.push(result);
+    }
+    let amount_out = hop_amount_in.saturating_sub(transfer_fee(
+        destination_transfer_fee,
+        epoch,
+        hop_amount_in,
+    )?);
+    Some(RouteQuote {
+        hops: results,
+        amount_out,
+    })
+}
+
+fn transfer_fee(config: Option<&TransferFeeConfig>, epoch: Epoch, amount: u64) -> Option<u64> {
+    config.map_or(Some(0), |config| config.calculate_epoch_fee(epoch, amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::curve::{base::CurveType, constant_product::ConstantProductCurve},
+        spl_token_2022::extension::transfer_fee::TransferFee,
+        std::sync::Arc,
+    };
+
+    fn constant_product() -> SwapCurve {
+        SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        }
+    }
+
+    #[test]
+    fn quote_matches_sequential_swaps() {
+        let swap_curve = constant_product();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let amount_in = 10_000;
+        let first = swap_curve
+            .swap(
+                amount_in.into(),
+                1_000_000,
+                2_000_000,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        let second = swap_curve
+            .swap(
+                first.destination_amount_swapped,
+                3_000_000,
+                500_000,
+                TradeDirection::BtoA,
+                &fees,
+            )
+            .unwrap();
+        let amount_out = second.destination_amount_swapped.try_into().unwrap();
+
+        let hops = [
+            RouteHop {
+                swap_curve: &swap_curve,
+                fees: &fees,
+                swap_source_amount: 1_000_000,
+                swap_destination_amount: 2_000_000,
+                trade_direction: TradeDirection::AtoB,
+                source_transfer_fee: None,
+            },
+            RouteHop {
+                swap_curve: &swap_curve,
+                fees: &fees,
+                swap_source_amount: 3_000_000,
+                swap_destination_amount: 500_000,
+                trade_direction: TradeDirection::BtoA,
+                source_transfer_fee: None,
+            },
+        ];
+        assert_eq!(
+            quote_route(amount_in, &hops, None, 0).unwrap(),
+            RouteQuote {
+                hops: vec![first, second],
+                amount_out,
+            }
+        );
+        assert_eq!(quote_route(amount_in, &[], None, 0), None);
+    }
+
+    #[test]
+    fn quote_with_transfer_fees() {
+        let swap_curve = constant_product();
+        let fees = Fees::default();
+        let transfer_fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: 100.into(),
+        };
+        let transfer_fee_config = TransferFeeConfig {
+            older_transfer_fee: transfer_fee,
+            newer_transfer_fee: transfer_fee,
+            ..TransferFeeConfig::default()
+        };
+        let hops = [RouteHop {
+            swap_curve: &swap_curve,
+            fees: &fees,
+            swap_source_amount: 1_000_000,
+            swap_destination_amount: 1_000_000,
+            trade_direction: TradeDirection::AtoB,
+            source_transfer_fee: Some(&transfer_fee_config),
+        }];
+        let quote = quote_route(10_000, &hops, Some(&transfer_fee_config), 0).unwrap();
+        assert_eq!(quote.hops[0].source_amount_swapped, 9_900);
+        let swapped: u64 = quote.hops[0].destination_amount_swapped.try_into().unwrap();
+        assert_eq!(
+            quote.amount_out,
+            swapped - transfer_fee.calculate_fee(swapped).unwrap()
+        );
+    }
+}
@@ -4,6 +4,7 @@ use {
     crate::{
         curve::{base::SwapCurve, fees::Fees},
         error::SwapError,
+        oracle::PriceOracle,
     },
     arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
     enum_dispatch::enum_dispatch,
@@ -52,13 +53,17 @@ pub trait SwapState {
     fn fees(&self) -> &Fees;
     /// Curve associated with swap
     fn swap_curve(&self) -> &SwapCurve;
+    /// Price oracle of the swap, if its version has one
+    fn price_oracle(&self) -> Option<&PriceOracle>;
 }
 
 /// All versions of SwapState
 #[enum_dispatch(SwapState)]
 pub enum SwapVersion {
-    /// Latest version, used for all new swaps
+    /// Original version, without a price oracle
     SwapV1,
+    /// Latest version, used for all new swaps
+    SwapV2,
 }
 
 /// SwapVersion does not implement program_pack::Pack because there are size
@@ -66,14 +71,26 @@ pub enum SwapVersion {
 /// special implementations are provided here
 impl SwapVersion {
     /// Size of the latest version of the SwapState
-    pub const LATEST_LEN: usize = 1 + SwapV1::LEN; // add one for the version enum
+    pub const LATEST_LEN: usize = 1 + SwapV2::LEN; // add one for the version enum
 
     /// Pack a swap into a byte array, based on its version
     pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
         match src {
             Self::SwapV1(swap_info) => {
                 dst[0] = 1;
-                SwapV1::pack(swap_info, &mut dst[1..])
+                SwapV1::pack(
+                    swap_info,
+                    dst.get_mut(1..1 + SwapV1::LEN)
+                        .ok_or(ProgramError::AccountDataTooSmall)?,
+                )
+            }
+            Self::SwapV2(swap_info) => {
+                dst[0] = 2;
+                SwapV2::pack(
+                    swap_info,
+                    dst.get_mut(1..1 + SwapV2::LEN)
+                        .ok_or(ProgramError::AccountDataTooSmall)?,
+                )
             }
         }
     }
@@ -85,7 +102,14 @@ impl SwapVersion {
             .split_first()
             .ok_or(ProgramError::InvalidAccountData)?;
         match version {
-            1 => Ok(Arc::new(SwapV1::unpack(rest)?)),
+            1 => Ok(Arc::new(SwapV1::unpack(
+                rest.get(..SwapV1::LEN)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            )?)),
+            2 => Ok(Arc::new(SwapV2::unpack(
+                rest.get(..SwapV2::LEN)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            )?)),
             _ => Err(ProgramError::UninitializedAccount),
         }
     }
@@ -179,22 +203,7 @@ impl SwapState for SwapV1 {
     }
 
     fn check_pool_fee_info(&self, pool_fee_info: &AccountInfo) -> Result<(), ProgramError> {
-        let data = &pool_fee_info.data.borrow();
-        let token_account =
-            StateWithExtensions::<Account>::unpack(data).map_err(|err| match err {
-                ProgramError::InvalidAccountData | ProgramError::UninitializedAccount => {
-                    SwapError::InvalidFeeAccount.into()
-                }
-                _ => err,
-            })?;
-        if pool_fee_info.owner != &self.token_program_id
-            || token_account.base.state != AccountState::Initialized
-            || token_account.base.mint != self.pool_mint
-        {
-            msg!("Pool fee account is not owned by token program, is not initialized, or does not match stake pool's mint");
-            return Err(SwapError::InvalidFeeAccount.into());
-        }
-        Ok(())
+        check_pool_fee_info(pool_fee_info, &self.token_program_id, &self.pool_mint)
     }
 
     fn fees(&self) -> &Fees {
@@ -204,6 +213,10 @@ impl SwapState for SwapV1 {
     fn swap_curve(&self) -> &SwapCurve {
         &self.swap_curve
     }
+
+    fn price_oracle(&self) -> Option<&PriceOracle> {
+        None
+    }
 }
 
 impl Sealed for SwapV1 {}
@@ -281,6 +294,199 @@ impl Pack for SwapV1 {
     }
 }
 
+/// Program states, with a price oracle.
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct SwapV2 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Bump seed used in program address.
+    pub bump_seed: u8,
+
+    /// Program ID of the tokens being exchanged.
+    pub token_program_id: Pubkey,
+
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
+
+    /// Pool tokens are issued when A or B tokens are deposited.
+    pub pool_mint: Pubkey,
+
+    /// Mint information for token A
+    pub token_a_mint: Pubkey,
+    /// Mint information for token B
+    pub token_b_mint: Pubkey,
+
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
+
+    /// All fee information
+    pub fees: Fees,
+
+    /// Swap curve parameters
+    pub swap_curve: SwapCurve,
+
+    /// Cumulative prices, updated before every change to the pool amounts
+    pub price_oracle: PriceOracle,
+}
+
+impl SwapState for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    fn bump_seed(&self) -> u8 {
+        self.bump_seed
+    }
+
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
+
+    fn token_a_account(&self) -> &Pubkey {
+        &self.token_a
+    }
+
+    fn token_b_account(&self) -> &Pubkey {
+        &self.token_b
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.token_a_mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.token_b_mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn check_pool_fee_info(&self, pool_fee_info: &AccountInfo) -> Result<(), ProgramError> {
+        check_pool_fee_info(pool_fee_info, &self.token_program_id, &self.pool_mint)
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+
+    fn price_oracle(&self) -> Option<&PriceOracle> {
+        Some(&self.price_oracle)
+    }
+}
+
+impl Sealed for SwapV2 {}
+impl IsInitialized for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapV2 {
+    const LEN: usize = 371;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 371];
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_oracle,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 48];
+        is_initialized[0] = self.is_initialized as u8;
+        bump_seed[0] = self.bump_seed;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        self.price_oracle.pack_into_slice(&mut price_oracle[..]);
+    }
+
+    /// Unpacks a byte buffer into a [SwapV2](struct.SwapV2.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 371];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_oracle,
+        ) = array_refs![input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 48];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            bump_seed: bump_seed[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            swap_curve: SwapCurve::unpack_from_slice(swap_curve)?,
+            price_oracle: PriceOracle::unpack_from_slice(price_oracle)?,
+        })
+    }
+}
+
+fn check_pool_fee_info(
+    pool_fee_info: &AccountInfo,
+    token_program_id: &Pubkey,
+    pool_mint: &Pubkey,
+) -> Result<(), ProgramError> {
+    let data = &pool_fee_info.data.borrow();
+    let token_account = StateWithExtensions::<Account>::unpack(data).map_err(|err| match err {
+        ProgramError::InvalidAccountData | ProgramError::UninitializedAccount => {
+            SwapError::InvalidFeeAccount.into()
+        }
+        _ => err,
+    })?;
+    if pool_fee_info.owner != token_program_id
+        || token_account.base.state != AccountState::Initialized
+        || token_account.base.mint != *pool_mint
+    {
+        msg!("Pool fee account is not owned by token program, is not initialized, or does not match stake pool's mint");
+        return Err(SwapError::InvalidFeeAccount.into());
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, crate::curve::offset::OffsetCurve, std::convert::TryInto};
@@ -406,4 +612,71 @@ mod tests {
         let err = SwapV1::unpack(&packed).unwrap_err();
         assert_eq!(err, ProgramError::UninitializedAccount);
     }
+
+    #[test]
+    fn swap_v2_pack() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Arc::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let price_oracle = PriceOracle {
+            price_a_cumulative: 1 << 70,
+            price_b_cumulative: 1 << 60,
+            last_update_timestamp: 1_700_000_000,
+            last_update_slot: 250_000_000,
+        };
+        let swap_info = SwapVersion::SwapV2(SwapV2 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+            price_oracle,
+        });
+
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(swap_info, &mut packed).unwrap();
+        assert_eq!(packed[0], 2);
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert!(unpacked.is_initialized());
+        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
+        assert_eq!(*unpacked.fees(), TEST_FEES);
+        assert_eq!(*unpacked.swap_curve(), swap_curve);
+        assert_eq!(unpacked.price_oracle(), Some(&price_oracle));
+
+        // the oracle is stored after all fields of the first version
+        let mut expect = [0u8; 1 + SwapV1::LEN];
+        expect[0] = 1;
+        SwapV1 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+        }
+        .pack_into_slice(&mut expect[1..]);
+        assert_eq!(packed[1..1 + SwapV1::LEN], expect[1..]);
+        assert_eq!(
+            PriceOracle::unpack_from_slice(&packed[1 + SwapV1::LEN..]).unwrap(),
+            price_oracle
+        );
+
+        // first version swaps have no oracle
+        let unpacked = SwapVersion::unpack(&expect).unwrap();
+        assert_eq!(unpacked.price_oracle(), None);
+    }
 }
@@ -0,0 +1,862 @@
+use {
+    clap::{crate_description, crate_name, crate_version, value_parser, Arg, ArgMatches, Command},
+    solana_clap_v3_utils::{
+        input_parsers::{parse_url_or_moniker, pubkey_of, signer::SignerSourceParserBuilder},
+        input_validators::normalize_to_url_if_moniker,
+        keypair::{
+            signer_from_path, signer_from_path_with_config, DefaultSigner, SignerFromPathConfig,
+        },
+        nonce::{NonceArgs, NONCE_ARG, NONCE_AUTHORITY_ARG},
+        offline::{OfflineArgs, BLOCKHASH_ARG, DUMP_TRANSACTION_MESSAGE, SIGN_ONLY_ARG},
+    },
+    solana_cli_output::{return_signers_with_config, OutputFormat, ReturnSignersConfig},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_remote_wallet::remote_wallet::RemoteWalletManager,
+    solana_sdk::{
+        commitment_config::CommitmentConfig, hash::Hash, instruction::Instruction, pubkey::Pubkey,
+        signature::Signer,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address_with_program_id,
+        instruction::create_associated_token_account_idempotent,
+    },
+    spl_pod::bytemuck::pod_from_bytes,
+    spl_token_2022::{
+        extension::StateWithExtensions,
+        state::{Account, Mint},
+    },
+    spl_token_client::{
+        client::{
+            ProgramClient, ProgramOfflineClient, ProgramRpcClient, ProgramRpcClientSendTransaction,
+            RpcClientResponse,
+        },
+        token::Token,
+    },
+    spl_token_wrap::{
+        get_wrapped_mint_address, get_wrapped_mint_authority, get_wrapped_mint_backpointer_address,
+        instruction::{unwrap, wrap},
+        state::Backpointer,
+    },
+    std::{error::Error, process::exit, rc::Rc, sync::Arc},
+};
+
+type BulkSigners = Vec<Arc<dyn Signer>>;
+
+struct Config {
+    commitment_config: CommitmentConfig,
+    payer: Arc<dyn Signer>,
+    json_rpc_url: String,
+    verbose: bool,
+    sign_only: bool,
+    dump_transaction_message: bool,
+    blockhash: Option<Hash>,
+    nonce_account: Option<Pubkey>,
+    nonce_authority: Option<Arc<dyn Signer>>,
+}
+
+impl Config {
+    fn program_client(
+        &self,
+        rpc_client: &Arc<RpcClient>,
+    ) -> Arc<dyn ProgramClient<ProgramRpcClientSendTransaction>> {
+        if self.sign_only {
+            Arc::new(ProgramOfflineClient::new(
+                self.blockhash.unwrap_or_default(),
+                ProgramRpcClientSendTransaction,
+            ))
+        } else {
+            Arc::new(ProgramRpcClient::new(
+                rpc_client.clone(),
+                ProgramRpcClientSendTransaction,
+            ))
+        }
+    }
+
+    fn token(
+        &self,
+        rpc_client: &Arc<RpcClient>,
+        program_id: &Pubkey,
+        mint: &Pubkey,
+    ) -> Token<ProgramRpcClientSendTransaction> {
+        let token = Token::new(
+            self.program_client(rpc_client),
+            program_id,
+            mint,
+            None,
+            self.payer.clone(),
+        );
+        if let (Some(nonce_account), Some(nonce_authority), Some(nonce_blockhash)) =
+            (self.nonce_account, &self.nonce_authority, self.blockhash)
+        {
+            token.with_nonce(&nonce_account, nonce_authority.clone(), &nonce_blockhash)
+        } else {
+            token
+        }
+    }
+
+    fn format_response(&self, response: RpcClientResponse) -> String {
+        match response {
+            RpcClientResponse::Signature(signature) => format!("Signature: {}", signature),
+            RpcClientResponse::Transaction(transaction) => return_signers_with_config(
+                &transaction,
+                &OutputFormat::Display,
+                &ReturnSignersConfig {
+                    dump_transaction_message: self.dump_transaction_message,
+                },
+            )
+            .unwrap_or_else(|err| format!("error: unable to format transaction: {}", err)),
+            RpcClientResponse::Simulation(_) => unreachable!(),
+        }
+    }
+}
+
+async fn get_mint_owner_checked(
+    rpc_client: &RpcClient,
+    mint: &Pubkey,
+) -> Result<Pubkey, Box<dyn Error>> {
+    let mint_account = rpc_client.get_account(mint).await?;
+    let _ = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+        .map_err(|_| format!("Account {} is not a valid mint", mint))?;
+    Ok(mint_account.owner)
+}
+
+/// Returns the mint and token program of a token account
+async fn get_token_account_mint_checked(
+    rpc_client: &RpcClient,
+    account: &Pubkey,
+) -> Result<(Pubkey, Pubkey), Box<dyn Error>> {
+    let token_account = rpc_client.get_account(account).await?;
+    let account_state = StateWithExtensions::<Account>::unpack(&token_account.data)
+        .map_err(|_| format!("Account {} is not a valid token account", account))?;
+    Ok((account_state.base.mint, token_account.owner))
+}
+
+async fn get_unwrapped_mint_checked(
+    rpc_client: &RpcClient,
+    wrapped_mint: &Pubkey,
+) -> Result<Pubkey, Box<dyn Error>> {
+    let backpointer_address = get_wrapped_mint_backpointer_address(wrapped_mint);
+    let backpointer_account = rpc_client
+        .get_account(&backpointer_address)
+        .await
+        .map_err(|_| format!("Mint {} is not a wrapped mint", wrapped_mint))?;
+    if backpointer_account.owner != spl_token_wrap::id() {
+        return Err(format!(
+            "Backpointer {} is not owned by the token-wrap program",
+            backpointer_address
+        )
+        .into());
+    }
+    let backpointer = pod_from_bytes::<Backpointer>(&backpointer_account.data)
+        .map_err(|_| format!("Account {} is not a valid backpointer", backpointer_address))?;
+    Ok(backpointer.unwrapped_mint)
+}
+
+/// Resolves an address either from the command line or from the network,
+/// making sure that they agree if both are available
+fn resolve_address(
+    name: &str,
+    provided: Option<Pubkey>,
+    fetched: Option<Pubkey>,
+) -> Result<Pubkey, Box<dyn Error>> {
+    match (provided, fetched) {
+        (Some(provided), Some(fetched)) if provided != fetched => Err(format!(
+            "Provided {} {} does not match on-chain value {}",
+            name, provided, fetched
+        )
+        .into()),
+        (Some(address), _) | (None, Some(address)) => Ok(address),
+        (None, None) => Err(format!("{} must be provided in sign-only mode", name).into()),
+    }
+}
+
+async fn process_create_mint(
+    config: &Config,
+    rpc_client: &Arc<RpcClient>,
+    unwrapped_mint: &Pubkey,
+    wrapped_token_program_id: &Pubkey,
+    idempotent: bool,
+) -> Result<RpcClientResponse, Box<dyn Error>> {
+    let unwrapped_token_program_id = if config.sign_only {
+        spl_token::id()
+    } else {
+        get_mint_owner_checked(rpc_client, unwrapped_mint).await?
+    };
+    let token = config.token(rpc_client, &unwrapped_token_program_id, unwrapped_mint);
+
+    if config.verbose {
+        println!(
+            "Creating wrapped mint {} for mint {} under program {}",
+            token.get_wrapped_mint_address(wrapped_token_program_id),
+            unwrapped_mint,
+            wrapped_token_program_id
+        );
+    }
+    token
+        .create_wrapped_mint(wrapped_token_program_id, idempotent)
+        .await
+        .map_err(|e| e.into())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn process_wrap(
+    config: &Config,
+    rpc_client: &Arc<RpcClient>,
+    unwrapped_token_account: &Pubkey,
+    wrapped_token_program_id: &Pubkey,
+    amount: u64,
+    owner: &Pubkey,
+    recipient: Option<Pubkey>,
+    unwrapped_mint: Option<Pubkey>,
+    unwrapped_token_program_id: Option<Pubkey>,
+    multisig_pubkeys: &[Pubkey],
+    bulk_signers: BulkSigners,
+) -> Result<RpcClientResponse, Box<dyn Error>> {
+    let (fetched_mint, fetched_program_id) = if config.sign_only {
+        (None, None)
+    } else {
+        let (mint, program_id) =
+            get_token_account_mint_checked(rpc_client, unwrapped_token_account).await?;
+        (Some(mint), Some(program_id))
+    };
+    let unwrapped_mint = resolve_address("unwrapped mint", unwrapped_mint, fetched_mint)?;
+    let unwrapped_token_program_id = resolve_address(
+        "unwrapped token program",
+        unwrapped_token_program_id,
+        fetched_program_id,
+    )?;
+
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, wrapped_token_program_id);
+    let wrapped_mint_authority = get_wrapped_mint_authority(&wrapped_mint);
+    let escrow = get_associated_token_address_with_program_id(
+        &wrapped_mint_authority,
+        &unwrapped_mint,
+        &unwrapped_token_program_id,
+    );
+
+    let mut instructions: Vec<Instruction> = vec![create_associated_token_account_idempotent(
+        &config.payer.pubkey(),
+        &wrapped_mint_authority,
+        &unwrapped_mint,
+        &unwrapped_token_program_id,
+    )];
+    let recipient = recipient.unwrap_or_else(|| {
+        instructions.push(create_associated_token_account_idempotent(
+            &config.payer.pubkey(),
+            owner,
+            &wrapped_mint,
+            wrapped_token_program_id,
+        ));
+        get_associated_token_address_with_program_id(owner, &wrapped_mint, wrapped_token_program_id)
+    });
+
+    println!(
+        "Wrapping {} tokens from account {}, receiving wrapped tokens into account {}",
+        amount, unwrapped_token_account, recipient
+    );
+    instructions.push(wrap(
+        &spl_token_wrap::id(),
+        unwrapped_token_account,
+        &escrow,
+        &unwrapped_mint,
+        &wrapped_mint,
+        &recipient,
+        &unwrapped_token_program_id,
+        wrapped_token_program_id,
+        owner,
+        &multisig_pubkeys.iter().collect::<Vec<_>>(),
+        amount,
+    ));
+
+    let token = config.token(rpc_client, &unwrapped_token_program_id, &unwrapped_mint);
+    token
+        .process_ixs(&instructions, &bulk_signers)
+        .await
+        .map_err(|e| e.into())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn process_unwrap(
+    config: &Config,
+    rpc_client: &Arc<RpcClient>,
+    wrapped_token_account: &Pubkey,
+    amount: u64,
+    owner: &Pubkey,
+    recipient: Option<Pubkey>,
+    unwrapped_mint: Option<Pubkey>,
+    unwrapped_token_program_id: Option<Pubkey>,
+    wrapped_token_program_id: Option<Pubkey>,
+    multisig_pubkeys: &[Pubkey],
+    bulk_signers: BulkSigners,
+) -> Result<RpcClientResponse, Box<dyn Error>> {
+    let (fetched_unwrapped_mint, fetched_unwrapped_program_id, fetched_wrapped_program_id) =
+        if config.sign_only {
+            (None, None, None)
+        } else {
+            let (wrapped_mint, wrapped_program_id) =
+                get_token_account_mint_checked(rpc_client, wrapped_token_account).await?;
+            let unwrapped_mint = get_unwrapped_mint_checked(rpc_client, &wrapped_mint).await?;
+            let unwrapped_program_id = get_mint_owner_checked(rpc_client, &unwrapped_mint).await?;
+            (
+                Some(unwrapped_mint),
+                Some(unwrapped_program_id),
+                Some(wrapped_program_id),
+            )
+        };
+    let unwrapped_mint = resolve_address("unwrapped mint", unwrapped_mint, fetched_unwrapped_mint)?;
+    let unwrapped_token_program_id = resolve_address(
+        "unwrapped token program",
+        unwrapped_token_program_id,
+        fetched_unwrapped_program_id,
+    )?;
+    let wrapped_token_program_id = resolve_address(
+        "wrapped token program",
+        wrapped_token_program_id,
+        fetched_wrapped_program_id,
+    )?;
+
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &wrapped_token_program_id);
+    let escrow = get_associated_token_address_with_program_id(
+        &get_wrapped_mint_authority(&wrapped_mint),
+        &unwrapped_mint,
+        &unwrapped_token_program_id,
+    );
+
+    let mut instructions: Vec<Instruction> = vec![];
+    let recipient = recipient.unwrap_or_else(|| {
+        instructions.push(create_associated_token_account_idempotent(
+            &config.payer.pubkey(),
+            owner,
+            &unwrapped_mint,
+            &unwrapped_token_program_id,
+        ));
+        get_associated_token_address_with_program_id(
+            owner,
+            &unwrapped_mint,
+            &unwrapped_token_program_id,
+        )
+    });
+
+    println!(
+        "Unwrapping {} tokens from account {}, receiving unwrapped tokens into account {}",
+        amount, wrapped_token_account, recipient
+    );
+    instructions.push(unwrap(
+        &spl_token_wrap::id(),
+        wrapped_token_account,
+        &wrapped_mint,
+        &escrow,
+        &recipient,
+        &unwrapped_mint,
+        &wrapped_token_program_id,
+        &unwrapped_token_program_id,
+        owner,
+        &multisig_pubkeys.iter().collect::<Vec<_>>(),
+        amount,
+    ));
+
+    let token = config.token(rpc_client, &unwrapped_token_program_id, &unwrapped_mint);
+    token
+        .process_ixs(&instructions, &bulk_signers)
+        .await
+        .map_err(|e| e.into())
+}
+
+async fn process_find_pdas(
+    rpc_client: &RpcClient,
+    unwrapped_mint: &Pubkey,
+    wrapped_token_program_id: &Pubkey,
+    unwrapped_token_program_id: Option<Pubkey>,
+) -> Result<(), Box<dyn Error>> {
+    let wrapped_mint = get_wrapped_mint_address(unwrapped_mint, wrapped_token_program_id);
+    let wrapped_mint_authority = get_wrapped_mint_authority(&wrapped_mint);
+    println!("Wrapped mint: {}", wrapped_mint);
+    println!("Wrapped mint authority: {}", wrapped_mint_authority);
+    println!(
+        "Wrapped backpointer: {}",
+        get_wrapped_mint_backpointer_address(&wrapped_mint)
+    );
+
+    let unwrapped_token_program_id = match unwrapped_token_program_id {
+        Some(program_id) => program_id,
+        None => get_mint_owner_checked(rpc_client, unwrapped_mint).await?,
+    };
+    println!(
+        "Unwrapped escrow: {}",
+        get_associated_token_address_with_program_id(
+            &wrapped_mint_authority,
+            unwrapped_mint,
+            &unwrapped_token_program_id,
+        )
+    );
+    Ok(())
+}
+
+/// Parses the multisig signers and the owner of a token account, returning the
+/// owner, the multisig signer pubkeys, and all the signers for the transaction
+fn parse_owner_and_signers(
+    arg_matches: &ArgMatches,
+    payer: &Arc<dyn Signer>,
+    wallet_manager: &mut Option<Rc<RemoteWalletManager>>,
+) -> (Arc<dyn Signer>, Vec<Pubkey>, BulkSigners) {
+    let mut bulk_signers = vec![payer.clone()];
+    let mut multisig_pubkeys = vec![];
+    if let Some(values) = arg_matches.values_of("multisig_signer") {
+        for (i, value) in values.enumerate() {
+            let name = format!("{}-{}", "multisig_signer", i.saturating_add(1));
+            let signer = signer_from_path(arg_matches, value, &name, wallet_manager)
+                .unwrap_or_else(|e| {
+                    eprintln!("error parsing multisig signer: {}", e);
+                    exit(1);
+                });
+            let signer_pubkey = signer.pubkey();
+            let signer = Arc::from(signer);
+            if !bulk_signers.contains(&signer) {
+                bulk_signers.push(signer);
+            }
+            if !multisig_pubkeys.contains(&signer_pubkey) {
+                multisig_pubkeys.push(signer_pubkey);
+            }
+        }
+    }
+
+    let signer_config = SignerFromPathConfig {
+        allow_null_signer: !multisig_pubkeys.is_empty(),
+    };
+    let owner = arg_matches
+        .value_of("owner")
+        .map_or(Ok(payer.clone()), |path| {
+            signer_from_path_with_config(arg_matches, path, "owner", wallet_manager, &signer_config)
+                .map(Arc::from)
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("error: owner signer: {}", err);
+            exit(1);
+        });
+    if !signer_config.allow_null_signer && !bulk_signers.contains(&owner) {
+        bulk_signers.push(owner.clone());
+    }
+    (owner, multisig_pubkeys, bulk_signers)
+}
+
+fn owner_arg<'a>() -> Arg<'a> {
+    Arg::new("owner")
+        .long("owner")
+        .value_name("OWNER_KEYPAIR")
+        .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+        .takes_value(true)
+        .help("Specify the owner or delegate of the token account. This may be a keypair file or the ASK keyword. [default: fee payer]")
+}
+
+fn multisig_signer_arg<'a>() -> Arg<'a> {
+    Arg::new("multisig_signer")
+        .long("multisig-signer")
+        .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+        .value_name("MULTISIG_SIGNER")
+        .takes_value(true)
+        .multiple(true)
+        .min_values(0)
+        .max_values(spl_token_2022::instruction::MAX_SIGNERS)
+        .help("Member signer of a multisig account")
+}
+
+fn program_id_arg<'a>(name: &'a str, long: &'a str, help: &'a str) -> Arg<'a> {
+    Arg::new(name)
+        .long(long)
+        .value_name("PROGRAM_ID")
+        .value_parser(SignerSourceParserBuilder::default().allow_pubkey().build())
+        .takes_value(true)
+        .help(help)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let app_matches = Command::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg({
+            let arg = Arg::new("config_file")
+                .short('C')
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Configuration file to use");
+            if let Some(ref config_file) = *solana_cli_config::CONFIG_FILE {
+                arg.default_value(config_file)
+            } else {
+                arg
+            }
+        })
+        .arg(
+            Arg::new("payer")
+                .long("payer")
+                .value_name("KEYPAIR")
+                .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+                .takes_value(true)
+                .global(true)
+                .help("Filepath or URL to a keypair [default: client keypair]"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .short('v')
+                .takes_value(false)
+                .global(true)
+                .help("Show additional information"),
+        )
+        .arg(
+            Arg::new("json_rpc_url")
+                .short('u')
+                .long("url")
+                .value_name("URL")
+                .takes_value(true)
+                .global(true)
+                .value_parser(parse_url_or_moniker)
+                .help("JSON RPC URL for the cluster [default: value from configuration file]"),
+        )
+        .subcommand(
+            Command::new("create-mint").about("Create a wrapped mint for an existing mint")
+            .arg(
+                Arg::new("unwrapped_mint")
+                    .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+                    .value_name("ADDRESS")
+                    .required(true)
+                    .takes_value(true)
+                    .index(1)
+                    .help("Address of the mint to wrap")
+            )
+            .arg(
+                Arg::new("wrapped_token_program")
+                    .value_parser(SignerSourceParserBuilder::default().allow_pubkey().build())
+                    .value_name("PROGRAM_ID")
+                    .required(true)
+                    .takes_value(true)
+                    .index(2)
+                    .help("Token program of the new wrapped mint")
+            )
+            .arg(
+                Arg::new("idempotent")
+                    .long("idempotent")
+                    .takes_value(false)
+                    .help("Do not fail if the wrapped mint already exists")
+            )
+            .offline_args()
+            .nonce_args(false)
+        )
+        .subcommand(
+            Command::new("wrap").about("Wrap tokens into the wrapped mint")
+            .arg(
+                Arg::new("unwrapped_token_account")
+                    .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+                    .value_name("ADDRESS")
+                    .required(true)
+                    .takes_value(true)
+                    .index(1)
+                    .help("Token account holding the tokens to wrap")
+            )
+            .arg(
+                Arg::new("wrapped_token_program")
+                    .value_parser(SignerSourceParserBuilder::default().allow_pubkey().build())
+                    .value_name("PROGRAM_ID")
+                    .required(true)
+                    .takes_value(true)
+                    .index(2)
+                    .help("Token program of the wrapped mint")
+            )
+            .arg(
+                Arg::new("amount")
+                    .value_parser(value_parser!(u64))
+                    .value_name("AMOUNT")
+                    .required(true)
+                    .takes_value(true)
+                    .index(3)
+                    .help("Amount of tokens to wrap, in base units")
+            )
+            .arg(owner_arg())
+            .arg(
+                Arg::new("recipient")
+                    .long("recipient")
+                    .value_name("RECIPIENT_TOKEN_ACCOUNT_ADDRESS")
+                    .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+                    .takes_value(true)
+                    .help("Specify the account to receive wrapped tokens. [default: associated token account for owner on wrapped mint, created if needed]"),
+            )
+            .arg(
+                Arg::new("unwrapped_mint")
+                    .long("unwrapped-mint")
+                    .value_name("ADDRESS")
+                    .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+                    .takes_value(true)
+                    .help("Mint of the unwrapped token account, required in sign-only mode"),
+            )
+            .arg(program_id_arg(
+                "unwrapped_token_program",
+                "unwrapped-token-program",
+                "Token program of the unwrapped mint, required in sign-only mode",
+            ))
+            .arg(multisig_signer_arg())
+            .offline_args()
+            .nonce_args(false)
+        )
+        .subcommand(
+            Command::new("unwrap").about("Unwrap tokens back into the unwrapped mint")
+            .arg(
+                Arg::new("wrapped_token_account")
+                    .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+                    .value_name("ADDRESS")
+                    .required(true)
+                    .takes_value(true)
+                    .index(1)
+                    .help("Token account holding the wrapped tokens to unwrap")
+            )
+            .arg(
+                Arg::new("amount")
+                    .value_parser(value_parser!(u64))
+                    .value_name("AMOUNT")
+                    .required(true)
+                    .takes_value(true)
+                    .index(2)
+                    .help("Amount of tokens to unwrap, in base units")
+            )
+            .arg(owner_arg())
+            .arg(
+                Arg::new("recipient")
+                    .long("recipient")
+                    .value_name("RECIPIENT_TOKEN_ACCOUNT_ADDRESS")
+                    .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+                    .takes_value(true)
+                    .help("Specify the account to receive unwrapped tokens. [default: associated token account for owner on unwrapped mint, created if needed]"),
+            )
+            .arg(
+                Arg::new("unwrapped_mint")
+                    .long("unwrapped-mint")
+                    .value_name("ADDRESS")
+                    .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+                    .takes_value(true)
+                    .help("Unwrapped mint, required in sign-only mode"),
+            )
+            .arg(program_id_arg(
+                "unwrapped_token_program",
+                "unwrapped-token-program",
+                "Token program of the unwrapped mint, required in sign-only mode",
+            ))
+            .arg(program_id_arg(
+                "wrapped_token_program",
+                "wrapped-token-program",
+                "Token program of the wrapped mint, required in sign-only mode",
+            ))
+            .arg(multisig_signer_arg())
+            .offline_args()
+            .nonce_args(false)
+        )
+        .subcommand(
+            Command::new("find-pdas").about("Display the program-derived addresses used to wrap a mint")
+            .arg(
+                Arg::new("unwrapped_mint")
+                    .value_parser(SignerSourceParserBuilder::default().allow_all().build())
+                    .value_name("ADDRESS")
+                    .required(true)
+                    .takes_value(true)
+                    .index(1)
+                    .help("Address of the unwrapped mint")
+            )
+            .arg(
+                Arg::new("wrapped_token_program")
+                    .value_parser(SignerSourceParserBuilder::default().allow_pubkey().build())
+                    .value_name("PROGRAM_ID")
+                    .required(true)
+                    .takes_value(true)
+                    .index(2)
+                    .help("Token program of the wrapped mint")
+            )
+            .arg(program_id_arg(
+                "unwrapped_token_program",
+                "unwrapped-token-program",
+                "Token program of the unwrapped mint [default: owner of the unwrapped mint]",
+            ))
+        )
+        .get_matches();
+
+    let (command, matches) = app_matches.subcommand().unwrap();
+    let mut wallet_manager: Option<Rc<RemoteWalletManager>> = None;
+
+    let config = {
+        let cli_config = if let Some(config_file) = matches.value_of("config_file") {
+            solana_cli_config::Config::load(config_file).unwrap_or_default()
+        } else {
+            solana_cli_config::Config::default()
+        };
+
+        let payer = DefaultSigner::new(
+            "payer",
+            matches
+                .value_of("payer")
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| cli_config.keypair_path.clone()),
+        );
+
+        let json_rpc_url = normalize_to_url_if_moniker(
+            matches
+                .get_one::<String>("json_rpc_url")
+                .unwrap_or(&cli_config.json_rpc_url),
+        );
+
+        let has_offline_args = command != "find-pdas";
+        let sign_only = has_offline_args && matches.is_present(SIGN_ONLY_ARG.name);
+        let dump_transaction_message =
+            has_offline_args && matches.is_present(DUMP_TRANSACTION_MESSAGE.name);
+        let blockhash = if has_offline_args {
+            matches.get_one::<Hash>(BLOCKHASH_ARG.name).copied()
+        } else {
+            None
+        };
+        let nonce_account = if has_offline_args {
+            pubkey_of(matches, NONCE_ARG.name)
+        } else {
+            None
+        };
+        let nonce_authority = nonce_account.map(|_| {
+            let path = matches
+                .value_of(NONCE_AUTHORITY_ARG.name)
+                .unwrap_or(&cli_config.keypair_path);
+            signer_from_path(matches, path, NONCE_AUTHORITY_ARG.name, &mut wallet_manager)
+                .map(Arc::from)
+                .unwrap_or_else(|err| {
+                    eprintln!("error: nonce authority: {}", err);
+                    exit(1);
+                })
+        });
+
+        Config {
+            commitment_config: CommitmentConfig::confirmed(),
+            payer: Arc::from(
+                payer
+                    .signer_from_path(matches, &mut wallet_manager)
+                    .unwrap_or_else(|err| {
+                        eprintln!("error: {}", err);
+                        exit(1);
+                    }),
+            ),
+            json_rpc_url,
+            verbose: matches.is_present("verbose"),
+            sign_only,
+            dump_transaction_message,
+            blockhash,
+            nonce_account,
+            nonce_authority,
+        }
+    };
+    solana_logger::setup_with_default("solana=info");
+
+    if config.verbose {
+        println!("JSON RPC URL: {}", config.json_rpc_url);
+    }
+    let rpc_client = Arc::new(RpcClient::new_with_commitment(
+        config.json_rpc_url.clone(),
+        config.commitment_config,
+    ));
+
+    match (command, matches) {
+        ("create-mint", arg_matches) => {
+            let unwrapped_mint = pubkey_of(arg_matches, "unwrapped_mint").unwrap();
+            let wrapped_token_program = pubkey_of(arg_matches, "wrapped_token_program").unwrap();
+            let idempotent = arg_matches.is_present("idempotent");
+            let response = process_create_mint(
+                &config,
+                &rpc_client,
+                &unwrapped_mint,
+                &wrapped_token_program,
+                idempotent,
+            )
+            .await
+            .unwrap_or_else(|err| {
+                eprintln!("error: create mint: {}", err);
+                exit(1);
+            });
+            println!("{}", config.format_response(response));
+        }
+        ("wrap", arg_matches) => {
+            let (owner, multisig_pubkeys, bulk_signers) =
+                parse_owner_and_signers(arg_matches, &config.payer, &mut wallet_manager);
+            let unwrapped_token_account =
+                pubkey_of(arg_matches, "unwrapped_token_account").unwrap();
+            let wrapped_token_program = pubkey_of(arg_matches, "wrapped_token_program").unwrap();
+            let amount = *arg_matches.get_one::<u64>("amount").unwrap();
+            let recipient = pubkey_of(arg_matches, "recipient");
+            let unwrapped_mint = pubkey_of(arg_matches, "unwrapped_mint");
+            let unwrapped_token_program = pubkey_of(arg_matches, "unwrapped_token_program");
+
+            let response = process_wrap(
+                &config,
+                &rpc_client,
+                &unwrapped_token_account,
+                &wrapped_token_program,
+                amount,
+                &owner.pubkey(),
+                recipient,
+                unwrapped_mint,
+                unwrapped_token_program,
+                &multisig_pubkeys,
+                bulk_signers,
+            )
+            .await
+            .unwrap_or_else(|err| {
+                eprintln!("error: wrap: {}", err);
+                exit(1);
+            });
+            println!("{}", config.format_response(response));
+        }
+        ("unwrap", arg_matches) => {
+            let (owner, multisig_pubkeys, bulk_signers) =
+                parse_owner_and_signers(arg_matches, &config.payer, &mut wallet_manager);
+            let wrapped_token_account = pubkey_of(arg_matches, "wrapped_token_account").unwrap();
+            let amount = *arg_matches.get_one::<u64>("amount").unwrap();
+            let recipient = pubkey_of(arg_matches, "recipient");
+            let unwrapped_mint = pubkey_of(arg_matches, "unwrapped_mint");
+            let unwrapped_token_program = pubkey_of(arg_matches, "unwrapped_token_program");
+            let wrapped_token_program = pubkey_of(arg_matches, "wrapped_token_program");
+
+            let response = process_unwrap(
+                &config,
+                &rpc_client,
+                &wrapped_token_account,
+                amount,
+                &owner.pubkey(),
+                recipient,
+                unwrapped_mint,
+                unwrapped_token_program,
+                wrapped_token_program,
+                &multisig_pubkeys,
+                bulk_signers,
+            )
+            .await
+            .unwrap_or_else(|err| {
+                eprintln!("error: unwrap: {}", err);
+                exit(1);
+            });
+            println!("{}", config.format_response(response));
+        }
+        ("find-pdas", arg_matches) => {
+            let unwrapped_mint = pubkey_of(arg_matches, "unwrapped_mint").unwrap();
+            let wrapped_token_program = pubkey_of(arg_matches, "wrapped_token_program").unwrap();
+            let unwrapped_token_program = pubkey_of(arg_matches, "unwrapped_token_program");
+            process_find_pdas(
+                &rpc_client,
+                &unwrapped_mint,
+                &wrapped_token_program,
+                unwrapped_token_program,
+            )
+            .await
+            .unwrap_or_else(|err| {
+                eprintln!("error: find pdas: {}", err);
+                exit(1);
+            });
+        }
+        _ => unreachable!(),
+    };
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Error types
+
+use {
+    num_derive::FromPrimitive,
+    solana_program::{decode_error::DecodeError, program_error::ProgramError},
+    thiserror::Error,
+};
+
+/// Errors that may be returned by the program.
+#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum TokenWrapError {
+    // 0
+    /// Wrapped mint account address does not match expected PDA
+    #[error("Wrapped mint account address does not match expected PDA")]
+    WrappedMintMismatch,
+    /// Wrapped backpointer account address does not match expected PDA
+    #[error("Wrapped backpointer account address does not match expected PDA")]
+    BackpointerMismatch,
+    /// Wrap amount should be positive
+    #[error("Wrap amount should be positive")]
+    ZeroWrapAmount,
+    /// Wrapped mint authority does not match expected PDA
+    #[error("Wrapped mint authority does not match expected PDA")]
+    MintAuthorityMismatch,
+    /// Unwrapped escrow token owner is not set to expected PDA
+    #[error("Unwrapped escrow token owner is not set to expected PDA")]
+    EscrowOwnerMismatch,
+
+    // 5
+    /// Wrapped mint account owner is not the expected token program
+    #[error("Wrapped mint account owner is not the expected token program")]
+    InvalidWrappedMintOwner,
+    /// Wrapped backpointer account does not point to the unwrapped mint
+    #[error("Wrapped backpointer account does not point to the unwrapped mint")]
+    InvalidBackpointer,
+}
+impl From<TokenWrapError> for ProgramError {
+    fn from(e: TokenWrapError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+impl<T> DecodeError<T> for TokenWrapError {
+    fn type_of() -> &'static str {
+        "TokenWrapError"
+    }
+}
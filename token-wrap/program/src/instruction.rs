@@ -1,6 +1,14 @@
 //! Program instructions
 
-use num_enum::{IntoPrimitive, TryFromPrimitive};
+use {
+    crate::get_wrapped_mint_authority,
+    num_enum::{IntoPrimitive, TryFromPrimitive},
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_program,
+    },
+};
 
 /// Instructions supported by the Token Wrap program
 #[derive(Clone, Debug, PartialEq, TryFromPrimitive, IntoPrimitive)]
@@ -27,8 +35,10 @@ pub enum TokenWrapInstruction {
 
     /// Wrap tokens
     ///
-    /// Move a user's unwrapped tokens into an escrow account and mint the same
-    /// number of wrapped tokens into the provided account.
+    /// Move a user's unwrapped tokens into an escrow account and mint the
+    /// number of tokens received by the escrow as wrapped tokens into the
+    /// provided account. This is less than the amount moved if the unwrapped
+    /// mint has a transfer fee.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -79,3 +89,127 @@ pub enum TokenWrapInstruction {
     ///   * little-endian u64 representing the amount to unwrap
     Unwrap,
 }
+
+/// Create a `CreateMint` instruction
+pub fn create_mint(
+    program_id: &Pubkey,
+    wrapped_mint_address: &Pubkey,
+    wrapped_backpointer_address: &Pubkey,
+    funder_address: &Pubkey,
+    unwrapped_mint_address: &Pubkey,
+    wrapped_token_program_id: &Pubkey,
+    idempotent: bool,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*funder_address, true),
+        AccountMeta::new(*wrapped_mint_address, false),
+        AccountMeta::new(*wrapped_backpointer_address, false),
+        AccountMeta::new_readonly(*unwrapped_mint_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*wrapped_token_program_id, false),
+    ];
+    let data = vec![TokenWrapInstruction::CreateMint.into(), idempotent.into()];
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Create a `Wrap` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn wrap(
+    program_id: &Pubkey,
+    unwrapped_token_account_address: &Pubkey,
+    unwrapped_escrow_address: &Pubkey,
+    unwrapped_mint_address: &Pubkey,
+    wrapped_mint_address: &Pubkey,
+    recipient_wrapped_token_account_address: &Pubkey,
+    unwrapped_token_program_id: &Pubkey,
+    wrapped_token_program_id: &Pubkey,
+    transfer_authority_address: &Pubkey,
+    multisig_signer_addresses: &[&Pubkey],
+    amount: u64,
+) -> Instruction {
+    let wrapped_mint_authority = get_wrapped_mint_authority(wrapped_mint_address);
+    let mut accounts = Vec::with_capacity(9usize.saturating_add(multisig_signer_addresses.len()));
+    accounts.push(AccountMeta::new(*unwrapped_token_account_address, false));
+    accounts.push(AccountMeta::new(*unwrapped_escrow_address, false));
+    accounts.push(AccountMeta::new_readonly(*unwrapped_mint_address, false));
+    accounts.push(AccountMeta::new(*wrapped_mint_address, false));
+    accounts.push(AccountMeta::new(
+        *recipient_wrapped_token_account_address,
+        false,
+    ));
+    accounts.push(AccountMeta::new_readonly(wrapped_mint_authority, false));
+    accounts.push(AccountMeta::new_readonly(
+        *unwrapped_token_program_id,
+        false,
+    ));
+    accounts.push(AccountMeta::new_readonly(*wrapped_token_program_id, false));
+    accounts.push(AccountMeta::new_readonly(
+        *transfer_authority_address,
+        multisig_signer_addresses.is_empty(),
+    ));
+    for signer_pubkey in multisig_signer_addresses.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    let mut data = Vec::with_capacity(9);
+    data.push(TokenWrapInstruction::Wrap.into());
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Create an `Unwrap` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn unwrap(
+    program_id: &Pubkey,
+    wrapped_token_account_address: &Pubkey,
+    wrapped_mint_address: &Pubkey,
+    unwrapped_escrow_address: &Pubkey,
+    recipient_unwrapped_token_account_address: &Pubkey,
+    unwrapped_mint_address: &Pubkey,
+    wrapped_token_program_id: &Pubkey,
+    unwrapped_token_program_id: &Pubkey,
+    transfer_authority_address: &Pubkey,
+    multisig_signer_addresses: &[&Pubkey],
+    amount: u64,
+) -> Instruction {
+    let wrapped_mint_authority = get_wrapped_mint_authority(wrapped_mint_address);
+    let mut accounts = Vec::with_capacity(9usize.saturating_add(multisig_signer_addresses.len()));
+    accounts.push(AccountMeta::new(*wrapped_token_account_address, false));
+    accounts.push(AccountMeta::new(*wrapped_mint_address, false));
+    accounts.push(AccountMeta::new(*unwrapped_escrow_address, false));
+    accounts.push(AccountMeta::new(
+        *recipient_unwrapped_token_account_address,
+        false,
+    ));
+    accounts.push(AccountMeta::new_readonly(*unwrapped_mint_address, false));
+    accounts.push(AccountMeta::new_readonly(wrapped_mint_authority, false));
+    accounts.push(AccountMeta::new_readonly(*wrapped_token_program_id, false));
+    accounts.push(AccountMeta::new_readonly(
+        *unwrapped_token_program_id,
+        false,
+    ));
+    accounts.push(AccountMeta::new_readonly(
+        *transfer_authority_address,
+        multisig_signer_addresses.is_empty(),
+    ));
+    for signer_pubkey in multisig_signer_addresses.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    let mut data = Vec::with_capacity(9);
+    data.push(TokenWrapInstruction::Unwrap.into());
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
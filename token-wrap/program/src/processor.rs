@@ -1,26 +1,443 @@
 //! Program state processor
 
 use {
-    crate::instruction::TokenWrapInstruction,
-    solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey},
-    spl_token_2022::instruction::decode_instruction_type,
+    crate::{
+        error::TokenWrapError, get_wrapped_mint_address_with_seed,
+        get_wrapped_mint_authority_signer_seeds, get_wrapped_mint_authority_with_seed,
+        get_wrapped_mint_backpointer_address_signer_seeds,
+        get_wrapped_mint_backpointer_address_with_seed, get_wrapped_mint_signer_seeds,
+        instruction::TokenWrapInstruction, state::Backpointer,
+    },
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        rent::Rent,
+        sysvar::Sysvar,
+    },
+    spl_associated_token_account::tools::account::create_pda_account,
+    spl_pod::{
+        bytemuck::{pod_from_bytes, pod_from_bytes_mut},
+        primitives::{PodBool, PodU64},
+    },
+    spl_token_2022::{
+        extension::StateWithExtensions,
+        instruction::{decode_instruction_data, decode_instruction_type},
+        state::{Account, Mint},
+    },
+    std::mem::size_of,
 };
 
+fn check_owner(account_info: &AccountInfo, expected_owner: &Pubkey) -> ProgramResult {
+    if account_info.owner != expected_owner {
+        Err(ProgramError::IllegalOwner)
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that the wrapped mint authority matches its derivation, returning
+/// its bump seed
+fn check_wrapped_mint_authority(
+    wrapped_mint_info: &AccountInfo,
+    wrapped_mint_authority_info: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let (expected_authority, bump_seed) =
+        get_wrapped_mint_authority_with_seed(wrapped_mint_info.key);
+    if expected_authority != *wrapped_mint_authority_info.key {
+        msg!(
+            "Expected wrapped mint authority {}, received {}",
+            &expected_authority,
+            wrapped_mint_authority_info.key
+        );
+        Err(TokenWrapError::MintAuthorityMismatch.into())
+    } else {
+        Ok(bump_seed)
+    }
+}
+
+/// Checks that the wrapped mint matches its derivation from the unwrapped mint
+/// and wrapped token program, returning its bump seed
+fn check_wrapped_mint(
+    unwrapped_mint_info: &AccountInfo,
+    wrapped_mint_info: &AccountInfo,
+    wrapped_token_program_info: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let (expected_wrapped_mint, bump_seed) =
+        get_wrapped_mint_address_with_seed(unwrapped_mint_info.key, wrapped_token_program_info.key);
+    if expected_wrapped_mint != *wrapped_mint_info.key {
+        msg!(
+            "Expected wrapped mint {}, received {}",
+            &expected_wrapped_mint,
+            wrapped_mint_info.key
+        );
+        Err(TokenWrapError::WrappedMintMismatch.into())
+    } else {
+        Ok(bump_seed)
+    }
+}
+
+/// Checks that the escrow is a token account owned by the wrapped mint
+/// authority
+fn check_escrow(
+    unwrapped_escrow_info: &AccountInfo,
+    wrapped_mint_authority_info: &AccountInfo,
+) -> ProgramResult {
+    let escrow_data = unwrapped_escrow_info.try_borrow_data()?;
+    let escrow = StateWithExtensions::<Account>::unpack(&escrow_data)?;
+    if escrow.base.owner != *wrapped_mint_authority_info.key {
+        msg!(
+            "Expected escrow owner {}, received {}",
+            wrapped_mint_authority_info.key,
+            &escrow.base.owner
+        );
+        Err(TokenWrapError::EscrowOwnerMismatch.into())
+    } else {
+        Ok(())
+    }
+}
+
+fn get_token_account_amount(token_account_info: &AccountInfo) -> Result<u64, ProgramError> {
+    let token_account_data = token_account_info.try_borrow_data()?;
+    let token_account = StateWithExtensions::<Account>::unpack(&token_account_data)?;
+    Ok(token_account.base.amount)
+}
+
+fn get_mint_decimals(mint_info: &AccountInfo) -> Result<u8, ProgramError> {
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    Ok(mint.base.decimals)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn transfer_tokens<'a>(
+    token_program: AccountInfo<'a>,
+    source: AccountInfo<'a>,
+    mint: AccountInfo<'a>,
+    destination: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    multisig_signers: &[AccountInfo<'a>],
+    authority_seeds: &[&[u8]],
+    amount: u64,
+    decimals: u8,
+) -> ProgramResult {
+    let multisig_pubkeys = multisig_signers.iter().map(|s| s.key).collect::<Vec<_>>();
+    let ix = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        source.key,
+        mint.key,
+        destination.key,
+        authority.key,
+        &multisig_pubkeys,
+        amount,
+        decimals,
+    )?;
+    let mut account_infos = vec![source, mint, destination, authority];
+    account_infos.extend_from_slice(multisig_signers);
+    if authority_seeds.is_empty() {
+        invoke(&ix, &account_infos)
+    } else {
+        invoke_signed(&ix, &account_infos, &[authority_seeds])
+    }
+}
+
+fn process_create_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    idempotent: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let wrapped_mint_info = next_account_info(account_info_iter)?;
+    let wrapped_backpointer_info = next_account_info(account_info_iter)?;
+    let unwrapped_mint_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let wrapped_token_program_info = next_account_info(account_info_iter)?;
+
+    // PDA derivation checks
+    let wrapped_mint_bump_seed = check_wrapped_mint(
+        unwrapped_mint_info,
+        wrapped_mint_info,
+        wrapped_token_program_info,
+    )?;
+    let (expected_backpointer, backpointer_bump_seed) =
+        get_wrapped_mint_backpointer_address_with_seed(wrapped_mint_info.key);
+    if expected_backpointer != *wrapped_backpointer_info.key {
+        msg!(
+            "Expected wrapped backpointer {}, received {}",
+            &expected_backpointer,
+            wrapped_backpointer_info.key
+        );
+        return Err(TokenWrapError::BackpointerMismatch.into());
+    }
+
+    // the unwrapped mint must be a valid mint, and its token program must be
+    // one of the known token programs to avoid wrapping arbitrary accounts
+    if unwrapped_mint_info.owner != &spl_token::id()
+        && unwrapped_mint_info.owner != &spl_token_2022::id()
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if wrapped_token_program_info.key != &spl_token::id()
+        && wrapped_token_program_info.key != &spl_token_2022::id()
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let decimals = get_mint_decimals(unwrapped_mint_info)?;
+
+    if idempotent && wrapped_mint_info.owner == wrapped_token_program_info.key {
+        // mint already exists, make sure that the backpointer is correct too
+        check_owner(wrapped_backpointer_info, program_id)?;
+        let backpointer_data = wrapped_backpointer_info.try_borrow_data()?;
+        let backpointer = pod_from_bytes::<Backpointer>(&backpointer_data)?;
+        if backpointer.unwrapped_mint != *unwrapped_mint_info.key {
+            return Err(TokenWrapError::InvalidBackpointer.into());
+        }
+        return Ok(());
+    }
+
+    let rent = Rent::get()?;
+
+    // create and initialize the wrapped mint
+    let wrapped_mint_bump_seed = [wrapped_mint_bump_seed];
+    let wrapped_mint_signer_seeds = get_wrapped_mint_signer_seeds(
+        unwrapped_mint_info.key,
+        wrapped_token_program_info.key,
+        &wrapped_mint_bump_seed,
+    );
+    create_pda_account(
+        funder_info,
+        &rent,
+        Mint::LEN,
+        wrapped_token_program_info.key,
+        system_program_info,
+        wrapped_mint_info,
+        &wrapped_mint_signer_seeds,
+    )?;
+    let wrapped_mint_authority = get_wrapped_mint_authority_with_seed(wrapped_mint_info.key).0;
+    invoke(
+        &spl_token_2022::instruction::initialize_mint2(
+            wrapped_token_program_info.key,
+            wrapped_mint_info.key,
+            &wrapped_mint_authority,
+            None,
+            decimals,
+        )?,
+        &[wrapped_mint_info.clone()],
+    )?;
+
+    // create the backpointer
+    let backpointer_bump_seed = [backpointer_bump_seed];
+    let backpointer_signer_seeds = get_wrapped_mint_backpointer_address_signer_seeds(
+        wrapped_mint_info.key,
+        &backpointer_bump_seed,
+    );
+    create_pda_account(
+        funder_info,
+        &rent,
+        size_of::<Backpointer>(),
+        program_id,
+        system_program_info,
+        wrapped_backpointer_info,
+        &backpointer_signer_seeds,
+    )?;
+    let mut backpointer_data = wrapped_backpointer_info.try_borrow_mut_data()?;
+    let backpointer = pod_from_bytes_mut::<Backpointer>(&mut backpointer_data)?;
+    backpointer.unwrapped_mint = *unwrapped_mint_info.key;
+
+    Ok(())
+}
+
+fn process_wrap(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    if amount == 0 {
+        return Err(TokenWrapError::ZeroWrapAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+
+    let unwrapped_token_account_info = next_account_info(account_info_iter)?;
+    let unwrapped_escrow_info = next_account_info(account_info_iter)?;
+    let unwrapped_mint_info = next_account_info(account_info_iter)?;
+    let wrapped_mint_info = next_account_info(account_info_iter)?;
+    let recipient_wrapped_token_account_info = next_account_info(account_info_iter)?;
+    let wrapped_mint_authority_info = next_account_info(account_info_iter)?;
+    let unwrapped_token_program_info = next_account_info(account_info_iter)?;
+    let wrapped_token_program_info = next_account_info(account_info_iter)?;
+    let transfer_authority_info = next_account_info(account_info_iter)?;
+
+    // owner checks
+    check_owner(
+        unwrapped_token_account_info,
+        unwrapped_token_program_info.key,
+    )?;
+    check_owner(unwrapped_escrow_info, unwrapped_token_program_info.key)?;
+    check_owner(unwrapped_mint_info, unwrapped_token_program_info.key)?;
+    check_owner(wrapped_mint_info, wrapped_token_program_info.key)?;
+    check_owner(
+        recipient_wrapped_token_account_info,
+        wrapped_token_program_info.key,
+    )?;
+
+    // PDA derivation checks
+    check_wrapped_mint(
+        unwrapped_mint_info,
+        wrapped_mint_info,
+        wrapped_token_program_info,
+    )?;
+    let bump_seed = check_wrapped_mint_authority(wrapped_mint_info, wrapped_mint_authority_info)?;
+    let bump_seed = [bump_seed];
+    let authority_seeds =
+        get_wrapped_mint_authority_signer_seeds(wrapped_mint_info.key, &bump_seed);
+    check_escrow(unwrapped_escrow_info, wrapped_mint_authority_info)?;
+
+    let unwrapped_decimals = get_mint_decimals(unwrapped_mint_info)?;
+    let wrapped_decimals = get_mint_decimals(wrapped_mint_info)?;
+
+    // move the unwrapped tokens into the escrow
+    let pre_escrow_amount = get_token_account_amount(unwrapped_escrow_info)?;
+    transfer_tokens(
+        unwrapped_token_program_info.clone(),
+        unwrapped_token_account_info.clone(),
+        unwrapped_mint_info.clone(),
+        unwrapped_escrow_info.clone(),
+        transfer_authority_info.clone(),
+        account_info_iter.as_slice(),
+        &[],
+        amount,
+        unwrapped_decimals,
+    )?;
+
+    // mint as many wrapped tokens as the escrow received, which is less than
+    // the amount sent if the unwrapped mint has a transfer fee
+    let wrapped_amount = get_token_account_amount(unwrapped_escrow_info)?
+        .checked_sub(pre_escrow_amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if wrapped_amount == 0 {
+        return Err(TokenWrapError::ZeroWrapAmount.into());
+    }
+    invoke_signed(
+        &spl_token_2022::instruction::mint_to_checked(
+            wrapped_token_program_info.key,
+            wrapped_mint_info.key,
+            recipient_wrapped_token_account_info.key,
+            wrapped_mint_authority_info.key,
+            &[],
+            wrapped_amount,
+            wrapped_decimals,
+        )?,
+        &[
+            wrapped_mint_info.clone(),
+            recipient_wrapped_token_account_info.clone(),
+            wrapped_mint_authority_info.clone(),
+        ],
+        &[&authority_seeds],
+    )
+}
+
+fn process_unwrap(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    if amount == 0 {
+        return Err(TokenWrapError::ZeroWrapAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+
+    let wrapped_token_account_info = next_account_info(account_info_iter)?;
+    let wrapped_mint_info = next_account_info(account_info_iter)?;
+    let unwrapped_escrow_info = next_account_info(account_info_iter)?;
+    let recipient_unwrapped_token_account_info = next_account_info(account_info_iter)?;
+    let unwrapped_mint_info = next_account_info(account_info_iter)?;
+    let wrapped_mint_authority_info = next_account_info(account_info_iter)?;
+    let wrapped_token_program_info = next_account_info(account_info_iter)?;
+    let unwrapped_token_program_info = next_account_info(account_info_iter)?;
+    let transfer_authority_info = next_account_info(account_info_iter)?;
+
+    // owner checks
+    check_owner(wrapped_token_account_info, wrapped_token_program_info.key)?;
+    check_owner(wrapped_mint_info, wrapped_token_program_info.key)?;
+    check_owner(unwrapped_escrow_info, unwrapped_token_program_info.key)?;
+    check_owner(
+        recipient_unwrapped_token_account_info,
+        unwrapped_token_program_info.key,
+    )?;
+    check_owner(unwrapped_mint_info, unwrapped_token_program_info.key)?;
+
+    // PDA derivation checks
+    check_wrapped_mint(
+        unwrapped_mint_info,
+        wrapped_mint_info,
+        wrapped_token_program_info,
+    )?;
+    let bump_seed = check_wrapped_mint_authority(wrapped_mint_info, wrapped_mint_authority_info)?;
+    let bump_seed = [bump_seed];
+    let authority_seeds =
+        get_wrapped_mint_authority_signer_seeds(wrapped_mint_info.key, &bump_seed);
+    check_escrow(unwrapped_escrow_info, wrapped_mint_authority_info)?;
+
+    let unwrapped_decimals = get_mint_decimals(unwrapped_mint_info)?;
+    let wrapped_decimals = get_mint_decimals(wrapped_mint_info)?;
+
+    // burn the wrapped tokens
+    let multisig_signers = account_info_iter.as_slice();
+    let multisig_pubkeys = multisig_signers.iter().map(|s| s.key).collect::<Vec<_>>();
+    let mut account_infos = vec![
+        wrapped_token_account_info.clone(),
+        wrapped_mint_info.clone(),
+        transfer_authority_info.clone(),
+    ];
+    account_infos.extend_from_slice(multisig_signers);
+    invoke(
+        &spl_token_2022::instruction::burn_checked(
+            wrapped_token_program_info.key,
+            wrapped_token_account_info.key,
+            wrapped_mint_info.key,
+            transfer_authority_info.key,
+            &multisig_pubkeys,
+            amount,
+            wrapped_decimals,
+        )?,
+        &account_infos,
+    )?;
+
+    // release the same amount of unwrapped tokens from the escrow
+    transfer_tokens(
+        unwrapped_token_program_info.clone(),
+        unwrapped_escrow_info.clone(),
+        unwrapped_mint_info.clone(),
+        recipient_unwrapped_token_account_info.clone(),
+        wrapped_mint_authority_info.clone(),
+        &[],
+        &authority_seeds,
+        amount,
+        unwrapped_decimals,
+    )
+}
+
 /// Instruction processor
 pub fn process_instruction(
-    _program_id: &Pubkey,
-    _accounts: &[AccountInfo],
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
     input: &[u8],
 ) -> ProgramResult {
     match decode_instruction_type(input)? {
         TokenWrapInstruction::CreateMint => {
-            unimplemented!();
+            msg!("Instruction: CreateMint");
+            let idempotent = decode_instruction_data::<PodBool>(input)?;
+            process_create_mint(program_id, accounts, idempotent.into())
         }
         TokenWrapInstruction::Wrap => {
-            unimplemented!();
+            msg!("Instruction: Wrap");
+            let amount = decode_instruction_data::<PodU64>(input)?;
+            process_wrap(accounts, u64::from(*amount))
         }
         TokenWrapInstruction::Unwrap => {
-            unimplemented!();
+            msg!("Instruction: Unwrap");
+            let amount = decode_instruction_data::<PodU64>(input)?;
+            process_unwrap(accounts, u64::from(*amount))
         }
     }
 }
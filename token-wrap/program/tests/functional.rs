@@ -0,0 +1,824 @@
+// Mark this test as SBF-only due to current `ProgramTest` limitations when
+// CPIing into the system program
+#![cfg(feature = "test-sbf")]
+
+use {
+    solana_program_test::{
+        processor,
+        tokio::{self, sync::Mutex},
+        ProgramTest, ProgramTestContext,
+    },
+    solana_sdk::{
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        signature::Signer,
+        signer::keypair::Keypair,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_pod::bytemuck::pod_from_bytes,
+    spl_token_client::{
+        client::{
+            ProgramBanksClient, ProgramBanksClientProcessTransaction, ProgramClient,
+            SendTransaction, SimulateTransaction,
+        },
+        token::{ExtensionInitializationParams, Token},
+    },
+    spl_token_wrap::{
+        error::TokenWrapError,
+        get_wrapped_mint_address, get_wrapped_mint_authority, get_wrapped_mint_backpointer_address,
+        instruction::{create_mint, unwrap, wrap},
+        state::Backpointer,
+    },
+    std::sync::Arc,
+    test_case::test_case,
+};
+
+fn keypair_clone(kp: &Keypair) -> Keypair {
+    Keypair::from_bytes(&kp.to_bytes()).expect("failed to copy keypair")
+}
+
+async fn setup() -> (
+    Arc<Mutex<ProgramTestContext>>,
+    Arc<dyn ProgramClient<ProgramBanksClientProcessTransaction>>,
+    Arc<Keypair>,
+) {
+    let mut program_test = ProgramTest::new(
+        "spl_token_wrap",
+        spl_token_wrap::id(),
+        processor!(spl_token_wrap::processor::process_instruction),
+    );
+
+    program_test.prefer_bpf(false); // simplicity in the build
+
+    program_test.add_program(
+        "spl_token_2022",
+        spl_token_2022::id(),
+        processor!(spl_token_2022::processor::Processor::process),
+    );
+    program_test.add_program(
+        "spl_token",
+        spl_token::id(),
+        processor!(spl_token::processor::Processor::process),
+    );
+    program_test.add_program(
+        "spl_associated_token_account",
+        spl_associated_token_account::id(),
+        processor!(spl_associated_token_account::processor::process_instruction),
+    );
+
+    let context = program_test.start_with_context().await;
+    let payer = Arc::new(keypair_clone(&context.payer));
+    let context = Arc::new(Mutex::new(context));
+
+    let client: Arc<dyn ProgramClient<ProgramBanksClientProcessTransaction>> =
+        Arc::new(ProgramBanksClient::new_from_context(
+            Arc::clone(&context),
+            ProgramBanksClientProcessTransaction,
+        ));
+    (context, client, payer)
+}
+
+async fn setup_mint<T: SendTransaction + SimulateTransaction>(
+    program_id: &Pubkey,
+    mint_authority: &Pubkey,
+    decimals: u8,
+    payer: Arc<Keypair>,
+    client: Arc<dyn ProgramClient<T>>,
+) -> Token<T> {
+    let mint_account = Keypair::new();
+    let token = Token::new(
+        client,
+        program_id,
+        &mint_account.pubkey(),
+        Some(decimals),
+        payer,
+    );
+    token
+        .create_mint(mint_authority, None, vec![], &[&mint_account])
+        .await
+        .unwrap();
+    token
+}
+
+async fn process_instruction(
+    context: &Arc<Mutex<ProgramTestContext>>,
+    instruction: Instruction,
+    additional_signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let mut context = context.lock().await;
+    let mut signers = vec![&context.payer];
+    signers.extend_from_slice(additional_signers);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|e| e.unwrap())
+}
+
+async fn create_wrapped_mint(
+    context: &Arc<Mutex<ProgramTestContext>>,
+    unwrapped_mint: &Pubkey,
+    wrapped_token_program_id: &Pubkey,
+    idempotent: bool,
+) -> Result<Pubkey, TransactionError> {
+    let wrapped_mint = get_wrapped_mint_address(unwrapped_mint, wrapped_token_program_id);
+    let backpointer = get_wrapped_mint_backpointer_address(&wrapped_mint);
+    let payer = context.lock().await.payer.pubkey();
+    process_instruction(
+        context,
+        create_mint(
+            &spl_token_wrap::id(),
+            &wrapped_mint,
+            &backpointer,
+            &payer,
+            unwrapped_mint,
+            wrapped_token_program_id,
+            idempotent,
+        ),
+        &[],
+    )
+    .await
+    .map(|_| wrapped_mint)
+}
+
+#[test_case(spl_token::id(), spl_token_2022::id() ; "token to token-2022")]
+#[test_case(spl_token_2022::id(), spl_token::id() ; "token-2022 to token")]
+#[test_case(spl_token::id(), spl_token::id() ; "token to token")]
+#[test_case(spl_token_2022::id(), spl_token_2022::id() ; "token-2022 to token-2022")]
+#[tokio::test]
+async fn success_create_mint(unwrapped_program_id: Pubkey, wrapped_program_id: Pubkey) {
+    let (context, client, payer) = setup().await;
+
+    let mint_authority = Pubkey::new_unique();
+    let decimals = 6;
+    let unwrapped_token = setup_mint(
+        &unwrapped_program_id,
+        &mint_authority,
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+
+    let wrapped_mint = create_wrapped_mint(
+        &context,
+        unwrapped_token.get_address(),
+        &wrapped_program_id,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let wrapped_token = Token::new(
+        client.clone(),
+        &wrapped_program_id,
+        &wrapped_mint,
+        Some(decimals),
+        payer.clone(),
+    );
+    let wrapped_mint_info = wrapped_token.get_mint_info().await.unwrap();
+    assert_eq!(wrapped_mint_info.base.decimals, decimals);
+    assert_eq!(wrapped_mint_info.base.supply, 0);
+    assert_eq!(
+        wrapped_mint_info.base.mint_authority,
+        Some(get_wrapped_mint_authority(&wrapped_mint)).into()
+    );
+    assert_eq!(wrapped_mint_info.base.freeze_authority, None.into());
+
+    let backpointer_account = client
+        .get_account(get_wrapped_mint_backpointer_address(&wrapped_mint))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(backpointer_account.owner, spl_token_wrap::id());
+    let backpointer = pod_from_bytes::<Backpointer>(&backpointer_account.data).unwrap();
+    assert_eq!(backpointer.unwrapped_mint, *unwrapped_token.get_address());
+
+    // idempotent creation succeeds on an existing mint
+    create_wrapped_mint(
+        &context,
+        unwrapped_token.get_address(),
+        &wrapped_program_id,
+        true,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn fail_create_mint_twice_non_idempotent() {
+    let (context, client, payer) = setup().await;
+
+    let unwrapped_token = setup_mint(
+        &spl_token::id(),
+        &Pubkey::new_unique(),
+        6,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+
+    create_wrapped_mint(
+        &context,
+        unwrapped_token.get_address(),
+        &spl_token_2022::id(),
+        false,
+    )
+    .await
+    .unwrap();
+
+    // refresh the blockhash to avoid sending a duplicate transaction
+    {
+        let mut context = context.lock().await;
+        context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    }
+    create_wrapped_mint(
+        &context,
+        unwrapped_token.get_address(),
+        &spl_token_2022::id(),
+        false,
+    )
+    .await
+    .unwrap_err();
+}
+
+#[tokio::test]
+async fn fail_create_mint_incorrect_derivation() {
+    let (context, client, payer) = setup().await;
+
+    let unwrapped_token = setup_mint(
+        &spl_token::id(),
+        &Pubkey::new_unique(),
+        6,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+
+    // derived for the wrong token program
+    let wrapped_mint = get_wrapped_mint_address(unwrapped_token.get_address(), &spl_token::id());
+    let backpointer = get_wrapped_mint_backpointer_address(&wrapped_mint);
+    let error = process_instruction(
+        &context,
+        create_mint(
+            &spl_token_wrap::id(),
+            &wrapped_mint,
+            &backpointer,
+            &payer.pubkey(),
+            unwrapped_token.get_address(),
+            &spl_token_2022::id(),
+            false,
+        ),
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TokenWrapError::WrappedMintMismatch as u32)
+        )
+    );
+
+    // backpointer derived from the wrong mint
+    let wrapped_mint =
+        get_wrapped_mint_address(unwrapped_token.get_address(), &spl_token_2022::id());
+    let error = process_instruction(
+        &context,
+        create_mint(
+            &spl_token_wrap::id(),
+            &wrapped_mint,
+            &backpointer,
+            &payer.pubkey(),
+            unwrapped_token.get_address(),
+            &spl_token_2022::id(),
+            false,
+        ),
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TokenWrapError::BackpointerMismatch as u32)
+        )
+    );
+}
+
+#[test_case(spl_token::id(), spl_token_2022::id(), false ; "token to token-2022")]
+#[test_case(spl_token_2022::id(), spl_token::id(), false ; "token-2022 to token")]
+#[test_case(spl_token::id(), spl_token_2022::id(), true ; "token to token-2022 multisig")]
+#[test_case(spl_token_2022::id(), spl_token::id(), true ; "token-2022 to token multisig")]
+#[tokio::test]
+async fn success_wrap_and_unwrap(
+    unwrapped_program_id: Pubkey,
+    wrapped_program_id: Pubkey,
+    use_multisig: bool,
+) {
+    let (context, client, payer) = setup().await;
+
+    let mint_authority = Keypair::new();
+    let decimals = 2;
+    let unwrapped_token = setup_mint(
+        &unwrapped_program_id,
+        &mint_authority.pubkey(),
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let wrapped_mint = create_wrapped_mint(
+        &context,
+        unwrapped_token.get_address(),
+        &wrapped_program_id,
+        false,
+    )
+    .await
+    .unwrap();
+    let wrapped_token = Token::new(
+        client.clone(),
+        &wrapped_program_id,
+        &wrapped_mint,
+        Some(decimals),
+        payer.clone(),
+    );
+    let wrapped_mint_authority = get_wrapped_mint_authority(&wrapped_mint);
+
+    // the owner of both user accounts is either a single keypair or a 2-of-3
+    // multisig, which must exist under both token programs
+    let wallet = Keypair::new();
+    let multisig_members = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let multisig_member_pubkeys = multisig_members
+        .iter()
+        .map(|k| k.pubkey())
+        .collect::<Vec<_>>();
+    let multisig_member_refs = multisig_member_pubkeys.iter().collect::<Vec<_>>();
+    let owner = if use_multisig {
+        let unwrapped_multisig = Keypair::new();
+        unwrapped_token
+            .create_multisig(&unwrapped_multisig, &multisig_member_refs, 2)
+            .await
+            .unwrap();
+        let wrapped_multisig = Keypair::new();
+        wrapped_token
+            .create_multisig(&wrapped_multisig, &multisig_member_refs, 2)
+            .await
+            .unwrap();
+        (unwrapped_multisig.pubkey(), wrapped_multisig.pubkey())
+    } else {
+        (wallet.pubkey(), wallet.pubkey())
+    };
+    let (signer_pubkeys, signers): (Vec<&Pubkey>, Vec<&Keypair>) = if use_multisig {
+        (
+            multisig_member_refs[..2].to_vec(),
+            multisig_members[..2].iter().collect(),
+        )
+    } else {
+        (vec![], vec![&wallet])
+    };
+
+    unwrapped_token
+        .create_associated_token_account(&owner.0)
+        .await
+        .unwrap();
+    let unwrapped_account = unwrapped_token.get_associated_token_address(&owner.0);
+    unwrapped_token
+        .create_associated_token_account(&wrapped_mint_authority)
+        .await
+        .unwrap();
+    let escrow_account = unwrapped_token.get_associated_token_address(&wrapped_mint_authority);
+    wrapped_token
+        .create_associated_token_account(&owner.1)
+        .await
+        .unwrap();
+    let wrapped_account = wrapped_token.get_associated_token_address(&owner.1);
+
+    let token_amount = 1_000_000;
+    unwrapped_token
+        .mint_to(
+            &unwrapped_account,
+            &mint_authority.pubkey(),
+            token_amount,
+            &[&mint_authority],
+        )
+        .await
+        .unwrap();
+
+    // wrap everything
+    process_instruction(
+        &context,
+        wrap(
+            &spl_token_wrap::id(),
+            &unwrapped_account,
+            &escrow_account,
+            unwrapped_token.get_address(),
+            &wrapped_mint,
+            &wrapped_account,
+            &unwrapped_program_id,
+            &wrapped_program_id,
+            &owner.0,
+            &signer_pubkeys,
+            token_amount,
+        ),
+        &signers,
+    )
+    .await
+    .unwrap();
+
+    let unwrapped_account_info = unwrapped_token
+        .get_account_info(&unwrapped_account)
+        .await
+        .unwrap();
+    assert_eq!(unwrapped_account_info.base.amount, 0);
+    let escrow_info = unwrapped_token
+        .get_account_info(&escrow_account)
+        .await
+        .unwrap();
+    assert_eq!(escrow_info.base.amount, token_amount);
+    let wrapped_account_info = wrapped_token
+        .get_account_info(&wrapped_account)
+        .await
+        .unwrap();
+    assert_eq!(wrapped_account_info.base.amount, token_amount);
+    let wrapped_mint_info = wrapped_token.get_mint_info().await.unwrap();
+    assert_eq!(wrapped_mint_info.base.supply, token_amount);
+
+    // unwrap part of it back
+    let unwrap_amount = token_amount / 4;
+    process_instruction(
+        &context,
+        unwrap(
+            &spl_token_wrap::id(),
+            &wrapped_account,
+            &wrapped_mint,
+            &escrow_account,
+            &unwrapped_account,
+            unwrapped_token.get_address(),
+            &wrapped_program_id,
+            &unwrapped_program_id,
+            &owner.1,
+            &signer_pubkeys,
+            unwrap_amount,
+        ),
+        &signers,
+    )
+    .await
+    .unwrap();
+
+    let remaining = token_amount - unwrap_amount;
+    let unwrapped_account_info = unwrapped_token
+        .get_account_info(&unwrapped_account)
+        .await
+        .unwrap();
+    assert_eq!(unwrapped_account_info.base.amount, unwrap_amount);
+    let escrow_info = unwrapped_token
+        .get_account_info(&escrow_account)
+        .await
+        .unwrap();
+    assert_eq!(escrow_info.base.amount, remaining);
+    let wrapped_account_info = wrapped_token
+        .get_account_info(&wrapped_account)
+        .await
+        .unwrap();
+    assert_eq!(wrapped_account_info.base.amount, remaining);
+    let wrapped_mint_info = wrapped_token.get_mint_info().await.unwrap();
+    assert_eq!(wrapped_mint_info.base.supply, remaining);
+}
+
+#[tokio::test]
+async fn fail_wrap_escrow_owner_mismatch() {
+    let (context, client, payer) = setup().await;
+
+    let mint_authority = Keypair::new();
+    let decimals = 2;
+    let unwrapped_token = setup_mint(
+        &spl_token::id(),
+        &mint_authority.pubkey(),
+        decimals,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    let wrapped_mint = create_wrapped_mint(
+        &context,
+        unwrapped_token.get_address(),
+        &spl_token_2022::id(),
+        false,
+    )
+    .await
+    .unwrap();
+    let wrapped_token = Token::new(
+        client.clone(),
+        &spl_token_2022::id(),
+        &wrapped_mint,
+        Some(decimals),
+        payer.clone(),
+    );
+
+    let wallet = Keypair::new();
+    unwrapped_token
+        .create_associated_token_account(&wallet.pubkey())
+        .await
+        .unwrap();
+    let unwrapped_account = unwrapped_token.get_associated_token_address(&wallet.pubkey());
+    wrapped_token
+        .create_associated_token_account(&wallet.pubkey())
+        .await
+        .unwrap();
+    let wrapped_account = wrapped_token.get_associated_token_address(&wallet.pubkey());
+    let token_amount = 1_000;
+    unwrapped_token
+        .mint_to(
+            &unwrapped_account,
+            &mint_authority.pubkey(),
+            token_amount,
+            &[&mint_authority],
+        )
+        .await
+        .unwrap();
+
+    // escrow owned by someone other than the wrapped mint authority
+    let bad_escrow_owner = Pubkey::new_unique();
+    unwrapped_token
+        .create_associated_token_account(&bad_escrow_owner)
+        .await
+        .unwrap();
+    let bad_escrow = unwrapped_token.get_associated_token_address(&bad_escrow_owner);
+
+    let error = process_instruction(
+        &context,
+        wrap(
+            &spl_token_wrap::id(),
+            &unwrapped_account,
+            &bad_escrow,
+            unwrapped_token.get_address(),
+            &wrapped_mint,
+            &wrapped_account,
+            &spl_token::id(),
+            &spl_token_2022::id(),
+            &wallet.pubkey(),
+            &[],
+            token_amount,
+        ),
+        &[&wallet],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TokenWrapError::EscrowOwnerMismatch as u32)
+        )
+    );
+
+    // zero amounts are rejected
+    let escrow_owner = get_wrapped_mint_authority(&wrapped_mint);
+    unwrapped_token
+        .create_associated_token_account(&escrow_owner)
+        .await
+        .unwrap();
+    let escrow = unwrapped_token.get_associated_token_address(&escrow_owner);
+    let error = process_instruction(
+        &context,
+        wrap(
+            &spl_token_wrap::id(),
+            &unwrapped_account,
+            &escrow,
+            unwrapped_token.get_address(),
+            &wrapped_mint,
+            &wrapped_account,
+            &spl_token::id(),
+            &spl_token_2022::id(),
+            &wallet.pubkey(),
+            &[],
+            0,
+        ),
+        &[&wallet],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TokenWrapError::ZeroWrapAmount as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_wrap_with_transfer_fee() {
+    let (context, client, payer) = setup().await;
+
+    // the unwrapped mint keeps 1% of every transfer, up to 1_000 tokens
+    let mint_authority = Keypair::new();
+    let decimals = 2;
+    let transfer_fee_basis_points = 100;
+    let maximum_fee = 1_000;
+    let mint_account = Keypair::new();
+    let unwrapped_token = Token::new(
+        client.clone(),
+        &spl_token_2022::id(),
+        &mint_account.pubkey(),
+        Some(decimals),
+        payer.clone(),
+    );
+    unwrapped_token
+        .create_mint(
+            &mint_authority.pubkey(),
+            None,
+            vec![ExtensionInitializationParams::TransferFeeConfig {
+                transfer_fee_config_authority: None,
+                withdraw_withheld_authority: None,
+                transfer_fee_basis_points,
+                maximum_fee,
+            }],
+            &[&mint_account],
+        )
+        .await
+        .unwrap();
+    let wrapped_mint = create_wrapped_mint(
+        &context,
+        unwrapped_token.get_address(),
+        &spl_token::id(),
+        false,
+    )
+    .await
+    .unwrap();
+    let wrapped_token = Token::new(
+        client.clone(),
+        &spl_token::id(),
+        &wrapped_mint,
+        Some(decimals),
+        payer.clone(),
+    );
+
+    let wallet = Keypair::new();
+    unwrapped_token
+        .create_associated_token_account(&wallet.pubkey())
+        .await
+        .unwrap();
+    let unwrapped_account = unwrapped_token.get_associated_token_address(&wallet.pubkey());
+    let wrapped_mint_authority = get_wrapped_mint_authority(&wrapped_mint);
+    unwrapped_token
+        .create_associated_token_account(&wrapped_mint_authority)
+        .await
+        .unwrap();
+    let escrow_account = unwrapped_token.get_associated_token_address(&wrapped_mint_authority);
+    wrapped_token
+        .create_associated_token_account(&wallet.pubkey())
+        .await
+        .unwrap();
+    let wrapped_account = wrapped_token.get_associated_token_address(&wallet.pubkey());
+
+    let token_amount = 10_000;
+    unwrapped_token
+        .mint_to(
+            &unwrapped_account,
+            &mint_authority.pubkey(),
+            token_amount,
+            &[&mint_authority],
+        )
+        .await
+        .unwrap();
+
+    process_instruction(
+        &context,
+        wrap(
+            &spl_token_wrap::id(),
+            &unwrapped_account,
+            &escrow_account,
+            unwrapped_token.get_address(),
+            &wrapped_mint,
+            &wrapped_account,
+            &spl_token_2022::id(),
+            &spl_token::id(),
+            &wallet.pubkey(),
+            &[],
+            token_amount,
+        ),
+        &[&wallet],
+    )
+    .await
+    .unwrap();
+
+    // only the amount received by the escrow is wrapped
+    let fee = token_amount * transfer_fee_basis_points as u64 / 10_000;
+    let escrow_info = unwrapped_token
+        .get_account_info(&escrow_account)
+        .await
+        .unwrap();
+    assert_eq!(escrow_info.base.amount, token_amount - fee);
+    let wrapped_account_info = wrapped_token
+        .get_account_info(&wrapped_account)
+        .await
+        .unwrap();
+    assert_eq!(wrapped_account_info.base.amount, token_amount - fee);
+    let wrapped_mint_info = wrapped_token.get_mint_info().await.unwrap();
+    assert_eq!(wrapped_mint_info.base.supply, escrow_info.base.amount);
+}
+
+#[test_case(spl_token::id(), spl_token_2022::id() ; "token to token-2022")]
+#[test_case(spl_token_2022::id(), spl_token::id() ; "token-2022 to token")]
+#[tokio::test]
+async fn success_wrap_and_unwrap_with_client(
+    unwrapped_program_id: Pubkey,
+    wrapped_program_id: Pubkey,
+) {
+    let (_context, client, payer) = setup().await;
+
+    let wallet = Keypair::new();
+    let mint_authority = Keypair::new();
+    let unwrapped_token = setup_mint(
+        &unwrapped_program_id,
+        &mint_authority.pubkey(),
+        2,
+        payer.clone(),
+        client.clone(),
+    )
+    .await;
+    unwrapped_token
+        .create_wrapped_mint(&wrapped_program_id, false)
+        .await
+        .unwrap();
+    let wrapped_token = Token::new(
+        client.clone(),
+        &wrapped_program_id,
+        &unwrapped_token.get_wrapped_mint_address(&wrapped_program_id),
+        None,
+        payer.clone(),
+    );
+
+    unwrapped_token
+        .create_associated_token_account(&wallet.pubkey())
+        .await
+        .unwrap();
+    let unwrapped_account = unwrapped_token.get_associated_token_address(&wallet.pubkey());
+    wrapped_token
+        .create_associated_token_account(&wallet.pubkey())
+        .await
+        .unwrap();
+    let wrapped_account = wrapped_token.get_associated_token_address(&wallet.pubkey());
+    let token_amount = 1_000;
+    unwrapped_token
+        .mint_to(
+            &unwrapped_account,
+            &mint_authority.pubkey(),
+            token_amount,
+            &[&mint_authority],
+        )
+        .await
+        .unwrap();
+
+    // escrow is created on the fly
+    unwrapped_token
+        .wrap_tokens(
+            &unwrapped_account,
+            &wrapped_account,
+            &wrapped_program_id,
+            &wallet.pubkey(),
+            token_amount,
+            &[&wallet],
+        )
+        .await
+        .unwrap();
+    let escrow = unwrapped_token.get_wrapped_escrow_address(&wrapped_program_id);
+    let escrow_info = unwrapped_token.get_account_info(&escrow).await.unwrap();
+    assert_eq!(escrow_info.base.amount, token_amount);
+    let wrapped_account_info = wrapped_token
+        .get_account_info(&wrapped_account)
+        .await
+        .unwrap();
+    assert_eq!(wrapped_account_info.base.amount, token_amount);
+
+    unwrapped_token
+        .unwrap_tokens(
+            &wrapped_account,
+            &unwrapped_account,
+            &wrapped_program_id,
+            &wallet.pubkey(),
+            token_amount,
+            &[&wallet],
+        )
+        .await
+        .unwrap();
+    let escrow_info = unwrapped_token.get_account_info(&escrow).await.unwrap();
+    assert_eq!(escrow_info.base.amount, 0);
+    let unwrapped_account_info = unwrapped_token
+        .get_account_info(&unwrapped_account)
+        .await
+        .unwrap();
+    assert_eq!(unwrapped_account_info.base.amount, token_amount);
+    let wrapped_mint = wrapped_token.get_mint_info().await.unwrap();
+    assert_eq!(wrapped_mint.base.supply, 0);
+}
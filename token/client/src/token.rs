@@ -4071,4 +4071,123 @@ where
         ));
         self.process_ixs(&instructions, signing_keypairs).await
     }
+
+    /// Get the address of the token-wrap mint wrapping this mint under the
+    /// given token program
+    pub fn get_wrapped_mint_address(&self, wrapped_token_program_id: &Pubkey) -> Pubkey {
+        spl_token_wrap::get_wrapped_mint_address(&self.pubkey, wrapped_token_program_id)
+    }
+
+    /// Get the address of the associated escrow account holding this mint's
+    /// tokens for the token-wrap mint under the given token program
+    pub fn get_wrapped_escrow_address(&self, wrapped_token_program_id: &Pubkey) -> Pubkey {
+        let wrapped_mint = self.get_wrapped_mint_address(wrapped_token_program_id);
+        self.get_associated_token_address(&spl_token_wrap::get_wrapped_mint_authority(
+            &wrapped_mint,
+        ))
+    }
+
+    /// Create the token-wrap mint wrapping this mint under the given token
+    /// program
+    pub async fn create_wrapped_mint(
+        &self,
+        wrapped_token_program_id: &Pubkey,
+        idempotent: bool,
+    ) -> TokenResult<T::Output> {
+        let wrapped_mint = self.get_wrapped_mint_address(wrapped_token_program_id);
+        self.process_ixs::<[&dyn Signer; 0]>(
+            &[spl_token_wrap::instruction::create_mint(
+                &spl_token_wrap::id(),
+                &wrapped_mint,
+                &spl_token_wrap::get_wrapped_mint_backpointer_address(&wrapped_mint),
+                &self.payer.pubkey(),
+                &self.pubkey,
+                wrapped_token_program_id,
+                idempotent,
+            )],
+            &[],
+        )
+        .await
+    }
+
+    /// Wrap tokens from an account of this mint into an account of the
+    /// token-wrap mint under the given token program, creating the associated
+    /// escrow account if needed
+    #[allow(clippy::too_many_arguments)]
+    pub async fn wrap_tokens<S: Signers>(
+        &self,
+        source: &Pubkey,
+        destination: &Pubkey,
+        wrapped_token_program_id: &Pubkey,
+        authority: &Pubkey,
+        amount: u64,
+        signing_keypairs: &S,
+    ) -> TokenResult<T::Output> {
+        let signing_pubkeys = signing_keypairs.pubkeys();
+        let multisig_signers = self.get_multisig_signers(authority, &signing_pubkeys);
+        let wrapped_mint = self.get_wrapped_mint_address(wrapped_token_program_id);
+        let escrow = self.get_wrapped_escrow_address(wrapped_token_program_id);
+
+        self.process_ixs(
+            &[
+                create_associated_token_account_idempotent(
+                    &self.payer.pubkey(),
+                    &spl_token_wrap::get_wrapped_mint_authority(&wrapped_mint),
+                    &self.pubkey,
+                    &self.program_id,
+                ),
+                spl_token_wrap::instruction::wrap(
+                    &spl_token_wrap::id(),
+                    source,
+                    &escrow,
+                    &self.pubkey,
+                    &wrapped_mint,
+                    destination,
+                    &self.program_id,
+                    wrapped_token_program_id,
+                    authority,
+                    &multisig_signers,
+                    amount,
+                ),
+            ],
+            signing_keypairs,
+        )
+        .await
+    }
+
+    /// Unwrap tokens from an account of the token-wrap mint under the given
+    /// token program back into an account of this mint
+    #[allow(clippy::too_many_arguments)]
+    pub async fn unwrap_tokens<S: Signers>(
+        &self,
+        source: &Pubkey,
+        destination: &Pubkey,
+        wrapped_token_program_id: &Pubkey,
+        authority: &Pubkey,
+        amount: u64,
+        signing_keypairs: &S,
+    ) -> TokenResult<T::Output> {
+        let signing_pubkeys = signing_keypairs.pubkeys();
+        let multisig_signers = self.get_multisig_signers(authority, &signing_pubkeys);
+        let wrapped_mint = self.get_wrapped_mint_address(wrapped_token_program_id);
+        let escrow = self.get_wrapped_escrow_address(wrapped_token_program_id);
+
+        self.process_ixs(
+            &[spl_token_wrap::instruction::unwrap(
+                &spl_token_wrap::id(),
+                source,
+                &wrapped_mint,
+                &escrow,
+                destination,
+                &self.pubkey,
+                wrapped_token_program_id,
+                &self.program_id,
+                authority,
+                &multisig_signers,
+                amount,
+            )],
+            signing_keypairs,
+        )
+        .await
+    }
 }